@@ -0,0 +1,11 @@
+//! Generates the gRPC service and client from `proto/artificer.proto`
+//! (see `api::grpc`). Uses a vendored protoc, so none needs installing.
+
+fn main() -> std::io::Result<()> {
+    let protoc = protoc_bin_vendored::protoc_bin_path().map_err(std::io::Error::other)?;
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc);
+
+    tonic_prost_build::configure()
+        .compile_with_config(config, &["proto/artificer.proto"], &["proto"])
+}
@@ -0,0 +1,60 @@
+/// Most characters of tool results the Critic is shown. The newest results
+/// are kept, since the answer usually rests on them.
+const MAX_EVIDENCE_CHARS: usize = 12_000;
+
+/// What the Critic made of a draft answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Approve,
+    /// The problems to fix, in the Critic's words.
+    Revise(String),
+}
+
+/// The Critic's input: the user's request, the tool results gathered while
+/// answering it, and the draft answer.
+pub fn critic_request(goal: &str, evidence: &[&str], answer: &str) -> String {
+    let mut kept = Vec::new();
+    let mut budget = MAX_EVIDENCE_CHARS;
+    for result in evidence.iter().rev() {
+        let len = result.chars().count();
+        if len > budget {
+            if budget > 0 {
+                let start: String = result.chars().take(budget).collect();
+                kept.push(format!("{}\n[...truncated]", start));
+            }
+            break;
+        }
+        budget -= len;
+        kept.push(result.to_string());
+    }
+    kept.reverse();
+
+    let evidence = if kept.is_empty() {
+        "No tools were used.".to_string()
+    } else {
+        kept.join("\n")
+    };
+    format!(
+        "<request>\n{}\n</request>\n\n<evidence>\n{}\n</evidence>\n\n<answer>\n{}\n</answer>",
+        goal, evidence, answer
+    )
+}
+
+/// Read the Critic's reply. Anything but a clear request for revision
+/// approves, so a confused Critic never holds up an answer.
+pub fn parse_verdict(reply: &str) -> Verdict {
+    let reply = reply.trim();
+    let Some(rest) = reply
+        .get(..6)
+        .filter(|head| head.eq_ignore_ascii_case("revise"))
+        .map(|_| &reply[6..])
+    else {
+        return Verdict::Approve;
+    };
+    let problems = rest.trim().trim_start_matches([':', '-']).trim();
+    if problems.is_empty() {
+        Verdict::Approve
+    } else {
+        Verdict::Revise(problems.to_string())
+    }
+}
@@ -0,0 +1,34 @@
+#[cfg(test)]
+mod tests {
+    use crate::agent::execution::critic::{critic_request, parse_verdict, Verdict};
+
+    #[test]
+    fn verdicts_are_read_leniently() {
+        assert_eq!(parse_verdict("APPROVE"), Verdict::Approve);
+        assert_eq!(parse_verdict("Looks fine to me."), Verdict::Approve);
+        assert_eq!(parse_verdict("REVISE"), Verdict::Approve);
+        assert_eq!(
+            parse_verdict("REVISE: the price is not in the evidence"),
+            Verdict::Revise("the price is not in the evidence".to_string())
+        );
+        assert_eq!(
+            parse_verdict("  revise\n- cites a page that was never fetched"),
+            Verdict::Revise("cites a page that was never fetched".to_string())
+        );
+    }
+
+    #[test]
+    fn request_keeps_the_newest_evidence() {
+        let old = "o".repeat(10_000);
+        let new = "n".repeat(5_000);
+        let request = critic_request("goal", &[&old, &new], "answer");
+
+        assert!(request.contains(&new));
+        assert!(request.contains("[...truncated]"));
+        assert!(!request.contains(&old));
+        assert!(request.ends_with("<answer>\nanswer\n</answer>"));
+
+        let request = critic_request("hi", &[], "hello");
+        assert!(request.contains("No tools were used."));
+    }
+}
@@ -0,0 +1,43 @@
+use artificer_shared::Message;
+
+/// Room the conversation's earlier messages get in the orchestrator's
+/// context, in characters (about 6k tokens). Older messages are dropped
+/// first.
+pub const HISTORY_BUDGET_CHARS: usize = 24_000;
+
+/// The newest messages that fit in `budget_chars`, and how many older ones
+/// were dropped. The last message, the one being answered, is always kept.
+/// The kept part starts at a user message, so a tool result or a reply is
+/// never shown without the turn it belongs to.
+pub fn trim_history(mut messages: Vec<Message>, budget_chars: usize) -> (Vec<Message>, usize) {
+    let Some(last) = messages.len().checked_sub(1) else {
+        return (messages, 0);
+    };
+
+    let mut start = last;
+    let mut used = message_chars(&messages[last]);
+    while start > 0 {
+        let size = message_chars(&messages[start - 1]);
+        if used + size > budget_chars {
+            break;
+        }
+        used += size;
+        start -= 1;
+    }
+    if start > 0 {
+        start = (start..last)
+            .find(|&i| messages[i].role == "user")
+            .unwrap_or(last);
+    }
+
+    let kept = messages.split_off(start);
+    (kept, start)
+}
+
+fn message_chars(message: &Message) -> usize {
+    let content = message.content.as_deref().map_or(0, |c| c.chars().count());
+    let tool_calls = message.tool_calls.as_ref()
+        .and_then(|calls| serde_json::to_string(calls).ok())
+        .map_or(0, |json| json.len());
+    content + tool_calls
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use artificer_shared::Message;
+    use crate::agent::execution::history::trim_history;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message { role: role.to_string(), content: Some(content.to_string()), tool_calls: None }
+    }
+
+    fn roles(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.role.as_str()).collect()
+    }
+
+    #[test]
+    fn short_conversations_are_kept_whole() {
+        let history = vec![message("user", "hi"), message("assistant", "hello"), message("user", "again")];
+        let (kept, dropped) = trim_history(history, 1_000);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(dropped, 0);
+        assert_eq!(trim_history(Vec::new(), 1_000).1, 0);
+    }
+
+    #[test]
+    fn oldest_messages_go_first_and_turns_stay_whole() {
+        let history = vec![
+            message("user", &"a".repeat(40)),
+            message("assistant", &"b".repeat(40)),
+            message("user", &"c".repeat(10)),
+            message("assistant", &"d".repeat(10)),
+            message("tool", &"e".repeat(10)),
+            message("assistant", &"f".repeat(10)),
+            message("user", &"g".repeat(10)),
+        ];
+        // The budget reaches back to the tool result, but the kept part
+        // starts at the next user message
+        let (kept, dropped) = trim_history(history.clone(), 35);
+        assert_eq!(roles(&kept), vec!["user"]);
+        assert_eq!(dropped, 6);
+
+        let (kept, dropped) = trim_history(history, 50);
+        assert_eq!(roles(&kept), vec!["user", "assistant", "tool", "assistant", "user"]);
+        assert_eq!(dropped, 2);
+    }
+
+    #[test]
+    fn the_new_message_is_kept_even_over_budget() {
+        let history = vec![message("user", "earlier"), message("user", &"x".repeat(100))];
+        let (kept, dropped) = trim_history(history, 10);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].content.as_deref().map(str::len), Some(100));
+        assert_eq!(dropped, 1);
+    }
+}
@@ -6,23 +6,70 @@ use std::sync::Arc;
 use futures_util::future::BoxFuture;
 use crate::agent::{Agent, AgentResponse};
 use crate::agent::state::{TaskState, ExecutionContext, SpecialistExecution, AgentState};
-use crate::agent::tools::{handle_task_tool, is_task_tool, handle_specialist_control_tool, is_return_triggering_tool};
-use crate::agent::llm_client::LlmClient;
+use crate::agent::tools::{handle_task_tool, is_plan_tool, is_task_tool, is_memory_tool, handle_specialist_control_tool, is_return_triggering_tool};
+use crate::agent::llm_client::{is_transient, LlmClient};
+use tool_repair::repair_tool_calls;
+use critic::{critic_request, parse_verdict, Verdict};
+use history::{trim_history, HISTORY_BUDGET_CHARS};
+use crate::agent::response_filters::{response_secrets, FilterContext};
 use crate::agent::llm_types::LlmRequest;
 use crate::agent::{AgentRoles, ExecutionMode};
+use crate::agent::language::{language_name, resolve_conversation_language};
 use crate::pool::AgentPool;
 use artificer_shared::{Message, ToolCall};
+use artificer_shared::db::{Confidence, PromptExperiment, PromptVariant, ResponseStyle};
+use artificer_shared::memory::{MemorySelection, MemoryStore};
+use artificer_shared::time;
 
 #[cfg(test)]
 mod tool_execution_tests;
+mod critic;
+#[cfg(test)]
+mod critic_tests;
 pub mod tool_validation;
+pub mod tool_repair;
+#[cfg(test)]
+mod tool_repair_tests;
+mod history;
+#[cfg(test)]
+mod history_tests;
+
+/// Waits before each retry of an LLM call that failed transiently (connection
+/// reset, Ollama 5xx). A call that still fails after the last one ends the run.
+const LLM_RETRY_DELAYS: [std::time::Duration; 3] = [
+    std::time::Duration::from_secs(1),
+    std::time::Duration::from_secs(2),
+    std::time::Duration::from_secs(4),
+];
+
+/// How long a specialist waits for the user to answer a question.
+const QUESTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
 
 pub struct AgentExecution {
     agent: Agent,
     context: ExecutionContext,
     task_state: TaskState,
     agent_pool: Arc<AgentPool>,
-    message_count: u32,
+    /// Prompt experiment this run was assigned to, fixed for the whole run.
+    experiment: Option<(PromptExperiment, PromptVariant)>,
+    /// LLM calls made by this run.
+    iterations: u32,
+    /// Language to reply to the user in. Only resolved for the orchestrator,
+    /// since specialists answer to it rather than to the user.
+    language: Option<String>,
+    /// The device's response style, likewise only loaded for the orchestrator.
+    style: ResponseStyle,
+    /// URLs this run's tools fetched, for citation filters.
+    sources: Vec<String>,
+    /// Whether a failed delegation was already handed back to the model to
+    /// plan around. A second failure ends the run.
+    replanned: bool,
+    /// Whether the Critic sent the answer back for revision. Each run is
+    /// revised at most once, and the revision replaces what was streamed.
+    revised: bool,
+    /// Ratings of the specialists this run delegated to, which the answer's
+    /// confidence is built from.
+    confidences: Vec<Confidence>,
 }
 
 impl AgentExecution {
@@ -38,23 +85,76 @@ impl AgentExecution {
 
         let task_state = TaskState::new(task_id, context.parent_task_id, goal);
 
-        let message_count = context.db
-            .get_message_count(context.conversation_id)
-            .unwrap_or(0);
+        let experiment = context.db
+            .active_prompt_experiment(agent.name)
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load prompt experiment for {}: {}", agent.name, e);
+                None
+            })
+            .map(|exp| {
+                let variant = PromptVariant::assign(exp.id, context.conversation_id);
+                (exp, variant)
+            });
+
+        let language = (agent.role == AgentRoles::Orchestrator)
+            .then(|| resolve_conversation_language(
+                &context.db,
+                context.device_id,
+                context.conversation_id,
+                goal,
+            ))
+            .flatten();
+
+        let style = if agent.role == AgentRoles::Orchestrator {
+            context.db.get_response_style(context.device_id).unwrap_or_else(|e| {
+                eprintln!("Failed to load response style for device {}: {}", context.device_id, e);
+                ResponseStyle::default()
+            })
+        } else {
+            ResponseStyle::default()
+        };
+
+        // Toolbelts the conversation turned on or off
+        let choices = context.db.conversation_toolbelts(context.conversation_id).unwrap_or_else(|e| {
+            eprintln!("Failed to load toolbelt choices for conversation {}: {}", context.conversation_id, e);
+            Vec::new()
+        });
 
         Self {
-            agent: agent.clone(),
+            agent: agent.clone().with_tool_choices(&choices),
             context,
             task_state,
             agent_pool: pool.clone(),
-            message_count,
+            experiment,
+            iterations: 0,
+            language,
+            style,
+            sources: Vec::new(),
+            replanned: false,
+            revised: false,
+            confidences: Vec::new(),
         }
     }
 
     /// Execute the agent. Boxed to allow the recursive delegation call chain.
     pub fn execute(mut self, pool: Arc<AgentPool>) -> BoxFuture<'static, Result<AgentResponse>> {
         Box::pin(async move {
-            match (self.agent.role, self.agent.execution_mode) {
+            let runtime = self.context.runtime.clone();
+            let conversation_id = self.context.conversation_id;
+            let previous_task = runtime.enter_task(conversation_id, self.task_state.id);
+            let started = std::time::Instant::now();
+
+            if self.context.debug
+                && self.context.parent_task_id.is_none()
+                && let Some(events) = &self.context.events
+            {
+                events.debug(
+                    &format!("task_{}", self.task_state.id),
+                    &format!("Prompts of this request are saved at /debug/requests/{}", self.task_state.id),
+                );
+            }
+
+            let result = match (self.agent.role, self.agent.execution_mode) {
                 (AgentRoles::Orchestrator, ExecutionMode::Agentic) => {
                     self.execute_orchestrator(&pool).await
                 }
@@ -72,7 +172,11 @@ impl AgentExecution {
                     self.agent.role,
                     self.agent.execution_mode
                 )),
-            }
+            };
+
+            runtime.exit_task(conversation_id, previous_task);
+            self.record_experiment_outcome(&result, started.elapsed().as_millis() as u64);
+            result
         })
     }
 
@@ -87,11 +191,14 @@ impl AgentExecution {
             content: Some(user_goal),
             tool_calls: None,
         });
+        let turn_start = messages.len();
 
         let mut iteration_count: u32 = 0;
 
         loop {
             iteration_count += 1;
+            self.iterations += 1;
+            self.context.runtime.record_iteration(self.context.conversation_id);
             self.update_system_prompt(&mut messages);
 
             let system_preview = messages.first()
@@ -112,10 +219,10 @@ impl AgentExecution {
             if let Some(tool_calls) = response.tool_calls.clone() {
                 let reasoning = response.content.clone();
 
-                if let (Some(text), Some(events)) = (&reasoning, &self.context.events) {
-                    if !text.is_empty() {
-                        events.reasoning(&format!("task_{}", self.task_state.id), text.clone());
-                    }
+                if let (Some(text), Some(events)) = (&reasoning, &self.context.events)
+                    && !text.is_empty()
+                {
+                    events.reasoning(&format!("task_{}", self.task_state.id), text.clone());
                 }
 
                 self.persist_assistant_message(reasoning.as_deref(), Some(&tool_calls))?;
@@ -166,7 +273,17 @@ impl AgentExecution {
 
             // Text response — stream already sent, persist and return
             if let Some(content) = &response.content {
-                let content_owned = content.clone();
+                if let Some(problems) = self.critique(&messages[turn_start..], content, pool).await {
+                    messages.push(Message {
+                        role: "assistant".to_string(),
+                        content: Some(content.clone()),
+                        tool_calls: None,
+                    });
+                    messages.push(revision_request(&problems));
+                    continue;
+                }
+
+                let content_owned = self.filter_response(content.clone());
 
                 let _ = pool.db().log_execution_trace(
                     self.task_state.id,
@@ -182,6 +299,7 @@ impl AgentExecution {
                 );
 
                 self.persist_assistant_message(Some(&content_owned), None)?;
+                self.annotate_answer();
                 return Ok(AgentResponse::complete(content_owned));
             }
 
@@ -189,8 +307,24 @@ impl AgentExecution {
         }
 
         // Task was marked complete via tool — generate a final summary
-        let final_response = self.generate_final_response(&messages, pool).await?;
+        let mut final_response = self.generate_final_response(&messages, pool).await?;
+        if let Some(problems) = self.critique(&messages[turn_start..], &final_response, pool).await {
+            let mut revision = messages.clone();
+            revision.push(Message {
+                role: "assistant".to_string(),
+                content: Some(final_response.clone()),
+                tool_calls: None,
+            });
+            revision.push(revision_request(&problems));
+            if let Some(revised) = self.call_llm(&revision, pool).await?.content
+                .filter(|content| !content.trim().is_empty())
+            {
+                final_response = revised;
+            }
+        }
+        let final_response = self.filter_response(final_response);
         self.persist_assistant_message(Some(&final_response), None)?;
+        self.annotate_answer();
         Ok(AgentResponse::complete(final_response))
     }
 
@@ -202,6 +336,8 @@ impl AgentExecution {
 
         loop {
             iteration_count += 1;
+            self.iterations += 1;
+            self.context.runtime.record_iteration(self.context.conversation_id);
             if iteration_count > MAX_SPECIALIST_ITERATIONS {
                 eprintln!(
                     "Specialist hit max iteration limit ({}) for task {}",
@@ -230,10 +366,10 @@ impl AgentExecution {
             if let Some(tool_calls) = response.tool_calls.clone() {
                 let reasoning = response.content.clone();
 
-                if let (Some(text), Some(events)) = (&reasoning, &self.context.events) {
-                    if !text.is_empty() {
-                        events.reasoning(&format!("task_{}", specialist_exec.task.id), text.clone());
-                    }
+                if let (Some(text), Some(events)) = (&reasoning, &self.context.events)
+                    && !text.is_empty()
+                {
+                    events.reasoning(&format!("task_{}", specialist_exec.task.id), text.clone());
                 }
 
                 self.persist_assistant_message(reasoning.as_deref(), Some(&tool_calls))?;
@@ -245,9 +381,12 @@ impl AgentExecution {
                 let (task_calls, regular_calls): (Vec<_>, Vec<_>) = non_return_calls.into_iter()
                     .partition(|tc| is_task_tool(&tc.function.name));
 
-                let (get_full_result_calls, toolbelt_calls): (Vec<_>, Vec<_>) = regular_calls.into_iter()
+                let (get_full_result_calls, regular_calls): (Vec<_>, Vec<_>) = regular_calls.into_iter()
                     .partition(|tc| tc.function.name == "response::get_full_result");
 
+                let (ask_calls, toolbelt_calls): (Vec<_>, Vec<_>) = regular_calls.into_iter()
+                    .partition(|tc| tc.function.name == "response::ask_user");
+
                 let mut tool_results_for_trace: Vec<String> = Vec::new();
 
                 // Execute task management tools
@@ -264,6 +403,11 @@ impl AgentExecution {
 
                     if let Some(events) = &self.context.events {
                         events.tool_result(&format!("task_{}", specialist_exec.task.id), tool_name, result.clone());
+                        if is_plan_tool(tool_name)
+                            && let Some(plan) = &specialist_exec.task.plan
+                        {
+                            events.plan_progress(&format!("task_{}", specialist_exec.task.id), plan);
+                        }
                     }
                     tool_results_for_trace.push(result.clone());
                     self.persist_tool_message(tool_name, &result)?;
@@ -278,12 +422,31 @@ impl AgentExecution {
                         events.tool_call(&format!("task_{}", specialist_exec.task.id), tool_name, args.clone());
                     }
 
-                    let result = pool.tool_executor()
-                        .execute(tool_name, args, self.context.device_id as i64, &self.context.device_key)
+                    let result = self.context.run_tool(pool, tool_name, args)
                         .await
                         .unwrap_or_else(|e| format!("Error: {}", e));
 
                     specialist_exec.record_tool_call(tool_name.clone(), args.clone(), result.clone());
+                    self.sources.extend(source_url(args));
+
+                    if let Some(events) = &self.context.events {
+                        events.tool_result(&format!("task_{}", specialist_exec.task.id), tool_name, result.clone());
+                    }
+                    tool_results_for_trace.push(result.clone());
+                    self.persist_tool_message(tool_name, &result)?;
+                }
+
+                // Execute response::ask_user — waits for the user's answer
+                for tool_call in &ask_calls {
+                    let tool_name = &tool_call.function.name;
+                    let args = &tool_call.function.arguments;
+
+                    if let Some(events) = &self.context.events {
+                        events.tool_call(&format!("task_{}", specialist_exec.task.id), tool_name, args.clone());
+                    }
+
+                    let result = self.ask_user(specialist_exec.task.id, args).await;
+                    specialist_exec.record_tool_call(tool_name.clone(), args.clone(), result.clone());
 
                     if let Some(events) = &self.context.events {
                         events.tool_result(&format!("task_{}", specialist_exec.task.id), tool_name, result.clone());
@@ -312,7 +475,7 @@ impl AgentExecution {
                 }
 
                 // Only process return tools if they were the ONLY calls in this batch
-                if !return_calls.is_empty() && task_calls.is_empty() && toolbelt_calls.is_empty() && get_full_result_calls.is_empty() {
+                if !return_calls.is_empty() && task_calls.is_empty() && toolbelt_calls.is_empty() && get_full_result_calls.is_empty() && ask_calls.is_empty() {
                     for tool_call in &return_calls {
                         let tool_name = &tool_call.function.name;
                         let args = &tool_call.function.arguments;
@@ -384,13 +547,143 @@ impl AgentExecution {
             return Err(anyhow::anyhow!("Specialist returned empty response"));
         }
 
-        let summary = specialist_exec.build_response();
+        self.store_artifacts(&mut specialist_exec);
+        let summary = self.filter_response(specialist_exec.build_response());
         specialist_exec.task.persist_if_dirty(&self.context)?;
-        Ok(AgentResponse::complete(summary))
+        let confidence = specialist_exec.confidence
+            .map(|level| Confidence { level, sources: self.sources.clone() });
+        Ok(AgentResponse::complete(summary).with_confidence(confidence))
+    }
+
+    /// Have the Critic check a draft answer against the tool results of this
+    /// turn, when the request asked for verification. Returns the problems it
+    /// found the first time it finds any; a failed check approves.
+    async fn critique(&mut self, turn: &[Message], draft: &str, pool: &Arc<AgentPool>) -> Option<String> {
+        if !self.context.verify || self.revised {
+            return None;
+        }
+        let critic = pool.get("Critic")?;
+
+        let evidence: Vec<&str> = turn.iter()
+            .filter(|m| m.role == "tool")
+            .filter_map(|m| m.content.as_deref())
+            .collect();
+        let request = critic_request(&self.task_state.user_goal, &evidence, draft);
+
+        let critic_context = ExecutionContext {
+            device_id: self.context.device_id,
+            device_key: self.context.device_key.clone(),
+            conversation_id: self.context.conversation_id,
+            parent_task_id: Some(self.task_state.id),
+            gpu: self.context.gpu.clone(),
+            events: None,
+            db: self.context.db.clone(),
+            runtime: self.context.runtime.clone(),
+            fresh: self.context.fresh,
+            debug: self.context.debug,
+            verify: false,
+            target: None,
+        };
+        let execution = AgentExecution::new(critic, critic_context, &request, pool);
+        let verdict = match execution.execute(Arc::clone(pool)).await {
+            Ok(response) => parse_verdict(&response.content),
+            Err(e) => {
+                eprintln!("Critic failed for task {}: {}", self.task_state.id, e);
+                return None;
+            }
+        };
+
+        let Verdict::Revise(problems) = verdict else {
+            return None;
+        };
+        self.revised = true;
+        if let Some(events) = &self.context.events {
+            events.debug(
+                &format!("task_{}", self.task_state.id),
+                &format!("Critic asked for a revision:\n{}", problems),
+            );
+        }
+        Some(problems)
+    }
+
+    /// Store and announce how sure the answer just persisted is, from the
+    /// ratings of the specialists behind it. Unrated answers get nothing.
+    fn annotate_answer(&self) {
+        let Some(confidence) = Confidence::combine(&self.confidences) else {
+            return;
+        };
+        if let Err(e) = self.agent_pool.db().set_answer_confidence(
+            self.context.conversation_id,
+            self.task_state.id,
+            &confidence,
+        ) {
+            eprintln!("Failed to store confidence for task {}: {}", self.task_state.id, e);
+        }
+        if let Some(events) = &self.context.events {
+            events.confidence(&format!("task_{}", self.task_state.id), &confidence);
+        }
+    }
+
+    /// Put a question to the user over the chat stream and wait for the answer
+    /// from /chat/continue. Without a stream (background jobs) nobody can answer,
+    /// so the specialist is told to decide for itself.
+    async fn ask_user(&self, task_id: u64, args: &serde_json::Value) -> String {
+        let question = args["question"].as_str().unwrap_or("").trim();
+        if question.is_empty() {
+            return "Error: question cannot be empty".to_string();
+        }
+        let Some(events) = &self.context.events else {
+            return "The user isn't available to answer. Use your best judgment and mention the assumption in your response.".to_string();
+        };
+        let options: Vec<String> = args["options"]
+            .as_array()
+            .map(|items| items.iter().filter_map(|o| o.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+
+        let runtime = &self.context.runtime;
+        let conversation_id = self.context.conversation_id;
+        let (question_id, answer) = runtime.ask_question(conversation_id, task_id, question, options.clone());
+        events.question(conversation_id, question_id, &format!("task_{}", task_id), question, &options);
+
+        match tokio::time::timeout(QUESTION_TIMEOUT, answer).await {
+            Ok(Ok(answer)) => format!("The user answered: {}", answer),
+            _ => {
+                runtime.withdraw_question(conversation_id, question_id);
+                "The user didn't answer. Use your best judgment and mention the assumption in your response.".to_string()
+            }
+        }
+    }
+
+    /// Keep the returned tool results as artifacts of the run, so later steps
+    /// can reference them by id. The run is the task that delegated here.
+    fn store_artifacts(&self, specialist_exec: &mut SpecialistExecution) {
+        let step_task_id = specialist_exec.task.id;
+        let run_id = self.context.parent_task_id.unwrap_or(step_task_id);
+
+        let stored: Vec<(usize, anyhow::Result<u64>)> = specialist_exec
+            .returned_tool_calls()
+            .map(|tc| {
+                let id = self.agent_pool.db().store_artifact(
+                    run_id,
+                    step_task_id,
+                    &tc.tool_name,
+                    &tc.tool_args.to_string(),
+                    &tc.tool_result,
+                );
+                (tc.index, id)
+            })
+            .collect();
+
+        for (index, result) in stored {
+            match result {
+                Ok(id) => specialist_exec.set_artifact(index, id),
+                Err(e) => eprintln!("Failed to store artifact for task {}: {}", step_task_id, e),
+            }
+        }
     }
 
     fn build_specialist_messages(&self, specialist_exec: &SpecialistExecution) -> Vec<Message> {
-        let system_prompt = self.agent.build_system_prompt("");
+        let system_prompt = self.agent.build_system_prompt(&self.instructions(), &MemorySelection::default(), "");
         let state_xml = specialist_exec.build_state_xml();
 
         let user_content = format!(
@@ -417,7 +710,7 @@ impl AgentExecution {
         let messages = vec![
             Message {
                 role: "system".to_string(),
-                content: Some(self.agent.system_prompt.to_string()),
+                content: Some(self.instructions()),
                 tool_calls: None,
             },
             Message {
@@ -427,12 +720,13 @@ impl AgentExecution {
             },
         ];
 
+        self.iterations += 1;
         let response = self.call_llm(&messages, pool).await?;
 
         if let Some(content) = response.content {
             self.task_state.mark_complete();
             self.task_state.persist_complete(&self.context)?;
-            Ok(AgentResponse::complete(content))
+            Ok(AgentResponse::complete(self.filter_response(content)))
         } else {
             self.task_state.mark_failed();
             self.task_state.persist_failed(&self.context)?;
@@ -453,8 +747,20 @@ impl AgentExecution {
                 .db()
                 .get_messages(self.context.conversation_id)
                 .unwrap_or_default();
+            let (history, dropped) = trim_history(history, HISTORY_BUDGET_CHARS);
 
             let mut messages = vec![system_message];
+            if dropped > 0 {
+                messages.push(Message {
+                    role: "system".to_string(),
+                    content: Some(format!(
+                        "{} earlier messages in this conversation are not shown. If you need them, \
+                         delegate to Archivist.",
+                        dropped
+                    )),
+                    tool_calls: None,
+                });
+            }
             messages.extend(history);
             messages
         } else {
@@ -463,21 +769,214 @@ impl AgentExecution {
     }
 
     fn build_system_prompt(&self) -> String {
-        self.agent.build_system_prompt(&self.task_state.build_task_xml())
+        self.agent.build_system_prompt(&self.instructions(), &self.memories(), &self.task_state.build_task_xml())
     }
 
-    fn update_system_prompt(&self, messages: &mut Vec<Message>) {
-        if let Some(first) = messages.first_mut() {
-            if first.role == "system" {
-                first.content = Some(self.build_system_prompt());
+    /// The memories from the conversation's profile most relevant to the
+    /// user's message, for agents that can manage them. Facts from other
+    /// profiles never reach the prompt.
+    fn memories(&self) -> MemorySelection {
+        if !self.agent.tools.iter().any(|tool| is_memory_tool(&tool.function.name)) {
+            return MemorySelection::default();
+        }
+        let store = MemoryStore::new(self.agent_pool.db().clone());
+        match store.for_prompt(self.context.device_id, self.context.conversation_id, &self.task_state.user_goal) {
+            Ok(selection) => selection,
+            Err(e) => {
+                eprintln!("Failed to load memories for conversation {}: {}", self.context.conversation_id, e);
+                MemorySelection::default()
+            }
+        }
+    }
+
+    /// The agent's instructions: the assigned experiment variant, else the active
+    /// operator override, else the compiled prompt, followed by the reply
+    /// language, the device's response style and, for agentic agents, the
+    /// user's local time. Overrides are read on every prompt build so they
+    /// apply without a restart.
+    fn instructions(&self) -> String {
+        let mut instructions = if let Some((experiment, variant)) = &self.experiment {
+            experiment.prompt(*variant).to_string()
+        } else {
+            match self.agent_pool.db().active_prompt_override(self.agent.name) {
+                Ok(Some(prompt)) => prompt,
+                Ok(None) => self.agent.system_prompt.to_string(),
+                Err(e) => {
+                    eprintln!("Failed to load prompt override for {}: {}", self.agent.name, e);
+                    self.agent.system_prompt.to_string()
+                }
+            }
+        };
+
+        if let Some(name) = self.language.as_deref().filter(|code| *code != "en").and_then(language_name) {
+            instructions.push_str(&format!(
+                "\n\n# Language\n\nThe user writes in {name}. Always respond to the user in {name}, \
+                 even when tool results or specialist reports are in English.",
+            ));
+        }
+
+        if let Some(style) = self.style.instructions() {
+            instructions.push_str("\n\n");
+            instructions.push_str(&style);
+        }
+
+        // Relative dates ("tomorrow", "this week's news") need the user's clock
+        if self.agent.execution_mode == ExecutionMode::Agentic {
+            let zone = time::device_zone(&self.context.db, self.context.device_id);
+            match time::now_in(Some(&zone)) {
+                Ok(now) => instructions.push_str(&format!(
+                    "\n\n# Current Time\n\nIt is currently {} for the user.",
+                    now.describe(),
+                )),
+                Err(e) => eprintln!("Failed to resolve local time for device {}: {}", self.context.device_id, e),
             }
         }
+
+        instructions
+    }
+
+    fn record_experiment_outcome(&self, result: &Result<AgentResponse>, latency_ms: u64) {
+        let Some((experiment, variant)) = &self.experiment else {
+            return;
+        };
+        let success = result.as_ref().is_ok_and(|response| response.success);
+        if let Err(e) = self.agent_pool.db().record_experiment_outcome(
+            experiment.id,
+            *variant,
+            self.task_state.id,
+            self.context.conversation_id,
+            self.iterations,
+            latency_ms,
+            success,
+        ) {
+            eprintln!("Failed to record outcome for experiment {}: {}", experiment.id, e);
+        }
+    }
+
+    /// Run the agent's response filters over its final content. When they
+    /// change what the orchestrator already streamed, the client is sent the
+    /// final text to show instead.
+    fn filter_response(&self, content: String) -> String {
+        let secrets = response_secrets(&self.context.device_key);
+        let context = FilterContext { sources: &self.sources, secrets: &secrets };
+
+        let filtered = self.agent.response_filters.apply(content.clone(), &context);
+        if (filtered != content || self.revised)
+            && self.agent.role == AgentRoles::Orchestrator
+            && let Some(events) = &self.context.events
+        {
+            events.response_complete(&filtered);
+        }
+        filtered
+    }
+
+    fn update_system_prompt(&self, messages: &mut [Message]) {
+        if let Some(first) = messages.first_mut()
+            && first.role == "system"
+        {
+            first.content = Some(self.build_system_prompt());
+        }
     }
 
+    /// One LLM reply with its tool-call arguments repaired. If any are beyond
+    /// repair, the model is shown the problem and asked to reformat once.
     async fn call_llm(&self, messages: &[Message], pool: &Arc<AgentPool>) -> Result<Message> {
+        let mut response = self.call_llm_retrying(messages, pool).await?;
+        let Some(tool_calls) = response.tool_calls.as_mut() else {
+            return Ok(response);
+        };
+        let failures = repair_tool_calls(tool_calls);
+        if failures.is_empty() {
+            return Ok(response);
+        }
+
+        let problems: Vec<String> = failures
+            .iter()
+            .map(|(tool, problem)| format!("- {}: {}", tool, problem))
+            .collect();
+        eprintln!("{} sent malformed tool arguments, asking it to reformat:\n{}", self.agent.name, problems.join("\n"));
+
+        let mut retry = messages.to_vec();
+        retry.push(response);
+        retry.push(Message {
+            role: "user".to_string(),
+            content: Some(format!(
+                "These tool calls could not be run because their arguments were malformed:\n{}\n\n\
+                 Make the same tool calls again, with each call's arguments as a single JSON object.",
+                problems.join("\n")
+            )),
+            tool_calls: None,
+        });
+
+        let mut response = self.call_llm_retrying(&retry, pool).await?;
+        if let Some(tool_calls) = response.tool_calls.as_mut()
+            && let Some((tool, problem)) = repair_tool_calls(tool_calls).into_iter().next()
+        {
+            return Err(anyhow::anyhow!("{} sent malformed arguments for '{}' twice: {}", self.agent.name, tool, problem));
+        }
+        Ok(response)
+    }
+
+    /// One LLM call, retried with backoff while Ollama fails transiently.
+    async fn call_llm_retrying(&self, messages: &[Message], pool: &Arc<AgentPool>) -> Result<Message> {
+        if self.context.debug {
+            self.snapshot_prompt(messages);
+        }
+        let mut retries = LLM_RETRY_DELAYS.iter();
+        loop {
+            let error = match self.call_llm_once(messages, pool).await {
+                Ok(message) => return Ok(message),
+                Err(e) => e,
+            };
+            let Some(delay) = retries.next().filter(|_| is_transient(&error)) else {
+                return Err(error);
+            };
+
+            let attempt = LLM_RETRY_DELAYS.len() - retries.len();
+            let notice = format!(
+                "{} failed ({}); retry {}/{} in {}s",
+                self.agent.name, error, attempt, LLM_RETRY_DELAYS.len(), delay.as_secs()
+            );
+            eprintln!("{}", notice);
+            if let Some(events) = &self.context.events {
+                events.debug(&format!("task_{}", self.task_state.id), &notice);
+            }
+            tokio::time::sleep(*delay).await;
+        }
+    }
+
+    /// Save exactly what the model is about to be sent, for /debug/requests.
+    fn snapshot_prompt(&self, messages: &[Message]) {
+        let system_prompt = messages.first()
+            .filter(|m| m.role == "system")
+            .and_then(|m| m.content.as_deref())
+            .unwrap_or("");
+        // Only the orchestrator's own prompt carries memories
+        let memories = if self.agent.role == AgentRoles::Specialist || self.agent.execution_mode == ExecutionMode::OneTime {
+            String::new()
+        } else {
+            Agent::memory_section(&self.memories())
+        };
+        let tools = serde_json::to_value(&self.agent.tools).unwrap_or_default();
+        let messages = serde_json::to_value(messages).unwrap_or_default();
+
+        if let Err(e) = self.agent_pool.db().store_prompt_snapshot(
+            self.task_state.id,
+            self.agent.name,
+            system_prompt,
+            &memories,
+            &tools,
+            &messages,
+        ) {
+            eprintln!("Failed to save prompt snapshot for task {}: {}", self.task_state.id, e);
+        }
+    }
+
+    async fn call_llm_once(&self, messages: &[Message], pool: &Arc<AgentPool>) -> Result<Message> {
         let llm_client = LlmClient::new(pool.client(), &self.context.gpu);
         let request = LlmRequest::new(self.context.gpu.model.clone(), messages.to_vec())
-            .with_tools(self.agent.tools.clone());
+            .with_tools(self.agent.tools.clone())
+            .with_num_predict(self.context.gpu.num_predict);
 
         if let Some(events) = &self.context.events {
             llm_client.call_streaming(request, events).await
@@ -496,9 +995,19 @@ impl AgentExecution {
         let mut tool_ctx = ToolExecutionContext::new(&mut self.task_state, &self.context, pool);
 
         for tool_call in tool_calls {
-            let result = tool_ctx
+            let result = match tool_ctx
                 .execute_tool(&tool_call.function.name, &tool_call.function.arguments)
-                .await?;
+                .await
+            {
+                Ok(result) => result,
+                Err(e) if tool_call.function.name.starts_with("delegate::") && !self.replanned => {
+                    eprintln!("Delegation {} failed, asking for another plan: {}", tool_call.function.name, e);
+                    self.replanned = true;
+                    replan_notice(&tool_call.function.name, &e)
+                }
+                Err(e) => return Err(e),
+            };
+            self.sources.extend(source_url(&tool_call.function.arguments));
 
             let wrapped = format!(
                 "<tool_response>\n<tool_name>{}</tool_name>\n<tool_result>\n{}\n</tool_result>\n</tool_response>",
@@ -507,6 +1016,7 @@ impl AgentExecution {
             );
             results.push(wrapped);
         }
+        self.confidences.append(&mut tool_ctx.confidences);
 
         Ok(results)
     }
@@ -531,19 +1041,18 @@ impl AgentExecution {
             .ok_or_else(|| anyhow::anyhow!("No final response generated"))
     }
 
-    fn persist_user_message(&mut self, content: &str) -> Result<()> {
+    fn persist_user_message(&self, content: &str) -> Result<()> {
         self.agent_pool.db().add_message(
             self.context.conversation_id,
             Some(self.task_state.id as i64),
             "user",
             Some(content),
             None,
-            &mut self.message_count,
         )
     }
 
     fn persist_assistant_message(
-        &mut self,
+        &self,
         content: Option<&str>,
         tool_calls: Option<&Vec<ToolCall>>,
     ) -> Result<()> {
@@ -553,18 +1062,16 @@ impl AgentExecution {
             "assistant",
             content,
             tool_calls,
-            &mut self.message_count,
         )
     }
 
-    fn persist_tool_message(&mut self, _tool_name: &str, result: &str) -> Result<()> {
+    fn persist_tool_message(&self, _tool_name: &str, result: &str) -> Result<()> {
         self.agent_pool.db().add_message(
             self.context.conversation_id,
             Some(self.task_state.id as i64),
             "tool",
             Some(result),
             None,
-            &mut self.message_count,
         )
     }
 }
@@ -613,3 +1120,38 @@ fn classify_orchestrator_iteration(tool_calls: &[ToolCall]) -> String {
         "tool_call".to_string()
     }
 }
+
+/// The message sending a draft answer back with the Critic's objections.
+fn revision_request(problems: &str) -> Message {
+    Message {
+        role: "user".to_string(),
+        content: Some(format!(
+            "A reviewer checked your answer against the tool results and found problems:\n{}\n\n\
+             Write the full answer again with these fixed. Use tools if you need more information, \
+             and say so where something can't be verified.",
+            problems
+        )),
+        tool_calls: None,
+    }
+}
+
+/// Tool result for a delegation that failed, asking the orchestrator to get
+/// the goal done another way instead of ending the run.
+fn replan_notice(tool_name: &str, error: &anyhow::Error) -> String {
+    format!(
+        "Error: {}\n\nThis delegation failed and must not be retried for this request. \
+         Mark the step failed and replan without {}: use another specialist, or answer \
+         from what you already know. Tell the user what couldn't be done and why. \
+         If another delegation fails, the request ends.",
+        error, tool_name
+    )
+}
+
+/// The web page a tool call fetched, if it fetched one.
+fn source_url(args: &serde_json::Value) -> Option<String> {
+    args["url"]
+        .as_str()
+        .map(str::trim)
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .map(String::from)
+}
@@ -1,22 +1,29 @@
 use anyhow::Result;
 use serde_json::Value;
 use std::sync::Arc;
+use artificer_shared::db::{Confidence, TargetDevice, MAX_CACHE_AGE_SECS};
 use artificer_shared::tools::get_tool_schema;
 use crate::pool::AgentPool;
-use crate::agent::tools::{handle_task_tool, is_task_tool};
+use crate::agent::tools::{
+    handle_device_tool, handle_introspection_tool, handle_memory_tool, handle_scratchpad_tool, handle_task_tool,
+    is_device_tool, is_introspection_tool, is_memory_tool, is_plan_tool, is_scratchpad_tool, is_task_tool,
+};
 use super::tool_validation::validate_tool_call;
 use crate::agent::state::{TaskState, ExecutionContext};
+use crate::agent::language::detect;
 
 /// Per-turn context for routing and executing tool calls.
 pub struct ToolExecutionContext<'a> {
     pub task: &'a mut TaskState,
     pub context: &'a ExecutionContext,
     pub pool: &'a Arc<AgentPool>,
+    /// Ratings the specialists delegated to gave their results.
+    pub confidences: Vec<Confidence>,
 }
 
 impl<'a> ToolExecutionContext<'a> {
     pub fn new(task: &'a mut TaskState, context: &'a ExecutionContext, pool: &'a Arc<AgentPool>) -> Self {
-        Self { task, context, pool }
+        Self { task, context, pool, confidences: Vec::new() }
     }
 
     /// Execute any tool call — validates, routes, and emits events.
@@ -36,18 +43,18 @@ impl<'a> ToolExecutionContext<'a> {
         // Route to appropriate handler
         let result = if is_task_tool(tool_name) {
             handle_task_tool(self.task, tool_name, args)
+        } else if is_memory_tool(tool_name) {
+            handle_memory_tool(self.context, tool_name, args)
+        } else if is_scratchpad_tool(tool_name) {
+            handle_scratchpad_tool(self.context, tool_name, args)
+        } else if is_device_tool(tool_name) {
+            handle_device_tool(self.context, self.pool.tool_executor(), tool_name, args).await
+        } else if is_introspection_tool(tool_name) {
+            handle_introspection_tool(self.context, self.pool, tool_name)
         } else if tool_name.starts_with("delegate::") {
             self.execute_delegation(tool_name, args).await
         } else {
-            self.pool
-                .tool_executor()
-                .execute(
-                    tool_name,
-                    args,
-                    self.context.device_id as i64,
-                    &self.context.device_key,
-                )
-                .await
+            self.context.run_tool(self.pool, tool_name, args).await
         };
 
         // Emit tool result event
@@ -68,6 +75,11 @@ impl<'a> ToolExecutionContext<'a> {
                     );
                 }
             }
+            if result.is_ok() && is_plan_tool(tool_name)
+                && let Some(plan) = &self.task.plan
+            {
+                events.plan_progress(&format!("task_{}", self.task.id), plan);
+            }
         }
 
         result
@@ -92,6 +104,14 @@ impl<'a> ToolExecutionContext<'a> {
             .or_else(|| args["task"].as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing goal/request/task in delegation args"))?;
 
+        let target = match args["target"].as_str().filter(|t| !t.trim().is_empty()) {
+            Some(target) => match self.delegation_target(target) {
+                Ok(device) => Some(device),
+                Err(e) => return Ok(format!("Error: {}", e)),
+            },
+            None => self.context.target.clone(),
+        };
+
         // Emit task switch event
         if let Some(events) = &self.context.events {
             events.task_switch(
@@ -108,20 +128,56 @@ impl<'a> ToolExecutionContext<'a> {
             gpu: self.context.gpu.clone(),
             events: self.context.events.clone(),
             db: self.context.db.clone(),
+            runtime: self.context.runtime.clone(),
+            fresh: self.context.fresh,
+            debug: self.context.debug,
+            verify: self.context.verify,
+            target,
         };
 
         // Look up specialist again for AgentExecution::new
         let specialist = self.pool.get(&agent_name).unwrap();
+        let goal = if specialist.english_only {
+            self.goal_in_english(goal).await
+        } else {
+            goal.to_string()
+        };
+
+        // Opt-in shared cache, for goals that stand on their own
+        let cache_secs = args["cache_minutes"]
+            .as_i64()
+            .filter(|minutes| *minutes > 0 && !has_artifacts(&args["artifacts"]))
+            .map(|minutes| (minutes * 60).min(MAX_CACHE_AGE_SECS));
+        if let Some(max_age) = cache_secs
+            && !self.context.fresh
+            && let Some(cached) = self.context.db.get_cached_result(&agent_name, &goal, max_age)?
+        {
+            if let Some(events) = &self.context.events {
+                events.task_switch(
+                    &format!("specialist_{}", agent_name),
+                    &format!("task_{}", self.task.id),
+                );
+            }
+            let age_minutes = (now() - cached.created).max(0) / 60;
+            return Ok(format!("[Cached result from {} min ago]\n{}", age_minutes, cached.content));
+        }
+        let cache_goal = goal.clone();
+
+        let goal = match self.with_artifacts(goal, &args["artifacts"]) {
+            Ok(goal) => goal,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+
         let execution = crate::agent::AgentExecution::new(
             specialist,
             specialist_context,
-            goal,
+            &goal,
             self.pool,
         );
 
-        let response = execution.execute(Arc::clone(self.pool)).await?;
+        let response = execution.execute(Arc::clone(self.pool)).await;
 
-        // Emit task switch back event
+        // Emit task switch back event, also when the specialist failed
         if let Some(events) = &self.context.events {
             events.task_switch(
                 &format!("specialist_{}", agent_name),
@@ -129,9 +185,102 @@ impl<'a> ToolExecutionContext<'a> {
             );
         }
 
+        let response = response
+            .map_err(|e| anyhow::anyhow!("{} failed: {}", agent_name, e))?;
+
+        if cache_secs.is_some() && response.success
+            && let Err(e) = self.context.db.cache_result(&agent_name, &cache_goal, &response.content)
+        {
+            eprintln!("Failed to cache {} result: {}", agent_name, e);
+        }
+
+        self.confidences.extend(response.confidence);
         Ok(response.content)
     }
 
+    /// The one device a delegation's `target` names. It must be the user's
+    /// own and accept tool calls.
+    fn delegation_target(&self, target: &str) -> Result<TargetDevice> {
+        let mut devices = self.context.db.resolve_device_target(self.context.device_id, target)?;
+        if devices.len() != 1 {
+            return Err(anyhow::anyhow!(
+                "'{}' names {} devices; a delegation runs on one (use devices::run_on for several)",
+                target, devices.len()
+            ));
+        }
+        let device = devices.remove(0);
+        if device.tool_host.is_none() {
+            return Err(anyhow::anyhow!("{} does not accept tool calls (no tool_host reported)", device.device_name));
+        }
+        Ok(device)
+    }
+
+    /// Append the referenced artifacts of this run to a delegated goal.
+    fn with_artifacts(&self, goal: String, ids: &Value) -> Result<String> {
+        let Some(ids) = ids.as_array().filter(|ids| !ids.is_empty()) else {
+            return Ok(goal);
+        };
+        let ids: Vec<u64> = ids
+            .iter()
+            .map(|id| id.as_u64().ok_or_else(|| anyhow::anyhow!("artifacts must be a list of artifact ids")))
+            .collect::<Result<_>>()?;
+
+        let run_id = self.context.parent_task_id.unwrap_or(self.task.id);
+        let artifacts = self.context.db.get_artifacts(run_id, &ids)?;
+        if let Some(missing) = ids.iter().find(|id| !artifacts.iter().any(|a| a.id == **id)) {
+            return Err(anyhow::anyhow!("Artifact {} not found in this task", missing));
+        }
+
+        let mut goal = goal;
+        goal.push_str("\n\n<artifacts>\n");
+        for artifact in artifacts {
+            goal.push_str(&format!(
+                "<artifact id=\"{}\" tool=\"{}\">\n{}\n</artifact>\n",
+                artifact.id, artifact.tool_name, artifact.content
+            ));
+        }
+        goal.push_str("</artifacts>");
+        Ok(goal)
+    }
+
+    /// Translate a delegated goal for a specialist that only works well in English.
+    /// Goals that are already English, or too short to tell, pass through; a
+    /// failed translation falls back to the original text.
+    async fn goal_in_english(&self, goal: &str) -> String {
+        if detect(goal).is_none_or(|code| code == "en") {
+            return goal.to_string();
+        }
+        let Some(translator) = self.pool.get("Translator") else {
+            return goal.to_string();
+        };
+
+        let translator_context = ExecutionContext {
+            device_id: self.context.device_id,
+            device_key: self.context.device_key.clone(),
+            conversation_id: self.context.conversation_id,
+            parent_task_id: Some(self.task.id),
+            gpu: self.context.gpu.clone(),
+            events: None,
+            db: self.context.db.clone(),
+            runtime: self.context.runtime.clone(),
+            fresh: self.context.fresh,
+            debug: self.context.debug,
+            verify: self.context.verify,
+            target: self.context.target.clone(),
+        };
+        let request = format!("Translate into English:\n\n{}", goal);
+        let execution = crate::agent::AgentExecution::new(translator, translator_context, &request, self.pool);
+
+        match execution.execute(Arc::clone(self.pool)).await {
+            Ok(response) if response.success && !response.content.trim().is_empty() => response.content,
+            Ok(_) => goal.to_string(),
+            Err(e) => {
+                eprintln!("Failed to translate goal for specialist: {}", e);
+                goal.to_string()
+            }
+        }
+    }
+
     /// Convert snake_case specialist name to PascalCase: "file_smith" -> "FileSmith"
     fn normalize_specialist_name(name: &str) -> String {
         name.split('_')
@@ -153,6 +302,8 @@ impl<'a> ToolExecutionContext<'a> {
     /// Check whether a tool is available for this agent to call.
     pub fn is_tool_available(&self, tool_name: &str) -> bool {
         if is_task_tool(tool_name)
+            || is_memory_tool(tool_name)
+            || is_introspection_tool(tool_name)
             || tool_name.starts_with("delegate::")
             || tool_name.starts_with("response::")
         {
@@ -161,3 +312,14 @@ impl<'a> ToolExecutionContext<'a> {
         get_tool_schema(tool_name).is_ok()
     }
 }
+
+fn has_artifacts(ids: &Value) -> bool {
+    ids.as_array().is_some_and(|ids| !ids.is_empty())
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,105 @@
+use serde_json::{Map, Value};
+use artificer_shared::ToolCall;
+
+/// Bring a tool call's arguments into the JSON object every tool expects.
+///
+/// Models sometimes send the object as a JSON string, wrap it in a code
+/// fence, or follow it with commentary. Those are recovered; anything that
+/// still isn't an object is an error describing what arrived.
+pub fn repair_arguments(args: &Value) -> Result<Value, String> {
+    match args {
+        Value::Object(_) => Ok(args.clone()),
+        Value::Null => Ok(Value::Object(Map::new())),
+        Value::String(text) => parse_object(text),
+        other => Err(format!("arguments must be a JSON object, got {}", kind(other))),
+    }
+}
+
+/// Repair every call in place. Returns the name and problem of each call
+/// that couldn't be repaired.
+pub fn repair_tool_calls(tool_calls: &mut [ToolCall]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for call in tool_calls.iter_mut() {
+        match repair_arguments(&call.function.arguments) {
+            Ok(args) => call.function.arguments = args,
+            Err(problem) => failures.push((call.function.name.clone(), problem)),
+        }
+    }
+    failures
+}
+
+fn parse_object(text: &str) -> Result<Value, String> {
+    let text = strip_code_fence(text.trim());
+    if text.is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+
+    let parsed = serde_json::from_str::<Value>(text).or_else(|e| {
+        // Trailing text after the object ("{...} I'll read that file now")
+        first_object(text)
+            .and_then(|object| serde_json::from_str::<Value>(object).ok())
+            .ok_or_else(|| format!("arguments are not valid JSON ({})", e))
+    })?;
+
+    match parsed {
+        Value::Object(_) => Ok(parsed),
+        // Stringified twice
+        Value::String(inner) if inner.trim_start().starts_with('{') => match serde_json::from_str(&inner) {
+            Ok(value @ Value::Object(_)) => Ok(value),
+            _ => Err("arguments are a string that isn't a JSON object".to_string()),
+        },
+        other => Err(format!("arguments must be a JSON object, got {}", kind(&other))),
+    }
+}
+
+/// The contents of a ```-fenced block, or the text unchanged.
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    // Skip the language tag line
+    let body = rest.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// The first balanced `{...}` in `text`, ignoring braces inside strings.
+fn first_object(text: &str) -> Option<&str> {
+    let start = text.find('{')?;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (offset, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..=start + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::agent::execution::tool_repair::repair_arguments;
+
+    #[test]
+    fn objects_pass_through_and_null_becomes_empty() {
+        let args = json!({ "path": "notes.txt" });
+        assert_eq!(repair_arguments(&args).unwrap(), args);
+        assert_eq!(repair_arguments(&json!(null)).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn stringified_arguments_are_parsed() {
+        let args = json!("{\"path\": \"notes.txt\"}");
+        assert_eq!(repair_arguments(&args).unwrap(), json!({ "path": "notes.txt" }));
+
+        // Stringified twice
+        let args = json!("\"{\\\"path\\\": \\\"notes.txt\\\"}\"");
+        assert_eq!(repair_arguments(&args).unwrap(), json!({ "path": "notes.txt" }));
+    }
+
+    #[test]
+    fn code_fences_and_trailing_text_are_stripped() {
+        let fenced = json!("```json\n{\"query\": \"rust\"}\n```");
+        assert_eq!(repair_arguments(&fenced).unwrap(), json!({ "query": "rust" }));
+
+        let trailing = json!("{\"query\": \"a } in text\"} Let me search for that.");
+        assert_eq!(repair_arguments(&trailing).unwrap(), json!({ "query": "a } in text" }));
+    }
+
+    #[test]
+    fn unrecoverable_arguments_are_described() {
+        assert!(repair_arguments(&json!("path=notes.txt")).unwrap_err().contains("not valid JSON"));
+        assert!(repair_arguments(&json!([1, 2])).unwrap_err().contains("an array"));
+        assert!(repair_arguments(&json!("[1, 2]")).unwrap_err().contains("an array"));
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use serde_json::Value;
 use artificer_shared::tools::get_tool_schema;
-use crate::agent::tools::is_task_tool;
+use crate::agent::tools::{is_device_tool, is_introspection_tool, is_memory_tool, is_scratchpad_tool, is_task_tool};
 
 /// Validate a tool call before execution.
 ///
@@ -9,8 +9,12 @@ use crate::agent::tools::is_task_tool;
 /// parameters are present in `args`. Task tools bypass schema
 /// validation — they are always considered valid here.
 pub fn validate_tool_call(tool_name: &str, args: &Value) -> Result<()> {
-    // Task, delegation, and specialist control tools are handled internally — always valid here
+    // Task, memory, scratchpad, device, assistant, delegation, and specialist control tools are handled internally — always valid here
     if is_task_tool(tool_name)
+        || is_memory_tool(tool_name)
+        || is_scratchpad_tool(tool_name)
+        || is_device_tool(tool_name)
+        || is_introspection_tool(tool_name)
         || tool_name.starts_with("delegate::")
         || tool_name.starts_with("response::")
     {
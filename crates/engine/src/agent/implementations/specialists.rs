@@ -1,30 +1,65 @@
 use crate::define_agents;
 use crate::agent::{AgentRoles, ExecutionMode};
+use crate::agent::response_filters::{InsertCitations, NormalizeMarkdown, Redact, StripThinking};
 
 define_agents! {
     Orchestrator: AgentRoles::Orchestrator => {
         description: "Primary orchestrator that coordinates tasks and manages workflow",
         execution_mode: ExecutionMode::Agentic,
         system_prompt: "",
-        toolbelts: [],
+        toolbelts: ["Clock::", "Clipboard::", "Screen::", "Notifier::", "Watcher::"],
         task_tools: true,
         delegation_tools: true,
+        memory_tools: true,
+        scratchpad_tools: true,
+        device_tools: true,
+        introspection_tools: true,
+        response_filters: [StripThinking, Redact, NormalizeMarkdown],
     },
 
     FileSmith: AgentRoles::Specialist => {
-        description: "File system specialist for reading, writing, and manipulating files",
+        description: "File system specialist for reading, writing, and manipulating files, and for git repositories",
         execution_mode: ExecutionMode::Agentic,
         system_prompt: include_str!("../prompts/file_smith.txt"),
-        toolbelts: ["FileSmith::"],
+        toolbelts: ["FileSmith::", "GitSmith::"],
         task_tools: true,
         specialist_tools: true,
+        english_only: true,
     },
 
     WebResearcher: AgentRoles::Specialist => {
         description: "Web research specialist for searching and fetching web content",
         execution_mode: ExecutionMode::Agentic,
         system_prompt: include_str!("../prompts/web_researcher.txt"),
-        toolbelts: ["WebSearch::"],
+        toolbelts: ["WebSearch::", "Clock::"],
+        task_tools: true,
+        specialist_tools: true,
+        response_filters: [StripThinking, InsertCitations, Redact],
+    },
+
+    Cartographer: AgentRoles::Specialist => {
+        description: "Places and location specialist for finding businesses, landmarks and addresses",
+        execution_mode: ExecutionMode::Agentic,
+        system_prompt: include_str!("../prompts/cartographer.txt"),
+        toolbelts: ["Places::"],
+        task_tools: true,
+        specialist_tools: true,
+    },
+
+    ReportWriter: AgentRoles::Specialist => {
+        description: "Report specialist that turns research results into a Markdown report file",
+        execution_mode: ExecutionMode::Agentic,
+        system_prompt: include_str!("../prompts/report_writer.txt"),
+        toolbelts: ["Reports::", "FileSmith::"],
+        task_tools: true,
+        specialist_tools: true,
+    },
+
+    HostOps: AgentRoles::Specialist => {
+        description: "Host operations specialist for containers, system logs and shell commands",
+        execution_mode: ExecutionMode::Agentic,
+        system_prompt: include_str!("../prompts/host_ops.txt"),
+        toolbelts: ["Containers::", "LogScout::", "ShellSmith::"],
         task_tools: true,
         specialist_tools: true,
     },
@@ -36,6 +71,7 @@ define_agents! {
         toolbelts: ["Archivist::"],
         task_tools: true,
         specialist_tools: true,
+        english_only: true,
     },
 
     TitleGenerator: AgentRoles::Background => {
@@ -45,4 +81,36 @@ define_agents! {
         toolbelts: [],
         task_tools: false,
     },
+
+    Translator: AgentRoles::Background => {
+        description: "Translates text between languages",
+        execution_mode: ExecutionMode::OneTime,
+        system_prompt: "You translate text into the language named in the request. Keep names, file paths, URLs, code, and numbers exactly as written. Output only the translation, no explanation.",
+        toolbelts: [],
+        task_tools: false,
+    },
+
+    Summarizer: AgentRoles::Background => {
+        description: "Summarizes one part of a long conversation",
+        execution_mode: ExecutionMode::OneTime,
+        system_prompt: "You summarize one part of a longer conversation between a user and an assistant. Keep the decisions, facts, names, numbers, open questions and anything the user asked to remember. Drop greetings and small talk. Write a few short paragraphs or bullet points. Output only the summary.",
+        toolbelts: [],
+        task_tools: false,
+    },
+
+    SummaryMerger: AgentRoles::Background => {
+        description: "Merges summaries of a conversation's parts into one",
+        execution_mode: ExecutionMode::OneTime,
+        system_prompt: "You merge summaries of consecutive parts of one conversation into a single summary. Keep the order of events, drop repetition, and when a later part changes an earlier decision, keep only where it ended up. Keep the decisions, facts, names, numbers and open questions. Output only the merged summary.",
+        toolbelts: [],
+        task_tools: false,
+    },
+
+    Critic: AgentRoles::Background => {
+        description: "Checks a draft answer against the tool results it rests on",
+        execution_mode: ExecutionMode::OneTime,
+        system_prompt: include_str!("../prompts/critic.txt"),
+        toolbelts: [],
+        task_tools: false,
+    },
 }
@@ -0,0 +1,139 @@
+use artificer_shared::db::Db;
+
+/// Languages Artificer can detect and be asked to reply in, as (ISO 639-1 code, English name).
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("ru", "Russian"),
+    ("uk", "Ukrainian"),
+    ("el", "Greek"),
+    ("ar", "Arabic"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("th", "Thai"),
+    ("zh", "Chinese"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+];
+
+/// Frequent short words per Latin-script language. A message is scored by how
+/// many of its words appear in each list.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "of", "to", "in", "that", "it", "you", "what", "this", "with", "for", "can", "how", "my", "please"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "du", "sie", "ein", "eine", "mit", "für", "auf", "wie", "was", "bitte", "kannst"]),
+    ("fr", &["le", "la", "les", "et", "est", "une", "des", "je", "vous", "pas", "que", "qui", "pour", "dans", "avec", "comment", "mon", "s'il"]),
+    ("es", &["el", "la", "los", "las", "y", "es", "una", "que", "por", "para", "con", "no", "cómo", "qué", "mi", "puedes", "del", "está"]),
+    ("it", &["il", "lo", "gli", "e", "è", "una", "che", "per", "non", "con", "sono", "come", "cosa", "mi", "puoi", "della", "questo", "grazie"]),
+    ("pt", &["o", "os", "as", "e", "é", "uma", "que", "não", "para", "com", "por", "como", "você", "meu", "do", "da", "isso", "obrigado"]),
+    ("nl", &["de", "het", "een", "en", "is", "niet", "ik", "je", "van", "dat", "met", "voor", "op", "hoe", "wat", "mijn", "kun", "alsjeblieft"]),
+];
+
+/// Messages with fewer letters than this are too short to call.
+const MIN_LETTERS: usize = 12;
+
+/// English name for a language code, if it's one we know.
+pub fn language_name(code: &str) -> Option<&'static str> {
+    LANGUAGES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+/// Best guess at the language `text` is written in, or None when it's too
+/// short or too mixed to tell.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() < MIN_LETTERS {
+        return None;
+    }
+
+    if let Some(code) = detect_script(&letters) {
+        return Some(code);
+    }
+
+    detect_latin(text)
+}
+
+/// Non-Latin scripts mostly identify the language on their own.
+fn detect_script(letters: &[char]) -> Option<&'static str> {
+    let count = |range: &[(u32, u32)]| {
+        letters.iter()
+            .filter(|c| range.iter().any(|(lo, hi)| (*lo..=*hi).contains(&(**c as u32))))
+            .count()
+    };
+
+    let kana = count(&[(0x3040, 0x30FF)]);
+    let scripts = [
+        ("ko", count(&[(0xAC00, 0xD7AF), (0x1100, 0x11FF)])),
+        ("ja", kana),
+        // Han without any kana is Chinese; with kana it's Japanese
+        ("zh", if kana > 0 { 0 } else { count(&[(0x4E00, 0x9FFF)]) }),
+        ("ru", count(&[(0x0400, 0x04FF)])),
+        ("el", count(&[(0x0370, 0x03FF)])),
+        ("ar", count(&[(0x0600, 0x06FF)])),
+        ("he", count(&[(0x0590, 0x05FF)])),
+        ("hi", count(&[(0x0900, 0x097F)])),
+        ("th", count(&[(0x0E00, 0x0E7F)])),
+    ];
+
+    let (code, hits) = scripts.iter().max_by_key(|(_, hits)| *hits)?;
+    if *hits * 2 < letters.len() {
+        return None;
+    }
+
+    // Ukrainian shares Cyrillic with Russian but has letters Russian lacks
+    if *code == "ru" && letters.iter().any(|c| matches!(c, 'і' | 'ї' | 'є' | 'ґ' | 'І' | 'Ї' | 'Є' | 'Ґ')) {
+        return Some("uk");
+    }
+    Some(code)
+}
+
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphabetic() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut scores: Vec<(&str, usize)> = STOPWORDS.iter()
+        .map(|(code, list)| (*code, words.iter().filter(|w| list.contains(w)).count()))
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+
+    let (best, best_hits) = scores[0];
+    let runner_up = scores[1].1;
+    // Require a clear winner; ties and single hits are noise
+    if best_hits >= 2 && best_hits > runner_up {
+        Some(best)
+    } else {
+        None
+    }
+}
+
+/// The language to answer in for this conversation.
+///
+/// A language already detected for the conversation sticks. Otherwise the
+/// message is examined and a confident detection is saved to the conversation.
+/// Failing that, the device's preferred language is used without being saved,
+/// so a later, longer message can still set the conversation's language.
+pub fn resolve_conversation_language(
+    db: &Db,
+    device_id: u64,
+    conversation_id: u64,
+    message: &str,
+) -> Option<String> {
+    if let Ok(Some(language)) = db.get_conversation_language(conversation_id) {
+        return Some(language);
+    }
+
+    if let Some(code) = detect(message) {
+        if let Err(e) = db.set_conversation_language(conversation_id, code) {
+            eprintln!("Failed to save language for conversation {}: {}", conversation_id, e);
+        }
+        return Some(code.to_string());
+    }
+
+    db.get_device_language(device_id).ok().flatten()
+}
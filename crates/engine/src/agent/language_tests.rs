@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use artificer_shared::db::Db;
+    use crate::agent::language::{detect, resolve_conversation_language};
+
+    #[test]
+    fn detects_latin_languages_by_common_words() {
+        assert_eq!(detect("Can you tell me what the weather is like in the mountains?"), Some("en"));
+        assert_eq!(detect("Kannst du mir bitte sagen, wie das Wetter in den Bergen ist?"), Some("de"));
+        assert_eq!(detect("Est-ce que vous pouvez me dire comment est la météo dans les montagnes?"), Some("fr"));
+        assert_eq!(detect("¿Puedes decirme cómo está el tiempo en las montañas?"), Some("es"));
+    }
+
+    #[test]
+    fn detects_other_scripts() {
+        assert_eq!(detect("Какая сегодня погода в горах, расскажи подробно"), Some("ru"));
+        assert_eq!(detect("Яка сьогодні погода в горах, розкажи докладніше, будь ласка"), Some("uk"));
+        assert_eq!(detect("今日の山の天気はどうですか、詳しく教えてください"), Some("ja"));
+        assert_eq!(detect("오늘 산의 날씨는 어떻습니까 자세히 알려주세요"), Some("ko"));
+    }
+
+    #[test]
+    fn short_or_ambiguous_text_is_undetected() {
+        assert_eq!(detect("ok"), None);
+        assert_eq!(detect("cargo build --release"), None);
+    }
+
+    #[test]
+    fn detected_language_sticks_to_the_conversation() {
+        let db = Db::in_memory().unwrap();
+        db.execute(
+            "INSERT INTO devices (device_name, device_key, active, created, last_seen)
+             VALUES ('laptop', 'laptop-key', 1, 0, 0)",
+            [],
+        ).unwrap();
+        db.set_device_language(1, Some("fr")).unwrap();
+        let conversation = db.create_conversation(1).unwrap();
+
+        // Too short to detect: the device preference applies but isn't saved
+        assert_eq!(resolve_conversation_language(&db, 1, conversation, "ok").as_deref(), Some("fr"));
+        assert_eq!(db.get_conversation_language(conversation).unwrap(), None);
+
+        let german = "Kannst du mir bitte sagen, wie das Wetter ist?";
+        assert_eq!(resolve_conversation_language(&db, 1, conversation, german).as_deref(), Some("de"));
+        assert_eq!(resolve_conversation_language(&db, 1, conversation, "thanks").as_deref(), Some("de"));
+    }
+}
@@ -1,10 +1,57 @@
 use anyhow::Result;
 use futures_util::StreamExt;
 use reqwest::Client;
-use crate::agent::llm_types::{LlmRequest, LlmResponse, StreamChunk};
+use crate::agent::llm_stream::{ChunkDecoder, StreamAccumulator};
+use crate::agent::llm_types::{LlmRequest, LlmResponse};
 use crate::pool::GpuHandle;
 use crate::api::events::EventSender;
-use artificer_shared::{Message, ToolCall};
+use artificer_shared::Message;
+
+/// Why a call to Ollama failed. Returned inside `anyhow::Error`; use
+/// `is_transient` to decide whether trying again can help.
+#[derive(Debug)]
+pub enum LlmError {
+    /// The connection failed or reset, timed out, or Ollama answered 429/5xx.
+    /// Nothing reached the client, so the call is safe to repeat.
+    Transient(String),
+    /// Ollama answered, but not usefully: a rejected request or an empty reply.
+    Model(String),
+}
+
+impl std::fmt::Display for LlmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmError::Transient(message) | LlmError::Model(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// Whether a failed LLM call is worth retrying.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<LlmError>(), Some(LlmError::Transient(_)))
+}
+
+/// Classify a failed HTTP exchange with Ollama.
+fn request_failed(context: &str, error: reqwest::Error) -> anyhow::Error {
+    let message = format!("{}: {}", context, error);
+    if error.is_connect() || error.is_timeout() || error.is_request() || error.is_body() {
+        LlmError::Transient(message).into()
+    } else {
+        LlmError::Model(message).into()
+    }
+}
+
+/// Classify a non-2xx answer from Ollama.
+fn status_failed(context: &str, status: reqwest::StatusCode, body: String) -> anyhow::Error {
+    let message = format!("{} ({}): {}", context, status, body);
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LlmError::Transient(message).into()
+    } else {
+        LlmError::Model(message).into()
+    }
+}
 
 pub struct LlmClient<'a> {
     client: &'a Client,
@@ -25,27 +72,27 @@ impl<'a> LlmClient<'a> {
             .post(&url)
             .json(&request)
             .send()
-            .await?;
+            .await
+            .map_err(|e| request_failed("LLM request failed", e))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-            "LLM request failed ({}): {}",
-            status,
-            error_text
-        ));
+            return Err(status_failed("LLM request failed", status, error_text));
         }
 
-        let llm_response: LlmResponse = response.json().await?;
+        let llm_response: LlmResponse = response
+            .json()
+            .await
+            .map_err(|e| request_failed("LLM response unreadable", e))?;
 
         // ✓ Validate response has content
         if llm_response.message.content.is_none()
             && llm_response.message.tool_calls.is_none()
         {
-            return Err(anyhow::anyhow!(
-            "LLM returned empty response (no content and no tool_calls)"
-        ));
+            return Err(LlmError::Model(
+                "LLM returned empty response (no content and no tool_calls)".to_string()
+            ).into());
         }
 
         Ok(llm_response)
@@ -64,75 +111,63 @@ impl<'a> LlmClient<'a> {
             .post(&url)
             .json(&request)
             .send()
-            .await?;
+            .await
+            .map_err(|e| request_failed("LLM streaming request failed", e))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-            "LLM streaming request failed ({}): {}",
-            status,
-            error_text
-        ));
+            return Err(status_failed("LLM streaming request failed", status, error_text));
         }
 
         let mut stream = response.bytes_stream();
-        let mut accumulated_content = String::new();
-        let mut tool_calls: Option<Vec<ToolCall>> = None;
-        let mut buffer = Vec::new();
-        let mut done = false;  // ✓ Track done state at outer scope
-
-        while let Some(chunk) = stream.next().await {
-            if done {
-                break;  // ✓ Exit stream consumption when done
-            }
-
-            let bytes = chunk?;
-            buffer.extend_from_slice(&bytes);
-
-            while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
-                let line: Vec<u8> = buffer.drain(..=newline_pos).collect();
-                let line_str = String::from_utf8_lossy(&line);
-
-                if line_str.trim().is_empty() {
-                    continue;
+        let mut accumulator = StreamAccumulator::new();
+        let mut decoder = ChunkDecoder::new();
+
+        'read: while let Some(chunk) = stream.next().await {
+            // Once text has reached the client a retry would repeat it
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) if accumulator.content().is_empty() => {
+                    return Err(request_failed("LLM stream interrupted", e));
                 }
+                Err(e) => return Err(anyhow::anyhow!("LLM stream interrupted mid-answer: {}", e)),
+            };
 
-                if let Ok(chunk) = serde_json::from_str::<StreamChunk>(&line_str) {
-                    if let Some(msg) = chunk.message {
-                        if let Some(content) = msg.content {
-                            if !content.is_empty() {
-                                events.stream_chunk(content.clone());
-                                accumulated_content.push_str(&content);
-                            }
-                        }
-                        if let Some(calls) = msg.tool_calls {
-                            tool_calls = Some(calls);
-                        }
-                    }
-
-                    if chunk.done {
-                        done = true;  // ✓ Set flag
-                        break;        // ✓ Break inner loop
-                    }
+            for chunk in decoder.feed(&bytes) {
+                if let Some(content) = accumulator.push(chunk) {
+                    events.stream_chunk(content);
+                }
+                if accumulator.is_done() {
+                    break 'read;
                 }
             }
         }
 
+        // A last chunk without a trailing newline
+        if !accumulator.is_done()
+            && let Some(chunk) = decoder.finish()
+            && let Some(content) = accumulator.push(chunk)
+        {
+            events.stream_chunk(content);
+        }
+        if decoder.skipped() > 0 {
+            eprintln!("⚠ Skipped {} malformed line(s) in an LLM stream from {}", decoder.skipped(), self.gpu.url);
+        }
+
+        let role = accumulator.role().to_string();
+        let (content, tool_calls) = accumulator.finish();
+
         // ✓ Validate we got something back
-        if accumulated_content.is_empty() && tool_calls.is_none() {
-            return Err(anyhow::anyhow!(
-            "LLM returned empty response (no content and no tool_calls)"
-        ));
+        if content.is_none() && tool_calls.is_none() {
+            return Err(LlmError::Model(
+                "LLM returned empty response (no content and no tool_calls)".to_string()
+            ).into());
         }
 
         Ok(Message {
-            role: "assistant".to_string(),
-            content: if accumulated_content.is_empty() {
-                None
-            } else {
-                Some(accumulated_content)
-            },
+            role,
+            content,
             tool_calls,
         })
     }
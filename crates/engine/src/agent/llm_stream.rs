@@ -0,0 +1,223 @@
+use serde_json::Value;
+use artificer_shared::{FunctionCall, ToolCall};
+use crate::agent::llm_types::{StreamChunk, StreamMessage, StreamToolCall};
+
+/// Longest line kept while waiting for its newline. Ollama's chunks are a
+/// few hundred bytes; anything this long is a broken stream, and the rest of
+/// the line is thrown away instead of growing the buffer without bound.
+pub const MAX_LINE_BYTES: usize = 1 << 20;
+
+/// Splits Ollama's newline-delimited JSON byte stream into chunks.
+///
+/// Network reads end anywhere, so a partial line waits in the buffer for the
+/// rest. Lines end in `\n` or `\r\n`. A line that isn't JSON is logged and
+/// skipped; one that is JSON of the wrong shape keeps whatever content and
+/// role it carries. Either way the chunks after it still count.
+#[derive(Debug, Default)]
+pub struct ChunkDecoder {
+    buffer: Vec<u8>,
+    /// Set while throwing away the rest of an overlong line.
+    discarding: bool,
+    skipped: usize,
+}
+
+impl ChunkDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes read from the stream. Returns the chunks of every line they
+    /// complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<StreamChunk> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut chunks = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            if std::mem::take(&mut self.discarding) {
+                continue;
+            }
+            chunks.extend(self.parse_line(&line));
+        }
+
+        if self.buffer.len() > MAX_LINE_BYTES {
+            if !self.discarding {
+                eprintln!("⚠ Skipping LLM stream line longer than {} bytes", MAX_LINE_BYTES);
+                self.skipped += 1;
+            }
+            self.buffer.clear();
+            self.discarding = true;
+        }
+        chunks
+    }
+
+    /// The chunk of a last line the stream ended without a newline after.
+    pub fn finish(&mut self) -> Option<StreamChunk> {
+        let line = std::mem::take(&mut self.buffer);
+        if std::mem::take(&mut self.discarding) {
+            return None;
+        }
+        self.parse_line(&line)
+    }
+
+    /// Lines thrown away so far, for logging.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn parse_line(&mut self, line: &[u8]) -> Option<StreamChunk> {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            return None;
+        }
+        if line.len() > MAX_LINE_BYTES {
+            eprintln!("⚠ Skipping LLM stream line longer than {} bytes", MAX_LINE_BYTES);
+            self.skipped += 1;
+            return None;
+        }
+        let error = match serde_json::from_slice::<StreamChunk>(line) {
+            Ok(chunk) => return Some(chunk),
+            Err(e) => e,
+        };
+        let salvaged = serde_json::from_slice::<Value>(line).ok().and_then(|value| salvage(&value));
+        if salvaged.is_none() {
+            self.skipped += 1;
+        }
+        eprintln!(
+            "⚠ {} malformed LLM stream line ({}): {}",
+            if salvaged.is_some() { "Salvaged" } else { "Skipping" },
+            error,
+            preview(line),
+        );
+        salvaged
+    }
+}
+
+/// The content, role and done flag of a chunk whose other fields (usually
+/// its tool calls) don't parse.
+fn salvage(value: &Value) -> Option<StreamChunk> {
+    let message = &value["message"];
+    let content = message["content"].as_str().map(String::from);
+    let done = value["done"].as_bool().unwrap_or(false);
+    if content.is_none() && !done {
+        return None;
+    }
+    Some(StreamChunk {
+        message: Some(StreamMessage {
+            role: message["role"].as_str().map(String::from),
+            content,
+            tool_calls: None,
+        }),
+        done,
+    })
+}
+
+/// The start of a bad line, short enough for a log line.
+fn preview(line: &[u8]) -> String {
+    let text = String::from_utf8_lossy(line);
+    match text.char_indices().nth(120) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.into_owned(),
+    }
+}
+
+/// Builds one assistant message from a stream of chunks.
+///
+/// Ollama usually sends each tool call whole, but may send calls in
+/// different chunks, repeat a call on the final chunk, or split one call
+/// into deltas (numbered by `index`, or continuing the previous call when
+/// the name is missing). Content is concatenated; tool calls are merged.
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    /// The first role any chunk named, so one lost chunk doesn't lose it.
+    role: Option<String>,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    /// Server-assigned index of each call, where it had one.
+    indices: Vec<Option<usize>>,
+    done: bool,
+}
+
+impl StreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk. Returns its new content, for streaming to the client.
+    pub fn push(&mut self, chunk: StreamChunk) -> Option<String> {
+        if chunk.done {
+            self.done = true;
+        }
+        let message = chunk.message?;
+        if self.role.is_none() {
+            self.role = message.role.filter(|role| !role.is_empty());
+        }
+        for call in message.tool_calls.unwrap_or_default() {
+            self.merge_call(call);
+        }
+        let content = message.content.filter(|content| !content.is_empty())?;
+        self.content.push_str(&content);
+        Some(content)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The role the chunks named, `assistant` if none did.
+    pub fn role(&self) -> &str {
+        self.role.as_deref().unwrap_or("assistant")
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The finished content and tool calls; None for either that is empty.
+    pub fn finish(self) -> (Option<String>, Option<Vec<ToolCall>>) {
+        let content = (!self.content.is_empty()).then_some(self.content);
+        let tool_calls = (!self.tool_calls.is_empty()).then_some(self.tool_calls);
+        (content, tool_calls)
+    }
+
+    fn merge_call(&mut self, call: StreamToolCall) {
+        let function = call.function;
+
+        let existing = match function.index {
+            Some(index) => self.indices.iter().position(|i| *i == Some(index)),
+            None if function.name.is_empty() => self.tool_calls.len().checked_sub(1),
+            None => None,
+        };
+        if let Some(position) = existing {
+            let target = &mut self.tool_calls[position].function;
+            if target.name.is_empty() {
+                target.name = function.name;
+            }
+            merge_arguments(&mut target.arguments, function.arguments);
+            return;
+        }
+
+        // A complete call sent again (e.g. repeated on the final chunk)
+        if function.index.is_none()
+            && self.tool_calls.iter().any(|c| c.function.name == function.name && c.function.arguments == function.arguments)
+        {
+            return;
+        }
+
+        self.indices.push(function.index);
+        self.tool_calls.push(ToolCall {
+            function: FunctionCall { name: function.name, arguments: function.arguments },
+        });
+    }
+}
+
+/// Extend a call's arguments with a later delta: string fragments are
+/// appended, object fields added.
+fn merge_arguments(target: &mut Value, delta: Value) {
+    match (target, delta) {
+        (_, Value::Null) => {}
+        (Value::String(text), Value::String(more)) => text.push_str(&more),
+        (Value::Object(fields), Value::Object(more)) => fields.extend(more),
+        (target, delta) => *target = delta,
+    }
+}
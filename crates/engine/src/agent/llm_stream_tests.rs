@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::agent::llm_stream::{ChunkDecoder, StreamAccumulator, MAX_LINE_BYTES};
+    use crate::agent::llm_types::StreamChunk;
+
+    /// Feed recorded NDJSON lines through an accumulator, as the client does.
+    fn replay(lines: &[&str]) -> StreamAccumulator {
+        let mut accumulator = StreamAccumulator::new();
+        for line in lines {
+            let chunk: StreamChunk = serde_json::from_str(line).unwrap();
+            accumulator.push(chunk);
+        }
+        accumulator
+    }
+
+    #[test]
+    fn content_is_concatenated_and_done_is_tracked() {
+        let accumulator = replay(&[
+            r#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"lo"},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":""},"done":true}"#,
+        ]);
+        assert!(accumulator.is_done());
+        let (content, calls) = accumulator.finish();
+        assert_eq!(content.as_deref(), Some("Hello"));
+        assert!(calls.is_none());
+    }
+
+    #[test]
+    fn calls_in_separate_chunks_are_all_kept() {
+        let (content, calls) = replay(&[
+            r#"{"message":{"role":"assistant","content":"Let me look.","tool_calls":[{"function":{"name":"file_smith::read_file","arguments":{"path":"a.txt"}}}]},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"file_smith::read_file","arguments":{"path":"b.txt"}}}]},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":""},"done":true}"#,
+        ]).finish();
+
+        assert_eq!(content.as_deref(), Some("Let me look."));
+        let calls = calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function.arguments, json!({ "path": "a.txt" }));
+        assert_eq!(calls[1].function.arguments, json!({ "path": "b.txt" }));
+    }
+
+    #[test]
+    fn a_call_repeated_on_the_final_chunk_is_not_duplicated() {
+        let (_, calls) = replay(&[
+            r#"{"message":{"content":"","tool_calls":[{"function":{"name":"clock::now","arguments":{}}}]},"done":false}"#,
+            r#"{"message":{"content":"","tool_calls":[{"function":{"name":"clock::now","arguments":{}}}]},"done":true}"#,
+        ]).finish();
+        assert_eq!(calls.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn indexed_deltas_are_merged_into_their_calls() {
+        let (_, calls) = replay(&[
+            r#"{"message":{"content":"","tool_calls":[{"function":{"index":0,"name":"web_search::search","arguments":"{\"query\": \"ru"}}]},"done":false}"#,
+            r#"{"message":{"content":"","tool_calls":[{"function":{"index":1,"name":"clock::now","arguments":"{}"}}]},"done":false}"#,
+            r#"{"message":{"content":"","tool_calls":[{"function":{"index":0,"arguments":"st 2024\"}"}}]},"done":true}"#,
+        ]).finish();
+
+        let calls = calls.unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function.name, "web_search::search");
+        assert_eq!(calls[0].function.arguments, json!("{\"query\": \"rust 2024\"}"));
+        assert_eq!(calls[1].function.name, "clock::now");
+    }
+
+    #[test]
+    fn unnamed_deltas_continue_the_previous_call() {
+        let (_, calls) = replay(&[
+            r#"{"message":{"content":"","tool_calls":[{"function":{"name":"places::search","arguments":{"query":"coffee"}}}]},"done":false}"#,
+            r#"{"message":{"content":"","tool_calls":[{"function":{"arguments":{"near":"Berlin"}}}]},"done":true}"#,
+        ]).finish();
+
+        let calls = calls.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function.arguments, json!({ "query": "coffee", "near": "Berlin" }));
+    }
+
+    /// Feed a raw byte stream through a decoder in reads of `read_size`
+    /// bytes, as they might arrive off the network.
+    fn decode(stream: &[u8], read_size: usize) -> (StreamAccumulator, usize) {
+        let mut decoder = ChunkDecoder::new();
+        let mut accumulator = StreamAccumulator::new();
+        for bytes in stream.chunks(read_size) {
+            for chunk in decoder.feed(bytes) {
+                accumulator.push(chunk);
+            }
+        }
+        if let Some(chunk) = decoder.finish() {
+            accumulator.push(chunk);
+        }
+        (accumulator, decoder.skipped())
+    }
+
+    /// A stream with a garbage line, a truncated line, CRLF endings, a
+    /// multi-byte character split across reads and no final newline.
+    const CORRUPTED: &str = concat!(
+        "{\"message\":{\"role\":\"assistant\",\"content\":\"Grüß\"},\"done\":false}\r\n",
+        "<html>502 Bad Gateway</html>\n",
+        "{\"message\":{\"role\":\"assistant\",\"content\":\" Gott\n",
+        "\r\n",
+        "{\"message\":{\"role\":\"assistant\",\"content\":\", Welt\"},\"done\":false}\r\n",
+        "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}",
+    );
+
+    #[test]
+    fn bad_lines_are_skipped_and_the_rest_is_kept() {
+        for read_size in [1, 3, 7, 64, CORRUPTED.len()] {
+            let (accumulator, skipped) = decode(CORRUPTED.as_bytes(), read_size);
+            assert!(accumulator.is_done());
+            assert_eq!(skipped, 2);
+            assert_eq!(accumulator.content(), "Grüß, Welt");
+        }
+    }
+
+    #[test]
+    fn misshapen_chunks_keep_their_content_and_role() {
+        // The first chunk's tool calls don't parse; later chunks still name the role
+        let stream = concat!(
+            "{\"message\":{\"content\":\"Checking\",\"tool_calls\":\"oops\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\" now.\"},\"done\":true}\n",
+        );
+        let (accumulator, skipped) = decode(stream.as_bytes(), 16);
+        assert_eq!(skipped, 0);
+        assert_eq!(accumulator.role(), "assistant");
+        assert_eq!(accumulator.content(), "Checking now.");
+    }
+
+    #[test]
+    fn an_overlong_line_is_dropped_without_growing_the_buffer() {
+        let mut stream = String::from("{\"message\":{\"content\":\"");
+        stream.push_str(&"x".repeat(MAX_LINE_BYTES + 10));
+        stream.push_str("\"},\"done\":false}\n");
+        stream.push_str("{\"message\":{\"content\":\"short\"},\"done\":true}\n");
+
+        let (accumulator, skipped) = decode(stream.as_bytes(), 4096);
+        assert_eq!(skipped, 1);
+        assert!(accumulator.is_done());
+        assert_eq!(accumulator.content(), "short");
+    }
+}
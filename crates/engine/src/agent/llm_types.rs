@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use artificer_shared::{Message, Tool, ToolCall};
+use serde_json::Value;
+use artificer_shared::{Message, Tool};
 
 /// Response from the LLM (non-streaming)
 #[derive(Debug, Clone, Deserialize)]
@@ -16,6 +17,15 @@ pub struct LlmRequest {
     pub tools: Option<Vec<Tool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<LlmOptions>,
+}
+
+/// Ollama's per-request model options; only the ones we set.
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmOptions {
+    /// Most tokens to generate in this call
+    pub num_predict: u32,
 }
 
 impl LlmRequest {
@@ -25,6 +35,7 @@ impl LlmRequest {
             messages,
             tools: None,
             stream: None,
+            options: None,
         }
     }
 
@@ -37,6 +48,12 @@ impl LlmRequest {
         self.stream = Some(stream);
         self
     }
+
+    /// Cap generated tokens, if there is a cap.
+    pub fn with_num_predict(mut self, num_predict: Option<u32>) -> Self {
+        self.options = num_predict.map(|num_predict| LlmOptions { num_predict });
+        self
+    }
 }
 
 /// A single streaming chunk from the LLM
@@ -49,6 +66,28 @@ pub struct StreamChunk {
 
 #[derive(Debug, Deserialize)]
 pub struct StreamMessage {
+    /// Sent on every chunk by Ollama; missing on some compatible servers.
+    #[serde(default)]
+    pub role: Option<String>,
     pub content: Option<String>,
-    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_calls: Option<Vec<StreamToolCall>>,
+}
+
+/// A tool call as it appears in one chunk: complete, or a delta of a call
+/// started in an earlier chunk.
+#[derive(Debug, Deserialize)]
+pub struct StreamToolCall {
+    pub function: StreamFunction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamFunction {
+    /// Which call a delta belongs to, when the server numbers them.
+    #[serde(default)]
+    pub index: Option<usize>,
+    /// Empty on deltas that continue a call.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
 }
@@ -9,7 +9,13 @@ macro_rules! define_agents {
                 toolbelts: [$($toolbelt:literal),* $(,)?],
                 $(task_tools: $has_task_tools:expr,)?
                 $(delegation_tools: $has_delegation_tools:expr,)?
+                $(memory_tools: $has_memory_tools:expr,)?
+                $(scratchpad_tools: $has_scratchpad_tools:expr,)?
+                $(device_tools: $has_device_tools:expr,)?
+                $(introspection_tools: $has_introspection_tools:expr,)?
                 $(specialist_tools: $has_specialist_tools:expr,)?
+                $(english_only: $english_only:expr,)?
+                $(response_filters: [$($filter:expr),* $(,)?],)?
             }
         ),* $(,)?
     ) => {
@@ -23,6 +29,16 @@ macro_rules! define_agents {
                 &[$(AgentType::$name),*]
             }
 
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(AgentType::$name => stringify!($name)),*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<AgentType> {
+                Self::all().iter().copied().find(|t| t.name() == name)
+            }
+
             pub fn build(self) -> $crate::agent::Agent {
                 match self {
                     $(
@@ -38,7 +54,7 @@ macro_rules! define_agents {
                             // Task management tools
                             $(
                                 if $has_task_tools {
-                                    use crate::agent::tools::TASK_TOOLS;
+                                    use $crate::agent::tools::TASK_TOOLS;
                                     let task_tools: Vec<artificer_shared::Tool> = TASK_TOOLS
                                         .iter()
                                         .map(|schema| schema.to_tool())
@@ -50,7 +66,7 @@ macro_rules! define_agents {
                             // Delegation tools
                             $(
                                 if $has_delegation_tools {
-                                    use crate::agent::tools::DELEGATION_TOOLS;
+                                    use $crate::agent::tools::DELEGATION_TOOLS;
                                     let delegation_tools: Vec<artificer_shared::Tool> = DELEGATION_TOOLS
                                         .iter()
                                         .map(|schema| schema.to_tool())
@@ -59,10 +75,58 @@ macro_rules! define_agents {
                                 }
                             )?
 
+                            // Memory tools
+                            $(
+                                if $has_memory_tools {
+                                    use $crate::agent::tools::MEMORY_TOOLS;
+                                    let memory_tools: Vec<artificer_shared::Tool> = MEMORY_TOOLS
+                                        .iter()
+                                        .map(|schema| schema.to_tool())
+                                        .collect();
+                                    tools.extend(memory_tools);
+                                }
+                            )?
+
+                            // Scratchpad tools
+                            $(
+                                if $has_scratchpad_tools {
+                                    use $crate::agent::tools::SCRATCHPAD_TOOLS;
+                                    let scratchpad_tools: Vec<artificer_shared::Tool> = SCRATCHPAD_TOOLS
+                                        .iter()
+                                        .map(|schema| schema.to_tool())
+                                        .collect();
+                                    tools.extend(scratchpad_tools);
+                                }
+                            )?
+
+                            // Cross-device tools
+                            $(
+                                if $has_device_tools {
+                                    use $crate::agent::tools::DEVICE_TOOLS;
+                                    let device_tools: Vec<artificer_shared::Tool> = DEVICE_TOOLS
+                                        .iter()
+                                        .map(|schema| schema.to_tool())
+                                        .collect();
+                                    tools.extend(device_tools);
+                                }
+                            )?
+
+                            // Self-description tools
+                            $(
+                                if $has_introspection_tools {
+                                    use $crate::agent::tools::INTROSPECTION_TOOLS;
+                                    let introspection_tools: Vec<artificer_shared::Tool> = INTROSPECTION_TOOLS
+                                        .iter()
+                                        .map(|schema| schema.to_tool())
+                                        .collect();
+                                    tools.extend(introspection_tools);
+                                }
+                            )?
+
                             // Specialist control tools
                             $(
                                 if $has_specialist_tools {
-                                    use crate::agent::tools::SPECIALIST_CONTROL_TOOLS;
+                                    use $crate::agent::tools::SPECIALIST_CONTROL_TOOLS;
                                     let specialist_ctrl_tools: Vec<artificer_shared::Tool> =
                                         SPECIALIST_CONTROL_TOOLS
                                             .iter()
@@ -79,6 +143,14 @@ macro_rules! define_agents {
                                 execution_mode: $exec_mode,
                                 system_prompt: $prompt,
                                 tools,
+                                english_only: false $(|| $english_only)?,
+                                response_filters: {
+                                    let listed: Option<Vec<std::sync::Arc<dyn $crate::agent::response_filters::ResponseFilter>>> = None
+                                        $(.or(Some(vec![$(std::sync::Arc::new($filter)),*])))?;
+                                    listed
+                                        .map($crate::agent::response_filters::FilterChain::new)
+                                        .unwrap_or_else($crate::agent::response_filters::FilterChain::standard)
+                                },
                             }
                         }
                     ),*
@@ -3,10 +3,25 @@ pub mod macros;
 pub mod execution;
 pub mod state;
 pub mod tools;
+pub mod language;
+#[cfg(test)]
+mod language_tests;
 mod llm_types;
 mod llm_client;
+mod llm_stream;
+#[cfg(test)]
+mod llm_stream_tests;
+pub mod response_filters;
+#[cfg(test)]
+mod response_filters_tests;
+pub mod moderation;
+#[cfg(test)]
+mod moderation_tests;
 
 use artificer_shared::Tool;
+use artificer_shared::db::{Confidence, ToolChoice};
+use artificer_shared::memory::MemorySelection;
+use response_filters::FilterChain;
 pub use state::{TaskState, ExecutionContext, AgentState, SpecialistExecution, TaskPhase};
 pub use implementations::AgentType;
 pub use execution::AgentExecution;
@@ -30,14 +45,20 @@ pub enum AgentRoles {
 pub struct AgentResponse {
     pub content: String,
     pub success: bool,
+    /// The specialist's rating of its own results, when it gave one.
+    pub confidence: Option<Confidence>,
 }
 
 impl AgentResponse {
     pub fn complete(content: String) -> Self {
-        Self { content, success: true }
+        Self { content, success: true, confidence: None }
     }
     pub fn failed(content: String) -> Self {
-        Self { content, success: false }
+        Self { content, success: false, confidence: None }
+    }
+    pub fn with_confidence(mut self, confidence: Option<Confidence>) -> Self {
+        self.confidence = confidence;
+        self
     }
 }
 
@@ -49,10 +70,17 @@ pub struct Agent {
     pub execution_mode: ExecutionMode,
     pub system_prompt: &'static str,
     pub tools: Vec<Tool>,
+    /// Only reliable with English input; delegated goals are translated first.
+    pub english_only: bool,
+    /// Rewrites applied, in order, to the agent's final content.
+    pub response_filters: FilterChain,
 }
 
 impl Agent {
-    pub fn build_system_prompt(&self, task_state: &str) -> String {
+    /// `instructions` replaces `system_prompt` in stage 2; callers pass the
+    /// operator's override when one is active. `memories` must come from the
+    /// conversation's profile only.
+    pub fn build_system_prompt(&self, instructions: &str, memories: &MemorySelection, task_state: &str) -> String {
         let mut prompt = String::new();
 
         // Stage 1: Base prompt by role
@@ -68,15 +96,18 @@ impl Agent {
         prompt.push_str("\n\n");
 
         // Stage 2: Specialist-specific prompt
-        prompt.push_str(self.system_prompt);
+        prompt.push_str(instructions);
         prompt.push_str("\n\n");
 
-        // Stage 3: Available tools
+        // Stage 3: Long-term memories relevant to the request (omitted when there are none)
+        prompt.push_str(&Self::memory_section(memories));
+
+        // Stage 4: Available tools
         prompt.push_str("# Available Tools\n\n");
         prompt.push_str(&self.format_tools());
         prompt.push_str("\n\n");
 
-        // Stage 4: Current task state (omitted when empty — specialists use message 3 instead)
+        // Stage 5: Current task state (omitted when empty — specialists use message 3 instead)
         if !task_state.is_empty() {
             prompt.push_str("# Current Task State\n\n");
             prompt.push_str(task_state);
@@ -85,6 +116,48 @@ impl Agent {
         prompt
     }
 
+    /// The agent as it runs in a conversation that made `choices`: toolbelts
+    /// turned off lose their tools, and an orchestrator gains the tools of
+    /// those turned on.
+    pub fn with_tool_choices(mut self, choices: &[ToolChoice]) -> Self {
+        self.tools.retain(|tool| {
+            let toolbelt = tool.function.name.split("::").next().unwrap_or_default();
+            !choices.iter().any(|choice| !choice.enabled && choice.toolbelt == toolbelt)
+        });
+        if self.role == AgentRoles::Orchestrator {
+            for choice in choices.iter().filter(|choice| choice.enabled) {
+                let prefix = format!("{}::", choice.toolbelt);
+                for tool in artificer_shared::get_tools_for(&[prefix.as_str()]) {
+                    if !self.tools.iter().any(|t| t.function.name == tool.function.name) {
+                        self.tools.push(tool);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// The memories part of the system prompt, empty when there is nothing to show.
+    pub fn memory_section(memories: &MemorySelection) -> String {
+        let mut section = String::new();
+        if !memories.memories.is_empty() {
+            section.push_str("# Memories\n\n");
+            section.push_str("Facts saved in earlier conversations:\n");
+            for memory in &memories.memories {
+                section.push_str(&format!("- [{}] {}\n", memory.id, memory.content));
+            }
+            section.push('\n');
+        }
+        if memories.omitted > 0 {
+            section.push_str(&format!(
+                "{} less relevant memories are not shown. If you need them, delegate to Archivist \
+                 to search memories in profile '{}'.\n\n",
+                memories.omitted, memories.profile,
+            ));
+        }
+        section
+    }
+
     fn format_tools(&self) -> String {
         if self.tools.is_empty() {
             return "No tools available.".to_string();
@@ -95,20 +168,20 @@ impl Agent {
             output.push_str(&format!("## {}\n", tool.function.name));
             output.push_str(&format!("{}\n\n", tool.function.description));
 
-            if let Some(params) = tool.function.parameters.get("properties") {
-                if let Some(obj) = params.as_object() {
-                    output.push_str("Parameters:\n");
-                    for (name, details) in obj {
-                        let desc = details.get("description")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("");
-                        let type_name = details.get("type")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown");
-                        output.push_str(&format!("- {} ({}): {}\n", name, type_name, desc));
-                    }
-                    output.push('\n');
+            if let Some(params) = tool.function.parameters.get("properties")
+                && let Some(obj) = params.as_object()
+            {
+                output.push_str("Parameters:\n");
+                for (name, details) in obj {
+                    let desc = details.get("description")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("");
+                    let type_name = details.get("type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("any");
+                    output.push_str(&format!("- {} ({}): {}\n", name, type_name, desc));
                 }
+                output.push('\n');
             }
         }
         output
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+use std::time::Duration;
+use futures_util::future::BoxFuture;
+use reqwest::Client;
+use serde::Deserialize;
+
+/// How long the classifier may take before a text is let through unchecked.
+const CLASSIFIER_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A category of content this deployment blocks, from moderation.json.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Category {
+    pub name: String,
+    /// Words or phrases that put a text in the category, matched whole and
+    /// ignoring case.
+    #[serde(default)]
+    pub terms: Vec<String>,
+    /// What the category covers, for the classifier. Categories without a
+    /// description are matched by their terms only.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Shown to the user instead of the default refusal.
+    #[serde(default)]
+    pub refusal: Option<String>,
+}
+
+/// A local Ollama model that sorts texts into the described categories.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClassifierConfig {
+    pub url: String,
+    pub model: String,
+}
+
+/// The full moderation.json structure.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerationConfig {
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub classifier: Option<ClassifierConfig>,
+    /// Check tool results too, not only the user's messages.
+    #[serde(default = "default_tool_results")]
+    pub tool_results: bool,
+}
+
+fn default_tool_results() -> bool {
+    true
+}
+
+impl ModerationConfig {
+    /// Load moderation.json from the workspace root, or None if there is
+    /// none. A malformed file fails startup like a malformed hardware.json.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let Some(path) = Self::find_config()? else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read moderation.json at {}: {}", path.display(), e))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse moderation.json: {}", e))?;
+        Ok(Some(config))
+    }
+
+    /// Walk up from the current directory to find moderation.json.
+    fn find_config() -> anyhow::Result<Option<std::path::PathBuf>> {
+        let mut dir = std::env::current_dir()?;
+        loop {
+            let candidate = dir.join("moderation.json");
+            if candidate.exists() {
+                return Ok(Some(candidate));
+            }
+            if !dir.pop() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// A check run on incoming text before the model sees it. Returns the name
+/// of the blocked category the text falls in, if any.
+pub trait ModerationHook: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn check<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<String>>;
+}
+
+/// A blocked text: which category, and what to tell the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refusal {
+    pub category: String,
+    pub message: String,
+}
+
+/// The deployment's hooks, run in order on user messages and tool results.
+/// The first hook to name a category decides.
+#[derive(Clone)]
+pub struct Moderation {
+    hooks: Arc<[Arc<dyn ModerationHook>]>,
+    categories: Arc<[Category]>,
+    tool_results: bool,
+}
+
+impl Moderation {
+    /// No hooks: every text passes.
+    pub fn none() -> Self {
+        Self::new(Vec::new(), Vec::new(), false)
+    }
+
+    pub fn new(hooks: Vec<Arc<dyn ModerationHook>>, categories: Vec<Category>, tool_results: bool) -> Self {
+        Self { hooks: hooks.into(), categories: categories.into(), tool_results }
+    }
+
+    /// The rule list, then the classifier if one is configured and some
+    /// category is described for it.
+    pub fn from_config(config: ModerationConfig, client: Client) -> Self {
+        let mut hooks: Vec<Arc<dyn ModerationHook>> = vec![Arc::new(RuleList::new(&config.categories))];
+        let described: Vec<Category> = config.categories.iter()
+            .filter(|category| category.description.is_some())
+            .cloned()
+            .collect();
+        if let Some(classifier) = config.classifier
+            && !described.is_empty()
+        {
+            hooks.push(Arc::new(Classifier { config: classifier, categories: described, client }));
+        }
+        Self::new(hooks, config.categories, config.tool_results)
+    }
+
+    /// Check a message from the user.
+    pub async fn check_message(&self, text: &str) -> Option<Refusal> {
+        self.check(text).await
+    }
+
+    /// Check what a tool returned, unless moderation.json left tool results out.
+    pub async fn check_tool_result(&self, text: &str) -> Option<Refusal> {
+        if !self.tool_results {
+            return None;
+        }
+        self.check(text).await
+    }
+
+    async fn check(&self, text: &str) -> Option<Refusal> {
+        for hook in self.hooks.iter() {
+            if let Some(category) = hook.check(text).await {
+                return Some(self.refusal(category));
+            }
+        }
+        None
+    }
+
+    fn refusal(&self, category: String) -> Refusal {
+        let message = self.categories.iter()
+            .find(|c| c.name == category)
+            .and_then(|c| c.refusal.clone())
+            .unwrap_or_else(|| format!("This is blocked on this server: it falls under '{}'.", category));
+        Refusal { category, message }
+    }
+}
+
+impl std::fmt::Debug for Moderation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.hooks.iter().map(|hook| hook.name())).finish()
+    }
+}
+
+// ============================================================================
+// HOOKS
+// ============================================================================
+
+/// Blocks texts containing any of a category's terms as whole words, so
+/// "gun" doesn't catch "begun".
+pub struct RuleList {
+    /// Each category's terms, split into lowercase words.
+    rules: Vec<(String, Vec<Vec<String>>)>,
+}
+
+impl RuleList {
+    pub fn new(categories: &[Category]) -> Self {
+        let rules = categories.iter()
+            .map(|category| {
+                let terms = category.terms.iter()
+                    .map(|term| words(term))
+                    .filter(|term| !term.is_empty())
+                    .collect();
+                (category.name.clone(), terms)
+            })
+            .collect();
+        Self { rules }
+    }
+
+    fn matching(&self, text: &str) -> Option<String> {
+        let text = words(text);
+        self.rules.iter()
+            .find(|(_, terms)| terms.iter().any(|term| text.windows(term.len()).any(|window| window == term.as_slice())))
+            .map(|(name, _)| name.clone())
+    }
+}
+
+impl ModerationHook for RuleList {
+    fn name(&self) -> &'static str {
+        "rule_list"
+    }
+
+    fn check<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { self.matching(text) })
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Asks a small local model which described category a text falls in. An
+/// unreachable classifier lets the text through, so a stopped Ollama server
+/// doesn't stop every chat; the rule list still applies.
+pub struct Classifier {
+    config: ClassifierConfig,
+    categories: Vec<Category>,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct ClassifierResponse {
+    message: ClassifierMessage,
+}
+
+#[derive(Deserialize)]
+struct ClassifierMessage {
+    content: String,
+}
+
+impl Classifier {
+    fn prompt(&self, text: &str) -> String {
+        let categories: Vec<String> = self.categories.iter()
+            .map(|c| format!("- {}: {}", c.name, c.description.as_deref().unwrap_or("")))
+            .collect();
+        format!(
+            "Decide whether the text below falls in one of these categories:\n{}\n\n\
+             Reply with only the category's name, or `none` if it fits none of them.\n\n\
+             Text:\n{}",
+            categories.join("\n"),
+            text,
+        )
+    }
+
+    async fn classify(&self, text: &str) -> anyhow::Result<Option<String>> {
+        let body = serde_json::json!({
+            "model": self.config.model,
+            "stream": false,
+            "messages": [{ "role": "user", "content": self.prompt(text) }],
+        });
+        let response: ClassifierResponse = self.client
+            .post(format!("{}/api/chat", self.config.url))
+            .timeout(CLASSIFIER_TIMEOUT)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(parse_category(&response.message.content, &self.categories))
+    }
+}
+
+impl ModerationHook for Classifier {
+    fn name(&self) -> &'static str {
+        "classifier"
+    }
+
+    fn check<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            self.classify(text).await.unwrap_or_else(|e| {
+                eprintln!("Moderation classifier {} failed, letting the text through: {}", self.config.model, e);
+                None
+            })
+        })
+    }
+}
+
+/// The category a classifier's reply names. Anything but a known category
+/// name, `none` included, means the text passes.
+pub fn parse_category(reply: &str, categories: &[Category]) -> Option<String> {
+    let reply = reply.trim().trim_matches(|c: char| c == '`' || c == '"' || c == '.').to_lowercase();
+    categories.iter()
+        .find(|category| category.name.to_lowercase() == reply)
+        .map(|category| category.name.clone())
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::agent::moderation::{parse_category, Category, Moderation, ModerationConfig, ModerationHook, Refusal, RuleList};
+
+    fn category(name: &str, terms: &[&str]) -> Category {
+        Category {
+            name: name.to_string(),
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+            description: None,
+            refusal: None,
+        }
+    }
+
+    fn rules(categories: Vec<Category>, tool_results: bool) -> Moderation {
+        let hooks: Vec<Arc<dyn ModerationHook>> = vec![Arc::new(RuleList::new(&categories))];
+        Moderation::new(hooks, categories, tool_results)
+    }
+
+    #[tokio::test]
+    async fn terms_match_whole_words_ignoring_case() {
+        let moderation = rules(vec![category("weapons", &["gun", "assault rifle"])], true);
+
+        assert_eq!(
+            moderation.check_message("Where can I buy a GUN?").await.map(|r| r.category),
+            Some("weapons".to_string()),
+        );
+        assert!(moderation.check_message("The work has begun").await.is_none());
+        assert!(moderation.check_message("Assault, then a rifle").await.is_none());
+        assert!(moderation.check_message("an assault-rifle review").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn refusals_use_the_category_message_when_set() {
+        let mut gambling = category("gambling", &["casino"]);
+        gambling.refusal = Some("Ask a parent about that.".to_string());
+        let moderation = rules(vec![category("weapons", &["gun"]), gambling], true);
+
+        assert_eq!(
+            moderation.check_message("best casino odds").await,
+            Some(Refusal { category: "gambling".to_string(), message: "Ask a parent about that.".to_string() }),
+        );
+        assert!(moderation.check_message("gun").await.unwrap().message.contains("'weapons'"));
+    }
+
+    #[tokio::test]
+    async fn tool_results_are_only_checked_when_configured() {
+        let text = "Top 10 casino bonuses";
+        assert!(rules(vec![category("gambling", &["casino"])], true).check_tool_result(text).await.is_some());
+        assert!(rules(vec![category("gambling", &["casino"])], false).check_tool_result(text).await.is_none());
+        assert!(Moderation::none().check_message(text).await.is_none());
+    }
+
+    #[test]
+    fn config_checks_tool_results_by_default() {
+        let config: ModerationConfig = serde_json::from_str(
+            r#"{"categories": [{"name": "gambling", "terms": ["casino"]}]}"#,
+        ).unwrap();
+        assert!(config.tool_results);
+        assert!(config.classifier.is_none());
+        // No category is described, so only the rule list runs
+        let moderation = Moderation::from_config(config, reqwest::Client::new());
+        assert_eq!(format!("{:?}", moderation), r#"["rule_list"]"#);
+    }
+
+    #[test]
+    fn classifier_replies_name_a_known_category_or_nothing() {
+        let categories = vec![category("self_harm", &[]), category("gambling", &[])];
+        assert_eq!(parse_category(" `Gambling`.\n", &categories), Some("gambling".to_string()));
+        assert_eq!(parse_category("none", &categories), None);
+        assert_eq!(parse_category("I think it is gambling", &categories), None);
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+/// What a filter may know about the run that produced a response.
+#[derive(Debug, Default)]
+pub struct FilterContext<'a> {
+    /// URLs the run fetched or searched, oldest first.
+    pub sources: &'a [String],
+    /// Values that must never appear in output (the device's key, API keys).
+    pub secrets: &'a [String],
+}
+
+/// Environment variables holding secrets that must never reach a response.
+const SECRET_ENV_VARS: &[&str] = &["BRAVE_API_KEY", "ADMIN_KEY"];
+
+/// What `Redact` hides from a device's responses: its own key and the
+/// engine's API keys.
+pub fn response_secrets(device_key: &str) -> Vec<String> {
+    let mut secrets = vec![device_key.to_string()];
+    secrets.extend(SECRET_ENV_VARS.iter().filter_map(|name| std::env::var(name).ok()));
+    secrets
+}
+
+/// A rewrite applied to an agent's final content before it is stored or
+/// returned. Filters run in the order an agent lists them.
+pub trait ResponseFilter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn apply(&self, content: String, context: &FilterContext) -> String;
+}
+
+/// An agent's ordered filters. Agents without `response_filters:` in
+/// `define_agents!` get `FilterChain::standard()`.
+#[derive(Clone)]
+pub struct FilterChain {
+    filters: Arc<[Arc<dyn ResponseFilter>]>,
+}
+
+impl FilterChain {
+    pub fn new(filters: Vec<Arc<dyn ResponseFilter>>) -> Self {
+        Self { filters: filters.into() }
+    }
+
+    /// Think-block stripping and redaction, which every agent needs.
+    pub fn standard() -> Self {
+        Self::new(vec![Arc::new(StripThinking), Arc::new(Redact)])
+    }
+
+    pub fn apply(&self, content: String, context: &FilterContext) -> String {
+        self.filters.iter().fold(content, |content, filter| filter.apply(content, context))
+    }
+}
+
+impl std::fmt::Debug for FilterChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.filters.iter().map(|filter| filter.name())).finish()
+    }
+}
+
+// ============================================================================
+// FILTERS
+// ============================================================================
+
+/// Removes `<think>...</think>` reasoning some models put before their answer.
+/// An unclosed block runs to the end of the content.
+pub struct StripThinking;
+
+impl ResponseFilter for StripThinking {
+    fn name(&self) -> &'static str {
+        "strip_thinking"
+    }
+
+    fn apply(&self, content: String, _context: &FilterContext) -> String {
+        if !content.contains("<think>") {
+            return content;
+        }
+        let mut output = String::with_capacity(content.len());
+        let mut rest = content.as_str();
+        while let Some(start) = rest.find("<think>") {
+            output.push_str(&rest[..start]);
+            rest = match rest[start..].find("</think>") {
+                Some(end) => &rest[start + end + "</think>".len()..],
+                None => "",
+            };
+        }
+        output.push_str(rest);
+        output.trim().to_string()
+    }
+}
+
+/// Replaces secrets echoed back by a tool or the model with `[redacted]`.
+pub struct Redact;
+
+impl ResponseFilter for Redact {
+    fn name(&self) -> &'static str {
+        "redact"
+    }
+
+    fn apply(&self, content: String, context: &FilterContext) -> String {
+        context.secrets
+            .iter()
+            // Short values would match ordinary words
+            .filter(|secret| secret.len() >= 8)
+            .fold(content, |content, secret| content.replace(secret.as_str(), "[redacted]"))
+    }
+}
+
+/// Lists the pages a research run used under a "Sources" heading, unless
+/// the answer already links them.
+pub struct InsertCitations;
+
+/// Sources listed at most, newest last.
+const MAX_CITATIONS: usize = 8;
+
+impl ResponseFilter for InsertCitations {
+    fn name(&self) -> &'static str {
+        "insert_citations"
+    }
+
+    fn apply(&self, content: String, context: &FilterContext) -> String {
+        let mut unlinked: Vec<&String> = Vec::new();
+        for source in context.sources {
+            if !content.contains(source.as_str()) && !unlinked.contains(&source) {
+                unlinked.push(source);
+            }
+        }
+        if unlinked.is_empty() || content.trim().is_empty() {
+            return content;
+        }
+
+        let skip = unlinked.len().saturating_sub(MAX_CITATIONS);
+        let mut content = content.trim_end().to_string();
+        content.push_str("\n\nSources:\n");
+        for source in &unlinked[skip..] {
+            content.push_str(&format!("- {}\n", source));
+        }
+        content.trim_end().to_string()
+    }
+}
+
+/// Tidies Markdown: trailing whitespace, runs of blank lines, and a code
+/// fence left open.
+pub struct NormalizeMarkdown;
+
+impl ResponseFilter for NormalizeMarkdown {
+    fn name(&self) -> &'static str {
+        "normalize_markdown"
+    }
+
+    fn apply(&self, content: String, _context: &FilterContext) -> String {
+        let mut output = String::with_capacity(content.len());
+        let mut blank_run = 0;
+        let mut in_fence = false;
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            // Blank lines inside code are content
+            if line.is_empty() && !in_fence {
+                blank_run += 1;
+                if blank_run > 1 {
+                    continue;
+                }
+            } else {
+                blank_run = 0;
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+        if in_fence {
+            output.push_str("```\n");
+        }
+        output.trim().to_string()
+    }
+}
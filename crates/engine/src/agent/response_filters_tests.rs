@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::agent::response_filters::{
+        FilterChain, FilterContext, InsertCitations, NormalizeMarkdown, Redact, ResponseFilter, StripThinking,
+    };
+
+    fn apply(filter: &dyn ResponseFilter, content: &str, context: &FilterContext) -> String {
+        filter.apply(content.to_string(), context)
+    }
+
+    #[test]
+    fn think_blocks_are_removed_even_when_unclosed() {
+        let context = FilterContext::default();
+        assert_eq!(apply(&StripThinking, "<think>plan it</think>\nThe answer is 4.", &context), "The answer is 4.");
+        assert_eq!(apply(&StripThinking, "Sure.<think>never closed", &context), "Sure.");
+        assert_eq!(apply(&StripThinking, "No thinking here.", &context), "No thinking here.");
+    }
+
+    #[test]
+    fn secrets_are_redacted_but_short_values_are_left_alone() {
+        let secrets = vec!["BSA-0123456789abcdef".to_string(), "abc".to_string()];
+        let context = FilterContext { sources: &[], secrets: &secrets };
+        assert_eq!(
+            apply(&Redact, "The key is BSA-0123456789abcdef, abc.", &context),
+            "The key is [redacted], abc."
+        );
+    }
+
+    #[test]
+    fn citations_list_only_sources_the_answer_does_not_link() {
+        let sources = vec![
+            "https://example.com/a".to_string(),
+            "https://example.com/b".to_string(),
+            "https://example.com/a".to_string(),
+        ];
+        let context = FilterContext { sources: &sources, secrets: &[] };
+        assert_eq!(
+            apply(&InsertCitations, "See https://example.com/b for more.", &context),
+            "See https://example.com/b for more.\n\nSources:\n- https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn markdown_is_tidied_outside_code_blocks() {
+        let context = FilterContext::default();
+        let content = "Intro  \n\n\n\nList:\n```\nfn a() {}\n\n\nfn b() {}";
+        assert_eq!(
+            apply(&NormalizeMarkdown, content, &context),
+            "Intro\n\nList:\n```\nfn a() {}\n\n\nfn b() {}\n```"
+        );
+    }
+
+    #[test]
+    fn chain_applies_filters_in_order() {
+        let chain = FilterChain::new(vec![Arc::new(StripThinking), Arc::new(NormalizeMarkdown)]);
+        let context = FilterContext::default();
+        assert_eq!(chain.apply("<think>x</think>\n\n\nDone.  ".to_string(), &context), "Done.");
+        assert_eq!(format!("{:?}", FilterChain::standard()), r#"["strip_thinking", "redact"]"#);
+    }
+}
@@ -1,13 +1,22 @@
 use std::sync::Arc;
-use crate::pool::GpuHandle;
+use crate::pool::{AgentPool, GpuHandle};
 use crate::api::events::EventSender;
-use artificer_shared::db::Db;
+use crate::state::RuntimeState;
+use artificer_shared::db::{Db, TargetDevice, ALL_TOOLS};
+use artificer_shared::executor::ToolExecutor;
+use artificer_shared::schemas::ToolLocation;
+use artificer_shared::tools::get_tool_schema;
+use artificer_shared::DeviceContext;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use anyhow::Result;
 
 mod specialist;
+mod plan;
+#[cfg(test)]
+mod plan_tests;
 pub use specialist::SpecialistExecution;
+pub use plan::{Plan, PlanStep, StepStatus};
 
 // ============================================================================
 // EXECUTION CONTEXT
@@ -23,10 +32,81 @@ pub struct ExecutionContext {
     pub gpu: GpuHandle,
     pub events: Option<EventSender>,
     pub db: Arc<Db>,
+    pub runtime: Arc<RuntimeState>,
+    /// Skip the shared result cache; delegations always run.
+    pub fresh: bool,
+    /// Record the prompt of every LLM call for /debug/requests.
+    pub debug: bool,
+    /// Have the Critic check the orchestrator's answer against its tool
+    /// results, allowing one revision, before the answer is final.
+    pub verify: bool,
+    /// The device a delegation was aimed at (`target: device=nas`); its
+    /// client tools run there instead of on the requesting device.
+    pub target: Option<TargetDevice>,
+}
+
+impl ExecutionContext {
+    /// The device scope handed to every tool this execution runs.
+    pub fn device(&self) -> DeviceContext {
+        DeviceContext::new(self.device_id as i64, self.device_key.clone())
+    }
+
+    /// Run a toolbelt tool, sending client tools to the target device if
+    /// this execution has one.
+    /// A result the deployment's moderation blocks is withheld from the
+    /// model, and the user is told why.
+    pub async fn run_tool(&self, pool: &AgentPool, tool_name: &str, args: &Value) -> Result<String> {
+        let result = self.dispatch_tool(pool.tool_executor(), tool_name, args).await?;
+        let Some(refusal) = pool.moderation().check_tool_result(&result).await else {
+            return Ok(result);
+        };
+        if let Some(events) = &self.events {
+            events.refusal(&refusal, Some(tool_name));
+        }
+        Ok(format!(
+            "The result was withheld: it falls under '{}', which this server blocks. \
+             Don't try to get it another way; tell the user it isn't available.",
+            refusal.category
+        ))
+    }
+
+    async fn dispatch_tool(&self, executor: &ToolExecutor, tool_name: &str, args: &Value) -> Result<String> {
+        self.ensure_tool_enabled(tool_name)?;
+        let Some(target) = &self.target else {
+            return executor.execute(tool_name, args, &self.device()).await;
+        };
+        let is_client = get_tool_schema(tool_name).is_ok_and(|s| matches!(s.location, ToolLocation::Client));
+        match (&target.tool_host, is_client) {
+            (Some(url), true) => {
+                let device = DeviceContext::new(target.device_id as i64, target.device_key.clone());
+                executor.execute_on(url, tool_name, args, &device).await
+            }
+            _ => executor.execute(tool_name, args, &self.device()).await,
+        }
+    }
+
+    /// Fails if an operator turned off the tool's toolbelt, or all tools, or
+    /// the conversation turned off its toolbelt.
+    pub fn ensure_tool_enabled(&self, tool_name: &str) -> Result<()> {
+        if let Some(switch) = self.db.tool_switch_for(tool_name)? {
+            return Err(anyhow::anyhow!(
+                "{} is turned off by the operator{}. Tell the user it is unavailable right now.",
+                if switch.name == ALL_TOOLS { "Tool use".to_string() } else { switch.name },
+                switch.reason.map(|r| format!(" ({})", r)).unwrap_or_default(),
+            ));
+        }
+        if self.db.toolbelt_turned_off(self.conversation_id, tool_name)? {
+            return Err(anyhow::anyhow!(
+                "{} is turned off for this conversation by the user. Answer without it.",
+                tool_name.split("::").next().unwrap_or(tool_name),
+            ));
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
-// TASK PHASE & STEP TYPES
+// TASK PHASE & NOTES
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -38,20 +118,6 @@ pub enum TaskPhase {
     Failed,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum StepStatus {
-    Pending,
-    InProgress,
-    Complete,
-    Failed,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanStep {
-    pub description: String,
-    pub status: StepStatus,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub key: String,
@@ -71,7 +137,7 @@ pub struct TaskState {
     pub parent_task_id: Option<u64>,
     pub user_goal: String,
     pub agent_goal: Option<String>,
-    pub plan: Option<Vec<PlanStep>>,
+    pub plan: Option<Plan>,
     pub phase: TaskPhase,
     pub total_iterations: Option<u64>,
     pub completed_iterations: u64,
@@ -119,29 +185,9 @@ impl TaskState {
         xml.push_str(&format!("  <phase>{}</phase>\n", phase_str));
 
         if let Some(ref plan) = self.plan {
-            xml.push_str("  <plan>\n");
-            for step in plan {
-                let status_str = match step.status {
-                    StepStatus::Pending => "pending",
-                    StepStatus::InProgress => "in_progress",
-                    StepStatus::Complete => "complete",
-                    StepStatus::Failed => "failed",
-                };
-                if step.status == StepStatus::InProgress {
-                    if let Some(total) = self.total_iterations {
-                        xml.push_str(&format!(
-                            "    <step status=\"{}\" progress=\"{}/{}\">{}</step>\n",
-                            status_str, self.completed_iterations, total, step.description
-                        ));
-                        continue;
-                    }
-                }
-                xml.push_str(&format!(
-                    "    <step status=\"{}\">{}</step>\n",
-                    status_str, step.description
-                ));
-            }
-            xml.push_str("  </plan>\n");
+            let progress = self.total_iterations
+                .map(|total| format!("{}/{}", self.completed_iterations, total));
+            xml.push_str(&plan.build_xml(progress.as_deref()));
         }
 
         if let Some(total) = self.total_iterations {
@@ -154,7 +200,7 @@ impl TaskState {
         if !self.notes.is_empty() {
             xml.push_str("  <working_memory>\n");
             let mut sorted = self.notes.clone();
-            sorted.sort_by(|a, b| b.importance.cmp(&a.importance));
+            sorted.sort_by_key(|n| std::cmp::Reverse(n.importance));
             for note in &sorted {
                 xml.push_str(&format!(
                     "    <note key=\"{}\" importance=\"{}\">{}</note>\n",
@@ -178,45 +224,64 @@ impl TaskState {
     }
 
     pub fn set_plan(&mut self, steps: Vec<String>) {
-        self.plan = Some(
-            steps.into_iter()
-                .map(|description| PlanStep {
-                    description,
-                    status: StepStatus::Pending,
-                })
-                .collect(),
-        );
+        self.plan = Some(Plan::new(steps));
         self.phase = TaskPhase::Executing;
         self.dirty = true;
     }
 
-    /// Find the step in plan matching description and set it to InProgress.
-    /// If no match, does nothing.
-    pub fn set_current_step(&mut self, step_desc: &str) {
-        if let Some(ref mut plan) = self.plan {
-            if let Some(step) = plan.iter_mut().find(|s| s.description == step_desc) {
-                step.status = StepStatus::InProgress;
-                self.dirty = true;
-            }
+    /// Replace the unfinished steps of the plan, keeping completed ones.
+    /// Without a plan this sets one.
+    pub fn revise_plan(&mut self, steps: Vec<String>) {
+        match self.plan {
+            Some(ref mut plan) => plan.revise(steps),
+            None => self.plan = Some(Plan::new(steps)),
         }
+        self.phase = TaskPhase::Executing;
+        self.dirty = true;
     }
 
-    /// Find first InProgress step and set it to Complete.
-    pub fn mark_step_complete(&mut self) {
-        if let Some(ref mut plan) = self.plan {
-            if let Some(step) = plan.iter_mut().find(|s| s.status == StepStatus::InProgress) {
-                step.status = StepStatus::Complete;
-                self.dirty = true;
-            }
-        }
+    /// Set the step `step` names (see `Plan::find`) to InProgress.
+    /// Returns its index, or None if there is no such step.
+    pub fn set_current_step(&mut self, step: &Value) -> Option<usize> {
+        let plan = self.plan.as_mut()?;
+        let index = plan.find(step)?;
+        plan.start(index);
+        self.dirty = true;
+        Some(index)
+    }
+
+    /// Complete the named step, or the one in progress when `step` is None.
+    /// Returns its index, or None if there is no such step.
+    pub fn mark_step_complete(&mut self, step: Option<&Value>) -> Option<usize> {
+        let plan = self.plan.as_mut()?;
+        let index = match step {
+            Some(step) => plan.find(step)?,
+            None => plan.current()?,
+        };
+        plan.complete(index);
+        self.dirty = true;
+        Some(index)
+    }
+
+    /// Fail the named step, or the one in progress when `step` is None.
+    /// Returns its index, or None if there is no such step.
+    pub fn mark_step_failed(&mut self, step: Option<&Value>, reason: String) -> Option<usize> {
+        let plan = self.plan.as_mut()?;
+        let index = match step {
+            Some(step) => plan.find(step)?,
+            None => plan.current()?,
+        };
+        plan.fail(index, reason);
+        self.dirty = true;
+        Some(index)
     }
 
     pub fn mark_complete(&mut self) {
         self.phase = TaskPhase::Complete;
-        if let Some(ref mut plan) = self.plan {
-            if let Some(step) = plan.iter_mut().find(|s| s.status == StepStatus::InProgress) {
-                step.status = StepStatus::Complete;
-            }
+        if let Some(ref mut plan) = self.plan
+            && let Some(index) = plan.current()
+        {
+            plan.complete(index);
         }
         self.dirty = true;
     }
@@ -248,16 +313,15 @@ impl TaskState {
             return;
         }
 
-        if self.notes.len() >= MAX_NOTES {
-            if let Some(min_pos) = self
+        if self.notes.len() >= MAX_NOTES
+            && let Some(min_pos) = self
                 .notes
                 .iter()
                 .enumerate()
                 .min_by_key(|(_, n)| n.importance)
                 .map(|(i, _)| i)
-            {
-                self.notes.remove(min_pos);
-            }
+        {
+            self.notes.remove(min_pos);
         }
 
         self.notes.push(Note { key, value, importance });
@@ -316,7 +380,7 @@ impl TaskState {
 
     pub fn persist(&self, ctx: &ExecutionContext) -> Result<()> {
         let plan_json = self.plan.as_ref()
-            .map(|p| serde_json::to_string(p))
+            .map(serde_json::to_string)
             .transpose()?;
 
         let working_memory_json = serde_json::json!({
@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum StepStatus {
+    Pending,
+    InProgress,
+    Complete,
+    Failed,
+}
+
+impl StepStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::InProgress => "in_progress",
+            StepStatus::Complete => "complete",
+            StepStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub description: String,
+    pub status: StepStatus,
+    /// Why the step failed, as reported by the agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+/// A task's numbered plan. Steps are numbered from 1 in everything the
+/// model sees; completed steps are kept when the plan is revised.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plan {
+    pub steps: Vec<PlanStep>,
+    /// 0 for the first plan, bumped by every revision.
+    pub revision: u32,
+}
+
+impl Plan {
+    pub fn new(steps: Vec<String>) -> Self {
+        Self { steps: pending(steps).collect(), revision: 0 }
+    }
+
+    /// Index of the step `step` names: its number (as a JSON number or a
+    /// numeric string) or its exact description.
+    pub fn find(&self, step: &Value) -> Option<usize> {
+        let number = step.as_u64().or_else(|| step.as_str().and_then(|s| s.trim().parse().ok()));
+        if let Some(number) = number {
+            return (number >= 1 && number as usize <= self.steps.len()).then(|| number as usize - 1);
+        }
+        let description = step.as_str()?.trim();
+        self.steps.iter().position(|s| s.description == description)
+    }
+
+    /// Index of the step being worked on, if any.
+    pub fn current(&self) -> Option<usize> {
+        self.steps.iter().position(|s| s.status == StepStatus::InProgress)
+    }
+
+    pub fn start(&mut self, index: usize) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::InProgress;
+        step.failure = None;
+    }
+
+    pub fn complete(&mut self, index: usize) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Complete;
+        step.failure = None;
+    }
+
+    pub fn fail(&mut self, index: usize, reason: String) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Failed;
+        step.failure = Some(reason);
+    }
+
+    /// Replace every unfinished step with `steps`, keeping completed ones.
+    pub fn revise(&mut self, steps: Vec<String>) {
+        self.steps.retain(|s| s.status == StepStatus::Complete);
+        self.steps.extend(pending(steps));
+        self.revision += 1;
+    }
+
+    /// Completed steps and the total.
+    pub fn progress(&self) -> (usize, usize) {
+        let done = self.steps.iter().filter(|s| s.status == StepStatus::Complete).count();
+        (done, self.steps.len())
+    }
+
+    /// Numbered steps as XML. `in_progress_detail` is added to the step being
+    /// worked on (e.g. iteration progress).
+    pub fn build_xml(&self, in_progress_detail: Option<&str>) -> String {
+        let mut xml = format!("  <plan revision=\"{}\">\n", self.revision);
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut attrs = format!("number=\"{}\" status=\"{}\"", i + 1, step.status.as_str());
+            if step.status == StepStatus::InProgress
+                && let Some(detail) = in_progress_detail
+            {
+                attrs.push_str(&format!(" progress=\"{}\"", detail));
+            }
+            if let Some(reason) = &step.failure {
+                attrs.push_str(&format!(" failure=\"{}\"", reason));
+            }
+            xml.push_str(&format!("    <step {}>{}</step>\n", attrs, step.description));
+        }
+        xml.push_str("  </plan>\n");
+        xml
+    }
+
+    /// The plan as listed in tool results: one numbered line per step.
+    pub fn describe(&self) -> String {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. [{}] {}", i + 1, step.status.as_str(), step.description))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn pending(steps: Vec<String>) -> impl Iterator<Item = PlanStep> {
+    steps.into_iter().map(|description| PlanStep {
+        description,
+        status: StepStatus::Pending,
+        failure: None,
+    })
+}
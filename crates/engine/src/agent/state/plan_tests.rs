@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::agent::state::{StepStatus, TaskState};
+    use crate::agent::tools::handle_task_tool;
+
+    fn planned() -> TaskState {
+        let mut task = TaskState::new(1, None, "research and report");
+        handle_task_tool(&mut task, "task::set_plan", &json!({ "steps": ["search", "summarize", "write report"] })).unwrap();
+        task
+    }
+
+    #[test]
+    fn steps_are_named_by_number_or_description() {
+        let mut task = planned();
+        handle_task_tool(&mut task, "task::set_current_step", &json!({ "step": 2 })).unwrap();
+        assert_eq!(task.plan.as_ref().unwrap().current(), Some(1));
+
+        handle_task_tool(&mut task, "task::set_current_step", &json!({ "step": "search" })).unwrap();
+        handle_task_tool(&mut task, "task::mark_step_complete", &json!({ "step": "1" })).unwrap();
+        assert_eq!(task.plan.as_ref().unwrap().progress(), (1, 3));
+
+        assert!(handle_task_tool(&mut task, "task::set_current_step", &json!({ "step": 4 })).is_err());
+    }
+
+    #[test]
+    fn replanning_keeps_completed_steps() {
+        let mut task = planned();
+        handle_task_tool(&mut task, "task::set_current_step", &json!({ "step": 1 })).unwrap();
+        handle_task_tool(&mut task, "task::mark_step_complete", &json!({})).unwrap();
+        handle_task_tool(&mut task, "task::set_current_step", &json!({ "step": 2 })).unwrap();
+        handle_task_tool(&mut task, "task::mark_step_failed", &json!({ "reason": "page was paywalled" })).unwrap();
+
+        let plan = task.plan.as_ref().unwrap();
+        assert_eq!(plan.steps[1].status, StepStatus::Failed);
+        assert!(task.build_task_xml().contains("failure=\"page was paywalled\""));
+
+        handle_task_tool(&mut task, "task::replan", &json!({ "steps": ["try another source", "write report"] })).unwrap();
+        let plan = task.plan.as_ref().unwrap();
+        let steps: Vec<&str> = plan.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(steps, ["search", "try another source", "write report"]);
+        assert_eq!(plan.revision, 1);
+        assert_eq!(plan.progress(), (1, 3));
+    }
+
+    #[test]
+    fn step_tools_need_a_step_to_act_on() {
+        let mut task = TaskState::new(1, None, "goal");
+        assert!(handle_task_tool(&mut task, "task::mark_step_complete", &json!({})).is_err());
+
+        let mut task = planned();
+        assert!(handle_task_tool(&mut task, "task::mark_step_failed", &json!({ "reason": "x" })).is_err());
+    }
+}
@@ -1,6 +1,12 @@
 use serde_json::Value;
+use std::collections::HashMap;
+use artificer_shared::db::ConfidenceLevel;
 use super::{TaskState, AgentState};
 
+/// Longest tool result inlined in a delegation summary once it is stored as
+/// an artifact. Longer results are cut to a preview that points at the artifact.
+pub const SUMMARY_INLINE_CHARS: usize = 8_000;
+
 /// A single tool call and its result, tracked by index.
 #[derive(Debug, Clone)]
 pub struct ToolCallRecord {
@@ -18,6 +24,10 @@ pub struct SpecialistExecution {
     pub response_vec: Vec<usize>,
     pub return_signaled: bool,
     pub response_message: Option<String>,
+    /// Artifact id each returned tool call was stored under, by index.
+    pub artifacts: HashMap<usize, u64>,
+    /// How sure the specialist said it is of its results, if it said.
+    pub confidence: Option<ConfidenceLevel>,
 }
 
 impl SpecialistExecution {
@@ -28,6 +38,8 @@ impl SpecialistExecution {
             response_vec: Vec::new(),
             return_signaled: false,
             response_message: None,
+            artifacts: HashMap::new(),
+            confidence: None,
         }
     }
 
@@ -89,22 +101,45 @@ impl SpecialistExecution {
         Ok(self.tool_calls[index - 1].tool_result.clone())
     }
 
+    /// The tool calls selected for the response, in the order they were added.
+    pub fn returned_tool_calls(&self) -> impl Iterator<Item = &ToolCallRecord> {
+        self.response_vec
+            .iter()
+            .filter_map(|idx| self.tool_calls.iter().find(|t| t.index == *idx))
+    }
+
+    pub fn set_artifact(&mut self, index: usize, artifact_id: u64) {
+        self.artifacts.insert(index, artifact_id);
+    }
+
     /// Build the delegation summary XML returned to the orchestrator.
-    /// Uses full (non-truncated) tool results.
+    /// Uses full tool results, except that results stored as artifacts are
+    /// cut to a preview past `SUMMARY_INLINE_CHARS`.
     pub fn build_delegation_summary(&self, response_message: &str) -> String {
         let mut xml = String::new();
         xml.push_str("<delegation_summary>\n");
         xml.push_str(&format!("  <response_message>{}</response_message>\n", response_message));
         xml.push_str("  <tool_call_results>\n");
-        for &idx in &self.response_vec {
-            if let Some(tc) = self.tool_calls.iter().find(|t| t.index == idx) {
-                xml.push_str("    <tool_call>\n");
-                xml.push_str(&format!("      <index>{}</index>\n", tc.index));
-                xml.push_str(&format!("      <tool_name>{}</tool_name>\n", tc.tool_name));
-                xml.push_str(&format!("      <tool_args>{}</tool_args>\n", tc.tool_args));
-                xml.push_str(&format!("      <tool_result>{}</tool_result>\n", tc.tool_result));
-                xml.push_str("    </tool_call>\n");
-            }
+        for tc in self.returned_tool_calls() {
+            xml.push_str("    <tool_call>\n");
+            xml.push_str(&format!("      <index>{}</index>\n", tc.index));
+            xml.push_str(&format!("      <tool_name>{}</tool_name>\n", tc.tool_name));
+            xml.push_str(&format!("      <tool_args>{}</tool_args>\n", tc.tool_args));
+            let result = match self.artifacts.get(&tc.index) {
+                Some(id) => {
+                    xml.push_str(&format!("      <artifact_id>{}</artifact_id>\n", id));
+                    match tc.tool_result.char_indices().nth(SUMMARY_INLINE_CHARS) {
+                        Some((cut, _)) => format!(
+                            "{}... [truncated, {} chars total; pass artifact {} to a delegation for the rest]",
+                            &tc.tool_result[..cut], tc.tool_result.chars().count(), id
+                        ),
+                        None => tc.tool_result.clone(),
+                    }
+                }
+                None => tc.tool_result.clone(),
+            };
+            xml.push_str(&format!("      <tool_result>{}</tool_result>\n", result));
+            xml.push_str("    </tool_call>\n");
         }
         xml.push_str("  </tool_call_results>\n");
         xml.push_str("</delegation_summary>");
@@ -142,7 +177,7 @@ impl AgentState for SpecialistExecution {
                 continue;
             }
             let count = self.tool_calls.iter()
-                .filter(|t| t.tool_name == tc.tool_name && t.tool_args.to_string() == args_str)
+                .filter(|t| t.tool_name == tc.tool_name && t.tool_args == tc.tool_args)
                 .count();
             if count > 1 {
                 xml.push_str(&format!(
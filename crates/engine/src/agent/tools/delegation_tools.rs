@@ -1,5 +1,5 @@
 use once_cell::sync::Lazy;
-use artificer_shared::schemas::{ToolSchema, ParameterSchema, ToolLocation};
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
 
 pub static DELEGATION_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
     ToolSchema {
@@ -9,10 +9,13 @@ pub static DELEGATION_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "goal",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "What you need FileSmith to do",
                 required: true,
+                default: None,
             },
+            artifacts_param(),
+            target_param(),
         ],
     },
     ToolSchema {
@@ -22,10 +25,66 @@ pub static DELEGATION_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "goal",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "What you need WebResearcher to do",
                 required: true,
+                default: None,
             },
+            artifacts_param(),
+            ParameterSchema {
+                name: "cache_minutes",
+                param_type: ParamType::Integer,
+                description: "Reuse the answer to an identical goal from the last N minutes (any user's) instead of researching again. Only for general, non-personal goals like today's headlines; omit otherwise",
+                required: false,
+                default: None,
+            },
+        ],
+    },
+    ToolSchema {
+        name: "delegate::cartographer",
+        description: "Delegate place and location lookups to Cartographer specialist. Use for finding shops, restaurants, landmarks or addresses near somewhere.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "goal",
+                param_type: ParamType::String,
+                description: "What you need Cartographer to find",
+                required: true,
+                default: None,
+            },
+            artifacts_param(),
+        ],
+    },
+    ToolSchema {
+        name: "delegate::report_writer",
+        description: "Delegate writing a Markdown report to ReportWriter specialist. Use after research, passing the research results as artifacts, to produce a report file with sections and citations.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "goal",
+                param_type: ParamType::String,
+                description: "What the report should cover and where to save it (e.g. '~/reports'), or that it should be attached instead",
+                required: true,
+                default: None,
+            },
+            artifacts_param(),
+            target_param(),
+        ],
+    },
+    ToolSchema {
+        name: "delegate::host_ops",
+        description: "Delegate container and system log questions to HostOps specialist. Use for listing Docker or Podman containers, finding out why one is unhealthy or keeps restarting, restarting it, or reading the system journal and log files ('what went wrong on the server last night?').",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "goal",
+                param_type: ParamType::String,
+                description: "What you need HostOps to find out or do, e.g. 'why is the jellyfin container unhealthy?'",
+                required: true,
+                default: None,
+            },
+            artifacts_param(),
+            target_param(),
         ],
     },
     ToolSchema {
@@ -35,10 +94,34 @@ pub static DELEGATION_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "goal",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "What you need Archivist to do",
                 required: true,
+                default: None,
             },
+            artifacts_param(),
         ],
     },
 ]);
+
+/// Every delegation can carry results from earlier delegations by artifact id.
+fn artifacts_param() -> ParameterSchema {
+    ParameterSchema {
+        name: "artifacts",
+        param_type: ParamType::array(ParamType::Integer),
+        description: "Artifact ids from earlier delegation results this step needs. Their full content is passed to the specialist.",
+        required: false,
+        default: None,
+    }
+}
+
+/// Specialists working on the user's machine can work on another of the user's machines instead.
+fn target_param() -> ParameterSchema {
+    ParameterSchema {
+        name: "target",
+        param_type: ParamType::String,
+        description: "Run the specialist's device tools on another of the user's machines, e.g. 'device=nas' (by name or id). Omit to use the machine the request came from",
+        required: false,
+        default: None,
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
+use artificer_shared::tools::get_tool_schema;
+use artificer_shared::db::TargetDevice;
+use artificer_shared::executor::ToolExecutor;
+use artificer_shared::DeviceContext;
+use serde_json::Value;
+use anyhow::Result;
+use crate::agent::state::ExecutionContext;
+use crate::agent::execution::tool_validation::validate_tool_call;
+
+/// How long each device gets to answer a fanned-out tool call.
+const FAN_OUT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub static DEVICE_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
+    ToolSchema {
+        name: "devices::run_on_all",
+        description: "Run one device tool (e.g. a FileSmith, Clipboard or Notifier tool) on every one of the user's machines at once and get all their results together. Use it for requests like 'check disk space on all my machines'. Only machines that are online and accept tool calls take part.",
+        location: ToolLocation::Server,
+        parameters: vec![tool_param(), arguments_param()],
+    },
+    ToolSchema {
+        name: "devices::run_on",
+        description: "Run one device tool on some of the user's machines: one machine ('device=nas'), a named group ('group=homelab'), or several joined with commas. Results come back together, one section per machine.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "target",
+                param_type: ParamType::String,
+                description: "Which machines: 'device=<name or id>', 'group=<name>' or 'all', comma-separated for several",
+                required: true,
+                default: None,
+            },
+            tool_param(),
+            arguments_param(),
+        ],
+    },
+]);
+
+fn tool_param() -> ParameterSchema {
+    ParameterSchema {
+        name: "tool",
+        param_type: ParamType::String,
+        description: "Full name of the tool to run, e.g. 'FileSmith::list_directory'",
+        required: true,
+        default: None,
+    }
+}
+
+fn arguments_param() -> ParameterSchema {
+    ParameterSchema {
+        name: "arguments",
+        param_type: ParamType::Any,
+        description: "The tool's arguments as a JSON object, the same on every machine",
+        required: false,
+        default: None,
+    }
+}
+
+pub fn is_device_tool(tool_name: &str) -> bool {
+    tool_name.starts_with("devices::")
+}
+
+pub async fn handle_device_tool(
+    context: &ExecutionContext,
+    executor: &ToolExecutor,
+    tool_name: &str,
+    args: &Value,
+) -> Result<String> {
+    let target = match tool_name {
+        "devices::run_on_all" => "all",
+        "devices::run_on" => args["target"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'target' parameter"))?,
+        _ => return Err(anyhow::anyhow!("Unknown device tool: {}", tool_name)),
+    };
+    let tool = args["tool"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing 'tool' parameter"))?;
+    let arguments = match &args["arguments"] {
+        Value::Null => Value::Object(Default::default()),
+        Value::String(raw) => serde_json::from_str(raw)
+            .map_err(|_| anyhow::anyhow!("'arguments' must be a JSON object"))?,
+        other => other.clone(),
+    };
+    run_on_target(context, executor, target, tool, &arguments).await
+}
+
+/// Run `tool` on every device `target` names, concurrently, and combine the
+/// results into one report, one section per device. Only the requesting
+/// user's own devices can be targeted.
+async fn run_on_target(
+    context: &ExecutionContext,
+    executor: &ToolExecutor,
+    target: &str,
+    tool: &str,
+    arguments: &Value,
+) -> Result<String> {
+    let schema = get_tool_schema(tool).map_err(|_| anyhow::anyhow!("Unknown tool: '{}'", tool))?;
+    if !matches!(schema.location, ToolLocation::Client) {
+        return Err(anyhow::anyhow!("'{}' runs on the server; call it directly instead", tool));
+    }
+    validate_tool_call(tool, arguments)?;
+    context.ensure_tool_enabled(tool)?;
+
+    let devices = context.db.resolve_device_target(context.device_id, target)?;
+    if devices.iter().all(|d| d.tool_host.is_none()) {
+        return Ok("No targeted machine accepts tool calls. A machine takes part once its envoy reports a tool_host.".to_string());
+    }
+
+    let runs = devices.iter().map(|device| run_on(executor, device, tool, arguments));
+    let results = futures_util::future::join_all(runs).await;
+
+    let succeeded = results.iter().filter(|r| r.is_ok()).count();
+    let mut report = format!("Ran {} on {} machines ({} succeeded):\n", tool, devices.len(), succeeded);
+    for (device, result) in devices.iter().zip(results) {
+        let body = result.unwrap_or_else(|e| format!("Error: {}", e));
+        report.push_str(&format!("\n## {} (device {})\n{}\n", device.device_name, device.device_id, body));
+    }
+    Ok(report)
+}
+
+async fn run_on(executor: &ToolExecutor, device: &TargetDevice, tool: &str, arguments: &Value) -> Result<String> {
+    let Some(url) = &device.tool_host else {
+        return Err(anyhow::anyhow!("not reachable (no tool_host reported)"));
+    };
+    let ctx = DeviceContext::new(device.device_id as i64, device.device_key.clone());
+    match tokio::time::timeout(FAN_OUT_TIMEOUT, executor.execute_on(url, tool, arguments, &ctx)).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("no answer within {}s", FAN_OUT_TIMEOUT.as_secs())),
+    }
+}
@@ -0,0 +1,90 @@
+use once_cell::sync::Lazy;
+use artificer_shared::schemas::{ToolSchema, ToolLocation};
+use artificer_shared::tools::toolbelts;
+use anyhow::Result;
+use crate::agent::AgentRoles;
+use crate::agent::state::ExecutionContext;
+use crate::pool::AgentPool;
+use crate::pool::gpu_pool::{GpuRole, HardwareConfig};
+
+pub static INTROSPECTION_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
+    ToolSchema {
+        name: "assistant::describe_capabilities",
+        description: "Describe what you can actually do: your specialists, toolbelts (where they run, how risky they are, and whether they are turned off), the user's machines and the models available. Call it when the user asks what you can do or whether you can do something, instead of guessing.",
+        location: ToolLocation::Server,
+        parameters: vec![],
+    },
+]);
+
+pub fn is_introspection_tool(tool_name: &str) -> bool {
+    tool_name.starts_with("assistant::")
+}
+
+pub fn handle_introspection_tool(context: &ExecutionContext, pool: &AgentPool, tool_name: &str) -> Result<String> {
+    match tool_name {
+        "assistant::describe_capabilities" => describe_capabilities(context, pool),
+        _ => Err(anyhow::anyhow!("Unknown assistant tool: {}", tool_name)),
+    }
+}
+
+fn describe_capabilities(context: &ExecutionContext, pool: &AgentPool) -> Result<String> {
+    let mut report = String::from("# Specialists\n");
+    for agent_type in crate::agent::AgentType::all() {
+        let Some(agent) = pool.get(agent_type.name()) else {
+            continue;
+        };
+        if matches!(agent.role, AgentRoles::Specialist) {
+            report.push_str(&format!("- {}: {}\n", agent.name, agent.description));
+        }
+    }
+
+    report.push_str("\n# Toolbelts\n");
+    for toolbelt in toolbelts() {
+        let runs_on = match toolbelt.location {
+            ToolLocation::Server => "server",
+            ToolLocation::Client => "user's machine",
+        };
+        report.push_str(&format!(
+            "- {} (runs on the {}, {} risk): {}",
+            toolbelt.name,
+            runs_on,
+            toolbelt.risk.as_str(),
+            toolbelt.description
+        ));
+        if let Some(switch) = context.db.tool_switch_for(toolbelt.name)? {
+            match switch.reason {
+                Some(reason) => report.push_str(&format!(" [turned off: {}]", reason)),
+                None => report.push_str(" [turned off]"),
+            }
+        } else if context.db.toolbelt_turned_off(context.conversation_id, toolbelt.name)? {
+            report.push_str(" [turned off for this conversation]");
+        }
+        report.push('\n');
+    }
+
+    report.push_str("\n# Machines\n");
+    for device in context.db.list_owned_devices(context.device_id)? {
+        let this = if device.device_id == context.device_id { ", this one" } else { "" };
+        let reachable = if device.tool_host.is_some() { "accepts tool calls" } else { "no tool server" };
+        report.push_str(&format!("- {} (device {}{}): {}\n", device.device_name, device.device_id, this, reachable));
+    }
+
+    report.push_str("\n# Models\n");
+    report.push_str(&format!("- {} (answering now)\n", context.gpu.model));
+    // As hardware.json lists them; the pool re-reads it on reload
+    if let Ok(config) = HardwareConfig::load() {
+        for gpu in config.gpus {
+            let role = match gpu.role {
+                GpuRole::Interactive => "chats",
+                GpuRole::Background => "background jobs",
+            };
+            report.push_str(&format!("- {} on {} ({})", gpu.model, gpu.id, role));
+            if !gpu.models.is_empty() {
+                report.push_str(&format!("; also on request: {}", gpu.models.join(", ")));
+            }
+            report.push('\n');
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,94 @@
+use once_cell::sync::Lazy;
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
+use serde_json::Value;
+use anyhow::Result;
+use artificer_shared::db::PrivacyLevel;
+use artificer_shared::memory::MemoryStore;
+use crate::agent::state::ExecutionContext;
+
+pub static MEMORY_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
+    ToolSchema {
+        name: "memory::remember",
+        description: "Save a lasting fact about the user (preferences, projects, people, recurring needs) so it is available in future conversations. Facts are kept in the conversation's profile and never shown in other profiles. Save one short, self-contained fact per call.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "fact",
+                param_type: ParamType::String,
+                description: "The fact, phrased so it makes sense without this conversation, e.g. 'Prefers metric units'",
+                required: true,
+                default: None,
+            },
+            ParameterSchema {
+                name: "kind",
+                param_type: ParamType::String,
+                description: "'fact' for something true about the user (a project, a person, a condition), 'preference' for how they like things done",
+                required: false,
+                default: Some(Value::from("fact")),
+            },
+        ],
+    },
+    ToolSchema {
+        name: "memory::forget",
+        description: "Delete a saved fact that is wrong or that the user asked you to forget. Use the id shown next to the fact under '# Memories'.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "memory_id",
+                param_type: ParamType::Integer,
+                description: "Id of the memory to delete",
+                required: true,
+                default: None,
+            },
+        ],
+    },
+]);
+
+pub fn is_memory_tool(tool_name: &str) -> bool {
+    tool_name.starts_with("memory::")
+}
+
+/// Memory tools act on the profile of the conversation they run in.
+pub fn handle_memory_tool(context: &ExecutionContext, tool_name: &str, args: &Value) -> Result<String> {
+    let store = MemoryStore::new(context.db.clone());
+    let profile = context.db.get_conversation_profile(context.conversation_id)?;
+
+    match tool_name {
+        "memory::remember" => {
+            let fact = args["fact"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing 'fact' parameter"))?;
+            if context.db.is_incognito(context.conversation_id)? {
+                return Ok("Not remembered: this is an incognito conversation".to_string());
+            }
+            if context.db.is_conversation_locked(context.conversation_id)? {
+                return Ok("Not remembered: this conversation is locked as sensitive".to_string());
+            }
+            let preference = args["kind"].as_str().map(str::trim) == Some("preference");
+            match context.db.get_privacy_level(context.conversation_id)? {
+                PrivacyLevel::NoExtraction => {
+                    return Ok("Not remembered: the user turned off memory for this conversation".to_string());
+                }
+                PrivacyLevel::FactsOnly if preference => {
+                    return Ok("Not remembered: only facts, not preferences, are remembered from this conversation".to_string());
+                }
+                _ => {}
+            }
+            let id = store.remember(context.device_id, &profile, fact)?;
+            Ok(format!("Remembered (memory {}, profile '{}')", id, profile))
+        }
+
+        "memory::forget" => {
+            let id = args["memory_id"]
+                .as_u64()
+                .ok_or_else(|| anyhow::anyhow!("Missing or invalid 'memory_id' parameter"))?;
+            if store.forget(context.device_id, &profile, id)? {
+                Ok(format!("Forgot memory {}", id))
+            } else {
+                Ok(format!("No memory {} in profile '{}'", id, profile))
+            }
+        }
+
+        _ => Err(anyhow::anyhow!("Unknown memory tool: {}", tool_name)),
+    }
+}
@@ -1,9 +1,17 @@
 mod task_tools;
 mod delegation_tools;
 mod specialist_tools;
+mod memory_tools;
+mod scratchpad_tools;
+mod device_tools;
+mod introspection_tools;
 
-pub use task_tools::{TASK_TOOLS, handle_task_tool, is_task_tool};
+pub use task_tools::{TASK_TOOLS, handle_task_tool, is_plan_tool, is_task_tool};
 pub use delegation_tools::DELEGATION_TOOLS;
+pub use memory_tools::{MEMORY_TOOLS, handle_memory_tool, is_memory_tool};
+pub use scratchpad_tools::{SCRATCHPAD_TOOLS, handle_scratchpad_tool, is_scratchpad_tool};
+pub use device_tools::{DEVICE_TOOLS, handle_device_tool, is_device_tool};
+pub use introspection_tools::{INTROSPECTION_TOOLS, handle_introspection_tool, is_introspection_tool};
 pub use specialist_tools::{
     SPECIALIST_CONTROL_TOOLS,
     handle_specialist_control_tool,
@@ -0,0 +1,64 @@
+use once_cell::sync::Lazy;
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
+use serde_json::Value;
+use anyhow::Result;
+use crate::agent::state::ExecutionContext;
+
+pub static SCRATCHPAD_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
+    ToolSchema {
+        name: "scratchpad::write",
+        description: "Write to this conversation's scratchpad: plans, checklists and intermediate results you want to keep across iterations, checkpoints and later messages in this conversation. Replaces the scratchpad unless append is true.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "content",
+                param_type: ParamType::String,
+                description: "Text to write",
+                required: true,
+                default: None,
+            },
+            ParameterSchema {
+                name: "append",
+                param_type: ParamType::Boolean,
+                description: "Add to the end of the scratchpad on a new line instead of replacing it",
+                required: false,
+                default: Some(Value::Bool(false)),
+            },
+        ],
+    },
+    ToolSchema {
+        name: "scratchpad::read",
+        description: "Read this conversation's scratchpad.",
+        location: ToolLocation::Server,
+        parameters: vec![],
+    },
+]);
+
+pub fn is_scratchpad_tool(tool_name: &str) -> bool {
+    tool_name.starts_with("scratchpad::")
+}
+
+/// Scratchpad tools act on the conversation they run in.
+pub fn handle_scratchpad_tool(context: &ExecutionContext, tool_name: &str, args: &Value) -> Result<String> {
+    match tool_name {
+        "scratchpad::write" => {
+            let content = args["content"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing 'content' parameter"))?;
+            let append = args["append"].as_bool().unwrap_or(false);
+            let chars = context.db.write_scratchpad(context.conversation_id, content, append)?;
+            Ok(format!("Scratchpad saved ({} characters)", chars))
+        }
+
+        "scratchpad::read" => {
+            let pad = context.db.get_scratchpad(context.conversation_id)?;
+            if pad.is_empty() {
+                Ok("The scratchpad is empty.".to_string())
+            } else {
+                Ok(pad)
+            }
+        }
+
+        _ => Err(anyhow::anyhow!("Unknown scratchpad tool: {}", tool_name)),
+    }
+}
@@ -1,7 +1,8 @@
 use once_cell::sync::Lazy;
-use artificer_shared::schemas::{ToolSchema, ParameterSchema, ToolLocation};
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
 use serde_json::Value;
 use anyhow::Result;
+use artificer_shared::db::ConfidenceLevel;
 use crate::agent::state::SpecialistExecution;
 
 pub static SPECIALIST_CONTROL_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
@@ -12,16 +13,19 @@ pub static SPECIALIST_CONTROL_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "index",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "The index of the tool call to include in the response",
                 required: true,
+                default: None,
             },
             ParameterSchema {
                 name: "message",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "Brief summary for the orchestrator describing what was done",
                 required: false,
+                default: None,
             },
+            confidence_param(),
         ],
     },
     ToolSchema {
@@ -31,9 +35,10 @@ pub static SPECIALIST_CONTROL_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "index",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "The index of the tool call to include in the response",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -44,10 +49,12 @@ pub static SPECIALIST_CONTROL_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "message",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "Brief summary for the orchestrator describing what was done",
                 required: false,
+                default: None,
             },
+            confidence_param(),
         ],
     },
     ToolSchema {
@@ -57,14 +64,47 @@ pub static SPECIALIST_CONTROL_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "index",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "The index of the tool call whose full result you want to see",
                 required: true,
+                default: None,
+            },
+        ],
+    },
+    ToolSchema {
+        name: "response::ask_user",
+        description: "Pause and ask the user a question, then continue with their answer. Use only when their choice changes what you do next (e.g. which of several sources to go deeper on) and you can't reasonably decide yourself. Returns the answer, or a note to use your judgment if none comes.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "question",
+                param_type: ParamType::String,
+                description: "The question, short and self-contained",
+                required: true,
+                default: None,
+            },
+            ParameterSchema {
+                name: "options",
+                param_type: ParamType::array(ParamType::String),
+                description: "Choices to offer, if the answer is one of a few",
+                required: false,
+                default: None,
             },
         ],
     },
 ]);
 
+/// Returning specialists rate how sure they are of what they found.
+fn confidence_param() -> ParameterSchema {
+    ParameterSchema {
+        name: "confidence",
+        param_type: ParamType::String,
+        description: "How sure you are the results answer the request: 'high' (several sources agree or the result is direct), 'medium', or 'low' (a single weak source, conflicting or partial results)",
+        required: false,
+        default: None,
+    }
+}
+
 pub fn is_specialist_control_tool(name: &str) -> bool {
     name.starts_with("response::")
 }
@@ -87,6 +127,7 @@ pub fn handle_specialist_control_tool(
             let index = args["index"]
                 .as_u64()
                 .ok_or_else(|| anyhow::anyhow!("Missing 'index' parameter"))? as usize;
+            set_confidence(state, args)?;
             let message = args["message"].as_str().map(String::from);
             if let Some(msg) = message {
                 state.set_response_message(msg);
@@ -102,6 +143,7 @@ pub fn handle_specialist_control_tool(
                 .map_err(|e| anyhow::anyhow!(e))
         }
         "response::return_as_is" => {
+            set_confidence(state, args)?;
             let message = args["message"].as_str().map(String::from);
             if let Some(msg) = message {
                 state.set_response_message(msg);
@@ -118,3 +160,12 @@ pub fn handle_specialist_control_tool(
         _ => Err(anyhow::anyhow!("Unknown specialist control tool: {}", tool_name)),
     }
 }
+
+fn set_confidence(state: &mut SpecialistExecution, args: &Value) -> Result<()> {
+    if let Some(level) = args["confidence"].as_str() {
+        let level = ConfidenceLevel::parse(level)
+            .ok_or_else(|| anyhow::anyhow!("confidence must be 'high', 'medium' or 'low', got '{}'", level))?;
+        state.confidence = Some(level);
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use once_cell::sync::Lazy;
-use artificer_shared::schemas::{ToolSchema, ParameterSchema, ToolLocation};
+use artificer_shared::schemas::{ToolSchema, ParameterSchema, ParamType, ToolLocation};
 use serde_json::Value;
 use anyhow::Result;
 use crate::agent::state::TaskState;
@@ -12,22 +12,24 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "goal",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "Your interpretation/refinement of the user's goal",
                 required: true,
+                default: None,
             },
         ],
     },
     ToolSchema {
         name: "task::set_plan",
-        description: "Set your plan as an ordered list of steps.",
+        description: "Set your plan as an ordered list of steps. Steps are numbered from 1.",
         location: ToolLocation::Server,
         parameters: vec![
             ParameterSchema {
                 name: "steps",
-                type_name: "array",
+                param_type: ParamType::array(ParamType::String),
                 description: "Ordered list of step descriptions",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -38,9 +40,10 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "total",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "Total number of iterations required",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -52,22 +55,66 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
     },
     ToolSchema {
         name: "task::set_current_step",
-        description: "Set which step you are currently working on.",
+        description: "Set which step of the plan you are currently working on.",
         location: ToolLocation::Server,
         parameters: vec![
             ParameterSchema {
                 name: "step",
-                type_name: "string",
-                description: "Description of the current step",
+                param_type: ParamType::Integer,
+                description: "Number of the step, from 1, as shown in the plan",
                 required: true,
+                default: None,
             },
         ],
     },
     ToolSchema {
         name: "task::mark_step_complete",
-        description: "Mark the current step as complete.",
+        description: "Mark a step of the plan as complete.",
         location: ToolLocation::Server,
-        parameters: vec![],
+        parameters: vec![
+            ParameterSchema {
+                name: "step",
+                param_type: ParamType::Integer,
+                description: "Number of the step; defaults to the current step",
+                required: false,
+                default: None,
+            },
+        ],
+    },
+    ToolSchema {
+        name: "task::mark_step_failed",
+        description: "Mark a step of the plan as failed, with the reason. Then retry it with task::set_current_step or revise the rest of the plan with task::replan.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "reason",
+                param_type: ParamType::String,
+                description: "What went wrong",
+                required: true,
+                default: None,
+            },
+            ParameterSchema {
+                name: "step",
+                param_type: ParamType::Integer,
+                description: "Number of the step; defaults to the current step",
+                required: false,
+                default: None,
+            },
+        ],
+    },
+    ToolSchema {
+        name: "task::replan",
+        description: "Replace every unfinished step of the plan with new steps, e.g. after a step failed. Completed steps are kept and the new steps are numbered after them.",
+        location: ToolLocation::Server,
+        parameters: vec![
+            ParameterSchema {
+                name: "steps",
+                param_type: ParamType::array(ParamType::String),
+                description: "Ordered list of the remaining steps",
+                required: true,
+                default: None,
+            },
+        ],
     },
     ToolSchema {
         name: "task::mark_complete",
@@ -82,21 +129,24 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "key",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "Short identifier for this note, e.g. 'jobs_applied', 'target_role', 'failed_companies'",
                 required: true,
+                default: None,
             },
             ParameterSchema {
                 name: "value",
-                type_name: "string",
+                param_type: ParamType::Any,
                 description: "The value to store. Can be any JSON: string, number, array, object.",
                 required: true,
+                default: None,
             },
             ParameterSchema {
                 name: "importance",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "Importance from 1 (ephemeral, evicted first) to 10 (critical, never evict). Use 10 for goal-critical counters, 1 for debug/temp values.",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -107,9 +157,10 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "key",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "The key of the note to retrieve",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -120,9 +171,10 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "key",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "The key of the note to remove",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -133,21 +185,24 @@ pub static TASK_TOOLS: Lazy<Vec<ToolSchema>> = Lazy::new(|| vec![
         parameters: vec![
             ParameterSchema {
                 name: "key",
-                type_name: "string",
+                param_type: ParamType::String,
                 description: "The key of the numeric note",
                 required: true,
+                default: None,
             },
             ParameterSchema {
                 name: "delta",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "Amount to add (use negative to subtract)",
                 required: true,
+                default: None,
             },
             ParameterSchema {
                 name: "importance",
-                type_name: "integer",
+                param_type: ParamType::Integer,
                 description: "Importance 1-10 (applied on creation; ignored on update)",
                 required: true,
+                default: None,
             },
         ],
     },
@@ -165,18 +220,15 @@ pub fn handle_task_tool(task: &mut TaskState, tool_name: &str, args: &Value) ->
         }
 
         "task::set_plan" => {
-            let steps_array = args["steps"]
-                .as_array()
-                .ok_or_else(|| anyhow::anyhow!("'steps' must be an array"))?;
-            let steps: Vec<String> = steps_array
-                .iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect();
-            if steps.is_empty() {
-                return Err(anyhow::anyhow!("Plan must have at least one step"));
-            }
-            task.set_plan(steps.clone());
-            Ok(format!("Plan set with {} steps", steps.len()))
+            let steps = plan_steps(args)?;
+            task.set_plan(steps);
+            Ok(format!("Plan set:\n{}", describe_plan(task)))
+        }
+
+        "task::replan" => {
+            let steps = plan_steps(args)?;
+            task.revise_plan(steps);
+            Ok(format!("Plan revised:\n{}", describe_plan(task)))
         }
 
         "task::set_iterations" => {
@@ -209,17 +261,37 @@ pub fn handle_task_tool(task: &mut TaskState, tool_name: &str, args: &Value) ->
         }
 
         "task::set_current_step" => {
-            let step = args["step"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Missing 'step' parameter"))?
-                .to_string();
-            task.set_current_step(&step);
-            Ok(format!("Now working on: {}", step))
+            if args["step"].is_null() {
+                return Err(anyhow::anyhow!("Missing 'step' parameter"));
+            }
+            let index = task
+                .set_current_step(&args["step"])
+                .ok_or_else(|| no_such_step(task, &args["step"]))?;
+            Ok(format!("Now working on step {}: {}", index + 1, step_description(task, index)))
         }
 
         "task::mark_step_complete" => {
-            task.mark_step_complete();
-            Ok("Current step marked complete".to_string())
+            let step = Some(&args["step"]).filter(|step| !step.is_null());
+            let index = task
+                .mark_step_complete(step)
+                .ok_or_else(|| no_such_step(task, &args["step"]))?;
+            let (done, total) = task.plan.as_ref().map(|plan| plan.progress()).unwrap_or_default();
+            Ok(format!("Step {} complete ({}/{} done)", index + 1, done, total))
+        }
+
+        "task::mark_step_failed" => {
+            let reason = args["reason"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing 'reason' parameter"))?
+                .to_string();
+            let step = Some(&args["step"]).filter(|step| !step.is_null());
+            let index = task
+                .mark_step_failed(step, reason.clone())
+                .ok_or_else(|| no_such_step(task, &args["step"]))?;
+            Ok(format!(
+                "Step {} failed: {}. Retry it with task::set_current_step, or revise the remaining steps with task::replan.",
+                index + 1, reason
+            ))
         }
 
         "task::mark_complete" => {
@@ -290,3 +362,45 @@ pub fn handle_task_tool(task: &mut TaskState, tool_name: &str, args: &Value) ->
 pub fn is_task_tool(tool_name: &str) -> bool {
     tool_name.starts_with("task::")
 }
+
+/// Task tools that can change the plan; clients are sent its progress after each.
+pub fn is_plan_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "task::set_plan"
+            | "task::replan"
+            | "task::set_current_step"
+            | "task::mark_step_complete"
+            | "task::mark_step_failed"
+            | "task::mark_complete"
+    )
+}
+
+fn plan_steps(args: &Value) -> Result<Vec<String>> {
+    let steps: Vec<String> = args["steps"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("'steps' must be an array"))?
+        .iter()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect();
+    if steps.is_empty() {
+        return Err(anyhow::anyhow!("Plan must have at least one step"));
+    }
+    Ok(steps)
+}
+
+fn describe_plan(task: &TaskState) -> String {
+    task.plan.as_ref().map(|plan| plan.describe()).unwrap_or_default()
+}
+
+fn step_description(task: &TaskState, index: usize) -> &str {
+    task.plan.as_ref().map(|plan| plan.steps[index].description.as_str()).unwrap_or("")
+}
+
+fn no_such_step(task: &TaskState, step: &Value) -> anyhow::Error {
+    match &task.plan {
+        None => anyhow::anyhow!("No plan is set; call task::set_plan first"),
+        Some(_) if step.is_null() => anyhow::anyhow!("No step is in progress; name the step by its number"),
+        Some(plan) => anyhow::anyhow!("No step {} in the plan:\n{}", step, plan.describe()),
+    }
+}
@@ -0,0 +1,130 @@
+use axum::{
+    extract::Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use artificer_shared::errors::{ErrorBody, ErrorCode, FieldError, ModelHost};
+
+/// Every error an API handler can return. Each variant maps to one HTTP
+/// status and one machine-readable `ErrorCode`.
+#[derive(Debug)]
+pub enum ApiError {
+    /// Missing, invalid, or deactivated device credentials.
+    Unauthorized { message: String },
+    /// Credentials are valid but the device may not touch this resource.
+    Forbidden { message: String },
+    NotFound { message: String, resource: String },
+    /// One or more request fields failed validation. Never empty.
+    ValidationFailed { errors: Vec<FieldError> },
+    /// Every GPU that could serve the request is already busy.
+    ResourceBusy { message: String },
+    /// The model backend could not be reached or rejected the request.
+    /// `hosts` is what the latest probes found, when they found every
+    /// server down.
+    ModelUnavailable { message: String, hosts: Vec<ModelHost> },
+    ToolFailed { message: String, tool: String },
+    Internal { message: String },
+}
+
+impl ApiError {
+    /// Shorthand for a validation failure on a single field.
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        ApiError::ValidationFailed {
+            errors: vec![FieldError { field: field.to_string(), message: message.into() }],
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::Unauthorized { .. } => ErrorCode::Unauthorized,
+            ApiError::Forbidden { .. } => ErrorCode::Forbidden,
+            ApiError::NotFound { .. } => ErrorCode::NotFound,
+            ApiError::ValidationFailed { .. } => ErrorCode::ValidationFailed,
+            ApiError::ResourceBusy { .. } => ErrorCode::ResourceBusy,
+            ApiError::ModelUnavailable { .. } => ErrorCode::ModelUnavailable,
+            ApiError::ToolFailed { .. } => ErrorCode::ToolFailed,
+            ApiError::Internal { .. } => ErrorCode::Internal,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::ValidationFailed { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::ResourceBusy { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::ModelUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::ToolFailed { .. } => StatusCode::BAD_GATEWAY,
+            ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::ValidationFailed { errors } => match errors.as_slice() {
+                [single] => single.message.clone(),
+                many => format!("{} fields failed validation", many.len()),
+            },
+            ApiError::Unauthorized { message }
+            | ApiError::Forbidden { message }
+            | ApiError::NotFound { message, .. }
+            | ApiError::ResourceBusy { message }
+            | ApiError::ModelUnavailable { message, .. }
+            | ApiError::ToolFailed { message, .. }
+            | ApiError::Internal { message } => message.clone(),
+        }
+    }
+
+    pub fn to_body(&self) -> ErrorBody {
+        let mut body = ErrorBody {
+            error: self.message(),
+            code: self.code(),
+            field: None,
+            errors: Vec::new(),
+            resource: None,
+            tool: None,
+            hosts: Vec::new(),
+        };
+        match self {
+            ApiError::NotFound { resource, .. } => body.resource = Some(resource.clone()),
+            ApiError::ValidationFailed { errors } => {
+                body.field = errors.first().map(|e| e.field.clone());
+                body.errors = errors.clone();
+            }
+            ApiError::ToolFailed { tool, .. } => body.tool = Some(tool.clone()),
+            ApiError::ModelUnavailable { hosts, .. } => body.hosts = hosts.clone(),
+            _ => {}
+        }
+        body
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(self.to_body())).into_response()
+    }
+}
+
+/// The gRPC status for each error, as documented in `proto/artificer.proto`.
+impl From<ApiError> for tonic::Status {
+    fn from(error: ApiError) -> Self {
+        let code = match error {
+            ApiError::Unauthorized { .. } => tonic::Code::Unauthenticated,
+            ApiError::Forbidden { .. } => tonic::Code::PermissionDenied,
+            ApiError::NotFound { .. } => tonic::Code::NotFound,
+            ApiError::ValidationFailed { .. } => tonic::Code::InvalidArgument,
+            ApiError::ResourceBusy { .. } | ApiError::ModelUnavailable { .. } => tonic::Code::Unavailable,
+            ApiError::ToolFailed { .. } | ApiError::Internal { .. } => tonic::Code::Internal,
+        };
+        tonic::Status::new(code, error.message())
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code().as_str(), self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
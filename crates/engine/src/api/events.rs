@@ -1,18 +1,142 @@
-use tokio::sync::mpsc;
-use axum::response::sse::Event;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::response::sse::{Event, KeepAlive};
+use futures_util::Stream;
 use serde_json::Value;
+use tokio::sync::watch;
+use artificer_shared::db::Confidence;
+use crate::agent::moderation::Refusal;
+use crate::agent::state::Plan;
+
+/// How often an idle SSE stream sends a `: heartbeat` comment, so proxies
+/// don't close it during long tool calls.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a finished chat turn's events stay available to /chat/resume.
+pub const REPLAY_RETENTION: Duration = Duration::from_secs(5 * 60);
+
+/// Keep-alive for every SSE response.
+pub fn heartbeat() -> KeepAlive {
+    KeepAlive::new().interval(HEARTBEAT_INTERVAL).text("heartbeat")
+}
 
 /// A single SSE event ready to be sent to the client.
+#[derive(Debug, Clone)]
 pub struct SseEvent {
+    /// Position in its chat turn, sent as the SSE `id`. None outside chat turns.
+    id: Option<u64>,
     event_type: String,
     data: String,
 }
 
 impl SseEvent {
-    pub fn to_sse(self) -> Result<Event, std::convert::Infallible> {
-        Ok(Event::default()
-            .event(self.event_type)
-            .data(self.data))
+    pub fn new(event_type: &str, data: String) -> Self {
+        Self { id: None, event_type: event_type.to_string(), data }
+    }
+
+    /// Position in its chat turn. None outside chat turns.
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    /// The event's JSON payload, tagged with its `type`.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn to_sse(self) -> Result<Event, Infallible> {
+        let event = match self.id {
+            Some(id) => Event::default().id(id.to_string()),
+            None => Event::default(),
+        };
+        Ok(event.event(self.event_type).data(self.data))
+    }
+}
+
+/// Every event of one chat turn, numbered from 1. Each stream reads from
+/// here, so a client that lost its connection can reconnect and replay what
+/// it missed.
+#[derive(Debug)]
+pub struct EventLog {
+    /// Events so far, and whether the turn is over.
+    events: Mutex<(Vec<SseEvent>, bool)>,
+    /// Bumped on every append so readers wake up.
+    appended: watch::Sender<()>,
+}
+
+impl EventLog {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            events: Mutex::new((Vec::new(), false)),
+            appended: watch::Sender::new(()),
+        })
+    }
+
+    fn push(&self, event_type: &str, data: String, last: bool) {
+        {
+            let mut guard = self.events.lock().unwrap();
+            let (events, finished) = &mut *guard;
+            if *finished {
+                return;
+            }
+            let id = events.len() as u64 + 1;
+            events.push(SseEvent { id: Some(id), event_type: event_type.to_string(), data });
+            *finished = last;
+        }
+        self.appended.send_replace(());
+    }
+
+    /// Events after `after`, and whether the turn is over.
+    fn since(&self, after: u64) -> (Vec<SseEvent>, bool) {
+        let guard = self.events.lock().unwrap();
+        let (events, finished) = &*guard;
+        let start = (after as usize).min(events.len());
+        (events[start..].to_vec(), *finished)
+    }
+
+    /// The text streamed since the last tool call, tool result or task
+    /// switch: the answer being written when the turn stopped.
+    pub fn partial_answer(&self) -> String {
+        let guard = self.events.lock().unwrap();
+        let (events, _) = &*guard;
+        let start = events.iter()
+            .rposition(|event| matches!(event.event_type.as_str(), "tool_call" | "tool_result" | "task_switch"))
+            .map_or(0, |last| last + 1);
+        events[start..].iter()
+            .filter(|event| event.event_type == "stream_chunk")
+            .filter_map(|event| serde_json::from_str::<Value>(&event.data).ok())
+            .filter_map(|data| data["content"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Stream the events after `after` as SSE, then follow the turn live until it ends.
+    pub fn subscribe(self: &Arc<Self>, after: u64) -> impl Stream<Item = Result<Event, Infallible>> + use<> {
+        futures_util::StreamExt::map(self.follow(after), SseEvent::to_sse)
+    }
+
+    /// Like `subscribe`, but yields the events themselves, for in-process readers.
+    pub fn follow(self: &Arc<Self>, after: u64) -> impl Stream<Item = SseEvent> + use<> {
+        let state = (self.clone(), self.appended.subscribe(), after);
+        let batches = futures_util::stream::unfold(state, |(log, mut appended, cursor)| async move {
+            loop {
+                // Mark the current append seen before reading, so none is missed
+                appended.borrow_and_update();
+                let (batch, finished) = log.since(cursor);
+                if let Some(last) = batch.last() {
+                    let cursor = last.id.unwrap_or(cursor);
+                    return Some((batch, (log, appended, cursor)));
+                }
+                if finished || appended.changed().await.is_err() {
+                    return None;
+                }
+            }
+        });
+        futures_util::StreamExt::flat_map(batches, futures_util::stream::iter)
     }
 }
 
@@ -20,23 +144,24 @@ impl SseEvent {
 /// Created per-request by the handler, passed to the Orchestrator and specialists.
 #[derive(Clone)]
 pub struct EventSender {
-    tx: mpsc::Sender<SseEvent>,
+    log: Arc<EventLog>,
 }
 
 impl EventSender {
-    pub fn new(tx: mpsc::Sender<SseEvent>) -> Self {
-        Self { tx }
+    pub fn new(log: Arc<EventLog>) -> Self {
+        Self { log }
     }
 
     fn send(&self, event_type: &str, data: Value) {
+        self.push(event_type, data, false);
+    }
+
+    fn push(&self, event_type: &str, data: Value, last: bool) {
         let mut payload = data;
         if let Value::Object(ref mut map) = payload {
             map.insert("type".to_string(), Value::String(event_type.to_string()));
         }
-        let _ = self.tx.try_send(SseEvent {
-            event_type: event_type.to_string(),
-            data: payload.to_string(),
-        });
+        self.log.push(event_type, payload.to_string(), last);
     }
 
     pub fn task_switch(&self, from: &str, to: &str) {
@@ -46,6 +171,18 @@ impl EventSender {
         }));
     }
 
+    /// The task's plan after a change, with how many of its steps are done.
+    pub fn plan_progress(&self, task: &str, plan: &Plan) {
+        let (completed, total) = plan.progress();
+        self.send("plan_progress", serde_json::json!({
+            "task": task,
+            "revision": plan.revision,
+            "completed": completed,
+            "total": total,
+            "steps": plan.steps,
+        }));
+    }
+
     pub fn tool_call(&self, task: &str, tool: &str, args: Value) {
         self.send("tool_call", serde_json::json!({
             "task": task,
@@ -70,6 +207,13 @@ impl EventSender {
         }));
     }
 
+    /// Waiting for a free GPU, with `ahead` requests in line first.
+    pub fn queued(&self, ahead: usize) {
+        self.send("queued", serde_json::json!({
+            "position": ahead,
+        }));
+    }
+
     pub fn stream_chunk(&self, content: String) {
         self.send("stream_chunk", serde_json::json!({
             "content": content,
@@ -81,9 +225,56 @@ impl EventSender {
             "message": message,
         }));
     }
+
+    /// Moderation blocked the user's message, or a tool's result when `tool`
+    /// is given.
+    pub fn refusal(&self, refusal: &Refusal, tool: Option<&str>) {
+        self.send("refusal", serde_json::json!({
+            "category": refusal.category,
+            "message": refusal.message,
+            "tool": tool,
+        }));
+    }
+
+    /// The final answer, when it differs from what was streamed (e.g. after
+    /// response filters removed or added text).
+    pub fn response_complete(&self, content: &str) {
+        self.send("response_complete", serde_json::json!({
+            "content": content,
+        }));
+    }
+
+    /// The last event of a turn; streams end after it.
     pub fn done(&self, conversation_id: u64) {
-        self.send("done", serde_json::json!({
+        self.push("done", serde_json::json!({
+            "conversation_id": conversation_id,
+        }), true);
+    }
+
+    pub fn question(&self, conversation_id: u64, question_id: u64, task: &str, question: &str, options: &[String]) {
+        self.send("question", serde_json::json!({
             "conversation_id": conversation_id,
+            "question_id": question_id,
+            "task": task,
+            "question": question,
+            "options": options,
+        }));
+    }
+
+    /// Diagnostics such as a retried LLM call, for clients that show them.
+    pub fn debug(&self, task: &str, message: &str) {
+        self.send("debug", serde_json::json!({
+            "task": task,
+            "message": message,
+        }));
+    }
+
+    /// How sure the answer is and what it rests on, for low-confidence banners.
+    pub fn confidence(&self, task: &str, confidence: &Confidence) {
+        self.send("confidence", serde_json::json!({
+            "task": task,
+            "level": confidence.level,
+            "sources": confidence.sources,
         }));
     }
 
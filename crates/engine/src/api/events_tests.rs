@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use crate::api::events::{EventLog, EventSender};
+
+    #[tokio::test]
+    async fn resume_replays_only_missed_events_and_ends_at_done() {
+        let log = EventLog::new();
+        let events = EventSender::new(log.clone());
+        events.stream_chunk("one".to_string());
+        events.stream_chunk("two".to_string());
+        events.done(7);
+
+        assert_eq!(log.subscribe(0).count().await, 3);
+        assert_eq!(log.subscribe(2).count().await, 1);
+        assert_eq!(log.subscribe(3).count().await, 0);
+
+        // Nothing is recorded once the turn is over
+        events.error("late");
+        assert_eq!(log.subscribe(0).count().await, 3);
+    }
+
+    #[test]
+    fn partial_answer_is_the_text_after_the_last_tool_step() {
+        let log = EventLog::new();
+        let events = EventSender::new(log.clone());
+        assert_eq!(log.partial_answer(), "");
+
+        events.stream_chunk("Let me check. ".to_string());
+        events.tool_call("task_1", "Clock::now", serde_json::json!({}));
+        events.tool_result("task_1", "Clock::now", "12:00".to_string());
+        events.stream_chunk("It is ".to_string());
+        events.debug("task_1", "retried");
+        events.stream_chunk("noon".to_string());
+        assert_eq!(log.partial_answer(), "It is noon");
+    }
+
+    #[tokio::test]
+    async fn subscriber_follows_the_turn_live() {
+        let log = EventLog::new();
+        let events = EventSender::new(log.clone());
+        let stream = log.subscribe(0);
+
+        let producer = tokio::spawn(async move {
+            for i in 0..5 {
+                events.stream_chunk(i.to_string());
+                tokio::task::yield_now().await;
+            }
+            events.done(7);
+        });
+
+        assert_eq!(stream.count().await, 6);
+        producer.await.unwrap();
+    }
+}
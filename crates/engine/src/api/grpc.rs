@@ -0,0 +1,212 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use futures_util::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use crate::api::error::ApiError;
+use crate::api::events::EventLog;
+use crate::api::handlers::{
+    job_inbox, job_status, open_chat, queue_job, register_device, resume_chat, start_chat_turn, verify_device,
+};
+use crate::api::types;
+use crate::state::AppState;
+
+/// Messages and the service trait generated from `proto/artificer.proto`.
+pub mod proto {
+    tonic::include_proto!("artificer.v1");
+}
+
+use proto::artificer_server::{Artificer, ArtificerServer};
+
+type ChatEventStream = Pin<Box<dyn Stream<Item = Result<proto::ChatEvent, Status>> + Send>>;
+
+/// The gRPC service as routes, merged into the API's router so both are
+/// served on one port.
+pub fn routes(state: AppState) -> axum::Router {
+    tonic::service::Routes::new(ArtificerServer::new(GrpcApi { state })).into_axum_router()
+}
+
+/// The gRPC mirror of the chat, device and job endpoints. Each call does
+/// what its REST endpoint does, through the same functions.
+pub struct GrpcApi {
+    state: AppState,
+}
+
+impl GrpcApi {
+    /// Calls that change anything are refused on a read-only replica, as
+    /// their REST routes are.
+    fn writable(&self) -> Result<(), Status> {
+        if self.state.read_only {
+            return Err(ApiError::Forbidden {
+                message: "This engine is a read-only replica; send writes to the primary".to_string(),
+            }.into());
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Artificer for GrpcApi {
+    type ChatStream = ChatEventStream;
+    type ResumeChatStream = ChatEventStream;
+
+    async fn chat(&self, request: Request<proto::ChatRequest>) -> Result<Response<Self::ChatStream>, Status> {
+        self.writable()?;
+        let req = request.into_inner();
+        let (device_id, device_key) = credentials(req.device)?;
+        let req = types::ChatRequest {
+            device_id,
+            device_key,
+            conversation_id: req.conversation_id,
+            message: req.message,
+            stream: Some(true),
+            deferred: false,
+            profile: req.profile,
+            incognito: false,
+            privacy: None,
+            toolbelts: Vec::new(),
+            fresh: req.fresh,
+            debug: req.debug,
+            verify: false,
+            model: None,
+            latency_hint: None,
+        };
+
+        let (device_id, conversation_id) = open_chat(&self.state, &req)?;
+        let log = start_chat_turn(self.state.clone(), device_id, conversation_id, req)?;
+        Ok(Response::new(chat_events(&log, 0)))
+    }
+
+    async fn resume_chat(
+        &self,
+        request: Request<proto::ResumeChatRequest>,
+    ) -> Result<Response<Self::ResumeChatStream>, Status> {
+        let req = request.into_inner();
+        let (device_id, device_key) = credentials(req.device)?;
+        let req = types::ResumeChatRequest {
+            device_id,
+            device_key,
+            conversation_id: req.conversation_id,
+            last_event_id: req.last_event_id,
+        };
+
+        let log = resume_chat(&self.state, &req)?;
+        Ok(Response::new(chat_events(&log, req.last_event_id.unwrap_or(0))))
+    }
+
+    async fn register_device(
+        &self,
+        request: Request<proto::RegisterDeviceRequest>,
+    ) -> Result<Response<proto::RegisterDeviceResponse>, Status> {
+        self.writable()?;
+        let registered = register_device(self.state.agent_pool.db(), &request.into_inner().device_name)?;
+        Ok(Response::new(proto::RegisterDeviceResponse {
+            device_id: registered.device_id,
+            device_key: registered.device_key,
+        }))
+    }
+
+    async fn verify_device(
+        &self,
+        request: Request<proto::Credentials>,
+    ) -> Result<Response<proto::VerifyDeviceResponse>, Status> {
+        let req = request.into_inner();
+        let valid = match verify_device(self.state.agent_pool.db(), req.device_id, &req.device_key) {
+            Ok(()) => true,
+            Err(ApiError::Unauthorized { .. }) => false,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Response::new(proto::VerifyDeviceResponse { valid }))
+    }
+
+    async fn queue_job(
+        &self,
+        request: Request<proto::QueueJobRequest>,
+    ) -> Result<Response<proto::QueueJobResponse>, Status> {
+        self.writable()?;
+        let req = request.into_inner();
+        let (device_id, device_key) = credentials(req.device)?;
+        let arguments = match req.arguments.trim() {
+            "" => None,
+            json => Some(serde_json::from_str(json).map_err(|e| {
+                ApiError::invalid("arguments", format!("arguments must be a JSON object: {}", e))
+            })?),
+        };
+        let req = types::QueueJobRequest {
+            device_id,
+            device_key,
+            method: req.method,
+            conversation_id: req.conversation_id,
+            arguments,
+            priority: req.priority,
+            run_at: None,
+            delay_secs: None,
+            latency_hint: None,
+        };
+
+        let queued = queue_job(self.state.agent_pool.db(), req)?;
+        Ok(Response::new(proto::QueueJobResponse {
+            job_id: queued.job_id,
+            conversation_id: queued.conversation_id,
+        }))
+    }
+
+    async fn job_status(
+        &self,
+        request: Request<proto::JobStatusRequest>,
+    ) -> Result<Response<proto::BackgroundJob>, Status> {
+        let req = request.into_inner();
+        let (device_id, device_key) = credentials(req.device)?;
+        let req = types::JobStatusRequest { device_id, device_key, job_id: req.job_id };
+
+        let job = job_status(self.state.agent_pool.db(), &req)?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn job_inbox(
+        &self,
+        request: Request<proto::Credentials>,
+    ) -> Result<Response<proto::JobInboxResponse>, Status> {
+        self.writable()?;
+        let req = request.into_inner();
+        let req = types::DeviceRequest { device_id: req.device_id, device_key: req.device_key };
+
+        let jobs = job_inbox(self.state.agent_pool.db(), &req)?;
+        Ok(Response::new(proto::JobInboxResponse {
+            jobs: jobs.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+/// The device id and key of a request, which every call but registration needs.
+fn credentials(device: Option<proto::Credentials>) -> Result<(i64, String), Status> {
+    device
+        .map(|c| (c.device_id, c.device_key))
+        .ok_or_else(|| ApiError::Unauthorized { message: "Missing device credentials".to_string() }.into())
+}
+
+/// A chat turn's events after `after` as `ChatEvent`s, following the turn
+/// live until `done`, as /chat streams them over SSE.
+fn chat_events(log: &Arc<EventLog>, after: u64) -> ChatEventStream {
+    let events = log.follow(after).map(|event| {
+        Ok(proto::ChatEvent {
+            id: event.id().unwrap_or_default(),
+            r#type: event.event_type().to_string(),
+            data: event.data().to_string(),
+        })
+    });
+    Box::pin(events)
+}
+
+impl From<artificer_shared::db::BackgroundJob> for proto::BackgroundJob {
+    fn from(job: artificer_shared::db::BackgroundJob) -> Self {
+        Self {
+            id: job.id,
+            method: job.method,
+            arguments: job.arguments.to_string(),
+            status: job.status,
+            result: job.result,
+            created_at: job.created_at,
+            completed_at: job.completed_at,
+        }
+    }
+}
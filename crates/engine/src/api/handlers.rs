@@ -1,271 +1,1829 @@
 use std::sync::Arc;
 use axum::{
-    extract::{Extension, Json},
+    extract::{Json, Path, State},
     response::{IntoResponse, Response, Sse},
     http::StatusCode,
 };
 use futures_util::stream::StreamExt;
-use serde::Serialize;
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
 
-use artificer_shared::db::Db;
+use artificer_shared::db::{normalize_profile, BackgroundJob, ConversationAccess, Db, LockOutcome, MergeOutcome, PrivacyLevel, RedactOutcome, ResponseLength, SplitOutcome, DEFAULT_PROFILE, MAX_PROFILE_CHARS};
+use artificer_shared::memory::{MemoryStore, DEFAULT_TIMELINE_WEEKS};
+use artificer_shared::tools::{get_tool_schemas_for, tool_example, toolbelt_names, toolbelts};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use crate::agent::AgentType;
 use crate::agent::state::ExecutionContext;
-use crate::api::events::{EventSender, SseEvent};
+use crate::agent::response_filters::{response_secrets, FilterChain, FilterContext};
+use crate::api::error::ApiError;
+use crate::api::events::{heartbeat, EventLog, EventSender, SseEvent, REPLAY_RETENTION};
+use artificer_shared::events::CONVERSATION_ID_HEADER;
+use crate::api::validation::{
+    validate_agent_name, validate_chat_request, validate_continue_request, validate_conversation_access,
+    validate_experiment_request, validate_feedback_request, validate_job_request,
+    validate_language_request, validate_metadata_request, validate_prompt_request, validate_settings_request,
+    validate_attachment_upload, validate_checkpoint_request, validate_device_group_request, validate_lock_request, validate_redact_request, validate_tag_request,
+    validate_usage_request, validate_tool_switch_request, validate_workflow_request, validate_run_workflow_request,
+    validate_watch_request, validate_trigger_watch_request,
+    tool_switch_name, toolbelt_name,
+};
 use crate::api::types::{
-    ChatRequest,
+    CancelChatRequest, CancelChatResponse, ChatRequest, ContinueChatRequest, ContinueChatResponse, DeferredChatResponse, DeviceRequest, JobInboxResponse,
+    DeviceSettingsRequest, DeviceSettingsResponse,
+    JobStatusRequest, QueueJobRequest, QueueJobResponse,
+    SaveWorkflowRequest, WorkflowRequest, RunWorkflowRequest, WorkflowInfo, WorkflowsResponse,
+    AddWatchRequest, AddWatchResponse, WatchRequest, TriggerWatchRequest, WatchesResponse,
     RegisterDeviceRequest, RegisterDeviceResponse,
+    SearchConversationsRequest, SearchConversationsResponse,
+    ConversationStateRequest, ShareConversationRequest, LockConversationRequest,
+    ConversationPrivacyRequest, SetConversationPrivacyRequest, ConversationPrivacyResponse,
+    ConversationToolsRequest, SetConversationToolsRequest, ConversationToolsResponse,
+    MarkReadRequest, MarkReadResponse, UnreadRequest, UnreadResponse,
+    PromptHistoryRequest, PromptHistoryResponse, RollbackPromptRequest,
+    SetPromptRequest, SetPromptResponse,
+    ExperimentRequest, StartExperimentRequest, StartExperimentResponse, FeedbackRequest,
+    ToolSwitchesRequest, ToolSwitchesResponse, SetToolSwitchRequest, ToolsResponse, ToolbeltDoc, ToolDoc,
+    ProviderHealthResponse, ProviderStatus,
+    DeviceLanguageRequest, DeviceMetadataRequest, PurgeDeviceRequest,
+    LinkDeviceRequest, SetDeviceGroupRequest, DeleteDeviceGroupRequest, DeviceGroupsResponse,
+    UploadAttachmentRequest, UploadAttachmentResponse, AttachmentRequest, AttachmentResponse,
+    TagConversationRequest, ConversationTagsResponse, ListTagsResponse,
+    TaggedConversationsRequest, TaggedConversationsResponse,
+    UsageRequest, UsageResponse, ProviderUsage,
+    ListConversationsRequest, ListConversationsResponse,
+    ConversationMessagesRequest, ConversationMessagesResponse, DeleteMessageRequest,
+    CreateCheckpointRequest, CreateCheckpointResponse, ListCheckpointsResponse,
+    BranchConversationRequest, BranchConversationResponse, RedactMessageRequest, RedactMessageResponse,
+    MergeConversationsRequest, SplitConversationRequest, SplitConversationResponse,
+    MemoriesRequest, MemoriesResponse, MemoryTimelineRequest, MemoryTimelineResponse, ResumeChatRequest, RequestSnapshotsResponse,
 };
-use crate::pool::AgentPool;
-use crate::pool::gpu_pool::GpuPool;
-
-// ============================================================================
-// APP STATE
-// ============================================================================
-
-/// Shared application state injected into every handler via Extension.
-#[derive(Clone)]
-pub struct AppState {
-    pub gpu_pool: Arc<GpuPool>,
-    pub agent_pool: Arc<AgentPool>,
-}
+use crate::pool::{LatencyHint, MAX_QUEUED_CHATS};
+use crate::state::AppState;
 
 // ============================================================================
-// ERROR TYPE
+// HANDLERS
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-#[serde(tag = "error_type")]
-pub enum ApiError {
-    Authentication { message: String },
-    NotFound { message: String, resource: String },
-    InvalidRequest { message: String, field: Option<String> },
-    ResourceBusy { message: String },
-    InternalError { message: String },
-}
-
-impl ApiError {
-    fn to_response(self) -> Response {
-        let (status, body) = match self {
-            ApiError::Authentication { message } => (
-                StatusCode::UNAUTHORIZED,
-                serde_json::json!({ "error": message, "type": "authentication" }),
-            ),
-            ApiError::NotFound { message, resource } => (
-                StatusCode::NOT_FOUND,
-                serde_json::json!({ "error": message, "resource": resource, "type": "not_found" }),
-            ),
-            ApiError::InvalidRequest { message, field } => (
-                StatusCode::BAD_REQUEST,
-                serde_json::json!({ "error": message, "field": field, "type": "invalid_request" }),
-            ),
-            ApiError::ResourceBusy { message } => (
-                StatusCode::SERVICE_UNAVAILABLE,
-                serde_json::json!({ "error": message, "type": "resource_busy" }),
-            ),
-            ApiError::InternalError { message } => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                serde_json::json!({ "error": message, "type": "internal_error" }),
-            ),
-        };
-        (status, Json(body)).into_response()
-    }
-}
+/// Error event of a chat turn stopped by its client.
+pub const TURN_CANCELLED: &str = "Cancelled by the client";
 
-// ============================================================================
-// HANDLERS
-// ============================================================================
+/// Ends the partial answer a cancelled turn keeps, so the model later sees
+/// it was cut short.
+const PARTIAL_ANSWER_NOTE: &str = "[Stopped early: the user cancelled this answer.]";
 
 /// POST /chat
 pub async fn handle_chat(
-    Extension(state): Extension<AppState>,
+    State(state): State<AppState>,
     Json(req): Json<ChatRequest>,
 ) -> Response {
-    // Validate request
-    if let Err(e) = validate_chat_request(&req) {
-        return e.to_response();
-    }
+    let (device_id, conversation_id) = match open_chat(&state, &req) {
+        Ok(opened) => opened,
+        Err(e) => return e.into_response(),
+    };
 
-    // Authenticate device
-    let device_id = match authenticate_device(state.agent_pool.db(), &req.device_key) {
-        Ok(id) => {
-            println!("Device {} authenticated", id);
-            id
+    if req.deferred {
+        // Nobody is watching a background answer, so a blocked message is
+        // turned away before it is queued
+        if let Some(refusal) = state.agent_pool.moderation().check_message(&req.message).await {
+            return ApiError::invalid("message", refusal.message).into_response();
         }
-        Err(e) => return ApiError::Authentication {
-            message: format!("Invalid device key: {}", e),
-        }.to_response(),
-    };
+        return defer_chat(state.agent_pool.db(), device_id, conversation_id, &req);
+    }
+
+    match start_chat_turn(state, device_id, conversation_id, req) {
+        Ok(log) => chat_stream(conversation_id, log.subscribe(0)),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Validate and authenticate a chat request and find or start its
+/// conversation. Returns the device and conversation ids. Shared by every
+/// chat transport.
+pub fn open_chat(state: &AppState, req: &ChatRequest) -> Result<(u64, u64), ApiError> {
+    validate_chat_request(req)?;
+
+    let db = state.agent_pool.db();
+    let device_id = authenticate(db, req.device_id, &req.device_key)?;
+    println!("Device {} authenticated", device_id);
+
+    validate_conversation_access(db, device_id, req.conversation_id)?;
 
     // Resolve conversation
-    let conversation_id = match resolve_conversation(state.agent_pool.db(), device_id, req.conversation_id) {
-        Ok(id) => {
-            println!("Using conversation {} for device {}", id, device_id);
-            id
-        }
-        Err(e) => return ApiError::InternalError {
+    let privacy = req.privacy.as_deref().and_then(PrivacyLevel::parse).unwrap_or_default();
+    let conversation_id = resolve_conversation(db, device_id, req.conversation_id, req.profile.as_deref(), req.incognito, privacy)
+        .map_err(|e| ApiError::Internal {
             message: format!("Failed to create/retrieve conversation: {}", e),
-        }.to_response(),
-    };
+        })?;
+    println!("Using conversation {} for device {}", conversation_id, device_id);
 
-    // Acquire GPU
-    let gpu = match state.gpu_pool.acquire_interactive() {
-        Some(gpu) => {
-            println!("GPU {} acquired for conversation {}", gpu.id, conversation_id);
-            gpu
-        }
-        None => {
-            eprintln!("No GPUs available for conversation {}", conversation_id);
-            return ApiError::ResourceBusy {
-                message: "All GPUs are currently busy processing other requests. Please try again in a moment.".to_string(),
-            }.to_response();
+    // A new conversation starts with the toolbelts the request chose
+    if req.conversation_id.is_none() {
+        for choice in &req.toolbelts {
+            let toolbelt = toolbelt_name(&choice.toolbelt).unwrap_or_else(|| choice.toolbelt.clone());
+            db.set_conversation_toolbelt(conversation_id, &toolbelt, Some(choice.enabled))
+                .map_err(|e| ApiError::Internal {
+                    message: format!("Failed to set toolbelts: {}", e),
+                })?;
         }
+    }
+
+    Ok((device_id, conversation_id))
+}
+
+/// Run one streamed chat turn in the background and return the log its
+/// events are recorded in. Independent of the transport: the caller streams
+/// the log however its clients expect.
+pub fn start_chat_turn(
+    state: AppState,
+    device_id: u64,
+    conversation_id: u64,
+    req: ChatRequest,
+) -> Result<Arc<EventLog>, ApiError> {
+    // One answer per conversation at a time, streamed or in the background
+    let Some(turn_guard) = state.runtime.begin_stream(conversation_id) else {
+        return Err(ApiError::ResourceBusy {
+            message: format!(
+                "Conversation {} is still answering an earlier message. Wait for it to finish \
+                 (/chat/resume follows it), or send this one with deferred: true to queue it.",
+                conversation_id
+            ),
+        });
     };
-    let gpu_id = gpu.id.clone();
 
-    // Set up SSE channel
-    let (tx, rx) = mpsc::channel::<SseEvent>(32);
-    let events = EventSender::new(tx);
+    // The turn waits its turn for a GPU, unless there is none to wait for
+    // or the line is already long
+    if state.gpu_pool.interactive_total() == 0 {
+        return Err(ApiError::ResourceBusy {
+            message: "No interactive GPUs are configured in hardware.json.".to_string(),
+        });
+    }
+    if state.gpu_pool.is_degraded() {
+        return Err(ApiError::ModelUnavailable {
+            message: "No model server is reachable right now, so the engine can't answer. Conversations, \
+                      search and memories still work, and a message sent with deferred: true is queued \
+                      until a server answers again."
+                .to_string(),
+            hosts: state.gpu_pool.hosts(),
+        });
+    }
+    if let Some(model) = &req.model
+        && !state.gpu_pool.serves_interactive(model)
+    {
+        return Err(ApiError::invalid("model", format!(
+            "No interactive GPU serves '{}'. Available: {}",
+            model,
+            state.gpu_pool.interactive_models().join(", ")
+        )));
+    }
+    if state.gpu_pool.queued() >= MAX_QUEUED_CHATS {
+        eprintln!("Chat queue full; turning away conversation {}", conversation_id);
+        return Err(ApiError::ResourceBusy {
+            message: "All GPUs are busy and too many requests are already waiting. Please try again in a moment.".to_string(),
+        });
+    }
 
-    let gpu_pool = state.gpu_pool.clone();
-    let agent_pool = state.agent_pool.clone();
+    // Record the turn's events so a dropped stream can resume them
+    let log = state.runtime.open_replay(conversation_id);
+    let events = EventSender::new(log.clone());
+    let turn_log = log.clone();
+    let cancel = state.runtime.open_cancel(conversation_id);
 
     tokio::spawn(async move {
-        let context = ExecutionContext {
-            device_id,
-            device_key: req.device_key.clone(),
-            conversation_id,
-            parent_task_id: None,
-            gpu,
-            events: Some(events.clone()),
-            db: agent_pool.db().clone(),
+        let AppState { gpu_pool, agent_pool, runtime, .. } = state;
+        let replays = runtime.clone();
+
+        // A blocked message never reaches a model, and isn't kept
+        let refusal = agent_pool.moderation().check_message(&req.message).await;
+
+        // A cancel while waiting in line takes effect here, one while
+        // answering drops the execution wherever it is
+        let hint = req.latency_hint.as_deref().and_then(LatencyHint::parse).unwrap_or_default();
+        let gpu = match &refusal {
+            Some(refusal) => {
+                println!("Refused a message in conversation {} ({})", conversation_id, refusal.category);
+                events.refusal(refusal, None);
+                None
+            }
+            None => tokio::select! {
+                gpu = gpu_pool.acquire_interactive_queued(req.model.as_deref(), hint, |ahead| events.queued(ahead)) => Some(gpu),
+                _ = cancel.notified() => None,
+            },
         };
 
-        // Get orchestrator and execute
-        match agent_pool.get("Orchestrator") {
-            Some(orchestrator) => {
-                let execution = crate::agent::AgentExecution::new(
-                    orchestrator,
-                    context,
-                    &req.message,
-                    &agent_pool,
-                );
-                match execution.execute(agent_pool.clone()).await {
-                    Ok(_) => {
-                        // Success — response already streamed via events
+        match gpu {
+            Some(gpu) => {
+                println!("GPU {} ({}) acquired for conversation {}", gpu.id, gpu.model, conversation_id);
+                let gpu_id = gpu.id.clone();
+
+                let context = ExecutionContext {
+                    device_id,
+                    device_key: req.device_key.clone(),
+                    conversation_id,
+                    parent_task_id: None,
+                    gpu,
+                    events: Some(events.clone()),
+                    db: agent_pool.db().clone(),
+                    runtime,
+                    fresh: req.fresh,
+                    debug: req.debug,
+                    verify: req.verify,
+                    target: None,
+                };
+
+                // Get orchestrator and execute
+                match agent_pool.get("Orchestrator") {
+                    Some(orchestrator) => {
+                        let execution = crate::agent::AgentExecution::new(
+                            orchestrator,
+                            context,
+                            &req.message,
+                            &agent_pool,
+                        );
+                        tokio::select! {
+                            result = execution.execute(agent_pool.clone()) => {
+                                // On success the response was already streamed via events
+                                if let Err(e) = result {
+                                    events.error(&e.to_string());
+                                }
+                            }
+                            _ = cancel.notified() => {
+                                replays.abandon_turn(conversation_id);
+                                keep_partial_answer(agent_pool.db(), conversation_id, &req.device_key, &log);
+                                events.error(TURN_CANCELLED);
+                            }
+                        }
                     }
-                    Err(e) => {
-                        events.error(&e.to_string());
+                    None => {
+                        events.error("Orchestrator agent not found");
                     }
                 }
+
+                gpu_pool.release(&gpu_id);
             }
-            None => {
-                events.error("Orchestrator agent not found");
-            }
+            None if refusal.is_none() => events.error(TURN_CANCELLED),
+            None => {}
         }
+        replays.close_cancel(conversation_id, &cancel);
 
-        gpu_pool.release(&gpu_id);
-
-        // Queue title generation after the first exchange
+        // Queue title generation after the first exchange; incognito
+        // conversations are never listed, so they go untitled
         let message_count = agent_pool.db()
             .get_message_count(conversation_id)
             .unwrap_or(0);
+        let incognito = agent_pool.db().is_incognito(conversation_id).unwrap_or(false);
+
+        if message_count <= 2 && !incognito && refusal.is_none() {
+            let _ = agent_pool.db().queue_title_generation(
+                device_id as i64,
+                conversation_id,
+                &req.message,
+            );
+        }
+
+        // The asking device watched the answer stream in
+        if let Err(e) = agent_pool.db().mark_conversation_read(conversation_id, device_id, None) {
+            eprintln!("Failed to mark conversation {} read: {}", conversation_id, e);
+        }
+
+        // Free the conversation first, so a client may send its next
+        // message as soon as it sees `done`
+        drop(turn_guard);
+        events.done(conversation_id);
+
+        // A client that lost the stream near the end can still replay it
+        tokio::time::sleep(REPLAY_RETENTION).await;
+        replays.close_replay(conversation_id, &log);
+    });
+
+    Ok(turn_log)
+}
+
+/// Close the tasks a cancelled turn left open and keep the text it was
+/// streaming as the conversation's answer, so the user's history shows
+/// what they read before stopping it. Secrets are redacted from it as from
+/// any finished answer.
+fn keep_partial_answer(db: &Db, conversation_id: u64, device_key: &str, log: &EventLog) {
+    if let Err(e) = db.abandon_open_tasks(conversation_id) {
+        eprintln!("Failed to abandon the tasks of conversation {}: {}", conversation_id, e);
+    }
+    let secrets = response_secrets(device_key);
+    let context = FilterContext { sources: &[], secrets: &secrets };
+    let partial = FilterChain::standard().apply(log.partial_answer(), &context);
+    if partial.trim().is_empty() {
+        return;
+    }
+    let content = format!("{}\n\n{}", partial.trim_end(), PARTIAL_ANSWER_NOTE);
+    if let Err(e) = db.add_message(conversation_id, None, "assistant", Some(&content), None) {
+        eprintln!("Failed to keep the partial answer in conversation {}: {}", conversation_id, e);
+    }
+}
+
+/// POST /chat/cancel
+///
+/// Stop the conversation's running chat turn. Its stream, or socket, ends
+/// with an `error` event and `done`.
+pub async fn handle_cancel_chat(
+    State(state): State<AppState>,
+    Json(req): Json<CancelChatRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    if !state.runtime.cancel_turn(req.conversation_id) {
+        return ApiError::NotFound {
+            message: format!("Conversation {} has no chat turn running", req.conversation_id),
+            resource: "stream".to_string(),
+        }.into_response();
+    }
+    println!("Cancelling the chat turn of conversation {}", req.conversation_id);
+    Json(CancelChatResponse { conversation_id: req.conversation_id }).into_response()
+}
+
+/// POST /chat/resume
+///
+/// Reattach to a conversation's latest chat turn: replays the events after
+/// `last_event_id`, then follows the turn live until `done`.
+pub async fn handle_resume_chat(
+    State(state): State<AppState>,
+    Json(req): Json<ResumeChatRequest>,
+) -> Response {
+    match resume_chat(&state, &req) {
+        Ok(log) => chat_stream(req.conversation_id, log.subscribe(req.last_event_id.unwrap_or(0))),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// The log of the conversation's latest chat turn, for a device allowed to
+/// follow it. Shared by every chat transport.
+pub fn resume_chat(state: &AppState, req: &ResumeChatRequest) -> Result<Arc<EventLog>, ApiError> {
+    let db = state.agent_pool.db();
+    let device_id = authenticate(db, req.device_id, &req.device_key)?;
+    validate_conversation_access(db, device_id, Some(req.conversation_id))?;
+
+    state.runtime.replay(req.conversation_id).ok_or_else(|| ApiError::NotFound {
+        message: format!(
+            "Conversation {} has no chat turn to resume (none ran in the last {} minutes)",
+            req.conversation_id,
+            REPLAY_RETENTION.as_secs() / 60
+        ),
+        resource: "stream".to_string(),
+    })
+}
+
+/// An SSE response for a chat turn, with heartbeats and the conversation id header.
+fn chat_stream(
+    conversation_id: u64,
+    stream: impl futures_util::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>> + Send + 'static,
+) -> Response {
+    let header = [(CONVERSATION_ID_HEADER, conversation_id.to_string())];
+    (header, Sse::new(stream).keep_alive(heartbeat())).into_response()
+}
+
+/// Queue a chat turn for the worker and return immediately with 202.
+/// The outcome is announced on /jobs/events and collected via /jobs/inbox.
+fn defer_chat(db: &Db, device_id: u64, conversation_id: u64, req: &ChatRequest) -> Response {
+    let message = req.message.as_str();
+    let arguments = serde_json::json!({
+        "conversation_id": conversation_id,
+        "agent": "Orchestrator",
+        "goal": message,
+        "fresh": req.fresh,
+        "debug": req.debug,
+        "verify": req.verify,
+        "latency_hint": req.latency_hint,
+    });
+
+    let job_id = match db.create_job(device_id as i64, "agent", &arguments, 1, None) {
+        Ok(id) => id,
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to queue deferred chat: {}", e),
+        }.into_response(),
+    };
+
+    if let Err(e) = db.watch_job(job_id, device_id) {
+        eprintln!("Failed to register delivery for job {}: {}", job_id, e);
+    }
+
+    if db.get_message_count(conversation_id).unwrap_or(0) == 0
+        && !db.is_incognito(conversation_id).unwrap_or(false)
+    {
+        let _ = db.queue_title_generation(device_id as i64, conversation_id, message);
+    }
+
+    println!("Deferred chat queued as job {} in conversation {}", job_id, conversation_id);
+
+    (StatusCode::ACCEPTED, Json(DeferredChatResponse { job_id, conversation_id })).into_response()
+}
+
+/// POST /conversations/search
+pub async fn handle_search_conversations(
+    State(state): State<AppState>,
+    Json(req): Json<SearchConversationsRequest>,
+) -> Response {
+    if req.query.trim().is_empty() {
+        return ApiError::invalid("query", "Query cannot be empty").into_response();
+    }
+
+    let device_id = match authenticate(state.agent_pool.db(), req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let limit = req.limit.unwrap_or(20).clamp(1, 100);
+
+    match state.agent_pool.db().search_conversations(device_id, &req.query, limit) {
+        Ok(results) => Json(SearchConversationsResponse { results }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Search failed: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations
+///
+/// The device's own conversations, most recently used first.
+pub async fn handle_list_conversations(
+    State(state): State<AppState>,
+    Json(req): Json<ListConversationsRequest>,
+) -> Response {
+    let device_id = match authenticate(state.agent_pool.db(), req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let limit = req.limit.unwrap_or(20).clamp(1, 100);
+    let offset = req.offset.unwrap_or(0);
+
+    match state.agent_pool.db().list_conversations(device_id, limit, offset) {
+        Ok(conversations) => Json(ListConversationsResponse { conversations }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list conversations: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/messages
+///
+/// Every message in a conversation the device owns or was shared, in order.
+pub async fn handle_conversation_messages(
+    State(state): State<AppState>,
+    Json(req): Json<ConversationMessagesRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.get_stored_messages(req.conversation_id) {
+        Ok(messages) => Json(ConversationMessagesResponse {
+            conversation_id: req.conversation_id,
+            messages,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load messages: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/messages/delete
+///
+/// Delete a message's content. A tombstone keeps its place in the
+/// conversation; the model and search no longer see it.
+pub async fn handle_delete_message(
+    State(state): State<AppState>,
+    Json(req): Json<DeleteMessageRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "delete its messages") {
+        return e.into_response();
+    }
+
+    match db.delete_message(req.conversation_id, req.message_id) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => message_not_found(req.conversation_id, req.message_id),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to delete message: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/messages/redact
+///
+/// Scrub text (or a whole message) from the conversation and everything it
+/// was copied into, such as tool calls, artifacts and long-term memories.
+pub async fn handle_redact_message(
+    State(state): State<AppState>,
+    Json(req): Json<RedactMessageRequest>,
+) -> Response {
+    if let Err(e) = validate_redact_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "redact its messages") {
+        return e.into_response();
+    }
+
+    match db.redact_message(req.conversation_id, req.message_id, req.text.as_deref()) {
+        Ok(RedactOutcome::Redacted(redacted)) => Json(RedactMessageResponse { redacted }).into_response(),
+        Ok(RedactOutcome::MessageNotFound) => message_not_found(req.conversation_id, req.message_id),
+        Ok(RedactOutcome::TextNotFound) => ApiError::invalid("text", "The message does not contain this text").into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to redact message: {}", e),
+        }.into_response(),
+    }
+}
+
+fn message_not_found(conversation_id: u64, message_id: u64) -> Response {
+    ApiError::NotFound {
+        message: format!("Conversation {} has no message {}", conversation_id, message_id),
+        resource: "message".to_string(),
+    }.into_response()
+}
+
+/// POST /conversations/checkpoints
+///
+/// The checkpoints taken of a conversation, oldest first.
+pub async fn handle_list_checkpoints(
+    State(state): State<AppState>,
+    Json(req): Json<ConversationMessagesRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.list_checkpoints(req.conversation_id) {
+        Ok(checkpoints) => Json(ListCheckpointsResponse {
+            conversation_id: req.conversation_id,
+            checkpoints,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list checkpoints: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/checkpoints/create
+///
+/// Freeze the conversation's messages and scratchpad as they are now, to
+/// branch new conversations from later.
+pub async fn handle_create_checkpoint(
+    State(state): State<AppState>,
+    Json(req): Json<CreateCheckpointRequest>,
+) -> Response {
+    if let Err(e) = validate_checkpoint_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "checkpoint it") {
+        return e.into_response();
+    }
+
+    match db.create_checkpoint(req.conversation_id, req.name.as_deref().map(str::trim)) {
+        Ok(checkpoint_id) => Json(CreateCheckpointResponse { checkpoint_id }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to create checkpoint: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/checkpoints/branch
+///
+/// Start a new conversation, owned by the caller, from a checkpoint. The
+/// original carries on untouched.
+pub async fn handle_branch_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<BranchConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.branch_from_checkpoint(device_id, req.conversation_id, req.checkpoint_id) {
+        Ok(Some(conversation_id)) => Json(BranchConversationResponse { conversation_id }).into_response(),
+        Ok(None) => ApiError::NotFound {
+            message: format!("Conversation {} has no checkpoint {}", req.conversation_id, req.checkpoint_id),
+            resource: "checkpoint".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to branch conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/merge
+///
+/// Fold `source_id` into `conversation_id`, interleaving their exchanges by
+/// time, then delete `source_id`. Both must belong to the caller. The merged
+/// conversation is given a new title.
+pub async fn handle_merge_conversations(
+    State(state): State<AppState>,
+    Json(req): Json<MergeConversationsRequest>,
+) -> Response {
+    if req.conversation_id == req.source_id {
+        return ApiError::invalid("source_id", "Cannot merge a conversation into itself").into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "merge it") {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.source_id, "merge it") {
+        return e.into_response();
+    }
+
+    match db.merge_conversations(req.conversation_id, req.source_id) {
+        Ok(MergeOutcome::Done) => {
+            queue_retitle(db, device_id, req.conversation_id);
+            StatusCode::OK.into_response()
+        }
+        Ok(MergeOutcome::Mismatched) => ApiError::invalid(
+            "source_id",
+            "Only conversations that are both incognito or both kept, and locked with the same PIN or not at all, can be merged",
+        ).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to merge conversations: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/split
+///
+/// Move `message_id` and every message after it into a new conversation,
+/// which is given its own title.
+pub async fn handle_split_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<SplitConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "split it") {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.split_conversation(device_id, req.conversation_id, req.message_id) {
+        Ok(SplitOutcome::Done(conversation_id)) => {
+            queue_retitle(db, device_id, conversation_id);
+            Json(SplitConversationResponse { conversation_id }).into_response()
+        }
+        Ok(SplitOutcome::NoSuchMessage) => message_not_found(req.conversation_id, req.message_id),
+        Ok(SplitOutcome::FirstMessage) => ApiError::invalid(
+            "message_id",
+            "Splitting at the first message would move the whole conversation",
+        ).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to split conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+/// Have a conversation whose messages changed titled again from its opening
+/// user messages. Incognito conversations stay untitled, as in /chat.
+fn queue_retitle(db: &Db, device_id: u64, conversation_id: u64) {
+    if db.is_incognito(conversation_id).unwrap_or(false) {
+        return;
+    }
+    match db.title_source(conversation_id) {
+        Ok(Some(source)) => {
+            let _ = db.queue_title_generation(device_id as i64, conversation_id, &source);
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to read conversation {} for a title: {}", conversation_id, e),
+    }
+}
+
+/// POST /memories
+///
+/// The long-term memories saved in one of the device's profiles, or those
+/// most relevant to `query`.
+pub async fn handle_memories(
+    State(state): State<AppState>,
+    Json(req): Json<MemoriesRequest>,
+) -> Response {
+    let profile = match request_profile(req.profile.as_deref()) {
+        Ok(profile) => profile,
+        Err(e) => return e.into_response(),
+    };
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let store = MemoryStore::new(db.clone());
+    let limit = req.limit.unwrap_or(100).clamp(1, 500);
+    let memories = match req.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(query) => store.search(device_id, &profile, query, limit),
+        None => store.list(device_id, &profile).map(|mut all| {
+            all.truncate(limit);
+            all
+        }),
+    };
+
+    match memories {
+        Ok(memories) => Json(MemoriesResponse { profile, memories }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to read memories: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /memories/timeline
+///
+/// A profile's recent weeks: memories saved, the words the user used most
+/// and the conversations they were in.
+pub async fn handle_memory_timeline(
+    State(state): State<AppState>,
+    Json(req): Json<MemoryTimelineRequest>,
+) -> Response {
+    let profile = match request_profile(req.profile.as_deref()) {
+        Ok(profile) => profile,
+        Err(e) => return e.into_response(),
+    };
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let weeks = req.weeks.unwrap_or(DEFAULT_TIMELINE_WEEKS);
+    match MemoryStore::new(db.clone()).timeline(device_id, &profile, weeks) {
+        Ok(weeks) => Json(MemoryTimelineResponse { profile, weeks }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to build memory timeline: {}", e),
+        }.into_response(),
+    }
+}
+
+/// The profile a memory request names, normalized; `default` when it names none.
+fn request_profile(profile: Option<&str>) -> Result<String, ApiError> {
+    match profile {
+        None => Ok(DEFAULT_PROFILE.to_string()),
+        Some(profile) => normalize_profile(profile).ok_or_else(|| ApiError::invalid(
+            "profile",
+            format!("Profile must be up to {} letters, digits, '-' or '_'", MAX_PROFILE_CHARS),
+        )),
+    }
+}
+
+/// POST /conversations/state
+pub async fn handle_conversation_state(
+    State(state): State<AppState>,
+    Json(req): Json<ConversationStateRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    // Idle conversations aren't tracked; report them as the empty state
+    Json(state.runtime.get(req.conversation_id).unwrap_or_default()).into_response()
+}
+
+/// POST /conversations/privacy
+pub async fn handle_conversation_privacy(
+    State(state): State<AppState>,
+    Json(req): Json<ConversationPrivacyRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.get_privacy_level(req.conversation_id) {
+        Ok(level) => Json(ConversationPrivacyResponse { conversation_id: req.conversation_id, level }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to read privacy level: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/privacy/set
+///
+/// Change how much of a conversation may be saved as memories. Memories
+/// already saved from it are kept.
+pub async fn handle_set_conversation_privacy(
+    State(state): State<AppState>,
+    Json(req): Json<SetConversationPrivacyRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let Some(level) = PrivacyLevel::parse(&req.level) else {
+        return ApiError::invalid("level", "Privacy must be 'normal', 'facts-only' or 'no-extraction'")
+            .into_response();
+    };
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "change its privacy") {
+        return e.into_response();
+    }
+
+    match db.set_privacy_level(req.conversation_id, level) {
+        Ok(_) => Json(ConversationPrivacyResponse { conversation_id: req.conversation_id, level }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to set privacy level: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/tools
+pub async fn handle_conversation_tools(
+    State(state): State<AppState>,
+    Json(req): Json<ConversationToolsRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.conversation_toolbelts(req.conversation_id) {
+        Ok(toolbelts) => Json(ConversationToolsResponse { conversation_id: req.conversation_id, toolbelts }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to read toolbelts: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/tools/set
+///
+/// Turn a toolbelt on or off for one conversation, over the defaults of the
+/// agents answering in it. Applies from the next turn.
+pub async fn handle_set_conversation_tools(
+    State(state): State<AppState>,
+    Json(req): Json<SetConversationToolsRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let Some(toolbelt) = toolbelt_name(&req.toolbelt) else {
+        return ApiError::invalid("toolbelt", format!("Unknown toolbelt '{}'", req.toolbelt.trim()))
+            .into_response();
+    };
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "change its tools") {
+        return e.into_response();
+    }
+
+    let toolbelts = db.set_conversation_toolbelt(req.conversation_id, &toolbelt, req.enabled)
+        .and_then(|()| db.conversation_toolbelts(req.conversation_id));
+    match toolbelts {
+        Ok(toolbelts) => Json(ConversationToolsResponse { conversation_id: req.conversation_id, toolbelts }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to set toolbelts: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/read
+///
+/// Mark a conversation read on the calling device, up to a message or all
+/// of it. Other devices keep their own markers.
+pub async fn handle_mark_read(
+    State(state): State<AppState>,
+    Json(req): Json<MarkReadRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    let unread = db.mark_conversation_read(req.conversation_id, device_id, req.message_id)
+        .and_then(|()| db.unread_count(req.conversation_id, device_id));
+    match unread {
+        Ok(unread) => Json(MarkReadResponse { conversation_id: req.conversation_id, unread }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to mark conversation read: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/unread
+///
+/// The device's conversations with answers it hasn't read, e.g. deferred
+/// chats and research jobs that finished while it was away.
+pub async fn handle_unread(
+    State(state): State<AppState>,
+    Json(req): Json<UnreadRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.unread_conversations(device_id) {
+        Ok(conversations) => {
+            let total = conversations.iter().map(|c| c.unread).sum();
+            Json(UnreadResponse { total, conversations }).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to count unread answers: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/share
+pub async fn handle_share_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<ShareConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "manage sharing") {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if req.target_device_id == device_id {
+        return ApiError::invalid("target_device_id", "Cannot share a conversation with its owner")
+            .into_response();
+    }
+
+    let target_exists = db.query_row_optional(
+        "SELECT 1 FROM devices WHERE id = ?1 AND active = 1",
+        rusqlite::params![req.target_device_id as i64],
+        |_| Ok(()),
+    );
+    match target_exists {
+        Ok(Some(())) => {}
+        Ok(None) => return ApiError::NotFound {
+            message: format!("Device {} not found", req.target_device_id),
+            resource: "device".to_string(),
+        }.into_response(),
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to look up device: {}", e),
+        }.into_response(),
+    }
+
+    match db.share_conversation(req.conversation_id, req.target_device_id) {
+        Ok(()) => {
+            println!("Conversation {} shared with device {}", req.conversation_id, req.target_device_id);
+            StatusCode::OK.into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to share conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/unshare
+pub async fn handle_unshare_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<ShareConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "manage sharing") {
+        return e.into_response();
+    }
+
+    match db.unshare_conversation(req.conversation_id, req.target_device_id) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => ApiError::NotFound {
+            message: format!(
+                "Conversation {} is not shared with device {}",
+                req.conversation_id, req.target_device_id
+            ),
+            resource: "share".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to unshare conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/lock
+///
+/// Mark a conversation sensitive, locked with a PIN. Relocking an unlocked
+/// conversation needs the same PIN.
+pub async fn handle_lock_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<LockConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    if let Err(e) = validate_lock_request(&req) {
+        return e.into_response();
+    }
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "lock it") {
+        return e.into_response();
+    }
+
+    match db.lock_conversation(req.conversation_id, &req.pin) {
+        Ok(LockOutcome::WrongPin) => wrong_pin(),
+        Ok(LockOutcome::Throttled { retry_in }) => pin_throttled(retry_in),
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to lock conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/unlock
+///
+/// Unlock a sensitive conversation for a while, or for good with `remove`.
+pub async fn handle_unlock_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<LockConversationRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    if let Err(e) = validate_lock_request(&req) {
+        return e.into_response();
+    }
+    if let Err(e) = authorize_conversation_owner(db, req.device_id, &req.device_key, req.conversation_id, "unlock it") {
+        return e.into_response();
+    }
+
+    match db.unlock_conversation(req.conversation_id, &req.pin, req.remove) {
+        Ok(LockOutcome::Done) => StatusCode::OK.into_response(),
+        Ok(LockOutcome::WrongPin) => wrong_pin(),
+        Ok(LockOutcome::Throttled { retry_in }) => pin_throttled(retry_in),
+        Ok(LockOutcome::NotLocked) => ApiError::NotFound {
+            message: format!("Conversation {} is not locked", req.conversation_id),
+            resource: "lock".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to unlock conversation: {}", e),
+        }.into_response(),
+    }
+}
+
+fn wrong_pin() -> Response {
+    ApiError::Forbidden {
+        message: "Wrong PIN".to_string(),
+    }.into_response()
+}
+
+fn pin_throttled(retry_in: i64) -> Response {
+    ApiError::Forbidden {
+        message: format!("Too many wrong PINs; try again in {} seconds", retry_in),
+    }.into_response()
+}
+
+/// POST /chat/continue
+///
+/// Answer a question a specialist asked on a /chat stream. The specialist
+/// resumes with the answer and the original stream carries on.
+pub async fn handle_continue_chat(
+    State(state): State<AppState>,
+    Json(req): Json<ContinueChatRequest>,
+) -> Response {
+    if let Err(e) = validate_continue_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match state.runtime.answer_question(req.conversation_id, req.question_id, req.answer.trim()) {
+        Some(question) => Json(ContinueChatResponse {
+            question_id: question.id,
+            task_id: question.task_id,
+        }).into_response(),
+        None => ApiError::NotFound {
+            message: match req.question_id {
+                Some(id) => format!("No open question {} in conversation {}", id, req.conversation_id),
+                None => format!("Conversation {} has no open questions", req.conversation_id),
+            },
+            resource: "question".to_string(),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/feedback
+pub async fn handle_conversation_feedback(
+    State(state): State<AppState>,
+    Json(req): Json<FeedbackRequest>,
+) -> Response {
+    if let Err(e) = validate_feedback_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    match db.record_experiment_feedback(req.conversation_id, req.score) {
+        Ok(true) => StatusCode::OK.into_response(),
+        // Nothing to rate: no run in this conversation was part of an experiment
+        Ok(false) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to record feedback: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /conversations/tags
+///
+/// Add and remove the device's tags on a conversation; returns the tags it now
+/// has. With nothing to add or remove, just returns them.
+pub async fn handle_tag_conversation(
+    State(state): State<AppState>,
+    Json(req): Json<TagConversationRequest>,
+) -> Response {
+    if let Err(e) = validate_tag_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = validate_conversation_access(db, device_id, Some(req.conversation_id)) {
+        return e.into_response();
+    }
+
+    let result = (|| {
+        for tag in &req.add {
+            db.add_conversation_tag(device_id, req.conversation_id, tag)?;
+        }
+        for tag in &req.remove {
+            db.remove_conversation_tag(device_id, req.conversation_id, tag)?;
+        }
+        db.conversation_tags(device_id, req.conversation_id)
+    })();
+
+    match result {
+        Ok(tags) => Json(ConversationTagsResponse {
+            conversation_id: req.conversation_id,
+            tags,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to update tags: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /tags
+pub async fn handle_list_tags(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.list_tags(device_id) {
+        Ok(tags) => Json(ListTagsResponse { tags }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list tags: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /tags/conversations
+pub async fn handle_tagged_conversations(
+    State(state): State<AppState>,
+    Json(req): Json<TaggedConversationsRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.conversations_with_tag(device_id, &req.tag) {
+        Ok(conversations) => Json(TaggedConversationsResponse { conversations }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to search tags: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /jobs
+pub async fn handle_queue_job(
+    State(state): State<AppState>,
+    Json(req): Json<QueueJobRequest>,
+) -> Response {
+    match queue_job(state.agent_pool.db(), req) {
+        Ok(queued) => Json(queued).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Validate, authenticate and queue a background job. Shared by every
+/// transport.
+pub fn queue_job(db: &Db, req: QueueJobRequest) -> Result<QueueJobResponse, ApiError> {
+    validate_job_request(&req)?;
+
+    let device_id = authenticate(db, req.device_id, &req.device_key)?;
+
+    let mut arguments = req.arguments.unwrap_or_else(|| serde_json::json!({}));
+    let mut conversation_id = req.conversation_id
+        .or_else(|| arguments["conversation_id"].as_u64());
+
+    validate_conversation_access(db, device_id, conversation_id)?;
+
+    // Agent jobs write their messages somewhere the device can read them later
+    if req.method == "agent" && conversation_id.is_none() {
+        let id = db.create_conversation(device_id).map_err(|e| ApiError::Internal {
+            message: format!("Failed to create conversation: {}", e),
+        })?;
+        conversation_id = Some(id);
+    }
+
+    if let Some(id) = conversation_id {
+        arguments["conversation_id"] = serde_json::json!(id);
+    }
+    if let Some(hint) = &req.latency_hint {
+        arguments["latency_hint"] = serde_json::json!(hint);
+    }
+
+    let run_at = start_time(req.run_at, req.delay_secs);
+
+    let job_id = db.create_job(device_id as i64, &req.method, &arguments, req.priority.unwrap_or(1), run_at)
+        .map_err(|e| ApiError::Internal {
+            message: format!("Failed to queue job: {}", e),
+        })?;
+    println!("Queued '{}' job {} for device {}", req.method, job_id, device_id);
+    Ok(QueueJobResponse { job_id, conversation_id })
+}
+
+/// When a job given `run_at` or `delay_secs` starts. A delay counts from
+/// now; `run_at` is already a time.
+fn start_time(run_at: Option<i64>, delay_secs: Option<u64>) -> Option<i64> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    run_at.or_else(|| delay_secs.map(|delay| now + delay as i64))
+}
+
+/// POST /workflows
+///
+/// The device's saved workflows, by name, with the parameters each takes.
+pub async fn handle_workflows(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.list_workflows(device_id) {
+        Ok(workflows) => Json(WorkflowsResponse {
+            workflows: workflows.into_iter()
+                .map(|workflow| WorkflowInfo { parameters: workflow.parameters(), workflow })
+                .collect(),
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list workflows: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /workflows/set
+///
+/// Save a workflow, replacing any of the same name.
+pub async fn handle_save_workflow(
+    State(state): State<AppState>,
+    Json(req): Json<SaveWorkflowRequest>,
+) -> Response {
+    if let Err(e) = validate_workflow_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.save_workflow(device_id, &req.name, &req.template, &req.defaults) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to save workflow: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /workflows/delete
+pub async fn handle_delete_workflow(
+    State(state): State<AppState>,
+    Json(req): Json<WorkflowRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.delete_workflow(device_id, &req.name) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => workflow_not_found(&req.name),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to delete workflow: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /workflows/run
+///
+/// Fill in a workflow's parameters and queue it for the Orchestrator in a new
+/// conversation named after it, now or at a scheduled time. The answer is
+/// delivered to the job inbox like a deferred chat.
+pub async fn handle_run_workflow(
+    State(state): State<AppState>,
+    Json(req): Json<RunWorkflowRequest>,
+) -> Response {
+    if let Err(e) = validate_run_workflow_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let workflow = match db.get_workflow(device_id, &req.name) {
+        Ok(Some(workflow)) => workflow,
+        Ok(None) => return workflow_not_found(&req.name),
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to load workflow: {}", e),
+        }.into_response(),
+    };
+
+    let goal = match workflow.render(&req.arguments) {
+        Ok(goal) => goal,
+        Err(missing) => return ApiError::invalid(
+            "arguments",
+            format!("Workflow '{}' needs values for: {}", workflow.name, missing.join(", ")),
+        ).into_response(),
+    };
+
+    let conversation_id = match db.create_conversation(device_id) {
+        Ok(id) => id,
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to create conversation: {}", e),
+        }.into_response(),
+    };
+    let _ = db.set_conversation_title(conversation_id, device_id as i64, &workflow.name);
+
+    let arguments = serde_json::json!({
+        "conversation_id": conversation_id,
+        "agent": "Orchestrator",
+        "goal": goal,
+    });
+    let run_at = start_time(req.run_at, req.delay_secs);
+
+    match db.create_job(device_id as i64, "agent", &arguments, 1, run_at) {
+        Ok(job_id) => {
+            if let Err(e) = db.watch_job(job_id, device_id) {
+                eprintln!("Failed to register delivery for job {}: {}", job_id, e);
+            }
+            println!("Queued workflow '{}' as job {} for device {}", workflow.name, job_id, device_id);
+            Json(QueueJobResponse { job_id, conversation_id: Some(conversation_id) }).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to queue workflow: {}", e),
+        }.into_response(),
+    }
+}
+
+fn workflow_not_found(name: &str) -> Response {
+    ApiError::NotFound {
+        message: format!("No workflow named '{}'", name),
+        resource: "workflow".to_string(),
+    }.into_response()
+}
+
+/// POST /watches
+///
+/// The paths the device watches, oldest first. Envoy polls this to know
+/// what to watch.
+pub async fn handle_watches(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.list_watches(device_id) {
+        Ok(watches) => Json(WatchesResponse { watches }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list watches: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /watches/set
+///
+/// Watch a path on the device and queue a task when files under it change.
+pub async fn handle_add_watch(
+    State(state): State<AppState>,
+    Json(req): Json<AddWatchRequest>,
+) -> Response {
+    if let Err(e) = validate_watch_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let pattern = req.pattern.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    match db.add_watch(device_id, req.path.trim(), req.task.trim(), pattern) {
+        Ok(watch_id) => Json(AddWatchResponse { watch_id }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to add watch: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /watches/delete
+pub async fn handle_delete_watch(
+    State(state): State<AppState>,
+    Json(req): Json<WatchRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.delete_watch(device_id, req.watch_id) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => watch_not_found(req.watch_id),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to delete watch: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /watches/trigger
+///
+/// Envoy saw files change under a watched path: queue the watch's task for
+/// the Orchestrator, with the changed files, in a new conversation titled
+/// after the path. The answer is delivered to the job inbox.
+pub async fn handle_trigger_watch(
+    State(state): State<AppState>,
+    Json(req): Json<TriggerWatchRequest>,
+) -> Response {
+    if let Err(e) = validate_trigger_watch_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let watch = match db.get_watch(device_id, req.watch_id) {
+        Ok(Some(watch)) => watch,
+        Ok(None) => return watch_not_found(req.watch_id),
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to load watch: {}", e),
+        }.into_response(),
+    };
+
+    let changed: Vec<String> = req.paths.iter().map(|path| format!("- {}", path)).collect();
+    let goal = format!(
+        "{}\n\nThese files under {} on my machine were added or changed:\n{}",
+        watch.task, watch.path, changed.join("\n")
+    );
+
+    match db.queue_watch_task(device_id, &watch, &goal) {
+        Ok((job_id, conversation_id)) => {
+            println!("Queued watch {} as job {} for device {}", watch.id, job_id, device_id);
+            Json(QueueJobResponse { job_id, conversation_id: Some(conversation_id) }).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to queue watch task: {}", e),
+        }.into_response(),
+    }
+}
+
+fn watch_not_found(watch_id: u64) -> Response {
+    ApiError::NotFound {
+        message: format!("Watch {} not found", watch_id),
+        resource: "watch".to_string(),
+    }.into_response()
+}
+
+/// POST /jobs/status
+pub async fn handle_job_status(
+    State(state): State<AppState>,
+    Json(req): Json<JobStatusRequest>,
+) -> Response {
+    match job_status(state.agent_pool.db(), &req) {
+        Ok(job) => Json(job).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// A job of the requesting device. Shared by every transport.
+pub fn job_status(db: &Db, req: &JobStatusRequest) -> Result<BackgroundJob, ApiError> {
+    let device_id = authenticate(db, req.device_id, &req.device_key)?;
+
+    match db.get_job(req.job_id) {
+        Ok(Some(job)) if job.device_id == Some(device_id) => Ok(job),
+        Ok(Some(_)) => Err(ApiError::Forbidden {
+            message: format!("Job {} belongs to another device", req.job_id),
+        }),
+        Ok(None) => Err(ApiError::NotFound {
+            message: format!("Job {} not found", req.job_id),
+            resource: "job".to_string(),
+        }),
+        Err(e) => Err(ApiError::Internal {
+            message: format!("Failed to look up job: {}", e),
+        }),
+    }
+}
+
+/// POST /jobs/inbox
+pub async fn handle_job_inbox(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    match job_inbox(state.agent_pool.db(), &req) {
+        Ok(jobs) => Json(JobInboxResponse { jobs }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// The device's finished jobs not collected yet, which are marked collected.
+/// Shared by every transport.
+pub fn job_inbox(db: &Db, req: &DeviceRequest) -> Result<Vec<BackgroundJob>, ApiError> {
+    let device_id = authenticate(db, req.device_id, &req.device_key)?;
+    db.take_finished_jobs(device_id).map_err(|e| ApiError::Internal {
+        message: format!("Failed to read job inbox: {}", e),
+    })
+}
+
+/// POST /usage
+///
+/// The device's calls to metered providers such as Brave Search, with the
+/// limits in force, so a quota running low is visible before it runs out.
+pub async fn handle_usage(
+    State(state): State<AppState>,
+    Json(req): Json<UsageRequest>,
+) -> Response {
+    if let Err(e) = validate_usage_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let history = match db.get_tool_usage(device_id, req.days.unwrap_or(30)) {
+        Ok(history) => history,
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to read usage: {}", e),
+        }.into_response(),
+    };
+
+    let mut providers = Vec::new();
+    for quota in state.quotas.iter() {
+        let counts = db.get_daily_usage(device_id, quota.provider)
+            .and_then(|today| Ok((today, db.get_monthly_usage(quota.provider)?)));
+        let (today, this_month) = match counts {
+            Ok(counts) => counts,
+            Err(e) => return ApiError::Internal {
+                message: format!("Failed to read usage: {}", e),
+            }.into_response(),
+        };
+        providers.push(ProviderUsage {
+            provider: quota.provider.to_string(),
+            today,
+            daily_limit: quota.daily_per_device,
+            this_month,
+            monthly_limit: quota.monthly,
+        });
+    }
+
+    Json(UsageResponse { providers, history }).into_response()
+}
+
+/// POST /jobs/events
+pub async fn handle_job_events(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let device_id = match authenticate(state.agent_pool.db(), req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    job_event_stream(&state, device_id)
+}
+
+/// POST /devices/{id}/events
+///
+/// The device's notification stream: what its background jobs do, as they
+/// happen. The path must name the device the key belongs to.
+pub async fn handle_device_events(
+    State(state): State<AppState>,
+    Path(path_device_id): Path<i64>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let device_id = match authenticate(state.agent_pool.db(), req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    if path_device_id != req.device_id {
+        return ApiError::Forbidden {
+            message: format!("Device {} cannot read the events of device {}", req.device_id, path_device_id),
+        }.into_response();
+    }
+
+    job_event_stream(&state, device_id)
+}
 
-        if message_count <= 2 {
-            let _ = agent_pool.db().queue_title_generation(
-                device_id as i64,
-                conversation_id,
-                &req.message,
-            );
+/// SSE stream of the job notices meant for `device_id`.
+fn job_event_stream(state: &AppState, device_id: u64) -> Response {
+    let notices = tokio_stream::wrappers::BroadcastStream::new(state.job_events.subscribe());
+    let stream = notices.filter_map(move |notice| async move {
+        // Lagged receivers just skip the events they missed
+        let notice = notice.ok()?;
+        if notice.device_id != Some(device_id) {
+            return None;
         }
-
-        events.done(conversation_id);
+        let data = serde_json::to_value(&notice.event).ok()?;
+        let event_type = data["type"].as_str()?.to_string();
+        Some(SseEvent::new(&event_type, data.to_string()).to_sse())
     });
 
-    let stream = ReceiverStream::new(rx).map(|event| event.to_sse());
-    Sse::new(stream).into_response()
+    Sse::new(stream).keep_alive(heartbeat()).into_response()
 }
 
 /// POST /devices/register
 pub async fn handle_register_device(
-    Extension(state): Extension<AppState>,
+    State(state): State<AppState>,
     Json(req): Json<RegisterDeviceRequest>,
 ) -> Response {
+    match register_device(state.agent_pool.db(), &req.device_name) {
+        Ok(registered) => Json(registered).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Register a device under `device_name`, or give an existing one a new key.
+/// Shared by every transport.
+pub fn register_device(db: &Db, device_name: &str) -> Result<RegisterDeviceResponse, ApiError> {
     let device_key = uuid::Uuid::new_v4().to_string();
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_secs() as i64;
 
-    let conn = match state.agent_pool.db().lock() {
-        Ok(c) => c,
-        Err(e) => return ApiError::InternalError {
-            message: format!("Database unavailable: {}", e),
-        }.to_response(),
-    };
+    let conn = db.lock().map_err(|e| ApiError::Internal {
+        message: format!("Database unavailable: {}", e),
+    })?;
 
-    let result = conn.execute(
+    conn.execute(
         "INSERT INTO devices (device_name, device_key, active, created, last_seen)
          VALUES (?1, ?2, 1, ?3, ?4)
          ON CONFLICT(device_name) DO UPDATE SET
            device_key = excluded.device_key,
            active = 1,
            last_seen = excluded.last_seen",
-        rusqlite::params![req.device_name, device_key, now, now],
-    );
-
-    if let Err(e) = result {
-        return ApiError::InternalError {
-            message: format!("Failed to register device: {}", e),
-        }.to_response();
-    }
+        rusqlite::params![device_name, device_key, now, now],
+    ).map_err(|e| ApiError::Internal {
+        message: format!("Failed to register device: {}", e),
+    })?;
 
-    let device_id: i64 = match conn.query_row(
+    let device_id: i64 = conn.query_row(
         "SELECT id FROM devices WHERE device_name = ?1",
-        rusqlite::params![req.device_name],
+        rusqlite::params![device_name],
         |row| row.get(0),
-    ) {
-        Ok(id) => id,
-        Err(e) => return ApiError::InternalError {
-            message: format!("Failed to retrieve device id: {}", e),
-        }.to_response(),
-    };
+    ).map_err(|e| ApiError::Internal {
+        message: format!("Failed to retrieve device id: {}", e),
+    })?;
 
-    println!("Device registered: '{}' (id={})", req.device_name, device_id);
+    println!("Device registered: '{}' (id={})", device_name, device_id);
 
-    Json(RegisterDeviceResponse {
+    Ok(RegisterDeviceResponse {
         device_id,
         device_key,
-    }).into_response()
+    })
 }
 
 /// POST /devices/verify
 pub async fn handle_verify_device(
-    Extension(state): Extension<AppState>,
+    State(state): State<AppState>,
     Json(body): Json<serde_json::Value>,
 ) -> Response {
     let device_id = match body["device_id"].as_i64() {
         Some(id) => id,
-        None => return ApiError::InvalidRequest {
-            message: "Missing device_id".to_string(),
-            field: Some("device_id".to_string()),
-        }.to_response(),
+        None => return ApiError::invalid("device_id", "Missing device_id").into_response(),
     };
 
     let device_key = match body["device_key"].as_str() {
         Some(k) => k.to_string(),
-        None => return ApiError::InvalidRequest {
-            message: "Missing device_key".to_string(),
-            field: Some("device_key".to_string()),
-        }.to_response(),
+        None => return ApiError::invalid("device_key", "Missing device_key").into_response(),
     };
 
-    let conn = match state.agent_pool.db().lock() {
-        Ok(c) => c,
-        Err(e) => return ApiError::InternalError {
-            message: format!("Database unavailable: {}", e),
-        }.to_response(),
-    };
+    match verify_device(state.agent_pool.db(), device_id, &device_key) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Check a device's credentials and note that it was seen. Shared by every
+/// transport.
+pub fn verify_device(db: &Db, device_id: i64, device_key: &str) -> Result<(), ApiError> {
+    let conn = db.lock().map_err(|e| ApiError::Internal {
+        message: format!("Database unavailable: {}", e),
+    })?;
 
     let valid = conn.query_row(
         "SELECT 1 FROM devices WHERE id = ?1 AND device_key = ?2 AND active = 1",
@@ -274,9 +1832,9 @@ pub async fn handle_verify_device(
     ).unwrap_or(false);
 
     if !valid {
-        return ApiError::Authentication {
+        return Err(ApiError::Unauthorized {
             message: "Invalid or inactive device credentials".to_string(),
-        }.to_response();
+        });
     }
 
     let now = std::time::SystemTime::now()
@@ -288,29 +1846,452 @@ pub async fn handle_verify_device(
         rusqlite::params![now, device_id],
     );
 
-    StatusCode::OK.into_response()
+    Ok(())
+}
+
+/// POST /devices/language
+pub async fn handle_device_language(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceLanguageRequest>,
+) -> Response {
+    if let Err(e) = validate_language_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.set_device_language(device_id, req.language.as_deref()) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to set language: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /devices/settings
+///
+/// The device's answer preferences: language and response style.
+pub async fn handle_device_settings(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    device_settings_response(db, device_id)
+}
+
+/// POST /devices/settings/set
+///
+/// Change the given preferences and leave the rest, answering with all of
+/// them as they now stand.
+pub async fn handle_set_device_settings(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceSettingsRequest>,
+) -> Response {
+    if let Err(e) = validate_settings_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Some(language) = &req.language {
+        let language = Some(language.as_str()).filter(|l| *l != "auto");
+        if let Err(e) = db.set_device_language(device_id, language) {
+            return ApiError::Internal {
+                message: format!("Failed to set language: {}", e),
+            }.into_response();
+        }
+    }
+
+    if req.length.is_some() || req.code_first.is_some() {
+        let mut style = match db.get_response_style(device_id) {
+            Ok(style) => style,
+            Err(e) => return ApiError::Internal {
+                message: format!("Failed to load response style: {}", e),
+            }.into_response(),
+        };
+        if let Some(length) = &req.length {
+            style.length = ResponseLength::parse(length);
+        }
+        if let Some(code_first) = req.code_first {
+            style.code_first = code_first;
+        }
+        if let Err(e) = db.set_response_style(device_id, &style) {
+            return ApiError::Internal {
+                message: format!("Failed to set response style: {}", e),
+            }.into_response();
+        }
+    }
+
+    device_settings_response(db, device_id)
+}
+
+fn device_settings_response(db: &Db, device_id: u64) -> Response {
+    let settings = db.get_device_language(device_id)
+        .and_then(|language| Ok((language, db.get_response_style(device_id)?)));
+    match settings {
+        Ok((language, style)) => Json(DeviceSettingsResponse {
+            language,
+            length: style.length,
+            code_first: style.code_first,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load settings: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /devices/purge
+///
+/// Delete all of the device's conversations, memories, tags, attachments,
+/// jobs and audit rows in one transaction, answering with how many of each
+/// went. With dry_run, only counts. The device stays registered.
+pub async fn handle_purge_device(
+    State(state): State<AppState>,
+    Json(req): Json<PurgeDeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.purge_device(device_id, req.dry_run) {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to purge device: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /devices/metadata
+///
+/// Clients report their timezone and locale at startup; omitted fields keep
+/// their stored value.
+pub async fn handle_device_metadata(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceMetadataRequest>,
+) -> Response {
+    if let Err(e) = validate_metadata_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut changes = serde_json::Map::new();
+    if let Some(timezone) = req.timezone {
+        changes.insert("timezone".to_string(), timezone.into());
+    }
+    if let Some(locale) = req.locale {
+        changes.insert("locale".to_string(), locale.into());
+    }
+    if let Some(tool_host) = req.tool_host {
+        let value = if tool_host.is_empty() { serde_json::Value::Null } else { tool_host.into() };
+        changes.insert("tool_host".to_string(), value);
+    }
+
+    match db.update_device_metadata(device_id, &changes) {
+        Ok(metadata) => Json(serde_json::Value::Object(metadata)).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to update device metadata: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /devices/link
+///
+/// Make another device one of the caller's own, so it can be grouped and
+/// targeted by tool calls. Its credentials prove the caller controls it.
+pub async fn handle_link_device(
+    State(state): State<AppState>,
+    Json(req): Json<LinkDeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+    let target_id = match authenticate(db, req.target_device_id, &req.target_device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = db.link_device(device_id, target_id) {
+        return ApiError::Internal {
+            message: format!("Failed to link device: {}", e),
+        }.into_response();
+    }
+    device_groups_response(db, device_id)
+}
+
+/// POST /devices/groups
+pub async fn handle_device_groups(
+    State(state): State<AppState>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    device_groups_response(db, device_id)
+}
+
+/// POST /devices/groups/set
+///
+/// Create or replace a named group of the caller's devices.
+pub async fn handle_set_device_group(
+    State(state): State<AppState>,
+    Json(req): Json<SetDeviceGroupRequest>,
+) -> Response {
+    if let Err(e) = validate_device_group_request(&req) {
+        return e.into_response();
+    }
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let owned = match db.list_owned_devices(device_id) {
+        Ok(owned) => owned,
+        Err(e) => return ApiError::Internal {
+            message: format!("Failed to list devices: {}", e),
+        }.into_response(),
+    };
+    let foreign = req.devices.iter().find(|name| {
+        let name = name.trim();
+        !owned.iter().any(|d| d.device_name.eq_ignore_ascii_case(name) || d.device_id.to_string() == name)
+    });
+    if let Some(name) = foreign {
+        return ApiError::Forbidden {
+            message: format!("'{}' is not one of your devices", name),
+        }.into_response();
+    }
+
+    match db.set_device_group(device_id, &req.name, &req.devices) {
+        Ok(group) => Json(group).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to save device group: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /devices/groups/delete
+pub async fn handle_delete_device_group(
+    State(state): State<AppState>,
+    Json(req): Json<DeleteDeviceGroupRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.delete_device_group(device_id, &req.name) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => ApiError::NotFound {
+            message: format!("No device group '{}'", req.name),
+            resource: "device_group".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to delete device group: {}", e),
+        }.into_response(),
+    }
+}
+
+fn device_groups_response(db: &Db, device_id: u64) -> Response {
+    let result = db.list_owned_devices(device_id)
+        .and_then(|devices| Ok(DeviceGroupsResponse { devices, groups: db.list_device_groups(device_id)? }));
+    match result {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to list device groups: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /attachments/upload
+pub async fn handle_upload_attachment(
+    State(state): State<AppState>,
+    Json(req): Json<UploadAttachmentRequest>,
+) -> Response {
+    let data = match validate_attachment_upload(&req) {
+        Ok(data) => data,
+        Err(e) => return e.into_response(),
+    };
+
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.store_attachment(device_id, req.filename.trim(), &req.mime_type, &data) {
+        Ok(attachment_id) => Json(UploadAttachmentResponse {
+            attachment_id,
+            size: data.len() as u64,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to store attachment: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /attachments/get
+pub async fn handle_get_attachment(
+    State(state): State<AppState>,
+    Json(req): Json<AttachmentRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let found = db.get_attachment(req.attachment_id, device_id).and_then(|attachment| {
+        Ok(attachment.zip(db.get_attachment_data(req.attachment_id, device_id)?))
+    });
+
+    match found {
+        Ok(Some((attachment, data))) => Json(AttachmentResponse {
+            attachment,
+            data: BASE64.encode(data),
+        }).into_response(),
+        Ok(None) => ApiError::NotFound {
+            message: format!("Attachment {} not found", req.attachment_id),
+            resource: "attachment".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load attachment: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /debug/requests/{id}
+///
+/// The prompts saved for a `/chat` request made with `debug`, where `id` is
+/// the request's task id.
+pub async fn handle_request_snapshots(
+    State(state): State<AppState>,
+    Path(request_id): Path<u64>,
+    Json(req): Json<DeviceRequest>,
+) -> Response {
+    let db = state.agent_pool.db();
+
+    let device_id = match authenticate(db, req.device_id, &req.device_key) {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    match db.request_prompt_snapshots(request_id, device_id) {
+        Ok(snapshots) if snapshots.is_empty() => ApiError::NotFound {
+            message: format!("No prompts were saved for request {}", request_id),
+            resource: "request".to_string(),
+        }.into_response(),
+        Ok(snapshots) => Json(RequestSnapshotsResponse { request_id, snapshots }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load prompt snapshots: {}", e),
+        }.into_response(),
+    }
 }
 
 /// GET /status
 pub async fn handle_status(
-    Extension(state): Extension<AppState>,
+    State(state): State<AppState>,
 ) -> impl IntoResponse {
     let gpu_status = state.gpu_pool.status();
+    // Degraded: no model server answers, so only browsing works
+    let status = if state.gpu_pool.is_degraded() { "degraded" } else { "ok" };
     Json(serde_json::json!({
-        "status": "ok",
+        "status": status,
         "gpus": gpu_status,
+        "hosts": state.gpu_pool.hosts(),
     }))
 }
 
+/// GET /tools
+///
+/// Every toolbelt with its tools' argument schemas and example calls, for
+/// people writing against the tools rather than for the models.
+pub async fn handle_tools() -> Json<ToolsResponse> {
+    let toolbelts = toolbelts().into_iter().map(|toolbelt| {
+        let prefix = format!("{}::", toolbelt.name);
+        ToolbeltDoc {
+            name: toolbelt.name,
+            description: toolbelt.description,
+            location: toolbelt.location.clone(),
+            risk: toolbelt.risk,
+            tools: get_tool_schemas_for(&[prefix.as_str()]).into_iter().map(|schema| ToolDoc {
+                name: schema.name,
+                description: schema.description,
+                parameters: schema.to_tool().function.parameters,
+                example: tool_example(schema.name).cloned(),
+            }).collect(),
+        }
+    }).collect();
+    Json(ToolsResponse { toolbelts })
+}
+
+/// GET /health/providers
+///
+/// What each metered provider last said about its API key and remaining
+/// quota, from the checks run at startup and every few hours, and from the
+/// searches themselves.
+pub async fn handle_provider_health(
+    State(state): State<AppState>,
+) -> Response {
+    match state.agent_pool.db().provider_health() {
+        Ok(providers) => Json(ProviderHealthResponse {
+            providers: providers.into_iter().map(|health| ProviderStatus {
+                exhausted: health.exhausted(),
+                health,
+            }).collect(),
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to read provider health: {}", e),
+        }.into_response(),
+    }
+}
+
 /// GET /background/status
 pub async fn handle_background_status(
-    Extension(state): Extension<AppState>,
+    State(state): State<AppState>,
 ) -> Response {
     let conn = match state.agent_pool.db().lock() {
         Ok(c) => c,
-        Err(e) => return ApiError::InternalError {
+        Err(e) => return ApiError::Internal {
             message: format!("Database unavailable: {}", e),
-        }.to_response(),
+        }.into_response(),
     };
 
     let pending: i64 = conn.query_row(
@@ -319,6 +2300,14 @@ pub async fn handle_background_status(
         |row| row.get(0),
     ).unwrap_or(0);
 
+    // Pending jobs delayed until later
+    let scheduled: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM background
+         WHERE status = 'pending' AND run_at > CAST(strftime('%s', 'now') AS INTEGER)",
+        [],
+        |row| row.get(0),
+    ).unwrap_or(0);
+
     let running: i64 = conn.query_row(
         "SELECT COUNT(*) FROM background WHERE status = 'running'",
         [],
@@ -337,41 +2326,311 @@ pub async fn handle_background_status(
         |row| row.get(0),
     ).unwrap_or(0);
 
+    drop(conn);
+
+    // Which engine runs the jobs, when several share the database
+    let lease = state.leadership.current_lease().ok().flatten();
+
     Json(serde_json::json!({
         "pending": pending,
+        "scheduled": scheduled,
         "running": running,
         "failed": failed,
         "completed": completed,
+        "instance": state.leadership.instance_id(),
+        "leader": state.leadership.is_leader(),
+        "lease": lease,
+        // Jobs wait in the queue while no model server is reachable
+        "paused": state.gpu_pool.is_degraded(),
     })).into_response()
 }
 
 // ============================================================================
-// HELPERS
+// ADMIN HANDLERS
 // ============================================================================
 
-fn validate_chat_request(req: &ChatRequest) -> Result<(), ApiError> {
-    if req.message.trim().is_empty() {
-        return Err(ApiError::InvalidRequest {
-            message: "Message cannot be empty".to_string(),
-            field: Some("message".to_string()),
-        });
+/// POST /admin/prompts
+pub async fn handle_prompt_history(
+    State(state): State<AppState>,
+    Json(req): Json<PromptHistoryRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_agent_name(&req.agent) {
+        return e.into_response();
     }
 
-    if req.message.len() > 50_000 {
-        return Err(ApiError::InvalidRequest {
-            message: "Message too long (max 50,000 characters)".to_string(),
-            field: Some("message".to_string()),
-        });
+    let default_prompt = AgentType::from_name(&req.agent)
+        .map(|agent| agent.build().system_prompt.to_string())
+        .unwrap_or_default();
+
+    match state.agent_pool.db().prompt_override_history(&req.agent) {
+        Ok(versions) => Json(PromptHistoryResponse {
+            agent: req.agent,
+            default_prompt,
+            versions,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load prompt history: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/prompts/set
+pub async fn handle_set_prompt(
+    State(state): State<AppState>,
+    Json(req): Json<SetPromptRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_prompt_request(&req) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().set_prompt_override(&req.agent, &req.prompt, req.note.as_deref()) {
+        Ok(version) => {
+            println!("Prompt override for {} set to version {}", req.agent, version);
+            Json(SetPromptResponse { agent: req.agent, version }).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to save prompt override: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/prompts/rollback
+pub async fn handle_rollback_prompt(
+    State(state): State<AppState>,
+    Json(req): Json<RollbackPromptRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_agent_name(&req.agent) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().rollback_prompt_override(&req.agent, req.version) {
+        Ok(true) => {
+            match req.version {
+                Some(version) => println!("Prompt override for {} rolled back to version {}", req.agent, version),
+                None => println!("Prompt override for {} cleared", req.agent),
+            }
+            StatusCode::OK.into_response()
+        }
+        Ok(false) => ApiError::NotFound {
+            message: format!("{} has no prompt version {}", req.agent, req.version.unwrap_or_default()),
+            resource: "prompt_version".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to roll back prompt override: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/experiments/start
+pub async fn handle_start_experiment(
+    State(state): State<AppState>,
+    Json(req): Json<StartExperimentRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_experiment_request(&req) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().start_prompt_experiment(&req.agent, &req.name, &req.variant_a, &req.variant_b) {
+        Ok(experiment_id) => {
+            println!("Prompt experiment {} '{}' started for {}", experiment_id, req.name, req.agent);
+            Json(StartExperimentResponse { experiment_id }).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to start experiment: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/experiments/stop
+pub async fn handle_stop_experiment(
+    State(state): State<AppState>,
+    Json(req): Json<ExperimentRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().end_prompt_experiment(req.experiment_id) {
+        Ok(true) => {
+            println!("Prompt experiment {} stopped", req.experiment_id);
+            StatusCode::OK.into_response()
+        }
+        Ok(false) => ApiError::NotFound {
+            message: format!("No running experiment {}", req.experiment_id),
+            resource: "experiment".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to stop experiment: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/tools
+pub async fn handle_tool_switches(
+    State(state): State<AppState>,
+    Json(req): Json<ToolSwitchesRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().disabled_tools() {
+        Ok(disabled) => Json(ToolSwitchesResponse {
+            toolbelts: toolbelt_names().into_iter().map(String::from).collect(),
+            disabled,
+        }).into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to load tool switches: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/tools/disable
+///
+/// Turn off a toolbelt, or every tool with "all". Applies to the next tool call.
+pub async fn handle_disable_tools(
+    State(state): State<AppState>,
+    Json(req): Json<SetToolSwitchRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_tool_switch_request(&req) {
+        return e.into_response();
+    }
+
+    let name = tool_switch_name(&req.name).unwrap_or_default();
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    match state.agent_pool.db().disable_tools(&name, reason) {
+        Ok(switch) => {
+            println!("Tools turned off: {}", name);
+            Json(switch).into_response()
+        }
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to turn off tools: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/tools/enable
+pub async fn handle_enable_tools(
+    State(state): State<AppState>,
+    Json(req): Json<SetToolSwitchRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+    if let Err(e) = validate_tool_switch_request(&req) {
+        return e.into_response();
+    }
+
+    let name = tool_switch_name(&req.name).unwrap_or_default();
+    match state.agent_pool.db().enable_tools(&name) {
+        Ok(true) => {
+            println!("Tools turned back on: {}", name);
+            StatusCode::OK.into_response()
+        }
+        Ok(false) => ApiError::NotFound {
+            message: format!("'{}' is not turned off", name),
+            resource: "tool_switch".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to turn tools back on: {}", e),
+        }.into_response(),
+    }
+}
+
+/// POST /admin/experiments/report
+pub async fn handle_experiment_report(
+    State(state): State<AppState>,
+    Json(req): Json<ExperimentRequest>,
+) -> Response {
+    if let Err(e) = authenticate_admin(&state, &req.admin_key) {
+        return e.into_response();
+    }
+
+    match state.agent_pool.db().experiment_report(req.experiment_id) {
+        Ok(Some(report)) => Json(report).into_response(),
+        Ok(None) => ApiError::NotFound {
+            message: format!("Experiment {} not found", req.experiment_id),
+            resource: "experiment".to_string(),
+        }.into_response(),
+        Err(e) => ApiError::Internal {
+            message: format!("Failed to build experiment report: {}", e),
+        }.into_response(),
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Admin endpoints take the server's ADMIN_KEY instead of device credentials.
+fn authenticate_admin(state: &AppState, admin_key: &str) -> Result<(), ApiError> {
+    match &state.admin_key {
+        None => Err(ApiError::Forbidden {
+            message: "Admin endpoints are disabled (ADMIN_KEY not set)".to_string(),
+        }),
+        Some(key) if key == admin_key => Ok(()),
+        Some(_) => Err(ApiError::Unauthorized {
+            message: "Invalid admin key".to_string(),
+        }),
     }
+}
+
+/// Authenticate by key and make sure the key belongs to the claimed device id.
+fn authenticate(db: &Db, device_id: i64, device_key: &str) -> Result<u64, ApiError> {
+    let id = authenticate_device(db, device_key).map_err(|e| ApiError::Unauthorized {
+        message: format!("Invalid device key: {}", e),
+    })?;
 
-    if req.device_key.is_empty() {
-        return Err(ApiError::InvalidRequest {
-            message: "Device key required".to_string(),
-            field: Some("device_key".to_string()),
+    if id as i64 != device_id {
+        return Err(ApiError::Unauthorized {
+            message: "Device key does not match device_id".to_string(),
         });
     }
 
-    Ok(())
+    Ok(id)
+}
+
+/// Only the owning device may change who a conversation is shared with,
+/// or lock it. `what` names the action for the error.
+fn authorize_conversation_owner(
+    db: &Db,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: u64,
+    what: &str,
+) -> Result<u64, ApiError> {
+    let device_id = authenticate(db, device_id, device_key)?;
+
+    let access = db.conversation_access(conversation_id, device_id)
+        .map_err(|e| ApiError::Internal {
+            message: format!("Failed to look up conversation: {}", e),
+        })?;
+
+    match access {
+        ConversationAccess::Owner => Ok(device_id),
+        ConversationAccess::NotFound => Err(ApiError::NotFound {
+            message: format!("Conversation {} not found", conversation_id),
+            resource: "conversation".to_string(),
+        }),
+        ConversationAccess::Shared | ConversationAccess::Denied => Err(ApiError::Forbidden {
+            message: format!("Only the owning device can {}", what),
+        }),
+    }
 }
 
 fn authenticate_device(db: &Db, device_key: &str) -> anyhow::Result<u64> {
@@ -396,13 +2655,25 @@ fn authenticate_device(db: &Db, device_key: &str) -> anyhow::Result<u64> {
     }
 }
 
-fn resolve_conversation(
+/// An existing conversation keeps the profile it was created in, stays
+/// incognito or not, and keeps its privacy level until it is changed through
+/// /conversations/privacy/set.
+pub(crate) fn resolve_conversation(
     db: &Db,
     device_id: u64,
     existing_id: Option<u64>,
+    profile: Option<&str>,
+    incognito: bool,
+    privacy: PrivacyLevel,
 ) -> anyhow::Result<u64> {
     match existing_id {
         Some(id) => Ok(id),
-        None => db.create_conversation(device_id),
+        None => {
+            let id = db.start_conversation(device_id, profile, incognito)?;
+            if privacy != PrivacyLevel::Normal {
+                db.set_privacy_level(id, privacy)?;
+            }
+            Ok(id)
+        }
     }
 }
@@ -3,5 +3,16 @@ pub mod handlers;
 pub mod server;
 pub mod types;
 pub mod events;
+pub mod error;
+pub mod middleware;
+pub mod validation;
+pub mod ws;
+pub mod grpc;
 
+#[cfg(test)]
+mod events_tests;
+#[cfg(test)]
+mod validation_tests;
+
+pub use error::ApiError;
 pub use server::start_server;
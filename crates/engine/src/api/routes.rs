@@ -1,14 +1,134 @@
+use std::sync::Arc;
 use axum::{
+    extract::{DefaultBodyLimit, Request},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
+use artificer_shared::db::Db;
+use super::error::ApiError;
 use super::handlers;
+use super::middleware::{authenticate_device, MAX_REQUEST_BYTES};
+use super::ws;
+use crate::state::AppState;
 
-pub fn create_router() -> Router {
+/// Every endpoint. With `read_only`, endpoints that change anything answer
+/// 403 and only browsing, search and status are served.
+///
+/// Device endpoints check the body's `device_id`/`device_key` before their
+/// handler runs. The rest are public, take the admin key, or (the chat
+/// socket) authenticate the first frame.
+pub fn create_router(read_only: bool, db: Arc<Db>) -> Router<AppState> {
+    let reads = read_routes().merge(device_only(read_device_routes(), &db));
+    let writes = write_routes().merge(device_only(write_device_routes(), &db));
+    let writes = if read_only {
+        writes.route_layer(middleware::from_fn(reject_writes))
+    } else {
+        writes
+    };
+    reads.merge(writes)
+}
+
+fn device_only(routes: Router<AppState>, db: &Arc<Db>) -> Router<AppState> {
+    routes.route_layer(middleware::from_fn_with_state(db.clone(), authenticate_device))
+}
+
+fn read_routes() -> Router<AppState> {
     Router::new()
-        .route("/chat", post(handlers::handle_chat))
         .route("/status", get(handlers::handle_status))
+        .route("/tools", get(handlers::handle_tools))
+        .route("/health/providers", get(handlers::handle_provider_health))
         .route("/background/status", get(handlers::handle_background_status))
-        .route("/devices/register", post(handlers::handle_register_device))
+        .route("/admin/prompts", post(handlers::handle_prompt_history))
+        .route("/admin/experiments/report", post(handlers::handle_experiment_report))
+        .route("/admin/tools", post(handlers::handle_tool_switches))
+}
+
+fn read_device_routes() -> Router<AppState> {
+    Router::new()
+        .route("/chat/resume", post(handlers::handle_resume_chat))
+        .route("/conversations", post(handlers::handle_list_conversations))
+        .route("/conversations/messages", post(handlers::handle_conversation_messages))
+        .route("/conversations/search", post(handlers::handle_search_conversations))
+        .route("/conversations/state", post(handlers::handle_conversation_state))
+        .route("/conversations/privacy", post(handlers::handle_conversation_privacy))
+        .route("/conversations/tools", post(handlers::handle_conversation_tools))
+        .route("/conversations/unread", post(handlers::handle_unread))
+        .route("/conversations/checkpoints", post(handlers::handle_list_checkpoints))
+        .route("/tags", post(handlers::handle_list_tags))
+        .route("/tags/conversations", post(handlers::handle_tagged_conversations))
+        .route("/memories", post(handlers::handle_memories))
+        .route("/memories/timeline", post(handlers::handle_memory_timeline))
+        .route("/workflows", post(handlers::handle_workflows))
+        .route("/watches", post(handlers::handle_watches))
+        .route("/jobs/status", post(handlers::handle_job_status))
+        .route("/jobs/events", post(handlers::handle_job_events))
+        .route("/usage", post(handlers::handle_usage))
         .route("/devices/verify", post(handlers::handle_verify_device))
-}
\ No newline at end of file
+        .route("/devices/groups", post(handlers::handle_device_groups))
+        .route("/devices/settings", post(handlers::handle_device_settings))
+        .route("/devices/{id}/events", post(handlers::handle_device_events))
+        .route("/attachments/get", post(handlers::handle_get_attachment))
+        .route("/debug/requests/{id}", post(handlers::handle_request_snapshots))
+}
+
+fn write_routes() -> Router<AppState> {
+    Router::new()
+        .route("/chat/ws", get(ws::handle_chat_ws))
+        .route("/devices/register", post(handlers::handle_register_device))
+        .route("/admin/prompts/set", post(handlers::handle_set_prompt))
+        .route("/admin/prompts/rollback", post(handlers::handle_rollback_prompt))
+        .route("/admin/experiments/start", post(handlers::handle_start_experiment))
+        .route("/admin/experiments/stop", post(handlers::handle_stop_experiment))
+        .route("/admin/tools/disable", post(handlers::handle_disable_tools))
+        .route("/admin/tools/enable", post(handlers::handle_enable_tools))
+}
+
+fn write_device_routes() -> Router<AppState> {
+    Router::new()
+        .route("/chat", post(handlers::handle_chat))
+        .route("/chat/continue", post(handlers::handle_continue_chat))
+        .route("/chat/cancel", post(handlers::handle_cancel_chat))
+        .route("/conversations/share", post(handlers::handle_share_conversation))
+        .route("/conversations/unshare", post(handlers::handle_unshare_conversation))
+        .route("/conversations/lock", post(handlers::handle_lock_conversation))
+        .route("/conversations/unlock", post(handlers::handle_unlock_conversation))
+        .route("/conversations/privacy/set", post(handlers::handle_set_conversation_privacy))
+        .route("/conversations/tools/set", post(handlers::handle_set_conversation_tools))
+        .route("/conversations/read", post(handlers::handle_mark_read))
+        .route("/conversations/feedback", post(handlers::handle_conversation_feedback))
+        .route("/conversations/tags", post(handlers::handle_tag_conversation))
+        .route("/conversations/messages/delete", post(handlers::handle_delete_message))
+        .route("/conversations/messages/redact", post(handlers::handle_redact_message))
+        .route("/conversations/checkpoints/create", post(handlers::handle_create_checkpoint))
+        .route("/conversations/checkpoints/branch", post(handlers::handle_branch_conversation))
+        .route("/conversations/merge", post(handlers::handle_merge_conversations))
+        .route("/conversations/split", post(handlers::handle_split_conversation))
+        .route("/jobs", post(handlers::handle_queue_job))
+        .route("/jobs/inbox", post(handlers::handle_job_inbox))
+        .route("/workflows/set", post(handlers::handle_save_workflow))
+        .route("/workflows/delete", post(handlers::handle_delete_workflow))
+        .route("/workflows/run", post(handlers::handle_run_workflow))
+        .route("/watches/set", post(handlers::handle_add_watch))
+        .route("/watches/delete", post(handlers::handle_delete_watch))
+        .route("/watches/trigger", post(handlers::handle_trigger_watch))
+        .route("/devices/language", post(handlers::handle_device_language))
+        .route("/devices/settings/set", post(handlers::handle_set_device_settings))
+        .route("/devices/metadata", post(handlers::handle_device_metadata))
+        .route("/devices/purge", post(handlers::handle_purge_device))
+        .route("/devices/link", post(handlers::handle_link_device))
+        .route("/devices/groups/set", post(handlers::handle_set_device_group))
+        .route("/devices/groups/delete", post(handlers::handle_delete_device_group))
+        .route(
+            "/attachments/upload",
+            post(handlers::handle_upload_attachment).layer(DefaultBodyLimit::max(MAX_REQUEST_BYTES)),
+        )
+}
+
+/// Answer any write on a read-only replica with 403.
+async fn reject_writes(_req: Request, _next: Next) -> Response {
+    ApiError::Forbidden {
+        message: "This engine is a read-only replica; send writes to the primary".to_string(),
+    }.into_response()
+}
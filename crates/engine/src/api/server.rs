@@ -1,16 +1,36 @@
+use std::net::SocketAddr;
 use anyhow::Result;
-use axum::extract::Extension;
+use axum::Router;
 use tokio::sync::watch;
 
-use super::handlers::AppState;
+use crate::state::AppState;
+use super::grpc;
 use super::routes::create_router;
 
+/// The API as served, with `state` handed to every handler: the REST
+/// routes, and the gRPC service on the same port.
+pub fn app(state: AppState) -> Router {
+    let grpc = grpc::routes(state.clone());
+    create_router(state.read_only, state.agent_pool.db().clone()).with_state(state).merge(grpc)
+}
+
 pub async fn start_server(state: AppState, shutdown_rx: watch::Receiver<bool>) -> Result<()> {
-    let app = create_router()
-        .layer(Extension(state));
+    let app = app(state);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
-    println!("Artificer API server listening on http://0.0.0.0:8080");
+    // Under systemd socket activation the socket is already bound
+    let listener = match crate::service::inherited_listener() {
+        Some(listener) => {
+            listener.set_nonblocking(true)?;
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            println!("Artificer API server listening on {} (socket activated)", listener.local_addr()?);
+            listener
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
+            println!("Artificer API server listening on http://0.0.0.0:8080");
+            listener
+        }
+    };
 
     axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal(shutdown_rx))
@@ -19,6 +39,21 @@ pub async fn start_server(state: AppState, shutdown_rx: watch::Receiver<bool>) -
     Ok(())
 }
 
+/// Serve the API in this process on a free port of 127.0.0.1, for the
+/// integration tests under `tests/`. Returns the address once it accepts
+/// connections; the server stops when `shutdown_rx` turns true.
+pub async fn start_test_server(state: AppState, shutdown_rx: watch::Receiver<bool>) -> Result<SocketAddr> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let app = app(state);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).with_graceful_shutdown(shutdown_signal(shutdown_rx)).await {
+            eprintln!("Test server error: {}", e);
+        }
+    });
+    Ok(addr)
+}
+
 async fn shutdown_signal(mut shutdown_rx: watch::Receiver<bool>) {
     while !*shutdown_rx.borrow() {
         if shutdown_rx.changed().await.is_err() {
@@ -1,18 +1,103 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
-
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
+use artificer_shared::memory::{Memory, TimelineWeek};
+use artificer_shared::schemas::{ToolLocation, ToolRisk};
+use artificer_shared::db::{
+    Attachment, BackgroundJob, Checkpoint, StoredMessage, ConversationSummary, DeviceGroup, OwnedDevice, PromptSnapshot, ToolSwitch, ToolUsage, ConversationSearchHit, PromptOverride, TagCount, TaggedConversation, Workflow, PathWatch, PrivacyLevel, ProviderHealth, ResponseLength, ToolChoice, UnreadConversation,
+};
 
 // Chat endpoint
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct ChatRequest {
     pub device_id: i64,
     pub device_key: String,
     pub conversation_id: Option<u64>,
     pub message: String,
-    pub stream: Option<bool>
+    pub stream: Option<bool>,
+    /// Queue the request as a background job and answer later instead of streaming.
+    #[serde(default)]
+    pub deferred: bool,
+    /// Profile for a new conversation (default "default"). Memories from other
+    /// profiles are never used. Ignored when continuing a conversation.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Start a new conversation that is never listed, searched or remembered
+    /// from, and is deleted an hour after its last message. Ignored when
+    /// continuing a conversation.
+    #[serde(default)]
+    pub incognito: bool,
+    /// Privacy level for a new conversation: "normal" (the default),
+    /// "facts-only" or "no-extraction". Ignored when continuing a conversation.
+    #[serde(default)]
+    pub privacy: Option<String>,
+    /// Toolbelts a new conversation turns on or off over the agents'
+    /// defaults, e.g. WebSearch off. Ignored when continuing a conversation.
+    #[serde(default)]
+    pub toolbelts: Vec<ToolChoice>,
+    /// Bypass the shared result cache so every delegation runs anew.
+    #[serde(default)]
+    pub fresh: bool,
+    /// Save the prompt of every LLM call for /debug/requests.
+    #[serde(default)]
+    pub debug: bool,
+    /// Have a critic check the answer against the tool results before it is
+    /// final, and revise it once if the critic finds problems.
+    #[serde(default)]
+    pub verify: bool,
+    /// Run this turn on another model an interactive GPU serves (see
+    /// `models` in hardware.json) instead of the GPU's default.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// How long the answer may take: "interactive", "relaxed" (the default)
+    /// or "batch". Picks the GPU, model and answer length per the `latency`
+    /// section of hardware.json; an explicit `model` still wins.
+    #[serde(default)]
+    pub latency_hint: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeferredChatResponse {
+    pub job_id: u64,
+    pub conversation_id: u64,
+}
+
+/// Answer to a question a specialist asked mid-run.
+#[derive(Deserialize)]
+pub struct ContinueChatRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    /// Question being answered; the conversation's oldest open one if omitted.
+    #[serde(default)]
+    pub question_id: Option<u64>,
+    pub answer: String,
+}
+
+#[derive(Serialize)]
+pub struct ContinueChatResponse {
+    pub question_id: u64,
+    pub task_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct CancelChatRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct CancelChatResponse {
+    pub conversation_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ResumeChatRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    /// Id of the last event the client received; None replays the whole turn.
+    pub last_event_id: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -34,23 +119,719 @@ pub struct RegisterDeviceResponse {
 }
 
 // Conversation listing
-#[derive(Serialize)]
-pub struct ConversationInfo {
-    pub id: u64,
-    pub title: Option<String>,
-    pub created: i64,
-    pub last_accessed: i64,
+#[derive(Deserialize)]
+pub struct ListConversationsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
 }
 
 #[derive(Serialize)]
 pub struct ListConversationsResponse {
-    pub conversations: Vec<ConversationInfo>,
+    pub conversations: Vec<ConversationSummary>,
+}
+
+#[derive(Deserialize)]
+pub struct ConversationMessagesRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct ConversationMessagesResponse {
+    pub conversation_id: u64,
+    pub messages: Vec<StoredMessage>,
+}
+
+/// Freeze a conversation to branch from later.
+#[derive(Deserialize)]
+pub struct CreateCheckpointRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CreateCheckpointResponse {
+    pub checkpoint_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct ListCheckpointsResponse {
+    pub conversation_id: u64,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Start a new conversation from one of a conversation's checkpoints.
+#[derive(Deserialize)]
+pub struct BranchConversationRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub checkpoint_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct BranchConversationResponse {
+    /// The new conversation.
+    pub conversation_id: u64,
+}
+
+/// Fold one conversation into another.
+#[derive(Deserialize)]
+pub struct MergeConversationsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// The conversation that stays.
+    pub conversation_id: u64,
+    /// The conversation whose messages move over; deleted afterwards.
+    pub source_id: u64,
+}
+
+/// Move a message and everything after it into a new conversation.
+#[derive(Deserialize)]
+pub struct SplitConversationRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub message_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct SplitConversationResponse {
+    /// The new conversation.
+    pub conversation_id: u64,
+}
+
+/// Delete one message of a conversation, leaving a tombstone.
+#[derive(Deserialize)]
+pub struct DeleteMessageRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub message_id: u64,
+}
+
+/// Scrub text, or a whole message, from a conversation and what it left behind.
+#[derive(Deserialize)]
+pub struct RedactMessageRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub message_id: u64,
+    /// The text to scrub, e.g. a pasted secret; the whole message if omitted.
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RedactMessageResponse {
+    /// How many stored texts were changed, the message included.
+    pub redacted: usize,
+}
+
+// Conversation privacy
+#[derive(Deserialize)]
+pub struct ConversationPrivacyRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct SetConversationPrivacyRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    /// "normal", "facts-only" or "no-extraction".
+    pub level: String,
+}
+
+#[derive(Serialize)]
+pub struct ConversationPrivacyResponse {
+    pub conversation_id: u64,
+    pub level: PrivacyLevel,
+}
+
+#[derive(Deserialize)]
+pub struct ConversationToolsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct SetConversationToolsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub toolbelt: String,
+    /// true turns the toolbelt on, false off, null back to the default.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ConversationToolsResponse {
+    pub conversation_id: u64,
+    /// Toolbelts turned on or off; the rest keep each agent's default.
+    pub toolbelts: Vec<ToolChoice>,
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    /// Last message read; omitted reads the whole conversation.
+    #[serde(default)]
+    pub message_id: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct MarkReadResponse {
+    pub conversation_id: u64,
+    /// Answers still unread on this device, after `message_id`.
+    pub unread: u32,
+}
+
+#[derive(Deserialize)]
+pub struct UnreadRequest {
+    pub device_id: i64,
+    pub device_key: String,
+}
+
+#[derive(Serialize)]
+pub struct UnreadResponse {
+    /// Unread answers across all conversations.
+    pub total: u32,
+    pub conversations: Vec<UnreadConversation>,
+}
+
+// Memories
+#[derive(Deserialize)]
+pub struct MemoriesRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// Profile to read (default "default").
+    pub profile: Option<String>,
+    /// Only memories sharing keywords with this, most relevant first.
+    pub query: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct MemoriesResponse {
+    pub profile: String,
+    pub memories: Vec<Memory>,
+}
+
+#[derive(Deserialize)]
+pub struct MemoryTimelineRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// Profile to read (default "default").
+    pub profile: Option<String>,
+    /// Weeks back to cover, this one included (default 8, max 52).
+    pub weeks: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct MemoryTimelineResponse {
+    pub profile: String,
+    /// Newest first; weeks with no activity are left out.
+    pub weeks: Vec<TimelineWeek>,
+}
+
+// Conversation search
+#[derive(Deserialize)]
+pub struct SearchConversationsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub query: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SearchConversationsResponse {
+    pub results: Vec<ConversationSearchHit>,
 }
+// Conversation runtime state
+#[derive(Deserialize)]
+pub struct ConversationStateRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+}
+
+// Conversation sharing
+#[derive(Deserialize)]
+pub struct ShareConversationRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    /// Device being granted (or losing) access.
+    pub target_device_id: u64,
+}
+
+// Sensitive conversations
+#[derive(Deserialize)]
+pub struct LockConversationRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    pub pin: String,
+    /// On unlock, drop the lock instead of unlocking for a while.
+    #[serde(default)]
+    pub remove: bool,
+}
+
 #[derive(Deserialize)]
 pub struct QueueJobRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub method: String,
+    pub conversation_id: Option<u64>,
+    pub arguments: Option<serde_json::Value>,
+    pub priority: Option<u32>,
+    /// Unix timestamp before which the job does not start.
+    pub run_at: Option<i64>,
+    /// Seconds to wait before starting; the alternative to `run_at`.
+    pub delay_secs: Option<u64>,
+    /// "interactive", "relaxed" (the default) or "batch"; see `ChatRequest`.
+    #[serde(default)]
+    pub latency_hint: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct QueueJobResponse {
+    pub job_id: u64,
+    /// Conversation the job runs in, when it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<u64>,
+}
+
+// Saved workflows
+#[derive(Deserialize)]
+pub struct SaveWorkflowRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub name: String,
+    /// The request to send, with `{param}` placeholders.
+    pub template: String,
+    /// Values for parameters a run leaves out.
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct WorkflowRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct RunWorkflowRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: BTreeMap<String, String>,
+    /// Unix timestamp before which the run does not start.
+    pub run_at: Option<i64>,
+    /// Seconds to wait before starting; the alternative to `run_at`.
+    pub delay_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct WorkflowInfo {
+    #[serde(flatten)]
+    pub workflow: Workflow,
+    pub parameters: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct WorkflowsResponse {
+    pub workflows: Vec<WorkflowInfo>,
+}
+
+// Watched paths
+#[derive(Deserialize)]
+pub struct AddWatchRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// Folder or file on the device; `~/` is its home directory.
+    pub path: String,
+    /// The request queued when files under `path` change.
+    pub task: String,
+    /// File name glob such as `*.pdf`; every file if absent.
+    pub pattern: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AddWatchResponse {
+    pub watch_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct WatchRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub watch_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct TriggerWatchRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub watch_id: u64,
+    /// The files that were added or changed, as the device sees them.
+    pub paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct WatchesResponse {
+    pub watches: Vec<PathWatch>,
+}
+
+/// Body for endpoints that only need device credentials.
+#[derive(Deserialize)]
+pub struct DeviceRequest {
+    pub device_id: i64,
+    pub device_key: String,
+}
+
+// Metered provider usage
+#[derive(Deserialize)]
+pub struct UsageRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// How many days of history to return, today included (default 30).
+    #[serde(default)]
+    pub days: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderUsage {
+    pub provider: String,
+    /// Calls this device made today (UTC).
+    pub today: u64,
+    pub daily_limit: Option<u64>,
+    /// Calls all devices made this month (UTC).
+    pub this_month: u64,
+    pub monthly_limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct UsageResponse {
+    pub providers: Vec<ProviderUsage>,
+    /// The device's calls per provider per day, newest first.
+    pub history: Vec<ToolUsage>,
+}
+
+#[derive(Serialize)]
+pub struct JobInboxResponse {
+    pub jobs: Vec<BackgroundJob>,
+}
+
+#[derive(Deserialize)]
+pub struct JobStatusRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub job_id: u64,
+}
+
+// Admin: prompt overrides
+#[derive(Deserialize)]
+pub struct PromptHistoryRequest {
+    pub admin_key: String,
+    pub agent: String,
+}
+
+#[derive(Serialize)]
+pub struct PromptHistoryResponse {
+    pub agent: String,
+    /// The compiled prompt used when no version is active.
+    pub default_prompt: String,
+    pub versions: Vec<PromptOverride>,
+}
+
+#[derive(Deserialize)]
+pub struct SetPromptRequest {
+    pub admin_key: String,
+    pub agent: String,
+    pub prompt: String,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SetPromptResponse {
+    pub agent: String,
+    pub version: u32,
+}
+
+#[derive(Deserialize)]
+pub struct RollbackPromptRequest {
+    pub admin_key: String,
+    pub agent: String,
+    /// Version to reactivate. Omit to fall back to the compiled prompt.
+    pub version: Option<u32>,
+}
+
+// Admin: prompt experiments
+#[derive(Deserialize)]
+pub struct StartExperimentRequest {
+    pub admin_key: String,
+    pub agent: String,
+    pub name: String,
+    pub variant_a: String,
+    pub variant_b: String,
+}
+
+#[derive(Serialize)]
+pub struct StartExperimentResponse {
+    pub experiment_id: u64,
+}
+
+/// Body for admin endpoints that act on one experiment.
+#[derive(Deserialize)]
+pub struct ExperimentRequest {
+    pub admin_key: String,
+    pub experiment_id: u64,
+}
+
+// Tool documentation
+#[derive(Serialize)]
+pub struct ToolsResponse {
+    pub toolbelts: Vec<ToolbeltDoc>,
+}
+
+#[derive(Serialize)]
+pub struct ToolbeltDoc {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub location: ToolLocation,
+    pub risk: ToolRisk,
+    pub tools: Vec<ToolDoc>,
+}
+
+#[derive(Serialize)]
+pub struct ToolDoc {
+    /// Full name, e.g. "FileSmith::read_file".
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the arguments, as the models see it.
+    pub parameters: serde_json::Value,
+    /// Arguments of a typical call, when the toolbelt declares one.
+    pub example: Option<serde_json::Value>,
+}
+
+// Provider health
+#[derive(Serialize)]
+pub struct ProviderHealthResponse {
+    pub providers: Vec<ProviderStatus>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderStatus {
+    #[serde(flatten)]
+    pub health: ProviderHealth,
+    /// No calls left until the provider's quota resets.
+    pub exhausted: bool,
+}
+
+// Admin: tool switches
+#[derive(Deserialize)]
+pub struct ToolSwitchesRequest {
+    pub admin_key: String,
+}
+
+#[derive(Serialize)]
+pub struct ToolSwitchesResponse {
+    /// Every registered toolbelt, the names a switch can take besides "all".
+    pub toolbelts: Vec<String>,
+    pub disabled: Vec<ToolSwitch>,
+}
+
+#[derive(Deserialize)]
+pub struct SetToolSwitchRequest {
+    pub admin_key: String,
+    /// A toolbelt name (any case) or "all".
+    pub name: String,
+    /// Shown to the agents when they try a disabled tool. Ignored on enable.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+// Device language preference
+#[derive(Deserialize)]
+pub struct DeviceLanguageRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// ISO 639-1 code, or null to clear the preference.
+    pub language: Option<String>,
+}
+
+// Device response settings
+#[derive(Deserialize)]
+pub struct DeviceSettingsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// ISO 639-1 code, or "auto" to clear the preference. Absent leaves it as is.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "concise", "detailed", or "default" to leave length to the model.
+    /// Absent leaves it as is.
+    #[serde(default)]
+    pub length: Option<String>,
+    /// Absent leaves it as is.
+    #[serde(default)]
+    pub code_first: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct DeviceSettingsResponse {
+    pub language: Option<String>,
+    pub length: Option<ResponseLength>,
+    pub code_first: bool,
+}
+
+// Device metadata reported by the client
+#[derive(Deserialize)]
+pub struct DeviceMetadataRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// IANA timezone such as "Europe/Berlin".
+    pub timezone: Option<String>,
+    /// POSIX locale such as "de_DE.UTF-8".
+    pub locale: Option<String>,
+    /// URL of the device's tool server, for tools run on all machines. Empty clears it.
+    #[serde(default)]
+    pub tool_host: Option<String>,
+}
+
+/// Delete everything stored for the calling device.
+#[derive(Deserialize)]
+pub struct PurgeDeviceRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// Count what would be deleted without deleting it.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// Device ownership and groups
+#[derive(Deserialize)]
+pub struct LinkDeviceRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    /// The device to make one of the caller's own, with its credentials as proof.
+    pub target_device_id: i64,
+    pub target_device_key: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetDeviceGroupRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub name: String,
+    /// Device names or ids, all of them the caller's own.
+    #[serde(default)]
+    pub devices: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteDeviceGroupRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct DeviceGroupsResponse {
+    pub devices: Vec<OwnedDevice>,
+    pub groups: Vec<DeviceGroup>,
+}
+
+// Conversation feedback
+#[derive(Deserialize)]
+pub struct FeedbackRequest {
     pub device_id: i64,
     pub device_key: String,
     pub conversation_id: u64,
+    /// -1 (bad) or 1 (good).
+    pub score: i64,
+}
+
+// Conversation tags
+#[derive(Deserialize)]
+pub struct TagConversationRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub conversation_id: u64,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConversationTagsResponse {
+    pub conversation_id: u64,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ListTagsResponse {
+    pub tags: Vec<TagCount>,
+}
+
+#[derive(Deserialize)]
+pub struct TaggedConversationsRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub tag: String,
+}
+
+#[derive(Serialize)]
+pub struct TaggedConversationsResponse {
+    pub conversations: Vec<TaggedConversation>,
+}
+
+// Attachments
+#[derive(Deserialize)]
+pub struct UploadAttachmentRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub filename: String,
+    pub mime_type: String,
+    /// File contents, base64-encoded.
+    pub data: String,
+}
+
+#[derive(Serialize)]
+pub struct UploadAttachmentResponse {
+    pub attachment_id: u64,
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentRequest {
+    pub device_id: i64,
+    pub device_key: String,
+    pub attachment_id: u64,
+}
+
+#[derive(Serialize)]
+pub struct AttachmentResponse {
+    #[serde(flatten)]
+    pub attachment: Attachment,
+    /// File contents, base64-encoded.
+    pub data: String,
 }
 
 #[derive(Deserialize)]
@@ -64,4 +845,11 @@ pub struct ToolExecutionRequest {
 #[derive(Serialize)]
 pub struct ToolExecutionResponse {
     pub result: String,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize)]
+pub struct RequestSnapshotsResponse {
+    pub request_id: u64,
+    /// One per LLM call, oldest first, including those of delegated specialists.
+    pub snapshots: Vec<PromptSnapshot>,
+}
@@ -0,0 +1,671 @@
+use artificer_shared::db::{
+    normalize_group, normalize_profile, normalize_tag, normalize_workflow_name, workflow_parameters, ConversationAccess, Db, MAX_CHECKPOINT_NAME_CHARS, MAX_GROUP_CHARS, MAX_PIN_CHARS, MAX_PROFILE_CHARS,
+    MAX_TAG_CHARS, MAX_WATCH_TASK_CHARS, MAX_WATCH_TRIGGER_PATHS, MAX_WORKFLOW_CHARS, MAX_WORKFLOW_NAME_CHARS, MIN_PIN_CHARS, ALL_TOOLS,
+    PrivacyLevel, ResponseLength,
+};
+use artificer_shared::errors::FieldError;
+use artificer_shared::time;
+use artificer_shared::tools::toolbelt_names;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use crate::api::error::ApiError;
+use crate::api::types::{
+    ChatRequest, ContinueChatRequest, CreateCheckpointRequest, DeviceLanguageRequest, DeviceMetadataRequest, DeviceSettingsRequest, FeedbackRequest, LockConversationRequest,
+    RedactMessageRequest, SetToolSwitchRequest, TagConversationRequest,
+    UploadAttachmentRequest, QueueJobRequest, SetDeviceGroupRequest, SetPromptRequest,
+    StartExperimentRequest, UsageRequest, SaveWorkflowRequest, RunWorkflowRequest, AddWatchRequest, TriggerWatchRequest,
+};
+use crate::agent::language::{language_name, LANGUAGES};
+use crate::agent::AgentType;
+use crate::pool::LatencyHint;
+use crate::background::{job_required_arguments, summarize, JOB_METHODS, MAX_JOB_DELAY_SECS, MAX_JOB_PRIORITY};
+
+/// Longest message accepted by /chat, in characters.
+pub const MAX_MESSAGE_CHARS: usize = 50_000;
+
+/// Longest prompt override accepted, in characters.
+pub const MAX_PROMPT_CHARS: usize = 100_000;
+
+/// Longest locale string accepted from a device.
+pub const MAX_LOCALE_CHARS: usize = 64;
+
+/// Longest tool host URL accepted from a device.
+pub const MAX_TOOL_HOST_CHARS: usize = 512;
+
+/// Largest attachment accepted, in bytes (before base64 encoding).
+pub const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+/// Longest attachment filename accepted, in characters.
+pub const MAX_FILENAME_CHARS: usize = 255;
+
+/// Most tags one request may add or remove.
+pub const MAX_TAGS_PER_REQUEST: usize = 20;
+
+/// Most devices one group may hold.
+pub const MAX_GROUP_DEVICES: usize = 100;
+
+/// Longest reason accepted for turning tools off, in characters.
+pub const MAX_SWITCH_REASON_CHARS: usize = 500;
+
+/// Longest model name a chat may ask for.
+pub const MAX_MODEL_CHARS: usize = 128;
+
+/// Collects field errors so a request reports every problem at once
+/// instead of one per round trip.
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `message` against `field` unless `ok` holds.
+    pub fn check(&mut self, ok: bool, field: &str, message: impl Into<String>) -> &mut Self {
+        if !ok {
+            self.fail(field, message);
+        }
+        self
+    }
+
+    pub fn fail(&mut self, field: &str, message: impl Into<String>) -> &mut Self {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn finish(self) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::ValidationFailed { errors: self.errors })
+        }
+    }
+}
+
+// ============================================================================
+// REQUEST SHAPE — no database access, run before authentication
+// ============================================================================
+
+pub fn validate_chat_request(req: &ChatRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.message.trim().is_empty(), "message", "Message cannot be empty");
+    v.check(
+        req.message.chars().count() <= MAX_MESSAGE_CHARS,
+        "message",
+        format!("Message too long (max {} characters)", MAX_MESSAGE_CHARS),
+    );
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(profile) = &req.profile {
+        v.check(
+            normalize_profile(profile).is_some(),
+            "profile",
+            format!("Profile must be up to {} letters, digits, '-' or '_'", MAX_PROFILE_CHARS),
+        );
+    }
+    if let Some(privacy) = &req.privacy {
+        v.check(
+            PrivacyLevel::parse(privacy).is_some(),
+            "privacy",
+            "Privacy must be 'normal', 'facts-only' or 'no-extraction'",
+        );
+    }
+    for choice in &req.toolbelts {
+        v.check(
+            toolbelt_name(&choice.toolbelt).is_some(),
+            "toolbelts",
+            format!("Unknown toolbelt '{}'", choice.toolbelt),
+        );
+    }
+    // Whether a GPU serves the model is checked when the turn starts
+    if let Some(model) = &req.model {
+        v.check(!model.trim().is_empty(), "model", "Model cannot be empty");
+        v.check(
+            model.chars().count() <= MAX_MODEL_CHARS,
+            "model",
+            format!("Model name too long (max {} characters)", MAX_MODEL_CHARS),
+        );
+        v.check(!req.deferred, "model", "A model can only be chosen for streamed chats, not deferred ones");
+    }
+    check_latency_hint(&mut v, req.latency_hint.as_deref());
+
+    v.finish()
+}
+
+fn check_latency_hint(v: &mut Validator, hint: Option<&str>) {
+    if let Some(hint) = hint {
+        v.check(
+            LatencyHint::parse(hint).is_some(),
+            "latency_hint",
+            format!("Latency hint must be one of: {}", LatencyHint::NAMES.join(", ")),
+        );
+    }
+}
+
+pub fn validate_job_request(req: &QueueJobRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+
+    match job_required_arguments(&req.method) {
+        Some(required) => {
+            for arg in required {
+                // conversation_id may be sent top-level or inside `arguments`
+                let in_arguments = req.arguments.as_ref().is_some_and(|a| a.get(*arg).is_some());
+                let present = match *arg {
+                    "conversation_id" => req.conversation_id.is_some() || in_arguments,
+                    _ => in_arguments,
+                };
+                let field = if *arg == "conversation_id" {
+                    arg.to_string()
+                } else {
+                    format!("arguments.{}", arg)
+                };
+                v.check(present, &field, format!("'{}' jobs require {}", req.method, arg));
+            }
+        }
+        None => {
+            let known: Vec<&str> = JOB_METHODS.iter().map(|(name, _)| *name).collect();
+            v.fail("method", format!(
+                "Unknown job method '{}'. Known methods: {}",
+                req.method,
+                known.join(", ")
+            ));
+        }
+    }
+
+    if req.method == "agent"
+        && let Some(name) = req.arguments.as_ref().and_then(|a| a["agent"].as_str())
+        && AgentType::from_name(name).is_none()
+    {
+        let known: Vec<&str> = AgentType::all().iter().map(|t| t.name()).collect();
+        v.fail("arguments.agent", format!(
+            "Unknown agent '{}'. Known agents: {}",
+            name,
+            known.join(", ")
+        ));
+    }
+
+    check_latency_hint(&mut v, req.latency_hint.as_deref());
+
+    if req.method == "summarize"
+        && let Some(chunk_chars) = req.arguments.as_ref().and_then(|a| a.get("chunk_chars"))
+    {
+        let in_range = chunk_chars.as_u64().is_some_and(|c| {
+            (summarize::MIN_CHUNK_CHARS..=summarize::MAX_CHUNK_CHARS).contains(&(c as usize))
+        });
+        v.check(
+            in_range,
+            "arguments.chunk_chars",
+            format!(
+                "chunk_chars must be between {} and {}",
+                summarize::MIN_CHUNK_CHARS,
+                summarize::MAX_CHUNK_CHARS
+            ),
+        );
+    }
+
+    if let Some(priority) = req.priority {
+        v.check(
+            priority <= MAX_JOB_PRIORITY,
+            "priority",
+            format!("Priority must be between 0 and {}", MAX_JOB_PRIORITY),
+        );
+    }
+
+    if let Some(arguments) = &req.arguments {
+        v.check(arguments.is_object(), "arguments", "Arguments must be a JSON object");
+    }
+
+    check_schedule(&mut v, req.run_at, req.delay_secs);
+
+    v.finish()
+}
+
+/// When a queued job may start. A time in the past is allowed and just
+/// means "now".
+fn check_schedule(v: &mut Validator, run_at: Option<i64>, delay_secs: Option<u64>) {
+    v.check(
+        run_at.is_none() || delay_secs.is_none(),
+        "run_at",
+        "Give either run_at or delay_secs, not both",
+    );
+    if let Some(delay) = delay_secs {
+        v.check(
+            delay <= MAX_JOB_DELAY_SECS,
+            "delay_secs",
+            format!("Jobs cannot be delayed more than {} days", MAX_JOB_DELAY_SECS / 86_400),
+        );
+    }
+    if let Some(run_at) = run_at {
+        let latest = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() + MAX_JOB_DELAY_SECS;
+        v.check(
+            run_at <= latest as i64,
+            "run_at",
+            format!("Jobs cannot be scheduled more than {} days ahead", MAX_JOB_DELAY_SECS / 86_400),
+        );
+    }
+}
+
+pub fn validate_prompt_request(req: &SetPromptRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    check_agent_name(&mut v, &req.agent);
+    v.check(!req.prompt.trim().is_empty(), "prompt", "Prompt cannot be empty");
+    v.check(
+        req.prompt.chars().count() <= MAX_PROMPT_CHARS,
+        "prompt",
+        format!("Prompt too long (max {} characters)", MAX_PROMPT_CHARS),
+    );
+
+    v.finish()
+}
+
+pub fn validate_experiment_request(req: &StartExperimentRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    check_agent_name(&mut v, &req.agent);
+    v.check(!req.name.trim().is_empty(), "name", "Experiment name cannot be empty");
+    for (field, prompt) in [("variant_a", &req.variant_a), ("variant_b", &req.variant_b)] {
+        v.check(!prompt.trim().is_empty(), field, "Variant prompt cannot be empty");
+        v.check(
+            prompt.chars().count() <= MAX_PROMPT_CHARS,
+            field,
+            format!("Variant prompt too long (max {} characters)", MAX_PROMPT_CHARS),
+        );
+    }
+    v.check(req.variant_a != req.variant_b, "variant_b", "Variants must differ");
+
+    v.finish()
+}
+
+pub fn validate_continue_request(req: &ContinueChatRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.answer.trim().is_empty(), "answer", "Answer cannot be empty");
+    v.check(
+        req.answer.chars().count() <= MAX_MESSAGE_CHARS,
+        "answer",
+        format!("Answer too long (max {} characters)", MAX_MESSAGE_CHARS),
+    );
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+
+    v.finish()
+}
+
+/// Longest /usage history, in days.
+pub const MAX_USAGE_DAYS: u32 = 366;
+
+pub fn validate_usage_request(req: &UsageRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(days) = req.days {
+        v.check(
+            (1..=MAX_USAGE_DAYS).contains(&days),
+            "days",
+            format!("Days must be between 1 and {}", MAX_USAGE_DAYS),
+        );
+    }
+
+    v.finish()
+}
+
+pub fn validate_feedback_request(req: &FeedbackRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(req.score == -1 || req.score == 1, "score", "Score must be -1 or 1");
+
+    v.finish()
+}
+
+pub fn validate_language_request(req: &DeviceLanguageRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(language) = &req.language
+        && language_name(language).is_none()
+    {
+        let known: Vec<&str> = LANGUAGES.iter().map(|(code, _)| *code).collect();
+        v.fail("language", format!(
+            "Unknown language '{}'. Known languages: {}",
+            language,
+            known.join(", ")
+        ));
+    }
+
+    v.finish()
+}
+
+pub fn validate_settings_request(req: &DeviceSettingsRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(language) = &req.language
+        && language != "auto"
+        && language_name(language).is_none()
+    {
+        let known: Vec<&str> = LANGUAGES.iter().map(|(code, _)| *code).collect();
+        v.fail("language", format!(
+            "Unknown language '{}'. Known languages: {}, or auto",
+            language,
+            known.join(", ")
+        ));
+    }
+    if let Some(length) = &req.length {
+        v.check(
+            length == "default" || ResponseLength::parse(length).is_some(),
+            "length",
+            "Length must be concise, detailed or default",
+        );
+    }
+
+    v.finish()
+}
+
+pub fn validate_metadata_request(req: &DeviceMetadataRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(timezone) = &req.timezone {
+        v.check(time::is_valid_zone(timezone), "timezone", format!("Unknown timezone '{}'", timezone));
+    }
+    if let Some(locale) = &req.locale {
+        v.check(
+            !locale.is_empty()
+                && locale.len() <= MAX_LOCALE_CHARS
+                && locale.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '@')),
+            "locale",
+            "Locale must look like 'de_DE.UTF-8'",
+        );
+    }
+    // Empty clears it
+    if let Some(host) = req.tool_host.as_deref().filter(|host| !host.is_empty()) {
+        v.check(
+            (host.starts_with("http://") || host.starts_with("https://"))
+                && host.len() <= MAX_TOOL_HOST_CHARS
+                && !host.contains(char::is_whitespace),
+            "tool_host",
+            "Tool host must be an http(s) URL such as 'http://laptop:8081'",
+        );
+    }
+
+    v.finish()
+}
+
+/// Checks the request's fields and returns the decoded contents.
+pub fn validate_attachment_upload(req: &UploadAttachmentRequest) -> Result<Vec<u8>, ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(
+        !req.filename.trim().is_empty() && req.filename.chars().count() <= MAX_FILENAME_CHARS,
+        "filename",
+        format!("Filename must be 1-{} characters", MAX_FILENAME_CHARS),
+    );
+    v.check(
+        req.mime_type.split_once('/').is_some_and(|(kind, sub)| !kind.is_empty() && !sub.is_empty()),
+        "mime_type",
+        "MIME type must look like 'image/png'",
+    );
+
+    let data = match BASE64.decode(&req.data) {
+        Ok(data) => data,
+        Err(_) => {
+            v.fail("data", "Data must be base64-encoded");
+            Vec::new()
+        }
+    };
+    v.check(
+        data.len() <= MAX_ATTACHMENT_BYTES,
+        "data",
+        format!("Attachment exceeds {} bytes", MAX_ATTACHMENT_BYTES),
+    );
+
+    v.finish()?;
+    Ok(data)
+}
+
+pub fn validate_tag_request(req: &TagConversationRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    for (field, tags) in [("add", &req.add), ("remove", &req.remove)] {
+        v.check(
+            tags.len() <= MAX_TAGS_PER_REQUEST,
+            field,
+            format!("At most {} tags per request", MAX_TAGS_PER_REQUEST),
+        );
+        for tag in tags {
+            v.check(
+                normalize_tag(tag).is_some(),
+                field,
+                format!("Invalid tag '{}': use up to {} letters, digits, '-' or '_'", tag, MAX_TAG_CHARS),
+            );
+        }
+    }
+
+    v.finish()
+}
+
+pub fn validate_device_group_request(req: &SetDeviceGroupRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(
+        normalize_group(&req.name).is_some(),
+        "name",
+        format!("Invalid group name: use up to {} letters, digits, '-' or '_'", MAX_GROUP_CHARS),
+    );
+    v.check(
+        req.devices.len() <= MAX_GROUP_DEVICES,
+        "devices",
+        format!("At most {} devices per group", MAX_GROUP_DEVICES),
+    );
+    v.check(
+        req.devices.iter().all(|d| !d.trim().is_empty()),
+        "devices",
+        "Device names cannot be empty",
+    );
+
+    v.finish()
+}
+
+pub fn validate_redact_request(req: &RedactMessageRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(
+        req.text.as_deref().is_none_or(|text| !text.trim().is_empty()),
+        "text",
+        "Text to redact cannot be empty; omit it to redact the whole message",
+    );
+
+    v.finish()
+}
+
+pub fn validate_checkpoint_request(req: &CreateCheckpointRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    if let Some(name) = &req.name {
+        v.check(!name.trim().is_empty(), "name", "Checkpoint name cannot be empty; omit it for none");
+        v.check(
+            name.chars().count() <= MAX_CHECKPOINT_NAME_CHARS,
+            "name",
+            format!("Checkpoint name exceeds {} characters", MAX_CHECKPOINT_NAME_CHARS),
+        );
+    }
+
+    v.finish()
+}
+
+pub fn validate_workflow_request(req: &SaveWorkflowRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(
+        normalize_workflow_name(&req.name).is_some(),
+        "name",
+        format!("Workflow name must be up to {} letters, digits, '-' or '_'", MAX_WORKFLOW_NAME_CHARS),
+    );
+    v.check(!req.template.trim().is_empty(), "template", "Template cannot be empty");
+    v.check(
+        req.template.chars().count() <= MAX_WORKFLOW_CHARS,
+        "template",
+        format!("Template too long (max {} characters)", MAX_WORKFLOW_CHARS),
+    );
+    let parameters = workflow_parameters(&req.template);
+    for name in req.defaults.keys() {
+        v.check(
+            parameters.contains(name),
+            &format!("defaults.{}", name),
+            format!("The template has no {{{}}} placeholder", name),
+        );
+    }
+
+    v.finish()
+}
+
+pub fn validate_run_workflow_request(req: &RunWorkflowRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    check_schedule(&mut v, req.run_at, req.delay_secs);
+
+    v.finish()
+}
+
+pub fn validate_watch_request(req: &AddWatchRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(!req.path.trim().is_empty(), "path", "Path cannot be empty");
+    v.check(!req.task.trim().is_empty(), "task", "Task cannot be empty");
+    v.check(
+        req.task.chars().count() <= MAX_WATCH_TASK_CHARS,
+        "task",
+        format!("Task too long (max {} characters)", MAX_WATCH_TASK_CHARS),
+    );
+
+    v.finish()
+}
+
+pub fn validate_trigger_watch_request(req: &TriggerWatchRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    v.check(!req.paths.is_empty(), "paths", "At least one changed path required");
+    v.check(
+        req.paths.len() <= MAX_WATCH_TRIGGER_PATHS,
+        "paths",
+        format!("Too many paths (max {})", MAX_WATCH_TRIGGER_PATHS),
+    );
+
+    v.finish()
+}
+
+pub fn validate_lock_request(req: &LockConversationRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(!req.device_key.is_empty(), "device_key", "Device key required");
+    let pin_chars = req.pin.chars().count();
+    v.check(
+        (MIN_PIN_CHARS..=MAX_PIN_CHARS).contains(&pin_chars),
+        "pin",
+        format!("PIN must be {} to {} characters", MIN_PIN_CHARS, MAX_PIN_CHARS),
+    );
+
+    v.finish()
+}
+
+pub fn validate_tool_switch_request(req: &SetToolSwitchRequest) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+
+    v.check(
+        tool_switch_name(&req.name).is_some(),
+        "name",
+        format!("Unknown toolbelt '{}': use 'all' or one of {}", req.name, toolbelt_names().join(", ")),
+    );
+    v.check(
+        req.reason.as_deref().is_none_or(|r| r.chars().count() <= MAX_SWITCH_REASON_CHARS),
+        "reason",
+        format!("Reason too long (max {} characters)", MAX_SWITCH_REASON_CHARS),
+    );
+
+    v.finish()
+}
+
+/// The switch a name refers to: ALL_TOOLS, or a toolbelt as registered
+/// (`filesmith` becomes `FileSmith`).
+pub fn tool_switch_name(name: &str) -> Option<String> {
+    if name.trim().eq_ignore_ascii_case(ALL_TOOLS) {
+        return Some(ALL_TOOLS.to_string());
+    }
+    toolbelt_name(name)
+}
+
+/// A toolbelt as registered, whatever the case of `name`.
+pub fn toolbelt_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    toolbelt_names()
+        .into_iter()
+        .find(|toolbelt| toolbelt.eq_ignore_ascii_case(name))
+        .map(String::from)
+}
+
+pub fn validate_agent_name(agent: &str) -> Result<(), ApiError> {
+    let mut v = Validator::new();
+    check_agent_name(&mut v, agent);
+    v.finish()
+}
+
+fn check_agent_name(v: &mut Validator, agent: &str) {
+    if AgentType::from_name(agent).is_none() {
+        let known: Vec<&str> = AgentType::all().iter().map(|t| t.name()).collect();
+        v.fail("agent", format!(
+            "Unknown agent '{}'. Known agents: {}",
+            agent,
+            known.join(", ")
+        ));
+    }
+}
+
+// ============================================================================
+// OWNERSHIP — needs the authenticated device
+// ============================================================================
+
+/// A referenced conversation must exist and be owned by or shared with the requesting device.
+pub fn validate_conversation_access(
+    db: &Db,
+    device_id: u64,
+    conversation_id: Option<u64>,
+) -> Result<(), ApiError> {
+    let Some(conversation_id) = conversation_id else {
+        return Ok(());
+    };
+
+    let access = db.conversation_access(conversation_id, device_id)
+        .map_err(|e| ApiError::Internal {
+            message: format!("Failed to look up conversation: {}", e),
+        })?;
+
+    match access {
+        ConversationAccess::Owner | ConversationAccess::Shared => Ok(()),
+        ConversationAccess::NotFound => Err(ApiError::NotFound {
+            message: format!("Conversation {} not found", conversation_id),
+            resource: "conversation".to_string(),
+        }),
+        ConversationAccess::Denied => Err(ApiError::Forbidden {
+            message: format!("Conversation {} belongs to another device", conversation_id),
+        }),
+    }
+}
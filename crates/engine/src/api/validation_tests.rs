@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use artificer_shared::db::Db;
+    use crate::api::error::ApiError;
+    use crate::api::types::{
+        ChatRequest, DeviceMetadataRequest, LockConversationRequest, QueueJobRequest, SetDeviceGroupRequest, SetToolSwitchRequest,
+    };
+    use crate::api::validation::{
+        tool_switch_name, validate_conversation_access, validate_device_group_request, validate_lock_request,
+        validate_chat_request, validate_job_request, validate_metadata_request, validate_tool_switch_request,
+    };
+
+    fn register(db: &Db, name: &str) -> u64 {
+        db.execute(
+            "INSERT INTO devices (device_name, device_key, active, created, last_seen)
+             VALUES (?1, ?2, 1, 0, 0)",
+            rusqlite::params![name, format!("{}-key", name)],
+        ).unwrap();
+        db.query_row_optional(
+            "SELECT id FROM devices WHERE device_name = ?1",
+            rusqlite::params![name],
+            |row| row.get::<_, i64>(0),
+        ).unwrap().unwrap() as u64
+    }
+
+    fn setup() -> (Db, u64, u64, u64) {
+        let db = Db::in_memory().unwrap();
+        let owner = register(&db, "laptop");
+        let other = register(&db, "phone");
+        let conversation = db.create_conversation(owner).unwrap();
+        (db, owner, other, conversation)
+    }
+
+    #[test]
+    fn owner_can_access_conversation() {
+        let (db, owner, _, conversation) = setup();
+        assert!(validate_conversation_access(&db, owner, Some(conversation)).is_ok());
+    }
+
+    #[test]
+    fn new_conversation_needs_no_check() {
+        let (db, _, other, _) = setup();
+        assert!(validate_conversation_access(&db, other, None).is_ok());
+    }
+
+    #[test]
+    fn cross_device_access_is_forbidden() {
+        let (db, _, other, conversation) = setup();
+        let err = validate_conversation_access(&db, other, Some(conversation)).unwrap_err();
+        assert!(matches!(err, ApiError::Forbidden { .. }));
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn shared_conversation_is_accessible() {
+        let (db, _, other, conversation) = setup();
+        db.share_conversation(conversation, other).unwrap();
+        assert!(validate_conversation_access(&db, other, Some(conversation)).is_ok());
+    }
+
+    #[test]
+    fn revoked_share_is_forbidden_again() {
+        let (db, _, other, conversation) = setup();
+        db.share_conversation(conversation, other).unwrap();
+        assert!(db.unshare_conversation(conversation, other).unwrap());
+
+        let err = validate_conversation_access(&db, other, Some(conversation)).unwrap_err();
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn missing_conversation_is_not_found() {
+        let (db, owner, _, conversation) = setup();
+        let err = validate_conversation_access(&db, owner, Some(conversation + 100)).unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn tool_host_must_be_an_http_url() {
+        let request = |host: &str| DeviceMetadataRequest {
+            device_id: 1,
+            device_key: "key".to_string(),
+            timezone: None,
+            locale: None,
+            tool_host: Some(host.to_string()),
+        };
+        assert!(validate_metadata_request(&request("http://laptop.tailnet:8081")).is_ok());
+        assert!(validate_metadata_request(&request("")).is_ok());
+        assert!(validate_metadata_request(&request("laptop:8081")).is_err());
+        assert!(validate_metadata_request(&request("http://laptop 8081")).is_err());
+    }
+
+    #[test]
+    fn device_groups_need_a_valid_name() {
+        let request = |name: &str, devices: &[&str]| SetDeviceGroupRequest {
+            device_id: 1,
+            device_key: "key".to_string(),
+            name: name.to_string(),
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+        };
+        assert!(validate_device_group_request(&request("homelab", &["nas", "2"])).is_ok());
+        assert!(validate_device_group_request(&request("home lab", &["nas"])).is_err());
+        assert!(validate_device_group_request(&request("", &[])).is_err());
+        assert!(validate_device_group_request(&request("homelab", &[" "])).is_err());
+    }
+
+    #[test]
+    fn lock_pins_have_a_length_range() {
+        let request = |pin: &str| LockConversationRequest {
+            device_id: 1,
+            device_key: "key".to_string(),
+            conversation_id: 1,
+            pin: pin.to_string(),
+            remove: false,
+        };
+        assert!(validate_lock_request(&request("4821")).is_ok());
+        assert!(validate_lock_request(&request("482")).is_err());
+        assert!(validate_lock_request(&request(&"x".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn tool_switches_name_a_toolbelt_or_all() {
+        assert_eq!(tool_switch_name("filesmith").as_deref(), Some("FileSmith"));
+        assert_eq!(tool_switch_name(" ALL ").as_deref(), Some("all"));
+        assert_eq!(tool_switch_name("FileSmith::read_file"), None);
+
+        let request = |name: &str, reason: &str| SetToolSwitchRequest {
+            admin_key: "key".to_string(),
+            name: name.to_string(),
+            reason: Some(reason.to_string()),
+        };
+        assert!(validate_tool_switch_request(&request("Containers", "restart loop")).is_ok());
+        assert!(validate_tool_switch_request(&request("Nope", "")).is_err());
+        assert!(validate_tool_switch_request(&request("all", &"x".repeat(501))).is_err());
+    }
+
+    #[test]
+    fn jobs_are_delayed_one_way_and_not_too_far() {
+        let request = |run_at: Option<i64>, delay_secs: Option<u64>| QueueJobRequest {
+            device_id: 1,
+            device_key: "key".to_string(),
+            method: "title_generation".to_string(),
+            conversation_id: Some(1),
+            arguments: Some(serde_json::json!({ "user_message": "hi" })),
+            priority: None,
+            run_at,
+            delay_secs,
+            latency_hint: None,
+        };
+        assert!(validate_job_request(&request(None, Some(600))).is_ok());
+        assert!(validate_job_request(&request(Some(0), None)).is_ok());
+        assert!(validate_job_request(&request(Some(0), Some(600))).is_err());
+        assert!(validate_job_request(&request(None, Some(31 * 86_400))).is_err());
+        assert!(validate_job_request(&request(Some(i64::MAX), None)).is_err());
+    }
+
+    #[test]
+    fn model_overrides_are_for_streamed_chats() {
+        let request = |model: &str, deferred: bool| ChatRequest {
+            device_id: 1,
+            device_key: "key".to_string(),
+            conversation_id: None,
+            message: "quick question".to_string(),
+            stream: None,
+            deferred,
+            profile: None,
+            incognito: false,
+            privacy: None,
+            toolbelts: Vec::new(),
+            fresh: false,
+            debug: false,
+            verify: false,
+            model: Some(model.to_string()),
+            latency_hint: None,
+        };
+        assert!(validate_chat_request(&request("qwen3:8b", false)).is_ok());
+        assert!(validate_chat_request(&request("qwen3:8b", true)).is_err());
+        assert!(validate_chat_request(&request(" ", false)).is_err());
+        assert!(validate_chat_request(&request(&"q".repeat(129), false)).is_err());
+    }
+}
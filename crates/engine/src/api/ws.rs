@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use artificer_shared::events::ChatSocketMessage;
+use crate::api::error::ApiError;
+use crate::api::events::{EventLog, HEARTBEAT_INTERVAL};
+use crate::api::handlers::{open_chat, start_chat_turn};
+use crate::api::types::ChatRequest;
+use crate::api::validation::validate_chat_request;
+use crate::state::AppState;
+
+type Sender = SplitSink<WebSocket, Message>;
+type Receiver = SplitStream<WebSocket>;
+
+/// How long a client may hold a socket open before sending its chat request.
+pub const OPENING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// GET /chat/ws
+///
+/// The /chat event stream over a WebSocket. The client opens with a chat
+/// request and gets the same events as the SSE stream, one JSON text frame
+/// each. While a turn runs it may answer questions or cancel; once `done`
+/// arrives it may send the next message in the same conversation.
+pub async fn handle_chat_ws(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| chat_socket(state, socket))
+}
+
+async fn chat_socket(state: AppState, socket: WebSocket) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let Ok(opening) = tokio::time::timeout(OPENING_TIMEOUT, next_text(&mut receiver)).await else {
+        let error = ApiError::invalid(
+            "request",
+            format!("No chat request within {} seconds of connecting", OPENING_TIMEOUT.as_secs()),
+        );
+        return close_with_error(&mut sender, &error).await;
+    };
+    let Some(mut req) = opening.and_then(|text| serde_json::from_str::<ChatRequest>(&text).ok()) else {
+        let error = ApiError::invalid("request", "The first message must be a chat request, as for POST /chat");
+        return close_with_error(&mut sender, &error).await;
+    };
+    if req.deferred {
+        let error = ApiError::invalid("deferred", "Deferred chats are queued with POST /chat, not over a socket");
+        return close_with_error(&mut sender, &error).await;
+    }
+    let (device_id, conversation_id) = match open_chat(&state, &req) {
+        Ok(opened) => opened,
+        Err(e) => return close_with_error(&mut sender, &e).await,
+    };
+    // Later turns continue the conversation the first one opened
+    req.conversation_id = Some(conversation_id);
+
+    loop {
+        let log = match start_chat_turn(state.clone(), device_id, conversation_id, req.clone()) {
+            Ok(log) => log,
+            Err(e) => return close_with_error(&mut sender, &e).await,
+        };
+        // A client that leaves mid-turn doesn't stop it; /chat/resume follows it
+        if !follow_turn(&state, &mut sender, &mut receiver, &log, conversation_id).await {
+            return;
+        }
+
+        req.message = match next_message(&mut sender, &mut receiver, &req).await {
+            Some(message) => message,
+            None => return,
+        };
+    }
+}
+
+/// Forward a turn's events until `done`, handling the client's answers and
+/// cancels meanwhile. Returns false if the client went away.
+async fn follow_turn(
+    state: &AppState,
+    sender: &mut Sender,
+    receiver: &mut Receiver,
+    log: &Arc<EventLog>,
+    conversation_id: u64,
+) -> bool {
+    let mut events = std::pin::pin!(log.follow(0));
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else {
+                    return true;
+                };
+                if sender.send(Message::Text(event.data().to_string().into())).await.is_err() {
+                    return false;
+                }
+            }
+            text = next_text(receiver) => {
+                let Some(text) = text else {
+                    return false;
+                };
+                let error = match serde_json::from_str::<ChatSocketMessage>(&text) {
+                    Ok(ChatSocketMessage::Cancel) => {
+                        state.runtime.cancel_turn(conversation_id);
+                        None
+                    }
+                    Ok(ChatSocketMessage::Answer { question_id, answer }) => {
+                        match state.runtime.answer_question(conversation_id, question_id, answer.trim()) {
+                            Some(_) => None,
+                            None => Some(ApiError::NotFound {
+                                message: format!("Conversation {} has no such open question", conversation_id),
+                                resource: "question".to_string(),
+                            }),
+                        }
+                    }
+                    Ok(ChatSocketMessage::Message { .. }) => Some(ApiError::ResourceBusy {
+                        message: "Still answering; send the next message after `done`, or cancel first".to_string(),
+                    }),
+                    Err(e) => Some(ApiError::invalid("message", format!("Unrecognized socket message: {}", e))),
+                };
+                if let Some(error) = error
+                    && send_error(sender, &error).await.is_err()
+                {
+                    return false;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if sender.send(Message::Ping(Default::default())).await.is_err() {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Wait for the client's next chat message between turns. Returns None once
+/// the client closes the socket.
+async fn next_message(sender: &mut Sender, receiver: &mut Receiver, previous: &ChatRequest) -> Option<String> {
+    loop {
+        let text = next_text(receiver).await?;
+        let error = match serde_json::from_str::<ChatSocketMessage>(&text) {
+            Ok(ChatSocketMessage::Message { message }) => {
+                let next = ChatRequest { message, ..previous.clone() };
+                match validate_chat_request(&next) {
+                    Ok(()) => return Some(next.message),
+                    Err(e) => e,
+                }
+            }
+            Ok(_) => ApiError::invalid("type", "No turn is running; send a `message`"),
+            Err(e) => ApiError::invalid("message", format!("Unrecognized socket message: {}", e)),
+        };
+        send_error(sender, &error).await.ok()?;
+    }
+}
+
+/// The next text frame, skipping pings and the like. None once the socket
+/// is closed or broken.
+async fn next_text(receiver: &mut Receiver) -> Option<String> {
+    loop {
+        match receiver.next().await? {
+            Ok(Message::Text(text)) => return Some(text.to_string()),
+            Ok(Message::Close(_)) | Err(_) => return None,
+            Ok(_) => continue,
+        }
+    }
+}
+
+/// An `error` frame shaped like the stream's own error events, so clients
+/// handle both the same way.
+async fn send_error(sender: &mut Sender, error: &ApiError) -> Result<(), axum::Error> {
+    let frame = serde_json::json!({
+        "type": "error",
+        "message": error.message(),
+        "code": error.code(),
+    });
+    sender.send(Message::Text(frame.to_string().into())).await
+}
+
+async fn close_with_error(sender: &mut Sender, error: &ApiError) {
+    let _ = send_error(sender, error).await;
+    let _ = sender.send(Message::Close(None)).await;
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use artificer_shared::db::Db;
+use artificer_shared::embeddings::{server_unreachable, EmbeddingClient};
+
+/// What one run of the `embed` job did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedReport {
+    pub embedded: usize,
+    /// Texts the model failed on. Each is marked and tried again on later
+    /// runs until it has failed `MAX_EMBED_FAILURES` times.
+    pub failed: usize,
+}
+
+/// Embed up to `limit` of the messages and memories waiting for an
+/// embedding, newest first. A text the model fails on is logged and marked
+/// so the rest of the batch still gets embedded; an unreachable server ends
+/// the run, since every other text would fail the same way.
+pub async fn embed_pending(db: &Db, embeddings: &EmbeddingClient, limit: usize) -> Result<EmbedReport> {
+    let mut report = EmbedReport::default();
+    for item in db.unembedded(embeddings.model(), limit)? {
+        match embeddings.embed(&item.text).await {
+            Ok(vector) => {
+                db.store_embedding(item.source, item.id, embeddings.model(), &vector)?;
+                report.embedded += 1;
+            }
+            Err(e) if server_unreachable(&e) => return Err(e),
+            Err(e) => {
+                let error = format!("{:#}", e);
+                let failures = db.record_embedding_failure(item.source, item.id, embeddings.model(), &error)?;
+                eprintln!("[embed] {:?} {} failed ({} times): {}", item.source, item.id, failures, error);
+                report.failed += 1;
+            }
+        }
+    }
+    Ok(report)
+}
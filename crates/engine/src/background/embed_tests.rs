@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use artificer_shared::db::{Db, MAX_EMBED_FAILURES};
+    use artificer_shared::embeddings::EmbeddingClient;
+
+    use crate::background::embed::{embed_pending, EmbedReport};
+
+    const MODEL: &str = "mock-embed";
+
+    /// An embedding server that fails on any prompt mentioning "poison".
+    async fn embedding_server() -> String {
+        let app = Router::new().route("/api/embeddings", post(|Json(request): Json<Value>| async move {
+            if request["prompt"].as_str().unwrap_or_default().contains("poison") {
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "model crashed"));
+            }
+            Ok(Json(json!({ "embedding": [1.0, 0.0] })))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    fn db_with_messages(messages: &[&str]) -> Db {
+        let db = Db::in_memory().unwrap();
+        db.execute(
+            "INSERT INTO devices (device_name, device_key, active, created, last_seen)
+             VALUES ('laptop', 'laptop-key', 1, 0, 0)",
+            [],
+        ).unwrap();
+        let conversation = db.create_conversation(1).unwrap();
+        for message in messages {
+            db.add_message(conversation, None, "user", Some(message), None).unwrap();
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn a_failing_text_is_skipped_not_the_batch() {
+        let db = db_with_messages(&["plan a trip", "poison pill", "book a table"]);
+        let client = EmbeddingClient::new(embedding_server().await, MODEL);
+
+        let report = embed_pending(&db, &client, 10).await.unwrap();
+        assert_eq!(report, EmbedReport { embedded: 2, failed: 1 });
+
+        // The failed text is retried on later runs, then given up on
+        let pending = db.unembedded(MODEL, 10).unwrap();
+        assert_eq!(pending.iter().map(|p| p.text.as_str()).collect::<Vec<_>>(), ["poison pill"]);
+        for _ in 1..MAX_EMBED_FAILURES {
+            assert_eq!(embed_pending(&db, &client, 10).await.unwrap(), EmbedReport { embedded: 0, failed: 1 });
+        }
+        assert!(db.unembedded(MODEL, 10).unwrap().is_empty());
+        assert_eq!(db.queue_embedding(MODEL).unwrap(), None);
+        // Failed rows hold no vector for search to compare against
+        assert_eq!(db.embedded_texts(1, "default", MODEL).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_server_marks_nothing() {
+        let db = db_with_messages(&["plan a trip", "book a table"]);
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+
+        assert!(embed_pending(&db, &EmbeddingClient::new(url, MODEL), 10).await.is_err());
+        assert_eq!(db.unembedded(MODEL, 10).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use anyhow::Result;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration};
+
+use artificer_shared::db::{Db, Lease};
+
+/// Lease role for running the background worker and its periodic cleanup.
+pub const WORKER_ROLE: &str = "worker";
+
+/// How long a lease lasts without renewal. A crashed leader's jobs are
+/// picked up by another instance after at most this long.
+const LEASE_TTL_SECS: i64 = 30;
+
+/// Renew well before the lease runs out.
+const RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Leader election between engines sharing one database. Every instance
+/// serves the API; only the one holding the worker lease runs background
+/// jobs, so no job is claimed twice.
+pub struct Leadership {
+    db: Arc<Db>,
+    instance_id: String,
+    leader: AtomicBool,
+}
+
+impl Leadership {
+    pub fn new(db: Arc<Db>) -> Self {
+        let host = hostname::get()
+            .ok()
+            .and_then(|h| h.into_string().ok())
+            .unwrap_or_else(|| "engine".to_string());
+        Self {
+            db,
+            instance_id: format!("{}-{}", host, std::process::id()),
+            leader: AtomicBool::new(false),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Whether this instance held the worker lease at its last renewal.
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::SeqCst)
+    }
+
+    /// The current holder of the worker lease, whichever instance it is.
+    pub fn current_lease(&self) -> Result<Option<Lease>> {
+        self.db.get_lease(WORKER_ROLE)
+    }
+
+    /// Try to take or keep the lease now. Returns whether this instance leads.
+    pub fn renew(&self) -> bool {
+        let leader = self.db
+            .try_acquire_lease(WORKER_ROLE, &self.instance_id, LEASE_TTL_SECS)
+            .unwrap_or_else(|e| {
+                // Can't confirm the lease, so assume another instance may have it
+                eprintln!("Failed to renew worker lease: {}", e);
+                false
+            });
+
+        let was_leader = self.leader.swap(leader, Ordering::SeqCst);
+        if leader && !was_leader {
+            println!("Instance {} is now running background jobs", self.instance_id);
+        } else if !leader && was_leader {
+            println!("Instance {} lost the worker lease; background jobs paused", self.instance_id);
+        }
+        leader
+    }
+
+    /// Keep renewing until shutdown, then hand the lease back.
+    pub async fn run(&self, mut shutdown_rx: watch::Receiver<bool>) {
+        loop {
+            self.renew();
+            tokio::select! {
+                _ = sleep(RENEW_INTERVAL) => {}
+                _ = shutdown_rx.changed() => break,
+            }
+            if *shutdown_rx.borrow() {
+                break;
+            }
+        }
+    }
+
+    /// Give the lease up so a standby instance takes over right away.
+    pub fn release(&self) {
+        if self.leader.swap(false, Ordering::SeqCst)
+            && let Err(e) = self.db.release_lease(WORKER_ROLE, &self.instance_id)
+        {
+            eprintln!("Failed to release worker lease: {}", e);
+        }
+    }
+}
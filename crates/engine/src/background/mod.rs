@@ -1,15 +1,64 @@
 use std::sync::Arc;
 use anyhow::Result;
 use tokio::time::{sleep, Duration};
-use tokio::sync::watch;
+use futures_util::StreamExt;
+use tokio::sync::{broadcast, watch};
+use artificer_shared::events::JobEvent;
 use artificer_shared::rusqlite;
 
-use crate::pool::{AgentPool, GpuPool};
+use crate::agent::state::ExecutionContext;
+use crate::agent::AgentExecution;
+use crate::api::events::{EventLog, EventSender};
+use crate::pool::{AgentPool, GpuHandle, GpuPool, LatencyHint};
+use crate::state::{RuntimeState, TurnGuard};
+
+mod leader;
+pub mod summarize;
+pub mod embed;
+
+#[cfg(test)]
+mod summarize_tests;
+#[cfg(test)]
+mod embed_tests;
+pub use leader::{Leadership, WORKER_ROLE};
+
+/// Job methods the worker knows how to run, with the arguments each requires.
+pub const JOB_METHODS: &[(&str, &[&str])] = &[
+    ("title_generation", &["conversation_id", "user_message"]),
+    // Run any agent against `goal`, storing its final response as the result.
+    // /jobs opens a new conversation when none is given.
+    ("agent", &["agent", "goal"]),
+    // Summarize a conversation of any length, chunk by chunk. Optional
+    // `chunk_chars` sets how much transcript each step reads.
+    ("summarize", &["conversation_id"]),
+    // Embed messages and memories that have no embedding yet, a batch at a
+    // time. Queued by the worker itself while an embedding model is set.
+    ("embed", &[]),
+];
+
+/// Required arguments for a job method, or None if the worker can't run it.
+pub fn job_required_arguments(method: &str) -> Option<&'static [&'static str]> {
+    JOB_METHODS.iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, args)| *args)
+}
+
+/// Highest priority a queued job may request.
+pub const MAX_JOB_PRIORITY: u32 = 10;
+
+/// Furthest ahead a job may be scheduled: 30 days.
+pub const MAX_JOB_DELAY_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Pending jobs read per page while looking past ones that wait on busy conversations.
+const JOB_CANDIDATES: i64 = 20;
+
+/// Messages and memories one `embed` job embeds; the worker queues another
+/// while more are waiting.
+const EMBED_BATCH: usize = 64;
 
 #[derive(Debug)]
 struct PendingJob {
     id: i64,
-    #[allow(dead_code)]
     device_id: Option<i64>,
     method: String,
     arguments: serde_json::Value,
@@ -29,6 +78,14 @@ impl PendingJob {
     }
 }
 
+/// A job event plus the device it belongs to, so subscribers can route it.
+/// Sent on a broadcast channel; events with no subscribers are dropped.
+#[derive(Debug, Clone)]
+pub struct JobNotice {
+    pub device_id: Option<u64>,
+    pub event: JobEvent,
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct WorkerHealth {
     pub pending_jobs: u64,
@@ -43,6 +100,11 @@ pub struct Worker {
     poll_interval: Duration,
     shutdown_rx: watch::Receiver<bool>,
     last_cleanup: Arc<std::sync::Mutex<std::time::Instant>>,
+    last_purge: Arc<std::sync::Mutex<std::time::Instant>>,
+    last_embedding_check: Arc<std::sync::Mutex<std::time::Instant>>,
+    events: broadcast::Sender<JobNotice>,
+    runtime: Arc<RuntimeState>,
+    leadership: Arc<Leadership>,
 }
 
 impl Worker {
@@ -51,6 +113,9 @@ impl Worker {
         gpu_pool: Arc<GpuPool>,
         poll_interval_secs: u64,
         shutdown_rx: watch::Receiver<bool>,
+        events: broadcast::Sender<JobNotice>,
+        runtime: Arc<RuntimeState>,
+        leadership: Arc<Leadership>,
     ) -> Self {
         Self {
             agent_pool,
@@ -58,6 +123,11 @@ impl Worker {
             poll_interval: Duration::from_secs(poll_interval_secs),
             shutdown_rx,
             last_cleanup: Arc::new(std::sync::Mutex::new(std::time::Instant::now())),
+            last_purge: Arc::new(std::sync::Mutex::new(std::time::Instant::now())),
+            last_embedding_check: Arc::new(std::sync::Mutex::new(std::time::Instant::now())),
+            events,
+            runtime,
+            leadership,
         }
     }
 
@@ -70,7 +140,17 @@ impl Worker {
                 break;
             }
 
-            if let Err(e) = self.process_next_job().await {
+            // Another instance sharing the database runs the jobs
+            if !self.leadership.is_leader() {
+                sleep(self.poll_interval).await;
+                continue;
+            }
+
+            // Every job needs a model; with no server reachable they wait
+            // in the queue while the maintenance below carries on
+            if !self.gpu_pool.is_degraded()
+                && let Err(e) = self.process_next_job().await
+            {
                 eprintln!("Worker error: {}", e);
             }
 
@@ -87,6 +167,30 @@ impl Worker {
                 }
             }
 
+            // Expired incognito conversations (every 5 minutes)
+            {
+                let mut last = self.last_purge.lock().unwrap();
+                if last.elapsed().as_secs() > 300 {
+                    match self.agent_pool.db().purge_expired_conversations() {
+                        Ok(0) => {}
+                        Ok(count) => println!("Deleted {} expired incognito conversations", count),
+                        Err(e) => eprintln!("Incognito purge failed: {}", e),
+                    }
+                    *last = std::time::Instant::now();
+                }
+            }
+
+            // New history waiting for embeddings (every minute)
+            if let Some(embeddings) = self.agent_pool.embeddings() {
+                let mut last = self.last_embedding_check.lock().unwrap();
+                if last.elapsed().as_secs() > 60 {
+                    if let Err(e) = self.agent_pool.db().queue_embedding(embeddings.model()) {
+                        eprintln!("Queueing embeddings failed: {}", e);
+                    }
+                    *last = std::time::Instant::now();
+                }
+            }
+
             sleep(self.poll_interval).await;
         }
 
@@ -100,10 +204,20 @@ impl Worker {
         let start_time = std::time::Instant::now();
 
         loop {
+            // Draining can outlast the lease, so keep it while jobs run
+            if !self.leadership.renew() {
+                println!("Another instance runs background jobs; leaving the queue to it");
+                break;
+            }
+
             let has_pending = self.has_pending_jobs()?;
             if !has_pending {
                 break;
             }
+            if self.gpu_pool.is_degraded() {
+                println!("No model server reachable; leaving the remaining jobs queued");
+                break;
+            }
 
             if let Err(e) = self.process_next_job().await {
                 eprintln!("Error during drain: {}", e);
@@ -172,10 +286,15 @@ impl Worker {
         }
     }
 
+    /// Whether anything is running or due. Jobs scheduled for later are
+    /// left for the next start rather than waited on.
     fn has_pending_jobs(&self) -> Result<bool> {
         let conn = self.agent_pool.db().lock()?;
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM background WHERE status IN ('pending', 'running')",
+            "SELECT COUNT(*) FROM background
+             WHERE status = 'running'
+                OR (status = 'pending'
+                    AND (run_at IS NULL OR run_at <= CAST(strftime('%s', 'now') AS INTEGER)))",
             [],
             |row| row.get(0)
         )?;
@@ -183,86 +302,306 @@ impl Worker {
     }
 
     async fn process_next_job(&self) -> Result<()> {
-        let job = self.agent_pool.db().query_row_optional(
-            "SELECT id, device_id, method, arguments FROM background
-             WHERE status = 'pending'
-             ORDER BY priority DESC, created_at ASC
-             LIMIT 1",
-            [],
-            PendingJob::from_row
-        )?;
-
-        let Some(job) = job else {
+        let Some((job, _turn_guard)) = self.next_runnable_job()? else {
             return Ok(());
         };
 
-        let gpu = match self.gpu_pool.acquire_background() {
+        let hint = job.arguments["latency_hint"].as_str().and_then(LatencyHint::parse).unwrap_or_default();
+        let gpu = match self.gpu_pool.acquire_background_for(hint) {
             Some(gpu) => gpu,
             None => return Ok(()),
         };
         let gpu_id = gpu.id.clone();
 
-        self.mark_job_running(job.id)?;
+        if let Err(e) = self.mark_job_running(job.id) {
+            self.gpu_pool.release(&gpu_id);
+            return Err(e);
+        }
+        self.emit(&job, JobEvent::JobStarted {
+            job_id: job.id as u64,
+            method: job.method.clone(),
+        });
 
         let result = match job.method.as_str() {
-            "title_generation" => {
-                let agent = match self.agent_pool.get("TitleGenerator") {
-                    Some(a) => a,
-                    None => {
-                        self.gpu_pool.release(&gpu_id);
-                        return Err(anyhow::anyhow!("TitleGenerator agent not found"));
-                    }
-                };
-
-                let conversation_id = job.arguments["conversation_id"]
-                    .as_u64()
-                    .ok_or_else(|| anyhow::anyhow!("Missing conversation_id in job args"))?;
-                let user_message = job.arguments["user_message"]
-                    .as_str()
-                    .ok_or_else(|| anyhow::anyhow!("Missing user_message in job args"))?
-                    .to_string();
-
-                let context = crate::agent::state::ExecutionContext {
-                    device_id: job.device_id.unwrap_or(0) as u64,
-                    device_key: String::new(),
-                    conversation_id,
-                    parent_task_id: None,
-                    gpu: gpu.clone(),
-                    events: None,
-                    db: self.agent_pool.db().clone(),
-                };
-
-                let execution = crate::agent::AgentExecution::new(
-                    agent,
-                    context,
-                    &user_message,
-                    &self.agent_pool,
-                );
-
-                let response = execution.execute(self.agent_pool.clone()).await?;
-
-                let device_id = job.device_id.unwrap_or(0);
-                self.agent_pool
-                    .db()
-                    .set_conversation_title(conversation_id, device_id, &response.content)?;
-
-                Ok(format!("Set title: {}", response.content))
-            }
+            "title_generation" => self.run_title_generation(&job, &gpu).await,
+            "agent" => self.run_agent(&job, &gpu).await,
+            "summarize" => self.run_summarize(&job, &gpu).await,
+            "embed" => self.run_embed().await,
             other => Err(anyhow::anyhow!("Unknown job method: {}", other)),
         };
 
         self.gpu_pool.release(&gpu_id);
 
         match result {
-            Ok(res) => self.mark_job_complete(job.id, &res)?,
+            Ok(res) => {
+                self.mark_job_complete(job.id, &res)?;
+                self.emit(&job, JobEvent::JobCompleted {
+                    job_id: job.id as u64,
+                    method: job.method.clone(),
+                    conversation_id: job.arguments["conversation_id"].as_u64(),
+                    result: res,
+                });
+            }
             Err(e) => {
-                let _ = self.mark_job_failed(job.id, &e.to_string())?;
+                let exhausted = self.mark_job_failed(job.id, &e.to_string())?;
+                self.emit(&job, JobEvent::JobFailed {
+                    job_id: job.id as u64,
+                    method: job.method.clone(),
+                    error: e.to_string(),
+                    retrying: !exhausted,
+                });
             }
         }
 
         Ok(())
     }
 
+    /// The most urgent due job that can start now. An agent job waits
+    /// while its conversation is answering something else, and holds the
+    /// conversation while it runs. Candidates are read a page at a time, so
+    /// jobs behind a run of waiting ones still get their turn.
+    fn next_runnable_job(&self) -> Result<Option<(PendingJob, Option<TurnGuard>)>> {
+        let mut offset = 0;
+        loop {
+            let candidates = {
+                let conn = self.agent_pool.db().reader()?;
+                let mut stmt = conn.prepare_cached(
+                    "SELECT id, device_id, method, arguments FROM background
+                     WHERE status = 'pending'
+                       AND (run_at IS NULL OR run_at <= CAST(strftime('%s', 'now') AS INTEGER))
+                     ORDER BY priority DESC, created_at ASC, id ASC
+                     LIMIT ?1 OFFSET ?2",
+                )?;
+                let rows = stmt.query_map(rusqlite::params![JOB_CANDIDATES, offset], PendingJob::from_row)?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            let last_page = (candidates.len() as i64) < JOB_CANDIDATES;
+
+            for job in candidates {
+                let conversation_id = job.arguments["conversation_id"].as_u64();
+                match conversation_id {
+                    Some(conversation_id) if job.method == "agent" => {
+                        if let Some(guard) = self.runtime.begin_job(conversation_id, job.id as u64) {
+                            return Ok(Some((job, Some(guard))));
+                        }
+                    }
+                    _ => return Ok(Some((job, None))),
+                }
+            }
+            if last_page {
+                return Ok(None);
+            }
+            offset += JOB_CANDIDATES;
+        }
+    }
+
+    fn emit(&self, job: &PendingJob, event: JobEvent) {
+        let _ = self.events.send(JobNotice {
+            device_id: job.device_id.map(|id| id as u64),
+            event,
+        });
+    }
+
+    fn job_context(&self, job: &PendingJob, gpu: &GpuHandle) -> Result<ExecutionContext> {
+        let conversation_id = job.arguments["conversation_id"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing conversation_id in job args"))?;
+
+        Ok(ExecutionContext {
+            device_id: job.device_id.unwrap_or(0) as u64,
+            device_key: String::new(),
+            conversation_id,
+            parent_task_id: None,
+            gpu: gpu.clone(),
+            events: None,
+            db: self.agent_pool.db().clone(),
+            runtime: self.runtime.clone(),
+            fresh: job.arguments["fresh"].as_bool().unwrap_or(false),
+            debug: job.arguments["debug"].as_bool().unwrap_or(false),
+            verify: job.arguments["verify"].as_bool().unwrap_or(false),
+            target: None,
+        })
+    }
+
+    async fn run_title_generation(&self, job: &PendingJob, gpu: &GpuHandle) -> Result<String> {
+        let agent = self.agent_pool.get("TitleGenerator")
+            .ok_or_else(|| anyhow::anyhow!("TitleGenerator agent not found"))?;
+
+        let user_message = job.arguments["user_message"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing user_message in job args"))?;
+
+        let context = self.job_context(job, gpu)?;
+        let conversation_id = context.conversation_id;
+
+        let execution = AgentExecution::new(agent, context, user_message, &self.agent_pool);
+        let response = execution.execute(self.agent_pool.clone()).await?;
+
+        let device_id = job.device_id.unwrap_or(0);
+        self.agent_pool
+            .db()
+            .set_conversation_title(conversation_id, device_id, &response.content)?;
+
+        Ok(format!("Set title: {}", response.content))
+    }
+
+    /// Run an interactive agent unattended. Its messages land in the conversation as usual;
+    /// the final response is also stored as the job result.
+    async fn run_agent(&self, job: &PendingJob, gpu: &GpuHandle) -> Result<String> {
+        let name = job.arguments["agent"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing agent in job args"))?;
+        let agent = self.agent_pool.get(name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent: {}", name))?;
+
+        let goal = job.arguments["goal"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing goal in job args"))?;
+
+        let mut context = self.job_context(job, gpu)?;
+
+        // Client-side tools authenticate against envoy with the device key
+        if let Some(device_id) = job.device_id {
+            context.device_key = self.agent_pool.db()
+                .get_device_key(device_id as u64)?
+                .unwrap_or_default();
+        }
+
+        let execution = AgentExecution::new(agent, context, goal, &self.agent_pool);
+        let response = execution.execute(self.agent_pool.clone()).await?;
+
+        if !response.success {
+            return Err(anyhow::anyhow!("{} failed: {}", name, response.content));
+        }
+
+        Ok(response.content)
+    }
+
+    /// Summarize a whole conversation by map-reduce: the Summarizer condenses
+    /// each chunk of the transcript, then the SummaryMerger folds the chunk
+    /// summaries together until one is left. No single prompt holds more
+    /// than about a chunk, however long the conversation.
+    async fn run_summarize(&self, job: &PendingJob, gpu: &GpuHandle) -> Result<String> {
+        let conversation_id = job.arguments["conversation_id"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing conversation_id in job args"))?;
+        let chunk_chars = job.arguments["chunk_chars"]
+            .as_u64()
+            .map(|chars| chars as usize)
+            .unwrap_or(summarize::DEFAULT_CHUNK_CHARS)
+            .clamp(summarize::MIN_CHUNK_CHARS, summarize::MAX_CHUNK_CHARS);
+
+        let messages = self.agent_pool.db().get_messages(conversation_id)?;
+        let chunks = summarize::chunk(&summarize::transcript(&messages), chunk_chars);
+        if chunks.is_empty() {
+            return Err(anyhow::anyhow!("Conversation {} has nothing to summarize", conversation_id));
+        }
+
+        // Earlier steps report progress; the step that writes the result
+        // streams it, as a chat answer would
+        if let [chunk] = chunks.as_slice() {
+            let request = summarize::map_request(1, 1, chunk);
+            return self.run_one_time_streaming("Summarizer", job, gpu, &request).await;
+        }
+        let mut summaries = Vec::with_capacity(chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            self.progress(job, format!("Summarizing part {} of {}", i + 1, chunks.len()));
+            let request = summarize::map_request(i + 1, chunks.len(), chunk);
+            summaries.push(self.run_one_time("Summarizer", job, gpu, &request).await?);
+        }
+
+        while summaries.len() > 1 {
+            let batches = summarize::merge_batches(&summaries, chunk_chars);
+            if let [batch] = batches.as_slice() {
+                self.progress(job, format!("Writing the summary from {} parts", batch.len()));
+                let request = summarize::reduce_request(batch);
+                return self.run_one_time_streaming("SummaryMerger", job, gpu, &request).await;
+            }
+            self.progress(job, format!("Merging {} summaries into {}", summaries.len(), batches.len()));
+            let mut merged = Vec::new();
+            for batch in batches {
+                match batch.as_slice() {
+                    [single] => merged.push(single.clone()),
+                    _ => {
+                        let request = summarize::reduce_request(&batch);
+                        merged.push(self.run_one_time("SummaryMerger", job, gpu, &request).await?);
+                    }
+                }
+            }
+            summaries = merged;
+        }
+
+        Ok(summaries.pop().unwrap_or_default())
+    }
+
+    /// Embed a batch of the messages and memories that have no embedding
+    /// from the configured model yet, newest first.
+    async fn run_embed(&self) -> Result<String> {
+        let embeddings = self.agent_pool.embeddings()
+            .ok_or_else(|| anyhow::anyhow!("No embedding model configured (set EMBEDDING_MODEL)"))?;
+        let report = embed::embed_pending(self.agent_pool.db(), embeddings, EMBED_BATCH).await?;
+        match report.failed {
+            0 => Ok(format!("Embedded {} messages and memories", report.embedded)),
+            failed => Ok(format!("Embedded {} messages and memories; {} failed", report.embedded, failed)),
+        }
+    }
+
+    /// Run a OneTime agent on `input` in the job's conversation and return its output.
+    async fn run_one_time(&self, name: &str, job: &PendingJob, gpu: &GpuHandle, input: &str) -> Result<String> {
+        let agent = self.agent_pool.get(name)
+            .ok_or_else(|| anyhow::anyhow!("{} agent not found", name))?;
+        let context = self.job_context(job, gpu)?;
+        let execution = AgentExecution::new(agent, context, input, &self.agent_pool);
+        Ok(execution.execute(self.agent_pool.clone()).await?.content)
+    }
+
+    /// Like `run_one_time`, but the model's text is sent to the device's job
+    /// stream as `job_chunk` events while it is written.
+    async fn run_one_time_streaming(&self, name: &str, job: &PendingJob, gpu: &GpuHandle, input: &str) -> Result<String> {
+        let agent = self.agent_pool.get(name)
+            .ok_or_else(|| anyhow::anyhow!("{} agent not found", name))?;
+        let log = EventLog::new();
+        let sender = EventSender::new(log.clone());
+        let mut context = self.job_context(job, gpu)?;
+        let conversation_id = context.conversation_id;
+        context.events = Some(sender.clone());
+
+        let forwarder = {
+            let events = self.events.clone();
+            let device_id = job.device_id.map(|id| id as u64);
+            let job_id = job.id as u64;
+            let mut turn = Box::pin(log.follow(0));
+            tokio::spawn(async move {
+                while let Some(event) = turn.next().await {
+                    let Ok(data) = serde_json::from_str::<serde_json::Value>(event.data()) else {
+                        continue;
+                    };
+                    if data["type"] != "stream_chunk" {
+                        continue;
+                    }
+                    let content = data["content"].as_str().unwrap_or_default().to_string();
+                    let _ = events.send(JobNotice { device_id, event: JobEvent::JobChunk { job_id, content } });
+                }
+            })
+        };
+
+        let execution = AgentExecution::new(agent, context, input, &self.agent_pool);
+        let result = execution.execute(self.agent_pool.clone()).await;
+        // Ends the forwarder once it has passed on every chunk, so they all
+        // arrive before job_completed
+        sender.done(conversation_id);
+        let _ = forwarder.await;
+        Ok(result?.content)
+    }
+
+    fn progress(&self, job: &PendingJob, message: String) {
+        self.emit(job, JobEvent::JobProgress {
+            job_id: job.id as u64,
+            method: job.method.clone(),
+            message,
+        });
+    }
+
     fn mark_job_running(&self, job_id: i64) -> Result<()> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
@@ -327,15 +666,15 @@ impl Worker {
                     |row| row.get(0),
                 )?;
 
-                if let Ok(args) = serde_json::from_str::<serde_json::Value>(&args_str) {
-                    if let Some(conv_id) = args["conversation_id"].as_i64() {
-                        let hash = &uuid::Uuid::new_v4().to_string()[..8];
-                        let fallback = format!("conversation_{}", hash);
-                        let _ = conn.execute(
-                            "UPDATE conversations SET title = ?1 WHERE id = ?2 AND title IS NULL",
-                            rusqlite::params![fallback, conv_id],
-                        );
-                    }
+                if let Ok(args) = serde_json::from_str::<serde_json::Value>(&args_str)
+                    && let Some(conv_id) = args["conversation_id"].as_i64()
+                {
+                    let hash = &uuid::Uuid::new_v4().to_string()[..8];
+                    let fallback = format!("conversation_{}", hash);
+                    let _ = conn.execute(
+                        "UPDATE conversations SET title = ?1 WHERE id = ?2 AND title IS NULL",
+                        rusqlite::params![fallback, conv_id],
+                    );
                 }
             }
         }
@@ -0,0 +1,94 @@
+use artificer_shared::Message;
+
+/// Characters of transcript each map step summarizes, unless a job asks
+/// for another size.
+pub const DEFAULT_CHUNK_CHARS: usize = 12_000;
+
+/// Smallest and largest chunk a job may ask for. Below the minimum the
+/// merges outnumber the useful work; above the maximum a chunk no longer
+/// fits a small model's context.
+pub const MIN_CHUNK_CHARS: usize = 2_000;
+pub const MAX_CHUNK_CHARS: usize = 48_000;
+
+const SEPARATOR: &str = "\n\n";
+
+/// The conversation as `role: text` lines, user and assistant turns only.
+pub fn transcript(messages: &[Message]) -> Vec<String> {
+    messages.iter()
+        .filter(|m| m.role == "user" || m.role == "assistant")
+        .filter_map(|m| {
+            let content = m.content.as_deref()?.trim();
+            (!content.is_empty()).then(|| format!("{}: {}", m.role, content))
+        })
+        .collect()
+}
+
+/// Pack lines into chunks of at most `chunk_chars` characters, in order.
+/// A line longer than a chunk is split across several.
+pub fn chunk(lines: &[String], chunk_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+
+    for line in lines {
+        for piece in split_long(line, chunk_chars) {
+            let len = piece.chars().count();
+            if current_chars > 0 && current_chars + SEPARATOR.len() + len > chunk_chars {
+                chunks.push(std::mem::take(&mut current));
+                current_chars = 0;
+            }
+            if current_chars > 0 {
+                current.push_str(SEPARATOR);
+                current_chars += SEPARATOR.len();
+            }
+            current.push_str(&piece);
+            current_chars += len;
+        }
+    }
+    if current_chars > 0 {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Group consecutive summaries to merge together, each group within
+/// `chunk_chars` where possible. Every group but a trailing one holds at
+/// least two, so each round of merging leaves fewer summaries.
+pub fn merge_batches(summaries: &[String], chunk_chars: usize) -> Vec<Vec<String>> {
+    let mut batches: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut current_chars = 0;
+
+    for summary in summaries {
+        let len = summary.chars().count();
+        if current.len() >= 2 && current_chars + len > chunk_chars {
+            batches.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current.push(summary.clone());
+        current_chars += len;
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// The map step's input: one chunk of the transcript.
+pub fn map_request(part: usize, parts: usize, chunk: &str) -> String {
+    format!("Part {} of {} of a conversation:\n\n{}", part, parts, chunk)
+}
+
+/// The reduce step's input: summaries of consecutive parts, in order.
+pub fn reduce_request(summaries: &[String]) -> String {
+    format!(
+        "Summaries of consecutive parts of one conversation, in order:\n\n{}",
+        summaries.join("\n\n---\n\n")
+    )
+}
+
+/// Split text into pieces of at most `max_chars` characters.
+fn split_long(text: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks(max_chars.max(1)).map(|piece| piece.iter().collect()).collect()
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use artificer_shared::Message;
+
+    use crate::background::summarize::{chunk, merge_batches, transcript};
+
+    fn message(role: &str, content: Option<&str>) -> Message {
+        Message { role: role.to_string(), content: content.map(String::from), tool_calls: None }
+    }
+
+    #[test]
+    fn transcript_keeps_what_was_said() {
+        let lines = transcript(&[
+            message("system", Some("You are helpful.")),
+            message("user", Some(" plan a shed ")),
+            message("assistant", None),
+            message("tool", Some("{\"ok\":true}")),
+            message("assistant", Some("Timber or steel?")),
+        ]);
+        assert_eq!(lines, ["user: plan a shed", "assistant: Timber or steel?"]);
+    }
+
+    #[test]
+    fn chunks_stay_within_their_size_and_keep_order() {
+        let lines: Vec<String> = (0..10).map(|i| format!("user: {}", i.to_string().repeat(40))).collect();
+        let chunks = chunk(&lines, 100);
+
+        assert!(chunks.iter().all(|c| c.chars().count() <= 100));
+        assert_eq!(chunks.join("\n\n"), lines.join("\n\n"));
+
+        let long = vec!["é".repeat(250)];
+        let pieces = chunk(&long, 100);
+        assert_eq!(pieces.iter().map(|p| p.chars().count()).collect::<Vec<_>>(), [100, 100, 50]);
+    }
+
+    #[test]
+    fn every_merge_round_shrinks() {
+        let big: Vec<String> = (0..5).map(|i| i.to_string().repeat(80)).collect();
+        let batches = merge_batches(&big, 100);
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+
+        let small: Vec<String> = (0..5).map(|i| i.to_string().repeat(10)).collect();
+        assert_eq!(merge_batches(&small, 100).len(), 1);
+    }
+}
@@ -0,0 +1,252 @@
+//! `artificer-engine doctor`: checks every part of the stack the engine
+//! depends on and prints a readiness report, without starting the server.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+
+use artificer_shared::db::Db;
+use artificer_shared::executor::ToolExecutor;
+use artificer_shared::tools::toolbelts::web_search::WebSearch;
+use crate::agent::{AgentExecution, ExecutionContext};
+use crate::pool::gpu_pool::{GpuConfig, HardwareConfig};
+use crate::pool::{AgentPool, GpuHandle, GpuRole};
+use crate::state::RuntimeState;
+
+/// How long each network check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pass, warning and failure counts, printed as the checks run.
+#[derive(Default)]
+struct Report {
+    warnings: usize,
+    failures: usize,
+}
+
+impl Report {
+    fn pass(&mut self, check: &str, detail: impl AsRef<str>) {
+        println!("  ✓ {} — {}", check, detail.as_ref());
+    }
+
+    fn warn(&mut self, check: &str, detail: impl AsRef<str>) {
+        self.warnings += 1;
+        println!("  ⚠ {} — {}", check, detail.as_ref());
+    }
+
+    fn fail(&mut self, check: &str, detail: impl AsRef<str>) {
+        self.failures += 1;
+        println!("  ✗ {} — {}", check, detail.as_ref());
+    }
+}
+
+/// Run every check. Returns whether the engine is ready to serve; warnings
+/// (an optional piece missing) don't count against that.
+pub async fn run() -> Result<bool> {
+    let mut report = Report::default();
+    let http = reqwest::Client::builder().timeout(CHECK_TIMEOUT).build()?;
+
+    println!("→ Database");
+    let db = check_database(&mut report);
+
+    println!("→ Ollama hosts");
+    let ready_gpus = check_ollama(&mut report, &http).await;
+
+    println!("→ Web search");
+    check_brave(&mut report, db.clone()).await;
+
+    println!("→ Client tools");
+    check_envoy(&mut report, &http).await;
+
+    println!("→ End to end");
+    match ready_gpus.iter().find(|gpu| gpu.role == GpuRole::Interactive).or(ready_gpus.first()) {
+        Some(gpu) => check_end_to_end(&mut report, gpu).await,
+        None => report.fail("request", "skipped, no Ollama host has its model"),
+    }
+
+    println!();
+    let ready = report.failures == 0;
+    if ready {
+        println!("Ready ({} warning{}).", report.warnings, if report.warnings == 1 { "" } else { "s" });
+    } else {
+        println!("Not ready: {} check{} failed, {} warning{}.",
+            report.failures, if report.failures == 1 { "" } else { "s" },
+            report.warnings, if report.warnings == 1 { "" } else { "s" });
+    }
+    Ok(ready)
+}
+
+/// Open memory.db (which applies pending migrations) and check its integrity.
+fn check_database(report: &mut Report) -> Option<Arc<Db>> {
+    let path = match std::env::current_dir() {
+        Ok(dir) => dir.join("memory.db"),
+        Err(e) => {
+            report.fail("open", format!("no working directory: {}", e));
+            return None;
+        }
+    };
+
+    let db = match Db::open(&path) {
+        Ok(db) => {
+            report.pass("open", format!("{} (schema up to date)", path.display()));
+            Arc::new(db)
+        }
+        Err(e) => {
+            report.fail("open", format!("{}: {}", path.display(), e));
+            return None;
+        }
+    };
+
+    match db.integrity_check() {
+        Ok(problems) if problems.is_empty() => report.pass("integrity", "ok"),
+        Ok(problems) => report.fail("integrity", problems.join("; ")),
+        Err(e) => report.fail("integrity", e.to_string()),
+    }
+    Some(db)
+}
+
+/// Ping each host in hardware.json and confirm its model is pulled. Returns
+/// the GPUs that passed.
+async fn check_ollama(report: &mut Report, http: &reqwest::Client) -> Vec<GpuConfig> {
+    let config = match HardwareConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            report.fail("hardware.json", e.to_string());
+            return Vec::new();
+        }
+    };
+
+    let mut ready = Vec::new();
+    for gpu in config.gpus {
+        let check = format!("{} ({})", gpu.id, gpu.url);
+        let models = match list_models(http, &gpu.url).await {
+            Ok(models) => models,
+            Err(e) => {
+                report.fail(&check, format!("unreachable: {}", e));
+                continue;
+            }
+        };
+
+        // Ollama lists untagged models as `name:latest`
+        let pulled = |model: &str| {
+            let tagged = format!("{}:latest", model);
+            models.iter().any(|m| m == model || *m == tagged)
+        };
+        if !pulled(&gpu.model) {
+            report.fail(&check, format!("model {} not pulled (run `ollama pull {}`)", gpu.model, gpu.model));
+            continue;
+        }
+        // Models chats may ask for by name are optional, so only warned about
+        let missing: Vec<&String> = gpu.models.iter().filter(|m| !pulled(m)).collect();
+        if missing.is_empty() {
+            report.pass(&check, format!("{} available", gpu.model));
+        } else {
+            report.warn(&check, format!(
+                "{} available; not pulled: {}",
+                gpu.model,
+                missing.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        ready.push(gpu);
+    }
+    ready
+}
+
+async fn list_models(http: &reqwest::Client, url: &str) -> Result<Vec<String>> {
+    let tags: serde_json::Value = http
+        .get(format!("{}/api/tags", url))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(tags["models"]
+        .as_array()
+        .map(|models| models.iter().filter_map(|m| m["name"].as_str().map(String::from)).collect())
+        .unwrap_or_default())
+}
+
+async fn check_brave(report: &mut Report, db: Option<Arc<Db>>) {
+    let Some(db) = db else {
+        report.fail("brave", "skipped, no database");
+        return;
+    };
+    match WebSearch::from_env(db, WebSearch::quota_from_env()) {
+        None => report.warn("brave", "BRAVE_API_KEY not set (web search disabled)"),
+        Some(web_search) => {
+            let health = web_search.check_health().await;
+            match (&health.message, health.remaining) {
+                (Some(message), _) => report.fail("brave", message),
+                _ if health.exhausted() => report.warn("brave", "API key accepted, but the monthly quota is exhausted"),
+                (None, Some(remaining)) => report.pass("brave", format!("API key accepted, {} searches left this month", remaining)),
+                (None, None) => report.pass("brave", "API key accepted"),
+            }
+        }
+    }
+}
+
+/// Client tools run in envoy on the user's device, so an unreachable envoy
+/// is a warning: server-side tools still work.
+async fn check_envoy(report: &mut Report, http: &reqwest::Client) {
+    let url = std::env::var("ENVOY_URL").unwrap_or_else(|_| "http://localhost:8081".to_string());
+
+    // Any HTTP answer means the tool server is up; these credentials are meant to be refused
+    let probe = http
+        .post(format!("{}/shared/execute", url))
+        .json(&serde_json::json!({ "device_id": 0, "device_key": "", "tool_name": "", "arguments": {} }))
+        .send()
+        .await;
+    match probe {
+        Ok(_) => report.pass("envoy", format!("tool server answering at {}", url)),
+        Err(e) => report.warn("envoy", format!("no tool server at {} ({})", url, e)),
+    }
+}
+
+/// Run one tiny request through the agent stack against a throwaway database.
+async fn check_end_to_end(report: &mut Report, gpu: &GpuConfig) {
+    let started = Instant::now();
+    match title_request(gpu).await {
+        Ok(title) => report.pass("request", format!(
+            "TitleGenerator on {} answered \"{}\" in {:.1}s",
+            gpu.id, title.trim(), started.elapsed().as_secs_f32()
+        )),
+        Err(e) => report.fail("request", format!("TitleGenerator on {}: {}", gpu.id, e)),
+    }
+}
+
+async fn title_request(gpu: &GpuConfig) -> Result<String> {
+    let db = Arc::new(Db::in_memory()?);
+    db.execute(
+        "INSERT INTO devices (device_name, device_key, active, created, last_seen)
+         VALUES ('doctor', 'doctor', 1, 0, 0)",
+        [],
+    )?;
+    let device_id = db.query_row_optional("SELECT id FROM devices", [], |row| row.get::<_, i64>(0))?
+        .ok_or_else(|| anyhow::anyhow!("test device missing"))? as u64;
+    let conversation_id = db.create_conversation(device_id)?;
+
+    let pool = Arc::new(AgentPool::new(db.clone(), Arc::new(ToolExecutor::new(None))));
+    let agent = pool.get("TitleGenerator")
+        .ok_or_else(|| anyhow::anyhow!("TitleGenerator agent not found"))?;
+
+    let context = ExecutionContext {
+        device_id,
+        device_key: "doctor".to_string(),
+        conversation_id,
+        parent_task_id: None,
+        gpu: GpuHandle::from_config(gpu),
+        events: None,
+        db,
+        runtime: Arc::new(RuntimeState::new()),
+        fresh: true,
+        debug: false,
+        verify: false,
+        target: None,
+    };
+    let execution = AgentExecution::new(agent, context, "Checking that the assistant is up and running", &pool);
+    let response = execution.execute(pool.clone()).await?;
+    if !response.success || response.content.trim().is_empty() {
+        return Err(anyhow::anyhow!("empty or failed response"));
+    }
+    Ok(response.content)
+}
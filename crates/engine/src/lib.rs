@@ -2,5 +2,12 @@ pub mod api;
 pub mod background;
 pub mod pool;
 pub mod agent;
+pub mod state;
+pub mod doctor;
+pub mod local;
+pub mod startup;
+pub mod service;
+#[cfg(test)]
+mod service_tests;
 
 pub use artificer_shared::{Message, ToolCall, FunctionCall};
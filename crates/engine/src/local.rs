@@ -0,0 +1,205 @@
+//! `artificer local`: the whole stack in one process. Chats in the terminal
+//! with the task system, background worker and every tool (client tools
+//! included) running in-process against the same memory.db, with no HTTP
+//! server and no envoy.
+
+use std::io::Write;
+use std::sync::Arc;
+use anyhow::Result;
+use futures_util::StreamExt;
+use tokio::sync::{broadcast, watch};
+
+use artificer_shared::db::{self, Db, PrivacyLevel};
+use artificer_shared::events::ChatEvent;
+use artificer_shared::executor::ToolExecutor;
+use crate::api::handlers::{resolve_conversation, start_chat_turn};
+use crate::api::types::ChatRequest;
+use crate::background::{Leadership, Worker};
+use crate::pool::{AgentPool, GpuPool};
+use crate::service::InstanceLock;
+use crate::startup;
+use crate::state::{AppState, RuntimeState};
+
+/// Device name local mode chats as. Kept apart from envoy devices so
+/// registering one never replaces the other's key.
+const LOCAL_DEVICE: &str = "local";
+
+pub async fn run() -> Result<()> {
+    println!("Artificer local — everything runs in this process\n");
+
+    let db_path = std::env::current_dir()?.join("memory.db");
+    let _lock = InstanceLock::acquire(&db_path)?;
+    let db = db::init();
+    let gpu_pool = Arc::new(GpuPool::load()?);
+    let embeddings = startup::embedding_client(&gpu_pool);
+    let brave_quota = startup::install_toolbelts(&db, embeddings.clone())?;
+    let moderation = startup::load_moderation()?;
+    let agent_pool = Arc::new(
+        AgentPool::new(db.clone(), Arc::new(ToolExecutor::in_process()))
+            .with_moderation(moderation)
+            .with_embeddings(embeddings),
+    );
+    let (device_id, device_key) = local_device(&db)?;
+
+    // The lock makes this the only engine on the database, so it always leads
+    let leadership = Arc::new(Leadership::new(db.clone()));
+    leadership.renew();
+
+    let (job_events, _) = broadcast::channel(64);
+    let runtime = Arc::new(RuntimeState::new());
+    let state = AppState {
+        gpu_pool: gpu_pool.clone(),
+        agent_pool: agent_pool.clone(),
+        job_events: job_events.clone(),
+        runtime: runtime.clone(),
+        admin_key: None,
+        quotas: Arc::new(vec![brave_quota]),
+        leadership: leadership.clone(),
+        read_only: false,
+    };
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let worker = Worker::new(agent_pool, gpu_pool, 2, shutdown_rx.clone(), job_events, runtime, leadership.clone());
+    let worker_handle = tokio::spawn(async move {
+        if let Err(e) = worker.run().await {
+            eprintln!("Worker crashed: {}", e);
+        }
+        worker
+    });
+    let lease_leadership = leadership.clone();
+    let lease_handle = tokio::spawn(async move {
+        lease_leadership.run(shutdown_rx).await;
+    });
+
+    println!();
+    println!("Type a message to chat, /new for a new conversation, or 'quit' to exit.");
+
+    let mut conversation_id = None;
+    while let Some(line) = prompt("\nYou: ").await? {
+        let message = line.trim();
+        match message {
+            "" => continue,
+            "quit" | "exit" => break,
+            "/new" => {
+                conversation_id = None;
+                println!("Started a new conversation.");
+            }
+            _ => match chat(&state, device_id, &device_key, conversation_id, message).await {
+                Ok(id) => conversation_id = Some(id),
+                Err(e) => eprintln!("❌ {}", e),
+            },
+        }
+    }
+
+    println!("\nFinishing background jobs...");
+    let _ = shutdown_tx.send(true);
+    let worker = worker_handle.await?;
+    worker.drain_queue().await?;
+    let _ = lease_handle.await;
+    leadership.release();
+    println!("Goodbye!");
+    Ok(())
+}
+
+/// The device local mode chats as, registered on first use.
+fn local_device(db: &Db) -> Result<(u64, String)> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    db.execute(
+        "INSERT OR IGNORE INTO devices (device_name, device_key, active, created, last_seen)
+         VALUES (?1, ?2, 1, ?3, ?3)",
+        rusqlite::params![LOCAL_DEVICE, uuid::Uuid::new_v4().to_string(), now],
+    )?;
+    db.query_row_optional(
+        "SELECT id, device_key FROM devices WHERE device_name = ?1",
+        rusqlite::params![LOCAL_DEVICE],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?)),
+    )?
+    .ok_or_else(|| anyhow::anyhow!("Failed to register the local device"))
+}
+
+/// Run one chat turn and print its events as they happen. Returns the
+/// conversation it ran in.
+async fn chat(
+    state: &AppState,
+    device_id: u64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    message: &str,
+) -> Result<u64> {
+    let conversation_id = resolve_conversation(state.agent_pool.db(), device_id, conversation_id, None, false, PrivacyLevel::Normal)?;
+    let request = ChatRequest {
+        device_id: device_id as i64,
+        device_key: device_key.to_string(),
+        conversation_id: Some(conversation_id),
+        message: message.to_string(),
+        stream: None,
+        deferred: false,
+        profile: None,
+        incognito: false,
+        privacy: None,
+        toolbelts: Vec::new(),
+        fresh: false,
+        debug: false,
+        verify: false,
+        model: None,
+        latency_hint: None,
+    };
+    let log = start_chat_turn(state.clone(), device_id, conversation_id, request)?;
+
+    let mut events = std::pin::pin!(log.follow(0));
+    while let Some(event) = events.next().await {
+        let Ok(event) = serde_json::from_str::<ChatEvent>(event.data()) else {
+            continue;
+        };
+        match event {
+            ChatEvent::StreamChunk { content } => {
+                print!("{}", content);
+                std::io::stdout().flush().ok();
+            }
+            ChatEvent::Queued { position } => println!("⏳ Waiting for model ({} ahead)", position),
+            ChatEvent::ToolCall { tool, .. } => println!("\n🔧 {}", tool),
+            ChatEvent::Error { message } => eprintln!("\n❌ {}", message),
+            ChatEvent::Refusal { message, .. } => println!("\n🚫 {}", message),
+            ChatEvent::Question { question_id, task, question, options, .. } => {
+                println!("\n❓ [{}] {}", task, question);
+                for (i, option) in options.iter().enumerate() {
+                    println!("   {}. {}", i + 1, option);
+                }
+                let input = prompt("Answer: ").await?.unwrap_or_default();
+                let answer = pick_answer(input.trim(), &options);
+                state.runtime.answer_question(conversation_id, Some(question_id), &answer);
+            }
+            ChatEvent::Done { .. } => println!(),
+            _ => {}
+        }
+    }
+    Ok(conversation_id)
+}
+
+/// A number picks one of the offered options; nothing leaves it to the assistant.
+fn pick_answer(input: &str, options: &[String]) -> String {
+    if input.is_empty() {
+        return "No preference, use your judgment.".to_string();
+    }
+    input
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| options.get(n.wrapping_sub(1)))
+        .cloned()
+        .unwrap_or_else(|| input.to_string())
+}
+
+/// Print `label` and read a line from stdin. None at end of input.
+async fn prompt(label: &str) -> Result<Option<String>> {
+    print!("{}", label);
+    std::io::stdout().flush().ok();
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        let read = std::io::stdin().read_line(&mut line)?;
+        Ok((read > 0).then_some(line))
+    })
+    .await?
+}
@@ -1,11 +1,15 @@
 use std::sync::Arc;
 use anyhow::Result;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 
 use artificer_engine::api;
-use artificer_engine::api::handlers::AppState;
-use artificer_engine::background::Worker;
+use artificer_engine::doctor;
+use artificer_engine::local;
+use artificer_engine::startup;
+use artificer_engine::service::{self, InstanceLock};
+use artificer_engine::background::{Leadership, Worker};
 use artificer_engine::pool::{GpuPool, AgentPool};
+use artificer_engine::state::{AppState, RuntimeState};
 use artificer_shared::db;
 use artificer_shared::executor::ToolExecutor;
 
@@ -13,11 +17,46 @@ use artificer_shared::executor::ToolExecutor;
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
 
+    if std::env::args().nth(1).as_deref() == Some("doctor") {
+        println!("Artificer doctor — checking the stack\n");
+        let ready = doctor::run().await?;
+        std::process::exit(if ready { 0 } else { 1 });
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("local") {
+        return local::run().await;
+    }
+
     println!("╔════════════════════════════════════════╗");
     println!("║        ARTIFICER STARTING UP           ║");
     println!("╚════════════════════════════════════════╝");
     println!();
 
+    // One engine per database, unless several are meant to share it. A
+    // read-only replica always shares: it runs beside the primary.
+    let read_only = env_flag("READ_ONLY");
+    let shared_db = env_flag("SHARED_DB");
+    let instance_lock = if read_only {
+        println!("→ Read-only replica (READ_ONLY): serving reads beside the primary");
+        None
+    } else if shared_db {
+        println!("→ Sharing database with other engines (SHARED_DB)");
+        None
+    } else {
+        println!("→ Locking database...");
+        let db_path = std::env::current_dir()?.join("memory.db");
+        match InstanceLock::acquire(&db_path) {
+            Ok(lock) => {
+                println!("  ✓ Holding {}", lock.path().display());
+                Some(lock)
+            }
+            Err(e) => {
+                eprintln!("  ✗ {}", e);
+                return Err(e);
+            }
+        }
+    };
+
     // Initialize database
     println!("→ Initializing database...");
     let db = db::init();
@@ -51,31 +90,83 @@ async fn main() -> Result<()> {
     }
     let tool_executor = Arc::new(ToolExecutor::new(envoy_url));
 
+    // Install server-side toolbelts with their config and secrets
+    let embeddings = startup::embedding_client(&gpu_pool);
+    let brave_quota = startup::install_toolbelts(&db, embeddings.clone())?;
+    if !read_only {
+        startup::spawn_provider_checks(&db);
+        startup::spawn_model_probes(&gpu_pool);
+    }
+
     // Initialize agent pool with shared resources
     println!("→ Building agent pool...");
-    let agent_pool = Arc::new(AgentPool::new(db.clone(), tool_executor));
+    let moderation = startup::load_moderation()?;
+    let agent_pool = Arc::new(
+        AgentPool::new(db.clone(), tool_executor)
+            .with_moderation(moderation)
+            .with_embeddings(embeddings),
+    );
     println!("  ✓ Agent pool ready");
 
+    // Only the instance holding the worker lease runs background jobs
+    let leadership = Arc::new(Leadership::new(db.clone()));
+    if read_only {
+        println!("  ⚠ Read-only: this instance never runs background jobs");
+    } else if leadership.renew() {
+        println!("  ✓ Instance {} runs background jobs", leadership.instance_id());
+    } else {
+        println!("  ⚠ Another instance runs background jobs; this one serves requests only");
+    }
+
+    // Job events flow from the worker to any connected /jobs/events stream
+    let (job_events, _) = broadcast::channel(64);
+    let runtime = Arc::new(RuntimeState::new());
+
     // Build shared application state
     let state = AppState {
         gpu_pool: gpu_pool.clone(),
         agent_pool: agent_pool.clone(),
+        job_events: job_events.clone(),
+        runtime: runtime.clone(),
+        admin_key: std::env::var("ADMIN_KEY").ok().filter(|key| !key.is_empty()),
+        quotas: Arc::new(vec![brave_quota]),
+        leadership: leadership.clone(),
+        read_only,
     };
 
     // Create shutdown channel
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
-    // Start background worker
-    println!("→ Starting background worker...");
-    let worker_shutdown_rx = shutdown_rx.clone();
-    let worker = Worker::new(agent_pool.clone(), gpu_pool.clone(), 2, worker_shutdown_rx);
-    let worker_handle = tokio::spawn(async move {
-        if let Err(e) = worker.run().await {
-            eprintln!("Worker crashed: {}", e);
-        }
-        worker
-    });
-    println!("  ✓ Background worker started");
+    // Start background worker, and the lease loop that decides whether it works
+    let background = if read_only {
+        None
+    } else {
+        println!("→ Starting background worker...");
+        let worker_shutdown_rx = shutdown_rx.clone();
+        let worker = Worker::new(
+            agent_pool.clone(),
+            gpu_pool.clone(),
+            2,
+            worker_shutdown_rx,
+            job_events,
+            runtime,
+            leadership.clone(),
+        );
+        let worker_handle = tokio::spawn(async move {
+            if let Err(e) = worker.run().await {
+                eprintln!("Worker crashed: {}", e);
+            }
+            worker
+        });
+        println!("  ✓ Background worker started");
+
+        let lease_shutdown_rx = shutdown_rx.clone();
+        let lease_leadership = leadership.clone();
+        let lease_handle = tokio::spawn(async move {
+            lease_leadership.run(lease_shutdown_rx).await;
+        });
+        Some((worker_handle, lease_handle))
+    };
 
     // Start API server
     println!("→ Starting API server...");
@@ -92,11 +183,31 @@ async fn main() -> Result<()> {
     println!("╚════════════════════════════════════════╝");
     println!();
     println!("API server: http://0.0.0.0:8080");
-    println!("Press Ctrl+C to shutdown gracefully");
+    println!("Press Ctrl+C to shutdown gracefully, or send SIGHUP to reload hardware.json");
     println!();
 
-    // Wait for Ctrl+C
-    tokio::signal::ctrl_c().await?;
+    service::notify("READY=1");
+    if let Some(interval) = service::watchdog_interval() {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                service::notify("WATCHDOG=1");
+            }
+        });
+    }
+
+    // Serve until Ctrl+C or SIGTERM. A reload swaps the GPU list in place, so
+    // conversations in progress carry on; prompt overrides are read from the
+    // database on every run and need no reload.
+    service::wait_for_shutdown(|| {
+        println!("→ Reloading configuration...");
+        match gpu_pool.reload() {
+            Ok(()) => println!("  ✓ hardware.json reloaded"),
+            Err(e) => eprintln!("  ✗ Keeping the current GPUs: {}", e),
+        }
+    }).await?;
+    service::notify("STOPPING=1");
 
     println!();
     println!("╔════════════════════════════════════════╗");
@@ -110,12 +221,22 @@ async fn main() -> Result<()> {
     let _ = api_handle.await;
     println!("  ✓ API server stopped");
 
-    println!("→ Draining background job queue...");
-    let worker = worker_handle.await?;
-    worker.drain_queue().await?;
-    println!("  ✓ Background jobs complete");
+    if let Some((worker_handle, lease_handle)) = background {
+        println!("→ Draining background job queue...");
+        let worker = worker_handle.await?;
+        worker.drain_queue().await?;
+        let _ = lease_handle.await;
+        leadership.release();
+        println!("  ✓ Background jobs complete");
+    }
 
     println!();
+    drop(instance_lock);
     println!("Artificer shutdown complete. Goodbye!");
     Ok(())
 }
+
+/// Whether an on/off environment variable is set to `1` or `true`.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
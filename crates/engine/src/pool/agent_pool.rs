@@ -2,14 +2,22 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use reqwest::Client;
 use artificer_shared::db::Db;
+use artificer_shared::embeddings::EmbeddingClient;
 use artificer_shared::executor::ToolExecutor;
 use crate::agent::{Agent, AgentType};
+use crate::agent::moderation::{Moderation, ModerationConfig};
 
 pub struct AgentPool {
     agents: HashMap<&'static str, Agent>,
     pub client: Client,
     pub db: Arc<Db>,
     pub tool_executor: Arc<ToolExecutor>,
+    /// Checks on user messages and tool results; passes everything unless
+    /// moderation.json configures some.
+    moderation: Moderation,
+    /// Embeds messages and memories for semantic search; None when no
+    /// embedding model is configured.
+    embeddings: Option<EmbeddingClient>,
 }
 
 impl AgentPool {
@@ -32,9 +40,25 @@ impl AgentPool {
             client,
             db,
             tool_executor,
+            moderation: Moderation::none(),
+            embeddings: None,
         }
     }
 
+    /// Moderate with the hooks moderation.json configures, if it exists.
+    pub fn with_moderation(mut self, config: Option<ModerationConfig>) -> Self {
+        if let Some(config) = config {
+            self.moderation = Moderation::from_config(config, self.client.clone());
+        }
+        self
+    }
+
+    /// Embed history for semantic search with `embeddings`, if configured.
+    pub fn with_embeddings(mut self, embeddings: Option<EmbeddingClient>) -> Self {
+        self.embeddings = embeddings;
+        self
+    }
+
     pub fn get(&self, name: &str) -> Option<&Agent> {
         self.agents.get(name)
     }
@@ -50,4 +74,12 @@ impl AgentPool {
     pub fn tool_executor(&self) -> &Arc<ToolExecutor> {
         &self.tool_executor
     }
+
+    pub fn moderation(&self) -> &Moderation {
+        &self.moderation
+    }
+
+    pub fn embeddings(&self) -> Option<&EmbeddingClient> {
+        self.embeddings.as_ref()
+    }
 }
@@ -1,14 +1,32 @@
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
 use anyhow::Result;
+use tokio::sync::Notify;
+use artificer_shared::errors::ModelHost;
+
+/// Most chat turns that may wait for an interactive GPU at once; more are
+/// turned away as busy.
+pub const MAX_QUEUED_CHATS: usize = 32;
 
 /// Tracks which GPUs exist and which are currently busy.
 /// Held as Arc<GpuPool> in shared server state, same as the database.
 pub struct GpuPool {
-    /// All GPUs indexed by id
-    gpus: Vec<GpuConfig>,
+    /// All GPUs indexed by id. Replaced wholesale by `reload`.
+    gpus: RwLock<Vec<GpuConfig>>,
     /// IDs of GPUs currently assigned to an active task
     busy: Mutex<HashSet<String>>,
+    /// How each latency hint picks GPUs and models. Replaced by `reload`.
+    latency: RwLock<LatencyProfiles>,
+    /// Requests waiting for an interactive GPU, oldest first
+    waiting: Mutex<VecDeque<Waiter>>,
+    next_ticket: AtomicU64,
+    /// Woken when a GPU is released or the queue moves
+    changed: Notify,
+    /// The latest probe of each model server by URL: None if it answered,
+    /// else why not, with when it was probed. Servers not probed yet count
+    /// as up.
+    probes: RwLock<HashMap<String, (Option<String>, i64)>>,
 }
 
 impl GpuPool {
@@ -23,8 +41,13 @@ impl GpuPool {
         }
 
         Self {
-            gpus: config.gpus,
+            gpus: RwLock::new(config.gpus),
+            latency: RwLock::new(config.latency),
             busy: Mutex::new(HashSet::new()),
+            waiting: Mutex::new(VecDeque::new()),
+            next_ticket: AtomicU64::new(0),
+            changed: Notify::new(),
+            probes: RwLock::new(HashMap::new()),
         }
     }
 
@@ -35,6 +58,23 @@ impl GpuPool {
         Ok(Self::from_config(config))
     }
 
+    /// Re-read hardware.json and swap in its GPUs. Requests already running
+    /// keep their handles; a busy GPU that was removed just isn't handed out again.
+    pub fn reload(&self) -> Result<()> {
+        let config = HardwareConfig::load()?;
+        println!("GPU pool reloaded:");
+        for gpu in &config.gpus {
+            println!(
+                "  [{:?}] {} — {} ({})",
+                gpu.role, gpu.id, gpu.model, gpu.url
+            );
+        }
+        *self.gpus.write().unwrap() = config.gpus;
+        *self.latency.write().unwrap() = config.latency;
+        self.changed.notify_waiters();
+        Ok(())
+    }
+
     /// Acquire a free interactive GPU.
     /// Returns None if all interactive GPUs are currently busy.
     /// The caller is responsible for releasing the GPU when the task completes.
@@ -42,12 +82,130 @@ impl GpuPool {
         self.acquire(GpuRole::Interactive)
     }
 
+    /// Wait for a free interactive GPU, first come first served. With a
+    /// `model`, only a GPU serving it will do, and the handle runs that model.
+    /// Otherwise `hint` picks among the free GPUs (see `LatencyProfile`).
+    /// `on_wait` hears how many requests are ahead whenever that changes; it
+    /// is not called when a GPU is free at once. Dropping the future leaves
+    /// the queue.
+    pub async fn acquire_interactive_queued(
+        &self,
+        model: Option<&str>,
+        hint: LatencyHint,
+        mut on_wait: impl FnMut(usize),
+    ) -> GpuHandle {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        self.waiting.lock().unwrap().push_back(Waiter { ticket, model: model.map(str::to_string), hint });
+        let _place = QueuePlace { pool: self, ticket };
+
+        let mut reported = None;
+        loop {
+            // Registered before looking, so a release in between still wakes us
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            if let Some(gpu) = self.take_in_turn(ticket) {
+                return gpu;
+            }
+            let ahead = self.waiting.lock().unwrap()
+                .iter()
+                .position(|waiter| waiter.ticket == ticket)
+                .unwrap_or(0);
+            if reported != Some(ahead) {
+                on_wait(ahead);
+                reported = Some(ahead);
+            }
+            changed.await;
+        }
+    }
+
+    /// Hand out free GPUs to the queue in order and take the one that falls
+    /// to `ticket`, if any. A request waiting for a particular model doesn't
+    /// hold up those behind it that another GPU can serve.
+    fn take_in_turn(&self, ticket: u64) -> Option<GpuHandle> {
+        let waiting = self.waiting.lock().unwrap();
+        let mut busy = self.busy.lock().unwrap();
+        let gpus = self.gpus.read().unwrap();
+        let latency = self.latency.read().unwrap();
+
+        // GPUs that fall to requests ahead of ours
+        let mut taken: HashSet<&str> = HashSet::new();
+        for waiter in waiting.iter() {
+            let profile = latency.profile(waiter.hint);
+            let free: Vec<&GpuConfig> = gpus.iter()
+                .filter(|g| !busy.contains(&g.id) && !taken.contains(g.id.as_str()))
+                .filter(|g| g.role == GpuRole::Interactive || profile.prefers(g))
+                .collect();
+            let Some((gpu, model)) = profile.choose(free, waiter.model.as_deref()) else {
+                continue;
+            };
+            if waiter.ticket != ticket {
+                taken.insert(&gpu.id);
+                continue;
+            }
+
+            busy.insert(gpu.id.clone());
+            println!("GPU acquired: {} for {:?} task ({:?})", gpu.id, GpuRole::Interactive, waiter.hint);
+            let mut handle = GpuHandle::from_config(gpu);
+            handle.model = model;
+            handle.num_predict = profile.num_predict;
+            return Some(handle);
+        }
+        None
+    }
+
+    /// Whether some interactive GPU can run `model`.
+    pub fn serves_interactive(&self, model: &str) -> bool {
+        self.gpus.read().unwrap().iter()
+            .any(|g| g.role == GpuRole::Interactive && g.serves(Some(model)))
+    }
+
+    /// Every model the interactive GPUs can run, for error messages.
+    pub fn interactive_models(&self) -> Vec<String> {
+        let mut models: Vec<String> = self.gpus.read().unwrap().iter()
+            .filter(|g| g.role == GpuRole::Interactive)
+            .flat_map(|g| std::iter::once(&g.model).chain(&g.models).cloned())
+            .collect();
+        models.sort();
+        models.dedup();
+        models
+    }
+
+    /// How many requests are waiting for an interactive GPU.
+    pub fn queued(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+
     /// Acquire a free background GPU.
     /// Returns None if all background GPUs are currently busy.
     pub fn acquire_background(&self) -> Option<GpuHandle> {
         self.acquire(GpuRole::Background)
     }
 
+    /// Acquire a free GPU for a background job with `hint`: a background GPU,
+    /// or one the hint's profile lists under `prefer`, picked the same way as
+    /// for chats. Jobs don't queue; None means try again later.
+    pub fn acquire_background_for(&self, hint: LatencyHint) -> Option<GpuHandle> {
+        let mut busy = self.busy.lock().unwrap();
+        let gpus = self.gpus.read().unwrap();
+        let latency = self.latency.read().unwrap();
+        let profile = latency.profile(hint);
+
+        let free: Vec<&GpuConfig> = gpus.iter()
+            .filter(|g| !busy.contains(&g.id))
+            .filter(|g| g.role == GpuRole::Background || profile.prefers(g))
+            .collect();
+        let (gpu, model) = profile.choose(free, None)?;
+
+        busy.insert(gpu.id.clone());
+        println!("GPU acquired: {} for {:?} task ({:?})", gpu.id, GpuRole::Background, hint);
+        let mut handle = GpuHandle::from_config(gpu);
+        handle.model = model;
+        handle.num_predict = profile.num_predict;
+        Some(handle)
+    }
+
     /// Release a GPU back to the pool.
     /// Should be called when a task completes, errors, or is abandoned.
     pub fn release(&self, gpu_id: &str) {
@@ -58,12 +216,21 @@ impl GpuPool {
         } else {
             eprintln!("Warning: tried to release GPU '{}' that wasn't marked busy", gpu_id);
         }
+        drop(busy);
+        self.changed.notify_waiters();
+    }
+
+    /// How many interactive GPUs exist, busy or not.
+    pub fn interactive_total(&self) -> usize {
+        self.gpus.read().unwrap().iter()
+            .filter(|g| g.role == GpuRole::Interactive)
+            .count()
     }
 
     /// How many interactive GPUs are currently free.
     pub fn interactive_available(&self) -> usize {
         let busy = self.busy.lock().unwrap();
-        self.gpus.iter()
+        self.gpus.read().unwrap().iter()
             .filter(|g| g.role == GpuRole::Interactive && !busy.contains(&g.id))
             .count()
     }
@@ -71,7 +238,7 @@ impl GpuPool {
     /// How many background GPUs are currently free.
     pub fn background_available(&self) -> usize {
         let busy = self.busy.lock().unwrap();
-        self.gpus.iter()
+        self.gpus.read().unwrap().iter()
             .filter(|g| g.role == GpuRole::Background && !busy.contains(&g.id))
             .count()
     }
@@ -79,7 +246,7 @@ impl GpuPool {
     /// All GPUs and their current status. Useful for a status endpoint.
     pub fn status(&self) -> Vec<GpuStatus> {
         let busy = self.busy.lock().unwrap();
-        self.gpus.iter()
+        self.gpus.read().unwrap().iter()
             .map(|g| GpuStatus {
                 id: g.id.clone(),
                 url: g.url.clone(),
@@ -91,10 +258,59 @@ impl GpuPool {
             .collect()
     }
 
+    /// Every model server URL in hardware.json, once each.
+    pub fn host_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self.gpus.read().unwrap().iter().map(|g| g.url.clone()).collect();
+        urls.sort();
+        urls.dedup();
+        urls
+    }
+
+    /// Record a probe of the server at `url`: None if it answered, else why
+    /// not. Returns whether the server went up or down since the last probe.
+    pub fn record_probe(&self, url: &str, error: Option<String>, checked_at: i64) -> bool {
+        let mut probes = self.probes.write().unwrap();
+        let was_down = probes.get(url).is_some_and(|(error, _)| error.is_some());
+        let is_down = error.is_some();
+        probes.insert(url.to_string(), (error, checked_at));
+        was_down != is_down
+    }
+
+    /// Whether the latest probes found every model server down, so nothing
+    /// that needs a model can run. Before the first probe the servers are
+    /// taken to be up.
+    pub fn is_degraded(&self) -> bool {
+        let urls = self.host_urls();
+        let probes = self.probes.read().unwrap();
+        !urls.is_empty() && urls.iter().all(|url| probes.get(url).is_some_and(|(error, _)| error.is_some()))
+    }
+
+    /// Each model server with its GPUs and what its latest probe found.
+    pub fn hosts(&self) -> Vec<ModelHost> {
+        let mut gpus_by_url: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for gpu in self.gpus.read().unwrap().iter() {
+            gpus_by_url.entry(gpu.url.clone()).or_default().push(gpu.id.clone());
+        }
+        let probes = self.probes.read().unwrap();
+        gpus_by_url.into_iter()
+            .map(|(url, gpus)| {
+                let probe = probes.get(&url);
+                ModelHost {
+                    reachable: probe.is_none_or(|(error, _)| error.is_none()),
+                    error: probe.and_then(|(error, _)| error.clone()),
+                    checked_at: probe.map(|(_, at)| *at),
+                    url,
+                    gpus,
+                }
+            })
+            .collect()
+    }
+
     fn acquire(&self, role: GpuRole) -> Option<GpuHandle> {
         let mut busy = self.busy.lock().unwrap();
 
-        let gpu = self.gpus.iter()
+        let gpus = self.gpus.read().unwrap();
+        let gpu = gpus.iter()
             .find(|g| g.role == role && !busy.contains(&g.id))?;
 
         busy.insert(gpu.id.clone());
@@ -104,6 +320,29 @@ impl GpuPool {
     }
 }
 
+/// A request's place in the interactive queue, given up when it gets a GPU
+/// or stops waiting.
+struct QueuePlace<'a> {
+    pool: &'a GpuPool,
+    ticket: u64,
+}
+
+impl Drop for QueuePlace<'_> {
+    fn drop(&mut self) {
+        self.pool.waiting.lock().unwrap().retain(|waiter| waiter.ticket != self.ticket);
+        // Everyone behind moves up
+        self.pool.changed.notify_waiters();
+    }
+}
+
+/// A request waiting in the interactive queue.
+struct Waiter {
+    ticket: u64,
+    /// A model the request asked for by name; only a GPU serving it will do
+    model: Option<String>,
+    hint: LatencyHint,
+}
+
 /// Public status view of a single GPU — used for the status endpoint.
 #[derive(Debug, serde::Serialize)]
 pub struct GpuStatus {
@@ -136,12 +375,118 @@ pub struct GpuConfig {
     pub role: GpuRole,
     #[serde(default)]
     pub description: String,
+    /// Other models pulled on this GPU's server, which a chat may ask for
+    /// by name instead of `model`.
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+impl GpuConfig {
+    /// Whether this GPU can run `model`; any GPU will do for None.
+    pub fn serves(&self, model: Option<&str>) -> bool {
+        model.is_none_or(|m| m == self.model || self.models.iter().any(|other| other == m))
+    }
+}
+
+/// How long a request can wait on the model, given with a chat or job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyHint {
+    /// Someone is watching the reply stream in: fast GPUs, small models,
+    /// short answers.
+    Interactive,
+    /// The default: whichever GPU of the right role is free first.
+    #[default]
+    Relaxed,
+    /// Nobody is waiting: the biggest model is worth the time.
+    Batch,
+}
+
+impl LatencyHint {
+    pub const NAMES: [&'static str; 3] = ["interactive", "relaxed", "batch"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "interactive" => Some(Self::Interactive),
+            "relaxed" => Some(Self::Relaxed),
+            "batch" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+}
+
+/// What a latency hint changes about the GPU and model a request gets,
+/// from the `latency` section of hardware.json. Every field is optional;
+/// an empty profile behaves like no hint at all.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyProfile {
+    /// GPU ids to try first, best first. These GPUs may be handed out
+    /// whatever their role, so a fast background host can take interactive
+    /// chats or a big interactive one batch jobs.
+    #[serde(default)]
+    pub prefer: Vec<String>,
+    /// Model to run when a preferred GPU serves it; any GPU still does if
+    /// none that serves it is free.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Cap on generated tokens per model call (Ollama's `num_predict`).
+    #[serde(default)]
+    pub num_predict: Option<u32>,
+}
+
+impl LatencyProfile {
+    pub fn prefers(&self, gpu: &GpuConfig) -> bool {
+        self.prefer.contains(&gpu.id)
+    }
+
+    /// Pick a GPU from `free` and the model to run on it. A `required` model
+    /// must be served; otherwise the profile's model is used where a free
+    /// GPU serves it. Preferred GPUs win ties, in `prefer` order.
+    fn choose<'a>(&self, mut free: Vec<&'a GpuConfig>, required: Option<&str>) -> Option<(&'a GpuConfig, String)> {
+        free.sort_by_key(|g| self.prefer.iter().position(|id| *id == g.id).unwrap_or(usize::MAX));
+
+        if let Some(model) = required {
+            let gpu = free.into_iter().find(|g| g.serves(Some(model)))?;
+            return Some((gpu, model.to_string()));
+        }
+        let wanted = self.model.as_deref()
+            .and_then(|model| free.iter().copied().find(|g| g.serves(Some(model))).map(|g| (g, model.to_string())));
+        if wanted.is_some() {
+            return wanted;
+        }
+        let gpu = free.into_iter().next()?;
+        Some((gpu, gpu.model.clone()))
+    }
+}
+
+/// One `LatencyProfile` per hint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyProfiles {
+    #[serde(default)]
+    pub interactive: LatencyProfile,
+    #[serde(default)]
+    pub relaxed: LatencyProfile,
+    #[serde(default)]
+    pub batch: LatencyProfile,
+}
+
+impl LatencyProfiles {
+    pub fn profile(&self, hint: LatencyHint) -> &LatencyProfile {
+        match hint {
+            LatencyHint::Interactive => &self.interactive,
+            LatencyHint::Relaxed => &self.relaxed,
+            LatencyHint::Batch => &self.batch,
+        }
+    }
 }
 
 /// The full hardware.json structure
 #[derive(Debug, Deserialize)]
 pub struct HardwareConfig {
     pub gpus: Vec<GpuConfig>,
+    /// Per-hint GPU and model preferences; empty means hints change nothing.
+    #[serde(default)]
+    pub latency: LatencyProfiles,
 }
 
 impl HardwareConfig {
@@ -192,6 +537,8 @@ pub struct GpuHandle {
     pub url: String,
     pub model: String,
     pub role: GpuRole,
+    /// Token cap for each model call, from the request's latency hint
+    pub num_predict: Option<u32>,
 }
 
 impl GpuHandle {
@@ -201,6 +548,7 @@ impl GpuHandle {
             url: config.url.clone(),
             model: config.model.clone(),
             role: config.role.clone(),
+            num_predict: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use crate::pool::gpu_pool::{GpuConfig, GpuPool, GpuRole, HardwareConfig, LatencyHint, LatencyProfile};
+
+    fn gpu(id: &str, model: &str, models: &[&str]) -> GpuConfig {
+        GpuConfig {
+            id: id.to_string(),
+            url: "http://localhost:11434".to_string(),
+            model: model.to_string(),
+            role: GpuRole::Interactive,
+            description: String::new(),
+            models: models.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn pool() -> Arc<GpuPool> {
+        Arc::new(GpuPool::from_config(HardwareConfig { gpus: vec![gpu("gpu-0", "test", &[])], latency: Default::default() }))
+    }
+
+    /// A waiting request: the GPU id and model it ends up with, and every
+    /// queue position it was told.
+    type Waiter = (tokio::task::JoinHandle<(String, String)>, Arc<Mutex<Vec<usize>>>);
+
+    /// Spawn a queued acquire that records every position it is told.
+    fn wait_for(pool: &Arc<GpuPool>, model: Option<&str>) -> Waiter {
+        let positions = Arc::new(Mutex::new(Vec::new()));
+        let seen = positions.clone();
+        let pool = pool.clone();
+        let model = model.map(str::to_string);
+        let handle = tokio::spawn(async move {
+            let gpu = pool
+                .acquire_interactive_queued(model.as_deref(), LatencyHint::Relaxed, |ahead| seen.lock().unwrap().push(ahead))
+                .await;
+            (gpu.id, gpu.model)
+        });
+        (handle, positions)
+    }
+
+    fn wait(pool: &Arc<GpuPool>) -> Waiter {
+        wait_for(pool, None)
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test]
+    async fn free_gpu_is_taken_without_queueing() {
+        let pool = pool();
+        let (handle, positions) = wait(&pool);
+        assert_eq!(handle.await.unwrap().0, "gpu-0");
+        assert!(positions.lock().unwrap().is_empty());
+        assert_eq!(pool.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn waiters_are_served_in_order_and_told_their_place() {
+        let pool = pool();
+        let held = pool.acquire_interactive().unwrap();
+
+        let (first, first_positions) = wait(&pool);
+        settle().await;
+        let (second, second_positions) = wait(&pool);
+        settle().await;
+        assert_eq!(pool.queued(), 2);
+        assert_eq!(*second_positions.lock().unwrap(), [1]);
+
+        pool.release(&held.id);
+        let (gpu, _) = first.await.unwrap();
+        settle().await;
+        assert_eq!(*first_positions.lock().unwrap(), [0]);
+        assert_eq!(*second_positions.lock().unwrap(), [1, 0]);
+        assert!(!second.is_finished());
+
+        pool.release(&gpu);
+        assert_eq!(second.await.unwrap().0, "gpu-0");
+        assert_eq!(pool.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiters_leave_the_queue() {
+        let pool = pool();
+        let _held = pool.acquire_interactive().unwrap();
+
+        let (first, _) = wait(&pool);
+        settle().await;
+        let (_second, second_positions) = wait(&pool);
+        settle().await;
+
+        first.abort();
+        settle().await;
+        assert_eq!(pool.queued(), 1);
+        assert_eq!(*second_positions.lock().unwrap(), [1, 0]);
+    }
+
+    #[tokio::test]
+    async fn model_overrides_wait_for_a_gpu_that_serves_them() {
+        let pool = Arc::new(GpuPool::from_config(HardwareConfig {
+            gpus: vec![gpu("big", "qwen2.5:32b", &["qwen3:8b"]), gpu("small", "llama3.2:3b", &[])],
+            latency: Default::default(),
+        }));
+        assert!(pool.serves_interactive("qwen3:8b"));
+        assert!(!pool.serves_interactive("mistral"));
+        assert_eq!(pool.interactive_models(), ["llama3.2:3b", "qwen2.5:32b", "qwen3:8b"]);
+
+        let held = pool.acquire_interactive().unwrap();
+        assert_eq!(held.id, "big");
+
+        // Only "big" serves qwen3:8b, so the next request takes "small" instead
+        let (picky, _) = wait_for(&pool, Some("qwen3:8b"));
+        settle().await;
+        let (any, _) = wait(&pool);
+        assert_eq!(any.await.unwrap().0, "small");
+        assert!(!picky.is_finished());
+
+        pool.release(&held.id);
+        assert_eq!(picky.await.unwrap(), ("big".to_string(), "qwen3:8b".to_string()));
+    }
+
+    #[tokio::test]
+    async fn latency_hints_pick_gpus_models_and_token_caps() {
+        let mut fast = gpu("fast", "llama3.2:3b", &[]);
+        fast.role = GpuRole::Background;
+        let mut config = HardwareConfig {
+            gpus: vec![gpu("big", "qwen2.5:32b", &["qwen3:8b"]), fast],
+            latency: Default::default(),
+        };
+        config.latency.interactive = LatencyProfile {
+            prefer: vec!["fast".to_string()],
+            model: None,
+            num_predict: Some(512),
+        };
+        config.latency.batch = LatencyProfile {
+            prefer: vec!["big".to_string()],
+            model: Some("qwen2.5:32b".to_string()),
+            num_predict: None,
+        };
+        let pool = Arc::new(GpuPool::from_config(config));
+
+        // Interactive chats may borrow the preferred background GPU
+        let quick = pool.acquire_interactive_queued(None, LatencyHint::Interactive, |_| {}).await;
+        assert_eq!((quick.id.as_str(), quick.num_predict), ("fast", Some(512)));
+        let relaxed = pool.acquire_interactive_queued(None, LatencyHint::Relaxed, |_| {}).await;
+        assert_eq!((relaxed.id.as_str(), relaxed.num_predict), ("big", None));
+        pool.release(&quick.id);
+        pool.release(&relaxed.id);
+
+        // Batch jobs may borrow the big interactive GPU and run its 32b model
+        let job = pool.acquire_background_for(LatencyHint::Batch).unwrap();
+        assert_eq!((job.id.as_str(), job.model.as_str()), ("big", "qwen2.5:32b"));
+        let other = pool.acquire_background_for(LatencyHint::Relaxed).unwrap();
+        assert_eq!(other.id, "fast");
+        assert!(pool.acquire_background_for(LatencyHint::Relaxed).is_none());
+    }
+
+    #[test]
+    fn degraded_only_when_every_server_is_down() {
+        let mut second = gpu("gpu-1", "test", &[]);
+        second.url = "http://other:11434".to_string();
+        let pool = GpuPool::from_config(HardwareConfig {
+            gpus: vec![gpu("gpu-0", "test", &[]), second],
+            latency: Default::default(),
+        });
+
+        // Servers count as up until a probe says otherwise
+        assert!(!pool.is_degraded());
+        assert!(pool.hosts().iter().all(|host| host.reachable && host.checked_at.is_none()));
+
+        assert!(pool.record_probe("http://localhost:11434", Some("connection refused".to_string()), 100));
+        assert!(!pool.is_degraded());
+        assert!(pool.record_probe("http://other:11434", Some("timed out".to_string()), 100));
+        assert!(pool.is_degraded());
+        // Still down is no change
+        assert!(!pool.record_probe("http://other:11434", Some("timed out".to_string()), 115));
+
+        let hosts = pool.hosts();
+        assert_eq!(hosts[0].url, "http://localhost:11434");
+        assert_eq!(hosts[0].gpus, vec!["gpu-0"]);
+        assert_eq!(hosts[0].error.as_deref(), Some("connection refused"));
+        assert_eq!(hosts[1].checked_at, Some(115));
+
+        assert!(pool.record_probe("http://other:11434", None, 130));
+        assert!(!pool.is_degraded());
+    }
+}
@@ -1,5 +1,8 @@
 pub mod gpu_pool;
 pub mod agent_pool;
 
-pub use gpu_pool::{GpuPool, GpuRole, GpuHandle};
+#[cfg(test)]
+mod gpu_pool_tests;
+
+pub use gpu_pool::{GpuPool, GpuRole, GpuHandle, LatencyHint, MAX_QUEUED_CHATS};
 pub use agent_pool::AgentPool;
\ No newline at end of file
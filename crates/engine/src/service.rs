@@ -0,0 +1,146 @@
+//! Running under a service manager: systemd readiness and watchdog
+//! notifications, socket activation, and the lock that keeps two engines off
+//! the same database. Everything here is a no-op when not started by systemd.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::Result;
+
+/// Send a state line (`READY=1`, `WATCHDOG=1`, ...) to systemd. Does nothing
+/// unless the unit is `Type=notify`, which sets NOTIFY_SOCKET.
+pub fn notify(state: &str) {
+    let Ok(socket) = std::env::var("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(e) = send_notify(&socket, state) {
+        eprintln!("Failed to notify systemd ({}): {}", state, e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send_notify(socket: &str, state: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+    // A leading '@' names a socket in the abstract namespace
+    let addr = match socket.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        None => SocketAddr::from_pathname(socket)?,
+    };
+    UnixDatagram::unbound()?.send_to_addr(state.as_bytes(), &addr)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_notify(_socket: &str, _state: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// How often to ping the systemd watchdog: half of `WatchdogSec=`, or None
+/// when the watchdog is off or meant for another process.
+pub fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID")
+        && pid.parse::<u32>().ok() != Some(std::process::id())
+    {
+        return None;
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+/// The listening socket systemd passed in (`LISTEN_FDS`), if the engine was
+/// socket-activated. Only the first socket is used.
+#[cfg(unix)]
+pub fn inherited_listener() -> Option<std::net::TcpListener> {
+    use std::os::fd::FromRawFd;
+
+    /// systemd passes sockets starting at this descriptor.
+    const LISTEN_FDS_START: i32 = 3;
+
+    let pid: u32 = std::env::var("LISTEN_PID").ok()?.parse().ok()?;
+    let fds: u32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    if pid != std::process::id() || fds == 0 {
+        return None;
+    }
+    // SAFETY: with LISTEN_PID naming this process, systemd guarantees
+    // LISTEN_FDS open sockets starting at fd 3, owned by nothing else here.
+    Some(unsafe { std::net::TcpListener::from_raw_fd(LISTEN_FDS_START) })
+}
+
+#[cfg(not(unix))]
+pub fn inherited_listener() -> Option<std::net::TcpListener> {
+    None
+}
+
+/// An exclusive lock on `<db>.lock`, held for the life of the process so a
+/// second engine can't open the same database. The file records the holder's
+/// PID for the error message. The OS drops the lock if the process dies, so a
+/// stale file never blocks startup.
+pub struct InstanceLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn acquire(db_path: &Path) -> Result<Self> {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(".lock");
+        let path = PathBuf::from(name);
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        if file.try_lock().is_err() {
+            let mut holder = String::new();
+            let _ = file.read_to_string(&mut holder);
+            return Err(anyhow::anyhow!(
+                "Another engine (pid {}) is already using {}. Stop it first, or run this one from another directory.",
+                holder.trim().parse::<u32>().map(|pid| pid.to_string()).unwrap_or_else(|_| "unknown".to_string()),
+                db_path.display()
+            ));
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+
+        Ok(Self { _file: file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Wait for Ctrl+C or SIGTERM (how systemd stops a unit), calling `reload`
+/// for each SIGHUP in the meantime.
+#[cfg(unix)]
+pub async fn wait_for_shutdown(reload: impl Fn()) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    loop {
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => return Ok(result?),
+            _ = terminate.recv() => return Ok(()),
+            _ = hangup.recv() => {
+                notify("RELOADING=1");
+                reload();
+                notify("READY=1");
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn wait_for_shutdown(_reload: impl Fn()) -> Result<()> {
+    Ok(tokio::signal::ctrl_c().await?)
+}
@@ -0,0 +1,19 @@
+#[cfg(test)]
+mod tests {
+    use crate::service::InstanceLock;
+
+    #[test]
+    fn second_engine_cannot_lock_the_same_database() {
+        let dir = std::env::temp_dir().join(format!("artificer-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("memory.db");
+
+        let lock = InstanceLock::acquire(&db_path).unwrap();
+        let err = InstanceLock::acquire(&db_path).err().unwrap();
+        assert!(err.to_string().contains(&std::process::id().to_string()));
+
+        drop(lock);
+        assert!(InstanceLock::acquire(&db_path).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+//! Startup steps shared by the server and `artificer local`.
+
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+
+use artificer_shared::db::{Db, Quota};
+use artificer_shared::embeddings::EmbeddingClient;
+use crate::agent::moderation::ModerationConfig;
+use crate::pool::GpuPool;
+use artificer_shared::tools::toolbelts::{self, archivist::Archivist, clock::Clock, places::Places, reports::Reports, watcher::Watcher, web_search::WebSearch};
+
+/// How often metered providers are asked about their key and quota. Each
+/// check spends one of Brave's own calls, so it stays far below its quota;
+/// searches refresh the quota in between.
+const PROVIDER_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// How often each model server in hardware.json is probed, and how long a
+/// probe may take. When every server is down the engine runs degraded until
+/// one answers again.
+const MODEL_PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const MODEL_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The embedding model semantic search uses, from EMBEDDING_MODEL. It is
+/// served at EMBEDDING_URL, or else by a model server from hardware.json.
+pub fn embedding_client(gpu_pool: &GpuPool) -> Option<EmbeddingClient> {
+    println!("→ Configuring embeddings...");
+    let client = EmbeddingClient::from_env(gpu_pool.host_urls().first().map(String::as_str));
+    match &client {
+        Some(client) => println!("  ✓ {} at {}", client.model(), client.url()),
+        None => println!("  ⚠ EMBEDDING_MODEL not set (semantic search disabled)"),
+    }
+    client
+}
+
+/// Install the server-side toolbelts with their config and secrets, printing
+/// a line for each. Returns the web search quota, which /usage reports.
+pub fn install_toolbelts(db: &Arc<Db>, embeddings: Option<EmbeddingClient>) -> Result<Quota> {
+    println!("→ Installing toolbelts...");
+    toolbelts::archivist::install(Archivist::new(db.clone()).with_embeddings(embeddings))?;
+    println!("  ✓ Archivist");
+    toolbelts::clock::install(Clock::new(db.clone()))?;
+    println!("  ✓ Clock");
+    let brave_quota = WebSearch::quota_from_env();
+    match WebSearch::from_env(db.clone(), brave_quota.clone()) {
+        Some(web_search) => {
+            toolbelts::web_search::install(web_search)?;
+            println!("  ✓ WebSearch");
+            if brave_quota.daily_per_device.is_none() && brave_quota.monthly.is_none() {
+                println!("  ⚠ No BRAVE_DAILY_LIMIT or BRAVE_MONTHLY_LIMIT (web search is unmetered)");
+            }
+        }
+        None => println!("  ⚠ BRAVE_API_KEY not set (web search disabled)"),
+    }
+    toolbelts::places::install(Places::from_env())?;
+    println!("  ✓ Places");
+    toolbelts::reports::install(Reports::new(db.clone()))?;
+    println!("  ✓ Reports");
+    toolbelts::watcher::install(Watcher::new(db.clone()))?;
+    println!("  ✓ Watcher");
+    Ok(brave_quota)
+}
+
+/// Load moderation.json, printing which categories it blocks.
+pub fn load_moderation() -> Result<Option<ModerationConfig>> {
+    println!("→ Loading moderation rules...");
+    let config = ModerationConfig::load()?;
+    match &config {
+        Some(config) => {
+            let names: Vec<&str> = config.categories.iter().map(|c| c.name.as_str()).collect();
+            println!("  ✓ Blocking: {}", names.join(", "));
+            if let Some(classifier) = &config.classifier {
+                println!("  ✓ Classifier: {} at {}", classifier.model, classifier.url);
+            }
+        }
+        None => println!("  ⚠ No moderation.json (messages and tool results are not moderated)"),
+    }
+    Ok(config)
+}
+
+/// Check each configured provider's key and quota now and every
+/// PROVIDER_CHECK_INTERVAL after, recording the answers for
+/// /health/providers and warning when one can't be used.
+pub fn spawn_provider_checks(db: &Arc<Db>) {
+    let Some(web_search) = WebSearch::from_env(db.clone(), WebSearch::quota_from_env()) else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(PROVIDER_CHECK_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let health = web_search.check_health().await;
+            if let Some(message) = &health.message {
+                eprintln!("⚠ Brave: {}", message);
+            } else if health.exhausted() {
+                eprintln!("⚠ Brave: monthly quota exhausted, web search unavailable");
+            }
+        }
+    });
+}
+
+/// Probe every model server now and every MODEL_PROBE_INTERVAL after,
+/// recording the answers in the GPU pool. Servers going down or coming
+/// back, and the engine entering or leaving degraded mode, are logged.
+pub fn spawn_model_probes(gpu_pool: &Arc<GpuPool>) {
+    let gpu_pool = gpu_pool.clone();
+    let http = reqwest::Client::new();
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(MODEL_PROBE_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let was_degraded = gpu_pool.is_degraded();
+            // hardware.json may have been reloaded since the last round
+            for url in gpu_pool.host_urls() {
+                let error = match http.get(format!("{}/api/tags", url)).timeout(MODEL_PROBE_TIMEOUT).send().await {
+                    Ok(response) => response.error_for_status().err().map(|e| e.to_string()),
+                    Err(e) => Some(e.to_string()),
+                };
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64;
+                if gpu_pool.record_probe(&url, error.clone(), now) {
+                    match &error {
+                        Some(e) => eprintln!("⚠ Model server {} unreachable: {}", url, e),
+                        None => println!("✓ Model server {} answering again", url),
+                    }
+                }
+            }
+            match (was_degraded, gpu_pool.is_degraded()) {
+                (false, true) => eprintln!("⚠ No model server reachable: chats refused and model jobs paused until one answers"),
+                (true, false) => println!("✓ A model server is reachable again; leaving degraded mode"),
+                _ => {}
+            }
+        }
+    });
+}
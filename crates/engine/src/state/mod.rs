@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tokio::sync::{broadcast, oneshot, Notify};
+
+use artificer_shared::db::Quota;
+
+use crate::api::events::EventLog;
+use crate::background::{JobNotice, Leadership};
+use crate::pool::{AgentPool, GpuPool};
+
+#[cfg(test)]
+mod state_tests;
+
+// ============================================================================
+// APP STATE
+// ============================================================================
+
+/// Shared application state, handed to every handler via axum `State`.
+#[derive(Clone)]
+pub struct AppState {
+    pub gpu_pool: Arc<GpuPool>,
+    pub agent_pool: Arc<AgentPool>,
+    pub job_events: broadcast::Sender<JobNotice>,
+    pub runtime: Arc<RuntimeState>,
+    /// Key for /admin endpoints. Admin endpoints are disabled when unset.
+    pub admin_key: Option<String>,
+    /// Limits on metered providers, reported by /usage.
+    pub quotas: Arc<Vec<Quota>>,
+    /// Whether this instance runs background jobs, when engines share a database.
+    pub leadership: Arc<Leadership>,
+    /// Serve reads only (`READ_ONLY=1`): no chat, no writes, no background work.
+    pub read_only: bool,
+}
+
+// ============================================================================
+// RUNTIME STATE
+// ============================================================================
+
+/// A question a specialist asked the user. The specialist waits until it is
+/// answered through /chat/continue.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingQuestion {
+    pub id: u64,
+    pub task_id: u64,
+    pub question: String,
+    pub options: Vec<String>,
+    pub asked_at: i64,
+}
+
+/// What is happening in one conversation right now. Lives only in memory —
+/// everything durable is in the database.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConversationState {
+    /// Innermost task currently executing (a specialist's task while it runs).
+    pub current_task_id: Option<u64>,
+    pub pending_questions: Vec<PendingQuestion>,
+    /// Open /chat streams answering in this conversation.
+    pub active_streams: u32,
+    /// Background job answering in this conversation, if any.
+    pub active_job: Option<u64>,
+    /// LLM iterations across all tasks since the conversation went active.
+    pub iterations: u64,
+}
+
+impl ConversationState {
+    fn is_idle(&self) -> bool {
+        self.current_task_id.is_none()
+            && self.pending_questions.is_empty()
+            && self.active_streams == 0
+            && self.active_job.is_none()
+    }
+
+    /// Whether a chat turn or background job is answering here. Only one
+    /// may at a time, or their messages would interleave.
+    pub fn is_answering(&self) -> bool {
+        self.active_streams > 0 || self.active_job.is_some()
+    }
+}
+
+/// Per-conversation runtime state shared by the API and the agent loop.
+/// Conversations drop out of the map once they go idle.
+#[derive(Debug, Default)]
+pub struct RuntimeState {
+    conversations: Mutex<HashMap<u64, ConversationState>>,
+    next_question_id: Mutex<u64>,
+    /// Where each pending question's answer goes, by question id.
+    answers: Mutex<HashMap<u64, oneshot::Sender<String>>>,
+    /// Events of each conversation's latest chat turn, for /chat/resume.
+    replays: Mutex<HashMap<u64, Arc<EventLog>>>,
+    /// How to stop each conversation's running chat turn.
+    cancels: Mutex<HashMap<u64, Arc<Notify>>>,
+}
+
+impl RuntimeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update<T>(&self, conversation_id: u64, f: impl FnOnce(&mut ConversationState) -> T) -> T {
+        let mut conversations = self.conversations.lock().unwrap();
+        let state = conversations.entry(conversation_id).or_default();
+        let result = f(state);
+        if state.is_idle() {
+            conversations.remove(&conversation_id);
+        }
+        result
+    }
+
+    pub fn get(&self, conversation_id: u64) -> Option<ConversationState> {
+        self.conversations.lock().unwrap().get(&conversation_id).cloned()
+    }
+
+    /// Claim the conversation for a streaming response. Returns None if a
+    /// stream or background job is already answering in it; the claim is
+    /// released when the guard drops.
+    pub fn begin_stream(self: &Arc<Self>, conversation_id: u64) -> Option<TurnGuard> {
+        self.update(conversation_id, |state| {
+            if state.is_answering() {
+                return None;
+            }
+            state.active_streams += 1;
+            Some(TurnGuard { runtime: self.clone(), conversation_id, job: false })
+        })
+    }
+
+    /// Claim the conversation for background job `job_id`, like `begin_stream`.
+    pub fn begin_job(self: &Arc<Self>, conversation_id: u64, job_id: u64) -> Option<TurnGuard> {
+        self.update(conversation_id, |state| {
+            if state.is_answering() {
+                return None;
+            }
+            state.active_job = Some(job_id);
+            Some(TurnGuard { runtime: self.clone(), conversation_id, job: true })
+        })
+    }
+
+    /// Mark `task_id` as the one executing. Returns the task it replaced, to hand
+    /// back to `exit_task` when this one finishes.
+    pub fn enter_task(&self, conversation_id: u64, task_id: u64) -> Option<u64> {
+        self.update(conversation_id, |state| state.current_task_id.replace(task_id))
+    }
+
+    pub fn exit_task(&self, conversation_id: u64, previous: Option<u64>) {
+        self.update(conversation_id, |state| state.current_task_id = previous);
+    }
+
+    pub fn record_iteration(&self, conversation_id: u64) {
+        self.update(conversation_id, |state| state.iterations += 1);
+    }
+}
+
+impl RuntimeState {
+    /// Park a question for the user. Returns its id and the receiver its
+    /// answer arrives on.
+    pub fn ask_question(
+        &self,
+        conversation_id: u64,
+        task_id: u64,
+        question: &str,
+        options: Vec<String>,
+    ) -> (u64, oneshot::Receiver<String>) {
+        let id = {
+            let mut next = self.next_question_id.lock().unwrap();
+            *next += 1;
+            *next
+        };
+        let asked_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let (tx, rx) = oneshot::channel();
+        self.answers.lock().unwrap().insert(id, tx);
+        self.update(conversation_id, |state| {
+            state.pending_questions.push(PendingQuestion {
+                id,
+                task_id,
+                question: question.to_string(),
+                options,
+                asked_at,
+            });
+        });
+        (id, rx)
+    }
+
+    /// Deliver an answer to a pending question in this conversation, or to its
+    /// oldest one when `question_id` is None. Returns the question answered.
+    pub fn answer_question(&self, conversation_id: u64, question_id: Option<u64>, answer: &str) -> Option<PendingQuestion> {
+        let question = self.take_question(conversation_id, question_id)?;
+        if let Some(tx) = self.answers.lock().unwrap().remove(&question.id) {
+            let _ = tx.send(answer.to_string());
+        }
+        Some(question)
+    }
+
+    /// Drop a question nobody answered.
+    pub fn withdraw_question(&self, conversation_id: u64, question_id: u64) {
+        self.take_question(conversation_id, Some(question_id));
+        self.answers.lock().unwrap().remove(&question_id);
+    }
+
+    fn take_question(&self, conversation_id: u64, question_id: Option<u64>) -> Option<PendingQuestion> {
+        self.update(conversation_id, |state| {
+            let index = match question_id {
+                Some(id) => state.pending_questions.iter().position(|q| q.id == id)?,
+                None if state.pending_questions.is_empty() => return None,
+                None => 0,
+            };
+            Some(state.pending_questions.remove(index))
+        })
+    }
+}
+
+impl RuntimeState {
+    /// Start recording a new chat turn, replacing the previous turn's events.
+    pub fn open_replay(&self, conversation_id: u64) -> Arc<EventLog> {
+        let log = EventLog::new();
+        self.replays.lock().unwrap().insert(conversation_id, log.clone());
+        log
+    }
+
+    /// The latest chat turn in a conversation, if it is running or recently ended.
+    pub fn replay(&self, conversation_id: u64) -> Option<Arc<EventLog>> {
+        self.replays.lock().unwrap().get(&conversation_id).cloned()
+    }
+
+    /// Forget a turn's events, unless a newer turn has replaced them.
+    pub fn close_replay(&self, conversation_id: u64, log: &Arc<EventLog>) {
+        let mut replays = self.replays.lock().unwrap();
+        if replays.get(&conversation_id).is_some_and(|current| Arc::ptr_eq(current, log)) {
+            replays.remove(&conversation_id);
+        }
+    }
+}
+
+impl RuntimeState {
+    /// A fresh cancel signal for a chat turn about to start.
+    pub fn open_cancel(&self, conversation_id: u64) -> Arc<Notify> {
+        let signal = Arc::new(Notify::new());
+        self.cancels.lock().unwrap().insert(conversation_id, signal.clone());
+        signal
+    }
+
+    /// Ask the conversation's running chat turn to stop. Returns false if
+    /// none is running. A turn still waiting for a GPU leaves the queue
+    /// right away.
+    pub fn cancel_turn(&self, conversation_id: u64) -> bool {
+        match self.cancels.lock().unwrap().get(&conversation_id) {
+            Some(signal) => {
+                // notify_one keeps the permit if the turn isn't listening yet
+                signal.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a turn's cancel signal, unless a newer turn has replaced it.
+    pub fn close_cancel(&self, conversation_id: u64, signal: &Arc<Notify>) {
+        let mut cancels = self.cancels.lock().unwrap();
+        if cancels.get(&conversation_id).is_some_and(|current| Arc::ptr_eq(current, signal)) {
+            cancels.remove(&conversation_id);
+        }
+    }
+
+    /// Clear what a cancelled turn left behind: the task it was in and the
+    /// questions nobody will wait for now.
+    pub fn abandon_turn(&self, conversation_id: u64) {
+        let questions: Vec<u64> = self.update(conversation_id, |state| {
+            state.current_task_id = None;
+            state.pending_questions.drain(..).map(|q| q.id).collect()
+        });
+        let mut answers = self.answers.lock().unwrap();
+        for id in questions {
+            answers.remove(&id);
+        }
+    }
+}
+
+/// Held for the lifetime of a /chat stream or background job; releases the
+/// conversation on drop.
+pub struct TurnGuard {
+    runtime: Arc<RuntimeState>,
+    conversation_id: u64,
+    job: bool,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        self.runtime.update(self.conversation_id, |state| {
+            if self.job {
+                state.active_job = None;
+            } else {
+                state.active_streams = state.active_streams.saturating_sub(1);
+            }
+        });
+    }
+}
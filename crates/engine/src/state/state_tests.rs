@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::state::RuntimeState;
+
+    #[test]
+    fn one_answer_per_conversation_at_a_time() {
+        let runtime = Arc::new(RuntimeState::new());
+
+        let stream = runtime.begin_stream(1).unwrap();
+        assert!(runtime.begin_stream(1).is_none());
+        assert!(runtime.begin_job(1, 10).is_none());
+        assert!(runtime.begin_job(2, 11).is_some());
+
+        drop(stream);
+        let job = runtime.begin_job(1, 10).unwrap();
+        assert_eq!(runtime.get(1).unwrap().active_job, Some(10));
+        assert!(runtime.begin_stream(1).is_none());
+
+        drop(job);
+        assert!(runtime.get(1).is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelling_reaches_the_running_turn_only() {
+        let runtime = Arc::new(RuntimeState::new());
+        assert!(!runtime.cancel_turn(1));
+
+        let first = runtime.open_cancel(1);
+        let second = runtime.open_cancel(1);
+        runtime.close_cancel(1, &first);
+        assert!(runtime.cancel_turn(1));
+        // The permit waits for a turn that wasn't listening yet
+        tokio::time::timeout(std::time::Duration::from_secs(1), second.notified()).await.unwrap();
+
+        let (_, answer) = runtime.ask_question(1, 7, "Which one?", Vec::new());
+        runtime.abandon_turn(1);
+        assert!(answer.await.is_err());
+        assert!(runtime.get(1).is_none());
+
+        runtime.close_cancel(1, &second);
+        assert!(!runtime.cancel_turn(1));
+    }
+}
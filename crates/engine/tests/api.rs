@@ -0,0 +1,237 @@
+//! The HTTP API as envoy uses it, end to end: registration, streamed and
+//! resumed and cancelled chats, deferred chats and jobs. Chat event sequences are kept as
+//! snapshots under `tests/snapshots`; a change to them is a change to the
+//! contract envoy depends on.
+
+mod common;
+
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use artificer_shared::events::CONVERSATION_ID_HEADER;
+use common::{assert_snapshot, sse_events, TestEngine, MODEL, MODEL_CHUNKS};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn registering_again_keeps_the_id_and_replaces_the_key() {
+    let engine = TestEngine::start().await;
+    let (first_id, first_key) = engine.register("laptop").await;
+    let (second_id, second_key) = engine.register("laptop").await;
+    assert_eq!(first_id, second_id);
+    assert_ne!(first_key, second_key);
+
+    let stale = engine.post("/jobs/inbox", json!({ "device_id": first_id, "device_key": first_key })).await;
+    assert_eq!(stale.status(), 401);
+    let current = engine.post("/jobs/inbox", json!({ "device_id": second_id, "device_key": second_key })).await;
+    assert_eq!(current.status(), 200);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chat_streams_the_answer_and_stores_the_exchange() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "Say hello",
+    })).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/event-stream"));
+    let conversation_id: u64 = response.headers()[CONVERSATION_ID_HEADER].to_str().unwrap().parse().unwrap();
+
+    assert_snapshot("chat_answer", &sse_events(response).await);
+
+    // The model saw the message, and the exchange was kept
+    let requests = engine.model.requests.lock().unwrap().clone();
+    let last = requests.last().expect("the model was never called");
+    assert_eq!(last["model"], MODEL);
+    assert!(last["messages"].as_array().unwrap().iter().any(|m| m["role"] == "user" && m["content"] == "Say hello"));
+
+    let messages: Value = engine.post("/conversations/messages", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "conversation_id": conversation_id,
+    })).await.json().await.unwrap();
+    let text = messages.to_string();
+    assert!(text.contains("Say hello"));
+    assert!(text.contains(&MODEL_CHUNKS.concat()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resume_replays_the_turn_from_any_event() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "Say hello",
+    })).await;
+    let conversation_id: u64 = response.headers()[CONVERSATION_ID_HEADER].to_str().unwrap().parse().unwrap();
+    let streamed = sse_events(response).await;
+
+    let resume = |last_event_id: Option<u64>| engine.post("/chat/resume", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "conversation_id": conversation_id,
+        "last_event_id": last_event_id,
+    }));
+    assert_eq!(sse_events(resume(None).await).await, streamed);
+    assert_eq!(sse_events(resume(Some(2)).await).await, streamed[2..]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chat_rejects_bad_credentials_and_empty_messages() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": "not-the-key",
+        "message": "Say hello",
+    })).await;
+    assert_eq!(response.status(), 401);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "unauthorized");
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "   ",
+    })).await;
+    assert_eq!(response.status(), 422);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["field"], "message");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chat_answers_model_unavailable_while_degraded() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+    engine.state.gpu_pool.record_probe(&engine.model.url, Some("connection refused".to_string()), 1_700_000_000);
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "Say hello",
+    })).await;
+    assert_eq!(response.status(), 503);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "model_unavailable");
+    assert_eq!(body["hosts"][0]["url"], engine.model.url.as_str());
+    assert_eq!(body["hosts"][0]["reachable"], false);
+
+    let status: Value = engine.get("/status").await.json().await.unwrap();
+    assert_eq!(status["status"], "degraded");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deferred_chat_is_queued_as_a_job() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "Say hello later",
+        "deferred": true,
+    })).await;
+    assert_eq!(response.status(), 202);
+    let queued: Value = response.json().await.unwrap();
+    assert!(queued["conversation_id"].is_u64());
+
+    let job: Value = engine.post("/jobs/status", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "job_id": queued["job_id"],
+    })).await.json().await.unwrap();
+    assert_eq!(job["method"], "agent");
+    assert_eq!(job["status"], "pending");
+    assert_eq!(job["arguments"]["agent"], "Orchestrator");
+    assert_eq!(job["arguments"]["goal"], "Say hello later");
+    assert_eq!(job["arguments"]["conversation_id"], queued["conversation_id"]);
+
+    // Nothing ran it, so nothing waits in the inbox
+    let inbox: Value = engine.post("/jobs/inbox", json!({ "device_id": device_id, "device_key": device_key })).await.json().await.unwrap();
+    assert_eq!(inbox["jobs"], json!([]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jobs_are_queued_validated_and_kept_to_their_device() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+    let (other_id, other_key) = engine.register("phone").await;
+
+    let response = engine.post("/jobs", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "method": "agent",
+        "arguments": { "agent": "Archivist", "goal": "Count my conversations" },
+    })).await;
+    assert_eq!(response.status(), 200);
+    let queued: Value = response.json().await.unwrap();
+    assert!(queued["job_id"].is_u64());
+    assert!(queued["conversation_id"].is_u64());
+
+    let status = |id: i64, key: &str| engine.post("/jobs/status", json!({
+        "device_id": id,
+        "device_key": key,
+        "job_id": queued["job_id"],
+    }));
+    let job: Value = status(device_id, &device_key).await.json().await.unwrap();
+    assert_eq!(job["status"], "pending");
+    assert_eq!(status(other_id, &other_key).await.status(), 403);
+
+    let response = engine.post("/jobs", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "method": "agent",
+        "arguments": { "agent": "Archivist" },
+    })).await;
+    assert_eq!(response.status(), 422);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["field"], "arguments.goal");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_cancelled_answer_is_kept_without_secrets() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+    let chunks = vec!["Your key is ".to_string(), device_key.clone(), ", keep it safe".to_string()];
+    *engine.model.reply.lock().unwrap() = Some((chunks, std::time::Duration::from_secs(30)));
+
+    let response = engine.post("/chat", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "message": "What is my key?",
+    })).await;
+    let conversation_id: u64 = response.headers()[CONVERSATION_ID_HEADER].to_str().unwrap().parse().unwrap();
+
+    // Cancel once the model has streamed everything but its end
+    let mut stream = response.bytes_stream();
+    let mut streamed = String::new();
+    while !streamed.contains("keep it safe") {
+        let bytes = tokio::time::timeout(std::time::Duration::from_secs(30), stream.next())
+            .await
+            .expect("the answer never streamed")
+            .expect("the stream ended early")
+            .unwrap();
+        streamed.push_str(&String::from_utf8_lossy(&bytes));
+    }
+    let cancelled = engine.post("/chat/cancel", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "conversation_id": conversation_id,
+    })).await;
+    assert_eq!(cancelled.status(), 200);
+    while stream.next().await.is_some() {}
+
+    let messages: Value = engine.post("/conversations/messages", json!({
+        "device_id": device_id,
+        "device_key": device_key,
+        "conversation_id": conversation_id,
+    })).await.json().await.unwrap();
+    let text = messages.to_string();
+    assert!(text.contains("Your key is [redacted], keep it safe"), "{}", text);
+    assert!(!text.contains(&device_key));
+}
@@ -0,0 +1,241 @@
+//! Shared setup for the API integration tests: an engine on an in-memory
+//! database, served in-process, answering through a stand-in model server.
+
+// Each test binary uses its own part of this
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::body::Body;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, watch};
+
+use artificer_engine::api::server::start_test_server;
+use artificer_engine::background::Leadership;
+use artificer_engine::pool::gpu_pool::{GpuConfig, HardwareConfig};
+use artificer_engine::pool::{AgentPool, GpuPool, GpuRole};
+use artificer_engine::state::{AppState, RuntimeState};
+use artificer_shared::db::Db;
+use artificer_shared::executor::ToolExecutor;
+
+/// What the mock model answers, one streamed chunk per item.
+pub const MODEL_CHUNKS: &[&str] = &["Hello", " from", " the mock model."];
+
+/// Model name of the one GPU the engine is given.
+pub const MODEL: &str = "mock-model";
+
+/// Chunks to stream, and how long to pause after them before `done`.
+pub type ScriptedReply = (Vec<String>, Duration);
+
+/// An Ollama stand-in that answers every chat with `MODEL_CHUNKS`, and
+/// keeps the requests it got.
+pub struct MockModel {
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<Value>>>,
+    /// How long it thinks before answering, to catch a turn mid-way.
+    pub delay: Arc<Mutex<Duration>>,
+    /// What it streams instead of `MODEL_CHUNKS`, and how long it then
+    /// pauses before finishing, to catch a turn mid-answer.
+    pub reply: Arc<Mutex<Option<ScriptedReply>>>,
+}
+
+impl MockModel {
+    async fn start() -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let delay = Arc::new(Mutex::new(Duration::ZERO));
+        let thinking = delay.clone();
+        let reply = Arc::new(Mutex::new(None));
+        let scripted = reply.clone();
+        let app = Router::new()
+            .route("/api/tags", get(|| async { Json(json!({ "models": [{ "name": MODEL }] })) }))
+            .route("/api/chat", post(move |Json(request): Json<Value>| {
+                let seen = seen.clone();
+                let thinking = *thinking.lock().unwrap();
+                let scripted = scripted.lock().unwrap().clone();
+                async move {
+                    let streaming = request["stream"].as_bool().unwrap_or(false);
+                    seen.lock().unwrap().push(request);
+                    tokio::time::sleep(thinking).await;
+                    match scripted {
+                        Some((chunks, pause)) if streaming => stalled_reply(chunks, pause),
+                        _ => model_reply(streaming),
+                    }
+                }
+            }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        Self { url, requests, delay, reply }
+    }
+}
+
+/// An answer as Ollama sends it: NDJSON chunks ending in `done`, or one object.
+fn model_reply(streaming: bool) -> Response {
+    if !streaming {
+        return Json(json!({
+            "message": { "role": "assistant", "content": MODEL_CHUNKS.concat() },
+            "done": true,
+        })).into_response();
+    }
+    let mut lines: Vec<String> = MODEL_CHUNKS.iter()
+        .map(|chunk| json!({ "message": { "role": "assistant", "content": chunk }, "done": false }).to_string())
+        .collect();
+    lines.push(json!({ "message": { "role": "assistant", "content": "" }, "done": true }).to_string());
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from(lines.join("\n") + "\n")).into_response()
+}
+
+/// `chunks` streamed as Ollama sends them, then `pause` before `done`.
+fn stalled_reply(chunks: Vec<String>, pause: Duration) -> Response {
+    let lines = chunks.into_iter()
+        .map(|chunk| json!({ "message": { "role": "assistant", "content": chunk }, "done": false }).to_string() + "\n");
+    let done = futures_util::stream::once(async move {
+        tokio::time::sleep(pause).await;
+        json!({ "message": { "role": "assistant", "content": "" }, "done": true }).to_string() + "\n"
+    });
+    let body = futures_util::stream::iter(lines).chain(done).map(Ok::<_, std::convert::Infallible>);
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(body)).into_response()
+}
+
+/// An engine serving the API on a local port. Nothing runs in the
+/// background: queued jobs stay pending.
+pub struct TestEngine {
+    pub url: String,
+    pub state: AppState,
+    pub model: MockModel,
+    client: reqwest::Client,
+    _shutdown: watch::Sender<bool>,
+}
+
+impl TestEngine {
+    pub async fn start() -> Self {
+        let model = MockModel::start().await;
+        let db = Arc::new(Db::in_memory().unwrap());
+        let gpu_pool = Arc::new(GpuPool::from_config(HardwareConfig {
+            gpus: vec![GpuConfig {
+                id: "gpu-0".to_string(),
+                url: model.url.clone(),
+                model: MODEL.to_string(),
+                role: GpuRole::Interactive,
+                description: String::new(),
+                models: Vec::new(),
+            }],
+            latency: Default::default(),
+        }));
+        let agent_pool = Arc::new(AgentPool::new(db.clone(), Arc::new(ToolExecutor::new(None))));
+        let (job_events, _) = broadcast::channel(64);
+
+        let state = AppState {
+            gpu_pool,
+            agent_pool,
+            job_events,
+            runtime: Arc::new(RuntimeState::new()),
+            admin_key: None,
+            quotas: Arc::new(Vec::new()),
+            leadership: Arc::new(Leadership::new(db)),
+            read_only: false,
+        };
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let addr = start_test_server(state.clone(), shutdown_rx).await.unwrap();
+        Self {
+            url: format!("http://{}", addr),
+            state,
+            model,
+            client: reqwest::Client::new(),
+            _shutdown: shutdown_tx,
+        }
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> reqwest::Response {
+        self.client.post(format!("{}{}", self.url, path)).json(&body).send().await.unwrap()
+    }
+
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        self.client.get(format!("{}{}", self.url, path)).send().await.unwrap()
+    }
+
+    /// Register a device as envoy does, returning its id and key.
+    pub async fn register(&self, name: &str) -> (i64, String) {
+        let response = self.post("/devices/register", json!({ "device_name": name })).await;
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        (body["device_id"].as_i64().unwrap(), body["device_key"].as_str().unwrap().to_string())
+    }
+}
+
+/// The events of an SSE response, read to its end, one line each:
+/// `<id> <event> <data>`. Data is JSON with its keys sorted and the
+/// conversation id masked, so runs compare equal.
+pub async fn sse_events(response: reqwest::Response) -> Vec<String> {
+    let body = tokio::time::timeout(std::time::Duration::from_secs(30), response.text())
+        .await
+        .expect("the stream did not end")
+        .unwrap();
+
+    body.split("\n\n")
+        .filter_map(|block| {
+            let (mut id, mut event, mut data) = ("-", None, None);
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("id:") {
+                    id = value.trim();
+                } else if let Some(value) = line.strip_prefix("event:") {
+                    event = Some(value.trim());
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data = Some(value.trim());
+                }
+            }
+            // Heartbeats are comments, with neither
+            let (event, data) = (event?, data?);
+            let mut data: Value = serde_json::from_str(data).unwrap();
+            if data.get("conversation_id").is_some() {
+                data["conversation_id"] = json!("<conversation>");
+            }
+            Some(format!("{} {} {}", id, event, canonical(&data)))
+        })
+        .collect()
+}
+
+/// `value` as JSON with object keys in sorted order.
+fn canonical(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys.iter()
+                .map(|key| format!("{}:{}", Value::String(key.to_string()), canonical(&map[key.as_str()])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical).collect::<Vec<_>>().join(",")),
+        other => other.to_string(),
+    }
+}
+
+/// Compare `lines` with `tests/snapshots/<name>.snap`. With
+/// `UPDATE_SNAPSHOTS=1` the snapshot is rewritten instead.
+pub fn assert_snapshot(name: &str, lines: &[String]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{}.snap", name));
+    let actual = lines.join("\n") + "\n";
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1") {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No snapshot at {}; run with UPDATE_SNAPSHOTS=1 to record it", path.display()));
+    assert_eq!(
+        actual, expected,
+        "Events differ from {}; if the change is intended, run with UPDATE_SNAPSHOTS=1",
+        path.display()
+    );
+}
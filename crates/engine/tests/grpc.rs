@@ -0,0 +1,81 @@
+//! The gRPC service, served on the API's port: the same chat events as the
+//! SSE stream, and the same errors as REST mapped to gRPC statuses.
+
+mod common;
+
+use serde_json::Value;
+use tonic::Code;
+use artificer_engine::api::grpc::proto::artificer_client::ArtificerClient;
+use artificer_engine::api::grpc::proto::{ChatRequest, Credentials, RegisterDeviceRequest, ResumeChatRequest};
+use common::{TestEngine, MODEL_CHUNKS};
+
+async fn client(engine: &TestEngine) -> ArtificerClient<tonic::transport::Channel> {
+    ArtificerClient::connect(engine.url.clone()).await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chat_streams_the_same_events_as_sse() {
+    let engine = TestEngine::start().await;
+    let mut client = client(&engine).await;
+
+    let device = client.register_device(RegisterDeviceRequest { device_name: "laptop".to_string() })
+        .await.unwrap().into_inner();
+    let credentials = Credentials { device_id: device.device_id, device_key: device.device_key };
+
+    let mut stream = client.chat(ChatRequest {
+        device: Some(credentials.clone()),
+        message: "Say hello".to_string(),
+        ..Default::default()
+    }).await.unwrap().into_inner();
+
+    let mut events = Vec::new();
+    while let Some(event) = tokio::time::timeout(std::time::Duration::from_secs(30), stream.message())
+        .await
+        .expect("the stream did not end")
+        .unwrap()
+    {
+        events.push(event);
+    }
+
+    let types: Vec<&str> = events.iter().map(|e| e.r#type.as_str()).collect();
+    assert_eq!(types, ["stream_chunk", "stream_chunk", "stream_chunk", "done"]);
+    assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    let chunks: String = events[..3].iter()
+        .map(|e| serde_json::from_str::<Value>(&e.data).unwrap()["content"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(chunks, MODEL_CHUNKS.concat());
+
+    // The turn can be followed again from any event, as /chat/resume does
+    let done: Value = serde_json::from_str(&events[3].data).unwrap();
+    let mut resumed = client.resume_chat(ResumeChatRequest {
+        device: Some(credentials),
+        conversation_id: done["conversation_id"].as_u64().unwrap(),
+        last_event_id: Some(2),
+    }).await.unwrap().into_inner();
+    let mut ids = Vec::new();
+    while let Some(event) = resumed.message().await.unwrap() {
+        ids.push(event.id);
+    }
+    assert_eq!(ids, [3, 4]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn errors_map_to_grpc_statuses() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+    let mut client = client(&engine).await;
+
+    let chat = |device_key: &str, message: &str| ChatRequest {
+        device: Some(Credentials { device_id, device_key: device_key.to_string() }),
+        message: message.to_string(),
+        ..Default::default()
+    };
+    let bad_key = client.chat(chat("not-the-key", "Say hello")).await.err().unwrap();
+    assert_eq!(bad_key.code(), Code::Unauthenticated);
+    let empty = client.chat(chat(&device_key, "   ")).await.err().unwrap();
+    assert_eq!(empty.code(), Code::InvalidArgument);
+
+    let verified = client.verify_device(Credentials { device_id, device_key: "not-the-key".to_string() })
+        .await.unwrap().into_inner();
+    assert!(!verified.valid);
+}
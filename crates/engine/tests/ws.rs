@@ -0,0 +1,106 @@
+//! /chat/ws as envoy uses it: one socket for a turn, the client's answers
+//! and cancels while it runs, and the next message after `done`.
+
+mod common;
+
+use std::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+use artificer_engine::api::handlers::TURN_CANCELLED;
+use common::{TestEngine, MODEL_CHUNKS};
+
+type Socket = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn connect(engine: &TestEngine) -> Socket {
+    let url = format!("ws{}/chat/ws", engine.url.trim_start_matches("http"));
+    tokio_tungstenite::connect_async(url.as_str()).await.unwrap().0
+}
+
+async fn send(socket: &mut Socket, frame: Value) {
+    socket.send(Message::Text(frame.to_string().into())).await.unwrap();
+}
+
+/// The next JSON frame, skipping pings.
+async fn next_frame(socket: &mut Socket) -> Value {
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(30), socket.next())
+            .await
+            .expect("no frame arrived")
+            .expect("the socket closed")
+            .unwrap();
+        if let Message::Text(text) = frame {
+            return serde_json::from_str(text.as_str()).unwrap();
+        }
+    }
+}
+
+/// Frames up to and including `done`.
+async fn frames_until_done(socket: &mut Socket) -> Vec<Value> {
+    let mut frames = Vec::new();
+    loop {
+        let frame = next_frame(socket).await;
+        let done = frame["type"] == "done";
+        frames.push(frame);
+        if done {
+            return frames;
+        }
+    }
+}
+
+fn streamed_text(frames: &[Value]) -> String {
+    frames.iter()
+        .filter(|frame| frame["type"] == "stream_chunk")
+        .filter_map(|frame| frame["content"].as_str())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn one_socket_carries_answers_cancels_and_the_next_message() {
+    let engine = TestEngine::start().await;
+    let (device_id, device_key) = engine.register("laptop").await;
+    let mut socket = connect(&engine).await;
+
+    // The model takes its time, so the first turn is still running below
+    *engine.model.delay.lock().unwrap() = Duration::from_secs(5);
+    send(&mut socket, json!({ "device_id": device_id, "device_key": device_key, "message": "Say hello" })).await;
+
+    send(&mut socket, json!({ "type": "answer", "question_id": 99, "answer": "yes" })).await;
+    let mut frames = Vec::new();
+    let answered = loop {
+        let frame = next_frame(&mut socket).await;
+        if frame["type"] == "error" {
+            break frame;
+        }
+        frames.push(frame);
+    };
+    assert_eq!(answered["code"], "not_found");
+
+    send(&mut socket, json!({ "type": "message", "message": "Too soon" })).await;
+    send(&mut socket, json!({ "type": "cancel" })).await;
+    frames.extend(frames_until_done(&mut socket).await);
+    let errors: Vec<&Value> = frames.iter().filter(|frame| frame["type"] == "error").collect();
+    assert_eq!(errors[0]["code"], "resource_busy");
+    assert_eq!(errors.last().unwrap()["message"], TURN_CANCELLED);
+    assert_eq!(streamed_text(&frames), "");
+    let conversation_id = frames.last().unwrap()["conversation_id"].clone();
+
+    // After `done`, the next message runs in the same conversation
+    *engine.model.delay.lock().unwrap() = Duration::ZERO;
+    send(&mut socket, json!({ "type": "message", "message": "Say hello again" })).await;
+    let frames = frames_until_done(&mut socket).await;
+    assert_eq!(streamed_text(&frames), MODEL_CHUNKS.concat());
+    assert_eq!(frames.last().unwrap()["conversation_id"], conversation_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_bad_opening_request_closes_the_socket() {
+    let engine = TestEngine::start().await;
+    let mut socket = connect(&engine).await;
+
+    send(&mut socket, json!({ "type": "message", "message": "hello?" })).await;
+    let error = next_frame(&mut socket).await;
+    assert_eq!((error["type"].as_str(), error["code"].as_str()), (Some("error"), Some("validation_failed")));
+    let closed = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap();
+    assert!(matches!(closed, None | Some(Ok(Message::Close(_))) | Some(Err(_))));
+}
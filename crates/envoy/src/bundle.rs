@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use crate::config::Config;
+
+const BUNDLE_VERSION: u32 = 1;
+const SALT_BYTES: usize = 16;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+/// What moves to a new machine: the server, the device identity (so its
+/// conversations, settings and memories come along) and the default
+/// profile. The workspace and tool host stay behind; they belong to the
+/// machine, not the device.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Contents {
+    pub server_url: String,
+    pub device_name: String,
+    pub device_id: i64,
+    pub device_key: String,
+    pub profile: Option<String>,
+}
+
+/// A bundle as written to disk. Everything but the format fields is
+/// encrypted with a key derived from the passphrase (Argon2id), so the file
+/// is safe to carry around as long as the passphrase is not.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Bundle {
+    envoy_bundle: u32,
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+/// Write the config's server and device identity to `path`, encrypted
+/// with a passphrase asked for twice.
+pub fn export(config: &Config, path: &Path) -> Result<()> {
+    let (Some(device_id), Some(device_key)) = (config.device_id, config.device_key.clone()) else {
+        return Err(anyhow!("This envoy isn't registered yet; run it once before exporting"));
+    };
+    let passphrase = ask_passphrase("Passphrase for the bundle: ")?;
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(anyhow!("Passphrase must be at least {} characters", MIN_PASSPHRASE_CHARS));
+    }
+    if ask_passphrase("Repeat the passphrase: ")? != passphrase {
+        return Err(anyhow!("Passphrases don't match"));
+    }
+
+    let contents = Contents {
+        server_url: config.server_url.clone(),
+        device_name: config.device_name.clone(),
+        device_id,
+        device_key,
+        profile: config.profile.clone(),
+    };
+    write_private(path, &serde_json::to_string_pretty(&seal(&contents, &passphrase)?)?)
+}
+
+/// Read a bundle from `path` and take its server and device identity into
+/// the config, keeping this machine's workspace and tool host.
+pub fn import(config: &mut Config, path: &Path) -> Result<()> {
+    let bundle = read(path)?;
+    let contents = open(&bundle, &ask_passphrase("Passphrase for the bundle: ")?)?;
+
+    config.server_url = contents.server_url;
+    config.device_name = contents.device_name;
+    config.profile = contents.profile;
+    config.set_device_credentials(contents.device_id, contents.device_key)
+}
+
+/// Encrypt `contents` under `passphrase`.
+pub(crate) fn seal(contents: &Contents, passphrase: &str) -> Result<Bundle> {
+    let plaintext = serde_json::to_vec(contents)?;
+
+    let mut salt = [0u8; SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = cipher(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let data = cipher.encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| anyhow!("Failed to encrypt the bundle"))?;
+
+    Ok(Bundle {
+        envoy_bundle: BUNDLE_VERSION,
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        data: BASE64.encode(data),
+    })
+}
+
+/// The bundle written at `path`, still encrypted.
+pub(crate) fn read(path: &Path) -> Result<Bundle> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let bundle: Bundle = serde_json::from_str(&content)
+        .map_err(|_| anyhow!("{} is not an envoy config bundle", path.display()))?;
+    if bundle.envoy_bundle != BUNDLE_VERSION {
+        return Err(anyhow!("Bundle format {} isn't supported by this envoy", bundle.envoy_bundle));
+    }
+    Ok(bundle)
+}
+
+/// Decrypt a bundle with `passphrase`.
+pub(crate) fn open(bundle: &Bundle, passphrase: &str) -> Result<Contents> {
+    let salt = BASE64.decode(&bundle.salt)?;
+    let nonce = BASE64.decode(&bundle.nonce)?;
+    let data = BASE64.decode(&bundle.data)?;
+    if nonce.len() != 12 {
+        return Err(anyhow!("The bundle is damaged"));
+    }
+
+    let plaintext = cipher(passphrase, &salt)?
+        .decrypt(Nonce::from_slice(&nonce), data.as_slice())
+        .map_err(|_| anyhow!("Wrong passphrase, or the bundle is damaged"))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the bundle key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// The passphrase from ENVOY_BUNDLE_PASSPHRASE, for scripts, or else read
+/// from the terminal without echoing it.
+fn ask_passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("ENVOY_BUNDLE_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    rpassword::prompt_password(prompt)
+        .map_err(|e| anyhow!("Failed to read the passphrase: {}", e))
+}
+
+/// The bundle carries the device key, so only its owner may read it.
+pub(crate) fn write_private(path: &Path, content: &str) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
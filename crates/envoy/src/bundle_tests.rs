@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use crate::bundle::{self, Contents};
+
+    fn contents() -> Contents {
+        Contents {
+            server_url: "http://engine.tailnet:8080".to_string(),
+            device_name: "laptop".to_string(),
+            device_id: 7,
+            device_key: "device-key".to_string(),
+            profile: Some("work".to_string()),
+        }
+    }
+
+    #[test]
+    fn exported_bundles_import_with_their_passphrase() {
+        let path = std::env::temp_dir().join(format!("envoy-bundle-{}.json", std::process::id()));
+        let sealed = bundle::seal(&contents(), "correct horse").unwrap();
+        bundle::write_private(&path, &serde_json::to_string(&sealed).unwrap()).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("device-key"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert_eq!(bundle::open(&bundle::read(&path).unwrap(), "correct horse").unwrap(), contents());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_wrong_passphrase_opens_nothing() {
+        let sealed = bundle::seal(&contents(), "correct horse").unwrap();
+        let error = bundle::open(&sealed, "battery staple").unwrap_err();
+        assert!(error.to_string().contains("Wrong passphrase"));
+    }
+}
@@ -1,23 +1,258 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use futures_util::StreamExt;
-use artificer_shared::events::ChatEvent;
+use futures_util::{SinkExt, StreamExt};
+use artificer_shared::db::{BackgroundJob, Checkpoint, PathWatch, PrivacyLevel, PurgeReport, ResponseLength, ResponseStyle, TagCount, TaggedConversation, ToolChoice, ToolSwitch, UnreadConversation};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use artificer_shared::events::{ChatEvent, ChatSocketMessage, JobEvent, CONVERSATION_ID_HEADER};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use artificer_shared::errors::{ErrorBody, ErrorCode, FieldError};
+
+/// A non-2xx response from the engine, decoded from its JSON error body.
+/// Returned inside `anyhow::Error`; use `downcast_ref::<ApiError>()` to react to `code`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: reqwest::StatusCode,
+    pub code: ErrorCode,
+    pub message: String,
+    pub field: Option<String>,
+    /// Every failing field for `validation_failed`; empty otherwise.
+    pub errors: Vec<FieldError>,
+}
+
+impl ApiError {
+    async fn from_response(response: reqwest::Response) -> anyhow::Error {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let error = match serde_json::from_str::<ErrorBody>(&text) {
+            Ok(body) => ApiError {
+                status,
+                code: body.code,
+                message: body.error,
+                field: body.field,
+                errors: body.errors,
+            },
+            Err(_) => ApiError {
+                status,
+                code: ErrorCode::Unknown,
+                message: if text.is_empty() { status.to_string() } else { text },
+                field: None,
+                errors: Vec::new(),
+            },
+        };
+        error.into()
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{} {}", self.message, self.status.as_u16(), self.code.as_str())?;
+        if let Some(field) = &self.field {
+            write!(f, ", field: {}", field)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl std::error::Error for ApiError {}
 #[derive(Serialize)]
 pub struct ChatRequest {
     pub device_id: i64,
     pub device_key: String,
     pub conversation_id: Option<u64>,
-    pub message: String
+    pub message: String,
+    pub deferred: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub incognito: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<PrivacyLevel>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub toolbelts: Vec<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+#[derive(Deserialize, Debug)]
+pub struct DeferredChat {
+    pub job_id: u64,
+    pub conversation_id: u64,
+}
+#[derive(Deserialize, Debug)]
+struct JobInboxResponse {
+    jobs: Vec<BackgroundJob>,
 }
 #[derive(Deserialize, Debug)]
 pub struct RegisterDeviceResponse {
     pub device_id: i64,
     pub device_key: String
 }
+#[derive(Deserialize, Debug)]
+pub struct SearchResult {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    pub snippet: String,
+    pub match_count: u32,
+}
+#[derive(Deserialize, Debug)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+}
+#[derive(Deserialize, Debug)]
+struct ConversationTagsResponse {
+    tags: Vec<String>,
+}
+#[derive(Deserialize, Debug)]
+struct CreateCheckpointResponse {
+    checkpoint_id: u64,
+}
+#[derive(Deserialize, Debug)]
+struct ListCheckpointsResponse {
+    checkpoints: Vec<Checkpoint>,
+}
+#[derive(Deserialize, Debug)]
+struct BranchConversationResponse {
+    conversation_id: u64,
+}
+#[derive(Deserialize, Debug)]
+pub struct SavedWorkflow {
+    pub name: String,
+    pub template: String,
+    pub parameters: Vec<String>,
+}
+#[derive(Deserialize, Debug)]
+struct WorkflowsResponse {
+    workflows: Vec<SavedWorkflow>,
+}
+#[derive(Deserialize, Debug)]
+struct WatchesResponse {
+    watches: Vec<PathWatch>,
+}
+#[derive(Deserialize, Debug)]
+struct AddWatchResponse {
+    watch_id: u64,
+}
+#[derive(Deserialize, Debug)]
+pub struct DeviceSettings {
+    pub language: Option<String>,
+    pub length: Option<ResponseLength>,
+    pub code_first: bool,
+}
+#[derive(Deserialize, Debug)]
+struct ConversationPrivacyResponse {
+    level: PrivacyLevel,
+}
+#[derive(Deserialize, Debug)]
+struct ConversationToolsResponse {
+    toolbelts: Vec<ToolChoice>,
+}
+#[derive(Deserialize, Debug)]
+struct UnreadResponse {
+    conversations: Vec<UnreadConversation>,
+}
+#[derive(Deserialize, Debug)]
+struct ListTagsResponse {
+    tags: Vec<TagCount>,
+}
+#[derive(Deserialize, Debug)]
+struct TaggedConversationsResponse {
+    conversations: Vec<TaggedConversation>,
+}
+#[derive(Deserialize, Debug)]
+pub struct ToolbeltDoc {
+    pub name: String,
+    pub description: String,
+    pub location: String,
+    pub risk: String,
+    pub tools: Vec<ToolDoc>,
+}
+#[derive(Deserialize, Debug)]
+pub struct ToolDoc {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+    pub example: Option<serde_json::Value>,
+}
+#[derive(Deserialize, Debug)]
+struct ToolsResponse {
+    toolbelts: Vec<ToolbeltDoc>,
+}
+#[derive(Deserialize, Debug)]
+pub struct ToolSwitches {
+    pub disabled: Vec<ToolSwitch>,
+}
+/// A chat stream silent for this long is presumed dead. The engine sends a
+/// heartbeat every 15 seconds, so this is several missed heartbeats.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Reconnects tried in a row before a chat gives up, waiting 1s, 2s, 4s...
+const MAX_RESUME_ATTEMPTS: u32 = 5;
+
+/// Read one chat stream, passing each event to `event_handler` and recording
+/// its SSE id in `last_event_id`. Returns the conversation id once `done`
+/// arrives, or None if the stream ended or went idle first.
+async fn read_chat_stream(
+    response: reqwest::Response,
+    last_event_id: &mut u64,
+    event_handler: &mut impl FnMut(ChatEvent),
+) -> Result<Option<u64>> {
+    let mut stream = response.bytes_stream();
+    let mut buffer = Vec::new();
+    let mut event_id = None;
+    let mut data = String::new();
+
+    while let Ok(Some(chunk)) = tokio::time::timeout(STREAM_IDLE_TIMEOUT, stream.next()).await {
+        buffer.extend_from_slice(&chunk?);
+
+        // Process complete lines; a blank line ends an event
+        while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline_pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if line.is_empty() {
+                if let Some(id) = event_id.take() {
+                    *last_event_id = id;
+                }
+                let payload = std::mem::take(&mut data);
+                if let Ok(event) = serde_json::from_str::<ChatEvent>(&payload) {
+                    let done = match &event {
+                        ChatEvent::Done { conversation_id } => Some(*conversation_id),
+                        _ => None,
+                    };
+                    event_handler(event);
+                    if done.is_some() {
+                        return Ok(done);
+                    }
+                }
+            } else if let Some(id) = line.strip_prefix("id:") {
+                event_id = id.trim().parse().ok();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                // Comments (": heartbeat") and event names need no handling
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 #[derive(Clone)]
 pub struct ApiClient {
     client: reqwest::Client,
     base_url: String,
+    /// Profile new conversations are started in; None for the server default.
+    profile: Option<String>,
+    /// Start new conversations incognito: never listed and soon deleted.
+    incognito: bool,
+    /// Privacy level new conversations start with; None for the server default.
+    privacy: Option<PrivacyLevel>,
+    /// Toolbelts new conversations start with turned on or off.
+    toolbelts: Vec<ToolChoice>,
+    /// Model streamed chats run on; None for the GPU's own.
+    model: Option<String>,
+    /// Chat over /chat/ws instead of the SSE stream.
+    websocket: bool,
 }
 
 impl ApiClient {
@@ -25,6 +260,80 @@ impl ApiClient {
         Self {
             client: reqwest::Client::new(),
             base_url,
+            profile: None,
+            incognito: false,
+            privacy: None,
+            toolbelts: Vec::new(),
+            model: None,
+            websocket: false,
+        }
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+
+    pub fn incognito(&self) -> bool {
+        self.incognito
+    }
+
+    pub fn set_incognito(&mut self, incognito: bool) {
+        self.incognito = incognito;
+    }
+
+    pub fn privacy(&self) -> Option<PrivacyLevel> {
+        self.privacy
+    }
+
+    pub fn set_privacy(&mut self, privacy: Option<PrivacyLevel>) {
+        self.privacy = privacy;
+    }
+
+    pub fn toolbelts(&self) -> &[ToolChoice] {
+        &self.toolbelts
+    }
+
+    /// Turn a toolbelt on or off for new conversations, or with None back
+    /// to the default.
+    pub fn set_toolbelt(&mut self, toolbelt: &str, enabled: Option<bool>) {
+        self.toolbelts.retain(|choice| !choice.toolbelt.eq_ignore_ascii_case(toolbelt));
+        if let Some(enabled) = enabled {
+            self.toolbelts.push(ToolChoice { toolbelt: toolbelt.to_string(), enabled });
+        }
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn set_model(&mut self, model: Option<String>) {
+        self.model = model;
+    }
+
+    pub fn websocket(&self) -> bool {
+        self.websocket
+    }
+
+    pub fn set_websocket(&mut self, websocket: bool) {
+        self.websocket = websocket;
+    }
+
+    fn chat_request(&self, device_id: i64, device_key: String, conversation_id: Option<u64>, message: String) -> ChatRequest {
+        ChatRequest {
+            device_id,
+            device_key,
+            conversation_id,
+            message,
+            deferred: false,
+            profile: self.profile.clone(),
+            incognito: self.incognito,
+            privacy: self.privacy,
+            toolbelts: self.toolbelts.clone(),
+            model: self.model.clone(),
         }
     }
 
@@ -34,15 +343,237 @@ impl ApiClient {
         device_key: String,
         conversation_id: Option<u64>,
         message: String,
+        event_handler: impl FnMut(ChatEvent),
+    ) -> Result<u64> {
+        let (_controls, mut receiver) = mpsc::unbounded_channel();
+        self.chat_with_controls(device_id, device_key, conversation_id, message, &mut receiver, event_handler).await
+    }
+
+    /// Like `chat`, taking answers to questions and cancels on `controls`
+    /// while the answer streams in, as `chat_ws` does. Each is sent to the
+    /// engine as its own request.
+    pub async fn chat_with_controls(
+        &self,
+        device_id: i64,
+        device_key: String,
+        conversation_id: Option<u64>,
+        message: String,
+        controls: &mut mpsc::UnboundedReceiver<ChatSocketMessage>,
         mut event_handler: impl FnMut(ChatEvent),
     ) -> Result<u64> {
         let url = format!("{}/chat", self.base_url);
+        let request = self.chat_request(device_id, device_key, conversation_id, message);
+
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        // The header names the conversation even if the stream drops before any event does
+        let conversation_id = response.headers()
+            .get(CONVERSATION_ID_HEADER)
+            .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+            .or(conversation_id);
+
+        let reading = async {
+            let mut last_event_id = 0;
+            let mut response = response;
+            let mut attempts = 0;
+            loop {
+                let before = last_event_id;
+                match read_chat_stream(response, &mut last_event_id, &mut event_handler).await {
+                    Ok(Some(done_id)) => return Ok(done_id),
+                    Ok(None) => eprintln!("\n[chat stream ended early or went quiet, reconnecting...]"),
+                    Err(e) => eprintln!("\n[connection to the engine dropped ({}), reconnecting...]", e),
+                }
+
+                // A stream that delivered events earns a fresh set of attempts
+                if last_event_id > before {
+                    attempts = 0;
+                }
+                let Some(conversation_id) = conversation_id else {
+                    return Err(anyhow::anyhow!("Chat stream ended before the engine named the conversation"));
+                };
+
+                response = loop {
+                    attempts += 1;
+                    if attempts > MAX_RESUME_ATTEMPTS {
+                        return Err(anyhow::anyhow!("Lost the chat stream after {} reconnect attempts", MAX_RESUME_ATTEMPTS));
+                    }
+                    tokio::time::sleep(Duration::from_secs(1 << (attempts - 1))).await;
+                    match self.resume_chat(device_id, &request.device_key, conversation_id, last_event_id).await {
+                        Ok(response) => break response,
+                        Err(e) if e.downcast_ref::<ApiError>().is_some() => return Err(e),
+                        Err(_) => continue,
+                    }
+                };
+            }
+        };
 
+        let controlling = async {
+            while let Some(control) = controls.recv().await {
+                let Some(conversation_id) = conversation_id else {
+                    continue;
+                };
+                let sent = match control {
+                    ChatSocketMessage::Cancel => self.cancel_chat(device_id, &request.device_key, conversation_id).await,
+                    ChatSocketMessage::Answer { question_id, answer } => {
+                        self.continue_chat(device_id, &request.device_key, conversation_id, question_id, &answer).await
+                    }
+                    // The next message waits for this answer to end
+                    ChatSocketMessage::Message { .. } => Ok(()),
+                };
+                if let Err(e) = sent {
+                    eprintln!("\n[{}]", e);
+                }
+            }
+        };
+
+        // Controls only end when their sender is dropped; the answer still
+        // streams in
+        tokio::pin!(reading);
+        tokio::select! {
+            result = &mut reading => return result,
+            _ = controlling => {}
+        }
+        reading.await
+    }
+
+    /// Like `chat`, but over /chat/ws. Messages sent on `controls` while the
+    /// turn runs (answers to questions, cancel) go to the engine on the same
+    /// socket. A dropped socket is not resumed; the turn keeps running and
+    /// its answer lands in the conversation.
+    pub async fn chat_ws(
+        &self,
+        device_id: i64,
+        device_key: String,
+        conversation_id: Option<u64>,
+        message: String,
+        controls: &mut mpsc::UnboundedReceiver<ChatSocketMessage>,
+        mut event_handler: impl FnMut(ChatEvent),
+    ) -> Result<u64> {
+        // http:// becomes ws://, https:// wss://
+        let url = format!("ws{}/chat/ws", self.base_url.trim_start_matches("http"));
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+        let (mut sender, mut receiver) = socket.split();
+
+        let request = self.chat_request(device_id, device_key, conversation_id, message);
+        sender.send(WsMessage::Text(serde_json::to_string(&request)?.into())).await?;
+
+        let mut last_error = None;
+        loop {
+            tokio::select! {
+                frame = receiver.next() => {
+                    let text = match frame {
+                        Some(Ok(WsMessage::Text(text))) => text,
+                        Some(Ok(WsMessage::Close(_))) | None => {
+                            return Err(anyhow::anyhow!(
+                                "The engine closed the chat socket: {}",
+                                last_error.as_deref().unwrap_or("no reason given")
+                            ));
+                        }
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => return Err(e.into()),
+                    };
+                    let Ok(event) = serde_json::from_str::<ChatEvent>(text.as_str()) else {
+                        continue;
+                    };
+                    let done = match &event {
+                        ChatEvent::Done { conversation_id } => Some(*conversation_id),
+                        ChatEvent::Error { message } => {
+                            last_error = Some(message.clone());
+                            None
+                        }
+                        _ => None,
+                    };
+                    event_handler(event);
+                    if let Some(conversation_id) = done {
+                        let _ = sender.send(WsMessage::Close(None)).await;
+                        return Ok(conversation_id);
+                    }
+                }
+                Some(control) = controls.recv() => {
+                    sender.send(WsMessage::Text(serde_json::to_string(&control)?.into())).await?;
+                }
+            }
+        }
+    }
+
+    /// Stop the conversation's running chat turn. A turn that already ended
+    /// is not an error.
+    pub async fn cancel_chat(&self, device_id: i64, device_key: &str, conversation_id: u64) -> Result<()> {
+        let url = format!("{}/chat/cancel", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+            }))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+        Ok(())
+    }
+
+    /// Reattach to a conversation's chat turn, asking for the events after `last_event_id`.
+    async fn resume_chat(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        last_event_id: u64,
+    ) -> Result<reqwest::Response> {
+        let url = format!("{}/chat/resume", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "last_event_id": last_event_id,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+        Ok(response)
+    }
+
+    /// Queue a message to be answered in the background. Returns as soon as the job is queued.
+    pub async fn chat_deferred(
+        &self,
+        device_id: i64,
+        device_key: String,
+        conversation_id: Option<u64>,
+        message: String,
+    ) -> Result<DeferredChat> {
+        let url = format!("{}/chat", self.base_url);
         let request = ChatRequest {
             device_id,
             device_key,
             conversation_id,
             message,
+            deferred: true,
+            profile: self.profile.clone(),
+            incognito: self.incognito,
+            privacy: self.privacy,
+            toolbelts: self.toolbelts.clone(),
+            // Background answers run on the background GPUs' models
+            model: None,
         };
 
         let response = self.client
@@ -52,63 +583,769 @@ impl ApiClient {
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!("Request failed: {}", response.status()));
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<DeferredChat>().await?)
+    }
+
+    /// Deferred jobs that finished since the last call. Each job is returned once.
+    pub async fn job_inbox(&self, device_id: i64, device_key: &str) -> Result<Vec<BackgroundJob>> {
+        let url = format!("{}/jobs/inbox", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<JobInboxResponse>().await?.jobs)
+    }
+
+    /// Follow the device's notification stream, calling `event_handler` for
+    /// each job event. Returns when the stream ends or goes quiet.
+    pub async fn device_events(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        mut event_handler: impl FnMut(JobEvent),
+    ) -> Result<()> {
+        let url = format!("{}/devices/{}/events", self.base_url, device_id);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
         }
 
         let mut stream = response.bytes_stream();
         let mut buffer = Vec::new();
-        let mut final_conv_id = 0;
+        let mut data = String::new();
 
-        while let Some(chunk) = stream.next().await {
-            let bytes = chunk?;
-            buffer.extend_from_slice(&bytes);
+        // Heartbeats keep an idle stream talking, so silence means it is gone
+        while let Ok(Some(chunk)) = tokio::time::timeout(STREAM_IDLE_TIMEOUT, stream.next()).await {
+            buffer.extend_from_slice(&chunk?);
 
-            // Process complete lines
             while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
                 let line: Vec<u8> = buffer.drain(..=newline_pos).collect();
                 let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches(['\r', '\n']);
 
-                // SSE format: "data: {json}\n"
-                if let Some(data) = line.strip_prefix("data: ") {
-                    let data = data.trim();
-                    if data.is_empty() {
-                        continue;
-                    }
-
-                    if let Ok(event) = serde_json::from_str::<ChatEvent>(data) {
-                        if let ChatEvent::Done { conversation_id } = &event {
-                            final_conv_id = *conversation_id;
-                        }
+                if line.is_empty() {
+                    let payload = std::mem::take(&mut data);
+                    if let Ok(event) = serde_json::from_str::<JobEvent>(&payload) {
                         event_handler(event);
                     }
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push_str(value.strip_prefix(' ').unwrap_or(value));
                 }
             }
         }
 
-        Ok(final_conv_id)
+        Ok(())
     }
 
-    pub async fn verify_device(&self, device_id: i64, device_key: &str) -> Result<bool> {
-        let url = format!("{}/devices/verify", self.base_url);
+    pub async fn search(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        query: &str,
+    ) -> Result<Vec<SearchResult>> {
+        let url = format!("{}/conversations/search", self.base_url);
         let response = self.client
             .post(&url)
-            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "query": query,
+            }))
             .send()
             .await?;
-        Ok(response.status().is_success())
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<SearchResponse>().await?.results)
     }
 
-    pub async fn register_device(&self, device_name: String) -> Result<(i64, String)> {
-        let url = format!("{}/devices/register", self.base_url);
+    /// Add and remove tags on a conversation. Returns the tags it now has.
+    pub async fn tag_conversation(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<Vec<String>> {
+        let url = format!("{}/conversations/tags", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "add": add,
+                "remove": remove,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ConversationTagsResponse>().await?.tags)
+    }
 
+    /// How much of a conversation may be saved as memories.
+    pub async fn conversation_privacy(&self, device_id: i64, device_key: &str, conversation_id: u64) -> Result<PrivacyLevel> {
+        let url = format!("{}/conversations/privacy", self.base_url);
         let response = self.client
             .post(&url)
-            .json(&serde_json::json!({ "device_name": device_name }))
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ConversationPrivacyResponse>().await?.level)
+    }
+
+    pub async fn set_conversation_privacy(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        level: PrivacyLevel,
+    ) -> Result<()> {
+        let url = format!("{}/conversations/privacy/set", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "level": level,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// The toolbelts a conversation turned on or off.
+    pub async fn conversation_tools(&self, device_id: i64, device_key: &str, conversation_id: u64) -> Result<Vec<ToolChoice>> {
+        let url = format!("{}/conversations/tools", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+            }))
             .send()
-            .await?
-            .json::<RegisterDeviceResponse>()
             .await?;
 
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ConversationToolsResponse>().await?.toolbelts)
+    }
+
+    /// Turn a toolbelt on or off for a conversation, or with None back to
+    /// the default. Returns every choice the conversation has made.
+    pub async fn set_conversation_tools(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        toolbelt: &str,
+        enabled: Option<bool>,
+    ) -> Result<Vec<ToolChoice>> {
+        let url = format!("{}/conversations/tools/set", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "toolbelt": toolbelt,
+                "enabled": enabled,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ConversationToolsResponse>().await?.toolbelts)
+    }
+
+    /// Conversations with answers this device hasn't read, newest first.
+    pub async fn unread(&self, device_id: i64, device_key: &str) -> Result<Vec<UnreadConversation>> {
+        let url = format!("{}/conversations/unread", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<UnreadResponse>().await?.conversations)
+    }
+
+    /// Mark a conversation read on this device, up to its newest message.
+    pub async fn mark_read(&self, device_id: i64, device_key: &str, conversation_id: u64) -> Result<()> {
+        let url = format!("{}/conversations/read", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Freeze the conversation as it is now. Returns the checkpoint's id.
+    pub async fn create_checkpoint(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        name: Option<&str>,
+    ) -> Result<u64> {
+        let url = format!("{}/conversations/checkpoints/create", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "name": name,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<CreateCheckpointResponse>().await?.checkpoint_id)
+    }
+
+    pub async fn list_checkpoints(&self, device_id: i64, device_key: &str, conversation_id: u64) -> Result<Vec<Checkpoint>> {
+        let url = format!("{}/conversations/checkpoints", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ListCheckpointsResponse>().await?.checkpoints)
+    }
+
+    /// Start a new conversation from a checkpoint. Returns its id.
+    pub async fn branch_conversation(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        checkpoint_id: u64,
+    ) -> Result<u64> {
+        let url = format!("{}/conversations/checkpoints/branch", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "checkpoint_id": checkpoint_id,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<BranchConversationResponse>().await?.conversation_id)
+    }
+
+    pub async fn list_workflows(&self, device_id: i64, device_key: &str) -> Result<Vec<SavedWorkflow>> {
+        let url = format!("{}/workflows", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<WorkflowsResponse>().await?.workflows)
+    }
+
+    pub async fn save_workflow(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        name: &str,
+        template: &str,
+        defaults: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let url = format!("{}/workflows/set", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "name": name,
+                "template": template,
+                "defaults": defaults,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_workflow(&self, device_id: i64, device_key: &str, name: &str) -> Result<()> {
+        let url = format!("{}/workflows/delete", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key, "name": name }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Queue a saved workflow to run in the background, answered like a
+    /// deferred chat.
+    pub async fn run_workflow(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        name: &str,
+        arguments: &BTreeMap<String, String>,
+    ) -> Result<DeferredChat> {
+        let url = format!("{}/workflows/run", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "name": name,
+                "arguments": arguments,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<DeferredChat>().await?)
+    }
+
+    pub async fn list_watches(&self, device_id: i64, device_key: &str) -> Result<Vec<PathWatch>> {
+        let url = format!("{}/watches", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<WatchesResponse>().await?.watches)
+    }
+
+    pub async fn add_watch(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        path: &str,
+        task: &str,
+        pattern: Option<&str>,
+    ) -> Result<u64> {
+        let url = format!("{}/watches/set", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "path": path,
+                "task": task,
+                "pattern": pattern,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<AddWatchResponse>().await?.watch_id)
+    }
+
+    pub async fn delete_watch(&self, device_id: i64, device_key: &str, watch_id: u64) -> Result<()> {
+        let url = format!("{}/watches/delete", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key, "watch_id": watch_id }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Report files that changed under a watched path, queuing the watch's
+    /// task. The answer arrives like a deferred chat.
+    pub async fn trigger_watch(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        watch_id: u64,
+        paths: &[String],
+    ) -> Result<DeferredChat> {
+        let url = format!("{}/watches/trigger", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "watch_id": watch_id,
+                "paths": paths,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<DeferredChat>().await?)
+    }
+
+    pub async fn list_tags(&self, device_id: i64, device_key: &str) -> Result<Vec<TagCount>> {
+        let url = format!("{}/tags", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ListTagsResponse>().await?.tags)
+    }
+
+    /// Delete everything the server stores for this device, or with
+    /// `dry_run` only count it.
+    pub async fn purge_device(&self, device_id: i64, device_key: &str, dry_run: bool) -> Result<PurgeReport> {
+        let url = format!("{}/devices/purge", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "dry_run": dry_run,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    pub async fn tagged_conversations(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        tag: &str,
+    ) -> Result<Vec<TaggedConversation>> {
+        let url = format!("{}/tags/conversations", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "tag": tag,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<TaggedConversationsResponse>().await?.conversations)
+    }
+
+    /// Every toolbelt the engine has, with its tools' arguments and examples.
+    pub async fn tools(&self) -> Result<Vec<ToolbeltDoc>> {
+        let url = format!("{}/tools", self.base_url);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ToolsResponse>().await?.toolbelts)
+    }
+
+    /// Which toolbelts the engine has and which are turned off. Needs the
+    /// engine's admin key.
+    pub async fn tool_switches(&self, admin_key: &str) -> Result<ToolSwitches> {
+        let url = format!("{}/admin/tools", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "admin_key": admin_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json::<ToolSwitches>().await?)
+    }
+
+    /// Turn a toolbelt, or "all", off or back on.
+    pub async fn set_tool_switch(&self, admin_key: &str, name: &str, enabled: bool, reason: Option<&str>) -> Result<()> {
+        let action = if enabled { "enable" } else { "disable" };
+        let url = format!("{}/admin/tools/{}", self.base_url, action);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "admin_key": admin_key,
+                "name": name,
+                "reason": reason,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+        Ok(())
+    }
+
+    /// Answer a question a specialist asked on a chat stream.
+    pub async fn answer_question(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        question_id: u64,
+        answer: &str,
+    ) -> Result<()> {
+        self.continue_chat(device_id, device_key, conversation_id, Some(question_id), answer).await
+    }
+
+    async fn continue_chat(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        conversation_id: u64,
+        question_id: Option<u64>,
+        answer: &str,
+    ) -> Result<()> {
+        let url = format!("{}/chat/continue", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "conversation_id": conversation_id,
+                "question_id": question_id,
+                "answer": answer,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Set the language the server answers this device in until it detects one
+    /// per conversation. None clears the preference.
+    pub async fn set_language(&self, device_id: i64, device_key: &str, language: Option<&str>) -> Result<()> {
+        let url = format!("{}/devices/language", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "language": language,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    pub async fn device_settings(&self, device_id: i64, device_key: &str) -> Result<DeviceSettings> {
+        let url = format!("{}/devices/settings", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Replace the device's response style, leaving its language alone.
+    pub async fn set_response_style(&self, device_id: i64, device_key: &str, style: &ResponseStyle) -> Result<DeviceSettings> {
+        let url = format!("{}/devices/settings/set", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "length": style.length.map_or("default", |length| length.as_str()),
+                "code_first": style.code_first,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Tell the server this device's timezone and locale so answers use the
+    /// user's clock, and where its tool server listens. Fields left as None
+    /// keep their stored value.
+    pub async fn report_metadata(
+        &self,
+        device_id: i64,
+        device_key: &str,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+        tool_host: Option<&str>,
+    ) -> Result<()> {
+        let url = format!("{}/devices/metadata", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "device_id": device_id,
+                "device_key": device_key,
+                "timezone": timezone,
+                "locale": locale,
+                "tool_host": tool_host,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    pub async fn verify_device(&self, device_id: i64, device_key: &str) -> Result<bool> {
+        let url = format!("{}/devices/verify", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_id": device_id, "device_key": device_key }))
+            .send()
+            .await?;
+        Ok(response.status().is_success())
+    }
+
+    pub async fn register_device(&self, device_name: String) -> Result<(i64, String)> {
+        let url = format!("{}/devices/register", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({ "device_name": device_name }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        let response = response.json::<RegisterDeviceResponse>().await?;
+
         Ok((response.device_id, response.device_key))
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use artificer_shared::tools::toolbelts::shell_smith::ShellConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -7,7 +8,23 @@ pub struct Config {
     pub server_url: String,
     pub device_name: String,
     pub device_id: Option<i64>,
-    pub device_key: Option<String>
+    pub device_key: Option<String>,
+    /// Directory FileSmith resolves relative paths against. Defaults to the
+    /// directory envoy was started in.
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+    /// Profile new conversations start in unless `--profile` says otherwise.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// URL the engine reaches this machine's tool server at, e.g.
+    /// "http://laptop.tailnet:8081". Unset keeps the machine out of tools run
+    /// on all machines.
+    #[serde(default)]
+    pub tool_host: Option<String>,
+    /// Which programs ShellSmith may run, which need confirmation, and its
+    /// default timeout.
+    #[serde(default)]
+    pub shell: ShellConfig,
 }
 
 impl Default for Config {
@@ -17,6 +34,10 @@ impl Default for Config {
             device_name: get_hostname(),
             device_id: None,
             device_key: None,
+            workspace: None,
+            profile: None,
+            tool_host: None,
+            shell: ShellConfig::default(),
         }
     }
 }
@@ -54,10 +75,19 @@ impl Config {
         self.save()
     }
 
+    /// Where FileSmith keeps its index of workspace files.
+    pub fn index_path() -> Result<PathBuf> {
+        Ok(Self::config_dir()?.join("workspace-index.db"))
+    }
+
     fn config_path() -> Result<PathBuf> {
+        Ok(Self::config_dir()?.join("config.json"))
+    }
+
+    fn config_dir() -> Result<PathBuf> {
         let home = dirs::home_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        Ok(home.join(".config").join("envoy").join("config.json"))
+        Ok(home.join(".config").join("envoy"))
     }
 }
 
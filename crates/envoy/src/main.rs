@@ -1,11 +1,26 @@
+mod bundle;
 mod client;
 mod config;
 mod ui;
 mod tools;
+mod watcher;
+
+#[cfg(test)]
+mod bundle_tests;
 
 use anyhow::Result;
+use std::sync::Arc;
 use client::ApiClient;
 use config::Config;
+use artificer_shared::db::{Db, PrivacyLevel};
+use artificer_shared::tools::toolbelts::clipboard::Clipboard;
+use artificer_shared::tools::toolbelts::containers::Containers;
+use artificer_shared::tools::toolbelts::file_smith::FileSmith;
+use artificer_shared::tools::toolbelts::git_smith::GitSmith;
+use artificer_shared::tools::toolbelts::log_scout::LogScout;
+use artificer_shared::tools::toolbelts::notifier::Notifier;
+use artificer_shared::tools::toolbelts::screen::Screen;
+use artificer_shared::tools::toolbelts::shell_smith::ShellSmith;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -19,10 +34,93 @@ async fn main() -> Result<()> {
     };
 
     // Parse args
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // `--profile NAME` picks the profile for this session only
+    let mut profile = config.profile.clone();
+    if let Some(pos) = args.iter().position(|a| a == "--profile") {
+        if pos + 1 >= args.len() {
+            print_usage();
+            return Ok(());
+        }
+        profile = Some(args.remove(pos + 1));
+        args.remove(pos);
+    }
+
+    // `--model NAME` runs streamed chats on another model the server offers
+    let mut model = None;
+    if let Some(pos) = args.iter().position(|a| a == "--model") {
+        if pos + 1 >= args.len() {
+            print_usage();
+            return Ok(());
+        }
+        model = Some(args.remove(pos + 1));
+        args.remove(pos);
+    }
+
+    // `--privacy LEVEL` limits what new conversations may add to memory
+    let mut privacy = None;
+    if let Some(pos) = args.iter().position(|a| a == "--privacy") {
+        match args.get(pos + 1).and_then(|level| PrivacyLevel::parse(level)) {
+            Some(level) => privacy = Some(level),
+            None => {
+                print_usage();
+                return Ok(());
+            }
+        }
+        args.drain(pos..=pos + 1);
+    }
+
+    // `--ws` streams chats over a WebSocket instead of an HTTP stream
+    let websocket = match args.iter().position(|a| a == "--ws") {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        }
+        None => false,
+    };
+
+    // `--incognito` starts conversations that are never kept
+    let incognito = match args.iter().position(|a| a == "--incognito") {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        }
+        None => false,
+    };
+
+    // Moving the device between machines works offline and must happen
+    // before registering, or a new machine would become a new device
+    if args.get(1).map(|s| s.as_str()) == Some("config") {
+        match (args.get(2).map(|s| s.as_str()), args.get(3)) {
+            (Some("export"), Some(path)) => {
+                bundle::export(&config, std::path::Path::new(path))?;
+                println!("Config exported to {}. Keep the passphrase; the bundle is useless without it.", path);
+                return Ok(());
+            }
+            (Some("import"), Some(path)) => {
+                bundle::import(&mut config, std::path::Path::new(path))?;
+                println!(
+                    "Config imported: device '{}' (ID {:?}) on {}",
+                    config.device_name, config.device_id, config.server_url
+                );
+                return Ok(());
+            }
+            (Some("export" | "import"), None) => {
+                print_usage();
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
 
     // Create API client
-    let client = ApiClient::new(config.server_url.clone());
+    let mut client = ApiClient::new(config.server_url.clone());
+    client.set_profile(profile);
+    client.set_incognito(incognito);
+    client.set_privacy(privacy);
+    client.set_model(model);
+    client.set_websocket(websocket);
 
     // Register device if needed, or verify stored credentials are still valid
     let valid_creds = match (config.device_id, config.device_key.clone()) {
@@ -58,6 +156,46 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Share this machine's clock and locale so the server can reason about "today",
+    // and where its tool server listens for tools run on all machines
+    let timezone = artificer_shared::time::local_zone();
+    let locale = std::env::var("LC_ALL")
+        .or_else(|_| std::env::var("LANG"))
+        .ok()
+        .filter(|l| !l.is_empty());
+    if let Err(e) = client.report_metadata(device_id, &device_key, timezone.as_deref(), locale.as_deref(), config.tool_host.as_deref()).await {
+        eprintln!("Failed to report device timezone: {}", e);
+    }
+
+    // Client-side toolbelts run in the workspace
+    let workspace = match config.workspace.clone() {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let mut file_smith = FileSmith::new(workspace.clone());
+    match Config::index_path().and_then(|path| Db::open(&path)) {
+        Ok(db) => file_smith = file_smith.with_index(Arc::new(db)),
+        Err(e) => eprintln!("Workspace index unavailable, FileSmith will search without it: {}", e),
+    }
+    artificer_shared::tools::toolbelts::file_smith::install(file_smith)?;
+    artificer_shared::tools::toolbelts::log_scout::install(LogScout::default())?;
+    artificer_shared::tools::toolbelts::shell_smith::install(ShellSmith::new(workspace.clone(), config.shell.clone()))?;
+    if let Some(clipboard) = Clipboard::detect() {
+        artificer_shared::tools::toolbelts::clipboard::install(clipboard)?;
+    }
+    if let Some(screen) = Screen::detect(config.server_url.clone()) {
+        artificer_shared::tools::toolbelts::screen::install(screen)?;
+    }
+    if let Some(notifier) = Notifier::detect() {
+        artificer_shared::tools::toolbelts::notifier::install(notifier)?;
+    }
+    if let Some(containers) = Containers::detect() {
+        artificer_shared::tools::toolbelts::containers::install(containers)?;
+    }
+    if let Some(git_smith) = GitSmith::detect(workspace.clone()) {
+        artificer_shared::tools::toolbelts::git_smith::install(git_smith)?;
+    }
+
     // Start tool server in background
     let tool_device_key = device_key.clone();
     tokio::spawn(async move {
@@ -71,7 +209,63 @@ async fn main() -> Result<()> {
 
     match command {
         "chat" => {
-            ui::interactive_chat(client, device_id, device_key.clone()).await?;
+            ui::interactive_chat(client, device_id, device_key.clone(), None).await?;
+        }
+        "later" => {
+            if args.len() < 3 {
+                print_usage();
+            } else {
+                let message = args[2..].join(" ");
+                ui::later(client, device_id, device_key.clone(), message).await?;
+            }
+        }
+        "inbox" => {
+            if ui::print_finished_jobs(&client, device_id, &device_key).await == 0 {
+                println!("No finished background jobs.");
+            }
+        }
+        "watch" => {
+            // Only the long-running envoy follows watched paths, so an open
+            // chat alongside it doesn't queue every task twice
+            tokio::spawn(watcher::run(client.clone(), device_id, device_key.clone(), workspace));
+            ui::watch(client, device_id, device_key.clone()).await?;
+        }
+        "watches" => {
+            ui::watches(client, device_id, device_key.clone(), &args[2..]).await?;
+        }
+        "unread" => {
+            ui::unread(client, device_id, device_key.clone(), &args[2..]).await?;
+        }
+        "tags" => {
+            ui::tags(client, device_id, device_key.clone(), args.get(2).cloned()).await?;
+        }
+        "tools" => {
+            ui::tools(client, &args[2..]).await?;
+        }
+        "workflows" => {
+            ui::workflows(client, device_id, device_key.clone(), &args[2..]).await?;
+        }
+        "run" => {
+            if args.len() < 3 {
+                print_usage();
+            } else {
+                ui::run_workflow(client, device_id, device_key.clone(), &args[2], &args[3..]).await?;
+            }
+        }
+        "purge" => {
+            if args.get(2).map(|s| s.as_str()) == Some("--device") {
+                ui::purge_device(client, device_id, device_key.clone()).await?;
+            } else {
+                print_usage();
+            }
+        }
+        "search" => {
+            if args.len() < 3 {
+                print_usage();
+            } else {
+                let query = args[2..].join(" ");
+                ui::search(client, device_id, device_key.clone(), query).await?;
+            }
         }
         "config" => {
             if args.len() < 3 {
@@ -79,6 +273,18 @@ async fn main() -> Result<()> {
                 println!("  Server URL: {}", config.server_url);
                 println!("  Device Name: {}", config.device_name);
                 println!("  Device ID: {:?}", config.device_id);
+                match &config.workspace {
+                    Some(dir) => println!("  Workspace: {}", dir.display()),
+                    None => println!("  Workspace: (current directory)"),
+                }
+                println!("  Profile: {}", config.profile.as_deref().unwrap_or("default"));
+                match client.device_settings(device_id, &device_key).await {
+                    Ok(settings) => {
+                        println!("  Language: {}", settings.language.as_deref().unwrap_or("auto"));
+                        println!("  Style: {}", ui::describe_style(&settings));
+                    }
+                    Err(e) => eprintln!("  Language and style unavailable: {}", e),
+                }
             } else if args[2] == "set" && args.len() >= 5 {
                 match args[3].as_str() {
                     "server" => {
@@ -92,6 +298,27 @@ async fn main() -> Result<()> {
                         config.save()?;
                         println!("Device name updated to: {}", config.device_name);
                     }
+                    "language" => {
+                        // "auto" clears the preference and relies on detection alone
+                        let language = Some(args[4].as_str()).filter(|l| *l != "auto");
+                        match client.set_language(device_id, &device_key, language).await {
+                            Ok(()) => println!("Language updated to: {}", args[4]),
+                            Err(e) => eprintln!("Failed to set language: {}", e),
+                        }
+                    }
+                    "style" => {
+                        ui::set_style(client, device_id, device_key.clone(), &args[4]).await?;
+                    }
+                    "workspace" => {
+                        config.workspace = Some(std::path::PathBuf::from(&args[4]));
+                        config.save()?;
+                        println!("Workspace updated to: {}", args[4]);
+                    }
+                    "profile" => {
+                        config.profile = Some(args[4].clone()).filter(|p| p != "default");
+                        config.save()?;
+                        println!("Default profile updated to: {}", args[4]);
+                    }
                     _ => print_usage(),
                 }
             } else {
@@ -109,10 +336,38 @@ async fn main() -> Result<()> {
 
 fn print_usage() {
     println!("Envoy - Client for Artificer AI");
-    println!("\nUsage:");
+    println!("\nUsage: envoy [--profile NAME] [--incognito] [--privacy LEVEL] [--model NAME] [--ws] COMMAND");
     println!("  envoy chat                    Start interactive chat");
+    println!("  envoy chat --incognito        Chat without keeping history, titles or memories");
+    println!("  envoy chat --model qwen3:8b   Chat on another model the server offers");
+    println!("  envoy chat --ws               Chat over a WebSocket instead of an HTTP stream");
+    println!("  envoy chat --privacy facts-only  Save no preferences from new chats; no-extraction saves nothing");
     println!("  envoy \"your message\"          Send a single message");
+    println!("  envoy later \"your message\"    Queue a message to be answered in the background");
+    println!("  envoy inbox                   Show background answers that have finished");
+    println!("  envoy watch                   Run in the background, follow watched paths, and notify when jobs finish");
+    println!("  envoy search QUERY            Search past conversations and resume one");
+    println!("  envoy unread [clear]          List conversations with unread answers, or mark them all read");
+    println!("  envoy tags [NAME]             List tags, or conversations tagged NAME");
+    println!("  envoy tools                   List the engine's toolbelts; with ADMIN_KEY, which are off");
+    println!("  envoy tools NAME              Show a toolbelt's tools, their arguments and examples");
+    println!("  envoy tools off NAME [REASON] Turn a toolbelt, or all tools, off at once");
+    println!("  envoy tools on NAME           Turn a toolbelt, or all tools, back on");
+    println!("  envoy workflows               List saved workflows");
+    println!("  envoy workflows save NAME \"TEMPLATE\" [KEY=VALUE ...]  Save a workflow; {{KEY}} in TEMPLATE is a parameter");
+    println!("  envoy workflows delete NAME   Delete a saved workflow");
+    println!("  envoy run NAME [KEY=VALUE ...]  Run a saved workflow in the background");
+    println!("  envoy watches                 List watched paths");
+    println!("  envoy watches add PATH \"TASK\" [PATTERN]  Queue TASK when files under PATH (matching e.g. '*.pdf') change");
+    println!("  envoy watches delete ID       Stop watching a path");
+    println!("  envoy purge --device          Delete everything the server stores for this device");
     println!("  envoy config                  Show current configuration");
     println!("  envoy config set server URL   Set server URL");
     println!("  envoy config set device NAME  Set device name");
+    println!("  envoy config set workspace DIR  Set the directory file tools work in");
+    println!("  envoy config set language CODE  Set the default reply language (e.g. de, or auto)");
+    println!("  envoy config set style STYLE  Set how answers are written: concise, detailed, code-first, combined with commas, or default");
+    println!("  envoy config set profile NAME   Set the profile new conversations start in (e.g. work)");
+    println!("  envoy config export FILE      Save the server and device identity to a passphrase-encrypted bundle");
+    println!("  envoy config import FILE      Set up this machine as the device in a bundle, keeping its conversations");
 }
\ No newline at end of file
@@ -2,7 +2,7 @@ use anyhow::Result;
 use axum::{extract::Json, http::StatusCode, response::IntoResponse, routing::post, Router};
 use serde::Deserialize;
 use serde_json::{json, Value};
-use artificer_shared::tools;
+use artificer_shared::{tools, DeviceContext};
 use std::sync::Arc;
 
 struct ToolServerState {
@@ -30,7 +30,8 @@ async fn handle_tool_execution(
         );
     }
 
-    match tools::use_tool(&req.tool_name, &req.arguments) {
+    let ctx = DeviceContext::new(req.device_id, req.device_key);
+    match tools::use_tool(&req.tool_name, &req.arguments, &ctx) {
         Ok(result) => (StatusCode::OK, Json(json!({ "result": result }))),
         Err(e) => {
             eprintln!("[tool-server] Tool '{}' failed: {}", req.tool_name, e);
@@ -1,7 +1,22 @@
-use artificer_shared::events::ChatEvent;
-use crate::client::ApiClient;
+use artificer_shared::events::{ChatEvent, ChatSocketMessage, JobEvent};
+use artificer_shared::errors::ErrorCode;
+use artificer_shared::db::{BackgroundJob, ConfidenceLevel, PrivacyLevel, PurgeReport, ResponseLength, ResponseStyle};
+use artificer_shared::tools::toolbelts::notifier::Notifier;
+use crate::client::{ApiClient, ApiError, DeviceSettings, ToolbeltDoc};
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often `envoy watch` checks the job inbox.
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long an interactive chat waits before reconnecting a dropped
+/// notification stream.
+const EVENTS_RETRY: Duration = Duration::from_secs(5);
 
 pub async fn single_message(
     client: ApiClient,
@@ -11,7 +26,8 @@ pub async fn single_message(
 ) -> Result<()> {
     match client
         .chat(device_id, device_key.clone(), None, message, |event| {
-            handle_event(&event)
+            handle_event(&event);
+            answer_question(&client, device_id, &device_key, &event);
         })
         .await
     {
@@ -19,18 +35,84 @@ pub async fn single_message(
 
         }
         Err(e) => {
-            eprintln!("Error: {}", e);
+            report_error(&e);
         }
     }
     Ok(())
 }
 
-pub async fn interactive_chat(client: ApiClient, device_id: i64, device_key: String) -> Result<()> {
-    println!("Envoy chat started. Type 'quit' to exit.\n");
+pub async fn interactive_chat(
+    mut client: ApiClient,
+    device_id: i64,
+    device_key: String,
+    mut conversation_id: Option<u64>,
+) -> Result<()> {
+    match conversation_id {
+        Some(id) => println!("Resuming conversation {}. Type 'quit' to exit.", id),
+        None => println!("Envoy chat started. Type 'quit' to exit."),
+    }
+    println!("Prefix a message with /later to have it answered in the background.");
+    println!("Use /tag NAME to tag this conversation, /tag -NAME to untag it, or /tag to list its tags.");
+    println!("Use /checkpoint [NAME] to save this point, /checkpoints to list them, or /branch ID to continue from one in a new conversation.");
+    println!("Use /profile NAME to start a new conversation in another profile.");
+    println!("Use /privacy facts-only|no-extraction|normal to limit what this conversation adds to memory.");
+    println!("Use /tools off|on|default TOOLBELT (e.g. /tools off web) to choose this conversation's tools, or /tools to list the choices.");
+    println!("Press Ctrl-C while an answer is being written to stop it.\n");
+    if conversation_id.is_none()
+        && let Some(profile) = client.profile()
+    {
+        println!("Profile: {}\n", profile);
+    }
+    if conversation_id.is_none() && client.incognito() {
+        println!("Incognito: this chat is not saved to history or memory, and is deleted an hour after your last message.\n");
+    }
+    if let Some(model) = client.model() {
+        println!("Model: {}\n", model);
+    }
+    if conversation_id.is_none()
+        && let Some(privacy) = client.privacy()
+    {
+        println!("Privacy: {}\n", privacy.as_str());
+    }
+
+    // Jobs that finish while the user is typing are printed right away;
+    // ones that finish mid-answer wait for the answer to end
+    let answering = Arc::new(AtomicBool::new(false));
+    let notifications = tokio::spawn(follow_device_events(
+        client.clone(),
+        device_id,
+        device_key.clone(),
+        answering.clone(),
+    ));
+
+    // Answers delivered to the inbox are read once printed; what's left
+    // unread came some other way, e.g. was taken from the inbox elsewhere
+    if conversation_id.is_none() {
+        print_finished_jobs(&client, device_id, &device_key).await;
+        print_unread(&client, device_id, &device_key).await;
+    }
 
-    let mut conversation_id: Option<u64> = None;
+    // Ctrl-C mid-answer cancels the answer, keeping what was written so
+    // far; at the prompt it still quits
+    let (controls, mut control_receiver) = mpsc::unbounded_channel();
+    let interrupts = {
+        let answering = answering.clone();
+        let controls = controls.clone();
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if !answering.load(Ordering::SeqCst) {
+                    println!();
+                    std::process::exit(130);
+                }
+                println!("\n(cancelling)");
+                let _ = controls.send(ChatSocketMessage::Cancel);
+            }
+        })
+    };
 
     loop {
+        print_finished_jobs(&client, device_id, &device_key).await;
+
         print!("You: ");
         io::stdout().flush()?;
 
@@ -47,28 +129,993 @@ pub async fn interactive_chat(client: ApiClient, device_id: i64, device_key: Str
             continue;
         }
 
+        if let Some(message) = input.strip_prefix("/later") {
+            defer_message(&client, device_id, &device_key, conversation_id, message.trim()).await;
+            continue;
+        }
+
+        if let Some(args) = input.strip_prefix("/tag") {
+            tag_command(&client, device_id, &device_key, conversation_id, args).await;
+            continue;
+        }
+
+        if input == "/checkpoints" {
+            list_checkpoints(&client, device_id, &device_key, conversation_id).await;
+            continue;
+        }
+
+        if let Some(args) = input.strip_prefix("/checkpoint") {
+            checkpoint_command(&client, device_id, &device_key, conversation_id, args).await;
+            continue;
+        }
+
+        if let Some(args) = input.strip_prefix("/branch") {
+            if let Some(branch) = branch_command(&client, device_id, &device_key, conversation_id, args).await {
+                conversation_id = Some(branch);
+            }
+            continue;
+        }
+
+        if let Some(name) = input.strip_prefix("/profile") {
+            // A conversation stays in the profile it started in, so switching starts a new one
+            match name.trim() {
+                "" => println!("Profile: {}\n", client.profile().unwrap_or("default")),
+                name => {
+                    client.set_profile(Some(name.to_string()));
+                    conversation_id = None;
+                    println!("Switched to profile '{}'. Your next message starts a new conversation.\n", name);
+                }
+            }
+            continue;
+        }
+
+        if let Some(level) = input.strip_prefix("/privacy") {
+            privacy_command(&mut client, device_id, &device_key, conversation_id, level).await;
+            continue;
+        }
+
+        if let Some(args) = input.strip_prefix("/tools") {
+            conversation_tools_command(&mut client, device_id, &device_key, conversation_id, args).await;
+            continue;
+        }
+
         println!(); // Blank line before response
 
-        match client.chat(
-            device_id,
-            device_key.clone(),
-            conversation_id,
-            input.to_string(),
-            |event| handle_event(&event),
-        ).await {
+        answering.store(true, Ordering::SeqCst);
+        // A cancel that came in after the last answer ended is stale
+        while control_receiver.try_recv().is_ok() {}
+        let handler = |event: ChatEvent| {
+            handle_event(&event);
+            if let Some((_, question_id, answer)) = read_answer(&event) {
+                let _ = controls.send(ChatSocketMessage::Answer { question_id: Some(question_id), answer });
+            }
+        };
+        let (message, key) = (input.to_string(), device_key.clone());
+        let answer = if client.websocket() {
+            client.chat_ws(device_id, key, conversation_id, message, &mut control_receiver, handler).await
+        } else {
+            client.chat_with_controls(device_id, key, conversation_id, message, &mut control_receiver, handler).await
+        };
+        answering.store(false, Ordering::SeqCst);
+
+        match answer {
             Ok(conv_id) => {
                 conversation_id = Some(conv_id);
                 println!("\n"); // Blank line after response
             }
             Err(e) => {
-                eprintln!("Error: {}\n", e);
+                report_error(&e);
+                println!();
             }
         }
     }
 
+    notifications.abort();
+    interrupts.abort();
     Ok(())
 }
 
+/// `/privacy [LEVEL]`: show or set the conversation's privacy level. Before
+/// the first message there is no conversation yet, so the level is sent
+/// along with it.
+async fn privacy_command(
+    client: &mut ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    args: &str,
+) {
+    let level = match args.trim() {
+        "" => None,
+        name => match PrivacyLevel::parse(name) {
+            Some(level) => Some(level),
+            None => {
+                eprintln!("Privacy must be normal, facts-only or no-extraction\n");
+                return;
+            }
+        },
+    };
+
+    match (conversation_id, level) {
+        (None, None) => {
+            let level = client.privacy().unwrap_or_default();
+            println!("Privacy: {}\n", level.as_str());
+        }
+        (None, Some(level)) => {
+            client.set_privacy(Some(level));
+            println!("New conversations start with privacy '{}'.\n", level.as_str());
+        }
+        (Some(id), None) => match client.conversation_privacy(device_id, device_key, id).await {
+            Ok(level) => println!("Privacy: {}\n", level.as_str()),
+            Err(e) => report_error(&e),
+        },
+        (Some(id), Some(level)) => match client.set_conversation_privacy(device_id, device_key, id, level).await {
+            Ok(()) => println!("Privacy set to '{}'. Memories already saved are kept.\n", level.as_str()),
+            Err(e) => report_error(&e),
+        },
+    }
+}
+
+/// `/tools [off|on|default TOOLBELT]`: list or change the toolbelts the
+/// conversation turned on or off. Before the first message the choices are
+/// sent along with it, so the first answer already honours them.
+async fn conversation_tools_command(
+    client: &mut ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    args: &str,
+) {
+    let mut words = args.split_whitespace();
+    let change = match (words.next(), words.next()) {
+        (None, _) => None,
+        (Some(state), Some(name)) if words.next().is_none() => {
+            let enabled = match state {
+                "on" => Some(true),
+                "off" => Some(false),
+                "default" => None,
+                _ => {
+                    eprintln!("Usage: /tools off|on|default TOOLBELT\n");
+                    return;
+                }
+            };
+            Some((toolbelt_alias(name), enabled))
+        }
+        _ => {
+            eprintln!("Usage: /tools off|on|default TOOLBELT\n");
+            return;
+        }
+    };
+
+    let choices = match (conversation_id, change) {
+        (None, None) => client.toolbelts().to_vec(),
+        (None, Some((toolbelt, enabled))) => {
+            client.set_toolbelt(toolbelt, enabled);
+            client.toolbelts().to_vec()
+        }
+        (Some(id), None) => match client.conversation_tools(device_id, device_key, id).await {
+            Ok(choices) => choices,
+            Err(e) => return report_error(&e),
+        },
+        (Some(id), Some((toolbelt, enabled))) => {
+            match client.set_conversation_tools(device_id, device_key, id, toolbelt, enabled).await {
+                Ok(choices) => choices,
+                Err(e) => return report_error(&e),
+            }
+        }
+    };
+
+    if choices.is_empty() {
+        println!("Tools: every agent's defaults\n");
+        return;
+    }
+    for choice in &choices {
+        println!("  {} {}", if choice.enabled { "on " } else { "off" }, choice.toolbelt);
+    }
+    println!();
+}
+
+/// Short names for the toolbelts people most often turn off.
+fn toolbelt_alias(name: &str) -> &str {
+    match name.to_ascii_lowercase().as_str() {
+        "web" | "search" => "WebSearch",
+        "files" => "FileSmith",
+        "maps" => "Places",
+        _ => name,
+    }
+}
+
+/// Print background jobs as they finish, for as long as the chat runs. The
+/// stream only says that a job finished; the inbox hands it over, so each
+/// job is printed once whichever of this and the prompt loop gets to it.
+async fn follow_device_events(
+    client: ApiClient,
+    device_id: i64,
+    device_key: String,
+    answering: Arc<AtomicBool>,
+) {
+    let (finished_tx, mut finished_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    let stream_client = client.clone();
+    let stream_key = device_key.clone();
+    tokio::spawn(async move {
+        loop {
+            // An engine without the stream, or a dropped connection, just means
+            // finished jobs show up at the next prompt instead
+            let _ = stream_client.device_events(device_id, &stream_key, |event| {
+                let finished = matches!(
+                    event,
+                    JobEvent::JobCompleted { .. } | JobEvent::JobFailed { retrying: false, .. }
+                );
+                if finished {
+                    let _ = finished_tx.send(());
+                }
+            }).await;
+            if finished_tx.is_closed() {
+                break;
+            }
+            tokio::time::sleep(EVENTS_RETRY).await;
+        }
+    });
+
+    while finished_rx.recv().await.is_some() {
+        if answering.load(Ordering::SeqCst) {
+            continue;
+        }
+        let Ok(jobs) = client.job_inbox(device_id, &device_key).await else {
+            continue;
+        };
+        if jobs.is_empty() {
+            continue;
+        }
+
+        // Interrupt the prompt, then put it back
+        println!("\n");
+        for job in &jobs {
+            print_job(job);
+        }
+        print!("You: ");
+        let _ = io::stdout().flush();
+    }
+}
+
+pub async fn later(
+    client: ApiClient,
+    device_id: i64,
+    device_key: String,
+    message: String,
+) -> Result<()> {
+    defer_message(&client, device_id, &device_key, None, &message).await;
+    Ok(())
+}
+
+async fn defer_message(
+    client: &ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    message: &str,
+) {
+    if message.is_empty() {
+        eprintln!("Usage: /later <message>\n");
+        return;
+    }
+
+    match client.chat_deferred(device_id, device_key.to_string(), conversation_id, message.to_string()).await {
+        Ok(deferred) => println!(
+            "Queued as job {} in conversation {}. The answer will show up next time envoy checks in.\n",
+            deferred.job_id, deferred.conversation_id
+        ),
+        Err(e) => {
+            report_error(&e);
+            println!();
+        }
+    }
+}
+
+/// Print deferred answers that finished since the last check. Returns how many were printed.
+pub async fn print_finished_jobs(client: &ApiClient, device_id: i64, device_key: &str) -> usize {
+    // A failed check shouldn't get in the way of chatting; the jobs stay in the inbox
+    let Ok(jobs) = client.job_inbox(device_id, device_key).await else {
+        return 0;
+    };
+
+    for job in &jobs {
+        print_job(job);
+        // The answer was just shown in full
+        if let Some(id) = job.arguments["conversation_id"].as_u64() {
+            let _ = client.mark_read(device_id, device_key, id).await;
+        }
+    }
+
+    jobs.len()
+}
+
+/// Point out answers waiting in conversations, e.g. research that finished
+/// while another client was in use. Stays quiet when there are none.
+async fn print_unread(client: &ApiClient, device_id: i64, device_key: &str) {
+    let Ok(conversations) = client.unread(device_id, device_key).await else {
+        return;
+    };
+    let total: u32 = conversations.iter().map(|c| c.unread).sum();
+    if total > 0 {
+        let plural = if total == 1 { "" } else { "s" };
+        println!("You have {} unread answer{} waiting; `envoy unread` lists them.\n", total, plural);
+    }
+}
+
+/// List conversations with unread answers and offer to resume one, or with
+/// `clear` mark them all read.
+pub async fn unread(client: ApiClient, device_id: i64, device_key: String, args: &[String]) -> Result<()> {
+    let conversations = client.unread(device_id, &device_key).await?;
+    if conversations.is_empty() {
+        println!("No unread answers.");
+        return Ok(());
+    }
+
+    if args.first().map(|s| s.as_str()) == Some("clear") {
+        for conversation in &conversations {
+            client.mark_read(device_id, &device_key, conversation.conversation_id).await?;
+        }
+        println!("Marked {} conversation(s) read.", conversations.len());
+        return Ok(());
+    }
+
+    println!("Unread answers:\n");
+    for (i, conversation) in conversations.iter().enumerate() {
+        let title = conversation.title.as_deref().unwrap_or("(untitled)");
+        println!(
+            "{:>3}. {} \x1b[90m[id={}, {} unread]\x1b[0m",
+            i + 1, title, conversation.conversation_id, conversation.unread
+        );
+    }
+    println!();
+
+    let ids: Vec<u64> = conversations.iter().map(|c| c.conversation_id).collect();
+    offer_resume(client, device_id, device_key, &ids).await
+}
+
+fn print_job(job: &BackgroundJob) {
+    let conversation = job.arguments["conversation_id"].as_u64()
+        .map(|id| format!(" (conversation {})", id))
+        .unwrap_or_default();
+    let result = job.result.as_deref().unwrap_or("");
+
+    let (color, outcome) = if job.status == "completed" { (32, "finished") } else { (31, "failed") };
+    println!("\x1b[1;{}m● Job {} {}{}\x1b[0m: {}", color, job.id, outcome, conversation, job_goal(job));
+    println!("{}\n", result);
+}
+
+fn job_goal(job: &BackgroundJob) -> &str {
+    job.arguments["goal"].as_str().unwrap_or(&job.method)
+}
+
+/// Stay running in the background: keep the tool server up, follow watched
+/// paths, and pop a desktop notification whenever a background job finishes
+/// or fails.
+pub async fn watch(client: ApiClient, device_id: i64, device_key: String) -> Result<()> {
+    let notifier = Notifier::detect();
+    if notifier.is_none() {
+        eprintln!("No desktop notification service found; finished jobs will only be printed here.");
+    }
+    println!("Watching for background jobs and changes to watched paths (Ctrl-C to stop)...\n");
+
+    loop {
+        match client.job_inbox(device_id, &device_key).await {
+            Ok(jobs) => {
+                for job in &jobs {
+                    print_job(job);
+                    if let Some(notifier) = &notifier {
+                        let title = if job.status == "completed" {
+                            format!("Artificer: job {} finished", job.id)
+                        } else {
+                            format!("Artificer: job {} failed", job.id)
+                        };
+                        if let Err(e) = notifier.send(&title, job_goal(job)) {
+                            eprintln!("Failed to show notification: {}", e);
+                        }
+                    }
+                }
+            }
+            // The server may be restarting; the jobs stay in the inbox until the next poll
+            Err(e) => eprintln!("Failed to check inbox: {}", e),
+        }
+
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+pub async fn search(
+    client: ApiClient,
+    device_id: i64,
+    device_key: String,
+    query: String,
+) -> Result<()> {
+    let results = match client.search(device_id, &device_key, &query).await {
+        Ok(r) => r,
+        Err(e) => {
+            report_error(&e);
+            return Ok(());
+        }
+    };
+
+    if results.is_empty() {
+        println!("No conversations match '{}'.", query);
+        return Ok(());
+    }
+
+    println!("Conversations matching '{}':\n", query);
+    for (i, result) in results.iter().enumerate() {
+        let title = result.title.as_deref().unwrap_or("(untitled)");
+        let plural = if result.match_count == 1 { "" } else { "es" };
+        println!(
+            "{:>3}. {} \x1b[90m[id={}, {} match{}]\x1b[0m",
+            i + 1, title, result.conversation_id, result.match_count, plural
+        );
+        println!("     {}\n", highlight_snippet(&result.snippet));
+    }
+
+    let ids: Vec<u64> = results.iter().map(|r| r.conversation_id).collect();
+    offer_resume(client, device_id, device_key, &ids).await
+}
+
+/// List conversations carrying `tag`, or every tag in use when it is None.
+pub async fn tags(
+    client: ApiClient,
+    device_id: i64,
+    device_key: String,
+    tag: Option<String>,
+) -> Result<()> {
+    let Some(tag) = tag else {
+        match client.list_tags(device_id, &device_key).await {
+            Ok(tags) if tags.is_empty() => println!("No tags yet. Tag a conversation with /tag NAME in chat."),
+            Ok(tags) => {
+                for tag in tags {
+                    println!("  {} \x1b[90m({})\x1b[0m", tag.name, tag.conversations);
+                }
+            }
+            Err(e) => report_error(&e),
+        }
+        return Ok(());
+    };
+
+    let conversations = match client.tagged_conversations(device_id, &device_key, &tag).await {
+        Ok(c) => c,
+        Err(e) => {
+            report_error(&e);
+            return Ok(());
+        }
+    };
+
+    if conversations.is_empty() {
+        println!("No conversations tagged '{}'.", tag);
+        return Ok(());
+    }
+
+    println!("Conversations tagged '{}':\n", tag);
+    for (i, conversation) in conversations.iter().enumerate() {
+        let title = conversation.title.as_deref().unwrap_or("(untitled)");
+        println!(
+            "{:>3}. {} \x1b[90m[id={}, tags: {}]\x1b[0m",
+            i + 1, title, conversation.conversation_id, conversation.tags.join(", ")
+        );
+    }
+    println!();
+
+    let ids: Vec<u64> = conversations.iter().map(|c| c.conversation_id).collect();
+    offer_resume(client, device_id, device_key, &ids).await
+}
+
+/// Ask which of the numbered conversations to resume, and resume it.
+async fn offer_resume(client: ApiClient, device_id: i64, device_key: String, ids: &[u64]) -> Result<()> {
+    print!("Resume a conversation? Enter its number (blank to exit): ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(());
+    }
+
+    match input.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).and_then(|i| ids.get(i)) {
+        Some(id) => interactive_chat(client, device_id, device_key, Some(*id)).await,
+        None => {
+            eprintln!("No result numbered '{}'", input);
+            Ok(())
+        }
+    }
+}
+
+/// `/tag work -old`: add `work`, remove `old`. With no names, show the tags.
+async fn tag_command(
+    client: &ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    args: &str,
+) {
+    let Some(conversation_id) = conversation_id else {
+        println!("Send a message first; there's no conversation to tag yet.\n");
+        return;
+    };
+
+    let (remove, add): (Vec<String>, Vec<String>) = args
+        .split_whitespace()
+        .map(String::from)
+        .partition(|name| name.starts_with('-'));
+    let remove: Vec<String> = remove.iter().map(|name| name[1..].to_string()).collect();
+
+    // With nothing to add or remove, the server just returns the current tags
+    match client.tag_conversation(device_id, device_key, conversation_id, &add, &remove).await {
+        Ok(tags) if tags.is_empty() => println!("No tags on this conversation.\n"),
+        Ok(tags) => println!("Tags: {}\n", tags.join(", ")),
+        Err(e) => {
+            report_error(&e);
+            println!();
+        }
+    }
+}
+
+/// `/checkpoint [NAME]`: save this point of the conversation to branch from.
+async fn checkpoint_command(
+    client: &ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    args: &str,
+) {
+    let Some(conversation_id) = conversation_id else {
+        println!("Send a message first; there's no conversation to checkpoint yet.\n");
+        return;
+    };
+
+    let name = Some(args.trim()).filter(|n| !n.is_empty());
+    match client.create_checkpoint(device_id, device_key, conversation_id, name).await {
+        Ok(id) => println!("Saved checkpoint {}. Use /branch {} to continue from here in a new conversation.\n", id, id),
+        Err(e) => {
+            report_error(&e);
+            println!();
+        }
+    }
+}
+
+/// `/checkpoints`: list the conversation's saved checkpoints.
+async fn list_checkpoints(client: &ApiClient, device_id: i64, device_key: &str, conversation_id: Option<u64>) {
+    let Some(conversation_id) = conversation_id else {
+        println!("No checkpoints yet. Save one with /checkpoint [NAME].\n");
+        return;
+    };
+
+    match client.list_checkpoints(device_id, device_key, conversation_id).await {
+        Ok(checkpoints) if checkpoints.is_empty() => println!("No checkpoints yet. Save one with /checkpoint [NAME].\n"),
+        Ok(checkpoints) => {
+            for checkpoint in &checkpoints {
+                let name = checkpoint.name.as_deref().unwrap_or("(unnamed)");
+                println!("  {:>4}  {} \x1b[90m({} messages)\x1b[0m", checkpoint.id, name, checkpoint.messages);
+            }
+            println!();
+        }
+        Err(e) => {
+            report_error(&e);
+            println!();
+        }
+    }
+}
+
+/// `/branch ID`: continue from a checkpoint in a new conversation. Returns
+/// the new conversation to switch to.
+async fn branch_command(
+    client: &ApiClient,
+    device_id: i64,
+    device_key: &str,
+    conversation_id: Option<u64>,
+    args: &str,
+) -> Option<u64> {
+    let Some(conversation_id) = conversation_id else {
+        println!("Send a message first; there's no conversation to branch yet.\n");
+        return None;
+    };
+    let Ok(checkpoint_id) = args.trim().parse::<u64>() else {
+        println!("Usage: /branch ID, with an ID from /checkpoints\n");
+        return None;
+    };
+
+    match client.branch_conversation(device_id, device_key, conversation_id, checkpoint_id).await {
+        Ok(branch) => {
+            println!("Switched to conversation {}, branched from checkpoint {}.\n", branch, checkpoint_id);
+            Some(branch)
+        }
+        Err(e) => {
+            report_error(&e);
+            println!();
+            None
+        }
+    }
+}
+
+/// `envoy tools [NAME | off NAME [REASON] | on NAME]`: list the engine's
+/// toolbelts, show one toolbelt's tools with their arguments and examples, or
+/// flip a tool switch. Switches need the ADMIN_KEY environment variable; with
+/// it set, the list also shows which toolbelts are turned off.
+pub async fn tools(client: ApiClient, args: &[String]) -> Result<()> {
+    let admin_key = std::env::var("ADMIN_KEY").ok();
+
+    match args {
+        [action, name, reason @ ..] if action == "off" || action == "on" => {
+            let Some(admin_key) = admin_key else {
+                eprintln!("Set ADMIN_KEY to the engine's admin key to manage tools.");
+                return Ok(());
+            };
+            let enabled = action == "on";
+            let reason = Some(reason.join(" ")).filter(|r| !r.is_empty());
+            match client.set_tool_switch(&admin_key, name, enabled, reason.as_deref()).await {
+                Ok(()) if enabled => println!("Turned {} back on.", name),
+                Ok(()) => println!("Turned {} off. Calls fail until it is turned back on.", name),
+                Err(e) => report_error(&e),
+            }
+        }
+        [] => {
+            let toolbelts = match client.tools().await {
+                Ok(toolbelts) => toolbelts,
+                Err(e) => {
+                    report_error(&e);
+                    return Ok(());
+                }
+            };
+            let disabled = match &admin_key {
+                Some(admin_key) => match client.tool_switches(admin_key).await {
+                    Ok(switches) => switches.disabled,
+                    Err(e) => {
+                        report_error(&e);
+                        Vec::new()
+                    }
+                },
+                None => Vec::new(),
+            };
+            let all_off = disabled.iter().find(|s| s.name == "all");
+            for toolbelt in &toolbelts {
+                let switch = all_off.or_else(|| disabled.iter().find(|s| s.name.eq_ignore_ascii_case(&toolbelt.name)));
+                let state = match switch {
+                    Some(switch) => {
+                        let reason = switch.reason.as_deref().map(|r| format!(" — {}", r)).unwrap_or_default();
+                        format!(" \x1b[31moff\x1b[0m{}", reason)
+                    }
+                    None => String::new(),
+                };
+                println!(
+                    "  {} \x1b[90m({}, {} risk, {} tools)\x1b[0m{}",
+                    toolbelt.name, toolbelt.location, toolbelt.risk, toolbelt.tools.len(), state
+                );
+                println!("      {}", toolbelt.description);
+            }
+            println!("\nShow a toolbelt's tools with: envoy tools NAME");
+        }
+        [name] => match client.tools().await {
+            Ok(toolbelts) => match toolbelts.iter().find(|t| t.name.eq_ignore_ascii_case(name)) {
+                Some(toolbelt) => print_toolbelt(toolbelt),
+                None => {
+                    let names: Vec<&str> = toolbelts.iter().map(|t| t.name.as_str()).collect();
+                    eprintln!("No toolbelt '{}'. The engine has: {}", name, names.join(", "));
+                }
+            },
+            Err(e) => report_error(&e),
+        },
+        _ => eprintln!("Usage: envoy tools [NAME | off NAME [REASON] | on NAME], where NAME is a toolbelt or 'all'"),
+    }
+    Ok(())
+}
+
+/// A toolbelt's tools, each with its arguments and an example call.
+fn print_toolbelt(toolbelt: &ToolbeltDoc) {
+    println!("{} \x1b[90m({}, {} risk)\x1b[0m", toolbelt.name, toolbelt.location, toolbelt.risk);
+    println!("{}", toolbelt.description);
+    for tool in &toolbelt.tools {
+        println!("\n  \x1b[1m{}\x1b[0m", tool.name);
+        println!("      {}", tool.description);
+
+        let required = tool.parameters["required"].as_array().cloned().unwrap_or_default();
+        if let Some(properties) = tool.parameters["properties"].as_object() {
+            for (param, schema) in properties {
+                let kind = schema["type"].as_str().unwrap_or("any");
+                let need = if required.iter().any(|r| r == param) {
+                    "required".to_string()
+                } else {
+                    match &schema["default"] {
+                        serde_json::Value::Null => "optional".to_string(),
+                        default => format!("default {}", default),
+                    }
+                };
+                let description = schema["description"].as_str().unwrap_or("");
+                println!("      \x1b[36m{}\x1b[0m {} \x1b[90m({})\x1b[0m {}", param, kind, need, description);
+            }
+        }
+        if let Some(example) = &tool.example {
+            println!("      \x1b[90mexample:\x1b[0m {}", example);
+        }
+    }
+}
+
+/// `envoy workflows [save NAME TEMPLATE [KEY=VALUE ...] | delete NAME]`:
+/// list, save or delete the device's saved workflows.
+pub async fn workflows(client: ApiClient, device_id: i64, device_key: String, args: &[String]) -> Result<()> {
+    match args {
+        [] => match client.list_workflows(device_id, &device_key).await {
+            Ok(workflows) if workflows.is_empty() => {
+                println!("No workflows yet. Save one with: envoy workflows save NAME \"TEMPLATE\"");
+            }
+            Ok(workflows) => {
+                for workflow in &workflows {
+                    println!("  {} \x1b[90m({})\x1b[0m", workflow.name, workflow.parameters.join(", "));
+                    println!("      {}", workflow.template);
+                }
+            }
+            Err(e) => report_error(&e),
+        },
+        [action, name, template, defaults @ ..] if action == "save" => {
+            let Some(defaults) = parse_arguments(defaults) else {
+                eprintln!("Defaults must be given as KEY=VALUE");
+                return Ok(());
+            };
+            match client.save_workflow(device_id, &device_key, name, template, &defaults).await {
+                Ok(()) => println!("Saved workflow '{}'. Run it with: envoy run {}", name, name),
+                Err(e) => report_error(&e),
+            }
+        }
+        [action, name] if action == "delete" => match client.delete_workflow(device_id, &device_key, name).await {
+            Ok(()) => println!("Deleted workflow '{}'.", name),
+            Err(e) => report_error(&e),
+        },
+        _ => eprintln!("Usage: envoy workflows [save NAME \"TEMPLATE\" [KEY=VALUE ...] | delete NAME]"),
+    }
+    Ok(())
+}
+
+/// `envoy watches [add PATH TASK [PATTERN] | delete ID]`: list, add or
+/// remove the paths `envoy watch` follows on this machine.
+pub async fn watches(client: ApiClient, device_id: i64, device_key: String, args: &[String]) -> Result<()> {
+    match args {
+        [] => match client.list_watches(device_id, &device_key).await {
+            Ok(watches) if watches.is_empty() => {
+                println!("No watched paths yet. Add one with: envoy watches add PATH \"TASK\"");
+            }
+            Ok(watches) => {
+                for watch in &watches {
+                    let pattern = watch.pattern.as_deref().unwrap_or("*");
+                    println!("  {} {} \x1b[90m({})\x1b[0m", watch.id, watch.path, pattern);
+                    println!("      {}", watch.task);
+                }
+            }
+            Err(e) => report_error(&e),
+        },
+        [action, path, task, rest @ ..] if action == "add" && rest.len() <= 1 => {
+            let pattern = rest.first().map(String::as_str);
+            match client.add_watch(device_id, &device_key, path, task, pattern).await {
+                Ok(watch_id) => println!("Added watch {}. It runs while `envoy watch` is running.", watch_id),
+                Err(e) => report_error(&e),
+            }
+        }
+        [action, id] if action == "delete" => {
+            let Ok(watch_id) = id.parse::<u64>() else {
+                eprintln!("Usage: envoy watches delete ID");
+                return Ok(());
+            };
+            match client.delete_watch(device_id, &device_key, watch_id).await {
+                Ok(()) => println!("Deleted watch {}.", watch_id),
+                Err(e) => report_error(&e),
+            }
+        }
+        _ => eprintln!("Usage: envoy watches [add PATH \"TASK\" [PATTERN] | delete ID]"),
+    }
+    Ok(())
+}
+
+/// `envoy config set style VALUE`: replace the device's response style with
+/// the comma separated presets in VALUE, e.g. `concise,code-first`.
+pub async fn set_style(client: ApiClient, device_id: i64, device_key: String, value: &str) -> Result<()> {
+    let mut style = ResponseStyle::default();
+    for token in value.split(',').map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+        match token.as_str() {
+            "default" => style = ResponseStyle::default(),
+            "code-first" | "code" => style.code_first = true,
+            other => match ResponseLength::parse(other) {
+                Some(length) => style.length = Some(length),
+                None => {
+                    eprintln!("Unknown style '{}'. Use concise, detailed, code-first or default, separated by commas.", other);
+                    return Ok(());
+                }
+            },
+        }
+    }
+
+    match client.set_response_style(device_id, &device_key, &style).await {
+        Ok(settings) => println!("Style updated to: {}", describe_style(&settings)),
+        Err(e) => report_error(&e),
+    }
+    Ok(())
+}
+
+/// The response style as `envoy config` shows it.
+pub fn describe_style(settings: &DeviceSettings) -> String {
+    let mut parts = Vec::new();
+    if let Some(length) = settings.length {
+        parts.push(length.as_str());
+    }
+    if settings.code_first {
+        parts.push("code-first");
+    }
+    if parts.is_empty() {
+        "default".to_string()
+    } else {
+        parts.join(",")
+    }
+}
+
+/// `envoy run NAME [KEY=VALUE ...]`: queue a saved workflow. Its answer
+/// arrives like one sent with `later`.
+pub async fn run_workflow(client: ApiClient, device_id: i64, device_key: String, name: &str, args: &[String]) -> Result<()> {
+    let Some(arguments) = parse_arguments(args) else {
+        eprintln!("Usage: envoy run NAME [KEY=VALUE ...]");
+        return Ok(());
+    };
+
+    match client.run_workflow(device_id, &device_key, name, &arguments).await {
+        Ok(run) => println!(
+            "Queued '{}' as job {} in conversation {}. The answer will show up next time envoy checks in.",
+            name, run.job_id, run.conversation_id
+        ),
+        Err(e) => report_error(&e),
+    }
+    Ok(())
+}
+
+/// `KEY=VALUE` pairs, or None if any argument isn't one.
+fn parse_arguments(args: &[String]) -> Option<BTreeMap<String, String>> {
+    args.iter()
+        .map(|arg| {
+            let (key, value) = arg.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `envoy purge --device`: show what the server stores for this device, and
+/// delete all of it once the user confirms.
+pub async fn purge_device(client: ApiClient, device_id: i64, device_key: String) -> Result<()> {
+    let preview = match client.purge_device(device_id, &device_key, true).await {
+        Ok(report) => report,
+        Err(e) => {
+            report_error(&e);
+            return Ok(());
+        }
+    };
+    if preview.total() == 0 {
+        println!("Nothing is stored for this device.");
+        return Ok(());
+    }
+
+    println!("This permanently deletes, for this device:");
+    print_purge_report(&preview);
+    print!("Type 'purge' to confirm: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim() != "purge" {
+        println!("Nothing was deleted.");
+        return Ok(());
+    }
+
+    match client.purge_device(device_id, &device_key, false).await {
+        Ok(report) => {
+            println!("Deleted:");
+            print_purge_report(&report);
+        }
+        Err(e) => report_error(&e),
+    }
+    Ok(())
+}
+
+fn print_purge_report(report: &PurgeReport) {
+    let rows = [
+        ("conversations", report.conversations),
+        ("messages", report.messages),
+        ("tasks", report.tasks),
+        ("artifacts", report.artifacts),
+        ("memories", report.memories),
+        ("tags", report.tags),
+        ("attachments", report.attachments),
+        ("background jobs", report.jobs),
+        ("workflows", report.workflows),
+        ("watched paths", report.watches),
+        ("audit entries", report.audit),
+    ];
+    for (what, count) in rows {
+        println!("  {:>6} {}", count, what);
+    }
+}
+
+/// Print an error, with a hint for engine error codes the user can act on.
+fn report_error(error: &anyhow::Error) {
+    eprintln!("Error: {}", error);
+
+    let Some(api_error) = error.downcast_ref::<ApiError>() else {
+        return;
+    };
+    match api_error.code {
+        ErrorCode::Unauthorized => {
+            eprintln!("Device credentials were rejected. Restart envoy to re-register.");
+        }
+        ErrorCode::ResourceBusy | ErrorCode::ModelUnavailable => {
+            eprintln!("The engine can't take this request right now. Try again in a moment.");
+        }
+        ErrorCode::ValidationFailed if api_error.errors.len() > 1 => {
+            for field_error in &api_error.errors {
+                eprintln!("  {}: {}", field_error.field, field_error.message);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Render the server's `**term**` highlight markers as bold yellow.
+fn highlight_snippet(snippet: &str) -> String {
+    let flat = snippet.replace('\n', " ");
+    flat.split("**")
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("\x1b[1;33m{}\x1b[0m", part)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Read the user's answer to a specialist's question and send it back. The
+/// stream stays open meanwhile; the specialist resumes once the answer lands.
+fn answer_question(client: &ApiClient, device_id: i64, device_key: &str, event: &ChatEvent) {
+    let Some((conversation_id, question_id, answer)) = read_answer(event) else {
+        return;
+    };
+
+    let client = client.clone();
+    let device_key = device_key.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = client.answer_question(device_id, &device_key, conversation_id, question_id, &answer).await {
+            report_error(&e);
+        }
+    });
+}
+
+/// For a question event, ask the user and return the conversation, question
+/// and answer.
+fn read_answer(event: &ChatEvent) -> Option<(u64, u64, String)> {
+    let ChatEvent::Question { conversation_id, question_id, options, .. } = event else {
+        return None;
+    };
+
+    print!("Answer: ");
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok()?;
+    let input = input.trim();
+
+    // A number picks one of the offered options; nothing leaves it to the assistant
+    let answer = if input.is_empty() {
+        "No preference, use your judgment.".to_string()
+    } else {
+        input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| options.get(n.wrapping_sub(1)))
+            .cloned()
+            .unwrap_or_else(|| input.to_string())
+    };
+    Some((*conversation_id, *question_id, answer))
+}
+
 fn handle_event(event: &ChatEvent) {
     match event {
         ChatEvent::TaskSwitch { from, to } => {
@@ -108,6 +1155,10 @@ fn handle_event(event: &ChatEvent) {
                      if content.len() > 200 { format!("{}…", &content[..200]) } else { content.clone() }
             );
         }
+        ChatEvent::Queued { position } => match position {
+            0 => println!("⏳ Waiting for model (next in line)"),
+            n => println!("⏳ Waiting for model ({} ahead of you)", n),
+        },
         ChatEvent::StreamChunk { content } => {
             print!("{}", content);
             io::stdout().flush().ok();
@@ -118,9 +1169,39 @@ fn handle_event(event: &ChatEvent) {
         ChatEvent::Error { message } => {
             eprintln!("\n❌ Error: {}", message);
         }
+        ChatEvent::Refusal { message, tool, .. } => match tool {
+            Some(tool) => println!("\n🚫 [{}] {}", tool, message),
+            None => println!("\n🚫 {}", message),
+        },
+        ChatEvent::Question { task, question, options, .. } => {
+            println!("\n❓ [{}] {}", task, question);
+            for (i, option) in options.iter().enumerate() {
+                println!("   {}. {}", i + 1, option);
+            }
+        }
+        ChatEvent::Confidence { level, sources, .. } => {
+            // Only doubtful answers get a banner
+            let label = match level {
+                ConfidenceLevel::High => return,
+                ConfidenceLevel::Medium => "Medium",
+                ConfidenceLevel::Low => "Low",
+            };
+            let basis = match sources.len() {
+                0 => "no sources checked".to_string(),
+                1 => "based on a single source".to_string(),
+                n => format!("based on {} sources", n),
+            };
+            println!("\n⚠️  {} confidence — {}", label, basis);
+        }
         ChatEvent::Reasoning { task, content } => {
             print!("\x1b[2m\x1b[90m💭 [{}] {}\x1b[0m", task, content);
             io::stdout().flush().ok();
         }
+        ChatEvent::Debug { task, message } => {
+            // Only shown when debugging, e.g. `just dev-envoy-debug`
+            if std::env::var("RUST_LOG").is_ok_and(|level| level.contains("debug")) {
+                println!("\n\x1b[2m\x1b[90m🐞 [{}] {}\x1b[0m", task, message);
+            }
+        }
     }
 }
\ No newline at end of file
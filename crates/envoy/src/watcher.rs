@@ -0,0 +1,175 @@
+//! Follows the paths the engine has on record for this device, and reports
+//! files added or changed under them so the engine can queue each watch's
+//! task. Runs inside `envoy watch`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use artificer_shared::db::{PathWatch, MAX_WATCH_TRIGGER_PATHS};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::client::ApiClient;
+
+/// How often the list of watches is fetched again, so ones added in a chat
+/// start soon after.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Quiet time after the last change before a watch reports, so a file still
+/// being copied in, or a batch of files, is reported once.
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+
+/// How long after a watch triggers its changes are ignored. The task it
+/// queued often writes under the watched path itself (a summary next to the
+/// files, say); without a pause those writes would trigger it again, and
+/// again.
+const TRIGGER_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// The watches in force and the directories the OS watcher follows for them.
+struct WatchSet {
+    /// Each watch with its path resolved on this machine.
+    watches: Vec<(PathWatch, PathBuf)>,
+    watched: BTreeSet<PathBuf>,
+    /// Paths that couldn't be watched and were already reported, so a missing
+    /// folder is mentioned once rather than every refresh.
+    failed: BTreeSet<PathBuf>,
+}
+
+impl WatchSet {
+    /// Follow exactly the paths in `latest`: drop the ones no longer wanted
+    /// and try the others, including ones that failed before.
+    fn update(&mut self, watcher: &mut impl Watcher, latest: Vec<PathWatch>, workspace: &Path) {
+        self.watches = latest.into_iter()
+            .map(|watch| {
+                let root = resolve(&watch.path, workspace);
+                (watch, root)
+            })
+            .collect();
+        let wanted: BTreeSet<PathBuf> = self.watches.iter().map(|(_, root)| root.clone()).collect();
+
+        for root in self.watched.difference(&wanted).cloned().collect::<Vec<_>>() {
+            let _ = watcher.unwatch(&root);
+            self.watched.remove(&root);
+        }
+        for root in wanted.difference(&self.watched).cloned().collect::<Vec<_>>() {
+            match watcher.watch(&root, RecursiveMode::Recursive) {
+                Ok(()) => {
+                    println!("Watching {}", root.display());
+                    self.failed.remove(&root);
+                    self.watched.insert(root);
+                }
+                Err(e) => {
+                    if self.failed.insert(root.clone()) {
+                        eprintln!("Can't watch {}: {}", root.display(), e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The watches a change to `path` concerns.
+    fn matching(&self, path: &Path) -> Vec<u64> {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return Vec::new();
+        };
+        self.watches.iter()
+            .filter(|(watch, root)| path.starts_with(root) && watch.matches(&name))
+            .map(|(watch, _)| watch.id)
+            .collect()
+    }
+}
+
+/// Watch until the process exits. Changes are gathered per watch and reported
+/// once they settle, then ignored for `TRIGGER_COOLDOWN`; failures are
+/// printed and the next change tries again.
+pub async fn run(client: ApiClient, device_id: i64, device_key: String, workspace: PathBuf) {
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            let _ = events_tx.send(event);
+        }
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("File watching is unavailable on this machine: {}", e);
+            return;
+        }
+    };
+
+    let mut set = WatchSet { watches: Vec::new(), watched: BTreeSet::new(), failed: BTreeSet::new() };
+    // Changed files per watch, and when the last of them changed
+    let mut pending: HashMap<u64, (BTreeSet<PathBuf>, Instant)> = HashMap::new();
+    // When each watch last queued its task
+    let mut triggered: HashMap<u64, Instant> = HashMap::new();
+    let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+    let mut settle = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            _ = refresh.tick() => {
+                // The server may be restarting; keep the watches we have
+                if let Ok(latest) = client.list_watches(device_id, &device_key).await {
+                    set.update(&mut watcher, latest, &workspace);
+                }
+            }
+            Some(event) = events_rx.recv() => {
+                if !is_addition_or_change(&event.kind) {
+                    continue;
+                }
+                for path in event.paths.into_iter().filter(|path| path.is_file()) {
+                    for watch_id in set.matching(&path) {
+                        if triggered.get(&watch_id).is_some_and(|at| at.elapsed() < TRIGGER_COOLDOWN) {
+                            continue;
+                        }
+                        let (paths, last_change) = pending.entry(watch_id)
+                            .or_insert_with(|| (BTreeSet::new(), Instant::now()));
+                        paths.insert(path.clone());
+                        *last_change = Instant::now();
+                    }
+                }
+            }
+            _ = settle.tick() => {
+                let settled: Vec<u64> = pending.iter()
+                    .filter(|(_, (_, last_change))| last_change.elapsed() >= SETTLE_TIME)
+                    .map(|(watch_id, _)| *watch_id)
+                    .collect();
+                for watch_id in settled {
+                    let Some((paths, _)) = pending.remove(&watch_id) else {
+                        continue;
+                    };
+                    let paths: Vec<String> = paths.iter()
+                        .take(MAX_WATCH_TRIGGER_PATHS)
+                        .map(|path| path.to_string_lossy().to_string())
+                        .collect();
+                    match client.trigger_watch(device_id, &device_key, watch_id, &paths).await {
+                        Ok(run) => {
+                            println!("Changes under watch {} queued as job {}", watch_id, run.job_id);
+                            triggered.insert(watch_id, Instant::now());
+                        }
+                        Err(e) => eprintln!("Failed to report changes for watch {}: {}", watch_id, e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// New files, written files and files moved in; not reads, deletions or
+/// permission changes.
+fn is_addition_or_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+    )
+}
+
+/// A watch path on this machine: `~/...` under the home directory, relative
+/// paths under the workspace.
+fn resolve(path: &str, workspace: &Path) -> PathBuf {
+    let in_home = if path == "~" { Some("") } else { path.strip_prefix("~/") };
+    match (in_home, dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => workspace.join(path),
+    }
+}
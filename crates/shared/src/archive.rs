@@ -0,0 +1,343 @@
+//! Zip and tar.gz archives for FileSmith. Extraction is checked before
+//! anything is written: every entry must land inside the destination, and
+//! the archive must stay within [`MAX_ARCHIVE_ENTRIES`] and
+//! [`MAX_ARCHIVE_BYTES`] however its headers describe it.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Most bytes an archive may hold, uncompressed, going in or coming out.
+pub const MAX_ARCHIVE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Most files and directories an archive may hold.
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// The format a tool argument names.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "zip" => Some(Self::Zip),
+            "tar.gz" | "tgz" | "targz" => Some(Self::TarGz),
+            _ => None,
+        }
+    }
+
+    /// The format an archive's file name suggests.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+
+    /// The format an existing archive's first bytes say it is.
+    fn sniff(path: &Path) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        let read = File::open(path)?.read(&mut magic)?;
+        match &magic[..read] {
+            [b'P', b'K', 3, 4] | [b'P', b'K', 5, 6] => Ok(Self::Zip),
+            [0x1F, 0x8B, ..] => Ok(Self::TarGz),
+            _ => bail!("{} is not a zip or tar.gz archive", path.display()),
+        }
+    }
+}
+
+/// What an archive operation wrote.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    pub files: usize,
+    pub directories: usize,
+    /// Uncompressed bytes of the files.
+    pub bytes: u64,
+    /// Entries left out: symlinks, devices and the like.
+    pub skipped: Vec<String>,
+}
+
+/// Pack `source`, a file or a directory, into a new archive at
+/// `destination`. Entries are named from the source's own name down, so
+/// unpacking `reports.zip` gives a `reports/` directory.
+pub fn create(source: &Path, destination: &Path, format: ArchiveFormat, overwrite: bool) -> Result<ArchiveReport> {
+    let source = source.canonicalize()?;
+    if destination.exists() && !overwrite {
+        bail!("{} already exists; pass overwrite=true to replace it", destination.display());
+    }
+    if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+        if source.is_dir() && parent.canonicalize()?.starts_with(&source) {
+            bail!("the archive can't go inside the directory it archives");
+        }
+    }
+
+    let base = source.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "archive".to_string());
+    let mut report = ArchiveReport::default();
+    let entries = collect(&source, &base, &mut report)?;
+
+    let file = File::create(destination)?;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for entry in &entries {
+                if entry.is_dir {
+                    zip.add_directory(entry.name.as_str(), options)?;
+                } else {
+                    zip.start_file(entry.name.as_str(), options)?;
+                    io::copy(&mut File::open(&entry.path)?, &mut zip)?;
+                }
+            }
+            zip.finish()?;
+        }
+        ArchiveFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut tar = tar::Builder::new(encoder);
+            for entry in &entries {
+                if entry.is_dir {
+                    tar.append_dir(&entry.name, &entry.path)?;
+                } else {
+                    tar.append_path_with_name(&entry.path, &entry.name)?;
+                }
+            }
+            tar.into_inner()?.finish()?;
+        }
+    }
+    Ok(report)
+}
+
+/// Unpack the archive at `path` into `destination`, creating it if needed.
+/// The whole archive is checked first, so a bad entry means nothing is
+/// written. Existing files are only replaced with `overwrite`.
+pub fn extract(path: &Path, destination: &Path, overwrite: bool) -> Result<ArchiveReport> {
+    let format = ArchiveFormat::sniff(path)?;
+
+    let mut entries = 0;
+    let mut declared = 0u64;
+    each_entry(path, format, |entry| {
+        entries += 1;
+        if entries > MAX_ARCHIVE_ENTRIES {
+            bail!("the archive has more than {} entries", MAX_ARCHIVE_ENTRIES);
+        }
+        let Some(relative) = contained_path(&entry.name) else {
+            bail!("refusing to unpack: entry '{}' points outside the destination", entry.name);
+        };
+        if entry.kind == EntryKind::File {
+            declared += entry.size;
+            if declared > MAX_ARCHIVE_BYTES {
+                bail!("the archive unpacks to more than {} bytes", MAX_ARCHIVE_BYTES);
+            }
+            let target = destination.join(&relative);
+            if is_symlink(&target) {
+                bail!("refusing to unpack: {} is a symlink", target.display());
+            }
+            if target.exists() && !overwrite {
+                bail!("{} already exists; pass overwrite=true to replace it", target.display());
+            }
+        }
+        Ok(())
+    })?;
+
+    fs::create_dir_all(destination)?;
+    let root = destination.canonicalize()?;
+    let mut report = ArchiveReport::default();
+    each_entry(path, format, |entry| {
+        let Some(relative) = contained_path(&entry.name) else {
+            bail!("refusing to unpack: entry '{}' points outside the destination", entry.name);
+        };
+        let target = root.join(&relative);
+        match entry.kind {
+            EntryKind::Other => report.skipped.push(entry.name),
+            EntryKind::Directory => {
+                if !stays_inside(&root, &target) {
+                    bail!("refusing to unpack: '{}' would land outside the destination", entry.name);
+                }
+                fs::create_dir_all(&target)?;
+                report.directories += 1;
+            }
+            EntryKind::File => {
+                // A symlink already in the destination mustn't lead elsewhere
+                if !stays_inside(&root, &target) {
+                    bail!("refusing to unpack: '{}' would land outside the destination", entry.name);
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // The file itself is replaced, never written through: a
+                // symlink planted since the check is refused, not followed
+                match fs::symlink_metadata(&target) {
+                    Ok(metadata) if metadata.is_symlink() => {
+                        bail!("refusing to unpack: {} is a symlink", target.display());
+                    }
+                    Ok(_) => fs::remove_file(&target)?,
+                    Err(_) => {}
+                }
+                // Headers can understate sizes, so count what actually comes out
+                let remaining = MAX_ARCHIVE_BYTES - report.bytes;
+                let mut out = OpenOptions::new().write(true).create_new(true).open(&target)?;
+                let written = io::copy(&mut entry.reader.take(remaining + 1), &mut out)?;
+                if written > remaining {
+                    drop(out);
+                    let _ = fs::remove_file(&target);
+                    bail!("the archive unpacks to more than {} bytes", MAX_ARCHIVE_BYTES);
+                }
+                report.bytes += written;
+                report.files += 1;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(report)
+}
+
+/// A file or directory to pack, by its name inside the archive.
+struct SourceEntry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+}
+
+/// Everything under `source` worth packing, counted into `report`.
+/// Symlinks are listed as skipped rather than followed.
+fn collect(source: &Path, base: &str, report: &mut ArchiveReport) -> Result<Vec<SourceEntry>> {
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.is_file() {
+        check_size(metadata.len())?;
+        report.files = 1;
+        report.bytes = metadata.len();
+        return Ok(vec![SourceEntry { path: source.to_path_buf(), name: base.to_string(), is_dir: false }]);
+    }
+
+    let mut entries = vec![SourceEntry { path: source.to_path_buf(), name: base.to_string(), is_dir: true }];
+    report.directories = 1;
+    let mut pending = vec![(source.to_path_buf(), base.to_string())];
+    while let Some((dir, name)) = pending.pop() {
+        let mut children = fs::read_dir(&dir)?.collect::<io::Result<Vec<_>>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+            let file_type = child.file_type()?;
+            if file_type.is_dir() {
+                pending.push((child.path(), child_name.clone()));
+                entries.push(SourceEntry { path: child.path(), name: child_name, is_dir: true });
+                report.directories += 1;
+            } else if file_type.is_file() {
+                report.bytes += child.metadata()?.len();
+                check_size(report.bytes)?;
+                entries.push(SourceEntry { path: child.path(), name: child_name, is_dir: false });
+                report.files += 1;
+            } else {
+                report.skipped.push(child_name);
+            }
+            if entries.len() > MAX_ARCHIVE_ENTRIES {
+                bail!("there are more than {} files and directories to pack", MAX_ARCHIVE_ENTRIES);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn check_size(bytes: u64) -> Result<()> {
+    if bytes > MAX_ARCHIVE_BYTES {
+        bail!("there is more than {} bytes to pack", MAX_ARCHIVE_BYTES);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    File,
+    Directory,
+    /// Symlinks, hard links, devices: never unpacked.
+    Other,
+}
+
+/// One entry of an archive being read, whatever its format.
+struct ArchiveEntry<'a> {
+    name: String,
+    kind: EntryKind,
+    /// Uncompressed size as the archive states it.
+    size: u64,
+    reader: &'a mut dyn Read,
+}
+
+/// Call `visit` with each entry of the archive, in order.
+fn each_entry(path: &Path, format: ArchiveFormat, mut visit: impl FnMut(ArchiveEntry) -> Result<()>) -> Result<()> {
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i)?;
+                let is_symlink = file.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000);
+                let kind = if file.is_dir() {
+                    EntryKind::Directory
+                } else if is_symlink {
+                    EntryKind::Other
+                } else {
+                    EntryKind::File
+                };
+                let name = file.name().to_string();
+                let size = file.size();
+                visit(ArchiveEntry { name, kind, size, reader: &mut file })?;
+            }
+        }
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(File::open(path)?));
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                let entry_type = entry.header().entry_type();
+                let kind = if entry_type.is_file() {
+                    EntryKind::File
+                } else if entry_type.is_dir() {
+                    EntryKind::Directory
+                } else {
+                    EntryKind::Other
+                };
+                let name = entry.path()?.to_string_lossy().to_string();
+                let size = entry.size();
+                visit(ArchiveEntry { name, kind, size, reader: &mut entry })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink())
+}
+
+/// Whether `target` stays inside `root` once the symlinks already on its
+/// way are followed. Only the part of it that exists is resolved.
+fn stays_inside(root: &Path, target: &Path) -> bool {
+    let existing = target.ancestors()
+        .find(|ancestor| fs::symlink_metadata(ancestor).is_ok())
+        .unwrap_or(root);
+    existing.canonicalize().is_ok_and(|path| path.starts_with(root))
+}
+
+/// An entry name as a path relative to the destination, or None if it is
+/// absolute or climbs out with `..`.
+fn contained_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use crate::archive::{self, ArchiveFormat};
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("artificer-archive-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trips_a_folder_in_both_formats() {
+        let work = temp_dir("round-trip");
+        let reports = work.join("reports");
+        fs::create_dir_all(reports.join("2024")).unwrap();
+        fs::write(reports.join("summary.txt"), "all good").unwrap();
+        fs::write(reports.join("2024/q1.csv"), "a,b\n1,2\n").unwrap();
+
+        for (name, format) in [("reports.zip", ArchiveFormat::Zip), ("reports.tar.gz", ArchiveFormat::TarGz)] {
+            assert_eq!(ArchiveFormat::from_extension(&work.join(name)), Some(format));
+            let created = archive::create(&reports, &work.join(name), format, false).unwrap();
+            assert_eq!((created.files, created.directories), (2, 2));
+
+            let out = work.join(format!("out-{}", name));
+            let extracted = archive::extract(&work.join(name), &out, false).unwrap();
+            assert_eq!((extracted.files, extracted.bytes), (2, created.bytes));
+            assert_eq!(fs::read_to_string(out.join("reports/2024/q1.csv")).unwrap(), "a,b\n1,2\n");
+
+            // Unpacking again would replace files
+            assert!(archive::extract(&work.join(name), &out, false).is_err());
+            assert!(archive::extract(&work.join(name), &out, true).is_ok());
+        }
+
+        assert!(archive::create(&reports, &work.join("reports.zip"), ArchiveFormat::Zip, false).is_err());
+        assert!(archive::create(&reports, &reports.join("self.zip"), ArchiveFormat::Zip, false).is_err());
+        fs::remove_dir_all(&work).unwrap();
+    }
+
+    #[test]
+    fn refuses_entries_that_escape_the_destination() {
+        let work = temp_dir("escape");
+        let path = work.join("evil.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("fine.txt", options).unwrap();
+        zip.write_all(b"fine").unwrap();
+        zip.start_file("../escaped.txt", options).unwrap();
+        zip.write_all(b"gotcha").unwrap();
+        zip.finish().unwrap();
+
+        let out = work.join("out");
+        let error = archive::extract(&path, &out, false).unwrap_err();
+        assert!(error.to_string().contains("outside the destination"));
+        // Checked up front, so not even the harmless entry was written
+        assert!(!out.join("fine.txt").exists());
+        assert!(!work.join("escaped.txt").exists());
+        fs::remove_dir_all(&work).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_never_lead_writes_outside_the_destination() {
+        let work = temp_dir("symlink");
+        let outside = work.join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret.txt"), "untouched").unwrap();
+
+        // A symlink entry is skipped, and the file of the same name after it
+        // is written in its place rather than through it
+        let path = work.join("links.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(&path).unwrap(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Symlink);
+        link.set_size(0);
+        tar.append_link(&mut link, "notes.txt", outside.join("secret.txt")).unwrap();
+        let mut file = tar::Header::new_gnu();
+        file.set_size(7);
+        file.set_mode(0o644);
+        tar.append_data(&mut file, "notes.txt", &b"gotcha!"[..]).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let out = work.join("out");
+        let report = archive::extract(&path, &out, false).unwrap();
+        assert_eq!(report.skipped, ["notes.txt"]);
+        assert!(!fs::symlink_metadata(out.join("notes.txt")).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(out.join("notes.txt")).unwrap(), "gotcha!");
+        assert_eq!(fs::read_to_string(outside.join("secret.txt")).unwrap(), "untouched");
+
+        // Nor does a symlink already in the destination, even with overwrite
+        fs::remove_file(out.join("notes.txt")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), out.join("notes.txt")).unwrap();
+        assert!(archive::extract(&path, &out, true).is_err());
+        assert_eq!(fs::read_to_string(outside.join("secret.txt")).unwrap(), "untouched");
+
+        let zip_path = work.join("nested.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("linked/inner", options).unwrap();
+        zip.start_file("linked/inner/planted.txt", options).unwrap();
+        zip.write_all(b"gotcha").unwrap();
+        zip.finish().unwrap();
+        let nested = work.join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        std::os::unix::fs::symlink(&outside, nested.join("linked")).unwrap();
+        let error = archive::extract(&zip_path, &nested, false).unwrap_err();
+        assert!(error.to_string().contains("outside the destination"));
+        assert!(!outside.join("inner").exists());
+        fs::remove_dir_all(&work).unwrap();
+    }
+}
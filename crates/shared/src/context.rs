@@ -0,0 +1,13 @@
+/// The device a tool call runs on behalf of. Passed explicitly down the
+/// executor → toolbelt chain so concurrent requests never share scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceContext {
+    pub device_id: i64,
+    pub device_key: String,
+}
+
+impl DeviceContext {
+    pub fn new(device_id: i64, device_key: impl Into<String>) -> Self {
+        Self { device_id, device_key: device_key.into() }
+    }
+}
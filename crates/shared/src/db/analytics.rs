@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db, VISIBLE_CONVERSATION};
+use crate::ToolCall;
+
+/// Most agents and tools task_usage lists.
+const MAX_USAGE_NAMES: usize = 15;
+
+/// How much a device used the assistant over a span of time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationCounts {
+    /// Conversations started in the span.
+    pub started: u32,
+    /// Conversations the user wrote in during the span, older ones included.
+    pub active: u32,
+    pub user_messages: u32,
+    /// Days (UTC) with at least one message from the user.
+    pub active_days: u32,
+    /// Active conversations per profile, most first.
+    pub by_profile: Vec<NameCount>,
+}
+
+/// What the assistant did for a device over a span of time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskUsage {
+    /// Requests the Orchestrator took on, and how they ended.
+    pub requests: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub abandoned: u32,
+    pub in_progress: u32,
+    /// Tasks handed to specialists.
+    pub delegations: u32,
+    /// Agents by how many tasks they worked on, most first.
+    pub agents: Vec<NameCount>,
+    /// Tools by how many times they were called, most first.
+    pub tools: Vec<NameCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameCount {
+    pub name: String,
+    pub count: u32,
+}
+
+// ============================================================================
+// ANALYTICS
+// ============================================================================
+
+// Aggregates over a device's history since a timestamp, for questions like
+// "what do I ask you about most?". They cover what the Archivist may show:
+// incognito and locked conversations are left out.
+impl Db {
+    pub fn conversation_counts(&self, device_id: u64, since: i64) -> Result<ConversationCounts> {
+        let started = self.query_row_optional(
+            &format!("SELECT COUNT(*) FROM conversations c WHERE {} AND c.created >= ?3", VISIBLE_CONVERSATION),
+            rusqlite::params![device_id as i64, now(), since],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0) as u32;
+        let (active, user_messages, active_days) = self.query_row_optional(
+            &format!(
+                "SELECT COUNT(DISTINCT m.conversation_id), COUNT(*), COUNT(DISTINCT m.created / 86400)
+                 FROM messages m JOIN conversations c ON c.id = m.conversation_id
+                 WHERE {} AND m.role = 'user' AND m.deleted_at IS NULL AND m.created >= ?3",
+                VISIBLE_CONVERSATION
+            ),
+            rusqlite::params![device_id as i64, now(), since],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+        )?.unwrap_or_default();
+
+        let by_profile = self.name_counts(
+            &format!(
+                "SELECT c.profile, COUNT(DISTINCT c.id)
+                 FROM messages m JOIN conversations c ON c.id = m.conversation_id
+                 WHERE {} AND m.role = 'user' AND m.deleted_at IS NULL AND m.created >= ?3
+                 GROUP BY c.profile
+                 ORDER BY 2 DESC, 1",
+                VISIBLE_CONVERSATION
+            ),
+            device_id,
+            since,
+        )?;
+
+        Ok(ConversationCounts {
+            started,
+            active: active as u32,
+            user_messages: user_messages as u32,
+            active_days: active_days as u32,
+            by_profile,
+        })
+    }
+
+    /// The user's own messages, oldest first, for counting what they talk about.
+    pub fn user_message_texts(&self, device_id: u64, since: i64) -> Result<Vec<String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT m.message FROM messages m JOIN conversations c ON c.id = m.conversation_id
+             WHERE {} AND m.role = 'user' AND m.deleted_at IS NULL AND m.message IS NOT NULL
+               AND m.created >= ?3
+             ORDER BY m.created, m.id",
+            VISIBLE_CONVERSATION
+        ))?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64, now(), since], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+
+    pub fn task_usage(&self, device_id: u64, since: i64) -> Result<TaskUsage> {
+        let statuses = self.name_counts(
+            &format!(
+                "SELECT t.status, COUNT(*) FROM tasks t JOIN conversations c ON c.id = t.conversation_id
+                 WHERE {} AND t.parent_task_id IS NULL AND t.created_at >= ?3
+                 GROUP BY t.status",
+                VISIBLE_CONVERSATION
+            ),
+            device_id,
+            since,
+        )?;
+        let status = |name: &str| statuses.iter().find(|s| s.name == name).map_or(0, |s| s.count);
+
+        let delegations = self.query_row_optional(
+            &format!(
+                "SELECT COUNT(*) FROM tasks t JOIN conversations c ON c.id = t.conversation_id
+                 WHERE {} AND t.parent_task_id IS NOT NULL AND t.created_at >= ?3",
+                VISIBLE_CONVERSATION
+            ),
+            rusqlite::params![device_id as i64, now(), since],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0) as u32;
+
+        let mut agents = self.name_counts(
+            &format!(
+                "SELECT e.agent_name, COUNT(DISTINCT e.task_id)
+                 FROM execution_traces e
+                 JOIN tasks t ON t.id = e.task_id
+                 JOIN conversations c ON c.id = t.conversation_id
+                 WHERE {} AND t.created_at >= ?3
+                 GROUP BY e.agent_name
+                 ORDER BY 2 DESC, 1",
+                VISIBLE_CONVERSATION
+            ),
+            device_id,
+            since,
+        )?;
+        agents.truncate(MAX_USAGE_NAMES);
+
+        Ok(TaskUsage {
+            requests: statuses.iter().map(|s| s.count).sum(),
+            completed: status("completed"),
+            failed: status("failed"),
+            abandoned: status("abandoned"),
+            in_progress: status("in_progress"),
+            delegations,
+            agents,
+            tools: self.tool_counts(device_id, since)?,
+        })
+    }
+
+    /// Tool calls recorded in the device's execution traces, by tool name.
+    fn tool_counts(&self, device_id: u64, since: i64) -> Result<Vec<NameCount>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT e.tool_calls FROM execution_traces e
+             JOIN tasks t ON t.id = e.task_id
+             JOIN conversations c ON c.id = t.conversation_id
+             WHERE {} AND t.created_at >= ?3 AND e.tool_calls IS NOT NULL",
+            VISIBLE_CONVERSATION
+        ))?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64, now(), since], |row| row.get::<_, String>(0))?;
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for calls in rows {
+            let Ok(calls) = serde_json::from_str::<Vec<ToolCall>>(&calls?) else {
+                continue;
+            };
+            for call in calls {
+                *counts.entry(call.function.name).or_default() += 1;
+            }
+        }
+        let mut tools: Vec<NameCount> = counts.into_iter().map(|(name, count)| NameCount { name, count }).collect();
+        tools.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        tools.truncate(MAX_USAGE_NAMES);
+        Ok(tools)
+    }
+
+    fn name_counts(&self, sql: &str, device_id: u64, since: i64) -> Result<Vec<NameCount>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(sql)?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64, now(), since], |row| {
+            Ok(NameCount { name: row.get(0)?, count: row.get::<_, i64>(1)? as u32 })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, NameCount};
+
+    fn name_count(name: &str, count: u32) -> NameCount {
+        NameCount { name: name.to_string(), count }
+    }
+
+    #[test]
+    fn conversation_counts_cover_visible_conversations_only() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let other = register_device(&db, "phone");
+
+        let work = db.start_conversation(device, Some("work"), false).unwrap();
+        db.add_message(work, None, "user", Some("deploy the staging server"), None).unwrap();
+        db.add_message(work, None, "assistant", Some("Deployed"), None).unwrap();
+        db.add_message(work, None, "user", Some("now roll it back"), None).unwrap();
+        let home = db.create_conversation(device).unwrap();
+        db.add_message(home, None, "user", Some("dinner ideas"), None).unwrap();
+
+        let incognito = db.start_conversation(device, None, true).unwrap();
+        db.add_message(incognito, None, "user", Some("something private"), None).unwrap();
+        let locked = db.create_conversation(device).unwrap();
+        db.add_message(locked, None, "user", Some("diary entry"), None).unwrap();
+        db.lock_conversation(locked, "4321").unwrap();
+        let elsewhere = db.create_conversation(other).unwrap();
+        db.add_message(elsewhere, None, "user", Some("not mine"), None).unwrap();
+
+        let counts = db.conversation_counts(device, 0).unwrap();
+        assert_eq!(counts.started, 2);
+        assert_eq!(counts.active, 2);
+        assert_eq!(counts.user_messages, 3);
+        assert_eq!(counts.active_days, 1);
+        assert_eq!(counts.by_profile, vec![name_count("default", 1), name_count("work", 1)]);
+        assert_eq!(
+            db.user_message_texts(device, 0).unwrap(),
+            vec!["deploy the staging server", "now roll it back", "dinner ideas"],
+        );
+
+        // Nothing happened after now
+        let later = db.conversation_counts(device, i64::MAX).unwrap();
+        assert_eq!((later.started, later.active, later.user_messages), (0, 0, 0));
+        assert!(later.by_profile.is_empty());
+    }
+
+    #[test]
+    fn task_usage_counts_requests_agents_and_tools() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+
+        let done = db.create_task(device, conversation, None, "find flights").unwrap();
+        let search = r#"[{"function": {"name": "web_search", "arguments": {}}},
+                         {"function": {"name": "web_search", "arguments": {}}}]"#;
+        db.log_execution_trace(done, "Orchestrator", 1, None, "input", None, Some(search), None, "tool_call", None).unwrap();
+        let delegated = db.create_task(device, conversation, Some(done), "read the page").unwrap();
+        let fetch = r#"[{"function": {"name": "fetch_url", "arguments": {}}}]"#;
+        db.log_execution_trace(delegated, "Researcher", 1, None, "input", None, Some(fetch), None, "tool_call", None).unwrap();
+        db.log_execution_trace(delegated, "Researcher", 2, None, "input", None, None, None, "final", None).unwrap();
+        db.complete_task(delegated as i64).unwrap();
+        db.complete_task(done as i64).unwrap();
+
+        let failed = db.create_task(device, conversation, None, "book it").unwrap();
+        db.log_execution_trace(failed, "Orchestrator", 1, None, "input", None, Some("not json"), None, "tool_call", None).unwrap();
+        db.fail_task(failed as i64).unwrap();
+        db.create_task(device, conversation, None, "still going").unwrap();
+
+        let usage = db.task_usage(device, 0).unwrap();
+        assert_eq!(usage.requests, 3);
+        assert_eq!((usage.completed, usage.failed, usage.abandoned, usage.in_progress), (1, 1, 0, 1));
+        assert_eq!(usage.delegations, 1);
+        assert_eq!(usage.agents, vec![name_count("Orchestrator", 2), name_count("Researcher", 1)]);
+        // Unparseable tool calls are skipped rather than failing the summary
+        assert_eq!(usage.tools, vec![name_count("web_search", 2), name_count("fetch_url", 1)]);
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{now, Db};
+
+/// A tool output stored during a run. `content` is the full, untruncated result.
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    pub id: u64,
+    pub run_id: u64,
+    pub step_task_id: u64,
+    pub tool_name: String,
+    pub tool_args: String,
+    pub content: String,
+    pub created: i64,
+}
+
+impl Artifact {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Artifact {
+            id: row.get::<_, i64>(0)? as u64,
+            run_id: row.get::<_, i64>(1)? as u64,
+            step_task_id: row.get::<_, i64>(2)? as u64,
+            tool_name: row.get(3)?,
+            tool_args: row.get(4)?,
+            content: row.get(5)?,
+            created: row.get(6)?,
+        })
+    }
+}
+
+const ARTIFACT_COLUMNS: &str = "id, run_id, step_task_id, tool_name, tool_args, content, created";
+
+// ============================================================================
+// ARTIFACTS
+// ============================================================================
+
+impl Db {
+    pub fn store_artifact(
+        &self,
+        run_id: u64,
+        step_task_id: u64,
+        tool_name: &str,
+        tool_args: &str,
+        content: &str,
+    ) -> Result<u64> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO artifacts (run_id, step_task_id, tool_name, tool_args, content, created)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![run_id as i64, step_task_id as i64, tool_name, tool_args, content, now()],
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// The requested artifacts that belong to `run_id`, in id order. Ids from
+    /// other runs are skipped, so one request can't read another's results.
+    pub fn get_artifacts(&self, run_id: u64, artifact_ids: &[u64]) -> Result<Vec<Artifact>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM artifacts WHERE run_id = ?1 AND id = ?2",
+            ARTIFACT_COLUMNS
+        ))?;
+
+        let mut ids = artifact_ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut artifacts = Vec::new();
+        for id in ids {
+            let mut rows = stmt.query_map(rusqlite::params![run_id as i64, id as i64], Artifact::from_row)?;
+            if let Some(artifact) = rows.next() {
+                artifacts.push(artifact?);
+            }
+        }
+        Ok(artifacts)
+    }
+}
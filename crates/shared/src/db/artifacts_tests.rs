@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    #[test]
+    fn artifacts_are_scoped_to_their_run() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+
+        let run = db.create_task(device, conversation, None, "research").unwrap();
+        let step = db.create_task(device, conversation, Some(run), "search").unwrap();
+        let other_run = db.create_task(device, conversation, None, "something else").unwrap();
+
+        let id = db.store_artifact(run, step, "WebSearch::search", "{}", "[\"result\"]").unwrap();
+
+        let artifacts = db.get_artifacts(run, &[id, id, 999]).unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].content, "[\"result\"]");
+        assert_eq!(artifacts[0].step_task_id, step);
+
+        assert!(db.get_artifacts(other_run, &[id]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{now, Db};
+
+/// A stored file's metadata. The bytes are fetched separately.
+#[derive(Debug, Clone, Serialize)]
+pub struct Attachment {
+    pub id: u64,
+    pub device_id: u64,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub created: i64,
+}
+
+impl Attachment {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Attachment {
+            id: row.get::<_, i64>(0)? as u64,
+            device_id: row.get::<_, i64>(1)? as u64,
+            filename: row.get(2)?,
+            mime_type: row.get(3)?,
+            size: row.get::<_, i64>(4)? as u64,
+            created: row.get(5)?,
+        })
+    }
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, device_id, filename, mime_type, size, created";
+
+// ============================================================================
+// ATTACHMENTS
+// ============================================================================
+
+impl Db {
+    pub fn store_attachment(
+        &self,
+        device_id: u64,
+        filename: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> Result<u64> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO attachments (device_id, filename, mime_type, size, data, created)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![device_id as i64, filename, mime_type, data.len() as i64, data, now()],
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// An attachment's metadata, if it belongs to `device_id`.
+    pub fn get_attachment(&self, attachment_id: u64, device_id: u64) -> Result<Option<Attachment>> {
+        self.query_row_optional(
+            &format!("SELECT {} FROM attachments WHERE id = ?1 AND device_id = ?2", ATTACHMENT_COLUMNS),
+            rusqlite::params![attachment_id as i64, device_id as i64],
+            Attachment::from_row,
+        )
+    }
+
+    /// An attachment's bytes, if it belongs to `device_id`.
+    pub fn get_attachment_data(&self, attachment_id: u64, device_id: u64) -> Result<Option<Vec<u8>>> {
+        self.query_row_optional(
+            "SELECT data FROM attachments WHERE id = ?1 AND device_id = ?2",
+            rusqlite::params![attachment_id as i64, device_id as i64],
+            |row| row.get(0),
+        )
+    }
+}
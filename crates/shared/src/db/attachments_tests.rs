@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    #[test]
+    fn attachments_are_scoped_to_their_device() {
+        let db = Db::in_memory().unwrap();
+        let mine = register_device(&db, "laptop");
+        let theirs = register_device(&db, "phone");
+
+        let id = db.store_attachment(mine, "screen.png", "image/png", &[1, 2, 3]).unwrap();
+
+        let attachment = db.get_attachment(id, mine).unwrap().unwrap();
+        assert_eq!(attachment.size, 3);
+        assert_eq!(db.get_attachment_data(id, mine).unwrap(), Some(vec![1, 2, 3]));
+
+        assert!(db.get_attachment(id, theirs).unwrap().is_none());
+        assert!(db.get_attachment_data(id, theirs).unwrap().is_none());
+    }
+}
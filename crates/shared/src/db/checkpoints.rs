@@ -0,0 +1,132 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db, INCOGNITO_TTL_SECS};
+
+/// Longest a checkpoint name may be, in characters.
+pub const MAX_CHECKPOINT_NAME_CHARS: usize = 80;
+
+/// A frozen point in a conversation that new conversations can branch from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: u64,
+    pub name: Option<String>,
+    /// Messages the conversation had when the checkpoint was taken.
+    pub messages: u64,
+    pub created: i64,
+}
+
+// ============================================================================
+// CHECKPOINTS
+// ============================================================================
+
+impl Db {
+    /// Freeze the conversation as it is now: its messages so far and its
+    /// scratchpad. Returns the checkpoint's id.
+    pub fn create_checkpoint(&self, conversation_id: u64, name: Option<&str>) -> Result<u64> {
+        self.transaction(|tx| {
+            tx.prepare_cached(
+                "INSERT INTO conversation_checkpoints (conversation_id, name, last_order, scratchpad, created)
+                 VALUES (?1, ?2,
+                         (SELECT COALESCE(MAX(m_order), -1) FROM messages WHERE conversation_id = ?1),
+                         COALESCE((SELECT content FROM scratchpads WHERE conversation_id = ?1), ''),
+                         ?3)",
+            )?.execute(rusqlite::params![conversation_id as i64, name, now()])?;
+            Ok(tx.last_insert_rowid() as u64)
+        })
+    }
+
+    /// The conversation's checkpoints, oldest first.
+    pub fn list_checkpoints(&self, conversation_id: u64) -> Result<Vec<Checkpoint>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT c.id, c.name, c.created,
+                    (SELECT COUNT(*) FROM messages m
+                     WHERE m.conversation_id = c.conversation_id
+                       AND m.m_order <= c.last_order AND m.deleted_at IS NULL)
+             FROM conversation_checkpoints c
+             WHERE c.conversation_id = ?1
+             ORDER BY c.id",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![conversation_id as i64], |row| {
+            Ok(Checkpoint {
+                id: row.get::<_, i64>(0)? as u64,
+                name: row.get(1)?,
+                created: row.get(2)?,
+                messages: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Start a new conversation for `device_id` from a checkpoint of
+    /// `conversation_id`: its messages up to the checkpoint, less any deleted
+    /// since, and its scratchpad as it was. The branch keeps the original's
+    /// profile, language, PIN lock, privacy level, toolbelt choices and
+    /// incognito status, and nothing after the checkpoint. None if the conversation has no such
+    /// checkpoint.
+    pub fn branch_from_checkpoint(
+        &self,
+        device_id: u64,
+        conversation_id: u64,
+        checkpoint_id: u64,
+    ) -> Result<Option<u64>> {
+        let now = now();
+        let branched = self.transaction(|tx| {
+            let checkpoint: Option<(i64, String, Option<String>)> = tx.query_row(
+                "SELECT c.last_order, c.scratchpad, COALESCE(c.name, v.title)
+                 FROM conversation_checkpoints c
+                 JOIN conversations v ON v.id = c.conversation_id
+                 WHERE c.id = ?1 AND c.conversation_id = ?2",
+                rusqlite::params![checkpoint_id as i64, conversation_id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).optional()?;
+            let Some((last_order, scratchpad, title)) = checkpoint else {
+                return Ok(None);
+            };
+
+            tx.execute(
+                "INSERT INTO conversations
+                 (device_id, created, last_accessed, profile, language, lock_hash, privacy, expires_at)
+                 SELECT ?1, ?2, ?2, profile, language, lock_hash, privacy,
+                        CASE WHEN expires_at IS NULL THEN NULL ELSE ?2 + ?3 END
+                 FROM conversations WHERE id = ?4",
+                rusqlite::params![device_id as i64, now, INCOGNITO_TTL_SECS, conversation_id as i64],
+            )?;
+            let branch = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT INTO conversation_tools (conversation_id, toolbelt, enabled, updated)
+                 SELECT ?1, toolbelt, enabled, updated FROM conversation_tools WHERE conversation_id = ?2",
+                rusqlite::params![branch, conversation_id as i64],
+            )?;
+
+            // Tasks stay with the original, so the copies belong to none
+            tx.execute(
+                "INSERT INTO messages
+                 (conversation_id, task_id, role, message, tool_calls, m_order, created, unindexed, confidence)
+                 SELECT ?1, NULL, role, message, tool_calls, m_order, created, unindexed, confidence
+                 FROM messages
+                 WHERE conversation_id = ?2 AND m_order <= ?3 AND deleted_at IS NULL
+                 ORDER BY m_order",
+                rusqlite::params![branch, conversation_id as i64, last_order],
+            )?;
+            if !scratchpad.is_empty() {
+                tx.execute(
+                    "INSERT INTO scratchpads (conversation_id, content, updated) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![branch, scratchpad, now],
+                )?;
+            }
+            Ok(Some((branch as u64, title)))
+        })?;
+
+        let Some((branch, title)) = branched else {
+            return Ok(None);
+        };
+        if let Some(title) = title {
+            // Titles are unique per device, so the branch gets a numbered one
+            let _ = self.set_conversation_title(branch, device_id as i64, &title);
+        }
+        Ok(Some(branch))
+    }
+}
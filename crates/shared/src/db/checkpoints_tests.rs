@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    fn contents(db: &Db, conversation: u64) -> Vec<String> {
+        db.get_messages(conversation).unwrap()
+            .into_iter()
+            .filter_map(|m| m.content)
+            .collect()
+    }
+
+    #[test]
+    fn a_branch_starts_from_the_frozen_state() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.set_conversation_title(conversation, device as i64, "Garden shed").unwrap();
+        db.add_message(conversation, None, "user", Some("plan a shed"), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some("timber or steel?"), None).unwrap();
+        db.write_scratchpad(conversation, "budget: 800", false).unwrap();
+
+        let checkpoint = db.create_checkpoint(conversation, None).unwrap();
+        db.add_message(conversation, None, "user", Some("timber"), None).unwrap();
+        db.write_scratchpad(conversation, "material: timber", true).unwrap();
+
+        let listed = db.list_checkpoints(conversation).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].id, listed[0].messages), (checkpoint, 2));
+
+        let branch = db.branch_from_checkpoint(device, conversation, checkpoint).unwrap().unwrap();
+        assert_eq!(contents(&db, branch), ["plan a shed", "timber or steel?"]);
+        assert_eq!(db.get_scratchpad(branch).unwrap(), "budget: 800");
+        assert_eq!(db.get_conversation_title(branch).unwrap().as_deref(), Some("Garden_shed_1"));
+
+        // Both sides go their own way from here
+        db.add_message(branch, None, "user", Some("steel"), None).unwrap();
+        assert_eq!(contents(&db, branch).last().map(String::as_str), Some("steel"));
+        assert_eq!(contents(&db, conversation).last().map(String::as_str), Some("timber"));
+    }
+
+    #[test]
+    fn checkpoints_belong_to_their_conversation() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let first = db.create_conversation(device).unwrap();
+        let second = db.create_conversation(device).unwrap();
+        let checkpoint = db.create_checkpoint(first, Some("empty")).unwrap();
+
+        assert_eq!(db.branch_from_checkpoint(device, second, checkpoint).unwrap(), None);
+        assert!(db.list_checkpoints(second).unwrap().is_empty());
+
+        let branch = db.branch_from_checkpoint(device, first, checkpoint).unwrap().unwrap();
+        assert!(contents(&db, branch).is_empty());
+        assert_eq!(db.get_conversation_title(branch).unwrap().as_deref(), Some("empty"));
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// How sure a specialist is of what it found, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfidenceLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl ConfidenceLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// A self-rated confidence and the sources (fetched URLs) it rests on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Confidence {
+    pub level: ConfidenceLevel,
+    pub sources: Vec<String>,
+}
+
+impl Confidence {
+    /// The confidence of an answer built from several ratings: no higher than
+    /// its least sure part, with their sources pooled. None without ratings.
+    pub fn combine(ratings: &[Confidence]) -> Option<Confidence> {
+        let level = ratings.iter().map(|r| r.level).min()?;
+        let mut sources: Vec<String> = Vec::new();
+        for source in ratings.iter().flat_map(|r| &r.sources) {
+            if !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+        Some(Confidence { level, sources })
+    }
+}
+
+// ============================================================================
+// MESSAGE CONFIDENCE
+// ============================================================================
+
+impl Db {
+    /// Store a confidence with the latest assistant message a task wrote.
+    pub fn set_answer_confidence(&self, conversation_id: u64, task_id: u64, confidence: &Confidence) -> Result<()> {
+        let json = serde_json::to_string(confidence)?;
+        let conn = self.lock()?;
+        conn.execute(
+            "UPDATE messages SET confidence = ?3
+             WHERE id = (SELECT id FROM messages
+                         WHERE conversation_id = ?1 AND task_id = ?2 AND role = 'assistant'
+                         ORDER BY m_order DESC LIMIT 1)",
+            rusqlite::params![conversation_id as i64, task_id as i64, json],
+        )?;
+        Ok(())
+    }
+}
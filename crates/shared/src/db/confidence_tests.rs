@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Confidence, ConfidenceLevel, Db};
+
+    fn rated(level: ConfidenceLevel, sources: &[&str]) -> Confidence {
+        Confidence { level, sources: sources.iter().map(|s| s.to_string()).collect() }
+    }
+
+    #[test]
+    fn an_answer_is_as_sure_as_its_least_sure_part() {
+        assert_eq!(Confidence::combine(&[]), None);
+
+        let combined = Confidence::combine(&[
+            rated(ConfidenceLevel::High, &["https://a.example", "https://b.example"]),
+            rated(ConfidenceLevel::Low, &["https://a.example"]),
+        ]).unwrap();
+        assert_eq!(combined.level, ConfidenceLevel::Low);
+        assert_eq!(combined.sources, vec!["https://a.example", "https://b.example"]);
+
+        assert_eq!(ConfidenceLevel::parse(" Medium "), Some(ConfidenceLevel::Medium));
+        assert_eq!(ConfidenceLevel::parse("certain"), None);
+    }
+
+    #[test]
+    fn confidence_is_stored_with_the_tasks_last_answer() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        let task = db.create_task(device, conversation, None, "what's new in rust?").unwrap();
+
+        db.add_message(conversation, None, "user", Some("what's new in rust?"), None).unwrap();
+        db.add_message(conversation, Some(task as i64), "assistant", Some("Let me look."), None).unwrap();
+        db.add_message(conversation, Some(task as i64), "assistant", Some("Rust 1.90 shipped."), None).unwrap();
+
+        let confidence = rated(ConfidenceLevel::Low, &["https://blog.rust-lang.org"]);
+        db.set_answer_confidence(conversation, task, &confidence).unwrap();
+
+        let messages = db.get_stored_messages(conversation).unwrap();
+        let confidences: Vec<_> = messages.iter().map(|m| m.confidence.clone()).collect();
+        assert_eq!(confidences, vec![None, None, Some(confidence)]);
+        assert_eq!(messages[2].message.content.as_deref(), Some("Rust 1.90 shipped."));
+    }
+}
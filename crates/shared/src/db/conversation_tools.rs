@@ -0,0 +1,68 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// A toolbelt a conversation turned on or off for itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolChoice {
+    pub toolbelt: String,
+    pub enabled: bool,
+}
+
+// ============================================================================
+// CONVERSATION TOOLS
+// ============================================================================
+
+// Choices sit on top of each agent's own toolbelts: one turned off is
+// taken from every agent in the conversation and refused if called anyway,
+// one turned on is given to the orchestrator. An operator's tool switch
+// still wins over both.
+impl Db {
+    /// Turn `toolbelt` on or off for the conversation, or with None go back
+    /// to the agents' defaults.
+    pub fn set_conversation_toolbelt(&self, conversation_id: u64, toolbelt: &str, enabled: Option<bool>) -> Result<()> {
+        match enabled {
+            Some(enabled) => self.execute(
+                "INSERT INTO conversation_tools (conversation_id, toolbelt, enabled, updated)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(conversation_id, toolbelt) DO UPDATE SET
+                     enabled = excluded.enabled, updated = excluded.updated",
+                rusqlite::params![conversation_id as i64, toolbelt, enabled, now()],
+            )?,
+            None => self.execute(
+                "DELETE FROM conversation_tools WHERE conversation_id = ?1 AND toolbelt = ?2",
+                rusqlite::params![conversation_id as i64, toolbelt],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Every choice the conversation made, by toolbelt.
+    pub fn conversation_toolbelts(&self, conversation_id: u64) -> Result<Vec<ToolChoice>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT toolbelt, enabled FROM conversation_tools
+             WHERE conversation_id = ?1 ORDER BY toolbelt",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![conversation_id as i64], |row| {
+            Ok(ToolChoice {
+                toolbelt: row.get(0)?,
+                enabled: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Whether the conversation turned off the toolbelt of `tool_name`
+    /// (e.g. `WebSearch::search`).
+    pub fn toolbelt_turned_off(&self, conversation_id: u64, tool_name: &str) -> Result<bool> {
+        let toolbelt = tool_name.split("::").next().unwrap_or(tool_name);
+        let enabled: Option<bool> = self.query_row_optional(
+            "SELECT enabled FROM conversation_tools WHERE conversation_id = ?1 AND toolbelt = ?2",
+            rusqlite::params![conversation_id as i64, toolbelt],
+            |row| row.get(0),
+        )?;
+        Ok(enabled == Some(false))
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, MergeOutcome, ToolChoice};
+
+    fn choice(toolbelt: &str, enabled: bool) -> ToolChoice {
+        ToolChoice { toolbelt: toolbelt.to_string(), enabled }
+    }
+
+    #[test]
+    fn choices_are_kept_per_conversation_until_reset() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let private = db.create_conversation(device).unwrap();
+        let other = db.create_conversation(device).unwrap();
+
+        db.set_conversation_toolbelt(private, "WebSearch", Some(false)).unwrap();
+        db.set_conversation_toolbelt(private, "Places", Some(true)).unwrap();
+        assert!(db.toolbelt_turned_off(private, "WebSearch::search").unwrap());
+        assert!(!db.toolbelt_turned_off(private, "Places::search").unwrap());
+        assert!(!db.toolbelt_turned_off(other, "WebSearch::search").unwrap());
+        assert_eq!(
+            db.conversation_toolbelts(private).unwrap(),
+            [choice("Places", true), choice("WebSearch", false)],
+        );
+
+        db.set_conversation_toolbelt(private, "WebSearch", None).unwrap();
+        assert!(!db.toolbelt_turned_off(private, "WebSearch::search").unwrap());
+        assert_eq!(db.conversation_toolbelts(private).unwrap(), [choice("Places", true)]);
+    }
+
+    #[test]
+    fn merging_keeps_a_toolbelt_off_if_either_turned_it_off() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let target = db.create_conversation(device).unwrap();
+        let source = db.create_conversation(device).unwrap();
+        db.set_conversation_toolbelt(target, "WebSearch", Some(true)).unwrap();
+        db.set_conversation_toolbelt(source, "WebSearch", Some(false)).unwrap();
+        db.set_conversation_toolbelt(source, "Places", Some(true)).unwrap();
+
+        assert_eq!(db.merge_conversations(target, source).unwrap(), MergeOutcome::Done);
+        assert_eq!(
+            db.conversation_toolbelts(target).unwrap(),
+            [choice("Places", true), choice("WebSearch", false)],
+        );
+    }
+}
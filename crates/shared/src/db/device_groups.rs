@@ -0,0 +1,268 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// Longest device group name, in characters.
+pub const MAX_GROUP_CHARS: usize = 32;
+
+/// One of the user's devices, as listed and targeted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedDevice {
+    pub device_id: u64,
+    pub device_name: String,
+    /// URL of its envoy tool server, if it reported one.
+    pub tool_host: Option<String>,
+}
+
+/// A device a tool call was aimed at, with what the engine needs to reach it.
+#[derive(Debug, Clone)]
+pub struct TargetDevice {
+    pub device_id: u64,
+    pub device_name: String,
+    pub device_key: String,
+    pub tool_host: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    pub name: String,
+    pub devices: Vec<OwnedDevice>,
+}
+
+/// Canonical form of a group name: trimmed and lowercase. None if it is
+/// empty, too long, or has characters other than letters, digits, `-` and `_`.
+pub fn normalize_group(name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_GROUP_CHARS
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(name)
+}
+
+// ============================================================================
+// OWNERSHIP
+// ============================================================================
+
+// A device belongs to the user its owner_id names; a device with none is its
+// own owner. Linking a device moves it, and everything it owned, to the
+// linking device's owner.
+impl Db {
+    /// The device standing for the user `device_id` belongs to.
+    pub fn device_owner(&self, device_id: u64) -> Result<u64> {
+        self.query_row_optional(
+            "SELECT COALESCE(owner_id, id) FROM devices WHERE id = ?1",
+            rusqlite::params![device_id as i64],
+            |row| row.get::<_, i64>(0),
+        )?
+        .map(|id| id as u64)
+        .ok_or_else(|| anyhow::anyhow!("Device {} does not exist", device_id))
+    }
+
+    /// Make `device_id` (and any devices it owns) belong to `owner_device`'s user.
+    pub fn link_device(&self, owner_device: u64, device_id: u64) -> Result<()> {
+        let owner = self.device_owner(owner_device)?;
+        let previous = self.device_owner(device_id)?;
+        if owner == previous {
+            return Ok(());
+        }
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE devices SET owner_id = ?1 WHERE id = ?2 OR owner_id = ?2",
+            rusqlite::params![owner as i64, previous as i64],
+        )?;
+        // The owner itself owns itself
+        tx.execute(
+            "UPDATE devices SET owner_id = NULL WHERE id = ?1",
+            rusqlite::params![owner as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every active device of `device_id`'s user, by id.
+    pub fn list_owned_devices(&self, device_id: u64) -> Result<Vec<OwnedDevice>> {
+        Ok(self.owned_targets(device_id)?
+            .into_iter()
+            .map(TargetDevice::into_owned)
+            .collect())
+    }
+
+    fn owned_targets(&self, device_id: u64) -> Result<Vec<TargetDevice>> {
+        let owner = self.device_owner(device_id)?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, device_name, device_key, metadata FROM devices
+             WHERE active = 1 AND COALESCE(owner_id, id) = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![owner as i64], |row| {
+            let metadata: Option<String> = row.get(3)?;
+            Ok(TargetDevice {
+                device_id: row.get::<_, i64>(0)? as u64,
+                device_name: row.get(1)?,
+                device_key: row.get(2)?,
+                tool_host: metadata
+                    .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                    .and_then(|m| m["tool_host"].as_str().map(String::from)),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+impl TargetDevice {
+    fn into_owned(self) -> OwnedDevice {
+        OwnedDevice {
+            device_id: self.device_id,
+            device_name: self.device_name,
+            tool_host: self.tool_host,
+        }
+    }
+}
+
+// ============================================================================
+// DEVICE GROUPS
+// ============================================================================
+
+impl Db {
+    /// Create or replace the user's group `name` with `members` (device names
+    /// or ids), which must all be the user's own devices.
+    pub fn set_device_group(&self, device_id: u64, name: &str, members: &[String]) -> Result<DeviceGroup> {
+        let name = normalize_group(name)
+            .ok_or_else(|| anyhow::anyhow!("Invalid group name '{}'", name))?;
+        let owned = self.owned_targets(device_id)?;
+        let mut ids = Vec::new();
+        for member in members {
+            let device = find_device(&owned, member)?;
+            if !ids.contains(&device.device_id) {
+                ids.push(device.device_id);
+            }
+        }
+        let owner = self.device_owner(device_id)?;
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO host_groups (owner_id, name, created) VALUES (?1, ?2, ?3)",
+            rusqlite::params![owner as i64, name, now()],
+        )?;
+        let group_id: i64 = tx.query_row(
+            "SELECT id FROM host_groups WHERE owner_id = ?1 AND name = ?2",
+            rusqlite::params![owner as i64, name],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "DELETE FROM host_group_members WHERE group_id = ?1",
+            rusqlite::params![group_id],
+        )?;
+        for id in &ids {
+            tx.execute(
+                "INSERT INTO host_group_members (group_id, device_id) VALUES (?1, ?2)",
+                rusqlite::params![group_id, *id as i64],
+            )?;
+        }
+        tx.commit()?;
+
+        let devices = owned.into_iter()
+            .filter(|d| ids.contains(&d.device_id))
+            .map(TargetDevice::into_owned)
+            .collect();
+        Ok(DeviceGroup { name, devices })
+    }
+
+    /// Delete the user's group `name`. Returns false if there was none.
+    pub fn delete_device_group(&self, device_id: u64, name: &str) -> Result<bool> {
+        let Some(name) = normalize_group(name) else {
+            return Ok(false);
+        };
+        let owner = self.device_owner(device_id)?;
+        let deleted = self.execute(
+            "DELETE FROM host_groups WHERE owner_id = ?1 AND name = ?2",
+            rusqlite::params![owner as i64, name],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// The user's groups by name, each with its active devices.
+    pub fn list_device_groups(&self, device_id: u64) -> Result<Vec<DeviceGroup>> {
+        let owned = self.owned_targets(device_id)?;
+        let owner = self.device_owner(device_id)?;
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT g.name, m.device_id FROM host_groups g
+             LEFT JOIN host_group_members m ON m.group_id = g.id
+             WHERE g.owner_id = ?1 ORDER BY g.name, m.device_id",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![owner as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+        })?;
+
+        let mut groups: Vec<DeviceGroup> = Vec::new();
+        for row in rows {
+            let (name, member) = row?;
+            if groups.last().is_none_or(|g| g.name != name) {
+                groups.push(DeviceGroup { name, devices: Vec::new() });
+            }
+            let group = groups.last_mut().expect("pushed above");
+            if let Some(device) = member.and_then(|id| owned.iter().find(|d| d.device_id == id as u64)) {
+                group.devices.push(device.clone().into_owned());
+            }
+        }
+        Ok(groups)
+    }
+
+    /// The devices a target names, for a tool call made by `device_id`.
+    ///
+    /// A target is `all`, `device=<name or id>` or `group=<name>`; several
+    /// can be joined with commas. Only the user's own devices can be named.
+    pub fn resolve_device_target(&self, device_id: u64, target: &str) -> Result<Vec<TargetDevice>> {
+        let owned = self.owned_targets(device_id)?;
+        let mut devices: Vec<TargetDevice> = Vec::new();
+        let mut add = |device: &TargetDevice| {
+            if !devices.iter().any(|d| d.device_id == device.device_id) {
+                devices.push(device.clone());
+            }
+        };
+
+        for part in target.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                None if part.eq_ignore_ascii_case("all") => owned.iter().for_each(&mut add),
+                Some(("device", name)) => add(find_device(&owned, name)?),
+                Some(("group", name)) => {
+                    let group = self.list_device_groups(device_id)?
+                        .into_iter()
+                        .find(|g| Some(&g.name) == normalize_group(name).as_ref())
+                        .ok_or_else(|| anyhow::anyhow!("No device group '{}'", name))?;
+                    for member in &group.devices {
+                        add(find_device(&owned, &member.device_id.to_string())?);
+                    }
+                }
+                _ => return Err(anyhow::anyhow!(
+                    "Invalid target '{}': use 'all', 'device=<name>' or 'group=<name>'",
+                    part
+                )),
+            }
+        }
+
+        if devices.is_empty() {
+            return Err(anyhow::anyhow!("Target '{}' names no devices", target));
+        }
+        Ok(devices)
+    }
+}
+
+/// The user's device with this name or id.
+fn find_device<'a>(owned: &'a [TargetDevice], name_or_id: &str) -> Result<&'a TargetDevice> {
+    let name_or_id = name_or_id.trim();
+    owned
+        .iter()
+        .find(|d| d.device_name.eq_ignore_ascii_case(name_or_id))
+        .or_else(|| {
+            let id: u64 = name_or_id.parse().ok()?;
+            owned.iter().find(|d| d.device_id == id)
+        })
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not one of your devices", name_or_id))
+}
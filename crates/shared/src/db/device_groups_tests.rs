@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    fn names(devices: &[crate::db::TargetDevice]) -> Vec<&str> {
+        devices.iter().map(|d| d.device_name.as_str()).collect()
+    }
+
+    #[test]
+    fn linking_moves_a_device_and_what_it_owned() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let nas = register_device(&db, "nas");
+        let pi = register_device(&db, "pi");
+
+        db.link_device(nas, pi).unwrap();
+        assert_eq!(db.device_owner(pi).unwrap(), nas);
+
+        db.link_device(laptop, nas).unwrap();
+        assert_eq!(db.device_owner(nas).unwrap(), laptop);
+        assert_eq!(db.device_owner(pi).unwrap(), laptop);
+
+        let owned: Vec<u64> = db.list_owned_devices(pi).unwrap().iter().map(|d| d.device_id).collect();
+        assert_eq!(owned, [laptop, nas, pi]);
+    }
+
+    #[test]
+    fn targets_resolve_to_owned_devices_only() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let nas = register_device(&db, "nas");
+        let pi = register_device(&db, "pi");
+        let stranger = register_device(&db, "stranger");
+        db.link_device(laptop, nas).unwrap();
+        db.link_device(laptop, pi).unwrap();
+
+        db.set_device_group(laptop, "HomeLab", &["nas".to_string(), pi.to_string()]).unwrap();
+        let groups = db.list_device_groups(nas).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "homelab");
+
+        assert_eq!(names(&db.resolve_device_target(laptop, "device=nas").unwrap()), ["nas"]);
+        assert_eq!(names(&db.resolve_device_target(laptop, "group=homelab, device=pi").unwrap()), ["nas", "pi"]);
+        assert_eq!(names(&db.resolve_device_target(laptop, "all").unwrap()), ["laptop", "nas", "pi"]);
+
+        assert!(db.resolve_device_target(laptop, "device=stranger").is_err());
+        assert!(db.resolve_device_target(stranger, "group=homelab").is_err());
+        assert!(db.resolve_device_target(laptop, "nas").is_err());
+        assert!(db.set_device_group(laptop, "mixed", &["stranger".to_string()]).is_err());
+
+        assert!(db.delete_device_group(pi, "homelab").unwrap());
+        assert!(db.list_device_groups(laptop).unwrap().is_empty());
+    }
+
+    #[test]
+    fn old_group_tables_are_renamed_on_startup() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let nas = register_device(&db, "nas");
+        db.link_device(laptop, nas).unwrap();
+        db.set_device_group(laptop, "homelab", &["nas".to_string()]).unwrap();
+
+        let conn = db.lock().unwrap();
+        conn.execute_batch("
+            ALTER TABLE host_groups RENAME TO device_groups;
+            ALTER TABLE host_group_members RENAME TO device_group_members;
+        ").unwrap();
+        crate::db::schema::create_tables(&conn).unwrap();
+        drop(conn);
+
+        let groups = db.list_device_groups(laptop).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].devices.len(), 1);
+        assert!(db.delete_device_group(laptop, "homelab").unwrap());
+    }
+}
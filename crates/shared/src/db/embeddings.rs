@@ -0,0 +1,221 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::memories::checked_profile;
+use super::{now, Db, VISIBLE_CONVERSATION};
+use crate::embeddings::{decode_vector, encode_vector};
+
+/// Times the embedding model may fail on a message or memory before the
+/// `embed` job stops trying it.
+pub const MAX_EMBED_FAILURES: u32 = 3;
+
+/// What an embedding was made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingSource {
+    Message,
+    Memory,
+}
+
+/// A message or memory with no embedding from the current model yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEmbedding {
+    pub source: EmbeddingSource,
+    pub id: u64,
+    pub text: String,
+}
+
+/// An embedded message or memory, with what semantic search shows of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedText {
+    pub source: EmbeddingSource,
+    pub id: u64,
+    /// The conversation a message is in; None for memories.
+    pub conversation_id: Option<u64>,
+    pub title: Option<String>,
+    pub created: i64,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+// ============================================================================
+// EMBEDDINGS
+// ============================================================================
+
+// Vectors for semantic search, made by the `embed` background job. Each is
+// tied to the model that made it: switching EMBEDDING_MODEL leaves the old
+// ones unused and the job embeds everything again. Incognito messages are
+// never embedded, and edited or redacted text loses its embedding (see
+// `create_embedding_triggers`) until the job embeds it anew. Text the model
+// fails on keeps a failed row (empty vector, `failures` > 0) and is tried
+// again until it has failed `MAX_EMBED_FAILURES` times.
+impl Db {
+    /// Up to `limit` messages and memories waiting for an embedding from
+    /// `model`, newest first so recent history is searchable soonest.
+    /// Only what people wrote and read is embedded: user and assistant text.
+    /// Text the model failed on comes back until it has failed too often.
+    pub fn unembedded(&self, model: &str, limit: usize) -> Result<Vec<PendingEmbedding>> {
+        let conn = self.reader()?;
+        let mut pending = Vec::new();
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT m.id, m.message FROM messages m
+             LEFT JOIN embeddings e ON e.message_id = m.id AND e.model = ?1
+             WHERE (e.id IS NULL OR e.failures BETWEEN 1 AND ?3) AND m.role IN ('user', 'assistant') AND m.unindexed = 0
+               AND m.deleted_at IS NULL AND m.message IS NOT NULL AND TRIM(m.message) != ''
+             ORDER BY m.id DESC
+             LIMIT ?2",
+        )?;
+        let retries = MAX_EMBED_FAILURES - 1;
+        let rows = stmt.query_map(rusqlite::params![model, limit as i64, retries], |row| {
+            Ok(PendingEmbedding {
+                source: EmbeddingSource::Message,
+                id: row.get::<_, i64>(0)? as u64,
+                text: row.get(1)?,
+            })
+        })?;
+        pending.extend(rows.collect::<rusqlite::Result<Vec<_>>>()?);
+
+        let left = limit.saturating_sub(pending.len());
+        if left > 0 {
+            let mut stmt = conn.prepare_cached(
+                "SELECT mem.id, mem.content FROM memories mem
+                 LEFT JOIN embeddings e ON e.memory_id = mem.id AND e.model = ?1
+                 WHERE e.id IS NULL OR e.failures BETWEEN 1 AND ?3
+                 ORDER BY mem.id DESC
+                 LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(rusqlite::params![model, left as i64, retries], |row| {
+                Ok(PendingEmbedding {
+                    source: EmbeddingSource::Memory,
+                    id: row.get::<_, i64>(0)? as u64,
+                    text: row.get(1)?,
+                })
+            })?;
+            pending.extend(rows.collect::<rusqlite::Result<Vec<_>>>()?);
+        }
+        Ok(pending)
+    }
+
+    /// Store the embedding of a message or memory, replacing any earlier one.
+    pub fn store_embedding(&self, source: EmbeddingSource, id: u64, model: &str, vector: &[f32]) -> Result<()> {
+        let sql = match source {
+            EmbeddingSource::Message =>
+                "INSERT INTO embeddings (message_id, model, vector, created) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(message_id) DO UPDATE
+                 SET model = excluded.model, vector = excluded.vector, created = excluded.created,
+                     failures = 0, error = NULL",
+            EmbeddingSource::Memory =>
+                "INSERT INTO embeddings (memory_id, model, vector, created) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(memory_id) DO UPDATE
+                 SET model = excluded.model, vector = excluded.vector, created = excluded.created,
+                     failures = 0, error = NULL",
+        };
+        self.execute(sql, rusqlite::params![id as i64, model, encode_vector(vector), now()])?;
+        Ok(())
+    }
+
+    /// Record that `model` failed to embed a message or memory, replacing
+    /// any embedding from another model. Returns how many times in a row it
+    /// has failed on it.
+    pub fn record_embedding_failure(&self, source: EmbeddingSource, id: u64, model: &str, error: &str) -> Result<u32> {
+        let column = match source {
+            EmbeddingSource::Message => "message_id",
+            EmbeddingSource::Memory => "memory_id",
+        };
+        let failures = self.query_row_optional(
+            &format!(
+                "INSERT INTO embeddings ({column}, model, vector, failures, error, created)
+                 VALUES (?1, ?2, X'', 1, ?3, ?4)
+                 ON CONFLICT({column}) DO UPDATE
+                 SET failures = CASE WHEN embeddings.model = excluded.model
+                                     THEN embeddings.failures + 1 ELSE 1 END,
+                     model = excluded.model, vector = X'', error = excluded.error, created = excluded.created
+                 RETURNING failures"
+            ),
+            rusqlite::params![id as i64, model, error, now()],
+            |row| row.get::<_, u32>(0),
+        )?;
+        Ok(failures.unwrap_or_default())
+    }
+
+    /// Everything in a device's profile that has an embedding from `model`
+    /// and that the Archivist may show: messages of visible conversations
+    /// and the profile's memories.
+    pub fn embedded_texts(&self, device_id: u64, profile: &str, model: &str) -> Result<Vec<EmbeddedText>> {
+        let profile = checked_profile(profile)?;
+        let conn = self.reader()?;
+        let mut texts = Vec::new();
+
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT m.id, c.id, c.title, m.created, m.message, e.vector
+             FROM embeddings e
+             JOIN messages m ON m.id = e.message_id
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE {} AND c.profile = ?3 AND e.model = ?4 AND e.failures = 0
+               AND m.deleted_at IS NULL AND m.message IS NOT NULL",
+            VISIBLE_CONVERSATION
+        ))?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64, now(), profile, model], |row| {
+            Ok(EmbeddedText {
+                source: EmbeddingSource::Message,
+                id: row.get::<_, i64>(0)? as u64,
+                conversation_id: Some(row.get::<_, i64>(1)? as u64),
+                title: row.get(2)?,
+                created: row.get(3)?,
+                text: row.get(4)?,
+                vector: decode_vector(&row.get::<_, Vec<u8>>(5)?),
+            })
+        })?;
+        texts.extend(rows.collect::<rusqlite::Result<Vec<_>>>()?);
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT mem.id, mem.created, mem.content, e.vector
+             FROM embeddings e JOIN memories mem ON mem.id = e.memory_id
+             WHERE mem.device_id = ?1 AND mem.profile = ?2 AND e.model = ?3 AND e.failures = 0",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64, profile, model], |row| {
+            Ok(EmbeddedText {
+                source: EmbeddingSource::Memory,
+                id: row.get::<_, i64>(0)? as u64,
+                conversation_id: None,
+                title: None,
+                created: row.get(1)?,
+                text: row.get(2)?,
+                vector: decode_vector(&row.get::<_, Vec<u8>>(3)?),
+            })
+        })?;
+        texts.extend(rows.collect::<rusqlite::Result<Vec<_>>>()?);
+        Ok(texts)
+    }
+
+    /// Queue an `embed` job if something waits for an embedding from
+    /// `model` and no such job is already pending. The job belongs to no
+    /// device, so its events reach no job stream. Returns the new job's id.
+    pub fn queue_embedding(&self, model: &str) -> Result<Option<u64>> {
+        let (waiting, queued) = self.query_row_optional(
+            "SELECT
+                 EXISTS(SELECT 1 FROM messages m
+                        LEFT JOIN embeddings e ON e.message_id = m.id AND e.model = ?1
+                        WHERE (e.id IS NULL OR e.failures BETWEEN 1 AND ?2) AND m.role IN ('user', 'assistant') AND m.unindexed = 0
+                          AND m.deleted_at IS NULL AND m.message IS NOT NULL AND TRIM(m.message) != '')
+                 OR EXISTS(SELECT 1 FROM memories mem
+                           LEFT JOIN embeddings e ON e.memory_id = mem.id AND e.model = ?1
+                           WHERE e.id IS NULL OR e.failures BETWEEN 1 AND ?2),
+                 EXISTS(SELECT 1 FROM background WHERE method = 'embed' AND status = 'pending')",
+            rusqlite::params![model, MAX_EMBED_FAILURES - 1],
+            |row| Ok((row.get::<_, bool>(0)?, row.get::<_, bool>(1)?)),
+        )?.unwrap_or_default();
+        if !waiting || queued {
+            return Ok(None);
+        }
+
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO background (device_id, method, arguments, priority, status, created_at)
+             VALUES (NULL, 'embed', '{}', 0, 'pending', ?1)",
+            rusqlite::params![now()],
+        )?;
+        Ok(Some(conn.last_insert_rowid() as u64))
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::{register_device, Db, EmbeddingSource, RedactOutcome};
+    use crate::memory::MemoryStore;
+
+    const MODEL: &str = "nomic-embed-text";
+
+    /// Embed everything waiting with the same vector.
+    fn embed_all(db: &Db, model: &str) -> usize {
+        let pending = db.unembedded(model, 100).unwrap();
+        for item in &pending {
+            db.store_embedding(item.source, item.id, model, &[1.0, 0.0]).unwrap();
+        }
+        pending.len()
+    }
+
+    #[test]
+    fn only_user_and_assistant_text_waits_for_an_embedding() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("plan a trip to Lisbon"), None).unwrap();
+        db.add_message(conversation, None, "tool", Some("{\"results\": []}"), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some("Here is a plan."), None).unwrap();
+        db.add_message(conversation, None, "assistant", None, None).unwrap();
+        MemoryStore::new(db.clone()).remember(device, "default", "Prefers window seats").unwrap();
+
+        let pending = db.unembedded(MODEL, 100).unwrap();
+        let texts: Vec<&str> = pending.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(texts, vec!["Here is a plan.", "plan a trip to Lisbon", "Prefers window seats"]);
+        assert_eq!(pending[2].source, EmbeddingSource::Memory);
+        assert_eq!(db.unembedded(MODEL, 1).unwrap().len(), 1);
+
+        assert_eq!(embed_all(&db, MODEL), 3);
+        assert!(db.unembedded(MODEL, 100).unwrap().is_empty());
+        // Another model has made none of them
+        assert_eq!(db.unembedded("other-model", 100).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn incognito_messages_are_never_embedded() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.start_conversation(device, None, true).unwrap();
+        db.add_message(conversation, None, "user", Some("something private"), None).unwrap();
+
+        assert!(db.unembedded(MODEL, 100).unwrap().is_empty());
+        assert_eq!(db.queue_embedding(MODEL).unwrap(), None);
+    }
+
+    #[test]
+    fn search_sees_only_what_the_archivist_may_show() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let other = register_device(&db, "phone");
+
+        let open = db.create_conversation(device).unwrap();
+        db.add_message(open, None, "user", Some("visible"), None).unwrap();
+        let locked = db.create_conversation(device).unwrap();
+        db.add_message(locked, None, "user", Some("locked away"), None).unwrap();
+        db.lock_conversation(locked, "4321").unwrap();
+        let work = db.start_conversation(device, Some("work"), false).unwrap();
+        db.add_message(work, None, "user", Some("work only"), None).unwrap();
+        let theirs = db.create_conversation(other).unwrap();
+        db.add_message(theirs, None, "user", Some("not yours"), None).unwrap();
+        let memories = MemoryStore::new(db.clone());
+        memories.remember(device, "default", "Lives in Porto").unwrap();
+        memories.remember(device, "work", "Works on billing").unwrap();
+        embed_all(&db, MODEL);
+
+        let mut texts: Vec<String> = db.embedded_texts(device, "default", MODEL).unwrap()
+            .into_iter()
+            .map(|t| t.text)
+            .collect();
+        texts.sort();
+        assert_eq!(texts, vec!["Lives in Porto", "visible"]);
+
+        let work_texts = db.embedded_texts(device, "work", MODEL).unwrap();
+        assert_eq!(work_texts.len(), 2);
+        let message = work_texts.iter().find(|t| t.source == EmbeddingSource::Message).unwrap();
+        assert_eq!(message.conversation_id, Some(work));
+        assert_eq!(message.vector, vec![1.0, 0.0]);
+
+        assert!(db.embedded_texts(device, "default", "other-model").unwrap().is_empty());
+        assert!(db.embedded_texts(device, "not a profile", MODEL).is_err());
+    }
+
+    #[test]
+    fn changed_text_loses_its_embedding() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("my key is sk-123"), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some("Noted."), None).unwrap();
+        MemoryStore::new(db.clone()).remember(device, "default", "Key is sk-123").unwrap();
+        embed_all(&db, MODEL);
+        let ids: Vec<u64> = db.get_stored_messages(conversation).unwrap().iter().map(|m| m.id).collect();
+
+        let outcome = db.redact_message(conversation, ids[0], Some("sk-123")).unwrap();
+        assert!(matches!(outcome, RedactOutcome::Redacted(_)));
+        let pending = db.unembedded(MODEL, 100).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|p| !p.text.contains("sk-123")));
+
+        // A deleted message is gone from search and never embedded again
+        embed_all(&db, MODEL);
+        assert!(db.delete_message(conversation, ids[1]).unwrap());
+        assert!(db.unembedded(MODEL, 100).unwrap().is_empty());
+        let texts = db.embedded_texts(device, "default", MODEL).unwrap();
+        assert!(texts.iter().all(|t| t.text != "Noted."));
+    }
+
+    #[test]
+    fn one_embed_job_waits_at_a_time() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        assert_eq!(db.queue_embedding(MODEL).unwrap(), None);
+
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("hello"), None).unwrap();
+        let job = db.queue_embedding(MODEL).unwrap().expect("a job for the new message");
+        assert_eq!(db.queue_embedding(MODEL).unwrap(), None);
+
+        let stored = db.get_job(job).unwrap().unwrap();
+        assert_eq!(stored.method, "embed");
+        assert_eq!(stored.device_id, None);
+    }
+}
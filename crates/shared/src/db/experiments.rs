@@ -0,0 +1,224 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{now, Db};
+
+/// Which of an experiment's two prompts a run used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptVariant {
+    A,
+    B,
+}
+
+impl PromptVariant {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromptVariant::A => "a",
+            PromptVariant::B => "b",
+        }
+    }
+
+    /// Deterministic split: a conversation always lands on the same variant
+    /// within an experiment, so a user never sees prompts flip mid-thread.
+    pub fn assign(experiment_id: u64, conversation_id: u64) -> Self {
+        // splitmix64 finalizer: spreads sequential ids evenly across both halves
+        let mut x = experiment_id.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ conversation_id;
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^= x >> 31;
+        if x & 1 == 0 { PromptVariant::A } else { PromptVariant::B }
+    }
+}
+
+/// Two competing instruction sets for one agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptExperiment {
+    pub id: u64,
+    pub agent: String,
+    pub name: String,
+    pub variant_a: String,
+    pub variant_b: String,
+    pub active: bool,
+    pub created: i64,
+    pub ended: Option<i64>,
+}
+
+impl PromptExperiment {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(PromptExperiment {
+            id: row.get::<_, i64>(0)? as u64,
+            agent: row.get(1)?,
+            name: row.get(2)?,
+            variant_a: row.get(3)?,
+            variant_b: row.get(4)?,
+            active: row.get::<_, i64>(5)? != 0,
+            created: row.get(6)?,
+            ended: row.get(7)?,
+        })
+    }
+
+    pub fn prompt(&self, variant: PromptVariant) -> &str {
+        match variant {
+            PromptVariant::A => &self.variant_a,
+            PromptVariant::B => &self.variant_b,
+        }
+    }
+}
+
+/// Aggregated outcomes for one variant.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantStats {
+    pub variant: PromptVariant,
+    pub runs: u64,
+    pub success_rate: Option<f64>,
+    pub avg_iterations: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub feedback_count: u64,
+    /// Mean of -1 / +1 ratings.
+    pub avg_feedback: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentReport {
+    pub experiment: PromptExperiment,
+    pub variants: Vec<VariantStats>,
+}
+
+const EXPERIMENT_COLUMNS: &str =
+    "id, agent, name, variant_a, variant_b, active, created, ended";
+
+// ============================================================================
+// PROMPT EXPERIMENTS
+// ============================================================================
+
+impl Db {
+    /// Start an experiment for `agent`, ending any experiment already running for it.
+    pub fn start_prompt_experiment(
+        &self,
+        agent: &str,
+        name: &str,
+        variant_a: &str,
+        variant_b: &str,
+    ) -> Result<u64> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        let now = now();
+
+        tx.execute(
+            "UPDATE prompt_experiments SET active = 0, ended = ?2 WHERE agent = ?1 AND active = 1",
+            rusqlite::params![agent, now],
+        )?;
+        tx.execute(
+            "INSERT INTO prompt_experiments (agent, name, variant_a, variant_b, active, created)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            rusqlite::params![agent, name, variant_a, variant_b, now],
+        )?;
+        let id = tx.last_insert_rowid() as u64;
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Stop assigning runs to an experiment. Returns false if it wasn't running.
+    pub fn end_prompt_experiment(&self, experiment_id: u64) -> Result<bool> {
+        let changed = self.execute(
+            "UPDATE prompt_experiments SET active = 0, ended = ?2 WHERE id = ?1 AND active = 1",
+            rusqlite::params![experiment_id as i64, now()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn active_prompt_experiment(&self, agent: &str) -> Result<Option<PromptExperiment>> {
+        self.query_row_optional(
+            &format!("SELECT {} FROM prompt_experiments WHERE agent = ?1 AND active = 1", EXPERIMENT_COLUMNS),
+            rusqlite::params![agent],
+            PromptExperiment::from_row,
+        )
+    }
+
+    pub fn get_prompt_experiment(&self, experiment_id: u64) -> Result<Option<PromptExperiment>> {
+        self.query_row_optional(
+            &format!("SELECT {} FROM prompt_experiments WHERE id = ?1", EXPERIMENT_COLUMNS),
+            rusqlite::params![experiment_id as i64],
+            PromptExperiment::from_row,
+        )
+    }
+
+    /// Record how one run under an experiment went.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_experiment_outcome(
+        &self,
+        experiment_id: u64,
+        variant: PromptVariant,
+        task_id: u64,
+        conversation_id: u64,
+        iterations: u32,
+        latency_ms: u64,
+        success: bool,
+    ) -> Result<()> {
+        self.execute(
+            "INSERT INTO experiment_outcomes
+             (experiment_id, variant, task_id, conversation_id, iterations, latency_ms, success, created)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                experiment_id as i64,
+                variant.as_str(),
+                task_id as i64,
+                conversation_id as i64,
+                iterations,
+                latency_ms as i64,
+                success,
+                now()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Attach a user rating to the most recent experiment run in a conversation.
+    /// Returns false if no run in the conversation was part of an experiment.
+    pub fn record_experiment_feedback(&self, conversation_id: u64, score: i64) -> Result<bool> {
+        let changed = self.execute(
+            "UPDATE experiment_outcomes SET feedback = ?2
+             WHERE id = (SELECT id FROM experiment_outcomes
+                         WHERE conversation_id = ?1
+                         ORDER BY created DESC, id DESC LIMIT 1)",
+            rusqlite::params![conversation_id as i64, score],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Per-variant comparison for an experiment.
+    pub fn experiment_report(&self, experiment_id: u64) -> Result<Option<ExperimentReport>> {
+        let Some(experiment) = self.get_prompt_experiment(experiment_id)? else {
+            return Ok(None);
+        };
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT COUNT(*), AVG(success), AVG(iterations), AVG(latency_ms),
+                    COUNT(feedback), AVG(feedback)
+             FROM experiment_outcomes
+             WHERE experiment_id = ?1 AND variant = ?2",
+        )?;
+
+        let mut variants = Vec::new();
+        for variant in [PromptVariant::A, PromptVariant::B] {
+            let stats = stmt.query_row(
+                rusqlite::params![experiment_id as i64, variant.as_str()],
+                |row| Ok(VariantStats {
+                    variant,
+                    runs: row.get::<_, i64>(0)? as u64,
+                    success_rate: row.get(1)?,
+                    avg_iterations: row.get(2)?,
+                    avg_latency_ms: row.get(3)?,
+                    feedback_count: row.get::<_, i64>(4)? as u64,
+                    avg_feedback: row.get(5)?,
+                }),
+            )?;
+            variants.push(stats);
+        }
+
+        Ok(Some(ExperimentReport { experiment, variants }))
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{Db, PromptVariant};
+
+    #[test]
+    fn assignment_is_stable_and_splits() {
+        for conversation in 0..50 {
+            assert_eq!(PromptVariant::assign(7, conversation), PromptVariant::assign(7, conversation));
+        }
+        let a = (0..1000).filter(|c| PromptVariant::assign(1, *c) == PromptVariant::A).count();
+        assert!((400..600).contains(&a), "lopsided split: {} of 1000 on A", a);
+    }
+
+    #[test]
+    fn starting_an_experiment_ends_the_previous_one() {
+        let db = Db::in_memory().unwrap();
+        let first = db.start_prompt_experiment("Archivist", "first", "a", "b").unwrap();
+        let second = db.start_prompt_experiment("Archivist", "second", "a", "b").unwrap();
+
+        assert_eq!(db.active_prompt_experiment("Archivist").unwrap().unwrap().id, second);
+        assert!(!db.get_prompt_experiment(first).unwrap().unwrap().active);
+        assert!(db.end_prompt_experiment(second).unwrap());
+        assert!(db.active_prompt_experiment("Archivist").unwrap().is_none());
+    }
+
+    #[test]
+    fn report_aggregates_per_variant() {
+        let db = Db::in_memory().unwrap();
+        let id = db.start_prompt_experiment("FileSmith", "terse", "a", "b").unwrap();
+
+        db.record_experiment_outcome(id, PromptVariant::A, 1, 10, 4, 1000, true).unwrap();
+        db.record_experiment_outcome(id, PromptVariant::A, 2, 11, 6, 3000, false).unwrap();
+        db.record_experiment_outcome(id, PromptVariant::B, 3, 12, 2, 500, true).unwrap();
+        assert!(db.record_experiment_feedback(12, 1).unwrap());
+        assert!(!db.record_experiment_feedback(99, 1).unwrap());
+
+        let report = db.experiment_report(id).unwrap().unwrap();
+        let (a, b) = (&report.variants[0], &report.variants[1]);
+        assert_eq!(a.runs, 2);
+        assert_eq!(a.success_rate, Some(0.5));
+        assert_eq!(a.avg_iterations, Some(5.0));
+        assert_eq!(a.feedback_count, 0);
+        assert_eq!(b.runs, 1);
+        assert_eq!(b.avg_feedback, Some(1.0));
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+
+use super::{now, Db};
+
+/// Seconds an incognito conversation outlives its last message.
+pub const INCOGNITO_TTL_SECS: i64 = 3600;
+
+// ============================================================================
+// INCOGNITO CONVERSATIONS
+// ============================================================================
+
+// An incognito conversation has an expires_at; one without is kept. Its
+// messages are stored for the turn loop but never indexed for search, it is
+// left out of conversation lists, and it is deleted once it expires.
+impl Db {
+    pub fn is_incognito(&self, conversation_id: u64) -> Result<bool> {
+        Ok(self.query_row_optional(
+            "SELECT expires_at IS NOT NULL FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, bool>(0),
+        )?.unwrap_or(false))
+    }
+
+    /// Delete expired incognito conversations, with their tasks and messages.
+    /// Returns how many were deleted.
+    pub fn purge_expired_conversations(&self) -> Result<usize> {
+        self.execute(
+            "DELETE FROM conversations WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            rusqlite::params![now()],
+        )
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    #[test]
+    fn incognito_messages_are_not_listed_or_searchable() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let kept = db.create_conversation(device).unwrap();
+        let secret = db.start_conversation(device, None, true).unwrap();
+        assert!(db.is_incognito(secret).unwrap());
+        assert!(!db.is_incognito(kept).unwrap());
+        db.add_message(kept, None, "user", Some("walrus facts"), None).unwrap();
+        db.add_message(secret, None, "user", Some("walrus secrets"), None).unwrap();
+        assert_eq!(db.get_messages(secret).unwrap().len(), 1);
+
+        let hits = db.search_conversations(device, "walrus", 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.conversation_id).collect::<Vec<_>>(), [kept]);
+
+        let listed = db.list_conversations(device, 10, 0).unwrap();
+        assert_eq!(listed.iter().map(|c| c.id).collect::<Vec<_>>(), [kept]);
+    }
+
+    #[test]
+    fn expired_conversations_are_purged() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let kept = db.create_conversation(device).unwrap();
+        let secret = db.start_conversation(device, None, true).unwrap();
+        db.add_message(secret, None, "user", Some("gone soon"), None).unwrap();
+
+        // Still within its TTL
+        assert_eq!(db.purge_expired_conversations().unwrap(), 0);
+
+        db.execute(
+            "UPDATE conversations SET expires_at = 0 WHERE id = ?1",
+            rusqlite::params![secret as i64],
+        ).unwrap();
+        assert_eq!(db.purge_expired_conversations().unwrap(), 1);
+        assert_eq!(db.get_conversation_device(secret).unwrap(), None);
+        assert!(db.get_messages(secret).unwrap().is_empty());
+        assert_eq!(db.get_conversation_device(kept).unwrap(), Some(device));
+
+        // Deleting unindexed messages leaves the search index intact
+        db.execute("INSERT INTO messages_fts(messages_fts) VALUES ('integrity-check')", []).unwrap();
+    }
+}
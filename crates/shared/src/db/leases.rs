@@ -0,0 +1,70 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// Who holds a role, since when, and until when unless renewed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub role: String,
+    pub holder: String,
+    pub acquired: i64,
+    pub expires: i64,
+}
+
+// ============================================================================
+// LEASES
+// ============================================================================
+
+impl Db {
+    /// Take or renew `role` for `ttl_secs`. Succeeds when the role is free,
+    /// expired, or already held by `holder`; returns whether `holder` has it.
+    pub fn try_acquire_lease(&self, role: &str, holder: &str, ttl_secs: i64) -> Result<bool> {
+        let now = now();
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        // Renewal keeps the original acquired time; a takeover resets it
+        tx.execute(
+            "INSERT INTO instance_leases (role, holder, acquired, expires) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (role) DO UPDATE SET
+                 acquired = CASE WHEN holder = excluded.holder THEN acquired ELSE excluded.acquired END,
+                 holder = excluded.holder,
+                 expires = excluded.expires
+             WHERE holder = excluded.holder OR expires < ?3",
+            rusqlite::params![role, holder, now, now + ttl_secs],
+        )?;
+        let current: String = tx.query_row(
+            "SELECT holder FROM instance_leases WHERE role = ?1",
+            rusqlite::params![role],
+            |row| row.get(0),
+        )?;
+
+        tx.commit()?;
+        Ok(current == holder)
+    }
+
+    /// Give up `role` if `holder` has it, so another instance can take over
+    /// without waiting for the lease to expire.
+    pub fn release_lease(&self, role: &str, holder: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM instance_leases WHERE role = ?1 AND holder = ?2",
+            rusqlite::params![role, holder],
+        )?;
+        Ok(())
+    }
+
+    /// The current lease on `role`, expired or not.
+    pub fn get_lease(&self, role: &str) -> Result<Option<Lease>> {
+        self.query_row_optional(
+            "SELECT role, holder, acquired, expires FROM instance_leases WHERE role = ?1",
+            rusqlite::params![role],
+            |row| Ok(Lease {
+                role: row.get(0)?,
+                holder: row.get(1)?,
+                acquired: row.get(2)?,
+                expires: row.get(3)?,
+            }),
+        )
+    }
+}
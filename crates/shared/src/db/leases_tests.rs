@@ -0,0 +1,22 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::Db;
+
+    #[test]
+    fn one_holder_at_a_time_until_the_lease_lapses() {
+        let db = Db::in_memory().unwrap();
+
+        assert!(db.try_acquire_lease("worker", "alpha", 30).unwrap());
+        assert!(!db.try_acquire_lease("worker", "beta", 30).unwrap());
+        assert!(db.try_acquire_lease("worker", "alpha", 30).unwrap());
+
+        // Alpha stops renewing
+        db.execute("UPDATE instance_leases SET expires = expires - 60", []).unwrap();
+        assert!(db.try_acquire_lease("worker", "beta", 30).unwrap());
+        assert!(!db.try_acquire_lease("worker", "alpha", 30).unwrap());
+        assert_eq!(db.get_lease("worker").unwrap().unwrap().holder, "beta");
+
+        db.release_lease("worker", "beta").unwrap();
+        assert!(db.try_acquire_lease("worker", "alpha", 30).unwrap());
+    }
+}
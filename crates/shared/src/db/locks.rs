@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use sha2::{Digest, Sha256};
+
+use super::{now, Db};
+
+/// Seconds a sensitive conversation stays unlocked after the right PIN.
+pub const UNLOCK_SECS: i64 = 900;
+
+/// Shortest and longest PIN, in characters.
+pub const MIN_PIN_CHARS: usize = 4;
+pub const MAX_PIN_CHARS: usize = 64;
+
+/// Wrong PINs in a row a conversation takes before each further try has
+/// to wait.
+pub const FREE_PIN_ATTEMPTS: i64 = 5;
+
+/// The wait after the last free wrong PIN; it doubles with every wrong PIN
+/// after that, up to MAX_PIN_BACKOFF_SECS.
+const PIN_BACKOFF_SECS: i64 = 30;
+const MAX_PIN_BACKOFF_SECS: i64 = 3600;
+
+/// What happened to a lock request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOutcome {
+    Done,
+    WrongPin,
+    /// The conversation is not sensitive, so there is nothing to unlock.
+    NotLocked,
+    /// Too many wrong PINs lately; the PIN was not checked. Seconds until
+    /// the next try.
+    Throttled { retry_in: i64 },
+}
+
+// ============================================================================
+// SENSITIVE CONVERSATIONS
+// ============================================================================
+
+// A sensitive conversation has a lock_hash: an Argon2id hash of the PIN it
+// was locked with. Unless unlocked_until lies in the future it is left out
+// of search, tag lookups, the Archivist's tools and memory saving. Wrong
+// PINs are counted in pin_failures; past FREE_PIN_ATTEMPTS, pin_retry_at
+// holds off the next try.
+impl Db {
+    /// Mark a conversation sensitive with `pin`. Relocking an unlocked
+    /// conversation needs the PIN it was locked with.
+    pub fn lock_conversation(&self, conversation_id: u64, pin: &str) -> Result<LockOutcome> {
+        match self.conversation_lock_hash(conversation_id)? {
+            Some(hash) => {
+                if let Some(refused) = self.check_pin(conversation_id, &hash, pin)? {
+                    return Ok(refused);
+                }
+                self.execute(
+                    "UPDATE conversations SET unlocked_until = NULL WHERE id = ?1",
+                    rusqlite::params![conversation_id as i64],
+                )?;
+                Ok(LockOutcome::Done)
+            }
+            None => {
+                self.execute(
+                    "UPDATE conversations SET lock_hash = ?1, unlocked_until = NULL, pin_failures = 0, pin_retry_at = NULL
+                     WHERE id = ?2",
+                    rusqlite::params![hash_pin(pin)?, conversation_id as i64],
+                )?;
+                Ok(LockOutcome::Done)
+            }
+        }
+    }
+
+    /// Unlock a sensitive conversation for UNLOCK_SECS, or for good when
+    /// `remove` is set.
+    pub fn unlock_conversation(&self, conversation_id: u64, pin: &str, remove: bool) -> Result<LockOutcome> {
+        let Some(hash) = self.conversation_lock_hash(conversation_id)? else {
+            return Ok(LockOutcome::NotLocked);
+        };
+        if let Some(refused) = self.check_pin(conversation_id, &hash, pin)? {
+            return Ok(refused);
+        }
+        if remove {
+            self.execute(
+                "UPDATE conversations SET lock_hash = NULL, unlocked_until = NULL WHERE id = ?1",
+                rusqlite::params![conversation_id as i64],
+            )?;
+        } else {
+            self.execute(
+                "UPDATE conversations SET unlocked_until = ?1 WHERE id = ?2",
+                rusqlite::params![now() + UNLOCK_SECS, conversation_id as i64],
+            )?;
+        }
+        Ok(LockOutcome::Done)
+    }
+
+    /// Whether a conversation is sensitive and not currently unlocked.
+    pub fn is_conversation_locked(&self, conversation_id: u64) -> Result<bool> {
+        Ok(self.query_row_optional(
+            "SELECT lock_hash IS NOT NULL AND COALESCE(unlocked_until, 0) <= ?2
+             FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64, now()],
+            |row| row.get::<_, bool>(0),
+        )?.unwrap_or(false))
+    }
+
+    /// Check `pin` against the conversation's `stored` hash. None when it
+    /// matches; otherwise why it was refused.
+    ///
+    /// The try is counted as wrong before the PIN is checked, so guesses
+    /// sent in parallel can't all get in under the limit; a right PIN then
+    /// clears the count. A hash from before Argon2 is replaced on a match.
+    fn check_pin(&self, conversation_id: u64, stored: &str, pin: &str) -> Result<Option<LockOutcome>> {
+        let throttled = self.transaction(|tx| {
+            let (failures, retry_at): (i64, Option<i64>) = tx.query_row(
+                "SELECT pin_failures, pin_retry_at FROM conversations WHERE id = ?1",
+                rusqlite::params![conversation_id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            let now = now();
+            if let Some(retry_at) = retry_at.filter(|at| *at > now) {
+                return Ok(Some(LockOutcome::Throttled { retry_in: retry_at - now }));
+            }
+            let failures = failures + 1;
+            let retry_at = (failures >= FREE_PIN_ATTEMPTS).then(|| now + pin_backoff(failures));
+            tx.execute(
+                "UPDATE conversations SET pin_failures = ?1, pin_retry_at = ?2 WHERE id = ?3",
+                rusqlite::params![failures, retry_at, conversation_id as i64],
+            )?;
+            Ok(None)
+        })?;
+        if throttled.is_some() {
+            return Ok(throttled);
+        }
+        if !pin_matches(stored, pin) {
+            return Ok(Some(LockOutcome::WrongPin));
+        }
+
+        let rehashed = if is_legacy_hash(stored) { Some(hash_pin(pin)?) } else { None };
+        self.execute(
+            "UPDATE conversations SET pin_failures = 0, pin_retry_at = NULL, lock_hash = COALESCE(?1, lock_hash)
+             WHERE id = ?2",
+            rusqlite::params![rehashed, conversation_id as i64],
+        )?;
+        Ok(None)
+    }
+
+    fn conversation_lock_hash(&self, conversation_id: u64) -> Result<Option<String>> {
+        Ok(self.query_row_optional(
+            "SELECT lock_hash FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, Option<String>>(0),
+        )?.flatten())
+    }
+}
+
+/// How long to wait after the `failures`th wrong PIN in a row.
+fn pin_backoff(failures: i64) -> i64 {
+    let doublings = (failures - FREE_PIN_ATTEMPTS).clamp(0, 16) as u32;
+    (PIN_BACKOFF_SECS << doublings).min(MAX_PIN_BACKOFF_SECS)
+}
+
+/// An Argon2id hash of `pin` with a random salt, as a PHC string.
+fn hash_pin(pin: &str) -> Result<String> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|e| anyhow!("Failed to make a PIN salt: {}", e))?;
+    let hash = Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Failed to hash the PIN: {}", e))?;
+    Ok(hash.to_string())
+}
+
+fn pin_matches(stored: &str, pin: &str) -> bool {
+    if !is_legacy_hash(stored) {
+        return PasswordHash::new(stored)
+            .is_ok_and(|hash| Argon2::default().verify_password(pin.as_bytes(), &hash).is_ok());
+    }
+    stored
+        .split_once(':')
+        .is_some_and(|(salt, hash)| constant_time_eq(digest(salt, pin).as_bytes(), hash.as_bytes()))
+}
+
+/// Hashes from before Argon2: `salt:hex(sha256(salt:pin))`.
+fn is_legacy_hash(stored: &str) -> bool {
+    !stored.starts_with('$')
+}
+
+/// Compares every byte whatever the first difference, so timing doesn't
+/// tell how much of a guess was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn digest(salt: &str, pin: &str) -> String {
+    Sha256::digest(format!("{}:{}", salt, pin).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+    use crate::db::{register_device, Db, LockOutcome, FREE_PIN_ATTEMPTS};
+
+    fn visible(db: &Db, device: u64) -> Vec<i64> {
+        let rows = db.query_sandboxed(device as i64, "SELECT id FROM device_conversations ORDER BY id", []).unwrap();
+        serde_json::from_str::<Vec<serde_json::Value>>(&rows)
+            .unwrap()
+            .iter()
+            .map(|row| row["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn pin_unlocks_and_relocks() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+
+        assert_eq!(db.unlock_conversation(conversation, "1234", false).unwrap(), LockOutcome::NotLocked);
+        assert_eq!(db.lock_conversation(conversation, "1234").unwrap(), LockOutcome::Done);
+        assert!(db.is_conversation_locked(conversation).unwrap());
+
+        assert_eq!(db.unlock_conversation(conversation, "4321", false).unwrap(), LockOutcome::WrongPin);
+        assert_eq!(db.unlock_conversation(conversation, "1234", false).unwrap(), LockOutcome::Done);
+        assert!(!db.is_conversation_locked(conversation).unwrap());
+
+        assert_eq!(db.lock_conversation(conversation, "9999").unwrap(), LockOutcome::WrongPin);
+        assert_eq!(db.lock_conversation(conversation, "1234").unwrap(), LockOutcome::Done);
+        assert!(db.is_conversation_locked(conversation).unwrap());
+
+        assert_eq!(db.unlock_conversation(conversation, "1234", true).unwrap(), LockOutcome::Done);
+        assert!(!db.is_conversation_locked(conversation).unwrap());
+        assert_eq!(db.unlock_conversation(conversation, "1234", false).unwrap(), LockOutcome::NotLocked);
+    }
+
+    #[test]
+    fn locked_conversations_are_hidden_until_unlocked() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let open = db.create_conversation(device).unwrap();
+        let secret = db.create_conversation(device).unwrap();
+        for id in [open, secret] {
+            db.add_message(id, None, "user", Some("walrus plans"), None).unwrap();
+            db.add_conversation_tag(device, id, "plans").unwrap();
+        }
+        db.lock_conversation(secret, "1234").unwrap();
+
+        let search = |db: &Db| -> Vec<u64> {
+            db.search_conversations(device, "walrus", 10).unwrap().iter().map(|h| h.conversation_id).collect()
+        };
+        let tagged = |db: &Db| -> Vec<u64> {
+            db.conversations_with_tag(device, "plans").unwrap().iter().map(|c| c.conversation_id).collect()
+        };
+        assert_eq!(search(&db), [open]);
+        assert_eq!(tagged(&db), [open]);
+        assert_eq!(visible(&db, device), [open as i64]);
+
+        // Still listed for its owner, marked sensitive
+        let listed = db.list_conversations(device, 10, 0).unwrap();
+        assert!(listed.iter().any(|c| c.id == secret && c.sensitive));
+
+        db.unlock_conversation(secret, "1234", false).unwrap();
+        assert_eq!(search(&db).len(), 2);
+        assert_eq!(tagged(&db).len(), 2);
+        assert_eq!(visible(&db, device), [open as i64, secret as i64]);
+    }
+
+    fn lock_hash(db: &Db, conversation: u64) -> String {
+        db.query_row_optional("SELECT lock_hash FROM conversations WHERE id = ?1", [conversation as i64], |r| r.get(0))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn wrong_pins_back_off() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.lock_conversation(conversation, "1234").unwrap();
+        assert!(lock_hash(&db, conversation).starts_with("$argon2id$"));
+
+        for _ in 0..FREE_PIN_ATTEMPTS {
+            assert_eq!(db.unlock_conversation(conversation, "0000", false).unwrap(), LockOutcome::WrongPin);
+        }
+        // Even the right PIN waits now
+        assert!(matches!(
+            db.unlock_conversation(conversation, "1234", false).unwrap(),
+            LockOutcome::Throttled { retry_in } if retry_in > 0 && retry_in <= 30
+        ));
+        assert!(matches!(db.lock_conversation(conversation, "1234").unwrap(), LockOutcome::Throttled { .. }));
+
+        // Once the wait is over, the right PIN clears the count
+        db.execute("UPDATE conversations SET pin_retry_at = 0 WHERE id = ?1", [conversation as i64]).unwrap();
+        assert_eq!(db.unlock_conversation(conversation, "1234", false).unwrap(), LockOutcome::Done);
+        assert_eq!(db.lock_conversation(conversation, "0000").unwrap(), LockOutcome::WrongPin);
+        assert_eq!(db.lock_conversation(conversation, "1234").unwrap(), LockOutcome::Done);
+    }
+
+    #[test]
+    fn old_sha256_hashes_unlock_and_are_upgraded() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        let digest: String = Sha256::digest(b"salt:1234").iter().map(|b| format!("{:02x}", b)).collect();
+        db.execute(
+            "UPDATE conversations SET lock_hash = ?1 WHERE id = ?2",
+            rusqlite::params![format!("salt:{}", digest), conversation as i64],
+        ).unwrap();
+
+        assert_eq!(db.unlock_conversation(conversation, "4321", false).unwrap(), LockOutcome::WrongPin);
+        assert_eq!(db.unlock_conversation(conversation, "1234", false).unwrap(), LockOutcome::Done);
+        assert!(lock_hash(&db, conversation).starts_with("$argon2id$"));
+        assert_eq!(db.lock_conversation(conversation, "1234").unwrap(), LockOutcome::Done);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+
+use super::{now, Db};
+use crate::memory::{Memory, TimelineMessage};
+
+/// Profile conversations and memories fall into when none is chosen.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Longest profile name, in characters.
+pub const MAX_PROFILE_CHARS: usize = 32;
+
+/// Canonical form of a profile name: trimmed and lowercase. None if it is
+/// empty, too long, or has characters other than letters, digits, `-` and `_`.
+pub fn normalize_profile(profile: &str) -> Option<String> {
+    let profile = profile.trim().to_lowercase();
+    let valid = !profile.is_empty()
+        && profile.chars().count() <= MAX_PROFILE_CHARS
+        && profile.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(profile)
+}
+
+// ============================================================================
+// PROFILES
+// ============================================================================
+
+impl Db {
+    /// The profile a conversation belongs to.
+    pub fn get_conversation_profile(&self, conversation_id: u64) -> Result<String> {
+        Ok(self.query_row_optional(
+            "SELECT profile FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, String>(0),
+        )?.unwrap_or_else(|| DEFAULT_PROFILE.to_string()))
+    }
+}
+
+// ============================================================================
+// MEMORIES
+// ============================================================================
+
+// Storage for `memory::MemoryStore`, which validates and ranks; everything
+// else goes through the store rather than these.
+impl Db {
+    /// Insert a memory, or find the identical one already saved. Returns its id.
+    pub(crate) fn insert_memory(&self, device_id: u64, profile: &str, content: &str) -> Result<u64> {
+        let profile = checked_profile(profile)?;
+        self.transaction(|tx| {
+            tx.prepare_cached(
+                "INSERT OR IGNORE INTO memories (device_id, profile, content, created)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?.execute(rusqlite::params![device_id as i64, profile, content, now()])?;
+            let id: i64 = tx.prepare_cached(
+                "SELECT id FROM memories WHERE device_id = ?1 AND profile = ?2 AND content = ?3",
+            )?.query_row(rusqlite::params![device_id as i64, profile, content], |row| row.get(0))?;
+            Ok(id as u64)
+        })
+    }
+
+    pub(crate) fn delete_memory(&self, device_id: u64, profile: &str, memory_id: u64) -> Result<bool> {
+        let profile = checked_profile(profile)?;
+        let removed = self.execute(
+            "DELETE FROM memories WHERE id = ?1 AND device_id = ?2 AND profile = ?3",
+            rusqlite::params![memory_id as i64, device_id as i64, profile],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Every memory in one of the device's profiles, oldest first.
+    pub(crate) fn get_memories(&self, device_id: u64, profile: &str) -> Result<Vec<Memory>> {
+        let profile = checked_profile(profile)?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, content, created FROM memories
+             WHERE device_id = ?1 AND profile = ?2
+             ORDER BY created, id",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![device_id as i64, profile],
+            |row| Ok(Memory {
+                id: row.get::<_, i64>(0)? as u64,
+                content: row.get(1)?,
+                created: row.get(2)?,
+            }),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The device's messages since `since` in conversations of one profile,
+    /// oldest first. Locked conversations and incognito messages are left out.
+    pub(crate) fn timeline_messages(&self, device_id: u64, profile: &str, since: i64) -> Result<Vec<TimelineMessage>> {
+        let profile = checked_profile(profile)?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT c.id, c.title, m.created, m.message
+             FROM messages m
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE c.device_id = ?1 AND c.profile = ?2 AND c.lock_hash IS NULL
+               AND m.role = 'user' AND m.created >= ?3 AND m.unindexed = 0
+               AND m.deleted_at IS NULL AND m.message IS NOT NULL
+             ORDER BY m.created, m.id",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![device_id as i64, profile, since],
+            |row| Ok(TimelineMessage {
+                conversation_id: row.get::<_, i64>(0)? as u64,
+                title: row.get(1)?,
+                created: row.get(2)?,
+                text: row.get(3)?,
+            }),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+pub(super) fn checked_profile(profile: &str) -> Result<String> {
+    normalize_profile(profile).ok_or_else(|| anyhow::anyhow!(
+        "Invalid profile '{}': use up to {} letters, digits, '-' or '_'", profile, MAX_PROFILE_CHARS
+    ))
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use rusqlite::{OptionalExtension, Transaction};
+
+use super::{now, Db, PrivacyLevel};
+
+/// What happened to a merge request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeOutcome {
+    Done,
+    /// One conversation is incognito or PIN-locked and the other isn't, or
+    /// they are locked with different PINs. Merging would keep messages
+    /// longer, or guard them less, than they were.
+    Mismatched,
+}
+
+/// What happened to a split request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitOutcome {
+    /// The new conversation holding the message and everything after it.
+    Done(u64),
+    NoSuchMessage,
+    /// Splitting at the first message would move the whole conversation.
+    FirstMessage,
+}
+
+/// Messages from the first three user turns are what a merged or split
+/// conversation's title is generated from.
+const TITLE_SOURCE_TURNS: usize = 3;
+
+// ============================================================================
+// MERGE AND SPLIT
+// ============================================================================
+
+impl Db {
+    /// Move every message of `source_id` into `target_id` and delete
+    /// `source_id`. Exchanges (a user message and everything answering it)
+    /// are interleaved by when they started, and never broken up. Tasks and
+    /// tags move along and scratchpads are joined. Checkpoints of both are
+    /// dropped, since they no longer describe a point in either history.
+    /// The target keeps its title, profile and language, and takes the
+    /// stricter of the two privacy levels. A toolbelt either turned off stays
+    /// off; one only the source turned on stays on.
+    pub fn merge_conversations(&self, target_id: u64, source_id: u64) -> Result<MergeOutcome> {
+        let (target, source) = (target_id as i64, source_id as i64);
+        let now = now();
+        self.transaction(|tx| {
+            let protection = |id: i64| -> rusqlite::Result<(Option<String>, bool)> {
+                tx.query_row(
+                    "SELECT lock_hash, expires_at IS NOT NULL FROM conversations WHERE id = ?1",
+                    rusqlite::params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+            };
+            if protection(target)? != protection(source)? {
+                return Ok(MergeOutcome::Mismatched);
+            }
+            let privacy = |id: i64| -> rusqlite::Result<PrivacyLevel> {
+                let level: String = tx.query_row(
+                    "SELECT privacy FROM conversations WHERE id = ?1",
+                    rusqlite::params![id],
+                    |row| row.get(0),
+                )?;
+                Ok(PrivacyLevel::parse(&level).unwrap_or_default())
+            };
+            let privacy = privacy(target)?.max(privacy(source)?);
+
+            let merged = interleave(exchanges(tx, target_id)?, exchanges(tx, source_id)?);
+            let mut update = tx.prepare_cached(
+                "UPDATE messages SET conversation_id = ?1, m_order = ?2 WHERE id = ?3",
+            )?;
+            for (order, id) in merged.into_iter().flatten().map(|(_, id)| id).enumerate() {
+                update.execute(rusqlite::params![target, order as i64, id])?;
+            }
+
+            for table in ["tasks", "experiment_outcomes"] {
+                tx.execute(
+                    &format!("UPDATE {} SET conversation_id = ?1 WHERE conversation_id = ?2", table),
+                    rusqlite::params![target, source],
+                )?;
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag_id, created)
+                 SELECT ?1, tag_id, created FROM conversation_tags WHERE conversation_id = ?2",
+                rusqlite::params![target, source],
+            )?;
+            tx.execute(
+                "INSERT INTO scratchpads (conversation_id, content, updated)
+                 SELECT ?1, content, ?3 FROM scratchpads WHERE conversation_id = ?2 AND content != ''
+                 ON CONFLICT(conversation_id) DO UPDATE SET
+                     content = CASE WHEN content = '' THEN excluded.content
+                                    ELSE content || char(10) || char(10) || excluded.content END,
+                     updated = excluded.updated",
+                rusqlite::params![target, source, now],
+            )?;
+            tx.execute(
+                "INSERT INTO conversation_tools (conversation_id, toolbelt, enabled, updated)
+                 SELECT ?1, toolbelt, enabled, updated FROM conversation_tools WHERE conversation_id = ?2
+                 ON CONFLICT(conversation_id, toolbelt) DO UPDATE SET
+                     enabled = MIN(enabled, excluded.enabled),
+                     updated = MAX(updated, excluded.updated)",
+                rusqlite::params![target, source],
+            )?;
+            tx.execute(
+                "DELETE FROM conversation_checkpoints WHERE conversation_id IN (?1, ?2)",
+                rusqlite::params![target, source],
+            )?;
+            tx.execute(
+                "UPDATE conversations SET privacy = ?3, last_accessed = MAX(last_accessed,
+                     (SELECT last_accessed FROM conversations WHERE id = ?2))
+                 WHERE id = ?1",
+                rusqlite::params![target, source, privacy.as_str()],
+            )?;
+            tx.execute("DELETE FROM conversations WHERE id = ?1", rusqlite::params![source])?;
+            Ok(MergeOutcome::Done)
+        })
+    }
+
+    /// Move `message_id` and every message after it into a new conversation
+    /// for `device_id`, which keeps the original's profile, language, PIN
+    /// lock, privacy level, toolbelt choices and incognito status. Tasks
+    /// whose messages all moved go with
+    /// them, tags are copied, and checkpoints taken after the split point are
+    /// dropped. The new conversation starts with the original's title,
+    /// numbered.
+    pub fn split_conversation(&self, device_id: u64, conversation_id: u64, message_id: u64) -> Result<SplitOutcome> {
+        let now = now();
+        let split = self.transaction(|tx| {
+            let at: Option<i64> = tx.query_row(
+                "SELECT m_order FROM messages WHERE id = ?1 AND conversation_id = ?2",
+                rusqlite::params![message_id as i64, conversation_id as i64],
+                |row| row.get(0),
+            ).optional()?;
+            let Some(at) = at else {
+                return Ok(None);
+            };
+            let before: i64 = tx.query_row(
+                "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1 AND m_order < ?2",
+                rusqlite::params![conversation_id as i64, at],
+                |row| row.get(0),
+            )?;
+            if before == 0 {
+                return Ok(Some(SplitOutcome::FirstMessage));
+            }
+
+            tx.execute(
+                "INSERT INTO conversations
+                 (device_id, created, last_accessed, profile, language, lock_hash, privacy, expires_at)
+                 SELECT ?1, ?2, ?2, profile, language, lock_hash, privacy, expires_at
+                 FROM conversations WHERE id = ?3",
+                rusqlite::params![device_id as i64, now, conversation_id as i64],
+            )?;
+            let split = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT INTO conversation_tools (conversation_id, toolbelt, enabled, updated)
+                 SELECT ?1, toolbelt, enabled, updated FROM conversation_tools WHERE conversation_id = ?2",
+                rusqlite::params![split, conversation_id as i64],
+            )?;
+
+            tx.execute(
+                "UPDATE messages SET conversation_id = ?1, m_order = m_order - ?3
+                 WHERE conversation_id = ?2 AND m_order >= ?3",
+                rusqlite::params![split, conversation_id as i64, at],
+            )?;
+            tx.execute(
+                "UPDATE tasks SET conversation_id = ?1
+                 WHERE conversation_id = ?2
+                   AND id IN (SELECT task_id FROM messages WHERE conversation_id = ?1 AND task_id IS NOT NULL)
+                   AND id NOT IN (SELECT task_id FROM messages WHERE conversation_id = ?2 AND task_id IS NOT NULL)",
+                rusqlite::params![split, conversation_id as i64],
+            )?;
+            tx.execute(
+                "INSERT INTO conversation_tags (conversation_id, tag_id, created)
+                 SELECT ?1, tag_id, ?3 FROM conversation_tags WHERE conversation_id = ?2",
+                rusqlite::params![split, conversation_id as i64, now],
+            )?;
+            tx.execute(
+                "DELETE FROM conversation_checkpoints WHERE conversation_id = ?1 AND last_order >= ?2",
+                rusqlite::params![conversation_id as i64, at],
+            )?;
+            Ok(Some(SplitOutcome::Done(split as u64)))
+        })?;
+
+        let Some(outcome) = split else {
+            return Ok(SplitOutcome::NoSuchMessage);
+        };
+        if let SplitOutcome::Done(split) = outcome
+            && let Some(title) = self.get_conversation_title(conversation_id)?
+        {
+            // Titles are unique per device, so the split gets a numbered one
+            let _ = self.set_conversation_title(split, device_id as i64, &title);
+        }
+        Ok(outcome)
+    }
+
+    /// What to generate a new title for a merged or split conversation
+    /// from: its first few user messages. None for one with no user messages.
+    pub fn title_source(&self, conversation_id: u64) -> Result<Option<String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT message FROM messages
+             WHERE conversation_id = ?1 AND role = 'user' AND deleted_at IS NULL AND message IS NOT NULL
+             ORDER BY m_order LIMIT ?2",
+        )?;
+        let turns = stmt.query_map(
+            rusqlite::params![conversation_id as i64, TITLE_SOURCE_TURNS as i64],
+            |row| row.get::<_, String>(0),
+        )?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok((!turns.is_empty()).then(|| turns.join("\n\n")))
+    }
+}
+
+/// Messages as (created, id) from one user message up to the next.
+type Exchange = Vec<(i64, i64)>;
+
+/// A conversation's messages in order, grouped into exchanges.
+fn exchanges(tx: &Transaction, conversation_id: u64) -> Result<Vec<Exchange>> {
+    let mut stmt = tx.prepare_cached(
+        "SELECT id, role, created FROM messages WHERE conversation_id = ?1 ORDER BY m_order",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![conversation_id as i64], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+    })?;
+
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    for row in rows {
+        let (id, role, created) = row?;
+        match exchanges.last_mut() {
+            Some(exchange) if role != "user" => exchange.push((created, id)),
+            _ => exchanges.push(vec![(created, id)]),
+        }
+    }
+    Ok(exchanges)
+}
+
+/// Merge two ordered lists of exchanges by when each started, taking the
+/// first list's on a tie.
+fn interleave(first: Vec<Exchange>, second: Vec<Exchange>) -> Vec<Exchange> {
+    let mut merged = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter().peekable();
+    let mut second = second.into_iter().peekable();
+    loop {
+        let take_first = match (first.peek(), second.peek()) {
+            (Some(a), Some(b)) => a[0].0 <= b[0].0,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        merged.extend(if take_first { first.next() } else { second.next() });
+    }
+    merged
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, MergeOutcome, SplitOutcome};
+
+    fn contents(db: &Db, conversation: u64) -> Vec<String> {
+        db.get_messages(conversation).unwrap()
+            .into_iter()
+            .filter_map(|m| m.content)
+            .collect()
+    }
+
+    /// Add a message as if it was sent at `created`.
+    fn say(db: &Db, conversation: u64, role: &str, text: &str, created: i64) {
+        db.add_message(conversation, None, role, Some(text), None).unwrap();
+        db.execute(
+            "UPDATE messages SET created = ?1 WHERE message = ?2",
+            rusqlite::params![created, text],
+        ).unwrap();
+    }
+
+    fn message_id(db: &Db, text: &str) -> u64 {
+        db.query_row_optional(
+            "SELECT id FROM messages WHERE message = ?1",
+            rusqlite::params![text],
+            |row| row.get::<_, i64>(0),
+        ).unwrap().unwrap() as u64
+    }
+
+    #[test]
+    fn merging_interleaves_whole_exchanges() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let trip = db.create_conversation(device).unwrap();
+        let hotel = db.create_conversation(device).unwrap();
+
+        say(&db, trip, "user", "plan a trip to Lisbon", 100);
+        say(&db, trip, "assistant", "when?", 101);
+        say(&db, hotel, "user", "find a hotel", 150);
+        // Answered after the trip's next question was asked, but stays with its exchange
+        say(&db, hotel, "assistant", "near the river?", 250);
+        say(&db, trip, "user", "in May", 200);
+        say(&db, trip, "assistant", "May it is", 201);
+        db.add_conversation_tag(device, hotel, "travel").unwrap();
+        db.write_scratchpad(trip, "dates: May", false).unwrap();
+        db.write_scratchpad(hotel, "budget: 120/night", false).unwrap();
+        db.create_checkpoint(trip, None).unwrap();
+
+        assert_eq!(db.merge_conversations(trip, hotel).unwrap(), MergeOutcome::Done);
+
+        assert_eq!(contents(&db, trip), [
+            "plan a trip to Lisbon", "when?",
+            "find a hotel", "near the river?",
+            "in May", "May it is",
+        ]);
+        assert_eq!(db.get_conversation_device(hotel).unwrap(), None);
+        assert_eq!(db.conversation_tags(device, trip).unwrap(), ["travel"]);
+        assert_eq!(db.get_scratchpad(trip).unwrap(), "dates: May\n\nbudget: 120/night");
+        assert!(db.list_checkpoints(trip).unwrap().is_empty());
+
+        // New messages still go after everything merged in
+        db.add_message(trip, None, "user", Some("book it"), None).unwrap();
+        assert_eq!(contents(&db, trip).last().map(String::as_str), Some("book it"));
+    }
+
+    #[test]
+    fn merging_keeps_protection_levels_apart() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let kept = db.create_conversation(device).unwrap();
+        let incognito = db.start_conversation(device, None, true).unwrap();
+        let locked = db.create_conversation(device).unwrap();
+        db.lock_conversation(locked, "2468").unwrap();
+        say(&db, incognito, "user", "off the record", 10);
+
+        assert_eq!(db.merge_conversations(kept, incognito).unwrap(), MergeOutcome::Mismatched);
+        assert_eq!(db.merge_conversations(locked, kept).unwrap(), MergeOutcome::Mismatched);
+        assert_eq!(contents(&db, incognito), ["off the record"]);
+        assert!(db.get_conversation_device(kept).unwrap().is_some());
+    }
+
+    #[test]
+    fn splitting_moves_the_rest_into_a_new_conversation() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.set_conversation_title(conversation, device as i64, "Groceries").unwrap();
+        db.add_conversation_tag(device, conversation, "home").unwrap();
+        say(&db, conversation, "user", "what do we need", 1);
+        say(&db, conversation, "assistant", "milk and eggs", 2);
+        db.create_checkpoint(conversation, Some("list")).unwrap();
+        say(&db, conversation, "user", "also, fix the bike", 3);
+        say(&db, conversation, "assistant", "flat tyre?", 4);
+        db.create_checkpoint(conversation, Some("bike")).unwrap();
+
+        let at = message_id(&db, "also, fix the bike");
+        let SplitOutcome::Done(split) = db.split_conversation(device, conversation, at).unwrap() else {
+            panic!("split failed");
+        };
+
+        assert_eq!(contents(&db, conversation), ["what do we need", "milk and eggs"]);
+        assert_eq!(contents(&db, split), ["also, fix the bike", "flat tyre?"]);
+        assert_eq!(db.get_conversation_title(split).unwrap().as_deref(), Some("Groceries_1"));
+        assert_eq!(db.conversation_tags(device, split).unwrap(), ["home"]);
+        let checkpoints = db.list_checkpoints(conversation).unwrap();
+        assert_eq!(checkpoints.iter().map(|c| c.name.as_deref()).collect::<Vec<_>>(), [Some("list")]);
+        assert_eq!(db.title_source(split).unwrap().as_deref(), Some("also, fix the bike"));
+
+        db.add_message(split, None, "user", Some("front wheel"), None).unwrap();
+        assert_eq!(contents(&db, split).last().map(String::as_str), Some("front wheel"));
+    }
+
+    #[test]
+    fn splitting_needs_a_later_message_of_the_conversation() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let first = db.create_conversation(device).unwrap();
+        let second = db.create_conversation(device).unwrap();
+        say(&db, first, "user", "hello", 1);
+        say(&db, first, "assistant", "hi", 2);
+
+        let hello = message_id(&db, "hello");
+        let hi = message_id(&db, "hi");
+        assert_eq!(db.split_conversation(device, first, hello).unwrap(), SplitOutcome::FirstMessage);
+        assert_eq!(db.split_conversation(device, second, hi).unwrap(), SplitOutcome::NoSuchMessage);
+        assert_eq!(contents(&db, first), ["hello", "hi"]);
+    }
+}
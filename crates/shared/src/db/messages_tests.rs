@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::{register_device, Db};
+
+    fn orders(db: &Db, conversation_id: u64) -> Vec<i64> {
+        let conn = db.reader().unwrap();
+        let mut stmt = conn.prepare("SELECT m_order FROM messages WHERE conversation_id = ?1 ORDER BY m_order").unwrap();
+        stmt.query_map(rusqlite::params![conversation_id as i64], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn concurrent_writers_get_distinct_orders() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        let other = db.create_conversation(device).unwrap();
+        db.add_message(other, None, "user", Some("elsewhere"), None).unwrap();
+
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for i in 0..10 {
+                        let text = format!("writer {} message {}", writer, i);
+                        db.add_message(conversation, None, "user", Some(&text), None).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(orders(&db, conversation), (0..40).collect::<Vec<i64>>());
+        assert_eq!(orders(&db, other), [0]);
+        assert_eq!(db.get_message_count(conversation).unwrap(), 40);
+    }
+
+    #[test]
+    fn abandoning_closes_only_the_conversations_open_tasks() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        let other = db.create_conversation(device).unwrap();
+        let root = db.create_task(device, conversation, None, "research").unwrap();
+        db.create_task(device, conversation, Some(root), "search").unwrap();
+        let done = db.create_task(device, conversation, None, "earlier").unwrap();
+        db.complete_task(done as i64).unwrap();
+        db.create_task(device, other, None, "elsewhere").unwrap();
+
+        assert_eq!(db.abandon_open_tasks(conversation).unwrap(), 2);
+        assert_eq!(db.abandon_open_tasks(conversation).unwrap(), 0);
+        assert_eq!(db.abandon_open_tasks(other).unwrap(), 1);
+    }
+}
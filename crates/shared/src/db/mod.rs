@@ -1,16 +1,162 @@
 mod schema;
-
+mod sandbox;
+mod prompts;
+mod experiments;
+mod attachments;
+mod tags;
+mod memories;
+mod artifacts;
+mod usage;
+mod result_cache;
+mod leases;
+mod prompt_snapshots;
+mod scratchpads;
+mod device_groups;
+mod incognito;
+mod locks;
+mod tool_switches;
+mod confidence;
+mod redaction;
+mod purge;
+mod checkpoints;
+mod workflows;
+mod watches;
+mod workspace_files;
+mod response_style;
+mod merge_split;
+mod privacy;
+mod provider_health;
+mod conversation_tools;
+mod read_state;
+mod analytics;
+mod embeddings;
+
+#[cfg(test)]
+mod sandbox_tests;
+#[cfg(test)]
+mod prompts_tests;
+#[cfg(test)]
+mod experiments_tests;
+#[cfg(test)]
+mod attachments_tests;
+#[cfg(test)]
+mod tags_tests;
+#[cfg(test)]
+mod artifacts_tests;
+#[cfg(test)]
+mod usage_tests;
+#[cfg(test)]
+mod result_cache_tests;
+#[cfg(test)]
+mod leases_tests;
+#[cfg(test)]
+mod prompt_snapshots_tests;
+#[cfg(test)]
+mod scratchpads_tests;
+#[cfg(test)]
+mod device_groups_tests;
+#[cfg(test)]
+mod incognito_tests;
+#[cfg(test)]
+mod locks_tests;
+#[cfg(test)]
+mod tool_switches_tests;
+#[cfg(test)]
+mod messages_tests;
+#[cfg(test)]
+mod search_tests;
+#[cfg(test)]
+mod transactions_tests;
+#[cfg(test)]
+mod statement_cache_tests;
+#[cfg(test)]
+mod confidence_tests;
+#[cfg(test)]
+mod redaction_tests;
+#[cfg(test)]
+mod purge_tests;
+#[cfg(test)]
+mod checkpoints_tests;
+#[cfg(test)]
+mod workflows_tests;
+#[cfg(test)]
+mod watches_tests;
+#[cfg(test)]
+mod workspace_files_tests;
+#[cfg(test)]
+mod response_style_tests;
+#[cfg(test)]
+mod merge_split_tests;
+#[cfg(test)]
+mod privacy_tests;
+#[cfg(test)]
+mod provider_health_tests;
+#[cfg(test)]
+mod conversation_tools_tests;
+#[cfg(test)]
+mod read_state_tests;
+#[cfg(test)]
+mod analytics_tests;
+#[cfg(test)]
+mod embeddings_tests;
+
+pub use sandbox::{SANDBOX_VIEWS, MAX_SANDBOX_ROWS};
+pub use prompts::PromptOverride;
+pub use experiments::{ExperimentReport, PromptExperiment, PromptVariant, VariantStats};
+pub use attachments::Attachment;
+pub use artifacts::Artifact;
+pub use usage::{Quota, QuotaCheck, ToolUsage};
+pub use leases::Lease;
+pub use prompt_snapshots::PromptSnapshot;
+pub use scratchpads::MAX_SCRATCHPAD_CHARS;
+pub use device_groups::{normalize_group, DeviceGroup, OwnedDevice, TargetDevice, MAX_GROUP_CHARS};
+pub use incognito::INCOGNITO_TTL_SECS;
+pub use locks::{LockOutcome, FREE_PIN_ATTEMPTS, MAX_PIN_CHARS, MIN_PIN_CHARS, UNLOCK_SECS};
+pub use tool_switches::{ToolSwitch, ALL_TOOLS};
+pub use confidence::{Confidence, ConfidenceLevel};
+pub use redaction::{RedactOutcome, REDACTED};
+pub use purge::PurgeReport;
+pub use checkpoints::{Checkpoint, MAX_CHECKPOINT_NAME_CHARS};
+pub use workflows::{normalize_workflow_name, workflow_parameters, Workflow, MAX_WORKFLOW_CHARS, MAX_WORKFLOW_NAME_CHARS};
+pub use watches::{watch_pattern_matches, PathWatch, MAX_WATCHES, MAX_WATCH_TASK_CHARS, MAX_WATCH_TRIGGER_PATHS};
+pub use workspace_files::{IndexHit, IndexedFile};
+pub use response_style::{ResponseLength, ResponseStyle};
+pub use merge_split::{MergeOutcome, SplitOutcome};
+pub use privacy::PrivacyLevel;
+pub use provider_health::ProviderHealth;
+pub use conversation_tools::ToolChoice;
+pub use read_state::UnreadConversation;
+pub use analytics::{ConversationCounts, NameCount, TaskUsage};
+pub use embeddings::{EmbeddedText, EmbeddingSource, PendingEmbedding, MAX_EMBED_FAILURES};
+pub use result_cache::{normalize_directions, CachedResult, MAX_CACHE_AGE_SECS};
+pub use tags::{normalize_tag, TagCount, TaggedConversation, MAX_TAG_CHARS};
+pub use memories::{normalize_profile, DEFAULT_PROFILE, MAX_PROFILE_CHARS};
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags};
 use anyhow::Result;
 use serde_json::Value;
 use once_cell::sync::OnceCell;
 
 use crate::{Message, ToolCall};
 
+/// Number of read-only connections opened alongside the writer.
+pub const READER_POOL_SIZE: usize = 4;
+
+/// Prepared statements each connection keeps for reuse. Enough to hold
+/// every query on the chat and worker hot paths at once.
+pub const STATEMENT_CACHE_SIZE: usize = 64;
+
+/// Database handle: one writer connection for everything that mutates, plus a
+/// pool of read-only connections for long reads (Archivist queries, search).
+/// Under WAL, readers never block the writer and vice versa.
 #[derive(Clone, Debug)]
 pub struct Db {
     conn: Arc<Mutex<Connection>>,
+    readers: Arc<Vec<Mutex<Connection>>>,
+    next_reader: Arc<AtomicUsize>,
 }
 
 impl Default for Db {
@@ -19,74 +165,145 @@ impl Default for Db {
             .expect("Could not get current directory")
             .join("memory.db");
 
+        Self::open(&db_path).expect("Failed to open database")
+    }
+}
+
+impl Db {
+    pub fn open(db_path: &Path) -> Result<Self> {
         if let Some(parent) = db_path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
 
-        let conn = Connection::open(&db_path).expect("Failed to open database");
+        let conn = Connection::open(db_path)?;
 
-        conn.busy_timeout(std::time::Duration::from_secs(5))
-            .expect("Failed to set busy timeout");
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_SIZE);
         conn.execute_batch("
             PRAGMA foreign_keys = ON;
             PRAGMA journal_mode = WAL;
             PRAGMA synchronous = NORMAL;
-        ").expect("Failed to set pragmas");
+        ")?;
+
+        schema::create_tables(&conn)?;
+
+        let readers = (0..READER_POOL_SIZE)
+            .map(|_| {
+                let reader = Connection::open_with_flags(
+                    db_path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )?;
+                reader.busy_timeout(std::time::Duration::from_secs(5))?;
+                reader.set_prepared_statement_cache_capacity(STATEMENT_CACHE_SIZE);
+                Ok(Mutex::new(reader))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        schema::create_tables(&conn).expect("Failed to create tables");
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            readers: Arc::new(readers),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Problems `PRAGMA integrity_check` reports; empty when the file is sound.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let problems = rows
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|line| line != "ok")
+            .collect();
+        Ok(problems)
+    }
 
-        Self {
+    /// A throwaway database with the full schema, for tests.
+    /// Has no reader pool; reads go through the writer.
+    pub fn in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_SIZE);
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        schema::create_tables(&conn)?;
+        Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
-        }
+            readers: Arc::new(Vec::new()),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+        })
     }
 }
 
+/// How a device relates to a conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationAccess {
+    NotFound,
+    Owner,
+    Shared,
+    Denied,
+}
+
 // ============================================================================
 // CORE DB PRIMITIVES
 // ============================================================================
 
 impl Db {
+    /// The writer connection. Use for anything that mutates.
     pub fn lock(&self) -> Result<MutexGuard<'_, Connection>> {
         self.conn.lock().map_err(|e| anyhow::anyhow!("Lock error: {}", e))
     }
 
-    /// Run a SELECT and return results as a JSON string.
+    /// A read-only connection from the pool: the first idle one, otherwise
+    /// round-robin. Falls back to the writer when there is no pool.
+    pub fn reader(&self) -> Result<MutexGuard<'_, Connection>> {
+        if self.readers.is_empty() {
+            return self.lock();
+        }
+
+        if let Some(idle) = self.readers.iter().find_map(|r| r.try_lock().ok()) {
+            return Ok(idle);
+        }
+
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[index].lock().map_err(|e| anyhow::anyhow!("Lock error: {}", e))
+    }
+
+    /// Run a SELECT on a reader and return results as a JSON string.
     /// Useful for passing query results to the LLM or tool responses.
     pub fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<String> {
-        let conn = self.lock()?;
-        let mut stmt = conn.prepare(sql)?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(sql)?;
         let column_names: Vec<String> = stmt.column_names()
             .iter()
             .map(|s| s.to_string())
             .collect();
 
         let rows: Vec<Value> = stmt
-            .query_map(params, |row| {
-                let mut map = serde_json::Map::new();
-                for (i, name) in column_names.iter().enumerate() {
-                    let val: rusqlite::types::Value = row.get(i)?;
-                    let json_val = match val {
-                        rusqlite::types::Value::Null => Value::Null,
-                        rusqlite::types::Value::Integer(n) => serde_json::json!(n),
-                        rusqlite::types::Value::Real(f) => serde_json::json!(f),
-                        rusqlite::types::Value::Text(s) => serde_json::json!(s),
-                        rusqlite::types::Value::Blob(b) => {
-                            serde_json::json!(format!("<blob:{} bytes>", b.len()))
-                        }
-                    };
-                    map.insert(name.clone(), json_val);
-                }
-                Ok(Value::Object(map))
-            })?
+            .query_map(params, |row| row_to_json(row, &column_names))?
             .filter_map(|r| r.ok())
             .collect();
 
         Ok(serde_json::json!(rows).to_string())
     }
 
+    /// Run `f` in one transaction on the writer. Everything it writes is
+    /// committed together, or rolled back if it returns an error.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&rusqlite::Transaction) -> Result<T>,
+    {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Run one statement on the writer. The helpers below all reuse
+    /// prepared statements, so a hot query is only compiled once per connection.
     pub fn execute(&self, sql: &str, params: impl rusqlite::Params) -> Result<usize> {
         let conn = self.lock()?;
-        Ok(conn.execute(sql, params)?)
+        Ok(conn.prepare_cached(sql)?.execute(params)?)
     }
 
     pub fn query_row_optional<T, F>(
@@ -99,7 +316,7 @@ impl Db {
         F: FnOnce(&rusqlite::Row) -> rusqlite::Result<T>,
     {
         let conn = self.lock()?;
-        match conn.query_row(sql, params, f) {
+        match conn.prepare_cached(sql)?.query_row(params, f) {
             Ok(val) => Ok(Some(val)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
@@ -111,43 +328,225 @@ impl Db {
 // CONVERSATIONS
 // ============================================================================
 
+/// A conversation as listed for browsing.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConversationSummary {
+    pub id: u64,
+    pub title: Option<String>,
+    pub profile: String,
+    pub created: i64,
+    pub last_accessed: i64,
+    /// Locked with a PIN; see /conversations/lock.
+    pub sensitive: bool,
+}
+
 impl Db {
     /// Create a new conversation for a device. Returns the new conversation_id.
     pub fn create_conversation(&self, device_id: u64) -> Result<u64> {
-        let conn = self.lock()?;
+        self.start_conversation(device_id, None, false)
+    }
+
+    /// Create a conversation already in `profile` and, if asked, incognito.
+    /// Set up in one transaction, so it never exists half-configured.
+    pub fn start_conversation(&self, device_id: u64, profile: Option<&str>, incognito: bool) -> Result<u64> {
+        let profile = profile.map(memories::checked_profile).transpose()?;
         let now = now();
 
-        let device_exists: bool = conn.query_row(
-            "SELECT 1 FROM devices WHERE id = ?1",
-            rusqlite::params![device_id],
-            |_| Ok(true),
-        ).unwrap_or(false);
-
-        if !device_exists {
-            return Err(anyhow::anyhow!(
-                "Device {} does not exist. Register the device before creating conversations.",
-                device_id
-            ));
+        self.transaction(|tx| {
+            let device_exists: bool = tx.query_row(
+                "SELECT 1 FROM devices WHERE id = ?1",
+                rusqlite::params![device_id],
+                |_| Ok(true),
+            ).unwrap_or(false);
+
+            if !device_exists {
+                return Err(anyhow::anyhow!(
+                    "Device {} does not exist. Register the device before creating conversations.",
+                    device_id
+                ));
+            }
+
+            tx.execute(
+                "INSERT INTO conversations (device_id, created, last_accessed)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![device_id, now, now],
+            )?;
+            let id = tx.last_insert_rowid();
+
+            if let Some(profile) = &profile {
+                tx.execute(
+                    "UPDATE conversations SET profile = ?1 WHERE id = ?2",
+                    rusqlite::params![profile, id],
+                )?;
+            }
+            if incognito {
+                tx.execute(
+                    "UPDATE conversations SET expires_at = ?1 WHERE id = ?2",
+                    rusqlite::params![now + INCOGNITO_TTL_SECS, id],
+                )?;
+            }
+            Ok(id as u64)
+        })
+    }
+
+    /// A device's own conversations, most recently used first.
+    pub fn list_conversations(&self, device_id: u64, limit: usize, offset: usize) -> Result<Vec<ConversationSummary>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, title, profile, created, last_accessed, lock_hash IS NOT NULL
+             FROM conversations
+             WHERE device_id = ?1 AND expires_at IS NULL
+             ORDER BY last_accessed DESC, id DESC
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![device_id as i64, limit as i64, offset as i64],
+            |row| Ok(ConversationSummary {
+                id: row.get::<_, i64>(0)? as u64,
+                title: row.get(1)?,
+                profile: row.get(2)?,
+                created: row.get(3)?,
+                last_accessed: row.get(4)?,
+                sensitive: row.get(5)?,
+            }),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Touch last_accessed on a conversation, pushing back its expiry if it is incognito.
+    pub fn touch_conversation(&self, conversation_id: u64) -> Result<()> {
+        self.execute(TOUCH_CONVERSATION, rusqlite::params![now(), conversation_id as i64, INCOGNITO_TTL_SECS])?;
+        Ok(())
+    }
+
+    /// The device that owns a conversation, or None if the conversation doesn't exist.
+    pub fn get_conversation_device(&self, conversation_id: u64) -> Result<Option<u64>> {
+        self.query_row_optional(
+            "SELECT device_id FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, i64>(0).map(|id| id as u64),
+        )
+    }
+
+    /// Whether `device_id` owns, shares, or has no access to a conversation.
+    pub fn conversation_access(&self, conversation_id: u64, device_id: u64) -> Result<ConversationAccess> {
+        let Some(owner) = self.get_conversation_device(conversation_id)? else {
+            return Ok(ConversationAccess::NotFound);
+        };
+        if owner == device_id {
+            return Ok(ConversationAccess::Owner);
         }
 
-        conn.execute(
-            "INSERT INTO conversations (device_id, created, last_accessed)
+        let shared = self.query_row_optional(
+            "SELECT 1 FROM conversation_shares WHERE conversation_id = ?1 AND device_id = ?2",
+            rusqlite::params![conversation_id as i64, device_id as i64],
+            |_| Ok(()),
+        )?;
+
+        Ok(if shared.is_some() { ConversationAccess::Shared } else { ConversationAccess::Denied })
+    }
+
+    /// Give another device access to a conversation. Sharing twice is a no-op.
+    pub fn share_conversation(&self, conversation_id: u64, device_id: u64) -> Result<()> {
+        self.execute(
+            "INSERT OR IGNORE INTO conversation_shares (conversation_id, device_id, created)
              VALUES (?1, ?2, ?3)",
-            rusqlite::params![device_id, now, now],
+            rusqlite::params![conversation_id as i64, device_id as i64, now()],
         )?;
+        Ok(())
+    }
 
-        Ok(conn.last_insert_rowid() as u64)
+    /// Revoke a share. Returns false if the device had no share.
+    pub fn unshare_conversation(&self, conversation_id: u64, device_id: u64) -> Result<bool> {
+        let removed = self.execute(
+            "DELETE FROM conversation_shares WHERE conversation_id = ?1 AND device_id = ?2",
+            rusqlite::params![conversation_id as i64, device_id as i64],
+        )?;
+        Ok(removed > 0)
     }
 
-    /// Touch last_accessed on a conversation.
-    pub fn touch_conversation(&self, conversation_id: u64) -> Result<()> {
+    /// ISO 639-1 code of the language the conversation is held in, once detected.
+    pub fn get_conversation_language(&self, conversation_id: u64) -> Result<Option<String>> {
+        Ok(self.query_row_optional(
+            "SELECT language FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, Option<String>>(0),
+        )?.flatten())
+    }
+
+    pub fn set_conversation_language(&self, conversation_id: u64, language: &str) -> Result<()> {
+        self.execute(
+            "UPDATE conversations SET language = ?1 WHERE id = ?2",
+            rusqlite::params![language, conversation_id as i64],
+        )?;
+        Ok(())
+    }
+
+    /// The device's preferred language, used until a conversation's own is detected.
+    pub fn get_device_language(&self, device_id: u64) -> Result<Option<String>> {
+        Ok(self.query_row_optional(
+            "SELECT language FROM devices WHERE id = ?1",
+            rusqlite::params![device_id as i64],
+            |row| row.get::<_, Option<String>>(0),
+        )?.flatten())
+    }
+
+    /// Set or, with None, clear the device's preferred language.
+    pub fn set_device_language(&self, device_id: u64, language: Option<&str>) -> Result<()> {
         self.execute(
-            "UPDATE conversations SET last_accessed = ?1 WHERE id = ?2",
-            rusqlite::params![now(), conversation_id as i64],
+            "UPDATE devices SET language = ?1 WHERE id = ?2",
+            rusqlite::params![language, device_id as i64],
         )?;
         Ok(())
     }
 
+    /// Client-reported facts about the device (timezone, locale, ...) as a JSON object.
+    pub fn get_device_metadata(&self, device_id: u64) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let raw: Option<String> = self.query_row_optional(
+            "SELECT metadata FROM devices WHERE id = ?1",
+            rusqlite::params![device_id as i64],
+            |row| row.get::<_, Option<String>>(0),
+        )?.flatten();
+
+        Ok(raw
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| match v {
+                serde_json::Value::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default())
+    }
+
+    /// Merge `changes` into the device's metadata. A null value removes its key.
+    pub fn update_device_metadata(
+        &self,
+        device_id: u64,
+        changes: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let mut metadata = self.get_device_metadata(device_id)?;
+        for (key, value) in changes {
+            if value.is_null() {
+                metadata.remove(key);
+            } else {
+                metadata.insert(key.clone(), value.clone());
+            }
+        }
+
+        self.execute(
+            "UPDATE devices SET metadata = ?1 WHERE id = ?2",
+            rusqlite::params![serde_json::Value::Object(metadata.clone()).to_string(), device_id as i64],
+        )?;
+        Ok(metadata)
+    }
+
+    /// The device's IANA timezone, if its client has reported one.
+    pub fn get_device_timezone(&self, device_id: u64) -> Result<Option<String>> {
+        Ok(self.get_device_metadata(device_id)?
+            .get("timezone")
+            .and_then(|v| v.as_str())
+            .map(String::from))
+    }
+
     pub fn get_conversation_title(&self, conversation_id: u64) -> Result<Option<String>> {
         self.query_row_optional(
             "SELECT title FROM conversations WHERE id = ?1",
@@ -161,8 +560,23 @@ impl Db {
 // MESSAGES
 // ============================================================================
 
+/// A stored message with its id, and the confidence of a rated answer. A
+/// deleted message is a tombstone: no content, only when it was deleted.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StoredMessage {
+    pub id: u64,
+    #[serde(flatten)]
+    pub message: Message,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+}
+
 impl Db {
-    /// Add a message to a conversation. Increments message_count in place.
+    /// Add a message to a conversation, after every message already in it.
+    /// The order is taken from the stored messages, so concurrent writers
+    /// never collide.
     pub fn add_message(
         &self,
         conversation_id: u64,
@@ -170,45 +584,41 @@ impl Db {
         role: &str,
         content: Option<&str>,
         tool_calls: Option<&Vec<ToolCall>>,
-        message_count: &mut u32,
     ) -> Result<()> {
         let tool_calls_json = tool_calls
-            .map(|tc| serde_json::to_string(tc))
+            .map(serde_json::to_string)
             .transpose()?;
 
-        let conn = self.lock()?;
         let now = now();
 
-        conn.execute(
-            "INSERT INTO messages
-             (conversation_id, task_id, role, message, tool_calls, m_order, created)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![
+        self.transaction(|tx| {
+            tx.prepare_cached(
+                "INSERT INTO messages
+                 (conversation_id, task_id, role, message, tool_calls, m_order, created, unindexed)
+                 VALUES (?1, ?2, ?3, ?4, ?5,
+                         (SELECT COALESCE(MAX(m_order) + 1, 0) FROM messages WHERE conversation_id = ?1),
+                         ?6,
+                         (SELECT expires_at IS NOT NULL FROM conversations WHERE id = ?1))",
+            )?.execute(rusqlite::params![
                 conversation_id as i64,
                 task_id,
                 role,
                 content,
                 tool_calls_json,
-                *message_count as i64,
                 now,
-            ],
-        )?;
-        *message_count += 1;
-
-        conn.execute(
-            "UPDATE conversations SET last_accessed = ?1 WHERE id = ?2",
-            rusqlite::params![now, conversation_id as i64],
-        )?;
-
-        Ok(())
+            ])?;
+            tx.prepare_cached(TOUCH_CONVERSATION)?
+                .execute(rusqlite::params![now, conversation_id as i64, INCOGNITO_TTL_SECS])?;
+            Ok(())
+        })
     }
 
-    /// Load all messages for a conversation in order.
+    /// Load all messages for a conversation in order, leaving out deleted ones.
     pub fn get_messages(&self, conversation_id: u64) -> Result<Vec<Message>> {
         let conn = self.lock()?;
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             "SELECT role, message, tool_calls FROM messages
-             WHERE conversation_id = ?1
+             WHERE conversation_id = ?1 AND deleted_at IS NULL
              ORDER BY m_order",
         )?;
 
@@ -232,7 +642,45 @@ impl Db {
         Ok(messages)
     }
 
-    /// Get the current message count for a conversation (for ordered inserts).
+    /// All messages of a conversation in order as clients see them, deleted
+    /// ones as tombstones.
+    pub fn get_stored_messages(&self, conversation_id: u64) -> Result<Vec<StoredMessage>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, role, message, tool_calls, confidence, deleted_at FROM messages
+             WHERE conversation_id = ?1
+             ORDER BY m_order",
+        )?;
+
+        let messages = stmt.query_map(
+            rusqlite::params![conversation_id as i64],
+            |row| {
+                let id: i64 = row.get(0)?;
+                let role: String = row.get(1)?;
+                let content: Option<String> = row.get(2)?;
+                let tool_calls: Option<String> = row.get(3)?;
+                let confidence: Option<String> = row.get(4)?;
+                let deleted_at: Option<i64> = row.get(5)?;
+                Ok((id, role, content, tool_calls, confidence, deleted_at))
+            },
+        )?
+            .filter_map(|r| r.ok())
+            .map(|(id, role, content, tool_calls, confidence, deleted_at)| StoredMessage {
+                id: id as u64,
+                message: Message {
+                    role,
+                    content,
+                    tool_calls: tool_calls.and_then(|j| serde_json::from_str(&j).ok()),
+                },
+                confidence: confidence.and_then(|j| serde_json::from_str(&j).ok()),
+                deleted_at,
+            })
+            .collect();
+
+        Ok(messages)
+    }
+
+    /// How many messages a conversation holds.
     pub fn get_message_count(&self, conversation_id: u64) -> Result<u32> {
         let conn = self.lock()?;
         let count: i64 = conn.query_row(
@@ -314,6 +762,17 @@ impl Db {
         Ok(())
     }
 
+    /// Mark a conversation's unfinished tasks abandoned, as when its chat
+    /// turn is cancelled. Returns how many there were.
+    pub fn abandon_open_tasks(&self, conversation_id: u64) -> Result<usize> {
+        let now = now();
+        self.execute(
+            "UPDATE tasks SET status = 'abandoned', completed_at = ?1, updated_at = ?2
+             WHERE conversation_id = ?3 AND status = 'in_progress'",
+            rusqlite::params![now, now, conversation_id as i64],
+        )
+    }
+
     /// Get goal and plan for a task by ID. Used for parent task queries.
     pub fn get_task_info(&self, task_id: u64) -> Result<Option<(String, Option<String>)>> {
         self.query_row_optional(
@@ -330,29 +789,11 @@ impl Db {
 
 impl Db {
     pub fn conversation_title_exists(&self, device_id: i64, title: &str) -> bool {
-        if let Ok(conn) = self.lock() {
-            conn.query_row(
-                "SELECT 1 FROM conversations WHERE device_id = ?1 AND title = ?2 LIMIT 1",
-                rusqlite::params![device_id, title],
-                |_| Ok(true),
-            ).unwrap_or(false)
-        } else {
-            false
-        }
+        self.lock().is_ok_and(|conn| title_exists(&conn, device_id, title))
     }
 
     pub fn find_available_conversation_title(&self, device_id: i64, base: &str) -> String {
-        let mut counter = 1u32;
-        loop {
-            let candidate = format!("{}_{}", base, counter);
-            if !self.conversation_title_exists(device_id, &candidate) {
-                return candidate;
-            }
-            counter += 1;
-            if counter > 1000 {
-                return format!("{}_{}", base, &uuid::Uuid::new_v4().to_string()[..8]);
-            }
-        }
+        self.lock().map_or_else(|_| format!("{}_1", base), |conn| available_title(&conn, device_id, base))
     }
 
     pub fn set_conversation_title(
@@ -361,23 +802,8 @@ impl Db {
         device_id: i64,
         raw_title: &str,
     ) -> Result<String> {
-        let sanitized = sanitize_title(raw_title);
-        if sanitized.is_empty() {
-            return Err(anyhow::anyhow!("Title is empty after sanitization"));
-        }
-
-        let final_title = if self.conversation_title_exists(device_id, &sanitized) {
-            self.find_available_conversation_title(device_id, &sanitized)
-        } else {
-            sanitized
-        };
-
-        self.execute(
-            "UPDATE conversations SET title = ?1 WHERE id = ?2",
-            rusqlite::params![final_title, conversation_id as i64],
-        )?;
-
-        Ok(final_title)
+        let conn = self.lock()?;
+        set_title(&conn, conversation_id, device_id, raw_title)
     }
 
     pub fn set_task_title(&self, task_id: i64, title: &str) -> Result<()> {
@@ -393,11 +819,215 @@ impl Db {
     }
 }
 
+fn title_exists(conn: &Connection, device_id: i64, title: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM conversations WHERE device_id = ?1 AND title = ?2 LIMIT 1",
+        rusqlite::params![device_id, title],
+        |_| Ok(true),
+    ).unwrap_or(false)
+}
+
+fn available_title(conn: &Connection, device_id: i64, base: &str) -> String {
+    let mut counter = 1u32;
+    loop {
+        let candidate = format!("{}_{}", base, counter);
+        if !title_exists(conn, device_id, &candidate) {
+            return candidate;
+        }
+        counter += 1;
+        if counter > 1000 {
+            return format!("{}_{}", base, &uuid::Uuid::new_v4().to_string()[..8]);
+        }
+    }
+}
+
+/// Title a conversation with `raw_title` sanitized, numbered if the device
+/// already has a conversation of that title. Takes the connection so it can
+/// run inside a transaction.
+pub(crate) fn set_title(conn: &Connection, conversation_id: u64, device_id: i64, raw_title: &str) -> Result<String> {
+    let sanitized = sanitize_title(raw_title);
+    if sanitized.is_empty() {
+        return Err(anyhow::anyhow!("Title is empty after sanitization"));
+    }
+
+    let final_title = if title_exists(conn, device_id, &sanitized) {
+        available_title(conn, device_id, &sanitized)
+    } else {
+        sanitized
+    };
+
+    conn.execute(
+        "UPDATE conversations SET title = ?1 WHERE id = ?2",
+        rusqlite::params![final_title, conversation_id as i64],
+    )?;
+
+    Ok(final_title)
+}
+
+// ============================================================================
+// SEARCH
+// ============================================================================
+
+/// Marker wrapped around matched terms in search snippets.
+pub const SNIPPET_HIGHLIGHT: &str = "**";
+
+/// A conversation matching a full-text search, with its best-ranked snippet.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConversationSearchHit {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    pub last_accessed: i64,
+    pub snippet: String,
+    pub match_count: u32,
+}
+
+impl Db {
+    /// Full-text search across a device's user and assistant messages.
+    /// Results are grouped per conversation, best match first.
+    pub fn search_conversations(
+        &self,
+        device_id: u64,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ConversationSearchHit>> {
+        let fts_query = to_fts_query(query);
+        if fts_query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.title, c.last_accessed,
+                    snippet(messages_fts, 0, ?3, ?3, '…', 16)
+             FROM messages_fts
+             JOIN messages m ON m.id = messages_fts.rowid
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE messages_fts MATCH ?1
+               AND c.device_id = ?2
+               AND m.role IN ('user', 'assistant')
+               AND (c.lock_hash IS NULL OR c.unlocked_until > ?4)
+             ORDER BY bm25(messages_fts)
+             LIMIT 500",
+        )?;
+
+        let rows = stmt.query_map(
+            rusqlite::params![fts_query, device_id as i64, SNIPPET_HIGHLIGHT, now()],
+            |row| {
+                Ok(ConversationSearchHit {
+                    conversation_id: row.get::<_, i64>(0)? as u64,
+                    title: row.get(1)?,
+                    last_accessed: row.get(2)?,
+                    snippet: row.get(3)?,
+                    match_count: 1,
+                })
+            },
+        )?;
+
+        let mut hits: Vec<ConversationSearchHit> = Vec::new();
+        for row in rows {
+            let row = row?;
+            match hits.iter_mut().find(|h| h.conversation_id == row.conversation_id) {
+                Some(existing) => existing.match_count += 1,
+                None => hits.push(row),
+            }
+        }
+        hits.truncate(limit);
+
+        Ok(hits)
+    }
+}
+
 // ============================================================================
 // BACKGROUND JOBS
 // ============================================================================
 
+/// A row of the `background` queue as reported back to clients.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BackgroundJob {
+    pub id: u64,
+    pub device_id: Option<u64>,
+    pub method: String,
+    pub arguments: Value,
+    pub status: String,
+    pub result: Option<String>,
+    pub created_at: i64,
+    /// When the job was scheduled to start; absent for "as soon as possible".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<i64>,
+    pub completed_at: Option<i64>,
+}
+
+impl BackgroundJob {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let arguments: String = row.get(3)?;
+        Ok(BackgroundJob {
+            id: row.get::<_, i64>(0)? as u64,
+            device_id: row.get::<_, Option<i64>>(1)?.map(|id| id as u64),
+            method: row.get(2)?,
+            arguments: serde_json::from_str(&arguments).unwrap_or(Value::Null),
+            status: row.get(4)?,
+            result: row.get(5)?,
+            created_at: row.get(6)?,
+            completed_at: row.get(7)?,
+            run_at: row.get(8)?,
+        })
+    }
+}
+
 impl Db {
+    pub fn get_job(&self, job_id: u64) -> Result<Option<BackgroundJob>> {
+        self.query_row_optional(
+            "SELECT id, device_id, method, arguments, status, result, created_at, completed_at, run_at
+             FROM background WHERE id = ?1",
+            rusqlite::params![job_id as i64],
+            BackgroundJob::from_row,
+        )
+    }
+
+    /// Ask for a job's outcome to be delivered to `device_id` once it finishes.
+    pub fn watch_job(&self, job_id: u64, device_id: u64) -> Result<()> {
+        self.execute(
+            "INSERT OR IGNORE INTO job_notifications (job_id, device_id, created)
+             VALUES (?1, ?2, ?3)",
+            rusqlite::params![job_id as i64, device_id as i64, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Finished watched jobs the device hasn't seen yet. Marks them delivered.
+    pub fn take_finished_jobs(&self, device_id: u64) -> Result<Vec<BackgroundJob>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.device_id, b.method, b.arguments, b.status, b.result, b.created_at, b.completed_at, b.run_at
+             FROM job_notifications n
+             JOIN background b ON b.id = n.job_id
+             WHERE n.device_id = ?1 AND n.delivered = 0
+               AND b.status IN ('completed', 'failed')
+             ORDER BY b.completed_at ASC, b.id ASC",
+        )?;
+        let jobs = stmt
+            .query_map(rusqlite::params![device_id as i64], BackgroundJob::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for job in &jobs {
+            conn.execute(
+                "UPDATE job_notifications SET delivered = 1 WHERE job_id = ?1",
+                rusqlite::params![job.id as i64],
+            )?;
+        }
+
+        Ok(jobs)
+    }
+
+    /// The key a device authenticates with, for work done on its behalf without a live request.
+    pub fn get_device_key(&self, device_id: u64) -> Result<Option<String>> {
+        self.query_row_optional(
+            "SELECT device_key FROM devices WHERE id = ?1 AND active = 1",
+            rusqlite::params![device_id as i64],
+            |row| row.get(0),
+        )
+    }
+
     /// Queue a title generation job for a conversation.
     pub fn queue_title_generation(
         &self,
@@ -413,6 +1043,7 @@ impl Db {
                 "user_message": first_message,
             }),
             1,
+            None,
         )
     }
 
@@ -433,26 +1064,30 @@ impl Db {
         Ok(count)
     }
 
+    /// Queue a job. It waits until `run_at` (a unix timestamp) when given,
+    /// otherwise it runs as soon as the worker gets to it.
     pub fn create_job(
         &self,
         device_id: i64,
         method: &str,
         arguments: &Value,
         priority: u32,
+        run_at: Option<i64>,
     ) -> Result<u64> {
         let conn = self.lock()?;
         let now = now();
 
         conn.execute(
             "INSERT INTO background
-             (device_id, method, arguments, priority, status, created_at)
-             VALUES (?1, ?2, ?3, ?4, 'pending', ?5)",
+             (device_id, method, arguments, priority, status, created_at, run_at)
+             VALUES (?1, ?2, ?3, ?4, 'pending', ?5, ?6)",
             rusqlite::params![
                 device_id,
                 method,
                 arguments.to_string(),
                 priority,
-                now
+                now,
+                run_at
             ],
         )?;
 
@@ -504,17 +1139,27 @@ impl Db {
     }
 
     /// Get all traces for a task, ordered by iteration.
-    pub fn get_execution_traces(&self, task_id: u64) -> Result<String> {
+    /// One page of a task's trace, as a JSON array, ordered by iteration.
+    pub fn get_execution_traces(&self, task_id: u64, limit: u64, offset: u64) -> Result<String> {
         self.query(
             "SELECT iteration, agent_name, classification, reasoning, tool_calls, tool_results,
                     llm_duration_ms, created_at
              FROM execution_traces
              WHERE task_id = ?1
-             ORDER BY iteration",
-            rusqlite::params![task_id as i64],
+             ORDER BY iteration
+             LIMIT ?2 OFFSET ?3",
+            rusqlite::params![task_id as i64, limit as i64, offset as i64],
         )
     }
 
+    pub fn count_execution_traces(&self, task_id: u64) -> Result<u64> {
+        Ok(self.query_row_optional(
+            "SELECT COUNT(*) FROM execution_traces WHERE task_id = ?1",
+            rusqlite::params![task_id as i64],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0) as u64)
+    }
+
     /// Get the full detail for a specific iteration of a task trace.
     pub fn get_execution_trace_detail(&self, task_id: u64, iteration: u32) -> Result<String> {
         self.query(
@@ -546,13 +1191,40 @@ pub fn get() -> &'static Arc<Db> {
 // HELPERS
 // ============================================================================
 
-fn now() -> i64 {
+/// Sets last_accessed to ?1 on conversation ?2; an incognito conversation
+/// also expires ?3 seconds after it.
+const TOUCH_CONVERSATION: &str =
+    "UPDATE conversations SET last_accessed = ?1,
+         expires_at = CASE WHEN expires_at IS NULL THEN NULL ELSE ?1 + ?3 END
+     WHERE id = ?2";
+
+/// Conversations the device can see in its own history: not incognito, and
+/// not locked unless unlocked right now. `?1` is the device, `?2` the time.
+pub(crate) const VISIBLE_CONVERSATION: &str =
+    "c.device_id = ?1 AND c.expires_at IS NULL AND (c.lock_hash IS NULL OR c.unlocked_until > ?2)";
+
+pub(crate) fn now() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_secs() as i64
 }
 
+/// Register a device named `name` for a test and return its id.
+#[cfg(test)]
+pub(crate) fn register_device(db: &Db, name: &str) -> u64 {
+    db.execute(
+        "INSERT INTO devices (device_name, device_key, active, created, last_seen)
+         VALUES (?1, ?2, 1, 0, 0)",
+        rusqlite::params![name, format!("{}-key", name)],
+    ).unwrap();
+    db.query_row_optional(
+        "SELECT id FROM devices WHERE device_name = ?1",
+        rusqlite::params![name],
+        |row| row.get::<_, i64>(0),
+    ).unwrap().unwrap() as u64
+}
+
 pub fn sanitize_title(title: &str) -> String {
     title.chars()
         .map(|c| match c {
@@ -567,6 +1239,34 @@ pub fn sanitize_title(title: &str) -> String {
         .join("_")
 }
 
+/// Turn free-form user input into an FTS5 query: every term is quoted so
+/// punctuation and FTS operators in the input are matched literally.
+pub fn to_fts_query(input: &str) -> String {
+    input.split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One result row as a JSON object keyed by column name. Blobs are summarized, not inlined.
+fn row_to_json(row: &rusqlite::Row, column_names: &[String]) -> rusqlite::Result<Value> {
+    let mut map = serde_json::Map::new();
+    for (i, name) in column_names.iter().enumerate() {
+        let val: rusqlite::types::Value = row.get(i)?;
+        let json_val = match val {
+            rusqlite::types::Value::Null => Value::Null,
+            rusqlite::types::Value::Integer(n) => serde_json::json!(n),
+            rusqlite::types::Value::Real(f) => serde_json::json!(f),
+            rusqlite::types::Value::Text(s) => serde_json::json!(s),
+            rusqlite::types::Value::Blob(b) => {
+                serde_json::json!(format!("<blob:{} bytes>", b.len()))
+            }
+        };
+        map.insert(name.clone(), json_val);
+    }
+    Ok(Value::Object(map))
+}
+
 pub fn json_to_rusqlite(val: &Value) -> rusqlite::types::Value {
     match val {
         Value::Null => rusqlite::types::Value::Null,
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// How much of a conversation may become long-term memory. Ordered from
+/// most to least permissive, so the stricter of two levels is the larger.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrivacyLevel {
+    #[default]
+    Normal,
+    /// Facts about the user may be saved, preferences may not.
+    FactsOnly,
+    /// Nothing said in the conversation is saved as a memory.
+    NoExtraction,
+}
+
+impl PrivacyLevel {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace('_', "-").as_str() {
+            "normal" => Some(Self::Normal),
+            "facts-only" => Some(Self::FactsOnly),
+            "no-extraction" => Some(Self::NoExtraction),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::FactsOnly => "facts-only",
+            Self::NoExtraction => "no-extraction",
+        }
+    }
+}
+
+// ============================================================================
+// PRIVACY LEVELS
+// ============================================================================
+
+impl Db {
+    /// A conversation's privacy level; `Normal` for an unknown conversation.
+    pub fn get_privacy_level(&self, conversation_id: u64) -> Result<PrivacyLevel> {
+        let level = self.query_row_optional(
+            "SELECT privacy FROM conversations WHERE id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, String>(0),
+        )?;
+        Ok(level.as_deref().and_then(PrivacyLevel::parse).unwrap_or_default())
+    }
+
+    /// Returns false if there is no such conversation.
+    pub fn set_privacy_level(&self, conversation_id: u64, level: PrivacyLevel) -> Result<bool> {
+        let updated = self.execute(
+            "UPDATE conversations SET privacy = ?1 WHERE id = ?2",
+            rusqlite::params![level.as_str(), conversation_id as i64],
+        )?;
+        Ok(updated > 0)
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, MergeOutcome, PrivacyLevel};
+
+    #[test]
+    fn levels_parse_from_their_names() {
+        assert_eq!(PrivacyLevel::parse("facts-only"), Some(PrivacyLevel::FactsOnly));
+        assert_eq!(PrivacyLevel::parse(" No_Extraction "), Some(PrivacyLevel::NoExtraction));
+        assert_eq!(PrivacyLevel::parse("secret"), None);
+        assert!(PrivacyLevel::NoExtraction > PrivacyLevel::FactsOnly);
+    }
+
+    #[test]
+    fn conversations_start_normal_and_keep_what_is_set() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        assert_eq!(db.get_privacy_level(conversation).unwrap(), PrivacyLevel::Normal);
+
+        assert!(db.set_privacy_level(conversation, PrivacyLevel::NoExtraction).unwrap());
+        assert_eq!(db.get_privacy_level(conversation).unwrap(), PrivacyLevel::NoExtraction);
+        assert!(!db.set_privacy_level(9999, PrivacyLevel::FactsOnly).unwrap());
+    }
+
+    #[test]
+    fn merging_keeps_the_stricter_level() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let budget = db.create_conversation(device).unwrap();
+        let doctor = db.create_conversation(device).unwrap();
+        db.set_privacy_level(doctor, PrivacyLevel::FactsOnly).unwrap();
+
+        assert_eq!(db.merge_conversations(budget, doctor).unwrap(), MergeOutcome::Done);
+        assert_eq!(db.get_privacy_level(budget).unwrap(), PrivacyLevel::FactsOnly);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{now, Db};
+
+/// The exact prompt one LLM call was sent, recorded for requests made with
+/// `debug`. `tools` and `messages` are the JSON the model received.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptSnapshot {
+    pub id: u64,
+    pub task_id: u64,
+    pub agent_name: String,
+    pub system_prompt: String,
+    /// The memory section of the system prompt, empty when none was injected.
+    pub memories: String,
+    pub tools: serde_json::Value,
+    pub messages: serde_json::Value,
+    pub created: i64,
+}
+
+impl PromptSnapshot {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let tools: String = row.get(5)?;
+        let messages: String = row.get(6)?;
+        Ok(PromptSnapshot {
+            id: row.get::<_, i64>(0)? as u64,
+            task_id: row.get::<_, i64>(1)? as u64,
+            agent_name: row.get(2)?,
+            system_prompt: row.get(3)?,
+            memories: row.get(4)?,
+            tools: serde_json::from_str(&tools).unwrap_or(serde_json::Value::Null),
+            messages: serde_json::from_str(&messages).unwrap_or(serde_json::Value::Null),
+            created: row.get(7)?,
+        })
+    }
+}
+
+// ============================================================================
+// PROMPT SNAPSHOTS
+// ============================================================================
+
+impl Db {
+    pub fn store_prompt_snapshot(
+        &self,
+        task_id: u64,
+        agent_name: &str,
+        system_prompt: &str,
+        memories: &str,
+        tools: &serde_json::Value,
+        messages: &serde_json::Value,
+    ) -> Result<u64> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO prompt_snapshots (task_id, agent_name, system_prompt, memories, tools, messages, created)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                task_id as i64,
+                agent_name,
+                system_prompt,
+                memories,
+                tools.to_string(),
+                messages.to_string(),
+                now(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Every snapshot taken while answering a request, oldest first: those of
+    /// the request's task and of all the tasks it delegated to. Empty unless
+    /// the request was made by `device_id`.
+    pub fn request_prompt_snapshots(&self, request_id: u64, device_id: u64) -> Result<Vec<PromptSnapshot>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE run(id) AS (
+                 SELECT id FROM tasks WHERE id = ?1 AND device_id = ?2
+                 UNION ALL
+                 SELECT tasks.id FROM tasks JOIN run ON tasks.parent_task_id = run.id
+             )
+             SELECT id, task_id, agent_name, system_prompt, memories, tools, messages, created
+             FROM prompt_snapshots
+             WHERE task_id IN (SELECT id FROM run)
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![request_id as i64, device_id as i64],
+            PromptSnapshot::from_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    #[test]
+    fn snapshots_cover_the_request_and_its_delegations() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let other_device = register_device(&db, "phone");
+        let conversation = db.create_conversation(device).unwrap();
+
+        let request = db.create_task(device, conversation, None, "research").unwrap();
+        let step = db.create_task(device, conversation, Some(request), "search").unwrap();
+        let nested = db.create_task(device, conversation, Some(step), "translate").unwrap();
+        let other_request = db.create_task(device, conversation, None, "something else").unwrap();
+
+        let tools = serde_json::json!([]);
+        let messages = serde_json::json!([{ "role": "user", "content": "hi" }]);
+        db.store_prompt_snapshot(request, "Orchestrator", "system", "# Memories", &tools, &messages).unwrap();
+        db.store_prompt_snapshot(step, "WebResearcher", "system", "", &tools, &messages).unwrap();
+        db.store_prompt_snapshot(nested, "Translator", "system", "", &tools, &messages).unwrap();
+        db.store_prompt_snapshot(other_request, "Orchestrator", "system", "", &tools, &messages).unwrap();
+
+        let snapshots = db.request_prompt_snapshots(request, device).unwrap();
+        let agents: Vec<&str> = snapshots.iter().map(|s| s.agent_name.as_str()).collect();
+        assert_eq!(agents, ["Orchestrator", "WebResearcher", "Translator"]);
+        assert_eq!(snapshots[0].memories, "# Memories");
+        assert_eq!(snapshots[0].messages, messages);
+
+        assert!(db.request_prompt_snapshots(request, other_device).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{now, Db};
+
+/// One saved version of an agent's instructions.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptOverride {
+    pub agent: String,
+    pub version: u32,
+    pub prompt: String,
+    pub note: Option<String>,
+    pub active: bool,
+    pub created: i64,
+}
+
+impl PromptOverride {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(PromptOverride {
+            agent: row.get(0)?,
+            version: row.get(1)?,
+            prompt: row.get(2)?,
+            note: row.get(3)?,
+            active: row.get::<_, i64>(4)? != 0,
+            created: row.get(5)?,
+        })
+    }
+}
+
+// ============================================================================
+// PROMPT OVERRIDES
+// ============================================================================
+
+impl Db {
+    /// The instructions currently in force for `agent`, if an operator has overridden them.
+    pub fn active_prompt_override(&self, agent: &str) -> Result<Option<String>> {
+        self.query_row_optional(
+            "SELECT prompt FROM prompt_overrides WHERE agent = ?1 AND active = 1",
+            rusqlite::params![agent],
+            |row| row.get(0),
+        )
+    }
+
+    /// Every saved version for `agent`, newest first.
+    pub fn prompt_override_history(&self, agent: &str) -> Result<Vec<PromptOverride>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT agent, version, prompt, note, active, created
+             FROM prompt_overrides WHERE agent = ?1
+             ORDER BY version DESC",
+        )?;
+        let versions = stmt
+            .query_map(rusqlite::params![agent], PromptOverride::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(versions)
+    }
+
+    /// Save `prompt` as the next version for `agent` and make it active. Returns the version.
+    pub fn set_prompt_override(&self, agent: &str, prompt: &str, note: Option<&str>) -> Result<u32> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        let version: u32 = tx.query_row(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM prompt_overrides WHERE agent = ?1",
+            rusqlite::params![agent],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "UPDATE prompt_overrides SET active = 0 WHERE agent = ?1",
+            rusqlite::params![agent],
+        )?;
+        tx.execute(
+            "INSERT INTO prompt_overrides (agent, version, prompt, note, active, created)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            rusqlite::params![agent, version, prompt, note, now()],
+        )?;
+
+        tx.commit()?;
+        Ok(version)
+    }
+
+    /// Make an earlier version active again, or with `None` go back to the
+    /// compiled prompt. Returns false if the version doesn't exist.
+    pub fn rollback_prompt_override(&self, agent: &str, version: Option<u32>) -> Result<bool> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        if let Some(version) = version {
+            let exists = tx.query_row(
+                "SELECT COUNT(*) FROM prompt_overrides WHERE agent = ?1 AND version = ?2",
+                rusqlite::params![agent, version],
+                |row| row.get::<_, i64>(0),
+            )? > 0;
+            if !exists {
+                return Ok(false);
+            }
+        }
+
+        tx.execute(
+            "UPDATE prompt_overrides SET active = (version IS ?2) WHERE agent = ?1",
+            rusqlite::params![agent, version],
+        )?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,34 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::Db;
+
+    #[test]
+    fn newest_version_is_active() {
+        let db = Db::in_memory().unwrap();
+        assert_eq!(db.active_prompt_override("Archivist").unwrap(), None);
+
+        assert_eq!(db.set_prompt_override("Archivist", "first", None).unwrap(), 1);
+        assert_eq!(db.set_prompt_override("Archivist", "second", Some("tweak")).unwrap(), 2);
+        assert_eq!(db.active_prompt_override("Archivist").unwrap().as_deref(), Some("second"));
+
+        let history = db.prompt_override_history("Archivist").unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].active && !history[1].active);
+    }
+
+    #[test]
+    fn rollback_reactivates_or_clears() {
+        let db = Db::in_memory().unwrap();
+        db.set_prompt_override("FileSmith", "first", None).unwrap();
+        db.set_prompt_override("FileSmith", "second", None).unwrap();
+
+        assert!(db.rollback_prompt_override("FileSmith", Some(1)).unwrap());
+        assert_eq!(db.active_prompt_override("FileSmith").unwrap().as_deref(), Some("first"));
+
+        assert!(!db.rollback_prompt_override("FileSmith", Some(9)).unwrap());
+        assert_eq!(db.active_prompt_override("FileSmith").unwrap().as_deref(), Some("first"));
+
+        assert!(db.rollback_prompt_override("FileSmith", None).unwrap());
+        assert_eq!(db.active_prompt_override("FileSmith").unwrap(), None);
+    }
+}
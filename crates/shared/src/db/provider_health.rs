@@ -0,0 +1,89 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// What a metered provider last said about its API key and quota.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    /// Whether the provider accepted the key.
+    pub ok: bool,
+    /// Why it didn't, when it didn't.
+    pub message: Option<String>,
+    /// Calls left in the provider's billing period, by its own count.
+    pub remaining: Option<u64>,
+    /// Calls the billing period allows in total.
+    pub limit: Option<u64>,
+    /// When `remaining` starts over, in unix seconds.
+    pub resets_at: Option<i64>,
+    pub checked_at: i64,
+}
+
+impl ProviderHealth {
+    /// The provider reported no calls left and the period hasn't reset since.
+    /// A period with no known reset stays exhausted until the next check.
+    pub fn exhausted(&self) -> bool {
+        self.remaining == Some(0) && self.resets_at.is_none_or(|at| at > now())
+    }
+}
+
+fn health_from_row(row: &rusqlite::Row) -> rusqlite::Result<ProviderHealth> {
+    Ok(ProviderHealth {
+        provider: row.get(0)?,
+        ok: row.get(1)?,
+        message: row.get(2)?,
+        remaining: row.get::<_, Option<i64>>(3)?.map(|n| n.max(0) as u64),
+        limit: row.get::<_, Option<i64>>(4)?.map(|n| n.max(0) as u64),
+        resets_at: row.get(5)?,
+        checked_at: row.get(6)?,
+    })
+}
+
+// ============================================================================
+// PROVIDER HEALTH
+// ============================================================================
+
+// One row per provider, replaced by every check and by every call whose
+// response carries quota headers, so the newest report always wins.
+impl Db {
+    /// Store `health` as the provider's latest report.
+    pub fn record_provider_health(&self, health: &ProviderHealth) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO provider_health
+                 (provider, ok, message, remaining, quota_limit, resets_at, checked_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                health.provider,
+                health.ok,
+                health.message,
+                health.remaining.map(|n| n as i64),
+                health.limit.map(|n| n as i64),
+                health.resets_at,
+                health.checked_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The latest report from `provider`, if it was ever checked.
+    pub fn get_provider_health(&self, provider: &str) -> Result<Option<ProviderHealth>> {
+        self.query_row_optional(
+            "SELECT provider, ok, message, remaining, quota_limit, resets_at, checked_at
+             FROM provider_health WHERE provider = ?1",
+            rusqlite::params![provider],
+            health_from_row,
+        )
+    }
+
+    /// The latest report from every provider checked so far, by name.
+    pub fn provider_health(&self) -> Result<Vec<ProviderHealth>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT provider, ok, message, remaining, quota_limit, resets_at, checked_at
+             FROM provider_health ORDER BY provider",
+        )?;
+        let rows = stmt.query_map([], health_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{now, Db, ProviderHealth};
+
+    fn report(provider: &str, remaining: Option<u64>, resets_at: Option<i64>) -> ProviderHealth {
+        ProviderHealth {
+            provider: provider.to_string(),
+            ok: true,
+            message: None,
+            remaining,
+            limit: Some(2000),
+            resets_at,
+            checked_at: now(),
+        }
+    }
+
+    #[test]
+    fn latest_report_replaces_the_last() {
+        let db = Db::in_memory().unwrap();
+        assert!(db.get_provider_health("brave").unwrap().is_none());
+
+        db.record_provider_health(&report("brave", Some(40), None)).unwrap();
+        db.record_provider_health(&report("brave", Some(39), None)).unwrap();
+        db.record_provider_health(&report("anthropic", None, None)).unwrap();
+
+        let brave = db.get_provider_health("brave").unwrap().unwrap();
+        assert_eq!(brave.remaining, Some(39));
+        assert_eq!(brave.limit, Some(2000));
+        let names: Vec<String> = db.provider_health().unwrap().into_iter().map(|h| h.provider).collect();
+        assert_eq!(names, ["anthropic", "brave"]);
+    }
+
+    #[test]
+    fn exhausted_until_the_period_resets() {
+        assert!(!report("brave", Some(1), None).exhausted());
+        assert!(!report("brave", None, None).exhausted());
+        assert!(report("brave", Some(0), None).exhausted());
+        assert!(report("brave", Some(0), Some(now() + 3600)).exhausted());
+        assert!(!report("brave", Some(0), Some(now() - 1)).exhausted());
+    }
+}
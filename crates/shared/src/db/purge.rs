@@ -0,0 +1,90 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// Rows a device purge removed, or would remove on a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub conversations: usize,
+    pub messages: usize,
+    pub tasks: usize,
+    pub artifacts: usize,
+    pub memories: usize,
+    pub tags: usize,
+    pub attachments: usize,
+    pub jobs: usize,
+    pub workflows: usize,
+    pub watches: usize,
+    /// Execution traces, prompt snapshots, experiment outcomes and tool
+    /// usage counters.
+    pub audit: usize,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> usize {
+        self.conversations + self.messages + self.tasks + self.artifacts + self.memories
+            + self.tags + self.attachments + self.jobs + self.workflows + self.watches
+            + self.audit
+    }
+}
+
+/// Tasks in the device's own conversations.
+const DEVICE_TASKS: &str =
+    "SELECT id FROM tasks WHERE conversation_id IN (SELECT id FROM conversations WHERE device_id = ?1)";
+
+// ============================================================================
+// DEVICE PURGE
+// ============================================================================
+
+impl Db {
+    /// Delete everything stored for a device: its conversations with their
+    /// messages, tasks, artifacts, traces and snapshots, plus its memories,
+    /// tags, attachments, background jobs, workflows, path watches and usage
+    /// counters. The device stays registered. All or nothing; a dry run counts
+    /// the same rows and rolls back.
+    pub fn purge_device(&self, device_id: u64, dry_run: bool) -> Result<PurgeReport> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        let device = device_id as i64;
+        let delete = |sql: &str| -> Result<usize> {
+            Ok(tx.prepare_cached(sql)?.execute(rusqlite::params![device])?)
+        };
+
+        let mut report = PurgeReport::default();
+        // Children first, so each count is rows deleted directly rather than
+        // by cascade
+        report.audit += delete(&format!("DELETE FROM execution_traces WHERE task_id IN ({DEVICE_TASKS})"))?;
+        report.audit += delete(&format!("DELETE FROM prompt_snapshots WHERE task_id IN ({DEVICE_TASKS})"))?;
+        report.audit += delete(
+            "DELETE FROM experiment_outcomes
+             WHERE conversation_id IN (SELECT id FROM conversations WHERE device_id = ?1)",
+        )?;
+        report.audit += delete("DELETE FROM tool_usage WHERE device_id = ?1")?;
+        report.artifacts = delete(&format!(
+            "DELETE FROM artifacts WHERE run_id IN ({DEVICE_TASKS}) OR step_task_id IN ({DEVICE_TASKS})"
+        ))?;
+        report.messages = delete(
+            "DELETE FROM messages
+             WHERE conversation_id IN (SELECT id FROM conversations WHERE device_id = ?1)",
+        )?;
+        report.tasks = delete(&format!("DELETE FROM tasks WHERE id IN ({DEVICE_TASKS})"))?;
+        // Access to other devices' conversations goes too
+        delete("DELETE FROM conversation_shares WHERE device_id = ?1")?;
+        report.conversations = delete("DELETE FROM conversations WHERE device_id = ?1")?;
+        report.memories = delete("DELETE FROM memories WHERE device_id = ?1")?;
+        report.tags = delete("DELETE FROM tags WHERE device_id = ?1")?;
+        report.attachments = delete("DELETE FROM attachments WHERE device_id = ?1")?;
+        delete("DELETE FROM job_notifications WHERE device_id = ?1")?;
+        report.jobs = delete("DELETE FROM background WHERE device_id = ?1")?;
+        report.workflows = delete("DELETE FROM workflows WHERE device_id = ?1")?;
+        report.watches = delete("DELETE FROM path_watches WHERE device_id = ?1")?;
+
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, PurgeReport};
+
+    fn fill(db: &Db, device: u64, text: &str) -> u64 {
+        let conversation = db.create_conversation(device).unwrap();
+        let task = db.create_task(device, conversation, None, text).unwrap();
+        db.add_message(conversation, None, "user", Some(text), None).unwrap();
+        db.add_message(conversation, Some(task as i64), "assistant", Some("noted"), None).unwrap();
+        db.log_execution_trace(task, "Orchestrator", 1, None, text, None, None, None, "final", None).unwrap();
+        db.insert_memory(device, "default", text).unwrap();
+        db.add_conversation_tag(device, conversation, "notes").unwrap();
+        db.store_attachment(device, "a.txt", "text/plain", b"hi").unwrap();
+        let job = db.create_job(device as i64, "chat", &serde_json::json!({}), 0, None).unwrap();
+        db.watch_job(job, device).unwrap();
+        db.save_workflow(device, "briefing", text, &Default::default()).unwrap();
+        db.add_watch(device, "~/inbox", text, None).unwrap();
+        conversation
+    }
+
+    #[test]
+    fn dry_run_counts_what_a_purge_deletes() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let desktop = register_device(&db, "desktop");
+        fill(&db, laptop, "walrus secrets");
+        let kept = fill(&db, desktop, "walrus facts");
+        db.share_conversation(kept, laptop).unwrap();
+
+        let preview = db.purge_device(laptop, true).unwrap();
+        assert_eq!(preview, PurgeReport {
+            conversations: 1,
+            messages: 2,
+            tasks: 1,
+            artifacts: 0,
+            memories: 1,
+            tags: 1,
+            attachments: 1,
+            jobs: 1,
+            workflows: 1,
+            watches: 1,
+            audit: 1,
+        });
+        assert_eq!(db.list_conversations(laptop, 10, 0).unwrap().len(), 1);
+
+        assert_eq!(db.purge_device(laptop, false).unwrap(), preview);
+        assert!(db.list_conversations(laptop, 10, 0).unwrap().is_empty());
+        let indexed = db.query_row_optional(
+            "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'secrets'",
+            [],
+            |row| row.get::<_, i64>(0),
+        ).unwrap();
+        assert_eq!(indexed, Some(0));
+        assert_eq!(db.purge_device(laptop, false).unwrap().total(), 0);
+        assert!(db.get_device_key(laptop).unwrap().is_some());
+
+        // Other devices keep their data, less the laptop's access to it
+        assert_eq!(db.get_messages(kept).unwrap().len(), 2);
+        assert_eq!(db.list_tags(desktop).unwrap().len(), 1);
+        assert!(!db.search_conversations(desktop, "walrus", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// A conversation with answers a device hasn't read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnreadConversation {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    pub unread: u32,
+    /// Id of the newest unread answer; marking up to it reads them all.
+    pub latest_message_id: u64,
+}
+
+/// An answer is a final assistant message: it has text, no tool calls, and
+/// wasn't deleted. Steps in between don't count as unread.
+const UNREAD_ANSWER: &str =
+    "m.role = 'assistant' AND m.message IS NOT NULL AND m.tool_calls IS NULL AND m.deleted_at IS NULL
+     AND m.id > COALESCE(
+         (SELECT last_read_message_id FROM conversation_reads r
+          WHERE r.conversation_id = m.conversation_id AND r.device_id = ?1), 0)";
+
+// ============================================================================
+// READ STATE
+// ============================================================================
+
+// Markers are per device, so each client of a user keeps its own: an answer
+// read in the terminal is still new in a web UI until read there too.
+impl Db {
+    /// Mark a conversation read on a device up to `message_id`, or up to its
+    /// newest message with None. Markers only move forward.
+    pub fn mark_conversation_read(&self, conversation_id: u64, device_id: u64, message_id: Option<u64>) -> Result<()> {
+        let up_to = match message_id {
+            Some(id) => Some(id as i64),
+            None => self.query_row_optional(
+                "SELECT MAX(id) FROM messages WHERE conversation_id = ?1",
+                rusqlite::params![conversation_id as i64],
+                |row| row.get::<_, Option<i64>>(0),
+            )?.flatten(),
+        };
+        let Some(up_to) = up_to else {
+            return Ok(());
+        };
+        self.execute(
+            "INSERT INTO conversation_reads (conversation_id, device_id, last_read_message_id, updated)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(conversation_id, device_id) DO UPDATE SET
+                 last_read_message_id = MAX(last_read_message_id, excluded.last_read_message_id),
+                 updated = excluded.updated",
+            rusqlite::params![conversation_id as i64, device_id as i64, up_to, now()],
+        )?;
+        Ok(())
+    }
+
+    /// How many answers in a conversation the device hasn't read.
+    pub fn unread_count(&self, conversation_id: u64, device_id: u64) -> Result<u32> {
+        let count: Option<i64> = self.query_row_optional(
+            &format!(
+                "SELECT COUNT(*) FROM messages m WHERE m.conversation_id = ?2 AND {}",
+                UNREAD_ANSWER
+            ),
+            rusqlite::params![device_id as i64, conversation_id as i64],
+            |row| row.get(0),
+        )?;
+        Ok(count.unwrap_or(0) as u32)
+    }
+
+    /// Conversations the device owns or was shared that hold unread answers,
+    /// newest answer first. Incognito conversations are left out.
+    pub fn unread_conversations(&self, device_id: u64) -> Result<Vec<UnreadConversation>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT c.id, c.title, COUNT(m.id), MAX(m.id)
+             FROM conversations c
+             JOIN messages m ON m.conversation_id = c.id
+             WHERE c.expires_at IS NULL
+               AND (c.device_id = ?1 OR EXISTS (
+                   SELECT 1 FROM conversation_shares s
+                   WHERE s.conversation_id = c.id AND s.device_id = ?1))
+               AND {}
+             GROUP BY c.id
+             ORDER BY MAX(m.id) DESC",
+            UNREAD_ANSWER
+        ))?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64], |row| {
+            Ok(UnreadConversation {
+                conversation_id: row.get::<_, i64>(0)? as u64,
+                title: row.get(1)?,
+                unread: row.get::<_, i64>(2)? as u32,
+                latest_message_id: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
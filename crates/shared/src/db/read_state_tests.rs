@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, UnreadConversation};
+
+    fn latest_message(db: &Db, conversation_id: u64) -> u64 {
+        db.query_row_optional(
+            "SELECT MAX(id) FROM messages WHERE conversation_id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, i64>(0),
+        ).unwrap().unwrap() as u64
+    }
+
+    #[test]
+    fn answers_are_unread_per_device_until_marked() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let phone = register_device(&db, "phone");
+        let conversation = db.create_conversation(laptop).unwrap();
+        db.share_conversation(conversation, phone).unwrap();
+
+        db.add_message(conversation, None, "user", Some("research rust releases"), None).unwrap();
+        db.add_message(conversation, None, "assistant", None, Some(&Vec::new())).unwrap();
+        db.add_message(conversation, None, "tool", Some("results"), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some("Here is what changed"), None).unwrap();
+        let first_answer = latest_message(&db, conversation);
+        db.add_message(conversation, None, "assistant", Some("And one more thing"), None).unwrap();
+
+        // Only final answers count, not the user's message or tool steps
+        assert_eq!(db.unread_count(conversation, laptop).unwrap(), 2);
+        assert_eq!(
+            db.unread_conversations(phone).unwrap(),
+            [UnreadConversation {
+                conversation_id: conversation,
+                title: None,
+                unread: 2,
+                latest_message_id: latest_message(&db, conversation),
+            }],
+        );
+
+        db.mark_conversation_read(conversation, laptop, Some(first_answer)).unwrap();
+        assert_eq!(db.unread_count(conversation, laptop).unwrap(), 1);
+        db.mark_conversation_read(conversation, laptop, None).unwrap();
+        assert!(db.unread_conversations(laptop).unwrap().is_empty());
+        assert_eq!(db.unread_count(conversation, phone).unwrap(), 2);
+
+        // Markers never move back
+        db.mark_conversation_read(conversation, laptop, Some(first_answer)).unwrap();
+        assert_eq!(db.unread_count(conversation, laptop).unwrap(), 0);
+    }
+
+    #[test]
+    fn other_devices_conversations_are_not_counted() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let stranger = register_device(&db, "stranger");
+        let conversation = db.create_conversation(laptop).unwrap();
+        db.add_message(conversation, None, "assistant", Some("Done"), None).unwrap();
+
+        assert_eq!(db.unread_conversations(laptop).unwrap().len(), 1);
+        assert!(db.unread_conversations(stranger).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::{now, Db};
+
+/// What redacted text is replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// Stored text a redaction scrubs, as (table, column, rows of the
+/// conversation `?1`). Tool calls, traces and snapshots hold JSON, where the
+/// text may also appear escaped.
+const SCRUBBED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("messages", "message", "conversation_id = ?1"),
+    ("messages", "tool_calls", "conversation_id = ?1"),
+    ("tasks", "goal", "conversation_id = ?1"),
+    ("tasks", "title", "conversation_id = ?1"),
+    ("tasks", "plan", "conversation_id = ?1"),
+    ("tasks", "working_memory", "conversation_id = ?1"),
+    ("scratchpads", "content", "conversation_id = ?1"),
+    ("conversation_checkpoints", "scratchpad", "conversation_id = ?1"),
+    ("execution_traces", "system_prompt_preview", CONVERSATION_TASKS),
+    ("execution_traces", "input_context", CONVERSATION_TASKS),
+    ("execution_traces", "reasoning", CONVERSATION_TASKS),
+    ("execution_traces", "tool_calls", CONVERSATION_TASKS),
+    ("execution_traces", "tool_results", CONVERSATION_TASKS),
+    ("artifacts", "tool_args", "run_id IN (SELECT id FROM tasks WHERE conversation_id = ?1)"),
+    ("artifacts", "content", "run_id IN (SELECT id FROM tasks WHERE conversation_id = ?1)"),
+    ("prompt_snapshots", "system_prompt", CONVERSATION_TASKS),
+    ("prompt_snapshots", "memories", CONVERSATION_TASKS),
+    ("prompt_snapshots", "messages", CONVERSATION_TASKS),
+];
+
+const CONVERSATION_TASKS: &str = "task_id IN (SELECT id FROM tasks WHERE conversation_id = ?1)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactOutcome {
+    /// How many stored texts the redaction changed, the message included.
+    Redacted(usize),
+    MessageNotFound,
+    /// The text to redact does not appear in the message.
+    TextNotFound,
+}
+
+// ============================================================================
+// MESSAGE DELETION & REDACTION
+// ============================================================================
+
+impl Db {
+    /// Delete a message's content, leaving a tombstone in its place so the
+    /// conversation's order is kept. Deleted messages are out of the search
+    /// index and the model's context. False if the conversation has no such
+    /// message.
+    pub fn delete_message(&self, conversation_id: u64, message_id: u64) -> Result<bool> {
+        let changed = self.execute(
+            "UPDATE messages
+             SET message = NULL, tool_calls = NULL, confidence = NULL,
+                 deleted_at = COALESCE(deleted_at, ?3)
+             WHERE id = ?1 AND conversation_id = ?2",
+            rusqlite::params![message_id as i64, conversation_id as i64, now()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Replace `text` with [`REDACTED`] in a message and everywhere the
+    /// conversation copied it: other messages, its title, tasks, traces,
+    /// artifacts, prompt snapshots, the scratchpad and its checkpoints, and
+    /// the owner's long-term memories. Without `text`, the message's whole
+    /// content is redacted. The search index follows the messages.
+    pub fn redact_message(&self, conversation_id: u64, message_id: u64, text: Option<&str>) -> Result<RedactOutcome> {
+        self.transaction(|tx| {
+            let stored: Option<(Option<String>, Option<String>)> = tx.query_row(
+                "SELECT message, tool_calls FROM messages WHERE id = ?1 AND conversation_id = ?2",
+                rusqlite::params![message_id as i64, conversation_id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).optional()?;
+            let Some((content, tool_calls)) = stored else {
+                return Ok(RedactOutcome::MessageNotFound);
+            };
+
+            let text = match text {
+                Some(text) => text.to_string(),
+                None => content.clone().unwrap_or_default(),
+            };
+            let variants = text_variants(&text);
+            let in_message = !text.trim().is_empty()
+                && variants.iter().any(|v| {
+                    content.as_deref().is_some_and(|c| c.contains(v.as_str()))
+                        || tool_calls.as_deref().is_some_and(|c| c.contains(v.as_str()))
+                });
+            if !in_message {
+                return Ok(RedactOutcome::TextNotFound);
+            }
+
+            let mut changed = 0;
+            for variant in &variants {
+                for (table, column, scope) in SCRUBBED_COLUMNS {
+                    changed += tx.prepare_cached(&format!(
+                        "UPDATE {table} SET {column} = REPLACE({column}, ?2, ?3)
+                         WHERE {scope} AND instr({column}, ?2) > 0"
+                    ))?.execute(rusqlite::params![conversation_id as i64, variant, REDACTED])?;
+                }
+                // A scrubbed title may clash with another of the device's
+                // titles; that conversation goes untitled instead
+                changed += tx.prepare_cached(
+                    "UPDATE OR IGNORE conversations SET title = REPLACE(title, ?2, ?3)
+                     WHERE id = ?1 AND instr(title, ?2) > 0",
+                )?.execute(rusqlite::params![conversation_id as i64, variant, REDACTED])?;
+                changed += tx.prepare_cached(
+                    "UPDATE conversations SET title = NULL WHERE id = ?1 AND instr(title, ?2) > 0",
+                )?.execute(rusqlite::params![conversation_id as i64, variant])?;
+                // A scrubbed memory may now equal another one; keep just one
+                changed += tx.prepare_cached(
+                    "UPDATE OR REPLACE memories SET content = REPLACE(content, ?2, ?3)
+                     WHERE device_id = (SELECT device_id FROM conversations WHERE id = ?1)
+                       AND instr(content, ?2) > 0",
+                )?.execute(rusqlite::params![conversation_id as i64, variant, REDACTED])?;
+            }
+            Ok(RedactOutcome::Redacted(changed))
+        })
+    }
+}
+
+/// The text as typed, and as it appears inside JSON when that differs.
+fn text_variants(text: &str) -> Vec<String> {
+    let mut variants = vec![text.to_string()];
+    if let Ok(quoted) = serde_json::to_string(text) {
+        let escaped = quoted[1..quoted.len() - 1].to_string();
+        if escaped != text {
+            variants.push(escaped);
+        }
+    }
+    variants
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, RedactOutcome, REDACTED};
+    use crate::memory::MemoryStore;
+    use std::sync::Arc;
+
+    fn message_ids(db: &Db, conversation_id: u64) -> Vec<u64> {
+        db.get_stored_messages(conversation_id).unwrap().iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn deleted_messages_leave_a_tombstone() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("my pin is 4711"), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some("Noted."), None).unwrap();
+        let ids = message_ids(&db, conversation);
+
+        assert!(db.delete_message(conversation, ids[0]).unwrap());
+        assert!(!db.delete_message(conversation, 999).unwrap());
+
+        let stored = db.get_stored_messages(conversation).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored[0].deleted_at.is_some() && stored[0].message.content.is_none());
+        assert!(stored[1].deleted_at.is_none());
+
+        let context = db.get_messages(conversation).unwrap();
+        assert_eq!(context.len(), 1);
+        assert_eq!(context[0].content.as_deref(), Some("Noted."));
+        assert!(db.search_conversations(device, "4711", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn redaction_scrubs_every_copy_of_the_text() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        let secret = "sk-live-abc123";
+
+        db.add_message(conversation, None, "user", Some(&format!("use key {} please", secret)), None).unwrap();
+        db.add_message(conversation, None, "assistant", Some(&format!("Saved {}.", secret)), None).unwrap();
+        db.create_task(device, conversation, None, &format!("store {}", secret)).unwrap();
+        db.write_scratchpad(conversation, &format!("key: {}", secret), false).unwrap();
+        let memories = MemoryStore::new(db.clone());
+        memories.remember(device, "default", &format!("API key is {}", secret)).unwrap();
+        let ids = message_ids(&db, conversation);
+
+        assert_eq!(db.redact_message(conversation, 999, Some(secret)).unwrap(), RedactOutcome::MessageNotFound);
+        assert_eq!(db.redact_message(conversation, ids[1], Some("nope")).unwrap(), RedactOutcome::TextNotFound);
+        let RedactOutcome::Redacted(changed) = db.redact_message(conversation, ids[0], Some(secret)).unwrap() else {
+            panic!("expected a redaction");
+        };
+        assert_eq!(changed, 5);
+
+        let messages = db.get_messages(conversation).unwrap();
+        assert_eq!(messages[0].content.as_deref(), Some(format!("use key {} please", REDACTED).as_str()));
+        assert_eq!(messages[1].content.as_deref(), Some(format!("Saved {}.", REDACTED).as_str()));
+        assert!(!db.get_scratchpad(conversation).unwrap().contains(secret));
+        assert!(memories.list(device, "default").unwrap().iter().all(|m| !m.content.contains(secret)));
+        assert!(db.search_conversations(device, "abc123", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn redacting_without_text_clears_the_whole_message() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("hunter2"), None).unwrap();
+        let ids = message_ids(&db, conversation);
+
+        assert_eq!(db.redact_message(conversation, ids[0], None).unwrap(), RedactOutcome::Redacted(1));
+        assert_eq!(db.get_messages(conversation).unwrap()[0].content.as_deref(), Some(REDACTED));
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// How long answers should run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseLength {
+    Concise,
+    Detailed,
+}
+
+impl ResponseLength {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "concise" | "short" => Some(Self::Concise),
+            "detailed" | "long" => Some(Self::Detailed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Concise => "concise",
+            Self::Detailed => "detailed",
+        }
+    }
+}
+
+/// A device's standing preferences for how it is answered. Set by the user,
+/// not learned, so they live on the device rather than among its memories.
+/// The reply language is kept separately, as the device language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseStyle {
+    /// None leaves length to the model.
+    pub length: Option<ResponseLength>,
+    /// Put code before the explanation of it.
+    pub code_first: bool,
+}
+
+impl ResponseStyle {
+    /// The system prompt section for these preferences, or None when there
+    /// are none. The same style always gives the same text.
+    pub fn instructions(&self) -> Option<String> {
+        let mut asks = Vec::new();
+        match self.length {
+            Some(ResponseLength::Concise) => asks.push(
+                "Concise answers: lead with the answer and keep it to a few sentences or a short list. \
+                 Leave out background and caveats unless they change what the user should do.",
+            ),
+            Some(ResponseLength::Detailed) => asks.push(
+                "Detailed answers: explain the reasoning, cover the relevant options and caveats, \
+                 and give examples where they help.",
+            ),
+            None => {}
+        }
+        if self.code_first {
+            asks.push(
+                "Code first: when the answer involves code or commands, show them before explaining them, \
+                 and keep the explanation short.",
+            );
+        }
+        if asks.is_empty() {
+            return None;
+        }
+
+        let list: Vec<String> = asks.iter().map(|ask| format!("- {}", ask)).collect();
+        Some(format!(
+            "# Response Style\n\nThe user has set these preferences for every answer:\n{}",
+            list.join("\n")
+        ))
+    }
+}
+
+// ============================================================================
+// RESPONSE STYLE
+// ============================================================================
+
+impl Db {
+    /// The device's response style; the default for an unknown device.
+    pub fn get_response_style(&self, device_id: u64) -> Result<ResponseStyle> {
+        let style = self.query_row_optional(
+            "SELECT response_length, code_first FROM devices WHERE id = ?1",
+            rusqlite::params![device_id as i64],
+            |row| {
+                let length: Option<String> = row.get(0)?;
+                Ok(ResponseStyle {
+                    length: length.as_deref().and_then(ResponseLength::parse),
+                    code_first: row.get(1)?,
+                })
+            },
+        )?;
+        Ok(style.unwrap_or_default())
+    }
+
+    pub fn set_response_style(&self, device_id: u64, style: &ResponseStyle) -> Result<()> {
+        self.execute(
+            "UPDATE devices SET response_length = ?1, code_first = ?2 WHERE id = ?3",
+            rusqlite::params![style.length.map(|l| l.as_str()), style.code_first, device_id as i64],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, ResponseLength, ResponseStyle};
+
+    #[test]
+    fn style_round_trips_per_device() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let phone = register_device(&db, "phone");
+
+        assert_eq!(db.get_response_style(laptop).unwrap(), ResponseStyle::default());
+
+        let style = ResponseStyle { length: Some(ResponseLength::Concise), code_first: true };
+        db.set_response_style(laptop, &style).unwrap();
+        assert_eq!(db.get_response_style(laptop).unwrap(), style);
+        assert_eq!(db.get_response_style(phone).unwrap(), ResponseStyle::default());
+
+        db.set_response_style(laptop, &ResponseStyle::default()).unwrap();
+        assert_eq!(db.get_response_style(laptop).unwrap(), ResponseStyle::default());
+    }
+
+    #[test]
+    fn instructions_are_fixed_per_style() {
+        assert!(ResponseStyle::default().instructions().is_none());
+
+        let style = ResponseStyle { length: Some(ResponseLength::Detailed), code_first: true };
+        let text = style.instructions().unwrap();
+        assert_eq!(text, style.instructions().unwrap());
+        assert!(text.starts_with("# Response Style"));
+        assert!(text.find("Detailed answers").unwrap() < text.find("Code first").unwrap());
+
+        let concise = ResponseStyle { length: Some(ResponseLength::Concise), code_first: false };
+        assert!(!concise.instructions().unwrap().contains("Code first"));
+    }
+
+    #[test]
+    fn lengths_parse_by_name() {
+        assert_eq!(ResponseLength::parse(" Concise "), Some(ResponseLength::Concise));
+        assert_eq!(ResponseLength::parse("detailed"), Some(ResponseLength::Detailed));
+        assert_eq!(ResponseLength::parse("medium"), None);
+    }
+}
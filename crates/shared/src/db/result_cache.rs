@@ -0,0 +1,63 @@
+use anyhow::Result;
+
+use super::{now, Db};
+
+/// Longest a cached result is kept, whatever window a request asks for.
+pub const MAX_CACHE_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// A cached specialist answer and when it was produced.
+#[derive(Debug, Clone)]
+pub struct CachedResult {
+    pub content: String,
+    pub created: i64,
+}
+
+/// Cache key form of a request: lowercase, whitespace collapsed, trailing
+/// punctuation dropped, so "Today's top headlines?" and "today's top
+/// headlines" share an entry.
+pub fn normalize_directions(directions: &str) -> String {
+    directions
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_string()
+}
+
+// ============================================================================
+// RESULT CACHE
+// ============================================================================
+
+impl Db {
+    /// The cached answer to these directions, if it is at most `max_age_secs` old.
+    pub fn get_cached_result(&self, specialist: &str, directions: &str, max_age_secs: i64) -> Result<Option<CachedResult>> {
+        let max_age_secs = max_age_secs.min(MAX_CACHE_AGE_SECS);
+        self.query_row_optional(
+            "SELECT content, created FROM result_cache
+             WHERE specialist = ?1 AND directions = ?2 AND created >= ?3",
+            rusqlite::params![specialist, normalize_directions(directions), now() - max_age_secs],
+            |row| Ok(CachedResult {
+                content: row.get(0)?,
+                created: row.get(1)?,
+            }),
+        )
+    }
+
+    /// Store an answer, replacing any older one, and drop entries past the
+    /// maximum age.
+    pub fn cache_result(&self, specialist: &str, directions: &str, content: &str) -> Result<()> {
+        let now = now();
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO result_cache (specialist, directions, content, created)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![specialist, normalize_directions(directions), content, now],
+        )?;
+        conn.execute(
+            "DELETE FROM result_cache WHERE created < ?1",
+            rusqlite::params![now - MAX_CACHE_AGE_SECS],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{normalize_directions, Db};
+
+    #[test]
+    fn equivalent_directions_share_a_cache_entry() {
+        assert_eq!(
+            normalize_directions("  Today's top   Headlines? "),
+            normalize_directions("today's top headlines"),
+        );
+
+        let db = Db::in_memory().unwrap();
+        db.cache_result("WebResearcher", "Today's top headlines?", "1. ...").unwrap();
+
+        let hit = db.get_cached_result("WebResearcher", "today's TOP headlines", 600).unwrap();
+        assert_eq!(hit.unwrap().content, "1. ...");
+        assert!(db.get_cached_result("Cartographer", "today's top headlines", 600).unwrap().is_none());
+
+        // Backdate the entry past the freshness window
+        db.execute("UPDATE result_cache SET created = created - 601", []).unwrap();
+        assert!(db.get_cached_result("WebResearcher", "today's top headlines", 600).unwrap().is_none());
+    }
+}
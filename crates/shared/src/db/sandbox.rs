@@ -0,0 +1,139 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use serde_json::Value;
+
+use super::{row_to_json, Db};
+
+/// Most rows a sandboxed query returns. Anything past this is cut off and flagged.
+pub const MAX_SANDBOX_ROWS: usize = 200;
+
+/// Views the Archivist may query, as (name, SELECT scoped by `{device}`).
+/// Created as TEMP views per call with the device id substituted in, so a query
+/// can only ever see the calling device's rows. Locked sensitive conversations
+/// and their tasks are left out.
+pub const SANDBOX_VIEWS: &[(&str, &str)] = &[
+    ("device_conversations",
+     "SELECT id, title, created, last_accessed FROM conversations
+      WHERE (device_id = {device}
+             OR id IN (SELECT conversation_id FROM conversation_shares WHERE device_id = {device}))
+        AND (lock_hash IS NULL OR unlocked_until > CAST(strftime('%s', 'now') AS INTEGER))"),
+    ("device_messages",
+     "SELECT id, conversation_id, task_id, role, message, m_order, created FROM messages
+      WHERE conversation_id IN (SELECT id FROM device_conversations)"),
+    ("device_tasks",
+     "SELECT id, conversation_id, parent_task_id, goal, title, status, created_at, updated_at, completed_at
+      FROM tasks WHERE device_id = {device}
+        AND conversation_id IN (SELECT id FROM device_conversations)"),
+    ("device_execution_traces",
+     "SELECT task_id, agent_name, iteration, reasoning, tool_calls, tool_results, classification,
+             created_at, llm_duration_ms
+      FROM execution_traces WHERE task_id IN (SELECT id FROM device_tasks)"),
+];
+
+// ============================================================================
+// ARCHIVIST SANDBOX
+// ============================================================================
+
+impl Db {
+    /// Run model-written SQL read-only against the device's views.
+    ///
+    /// The connection is switched to `query_only` and an authorizer rejects
+    /// anything but SELECTs over the `SANDBOX_VIEWS`, so writes, pragmas, ATTACH and
+    /// direct table reads fail at prepare time. Returns a JSON array of rows.
+    pub fn query_sandboxed(
+        &self,
+        device_id: i64,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<String> {
+        let conn = self.reader()?;
+        with_sandbox(&conn, device_id, |conn| {
+            let mut stmt = conn.prepare(sql)?;
+            let column_names: Vec<String> = stmt.column_names()
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+
+            let mut rows = stmt.query(params)?;
+            let mut out: Vec<Value> = Vec::new();
+            let mut truncated = false;
+            while let Some(row) = rows.next()? {
+                if out.len() == MAX_SANDBOX_ROWS {
+                    truncated = true;
+                    break;
+                }
+                out.push(row_to_json(row, &column_names)?);
+            }
+
+            if truncated {
+                return Ok(serde_json::json!({
+                    "rows": out,
+                    "truncated": true,
+                    "note": format!("Only the first {} rows are shown. Add a LIMIT or narrow the WHERE clause.", MAX_SANDBOX_ROWS),
+                }).to_string());
+            }
+            Ok(serde_json::json!(out).to_string())
+        })
+    }
+
+    /// Column names of every sandbox view, for the model to plan queries against.
+    pub fn describe_sandbox(&self, device_id: i64) -> Result<String> {
+        let conn = self.reader()?;
+        with_sandbox(&conn, device_id, |conn| {
+            let mut views = serde_json::Map::new();
+            for (name, _) in SANDBOX_VIEWS {
+                let stmt = conn.prepare(&format!("SELECT * FROM {}", name))?;
+                let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+                views.insert(name.to_string(), serde_json::json!(columns));
+            }
+            Ok(Value::Object(views).to_string())
+        })
+    }
+}
+
+/// Create the device's views, lock the connection down, run `f`, then restore it.
+/// The caller holds the connection mutex throughout, so no other query sees the sandbox.
+/// On a pooled reader only the TEMP schema is writable, so the views are all it can create.
+fn with_sandbox<T>(
+    conn: &Connection,
+    device_id: i64,
+    f: impl FnOnce(&Connection) -> Result<T>,
+) -> Result<T> {
+    for (name, select) in SANDBOX_VIEWS {
+        conn.execute_batch(&format!(
+            "DROP VIEW IF EXISTS temp.{name}; CREATE TEMP VIEW {name} AS {};",
+            select.replace("{device}", &device_id.to_string()),
+        ))?;
+    }
+    conn.execute_batch("PRAGMA query_only = ON;")?;
+    conn.authorizer(Some(authorize));
+
+    let result = f(conn);
+
+    conn.authorizer(None::<fn(AuthContext<'_>) -> Authorization>);
+    conn.execute_batch("PRAGMA query_only = OFF;")?;
+    for (name, _) in SANDBOX_VIEWS {
+        conn.execute_batch(&format!("DROP VIEW IF EXISTS temp.{name};"))?;
+    }
+
+    result
+}
+
+/// Allow reads of the sandbox views and of the tables behind them, and
+/// nothing else. Names must match `SANDBOX_VIEWS` exactly, and a view read
+/// directly must be the TEMP one made for this call: a prefix would let
+/// any later table whose name happens to start with `device_` through.
+fn authorize(ctx: AuthContext<'_>) -> Authorization {
+    let via_sandbox_view = ctx.accessor.is_some_and(is_sandbox_view);
+    match ctx.action {
+        AuthAction::Select | AuthAction::Function { .. } | AuthAction::Recursive => Authorization::Allow,
+        AuthAction::Read { table_name, .. }
+            if (ctx.database_name == Some("temp") && is_sandbox_view(table_name)) || via_sandbox_view => Authorization::Allow,
+        _ => Authorization::Deny,
+    }
+}
+
+fn is_sandbox_view(name: &str) -> bool {
+    SANDBOX_VIEWS.iter().any(|(view, _)| *view == name)
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, MAX_SANDBOX_ROWS};
+
+    fn setup() -> (Db, i64, i64) {
+        let db = Db::in_memory().unwrap();
+        let mine = register_device(&db, "laptop") as i64;
+        let theirs = register_device(&db, "phone") as i64;
+        db.create_conversation(mine as u64).unwrap();
+        db.create_conversation(theirs as u64).unwrap();
+        (db, mine, theirs)
+    }
+
+    #[test]
+    fn select_sees_only_own_rows() {
+        let (db, mine, _) = setup();
+        let rows = db.query_sandboxed(mine, "SELECT id FROM device_conversations", []).unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&rows).unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn writes_are_rejected() {
+        let (db, mine, _) = setup();
+        assert!(db.query_sandboxed(mine, "DELETE FROM conversations", []).is_err());
+        assert!(db.query_sandboxed(mine, "UPDATE devices SET active = 0", []).is_err());
+        assert!(db.query_sandboxed(mine, "DROP VIEW device_conversations", []).is_err());
+
+        let count: i64 = db.query_row_optional("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+            .unwrap().unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn base_tables_and_pragmas_are_rejected() {
+        let (db, mine, _) = setup();
+        assert!(db.query_sandboxed(mine, "SELECT device_key FROM devices", []).is_err());
+        assert!(db.query_sandboxed(mine, "SELECT * FROM conversations", []).is_err());
+        assert!(db.query_sandboxed(mine, "SELECT * FROM sqlite_master", []).is_err());
+        assert!(db.query_sandboxed(mine, "PRAGMA query_only = OFF", []).is_err());
+    }
+
+    #[test]
+    fn prefixed_base_tables_are_rejected() {
+        let (db, mine, _) = setup();
+        db.execute("CREATE TABLE device_secrets (device_id INTEGER, secret TEXT)", []).unwrap();
+        db.execute("INSERT INTO device_secrets VALUES (2, 'theirs')", []).unwrap();
+        assert!(db.query_sandboxed(mine, "SELECT secret FROM device_secrets", []).is_err());
+        assert!(db.query_sandboxed(mine, "SELECT * FROM main.device_conversations", []).is_err());
+    }
+
+    #[test]
+    fn device_groups_are_rejected() {
+        let (db, mine, theirs) = setup();
+        db.set_device_group(theirs as u64, "homelab", &[]).unwrap();
+        assert!(db.query_sandboxed(mine, "SELECT * FROM host_groups", []).is_err());
+        assert!(db.query_sandboxed(mine, "SELECT * FROM host_group_members", []).is_err());
+    }
+
+    #[test]
+    fn connection_is_writable_again_afterwards() {
+        let (db, mine, _) = setup();
+        let _ = db.query_sandboxed(mine, "DELETE FROM conversations", []);
+        assert!(db.create_conversation(mine as u64).is_ok());
+    }
+
+    #[test]
+    fn results_are_capped() {
+        let (db, mine, _) = setup();
+        let sql = format!(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < {})
+             SELECT i FROM n",
+            MAX_SANDBOX_ROWS + 50
+        );
+        let out: serde_json::Value = serde_json::from_str(&db.query_sandboxed(mine, &sql, []).unwrap()).unwrap();
+        assert_eq!(out["truncated"], true);
+        assert_eq!(out["rows"].as_array().unwrap().len(), MAX_SANDBOX_ROWS);
+    }
+
+    #[test]
+    fn pooled_readers_run_sandboxed_queries() {
+        let path = std::env::temp_dir().join(format!("artificer-sandbox-{}.db", std::process::id()));
+        let db = Db::open(&path).unwrap();
+        let mine = register_device(&db, "laptop") as i64;
+        db.create_conversation(mine as u64).unwrap();
+
+        let rows = db.query_sandboxed(mine, "SELECT id FROM device_conversations", []).unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&rows).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(db.reader().unwrap().execute("DELETE FROM conversations", []).is_err());
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
@@ -2,6 +2,12 @@ use rusqlite::Connection;
 use anyhow::Result;
 
 pub fn create_tables(conn: &Connection) -> Result<()> {
+    // Tables renamed after they first shipped, moved before the CREATEs
+    // below would make empty ones under the new name. The group tables
+    // left the `device_` prefix, which reads as a per-device sandbox view.
+    rename_table_if_present(conn, "device_groups", "host_groups")?;
+    rename_table_if_present(conn, "device_group_members", "host_group_members")?;
+
     conn.execute_batch("
         -- Device registry
         CREATE TABLE IF NOT EXISTS devices (
@@ -32,6 +38,20 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_conversations_device ON conversations(device_id);
         CREATE INDEX IF NOT EXISTS idx_conversations_title ON conversations(device_id, title);
 
+        -- Conversation shares
+        -- Grants a device other than the owner read/write access to a conversation.
+        CREATE TABLE IF NOT EXISTS conversation_shares (
+            conversation_id INTEGER NOT NULL,
+            device_id INTEGER NOT NULL,
+            created INTEGER NOT NULL,
+            PRIMARY KEY (conversation_id, device_id),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_conversation_shares_device ON conversation_shares(device_id);
+
         -- Tasks (device-specific)
         -- One row per user request the Orchestrator works on.
         -- Created when the Orchestrator starts work, updated at checkpoints, finalized on completion.
@@ -103,6 +123,21 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_jobs_device ON background(device_id);
         CREATE INDEX IF NOT EXISTS idx_jobs_priority ON background(priority DESC);
 
+        -- Job notifications
+        -- Jobs whose outcome a device wants delivered, e.g. deferred chats.
+        -- delivered flips to 1 once the device has fetched the finished job.
+        CREATE TABLE IF NOT EXISTS job_notifications (
+            job_id INTEGER PRIMARY KEY,
+            device_id INTEGER NOT NULL,
+            delivered INTEGER NOT NULL DEFAULT 0,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (job_id) REFERENCES background(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_job_notifications_device ON job_notifications(device_id, delivered);
+
         -- Execution traces
         -- One row per LLM call in the agent loop, for post-hoc behavioral analysis.
         CREATE TABLE IF NOT EXISTS execution_traces (
@@ -124,6 +159,524 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_traces_task ON execution_traces(task_id);
         CREATE INDEX IF NOT EXISTS idx_traces_task_iteration ON execution_traces(task_id, iteration);
         CREATE INDEX IF NOT EXISTS idx_traces_classification ON execution_traces(classification);
+
+        -- Prompt overrides
+        -- Operator-edited instructions per agent, versioned. At most one version
+        -- per agent is active; with none active the compiled prompt is used.
+        CREATE TABLE IF NOT EXISTS prompt_overrides (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            prompt TEXT NOT NULL,
+            note TEXT,
+            active INTEGER NOT NULL DEFAULT 0,
+            created INTEGER NOT NULL,
+            UNIQUE (agent, version)
+        );
+        CREATE INDEX IF NOT EXISTS idx_prompt_overrides_active ON prompt_overrides(agent, active);
+
+        -- Prompt experiments
+        -- Two instruction variants for one agent; runs are split between them
+        -- by conversation. At most one experiment per agent is active.
+        CREATE TABLE IF NOT EXISTS prompt_experiments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent TEXT NOT NULL,
+            name TEXT NOT NULL,
+            variant_a TEXT NOT NULL,
+            variant_b TEXT NOT NULL,
+            active INTEGER NOT NULL DEFAULT 1,
+            created INTEGER NOT NULL,
+            ended INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_prompt_experiments_active ON prompt_experiments(agent, active);
+
+        -- Experiment outcomes
+        -- One row per agent run under an experiment. feedback is the user's
+        -- -1 / +1 rating, attached after the fact.
+        CREATE TABLE IF NOT EXISTS experiment_outcomes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            experiment_id INTEGER NOT NULL,
+            variant TEXT NOT NULL CHECK (variant IN ('a', 'b')),
+            task_id INTEGER NOT NULL,
+            conversation_id INTEGER NOT NULL,
+            iterations INTEGER NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            success INTEGER NOT NULL,
+            feedback INTEGER,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (experiment_id) REFERENCES prompt_experiments(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_experiment_outcomes_experiment ON experiment_outcomes(experiment_id, variant);
+        CREATE INDEX IF NOT EXISTS idx_experiment_outcomes_conversation ON experiment_outcomes(conversation_id);
+
+        -- Attachments (device-specific)
+        -- Files a device uploads (screenshots, documents) for the assistant to
+        -- refer to by id. Stored inline; sizes are capped at the API.
+        CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            filename TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_attachments_device ON attachments(device_id);
+
+        -- Tags (device-specific)
+        -- Labels a user assigns to conversations, like 'work' or 'personal'.
+        -- Each device keeps its own tag set, including on shared conversations.
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            UNIQUE(device_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS conversation_tags (
+            conversation_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            created INTEGER NOT NULL,
+            PRIMARY KEY (conversation_id, tag_id),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag ON conversation_tags(tag_id);
+
+        -- Long-term memories (device-specific, partitioned by profile)
+        -- Facts about the user kept across conversations. Only memories from
+        -- the conversation's profile reach the prompt.
+        CREATE TABLE IF NOT EXISTS memories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            profile TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            UNIQUE(device_id, profile, content)
+        );
+        CREATE INDEX IF NOT EXISTS idx_memories_profile ON memories(device_id, profile);
+
+        -- Artifacts (task-specific)
+        -- Full tool outputs a specialist hands back, kept per run (the
+        -- Orchestrator's task) and step (the specialist's task) so later
+        -- delegations can reference them by id instead of re-inlining them.
+        CREATE TABLE IF NOT EXISTS artifacts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL,
+            step_task_id INTEGER NOT NULL,
+            tool_name TEXT NOT NULL,
+            tool_args TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (run_id) REFERENCES tasks(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (step_task_id) REFERENCES tasks(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_artifacts_run ON artifacts(run_id);
+
+        -- Calls to metered providers (e.g. Brave Search), counted per device
+        -- per UTC day so quotas can be enforced and usage reported.
+        CREATE TABLE IF NOT EXISTS tool_usage (
+            device_id INTEGER NOT NULL,
+            provider TEXT NOT NULL,
+            day TEXT NOT NULL,
+            calls INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (device_id, provider, day),
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_tool_usage_day ON tool_usage(provider, day);
+
+        -- Specialist answers to general (non-personal) requests, shared by all
+        -- devices so an identical request within its freshness window is
+        -- answered without re-running the specialist.
+        CREATE TABLE IF NOT EXISTS result_cache (
+            specialist TEXT NOT NULL,
+            directions TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            PRIMARY KEY (specialist, directions)
+        );
+
+        -- Free-form notes an agent keeps for a conversation (plans,
+        -- checklists), outliving any one task and its context pruning.
+        CREATE TABLE IF NOT EXISTS scratchpads (
+            conversation_id INTEGER PRIMARY KEY,
+            content TEXT NOT NULL,
+            updated INTEGER NOT NULL,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+
+        -- The assembled prompt of every LLM call in a request made with
+        -- `debug`, so prompt regressions can be diagnosed after the fact.
+        CREATE TABLE IF NOT EXISTS prompt_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL,
+            agent_name TEXT NOT NULL,
+            system_prompt TEXT NOT NULL,
+            memories TEXT NOT NULL,
+            tools TEXT NOT NULL,
+            messages TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES tasks(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_prompt_snapshots_task ON prompt_snapshots(task_id);
+
+        -- Frozen points in a conversation to branch new conversations from:
+        -- the messages up to last_order, and the scratchpad as it was then.
+        CREATE TABLE IF NOT EXISTS conversation_checkpoints (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id INTEGER NOT NULL,
+            name TEXT,
+            last_order INTEGER NOT NULL,
+            scratchpad TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_checkpoints_conversation ON conversation_checkpoints(conversation_id);
+
+        -- Saved requests a device runs by name, with {param} placeholders
+        -- filled in per run. defaults is a JSON object of parameter values.
+        CREATE TABLE IF NOT EXISTS workflows (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            template TEXT NOT NULL,
+            defaults TEXT NOT NULL DEFAULT '{}',
+            created INTEGER NOT NULL,
+            updated INTEGER NOT NULL,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            UNIQUE(device_id, name)
+        );
+
+        -- Paths a device watches, and the request queued for the Orchestrator
+        -- when files under them are added or changed. pattern is a file name
+        -- glob such as '*.pdf'; NULL matches every file.
+        CREATE TABLE IF NOT EXISTS path_watches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            task TEXT NOT NULL,
+            pattern TEXT,
+            created INTEGER NOT NULL,
+            last_triggered INTEGER,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_path_watches_device ON path_watches(device_id);
+
+        -- Named sets of one user's devices (e.g. 'homelab'), for targeting
+        -- tool calls at several machines at once.
+        CREATE TABLE IF NOT EXISTS host_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            created INTEGER NOT NULL,
+            FOREIGN KEY (owner_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            UNIQUE(owner_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS host_group_members (
+            group_id INTEGER NOT NULL,
+            device_id INTEGER NOT NULL,
+            PRIMARY KEY (group_id, device_id),
+            FOREIGN KEY (group_id) REFERENCES host_groups(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+
+        -- Time-limited claims on singleton roles (the background worker) by
+        -- engine instances sharing this database. A lease past its expiry is
+        -- free for another instance to take.
+        CREATE TABLE IF NOT EXISTS instance_leases (
+            role TEXT PRIMARY KEY,
+            holder TEXT NOT NULL,
+            acquired INTEGER NOT NULL,
+            expires INTEGER NOT NULL
+        );
+
+        -- Toolbelts an operator turned off at runtime, or 'all' for every
+        -- tool. Checked before each tool call.
+        CREATE TABLE IF NOT EXISTS tool_switches (
+            name TEXT PRIMARY KEY,
+            reason TEXT,
+            disabled_at INTEGER NOT NULL
+        );
+
+        -- Toolbelts a conversation turned on or off for itself, over the
+        -- defaults of the agents answering in it. No row means the default.
+        CREATE TABLE IF NOT EXISTS conversation_tools (
+            conversation_id INTEGER NOT NULL,
+            toolbelt TEXT NOT NULL,
+            enabled INTEGER NOT NULL,
+            updated INTEGER NOT NULL,
+            PRIMARY KEY (conversation_id, toolbelt),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+
+        -- What each metered provider last said about its API key and quota.
+        -- remaining and quota_limit are the provider's own counts for its
+        -- billing period, NULL when it doesn't report them.
+        CREATE TABLE IF NOT EXISTS provider_health (
+            provider TEXT PRIMARY KEY,
+            ok INTEGER NOT NULL,
+            message TEXT,
+            remaining INTEGER,
+            quota_limit INTEGER,
+            resets_at INTEGER,
+            checked_at INTEGER NOT NULL
+        );
+
+        -- Files under a workspace root, kept so code questions can be answered
+        -- by one search instead of a directory walk. modified is the file's
+        -- mtime when it was last read; a file whose size or mtime moved is
+        -- read again.
+        CREATE TABLE IF NOT EXISTS workspace_files (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            root TEXT NOT NULL,
+            path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            language TEXT,
+            UNIQUE(root, path)
+        );
+
+        -- Text files' contents in runs of lines, searched through
+        -- workspace_chunks_fts. Binary and oversized files have none.
+        CREATE TABLE IF NOT EXISTS workspace_chunks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_id INTEGER NOT NULL,
+            start_line INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            FOREIGN KEY (file_id) REFERENCES workspace_files(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_workspace_chunks_file ON workspace_chunks(file_id);
+
+        -- Vectors of messages and memories for semantic search, each made
+        -- from exactly one of them by the embedding model named in model.
+        -- vector holds the floats little-endian.
+        CREATE TABLE IF NOT EXISTS embeddings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            message_id INTEGER UNIQUE,
+            memory_id INTEGER UNIQUE,
+            model TEXT NOT NULL,
+            vector BLOB NOT NULL,
+            created INTEGER NOT NULL,
+            CHECK ((message_id IS NULL) != (memory_id IS NULL)),
+            FOREIGN KEY (message_id) REFERENCES messages(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (memory_id) REFERENCES memories(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+    ")?;
+
+    // Columns added after their table first shipped
+    add_column_if_missing(conn, "conversations", "language", "TEXT")?;
+    add_column_if_missing(conn, "devices", "language", "TEXT")?;
+    add_column_if_missing(conn, "conversations", "profile", "TEXT NOT NULL DEFAULT 'default'")?;
+    // The device standing for the user a device belongs to; NULL for itself
+    add_column_if_missing(conn, "devices", "owner_id", "INTEGER")?;
+    // When an incognito conversation is deleted; NULL for one that is kept
+    add_column_if_missing(conn, "conversations", "expires_at", "INTEGER")?;
+    // Incognito messages stay out of the search index
+    add_column_if_missing(conn, "messages", "unindexed", "INTEGER NOT NULL DEFAULT 0")?;
+    // Salted hash of the PIN a sensitive conversation is locked with, and
+    // until when it is unlocked
+    add_column_if_missing(conn, "conversations", "lock_hash", "TEXT")?;
+    add_column_if_missing(conn, "conversations", "unlocked_until", "INTEGER")?;
+    // Wrong PINs in a row, and when the next try is allowed after too many
+    add_column_if_missing(conn, "conversations", "pin_failures", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "conversations", "pin_retry_at", "INTEGER")?;
+    // When a delayed job becomes due; NULL runs it as soon as possible
+    add_column_if_missing(conn, "background", "run_at", "INTEGER")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_jobs_run_at ON background(status, run_at);")?;
+    // How sure the specialists behind an answer were, as JSON
+    add_column_if_missing(conn, "messages", "confidence", "TEXT")?;
+    // When a message was deleted; its row stays behind as a tombstone
+    add_column_if_missing(conn, "messages", "deleted_at", "INTEGER")?;
+    // The device's response style; NULL length leaves it to the model
+    add_column_if_missing(conn, "devices", "response_length", "TEXT")?;
+    add_column_if_missing(conn, "devices", "code_first", "INTEGER NOT NULL DEFAULT 0")?;
+    // How much of a conversation may be saved as memories: normal,
+    // facts-only or no-extraction
+    add_column_if_missing(conn, "conversations", "privacy", "TEXT NOT NULL DEFAULT 'normal'")?;
+    // Times in a row the embedding model failed on a message or memory; a
+    // failed row holds an empty vector and the last error
+    add_column_if_missing(conn, "embeddings", "failures", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "embeddings", "error", "TEXT")?;
+
+    create_search_index(conn)?;
+    create_workspace_search_index(conn)?;
+    create_read_markers(conn)?;
+    create_embedding_triggers(conn)?;
+    Ok(())
+}
+
+/// `CREATE TABLE IF NOT EXISTS` leaves existing tables alone, so new columns
+/// on old tables are added here.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+        .exists([column])?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, decl))?;
+    }
+    Ok(())
+}
+
+/// Renames `from` to `to` in a database that still has the old table.
+/// SQLite rewrites foreign keys pointing at it along the way.
+fn rename_table_if_present(conn: &Connection, from: &str, to: &str) -> Result<()> {
+    let exists = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?
+        .exists([from])?;
+    if exists {
+        conn.execute_batch(&format!("ALTER TABLE {} RENAME TO {};", from, to))?;
+    }
+    Ok(())
+}
+
+/// Full-text index over message content, kept in sync with `messages` by triggers.
+/// Triggers are recreated on startup so older databases pick up changes to them.
+/// External-content table: the text lives in `messages`, FTS only stores the index.
+fn create_search_index(conn: &Connection) -> Result<()> {
+    let existed = conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+        [],
+        |_| Ok(()),
+    ).is_ok();
+
+    conn.execute_batch("
+        CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+            message,
+            content = 'messages',
+            content_rowid = 'id'
+        );
+
+        DROP TRIGGER IF EXISTS messages_fts_insert;
+        DROP TRIGGER IF EXISTS messages_fts_delete;
+        DROP TRIGGER IF EXISTS messages_fts_update;
+        CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages
+        WHEN new.unindexed = 0 BEGIN
+            INSERT INTO messages_fts(rowid, message) VALUES (new.id, new.message);
+        END;
+        CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages
+        WHEN old.unindexed = 0 BEGIN
+            INSERT INTO messages_fts(messages_fts, rowid, message) VALUES ('delete', old.id, old.message);
+        END;
+        CREATE TRIGGER messages_fts_update AFTER UPDATE OF message ON messages
+        WHEN old.unindexed = 0 BEGIN
+            INSERT INTO messages_fts(messages_fts, rowid, message) VALUES ('delete', old.id, old.message);
+            INSERT INTO messages_fts(rowid, message) VALUES (new.id, new.message);
+        END;
+    ")?;
+
+    // Databases created before the index existed need a one-time backfill
+    if !existed {
+        conn.execute("INSERT INTO messages_fts(messages_fts) VALUES ('rebuild')", [])?;
+    }
+
+    Ok(())
+}
+
+/// How far each device has read a conversation: the last message id it has
+/// seen. Answers after it are unread on that device; no row means nothing
+/// was read yet. Databases from before read markers existed start with
+/// everything read, so upgrading doesn't flag every old answer as new.
+fn create_read_markers(conn: &Connection) -> Result<()> {
+    let existed = conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'conversation_reads'",
+        [],
+        |_| Ok(()),
+    ).is_ok();
+
+    conn.execute_batch("
+        CREATE TABLE IF NOT EXISTS conversation_reads (
+            conversation_id INTEGER NOT NULL,
+            device_id INTEGER NOT NULL,
+            last_read_message_id INTEGER NOT NULL,
+            updated INTEGER NOT NULL,
+            PRIMARY KEY (conversation_id, device_id),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+                ON DELETE CASCADE ON UPDATE CASCADE,
+            FOREIGN KEY (device_id) REFERENCES devices(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_conversation_reads_device ON conversation_reads(device_id);
+    ")?;
+
+    if !existed {
+        conn.execute_batch("
+            INSERT OR IGNORE INTO conversation_reads (conversation_id, device_id, last_read_message_id, updated)
+            SELECT c.id, c.device_id, MAX(m.id), CAST(strftime('%s', 'now') AS INTEGER)
+            FROM conversations c JOIN messages m ON m.conversation_id = c.id
+            GROUP BY c.id;
+            INSERT OR IGNORE INTO conversation_reads (conversation_id, device_id, last_read_message_id, updated)
+            SELECT s.conversation_id, s.device_id, MAX(m.id), CAST(strftime('%s', 'now') AS INTEGER)
+            FROM conversation_shares s JOIN messages m ON m.conversation_id = s.conversation_id
+            GROUP BY s.conversation_id, s.device_id;
+        ")?;
+    }
+
+    Ok(())
+}
+
+/// Text that changes, by an edit or a redaction, loses its embedding so the
+/// `embed` job makes a new one; a stale vector would find text that is gone.
+/// Recreated on startup like the search index triggers.
+fn create_embedding_triggers(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        DROP TRIGGER IF EXISTS embeddings_message_update;
+        DROP TRIGGER IF EXISTS embeddings_memory_update;
+        CREATE TRIGGER embeddings_message_update AFTER UPDATE OF message ON messages
+        WHEN old.message IS NOT new.message BEGIN
+            DELETE FROM embeddings WHERE message_id = old.id;
+        END;
+        CREATE TRIGGER embeddings_memory_update AFTER UPDATE OF content ON memories
+        WHEN old.content IS NOT new.content BEGIN
+            DELETE FROM embeddings WHERE memory_id = old.id;
+        END;
+    ")?;
+    Ok(())
+}
+
+/// Full-text index over workspace file chunks, kept in sync by triggers like
+/// `messages_fts`. The table shipped with its index, so nothing needs a backfill.
+fn create_workspace_search_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE VIRTUAL TABLE IF NOT EXISTS workspace_chunks_fts USING fts5(
+            content,
+            content = 'workspace_chunks',
+            content_rowid = 'id'
+        );
+
+        DROP TRIGGER IF EXISTS workspace_chunks_fts_insert;
+        DROP TRIGGER IF EXISTS workspace_chunks_fts_delete;
+        CREATE TRIGGER workspace_chunks_fts_insert AFTER INSERT ON workspace_chunks BEGIN
+            INSERT INTO workspace_chunks_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+        CREATE TRIGGER workspace_chunks_fts_delete AFTER DELETE ON workspace_chunks BEGIN
+            INSERT INTO workspace_chunks_fts(workspace_chunks_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        END;
     ")?;
     Ok(())
 }
@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use super::{now, Db};
+
+/// Longest a conversation's scratchpad may grow, in characters.
+pub const MAX_SCRATCHPAD_CHARS: usize = 16_000;
+
+// ============================================================================
+// SCRATCHPADS
+// ============================================================================
+
+impl Db {
+    /// The conversation's scratchpad, empty if nothing was written yet.
+    pub fn get_scratchpad(&self, conversation_id: u64) -> Result<String> {
+        Ok(self.query_row_optional(
+            "SELECT content FROM scratchpads WHERE conversation_id = ?1",
+            rusqlite::params![conversation_id as i64],
+            |row| row.get::<_, String>(0),
+        )?.unwrap_or_default())
+    }
+
+    /// Replace the scratchpad, or add to the end of it when `append` is set.
+    /// Returns its new length in characters; fails if that would exceed
+    /// `MAX_SCRATCHPAD_CHARS`.
+    pub fn write_scratchpad(&self, conversation_id: u64, content: &str, append: bool) -> Result<usize> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        let mut pad = if append {
+            tx.query_row(
+                "SELECT content FROM scratchpads WHERE conversation_id = ?1",
+                rusqlite::params![conversation_id as i64],
+                |row| row.get::<_, String>(0),
+            ).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        if !pad.is_empty() && !pad.ends_with('\n') {
+            pad.push('\n');
+        }
+        pad.push_str(content);
+
+        let chars = pad.chars().count();
+        if chars > MAX_SCRATCHPAD_CHARS {
+            return Err(anyhow::anyhow!(
+                "Scratchpad would be {} characters; the limit is {}. Rewrite it shorter instead of appending.",
+                chars, MAX_SCRATCHPAD_CHARS
+            ));
+        }
+
+        tx.execute(
+            "INSERT INTO scratchpads (conversation_id, content, updated) VALUES (?1, ?2, ?3)
+             ON CONFLICT (conversation_id) DO UPDATE SET content = excluded.content, updated = excluded.updated",
+            rusqlite::params![conversation_id as i64, pad, now()],
+        )?;
+        tx.commit()?;
+        Ok(chars)
+    }
+}
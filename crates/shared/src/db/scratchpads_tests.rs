@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, MAX_SCRATCHPAD_CHARS};
+
+    fn conversation(db: &Db) -> u64 {
+        let device = register_device(db, "laptop");
+        db.create_conversation(device).unwrap()
+    }
+
+    #[test]
+    fn scratchpad_appends_and_replaces() {
+        let db = Db::in_memory().unwrap();
+        let conversation = conversation(&db);
+        assert_eq!(db.get_scratchpad(conversation).unwrap(), "");
+
+        db.write_scratchpad(conversation, "1. search", true).unwrap();
+        db.write_scratchpad(conversation, "2. summarize", true).unwrap();
+        assert_eq!(db.get_scratchpad(conversation).unwrap(), "1. search\n2. summarize");
+
+        db.write_scratchpad(conversation, "done", false).unwrap();
+        assert_eq!(db.get_scratchpad(conversation).unwrap(), "done");
+        assert_eq!(db.get_scratchpad(conversation + 1).unwrap(), "");
+    }
+
+    #[test]
+    fn oversized_scratchpad_is_rejected() {
+        let db = Db::in_memory().unwrap();
+        let conversation = conversation(&db);
+        db.write_scratchpad(conversation, "keep", false).unwrap();
+
+        let huge = "x".repeat(MAX_SCRATCHPAD_CHARS);
+        assert!(db.write_scratchpad(conversation, &huge, true).is_err());
+        assert_eq!(db.get_scratchpad(conversation).unwrap(), "keep");
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, to_fts_query, Db};
+
+    #[test]
+    fn every_term_is_quoted() {
+        assert_eq!(to_fts_query("lisbon  trip"), "\"lisbon\" \"trip\"");
+        assert_eq!(to_fts_query("   "), "");
+        // Operators, prefixes, column filters and quotes are all plain text
+        assert_eq!(
+            to_fts_query("rust AND NOT go* title:x \"quoted\""),
+            "\"rust\" \"AND\" \"NOT\" \"go*\" \"title:x\" \"\"\"quoted\"\"\""
+        );
+    }
+
+    #[test]
+    fn operators_and_punctuation_search_literally() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        db.add_message(conversation, None, "user", Some("should I learn c++ or NOT bother"), None).unwrap();
+
+        for query in ["NOT bother", "c++", "\"unbalanced", "learn OR", "(c++", "title:learn", "NEAR(learn"] {
+            let hits = db.search_conversations(device, query, 10);
+            assert!(hits.is_ok(), "{} failed: {:?}", query, hits.err());
+        }
+        assert_eq!(db.search_conversations(device, "NOT bother", 10).unwrap().len(), 1);
+        // AND is a word to find, not an operator joining the others
+        assert!(db.search_conversations(device, "learn AND bother", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hits_are_grouped_per_conversation() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let busy = db.create_conversation(device).unwrap();
+        db.add_message(busy, None, "user", Some("tell me about the walrus"), None).unwrap();
+        db.add_message(busy, None, "assistant", Some("The walrus is a large marine mammal."), None).unwrap();
+        db.add_message(busy, None, "user", Some("where does a walrus live?"), None).unwrap();
+        let quiet = db.create_conversation(device).unwrap();
+        db.add_message(quiet, None, "user", Some("walrus"), None).unwrap();
+        let tools_only = db.create_conversation(device).unwrap();
+        db.add_message(tools_only, None, "tool", Some("walrus facts"), None).unwrap();
+
+        let hits = db.search_conversations(device, "walrus", 10).unwrap();
+        let mut counts: Vec<(u64, u32)> = hits.iter().map(|h| (h.conversation_id, h.match_count)).collect();
+        counts.sort();
+        assert_eq!(counts, vec![(busy, 3), (quiet, 1)]);
+        assert!(hits.iter().all(|h| h.snippet.contains("**walrus**")));
+
+        assert_eq!(db.search_conversations(device, "walrus", 1).unwrap().len(), 1);
+        assert!(db.search_conversations(device, "narwhal", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_stays_on_the_device() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let phone = register_device(&db, "phone");
+        let conversation = db.create_conversation(laptop).unwrap();
+        db.add_message(conversation, None, "user", Some("walrus"), None).unwrap();
+
+        assert_eq!(db.search_conversations(laptop, "walrus", 10).unwrap().len(), 1);
+        assert!(db.search_conversations(phone, "walrus", 10).unwrap().is_empty());
+    }
+}
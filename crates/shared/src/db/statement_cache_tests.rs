@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::db::{register_device, Db};
+
+    const ROUNDS: usize = 2000;
+
+    const POLL_JOBS: &str = "SELECT id, device_id, method, arguments FROM background
+                             WHERE status = 'pending'
+                             ORDER BY priority DESC, created_at ASC
+                             LIMIT ?1";
+
+    fn time(mut f: impl FnMut()) -> Duration {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            f();
+        }
+        start.elapsed()
+    }
+
+    fn report(name: &str, uncached: Duration, cached: Duration) {
+        println!(
+            "{:<16} prepare {:>8.1?}  prepare_cached {:>8.1?}  ({:.1}x)",
+            name,
+            uncached / ROUNDS as u32,
+            cached / ROUNDS as u32,
+            uncached.as_secs_f64() / cached.as_secs_f64(),
+        );
+    }
+
+    /// Not a correctness test: compares re-preparing hot queries against the
+    /// statement cache. Run with
+    /// `cargo test -p artificer-shared statement_cache -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_hot_queries() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        let conversation = db.create_conversation(device).unwrap();
+        for i in 0..50 {
+            db.add_message(conversation, None, "user", Some(&format!("message {}", i)), None).unwrap();
+        }
+
+        let conn = db.lock().unwrap();
+        let uncached = time(|| {
+            let mut stmt = conn.prepare(POLL_JOBS).unwrap();
+            stmt.query_map([20], |row| row.get::<_, i64>(0)).unwrap().for_each(drop);
+        });
+        let cached = time(|| {
+            let mut stmt = conn.prepare_cached(POLL_JOBS).unwrap();
+            stmt.query_map([20], |row| row.get::<_, i64>(0)).unwrap().for_each(drop);
+        });
+        report("job polling", uncached, cached);
+
+        let lookup = "SELECT role, message, tool_calls FROM messages
+                      WHERE conversation_id = ?1 ORDER BY m_order";
+        let uncached = time(|| {
+            let mut stmt = conn.prepare(lookup).unwrap();
+            stmt.query_map([conversation as i64], |row| row.get::<_, String>(0)).unwrap().for_each(drop);
+        });
+        let cached = time(|| {
+            let mut stmt = conn.prepare_cached(lookup).unwrap();
+            stmt.query_map([conversation as i64], |row| row.get::<_, String>(0)).unwrap().for_each(drop);
+        });
+        report("message history", uncached, cached);
+        drop(conn);
+
+        let start = Instant::now();
+        for i in 0..ROUNDS {
+            db.add_message(conversation, None, "assistant", Some(&format!("reply {}", i)), None).unwrap();
+        }
+        println!("{:<16} add_message {:>8.1?} per call", "message insert", start.elapsed() / ROUNDS as u32);
+    }
+}
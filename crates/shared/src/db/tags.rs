@@ -0,0 +1,170 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, ConversationAccess, Db};
+
+/// Longest tag name, in characters.
+pub const MAX_TAG_CHARS: usize = 32;
+
+/// A tag and how many of the device's conversations carry it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCount {
+    pub name: String,
+    pub conversations: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaggedConversation {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    pub last_accessed: i64,
+    pub tags: Vec<String>,
+}
+
+/// Canonical form of a tag: trimmed, lowercase, inner whitespace as single
+/// dashes. None if it is empty, too long, or has characters other than
+/// letters, digits, `-` and `_`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join("-");
+    let valid = !tag.is_empty()
+        && tag.chars().count() <= MAX_TAG_CHARS
+        && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(tag)
+}
+
+// ============================================================================
+// TAGS
+// ============================================================================
+
+impl Db {
+    /// Tag a conversation the device can access. Returns false if it already had the tag.
+    pub fn add_conversation_tag(&self, device_id: u64, conversation_id: u64, tag: &str) -> Result<bool> {
+        let tag = self.checked_tag(device_id, conversation_id, tag)?;
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        let now = now();
+
+        tx.execute(
+            "INSERT OR IGNORE INTO tags (device_id, name, created) VALUES (?1, ?2, ?3)",
+            rusqlite::params![device_id as i64, tag, now],
+        )?;
+        let added = tx.execute(
+            "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag_id, created)
+             SELECT ?1, id, ?4 FROM tags WHERE device_id = ?2 AND name = ?3",
+            rusqlite::params![conversation_id as i64, device_id as i64, tag, now],
+        )?;
+
+        tx.commit()?;
+        Ok(added > 0)
+    }
+
+    /// Untag a conversation. Returns false if it didn't have the tag. A tag
+    /// left on no conversations is deleted.
+    pub fn remove_conversation_tag(&self, device_id: u64, conversation_id: u64, tag: &str) -> Result<bool> {
+        let tag = self.checked_tag(device_id, conversation_id, tag)?;
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        let removed = tx.execute(
+            "DELETE FROM conversation_tags
+             WHERE conversation_id = ?1
+               AND tag_id = (SELECT id FROM tags WHERE device_id = ?2 AND name = ?3)",
+            rusqlite::params![conversation_id as i64, device_id as i64, tag],
+        )?;
+        tx.execute(
+            "DELETE FROM tags
+             WHERE device_id = ?1 AND name = ?2
+               AND NOT EXISTS (SELECT 1 FROM conversation_tags WHERE tag_id = tags.id)",
+            rusqlite::params![device_id as i64, tag],
+        )?;
+
+        tx.commit()?;
+        Ok(removed > 0)
+    }
+
+    /// The device's tags on one conversation, alphabetically.
+    pub fn conversation_tags(&self, device_id: u64, conversation_id: u64) -> Result<Vec<String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.name FROM conversation_tags ct
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.conversation_id = ?1 AND t.device_id = ?2
+             ORDER BY t.name",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![conversation_id as i64, device_id as i64],
+            |row| row.get(0),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Every tag the device uses, most used first.
+    pub fn list_tags(&self, device_id: u64) -> Result<Vec<TagCount>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.name, COUNT(ct.conversation_id) AS uses
+             FROM tags t
+             LEFT JOIN conversation_tags ct ON ct.tag_id = t.id
+             WHERE t.device_id = ?1
+             GROUP BY t.id
+             ORDER BY uses DESC, t.name",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64], |row| {
+            Ok(TagCount {
+                name: row.get(0)?,
+                conversations: row.get::<_, i64>(1)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Conversations the device tagged with `tag`, most recently used first.
+    pub fn conversations_with_tag(&self, device_id: u64, tag: &str) -> Result<Vec<TaggedConversation>> {
+        let Some(tag) = normalize_tag(tag) else {
+            return Ok(Vec::new());
+        };
+
+        let found: Vec<(u64, Option<String>, i64)> = {
+            let conn = self.reader()?;
+            let mut stmt = conn.prepare(
+                "SELECT c.id, c.title, c.last_accessed
+                 FROM conversations c
+                 JOIN conversation_tags ct ON ct.conversation_id = c.id
+                 JOIN tags t ON t.id = ct.tag_id
+                 WHERE t.device_id = ?1 AND t.name = ?2
+                   AND (c.lock_hash IS NULL OR c.unlocked_until > ?3)
+                 ORDER BY c.last_accessed DESC",
+            )?;
+            let rows = stmt.query_map(rusqlite::params![device_id as i64, tag, now()], |row| {
+                Ok((row.get::<_, i64>(0)? as u64, row.get(1)?, row.get(2)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        found
+            .into_iter()
+            .map(|(conversation_id, title, last_accessed)| {
+                Ok(TaggedConversation {
+                    conversation_id,
+                    title,
+                    last_accessed,
+                    tags: self.conversation_tags(device_id, conversation_id)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Normalize `tag` and make sure the device may tag the conversation.
+    fn checked_tag(&self, device_id: u64, conversation_id: u64, tag: &str) -> Result<String> {
+        let tag = normalize_tag(tag).ok_or_else(|| anyhow::anyhow!(
+            "Invalid tag '{}': use up to {} letters, digits, '-' or '_'", tag, MAX_TAG_CHARS
+        ))?;
+        match self.conversation_access(conversation_id, device_id)? {
+            ConversationAccess::Owner | ConversationAccess::Shared => Ok(tag),
+            _ => Err(anyhow::anyhow!("Conversation {} not found", conversation_id)),
+        }
+    }
+}
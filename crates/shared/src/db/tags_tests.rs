@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{normalize_tag, register_device, Db};
+
+    #[test]
+    fn tags_are_normalized() {
+        assert_eq!(normalize_tag("  #Work "), Some("work".to_string()));
+        assert_eq!(normalize_tag("side project"), Some("side-project".to_string()));
+        assert_eq!(normalize_tag(""), None);
+        assert_eq!(normalize_tag("a/b"), None);
+    }
+
+    #[test]
+    fn tag_search_and_cleanup() {
+        let db = Db::in_memory().unwrap();
+        let mine = register_device(&db, "laptop");
+        let theirs = register_device(&db, "phone");
+        let first = db.create_conversation(mine).unwrap();
+        let second = db.create_conversation(mine).unwrap();
+
+        assert!(db.add_conversation_tag(mine, first, "Work").unwrap());
+        assert!(!db.add_conversation_tag(mine, first, "work").unwrap());
+        db.add_conversation_tag(mine, second, "work").unwrap();
+        db.add_conversation_tag(mine, second, "urgent").unwrap();
+
+        let tagged = db.conversations_with_tag(mine, "work").unwrap();
+        assert_eq!(tagged.len(), 2);
+        assert_eq!(db.conversation_tags(mine, second).unwrap(), vec!["urgent", "work"]);
+        assert_eq!(db.list_tags(mine).unwrap()[0].conversations, 2);
+
+        // Other devices can't tag conversations they can't see, and don't see our tags
+        assert!(db.add_conversation_tag(theirs, first, "work").is_err());
+        assert!(db.conversations_with_tag(theirs, "work").unwrap().is_empty());
+
+        assert!(db.remove_conversation_tag(mine, second, "urgent").unwrap());
+        assert_eq!(db.list_tags(mine).unwrap().len(), 1);
+    }
+}
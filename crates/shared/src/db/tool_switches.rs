@@ -0,0 +1,81 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// Switch name that stops every toolbelt tool at once.
+pub const ALL_TOOLS: &str = "all";
+
+/// A toolbelt (or, for ALL_TOOLS, every toolbelt) an operator turned off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSwitch {
+    pub name: String,
+    pub reason: Option<String>,
+    pub disabled_at: i64,
+}
+
+// ============================================================================
+// TOOL SWITCHES
+// ============================================================================
+
+// A row means its toolbelt is off; turning it back on deletes the row. The
+// table is read on every tool call, so a change applies to the next call.
+impl Db {
+    /// Turn off a toolbelt by name, or every toolbelt with ALL_TOOLS.
+    /// Turning off one already off replaces its reason.
+    pub fn disable_tools(&self, name: &str, reason: Option<&str>) -> Result<ToolSwitch> {
+        let disabled_at = now();
+        self.execute(
+            "INSERT OR REPLACE INTO tool_switches (name, reason, disabled_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![name, reason, disabled_at],
+        )?;
+        Ok(ToolSwitch {
+            name: name.to_string(),
+            reason: reason.map(String::from),
+            disabled_at,
+        })
+    }
+
+    /// Turn a toolbelt back on. Returns false if it wasn't off.
+    pub fn enable_tools(&self, name: &str) -> Result<bool> {
+        let deleted = self.execute(
+            "DELETE FROM tool_switches WHERE name = ?1",
+            rusqlite::params![name],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Every switch that is off, the kill switch first.
+    pub fn disabled_tools(&self) -> Result<Vec<ToolSwitch>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, reason, disabled_at FROM tool_switches
+             ORDER BY name != ?1, name",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![ALL_TOOLS], |row| {
+            Ok(ToolSwitch {
+                name: row.get(0)?,
+                reason: row.get(1)?,
+                disabled_at: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The switch stopping `tool_name` (e.g. `FileSmith::read_file`), if any.
+    pub fn tool_switch_for(&self, tool_name: &str) -> Result<Option<ToolSwitch>> {
+        let toolbelt = tool_name.split("::").next().unwrap_or(tool_name);
+        self.query_row_optional(
+            "SELECT name, reason, disabled_at FROM tool_switches
+             WHERE name IN (?1, ?2)
+             ORDER BY name != ?1
+             LIMIT 1",
+            rusqlite::params![ALL_TOOLS, toolbelt],
+            |row| Ok(ToolSwitch {
+                name: row.get(0)?,
+                reason: row.get(1)?,
+                disabled_at: row.get(2)?,
+            }),
+        )
+    }
+}
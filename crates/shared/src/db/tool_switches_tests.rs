@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{Db, ALL_TOOLS};
+
+    #[test]
+    fn switches_stop_their_toolbelt_or_everything() {
+        let db = Db::in_memory().unwrap();
+        assert!(db.tool_switch_for("FileSmith::read_file").unwrap().is_none());
+
+        db.disable_tools("FileSmith", Some("deleting files")).unwrap();
+        let switch = db.tool_switch_for("FileSmith::read_file").unwrap().unwrap();
+        assert_eq!(switch.name, "FileSmith");
+        assert_eq!(switch.reason.as_deref(), Some("deleting files"));
+        assert!(db.tool_switch_for("Clock::now").unwrap().is_none());
+
+        db.disable_tools(ALL_TOOLS, None).unwrap();
+        assert_eq!(db.tool_switch_for("Clock::now").unwrap().unwrap().name, ALL_TOOLS);
+        assert_eq!(db.tool_switch_for("FileSmith::read_file").unwrap().unwrap().name, ALL_TOOLS);
+        let names: Vec<String> = db.disabled_tools().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, [ALL_TOOLS, "FileSmith"]);
+
+        assert!(db.enable_tools(ALL_TOOLS).unwrap());
+        assert!(!db.enable_tools(ALL_TOOLS).unwrap());
+        assert!(db.tool_switch_for("Clock::now").unwrap().is_none());
+        assert!(db.enable_tools("FileSmith").unwrap());
+        assert!(db.disabled_tools().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db};
+
+    fn conversation_count(db: &Db) -> i64 {
+        db.query_row_optional("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn failed_transaction_writes_nothing() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+
+        let result: anyhow::Result<()> = db.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO conversations (device_id, created, last_accessed) VALUES (?1, 0, 0)",
+                rusqlite::params![device as i64],
+            )?;
+            Err(anyhow::anyhow!("crashed half way"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(conversation_count(&db), 0);
+    }
+
+    #[test]
+    fn conversations_start_fully_configured() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+
+        let id = db.start_conversation(device, Some("work"), true).unwrap();
+
+        assert_eq!(db.get_conversation_profile(id).unwrap(), "work");
+        assert!(db.is_incognito(id).unwrap());
+    }
+
+    #[test]
+    fn bad_profile_leaves_no_conversation() {
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+
+        assert!(db.start_conversation(device, Some("not a profile!"), false).is_err());
+        assert!(db.start_conversation(device + 1, None, false).is_err());
+        assert_eq!(conversation_count(&db), 0);
+    }
+}
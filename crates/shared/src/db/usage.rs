@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+use crate::time;
+
+/// Limits on a metered provider. `None` means unlimited.
+#[derive(Debug, Clone)]
+pub struct Quota {
+    pub provider: &'static str,
+    /// Calls one device may make per UTC day.
+    pub daily_per_device: Option<u64>,
+    /// Calls all devices together may make per UTC calendar month, matching
+    /// how providers bill a shared API key.
+    pub monthly: Option<u64>,
+}
+
+/// Outcome of asking to spend one call against a quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaCheck {
+    /// The call was counted.
+    Allowed,
+    /// The device already made this many calls today.
+    DailyLimit(u64),
+    /// All devices already made this many calls this month.
+    MonthlyLimit(u64),
+}
+
+/// Calls a device made to one provider on one day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsage {
+    pub provider: String,
+    /// `2026-03-14`, UTC.
+    pub day: String,
+    pub calls: u64,
+}
+
+/// Today's date in UTC, which quota days are counted in.
+fn today() -> String {
+    time::at(now(), "UTC").map(|t| t.date).unwrap_or_default()
+}
+
+// ============================================================================
+// USAGE
+// ============================================================================
+
+impl Db {
+    /// Count one call against `quota` unless a limit is already reached.
+    /// Checking and counting happen in one transaction, so concurrent callers
+    /// can't overshoot.
+    pub fn consume_quota(&self, device_id: u64, quota: &Quota) -> Result<QuotaCheck> {
+        let day = today();
+        let month = format!("{}%", &day[..day.len().min(8)]);
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        if let Some(limit) = quota.daily_per_device {
+            let calls: i64 = tx.query_row(
+                "SELECT COALESCE(SUM(calls), 0) FROM tool_usage
+                 WHERE device_id = ?1 AND provider = ?2 AND day = ?3",
+                rusqlite::params![device_id as i64, quota.provider, day],
+                |row| row.get(0),
+            )?;
+            if calls as u64 >= limit {
+                return Ok(QuotaCheck::DailyLimit(limit));
+            }
+        }
+        if let Some(limit) = quota.monthly {
+            let calls: i64 = tx.query_row(
+                "SELECT COALESCE(SUM(calls), 0) FROM tool_usage
+                 WHERE provider = ?1 AND day LIKE ?2",
+                rusqlite::params![quota.provider, month],
+                |row| row.get(0),
+            )?;
+            if calls as u64 >= limit {
+                return Ok(QuotaCheck::MonthlyLimit(limit));
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO tool_usage (device_id, provider, day, calls) VALUES (?1, ?2, ?3, 1)
+             ON CONFLICT (device_id, provider, day) DO UPDATE SET calls = calls + 1",
+            rusqlite::params![device_id as i64, quota.provider, day],
+        )?;
+        tx.commit()?;
+        Ok(QuotaCheck::Allowed)
+    }
+
+    /// The device's calls to metered providers over the last `days` days
+    /// (today included), newest first.
+    pub fn get_tool_usage(&self, device_id: u64, days: u32) -> Result<Vec<ToolUsage>> {
+        let since = time::at(now() - days.saturating_sub(1) as i64 * 86_400, "UTC")
+            .map(|t| t.date)
+            .unwrap_or_default();
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT provider, day, calls FROM tool_usage
+             WHERE device_id = ?1 AND day >= ?2
+             ORDER BY day DESC, provider",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![device_id as i64, since],
+            |row| Ok(ToolUsage {
+                provider: row.get(0)?,
+                day: row.get(1)?,
+                calls: row.get::<_, i64>(2)? as u64,
+            }),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Calls the device made to a provider so far this UTC day.
+    pub fn get_daily_usage(&self, device_id: u64, provider: &str) -> Result<u64> {
+        let calls = self.query_row_optional(
+            "SELECT calls FROM tool_usage WHERE device_id = ?1 AND provider = ?2 AND day = ?3",
+            rusqlite::params![device_id as i64, provider, today()],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0);
+        Ok(calls as u64)
+    }
+
+    /// Calls all devices made to a provider so far this UTC month.
+    pub fn get_monthly_usage(&self, provider: &str) -> Result<u64> {
+        let day = today();
+        let month = format!("{}%", &day[..day.len().min(8)]);
+        let calls = self.query_row_optional(
+            "SELECT COALESCE(SUM(calls), 0) FROM tool_usage WHERE provider = ?1 AND day LIKE ?2",
+            rusqlite::params![provider, month],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0);
+        Ok(calls as u64)
+    }
+}
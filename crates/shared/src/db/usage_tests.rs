@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, Db, Quota, QuotaCheck};
+
+    #[test]
+    fn quotas_stop_counting_at_their_limit() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let phone = register_device(&db, "phone");
+        let quota = Quota { provider: "brave", daily_per_device: Some(2), monthly: Some(3) };
+
+        assert_eq!(db.consume_quota(laptop, &quota).unwrap(), QuotaCheck::Allowed);
+        assert_eq!(db.consume_quota(laptop, &quota).unwrap(), QuotaCheck::Allowed);
+        assert_eq!(db.consume_quota(laptop, &quota).unwrap(), QuotaCheck::DailyLimit(2));
+
+        // The monthly limit is shared by every device
+        assert_eq!(db.consume_quota(phone, &quota).unwrap(), QuotaCheck::Allowed);
+        assert_eq!(db.consume_quota(phone, &quota).unwrap(), QuotaCheck::MonthlyLimit(3));
+
+        let usage = db.get_tool_usage(laptop, 1).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].calls, 2);
+        assert_eq!(db.get_daily_usage(phone, "brave").unwrap(), 1);
+        assert_eq!(db.get_monthly_usage("brave").unwrap(), 3);
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{now, set_title, Db};
+
+/// Most paths one device may watch.
+pub const MAX_WATCHES: usize = 20;
+
+/// Longest request a watch queues, in characters.
+pub const MAX_WATCH_TASK_CHARS: usize = 4_000;
+
+/// Most changed files one trigger passes along.
+pub const MAX_WATCH_TRIGGER_PATHS: usize = 50;
+
+/// A path the device's envoy watches, and the request it queues when files
+/// under it are added or changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathWatch {
+    pub id: u64,
+    /// As the user gave it; `~/` is the home directory of the watching machine.
+    pub path: String,
+    pub task: String,
+    /// File name glob such as `*.pdf`; None for every file.
+    pub pattern: Option<String>,
+    pub created: i64,
+    pub last_triggered: Option<i64>,
+}
+
+impl PathWatch {
+    /// Whether a change to a file of this name should trigger the watch.
+    pub fn matches(&self, file_name: &str) -> bool {
+        self.pattern.as_deref().is_none_or(|pattern| watch_pattern_matches(pattern, file_name))
+    }
+}
+
+/// Match a file name against a glob where `*` is any run of characters and
+/// `?` is one character, ignoring case.
+pub fn watch_pattern_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has taken so far
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            star = Some((star_p, star_n + 1));
+            p = star_p + 1;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+const WATCH_COLUMNS: &str = "id, path, task, pattern, created, last_triggered";
+
+// ============================================================================
+// PATH WATCHES
+// ============================================================================
+
+impl Db {
+    /// Add a watch for the device. Fails once it has [`MAX_WATCHES`].
+    pub fn add_watch(&self, device_id: u64, path: &str, task: &str, pattern: Option<&str>) -> Result<u64> {
+        self.transaction(|tx| {
+            let count: i64 = tx.prepare_cached("SELECT COUNT(*) FROM path_watches WHERE device_id = ?1")?
+                .query_row(rusqlite::params![device_id as i64], |row| row.get(0))?;
+            if count as usize >= MAX_WATCHES {
+                bail!("A device can watch at most {} paths", MAX_WATCHES);
+            }
+            tx.prepare_cached(
+                "INSERT INTO path_watches (device_id, path, task, pattern, created)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?.execute(rusqlite::params![device_id as i64, path, task, pattern, now()])?;
+            Ok(tx.last_insert_rowid() as u64)
+        })
+    }
+
+    /// A watch, if it belongs to `device_id`.
+    pub fn get_watch(&self, device_id: u64, watch_id: u64) -> Result<Option<PathWatch>> {
+        self.query_row_optional(
+            &format!("SELECT {} FROM path_watches WHERE id = ?1 AND device_id = ?2", WATCH_COLUMNS),
+            rusqlite::params![watch_id as i64, device_id as i64],
+            watch_from_row,
+        )
+    }
+
+    /// The device's watches, oldest first.
+    pub fn list_watches(&self, device_id: u64) -> Result<Vec<PathWatch>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            &format!("SELECT {} FROM path_watches WHERE device_id = ?1 ORDER BY id", WATCH_COLUMNS),
+        )?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64], watch_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Delete a watch. False if the device has none with that id.
+    pub fn delete_watch(&self, device_id: u64, watch_id: u64) -> Result<bool> {
+        let removed = self.execute(
+            "DELETE FROM path_watches WHERE id = ?1 AND device_id = ?2",
+            rusqlite::params![watch_id as i64, device_id as i64],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Record that the watch just queued its request.
+    /// Queue a watch's task as an `agent` job working toward `goal`, in a
+    /// new conversation titled after the watch, delivered to the device when
+    /// it finishes. One transaction, so a failure leaves no empty
+    /// conversation behind. Returns the job and conversation ids.
+    pub fn queue_watch_task(&self, device_id: u64, watch: &PathWatch, goal: &str) -> Result<(u64, u64)> {
+        let now = now();
+        self.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO conversations (device_id, created, last_accessed) VALUES (?1, ?2, ?2)",
+                rusqlite::params![device_id as i64, now],
+            )?;
+            let conversation_id = tx.last_insert_rowid() as u64;
+            set_title(tx, conversation_id, device_id as i64, &format!("Watch: {}", watch.path))?;
+
+            let arguments = serde_json::json!({
+                "conversation_id": conversation_id,
+                "agent": "Orchestrator",
+                "goal": goal,
+            });
+            tx.execute(
+                "INSERT INTO background (device_id, method, arguments, priority, status, created_at)
+                 VALUES (?1, 'agent', ?2, 1, 'pending', ?3)",
+                rusqlite::params![device_id as i64, arguments.to_string(), now],
+            )?;
+            let job_id = tx.last_insert_rowid() as u64;
+            tx.execute(
+                "INSERT OR IGNORE INTO job_notifications (job_id, device_id, created) VALUES (?1, ?2, ?3)",
+                rusqlite::params![job_id as i64, device_id as i64, now],
+            )?;
+            tx.execute(
+                "UPDATE path_watches SET last_triggered = ?1 WHERE id = ?2",
+                rusqlite::params![now, watch.id as i64],
+            )?;
+            Ok((job_id, conversation_id))
+        })
+    }
+}
+
+fn watch_from_row(row: &rusqlite::Row) -> rusqlite::Result<PathWatch> {
+    Ok(PathWatch {
+        id: row.get::<_, i64>(0)? as u64,
+        path: row.get(1)?,
+        task: row.get(2)?,
+        pattern: row.get(3)?,
+        created: row.get(4)?,
+        last_triggered: row.get(5)?,
+    })
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{register_device, watch_pattern_matches, Db, MAX_WATCHES};
+
+    #[test]
+    fn patterns_match_file_names() {
+        assert!(watch_pattern_matches("*.pdf", "Invoice.PDF"));
+        assert!(watch_pattern_matches("report-??.csv", "report-03.csv"));
+        assert!(watch_pattern_matches("*scan*", "2024 scan 1.png"));
+        assert!(watch_pattern_matches("*", ""));
+        assert!(!watch_pattern_matches("*.pdf", "invoice.pdf.part"));
+        assert!(!watch_pattern_matches("report-??.csv", "report-3.csv"));
+    }
+
+    #[test]
+    fn watches_belong_to_their_device() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let desktop = register_device(&db, "desktop");
+
+        let id = db.add_watch(laptop, "~/inbox", "Summarize the new files", Some("*.pdf")).unwrap();
+        let watch = db.get_watch(laptop, id).unwrap().unwrap();
+        assert!(watch.matches("notes.pdf"));
+        assert!(!watch.matches("notes.txt"));
+        assert_eq!(watch.last_triggered, None);
+
+        assert!(db.get_watch(desktop, id).unwrap().is_none());
+        assert!(!db.delete_watch(desktop, id).unwrap());
+
+        db.queue_watch_task(laptop, &watch, "Summarize the new files").unwrap();
+        assert!(db.list_watches(laptop).unwrap()[0].last_triggered.is_some());
+        assert!(db.delete_watch(laptop, id).unwrap());
+        assert!(db.list_watches(laptop).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_trigger_queues_the_task_in_its_own_conversation() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let id = db.add_watch(laptop, "~/inbox", "Summarize the new files", None).unwrap();
+        let watch = db.get_watch(laptop, id).unwrap().unwrap();
+
+        let (job_id, conversation_id) = db.queue_watch_task(laptop, &watch, "Summarize a.pdf").unwrap();
+        let job = db.get_job(job_id).unwrap().unwrap();
+        assert_eq!((job.method.as_str(), job.status.as_str()), ("agent", "pending"));
+        assert_eq!(job.arguments["conversation_id"], conversation_id);
+        assert_eq!(job.arguments["goal"], "Summarize a.pdf");
+        assert_eq!(db.get_conversation_title(conversation_id).unwrap().as_deref(), Some("Watch_inbox"));
+
+        // Each trigger gets a conversation of its own
+        let (_, second) = db.queue_watch_task(laptop, &watch, "Summarize b.pdf").unwrap();
+        assert_ne!(second, conversation_id);
+        assert_eq!(db.get_conversation_title(second).unwrap().as_deref(), Some("Watch_inbox_1"));
+    }
+
+    #[test]
+    fn a_device_has_a_limited_number_of_watches() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        for i in 0..MAX_WATCHES {
+            db.add_watch(laptop, &format!("~/dir{}", i), "Tidy it", None).unwrap();
+        }
+        assert!(db.add_watch(laptop, "~/one-more", "Tidy it", None).is_err());
+    }
+}
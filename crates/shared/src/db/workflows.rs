@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Db};
+
+/// Longest workflow name, in characters.
+pub const MAX_WORKFLOW_NAME_CHARS: usize = 48;
+
+/// Longest workflow template, in characters.
+pub const MAX_WORKFLOW_CHARS: usize = 8_000;
+
+/// A saved request, run by name. `{param}` placeholders in the template are
+/// filled from the run's arguments, falling back to `defaults`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workflow {
+    pub name: String,
+    pub template: String,
+    pub defaults: BTreeMap<String, String>,
+    pub created: i64,
+    pub updated: i64,
+}
+
+impl Workflow {
+    /// The template's parameters, in order of first use.
+    pub fn parameters(&self) -> Vec<String> {
+        workflow_parameters(&self.template)
+    }
+
+    /// The template with every placeholder filled, or the names of the
+    /// parameters neither `arguments` nor the defaults give a value for.
+    pub fn render(&self, arguments: &BTreeMap<String, String>) -> std::result::Result<String, Vec<String>> {
+        let value = |name: &str| arguments.get(name).or_else(|| self.defaults.get(name));
+
+        let missing: Vec<String> = self.parameters()
+            .into_iter()
+            .filter(|name| value(name).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+
+        let mut rendered = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some((before, name, after)) = next_placeholder(rest) {
+            rendered.push_str(before);
+            rendered.push_str(value(name).map(String::as_str).unwrap_or_default());
+            rest = after;
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+/// Canonical form of a workflow name, as for tags: trimmed, lowercase,
+/// inner whitespace as single dashes. None if it is empty, too long, or has
+/// characters other than letters, digits, `-` and `_`.
+pub fn normalize_workflow_name(name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    let name = name.split_whitespace().collect::<Vec<_>>().join("-");
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_WORKFLOW_NAME_CHARS
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(name)
+}
+
+/// The `{param}` placeholders in a template, in order of first use. Braces
+/// around anything but letters, digits and `_` are left as text.
+pub fn workflow_parameters(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some((_, name, after)) = next_placeholder(rest) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = after;
+    }
+    names
+}
+
+/// Split off the text before the next placeholder, its name, and the text after.
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+    let mut from = 0;
+    while let Some(open) = text[from..].find('{').map(|i| from + i) {
+        let close = text[open..].find('}').map(|i| open + i)?;
+        let name = &text[open + 1..close];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Some((&text[..open], name, &text[close + 1..]));
+        }
+        from = open + 1;
+    }
+    None
+}
+
+// ============================================================================
+// WORKFLOWS
+// ============================================================================
+
+impl Db {
+    /// Save a workflow under its normalized name, replacing one of the same
+    /// name.
+    pub fn save_workflow(
+        &self,
+        device_id: u64,
+        name: &str,
+        template: &str,
+        defaults: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let name = normalize_workflow_name(name)
+            .ok_or_else(|| anyhow::anyhow!("Invalid workflow name: {}", name))?;
+        let now = now();
+        self.execute(
+            "INSERT INTO workflows (device_id, name, template, defaults, created, updated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+             ON CONFLICT (device_id, name) DO UPDATE SET
+                 template = excluded.template, defaults = excluded.defaults, updated = excluded.updated",
+            rusqlite::params![device_id as i64, name, template, serde_json::to_string(defaults)?, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_workflow(&self, device_id: u64, name: &str) -> Result<Option<Workflow>> {
+        let Some(name) = normalize_workflow_name(name) else {
+            return Ok(None);
+        };
+        self.query_row_optional(
+            "SELECT name, template, defaults, created, updated FROM workflows
+             WHERE device_id = ?1 AND name = ?2",
+            rusqlite::params![device_id as i64, name],
+            workflow_from_row,
+        )
+    }
+
+    /// The device's workflows, by name.
+    pub fn list_workflows(&self, device_id: u64) -> Result<Vec<Workflow>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, template, defaults, created, updated FROM workflows
+             WHERE device_id = ?1
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![device_id as i64], workflow_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Delete a workflow. False if the device has none of that name.
+    pub fn delete_workflow(&self, device_id: u64, name: &str) -> Result<bool> {
+        let Some(name) = normalize_workflow_name(name) else {
+            return Ok(false);
+        };
+        let removed = self.execute(
+            "DELETE FROM workflows WHERE device_id = ?1 AND name = ?2",
+            rusqlite::params![device_id as i64, name],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+fn workflow_from_row(row: &rusqlite::Row) -> rusqlite::Result<Workflow> {
+    let defaults: String = row.get(2)?;
+    Ok(Workflow {
+        name: row.get(0)?,
+        template: row.get(1)?,
+        defaults: serde_json::from_str(&defaults).unwrap_or_default(),
+        created: row.get(3)?,
+        updated: row.get(4)?,
+    })
+}
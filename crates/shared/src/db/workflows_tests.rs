@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::db::{normalize_workflow_name, register_device, workflow_parameters, Db};
+
+    fn args(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn placeholders_are_found_and_filled() {
+        let template = "News on {topic} and {topic_2}, then my {day} calendar. Keep {topic} short; ignore {not a param} and {}.";
+        assert_eq!(workflow_parameters(template), ["topic", "topic_2", "day"]);
+        assert_eq!(normalize_workflow_name(" Morning Briefing "), Some("morning-briefing".to_string()));
+        assert_eq!(normalize_workflow_name("morning/briefing"), None);
+
+        let db = Db::in_memory().unwrap();
+        let device = register_device(&db, "laptop");
+        db.save_workflow(device, "Morning Briefing", template, &args(&[("day", "today's")])).unwrap();
+        let workflow = db.get_workflow(device, "morning-briefing").unwrap().unwrap();
+
+        assert_eq!(workflow.render(&args(&[("topic", "rust")])), Err(vec!["topic_2".to_string()]));
+        assert_eq!(
+            workflow.render(&args(&[("topic", "rust"), ("topic_2", "go"), ("day", "Monday's")])).unwrap(),
+            "News on rust and go, then my Monday's calendar. Keep rust short; ignore {not a param} and {}."
+        );
+    }
+
+    #[test]
+    fn workflows_are_saved_per_device() {
+        let db = Db::in_memory().unwrap();
+        let laptop = register_device(&db, "laptop");
+        let desktop = register_device(&db, "desktop");
+
+        db.save_workflow(laptop, "briefing", "news", &BTreeMap::new()).unwrap();
+        db.save_workflow(laptop, "briefing", "news and weather", &BTreeMap::new()).unwrap();
+        db.save_workflow(laptop, "standup", "what did I do yesterday?", &BTreeMap::new()).unwrap();
+
+        let names: Vec<_> = db.list_workflows(laptop).unwrap().into_iter().map(|w| w.name).collect();
+        assert_eq!(names, ["briefing", "standup"]);
+        assert_eq!(db.get_workflow(laptop, "briefing").unwrap().unwrap().template, "news and weather");
+        assert!(db.get_workflow(desktop, "briefing").unwrap().is_none());
+
+        assert!(!db.delete_workflow(desktop, "briefing").unwrap());
+        assert!(db.delete_workflow(laptop, "briefing").unwrap());
+        assert!(db.get_workflow(laptop, "briefing").unwrap().is_none());
+    }
+}
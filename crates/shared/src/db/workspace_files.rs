@@ -0,0 +1,151 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{to_fts_query, Db, SNIPPET_HIGHLIGHT};
+
+/// A file as the workspace index last saw it. `path` is relative to the
+/// root, with `/` separators.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: i64,
+    pub language: Option<String>,
+}
+
+/// A match in the contents of an indexed file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexHit {
+    pub path: String,
+    /// First line (1-based) of the section the match is in.
+    pub line: u64,
+    pub language: Option<String>,
+    pub snippet: String,
+}
+
+// ============================================================================
+// WORKSPACE INDEX
+// ============================================================================
+
+impl Db {
+    /// Every file indexed under `root`, by path.
+    pub fn indexed_files(&self, root: &str) -> Result<Vec<IndexedFile>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT path, size, modified, language FROM workspace_files
+             WHERE root = ?1
+             ORDER BY path",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![root], indexed_file_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Record a file under `root` with its contents as `(start_line, text)`
+    /// chunks, replacing whatever was indexed for it before.
+    pub fn index_file(&self, root: &str, file: &IndexedFile, chunks: &[(u64, String)]) -> Result<()> {
+        self.transaction(|tx| {
+            tx.prepare_cached(
+                "INSERT INTO workspace_files (root, path, size, modified, language)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (root, path) DO UPDATE SET
+                     size = excluded.size, modified = excluded.modified, language = excluded.language",
+            )?.execute(rusqlite::params![root, file.path, file.size as i64, file.modified, file.language])?;
+            let file_id: i64 = tx.prepare_cached(
+                "SELECT id FROM workspace_files WHERE root = ?1 AND path = ?2",
+            )?.query_row(rusqlite::params![root, file.path], |row| row.get(0))?;
+
+            tx.prepare_cached("DELETE FROM workspace_chunks WHERE file_id = ?1")?
+                .execute(rusqlite::params![file_id])?;
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO workspace_chunks (file_id, start_line, content) VALUES (?1, ?2, ?3)",
+            )?;
+            for (start_line, content) in chunks {
+                insert.execute(rusqlite::params![file_id, *start_line as i64, content])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Drop files from the index. Returns how many were indexed.
+    pub fn unindex_files(&self, root: &str, paths: &[String]) -> Result<usize> {
+        self.transaction(|tx| {
+            let mut removed = 0;
+            for path in paths {
+                // Chunks go first so the search index hears about each one
+                tx.prepare_cached(
+                    "DELETE FROM workspace_chunks WHERE file_id IN
+                     (SELECT id FROM workspace_files WHERE root = ?1 AND path = ?2)",
+                )?.execute(rusqlite::params![root, path])?;
+                removed += tx.prepare_cached("DELETE FROM workspace_files WHERE root = ?1 AND path = ?2")?
+                    .execute(rusqlite::params![root, path])?;
+            }
+            Ok(removed)
+        })
+    }
+
+    /// Full-text search over the contents of the files indexed under `root`,
+    /// best matches first.
+    pub fn search_workspace(&self, root: &str, query: &str, limit: usize) -> Result<Vec<IndexHit>> {
+        let fts_query = to_fts_query(query);
+        if fts_query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT f.path, c.start_line, f.language,
+                    snippet(workspace_chunks_fts, 0, ?3, ?3, '…', 16)
+             FROM workspace_chunks_fts
+             JOIN workspace_chunks c ON c.id = workspace_chunks_fts.rowid
+             JOIN workspace_files f ON f.id = c.file_id
+             WHERE workspace_chunks_fts MATCH ?1 AND f.root = ?2
+             ORDER BY bm25(workspace_chunks_fts)
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![fts_query, root, SNIPPET_HIGHLIGHT, limit as i64],
+            |row| {
+                Ok(IndexHit {
+                    path: row.get(0)?,
+                    line: row.get::<_, i64>(1)? as u64,
+                    language: row.get(2)?,
+                    snippet: row.get(3)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Indexed files under `root` whose path contains `name` (any, if empty),
+    /// optionally only those in `language`, by path.
+    pub fn find_indexed_files(
+        &self,
+        root: &str,
+        name: &str,
+        language: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<IndexedFile>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT path, size, modified, language FROM workspace_files
+             WHERE root = ?1 AND (?2 = '' OR instr(lower(path), lower(?2)) > 0)
+               AND (?3 IS NULL OR language = ?3)
+             ORDER BY path
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![root, name, language, limit as i64],
+            indexed_file_from_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+fn indexed_file_from_row(row: &rusqlite::Row) -> rusqlite::Result<IndexedFile> {
+    Ok(IndexedFile {
+        path: row.get(0)?,
+        size: row.get::<_, i64>(1)? as u64,
+        modified: row.get(2)?,
+        language: row.get(3)?,
+    })
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::db::{Db, IndexedFile};
+
+    fn file(path: &str, modified: i64) -> IndexedFile {
+        IndexedFile {
+            path: path.to_string(),
+            size: 10,
+            modified,
+            language: Some("rust".to_string()),
+        }
+    }
+
+    #[test]
+    fn reindexing_a_file_replaces_its_contents() {
+        let db = Db::in_memory().unwrap();
+        db.index_file("/work", &file("src/lib.rs", 1), &[(1, "fn parse_walrus() {}".to_string())]).unwrap();
+        assert_eq!(db.search_workspace("/work", "parse_walrus", 10).unwrap().len(), 1);
+
+        db.index_file("/work", &file("src/lib.rs", 2), &[(41, "fn parse_narwhal() {}".to_string())]).unwrap();
+        assert!(db.search_workspace("/work", "parse_walrus", 10).unwrap().is_empty());
+        let hits = db.search_workspace("/work", "parse_narwhal", 10).unwrap();
+        assert_eq!((hits[0].path.as_str(), hits[0].line), ("src/lib.rs", 41));
+        assert_eq!(db.indexed_files("/work").unwrap(), [file("src/lib.rs", 2)]);
+    }
+
+    #[test]
+    fn roots_are_kept_apart() {
+        let db = Db::in_memory().unwrap();
+        db.index_file("/work", &file("main.rs", 1), &[(1, "shared words".to_string())]).unwrap();
+        db.index_file("/home", &file("notes.md", 1), &[(1, "shared words".to_string())]).unwrap();
+
+        let hits = db.search_workspace("/work", "shared", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(db.find_indexed_files("/home", "", None, 10).unwrap()[0].path, "notes.md");
+        assert!(db.find_indexed_files("/work", "MAIN", Some("python"), 10).unwrap().is_empty());
+        assert_eq!(db.find_indexed_files("/work", "MAIN", Some("rust"), 10).unwrap().len(), 1);
+
+        assert_eq!(db.unindex_files("/work", &["main.rs".to_string(), "gone.rs".to_string()]).unwrap(), 1);
+        assert!(db.search_workspace("/work", "shared", 10).unwrap().is_empty());
+        assert_eq!(db.search_workspace("/home", "shared", 10).unwrap().len(), 1);
+    }
+}
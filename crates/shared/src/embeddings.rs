@@ -0,0 +1,123 @@
+//! Vector embeddings of messages and memories, for searching them by
+//! meaning rather than by keyword. Vectors come from an Ollama embedding
+//! model and are compared by cosine similarity; a device has few enough of
+//! them that ranking every one per search is fast.
+
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+/// Most characters of a text that are embedded; a longer one is embedded by its start.
+pub const MAX_EMBED_CHARS: usize = 2_000;
+
+/// How long one embedding request may take.
+const EMBED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Embeds text through Ollama's `/api/embeddings`.
+#[derive(Clone)]
+pub struct EmbeddingClient {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+}
+
+impl EmbeddingClient {
+    pub fn new(url: impl Into<String>, model: impl Into<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(EMBED_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            url: url.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+        }
+    }
+
+    /// Built from EMBEDDING_MODEL, if it is set, served at EMBEDDING_URL or
+    /// else at `default_url`.
+    pub fn from_env(default_url: Option<&str>) -> Option<Self> {
+        let set = |var: &str| std::env::var(var).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let model = set("EMBEDDING_MODEL")?;
+        let url = set("EMBEDDING_URL").or_else(|| default_url.map(str::to_string))?;
+        Some(Self::new(url, model))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The embedding of `text`, cut to `MAX_EMBED_CHARS` first.
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let prompt: String = text.chars().take(MAX_EMBED_CHARS).collect();
+        let body: Value = self.client
+            .post(format!("{}/api/embeddings", self.url))
+            .json(&json!({ "model": self.model, "prompt": prompt }))
+            .send()
+            .await
+            .with_context(|| format!("Embedding server {} unreachable", self.url))?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let vector: Vec<f32> = body["embedding"].as_array()
+            .context("Embedding response has no embedding")?
+            .iter()
+            .filter_map(Value::as_f64)
+            .map(|v| v as f32)
+            .collect();
+        if vector.is_empty() {
+            bail!("Embedding model {} returned an empty vector", self.model);
+        }
+        Ok(vector)
+    }
+}
+
+/// Whether `error`, from `EmbeddingClient::embed`, means the server could
+/// not be reached at all, rather than that it failed on the text.
+pub fn server_unreachable(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect() || e.is_timeout())
+}
+
+/// Cosine similarity of two vectors, from -1 to 1. Vectors of different
+/// lengths (made by different models) or with no length score 0.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Indexes of the `top_k` vectors most similar to `query`, with their
+/// similarity, most similar first.
+pub fn nearest<'a>(query: &[f32], vectors: impl IntoIterator<Item = &'a [f32]>, top_k: usize) -> Vec<(usize, f32)> {
+    let mut scored: Vec<(usize, f32)> = vectors.into_iter()
+        .map(|vector| cosine_similarity(query, vector))
+        .enumerate()
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+    scored
+}
+
+/// A vector as stored in the database: its floats, little-endian.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// A vector read back from `encode_vector`'s bytes.
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
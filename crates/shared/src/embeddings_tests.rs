@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use crate::embeddings::{cosine_similarity, decode_vector, encode_vector, nearest};
+
+    #[test]
+    fn similarity_follows_direction_not_length() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mismatched_or_empty_vectors_score_zero() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0, 0.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn nearest_ranks_most_similar_first() {
+        let vectors = [vec![0.0, 1.0], vec![1.0, 0.1], vec![1.0, 1.0]];
+        let ranked = nearest(&[1.0, 0.0], vectors.iter().map(Vec::as_slice), 2);
+        let order: Vec<usize> = ranked.iter().map(|(index, _)| *index).collect();
+        assert_eq!(order, vec![1, 2]);
+        assert!(ranked[0].1 > ranked[1].1);
+    }
+
+    #[test]
+    fn vectors_survive_storage() {
+        let vector = vec![0.25, -1.5, 3.0e-7, f32::MAX];
+        let bytes = encode_vector(&vector);
+        assert_eq!(bytes.len(), vector.len() * 4);
+        assert_eq!(decode_vector(&bytes), vector);
+    }
+}
@@ -0,0 +1,210 @@
+//! Turning file bytes into text the model can read, and recognising the
+//! ones that aren't text at all.
+
+/// A text encoding FileSmith can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1: every byte is a character, so decoding never fails.
+    Latin1,
+}
+
+impl Encoding {
+    /// Parse an encoding name as a tool argument gives it. None for `auto`
+    /// and for names it doesn't know.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "utf-16le" | "utf-16" | "utf16le" | "utf16" => Some(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Self::Utf16Be),
+            "latin-1" | "latin1" | "iso-8859-1" => Some(Self::Latin1),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "latin-1",
+        }
+    }
+
+    /// Bytes per code unit, so cuts land on a unit boundary.
+    fn unit(&self) -> usize {
+        match self {
+            Self::Utf16Le | Self::Utf16Be => 2,
+            Self::Utf8 | Self::Latin1 => 1,
+        }
+    }
+}
+
+/// Guess how `bytes` are encoded: a byte order mark if there is one, UTF-8
+/// if they are valid UTF-8, UTF-16 if every other byte is mostly zero, and
+/// Latin-1 otherwise. None if they look binary.
+pub fn detect(bytes: &[u8]) -> Option<Encoding> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some(Encoding::Utf8);
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some(Encoding::Utf16Le);
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some(Encoding::Utf16Be);
+    }
+
+    let sample = &bytes[..bytes.len().min(8192)];
+    if let Some(utf16) = utf16_without_bom(sample) {
+        return Some(utf16);
+    }
+    if sample.contains(&0) {
+        return None;
+    }
+    // A sample cut mid-character is still UTF-8
+    match std::str::from_utf8(sample) {
+        Ok(_) => Some(Encoding::Utf8),
+        Err(e) if e.error_len().is_none() => Some(Encoding::Utf8),
+        Err(_) if looks_like_text(sample) => Some(Encoding::Latin1),
+        Err(_) => None,
+    }
+}
+
+/// Decode `bytes`, dropping a byte order mark. Lossy decoding replaces what
+/// can't be decoded with U+FFFD; strict decoding fails on it instead.
+pub fn decode(bytes: &[u8], encoding: Encoding, lossy: bool) -> Result<String, String> {
+    match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            if lossy {
+                Ok(String::from_utf8_lossy(bytes).into_owned())
+            } else {
+                std::str::from_utf8(bytes)
+                    .map(str::to_string)
+                    .map_err(|e| format!("not valid UTF-8 at byte {}", e.valid_up_to()))
+            }
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let bom: &[u8] = if encoding == Encoding::Utf16Le { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] };
+            let bytes = bytes.strip_prefix(bom).unwrap_or(bytes);
+            let units = bytes.chunks_exact(2).map(|pair| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            if lossy {
+                Ok(char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect())
+            } else {
+                char::decode_utf16(units)
+                    .collect::<Result<String, _>>()
+                    .map_err(|e| format!("not valid UTF-16: unpaired surrogate {:#06x}", e.unpaired_surrogate()))
+            }
+        }
+        Encoding::Latin1 => Ok(bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+/// At most `max_bytes` from the start (or, with `tail`, the end) of `bytes`,
+/// cut on a character boundary for `encoding`.
+pub fn window(bytes: &[u8], max_bytes: usize, tail: bool, encoding: Encoding) -> &[u8] {
+    if bytes.len() <= max_bytes {
+        return bytes;
+    }
+    let unit = encoding.unit();
+    let max_bytes = max_bytes - max_bytes % unit;
+
+    if !tail {
+        let mut end = max_bytes;
+        if encoding == Encoding::Utf8 {
+            // Back off to the start of a character
+            while end > 0 && bytes[end] & 0xC0 == 0x80 {
+                end -= 1;
+            }
+        }
+        return &bytes[..end];
+    }
+
+    let mut start = bytes.len() - max_bytes;
+    start += start % unit;
+    if encoding == Encoding::Utf8 {
+        while start < bytes.len() && bytes[start] & 0xC0 == 0x80 {
+            start += 1;
+        }
+    }
+    &bytes[start..]
+}
+
+/// What kind of binary file `bytes` start like, as `(description, MIME type)`.
+pub fn sniff(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    const SIGNATURES: &[(&[u8], &str, &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG image", "image/png"),
+        (b"\xFF\xD8\xFF", "JPEG image", "image/jpeg"),
+        (b"GIF87a", "GIF image", "image/gif"),
+        (b"GIF89a", "GIF image", "image/gif"),
+        (b"BM", "BMP image", "image/bmp"),
+        (b"%PDF-", "PDF document", "application/pdf"),
+        (b"PK\x03\x04", "ZIP archive (also docx, xlsx, jar, apk)", "application/zip"),
+        (b"\x1F\x8B", "gzip data", "application/gzip"),
+        (b"BZh", "bzip2 data", "application/x-bzip2"),
+        (b"\xFD7zXZ\x00", "xz data", "application/x-xz"),
+        (b"7z\xBC\xAF\x27\x1C", "7-Zip archive", "application/x-7z-compressed"),
+        (b"\x28\xB5\x2F\xFD", "zstd data", "application/zstd"),
+        (b"\x7FELF", "ELF executable or library", "application/x-elf"),
+        (b"MZ", "Windows executable", "application/vnd.microsoft.portable-executable"),
+        (b"\xCF\xFA\xED\xFE", "Mach-O binary", "application/x-mach-binary"),
+        (b"\xCA\xFE\xBA\xBE", "Mach-O universal binary or Java class", "application/octet-stream"),
+        (b"\x00asm", "WebAssembly module", "application/wasm"),
+        (b"SQLite format 3\x00", "SQLite database", "application/vnd.sqlite3"),
+        (b"ID3", "MP3 audio", "audio/mpeg"),
+        (b"OggS", "Ogg media", "audio/ogg"),
+        (b"fLaC", "FLAC audio", "audio/flac"),
+        (b"\x1A\x45\xDF\xA3", "Matroska or WebM video", "video/webm"),
+    ];
+
+    if let Some((_, description, mime)) = SIGNATURES.iter().find(|(magic, _, _)| bytes.starts_with(magic)) {
+        return Some((*description, *mime));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.len() >= 12 {
+        return match &bytes[8..12] {
+            b"WAVE" => Some(("WAV audio", "audio/wav")),
+            b"WEBP" => Some(("WebP image", "image/webp")),
+            b"AVI " => Some(("AVI video", "video/x-msvideo")),
+            _ => None,
+        };
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return Some(("MP4/QuickTime media", "video/mp4"));
+    }
+    if bytes.len() >= 262 && &bytes[257..262] == b"ustar" {
+        return Some(("tar archive", "application/x-tar"));
+    }
+    None
+}
+
+/// UTF-16 without a byte order mark: most odd (LE) or even (BE) bytes are
+/// zero and the others mostly aren't, as in ASCII text stored as UTF-16.
+fn utf16_without_bom(sample: &[u8]) -> Option<Encoding> {
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let zeros_at = |offset: usize| sample.iter().skip(offset).step_by(2).take(pairs).filter(|&&b| b == 0).count();
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if odd * 10 >= pairs * 9 && even * 10 <= pairs {
+        Some(Encoding::Utf16Le)
+    } else if even * 10 >= pairs * 9 && odd * 10 <= pairs {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Few control characters besides whitespace: text in some 8-bit encoding
+/// rather than binary data.
+fn looks_like_text(sample: &[u8]) -> bool {
+    let control = sample.iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0C))
+        .count();
+    control * 100 <= sample.len()
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{decode, detect, sniff, window, Encoding};
+
+    #[test]
+    fn detects_common_text_encodings() {
+        assert_eq!(detect("naïve café".as_bytes()), Some(Encoding::Utf8));
+        assert_eq!(detect(b"caf\xe9 cr\xe8me"), Some(Encoding::Latin1));
+        assert_eq!(detect(b"h\x00i\x00 \x00t\x00h\x00e\x00r\x00e\x00"), Some(Encoding::Utf16Le));
+        assert_eq!(detect(b"\xFE\xFF\x00h\x00i"), Some(Encoding::Utf16Be));
+        assert_eq!(detect(&[0x7F, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 3, 0, 0x3E, 0]), None);
+    }
+
+    #[test]
+    fn decodes_strictly_or_lossily() {
+        assert_eq!(decode(b"\xFF\xFEh\x00i\x00", Encoding::Utf16Le, false).unwrap(), "hi");
+        assert_eq!(decode(b"caf\xe9", Encoding::Latin1, false).unwrap(), "café");
+        assert_eq!(decode(b"caf\xe9", Encoding::Utf8, true).unwrap(), "caf\u{FFFD}");
+        assert!(decode(b"caf\xe9", Encoding::Utf8, false).is_err());
+        assert_eq!(Encoding::parse("ISO-8859-1"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::parse("auto"), None);
+    }
+
+    #[test]
+    fn windows_cut_on_character_boundaries() {
+        let text = "aé€b".as_bytes(); // 1 + 2 + 3 + 1 bytes
+        assert_eq!(window(text, 4, false, Encoding::Utf8), "aé".as_bytes());
+        assert_eq!(window(text, 3, true, Encoding::Utf8), "b".as_bytes());
+        assert_eq!(window(text, 4, true, Encoding::Utf8), "€b".as_bytes());
+        assert_eq!(window(b"a\x00b\x00c\x00", 3, true, Encoding::Utf16Le), b"c\x00");
+    }
+
+    #[test]
+    fn recognizes_binary_formats() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\x00\x00").map(|s| s.1), Some("image/png"));
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 ").map(|s| s.1), Some("image/webp"));
+        assert_eq!(sniff(b"plain text"), None);
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable error codes returned by the engine API.
+/// Shared so the envoy can match on them instead of parsing messages.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    ValidationFailed,
+    ResourceBusy,
+    ModelUnavailable,
+    ToolFailed,
+    Internal,
+    /// Code not known to this build — newer engine, older client.
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::ResourceBusy => "resource_busy",
+            ErrorCode::ModelUnavailable => "model_unavailable",
+            ErrorCode::ToolFailed => "tool_failed",
+            ErrorCode::Internal => "internal",
+            ErrorCode::Unknown => "unknown",
+        }
+    }
+}
+
+/// One failed check in a `validation_failed` response.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// A model server from hardware.json, as the engine's last probe found it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ModelHost {
+    pub url: String,
+    /// GPUs in hardware.json served from this URL.
+    pub gpus: Vec<String>,
+    pub reachable: bool,
+    /// Why the last probe failed, when it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the server was last probed, in unix seconds; None before the first probe.
+    #[serde(default)]
+    pub checked_at: Option<i64>,
+}
+
+/// JSON body of every non-2xx engine response.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorBody {
+    pub error: String,
+    pub code: ErrorCode,
+    /// First failing field, kept for clients that only show one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Model servers and whether they answer, for `model_unavailable`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<ModelHost>,
+}
@@ -1,4 +1,9 @@
 use serde::{Deserialize, Serialize};
+use crate::db::ConfidenceLevel;
+
+/// Response header on /chat and /chat/resume streams naming the conversation,
+/// so a client can resume a stream that dropped before any event named it.
+pub const CONVERSATION_ID_HEADER: &str = "x-conversation-id";
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -18,6 +23,10 @@ pub enum ChatEvent {
         result: String,
         truncated: bool,
     },
+    /// Waiting for a free model, with `position` requests ahead in line.
+    Queued {
+        position: usize,
+    },
     StreamChunk {
         content: String,
     },
@@ -34,4 +43,87 @@ pub enum ChatEvent {
         task: String,
         content: String,
     },
-}
\ No newline at end of file
+    /// Diagnostics, such as a retried LLM call. Safe to ignore.
+    Debug {
+        task: String,
+        message: String,
+    },
+    /// How sure the answer is, from the specialists' own ratings, and the
+    /// sources it rests on. Sent after the answer when a specialist rated it.
+    Confidence {
+        task: String,
+        level: ConfidenceLevel,
+        sources: Vec<String>,
+    },
+    /// A specialist is paused until the user answers via /chat/continue.
+    Question {
+        conversation_id: u64,
+        question_id: u64,
+        task: String,
+        question: String,
+        options: Vec<String>,
+    },
+    /// The deployment's moderation blocked the user's message, or withheld
+    /// a tool's result when `tool` is set.
+    Refusal {
+        category: String,
+        message: String,
+        #[serde(default)]
+        tool: Option<String>,
+    },
+}
+
+/// What a client sends on a /chat/ws socket after its opening chat request.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatSocketMessage {
+    /// The next message in the conversation, once the current turn is done.
+    Message {
+        message: String,
+    },
+    /// An answer to a specialist's `question`; the oldest open one when
+    /// `question_id` is left out.
+    Answer {
+        #[serde(default)]
+        question_id: Option<u64>,
+        answer: String,
+    },
+    /// Stop the turn in progress. It ends with an `error` and `done`.
+    Cancel,
+}
+
+/// Lifecycle of a queued background job, emitted by the engine's worker.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    JobStarted {
+        job_id: u64,
+        method: String,
+    },
+    /// A step of a multi-step job finished or started, such as one part of
+    /// a long conversation being summarized.
+    JobProgress {
+        job_id: u64,
+        method: String,
+        message: String,
+    },
+    /// Text of the job's final step as the model writes it, like a chat's
+    /// `stream_chunk`. `job_completed` still carries the whole result.
+    JobChunk {
+        job_id: u64,
+        content: String,
+    },
+    JobCompleted {
+        job_id: u64,
+        method: String,
+        conversation_id: Option<u64>,
+        result: String,
+    },
+    JobFailed {
+        job_id: u64,
+        method: String,
+        error: String,
+        /// True when the job went back to pending for another attempt.
+        retrying: bool,
+    },
+}
@@ -2,12 +2,16 @@ use anyhow::Result;
 use serde_json::Value;
 use crate::tools::get_tool_schema;
 use crate::schemas::ToolLocation;
+use crate::DeviceContext;
 
 /// Executes tools either locally or remotely based on their location.
 pub struct ToolExecutor {
     /// Base URL for remote envoy client (e.g., "http://localhost:8081").
     /// None means local-only mode — Client tools will error.
     envoy_url: Option<String>,
+    /// Run Client tools in this process instead, for an engine embedded in
+    /// the user's own CLI.
+    in_process: bool,
     /// Cached HTTP client for remote tool calls.
     client: reqwest::Client,
 }
@@ -16,6 +20,16 @@ impl ToolExecutor {
     pub fn new(envoy_url: Option<String>) -> Self {
         Self {
             envoy_url,
+            in_process: false,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// An executor that runs every tool, Client tools included, on this machine.
+    pub fn in_process() -> Self {
+        Self {
+            envoy_url: None,
+            in_process: true,
             client: reqwest::Client::new(),
         }
     }
@@ -31,8 +45,8 @@ impl ToolExecutor {
     }
 
     /// Execute a Server-location tool locally (synchronous).
-    pub fn execute_server(&self, tool_name: &str, args: &Value) -> Result<String> {
-        crate::tools::use_tool(tool_name, args)
+    pub fn execute_server(&self, tool_name: &str, args: &Value, ctx: &DeviceContext) -> Result<String> {
+        crate::tools::use_tool(tool_name, args, ctx)
     }
 
     /// Execute a tool with the configured strategy.
@@ -40,19 +54,21 @@ impl ToolExecutor {
         &self,
         tool_name: &str,
         args: &Value,
-        device_id: i64,
-        device_key: &str,
+        ctx: &DeviceContext,
     ) -> Result<String> {
         let schema = get_tool_schema(tool_name)?;
 
         match schema.location {
             ToolLocation::Server => {
-                crate::tools::use_tool(tool_name, args)
+                crate::tools::use_tool(tool_name, args, ctx)
+            }
+            ToolLocation::Client if self.in_process => {
+                crate::tools::use_tool(tool_name, args, ctx)
             }
             ToolLocation::Client => {
                 match &self.envoy_url {
                     Some(url) => {
-                        self.execute_remote(url, device_id, device_key, tool_name, args).await
+                        self.execute_remote(url, ctx, tool_name, args).await
                     }
                     None => {
                         Err(anyhow::anyhow!(
@@ -65,19 +81,28 @@ impl ToolExecutor {
         }
     }
 
+    /// Run a Client tool on the machine whose tool server listens at `url`,
+    /// on behalf of that machine's device.
+    pub async fn execute_on(&self, url: &str, tool_name: &str, args: &Value, ctx: &DeviceContext) -> Result<String> {
+        let schema = get_tool_schema(tool_name)?;
+        if !matches!(schema.location, ToolLocation::Client) {
+            return Err(anyhow::anyhow!("Tool '{}' runs on the server, not on a device", tool_name));
+        }
+        self.execute_remote(url.trim_end_matches('/'), ctx, tool_name, args).await
+    }
+
     async fn execute_remote(
         &self,
         base_url: &str,
-        device_id: i64,
-        device_key: &str,
+        ctx: &DeviceContext,
         tool_name: &str,
         args: &Value,
     ) -> Result<String> {
         let url = format!("{}/shared/execute", base_url);
 
         let request_body = serde_json::json!({
-            "device_id": device_id,
-            "device_key": device_key,
+            "device_id": ctx.device_id,
+            "device_key": ctx.device_key,
             "tool_name": tool_name,
             "arguments": args,
         });
@@ -1,14 +1,38 @@
+pub mod context;
 pub mod db;
+pub mod errors;
 pub mod schemas;
 pub mod executor;
 pub mod events;
 pub mod tools;
+pub mod time;
+pub mod memory;
+pub mod workspace_index;
+pub mod encoding;
+pub mod line_index;
+pub mod archive;
+pub mod embeddings;
+#[cfg(test)]
+mod time_tests;
+#[cfg(test)]
+mod memory_tests;
+#[cfg(test)]
+mod workspace_index_tests;
+#[cfg(test)]
+mod encoding_tests;
+#[cfg(test)]
+mod line_index_tests;
+#[cfg(test)]
+mod archive_tests;
+#[cfg(test)]
+mod embeddings_tests;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 pub use rusqlite;
-pub use schemas::{ParameterSchema, Tool, ToolLocation, ToolSchema};
+pub use context::DeviceContext;
+pub use schemas::{ParamType, ParameterSchema, Tool, ToolLocation, ToolRisk, ToolSchema, ToolbeltInfo};
 pub use tools::{get_tools, get_tools_for, use_tool, get_tool_schema};
 
 // Shared message types used by both engine and shared DB layer
@@ -0,0 +1,92 @@
+//! Random access to the lines of a large file without reading all of it
+//! each time: one pass records where every thousandth line starts, and
+//! later reads seek to the nearest one.
+
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Lines between recorded offsets. Reading any line skips at most this many.
+pub const CHECKPOINT_LINES: u64 = 1_000;
+
+/// Where a file's lines start, valid for as long as its size and mtime stay
+/// the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineIndex {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Lines in the file; a last line without a newline counts.
+    pub lines: u64,
+    /// Byte offset of line `i * CHECKPOINT_LINES + 1`, at index `i`.
+    checkpoints: Vec<u64>,
+}
+
+impl LineIndex {
+    /// Scan the file once.
+    pub fn build(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let mut reader = BufReader::new(file);
+
+        let mut checkpoints = vec![0];
+        let mut offset = 0u64;
+        let mut lines = 0u64;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            offset += read as u64;
+            lines += 1;
+            if lines.is_multiple_of(CHECKPOINT_LINES) {
+                checkpoints.push(offset);
+            }
+        }
+
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            lines,
+            checkpoints,
+        })
+    }
+
+    /// Whether the file still looks the way it did when it was scanned.
+    pub fn is_current(&self, metadata: &Metadata) -> bool {
+        metadata.len() == self.size && metadata.modified().ok() == self.modified
+    }
+
+    /// Up to `count` lines from line `start` (1-based), without their line
+    /// endings.
+    pub fn read(&self, path: &Path, start: u64, count: u64) -> io::Result<Vec<Vec<u8>>> {
+        if start == 0 || start > self.lines {
+            return Ok(Vec::new());
+        }
+        let checkpoint = ((start - 1) / CHECKPOINT_LINES) as usize;
+        let checkpoint = checkpoint.min(self.checkpoints.len() - 1);
+
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(self.checkpoints[checkpoint]))?;
+        let mut reader = BufReader::new(file);
+
+        let mut line_number = checkpoint as u64 * CHECKPOINT_LINES + 1;
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        while lines.len() < count as usize {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line_number >= start {
+                let end = line.len() - line.ends_with(b"\n") as usize;
+                let end = end - line[..end].ends_with(b"\r") as usize;
+                lines.push(line[..end].to_vec());
+            }
+            line_number += 1;
+        }
+        Ok(lines)
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod tests {
+    use crate::line_index::{LineIndex, CHECKPOINT_LINES};
+
+    #[test]
+    fn reads_any_page_of_a_long_file() {
+        let path = std::env::temp_dir().join(format!("artificer-lines-{}.log", uuid::Uuid::new_v4()));
+        let total = CHECKPOINT_LINES * 2 + 500;
+        let text: String = (1..=total).map(|n| format!("line {}\r\n", n)).collect();
+        std::fs::write(&path, format!("{}no newline", text)).unwrap();
+
+        let index = LineIndex::build(&path).unwrap();
+        assert_eq!(index.lines, total + 1);
+        assert!(index.is_current(&std::fs::metadata(&path).unwrap()));
+
+        let page = index.read(&path, CHECKPOINT_LINES * 2 + 1, 2).unwrap();
+        assert_eq!(page, [format!("line {}", CHECKPOINT_LINES * 2 + 1).into_bytes(), format!("line {}", CHECKPOINT_LINES * 2 + 2).into_bytes()]);
+        assert_eq!(index.read(&path, total, 10).unwrap().last().unwrap(), b"no newline");
+        assert!(index.read(&path, total + 2, 10).unwrap().is_empty());
+
+        std::fs::write(&path, "shorter").unwrap();
+        assert!(!index.is_current(&std::fs::metadata(&path).unwrap()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
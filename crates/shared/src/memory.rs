@@ -0,0 +1,313 @@
+//! Long-term memories: facts about the user kept across conversations.
+//!
+//! All memory reads and writes go through [`MemoryStore`]. Memories are
+//! partitioned by profile, and a conversation only ever sees memories from
+//! the profile it was started in.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::db::{self, Db};
+
+/// Longest single memory, in characters.
+pub const MAX_MEMORY_CHARS: usize = 500;
+
+/// Most memories injected into one prompt.
+pub const MAX_PROMPT_MEMORIES: usize = 20;
+
+/// Characters of memory injected into one prompt, roughly 500 tokens.
+pub const MEMORY_PROMPT_CHARS: usize = 2_000;
+
+/// Weeks the timeline covers unless asked for another number, and the most
+/// it covers.
+pub const DEFAULT_TIMELINE_WEEKS: u32 = 8;
+pub const MAX_TIMELINE_WEEKS: u32 = 52;
+
+/// Most keywords, conversations and memories listed for one timeline week.
+const TIMELINE_KEYWORDS: usize = 8;
+const TIMELINE_CONVERSATIONS: usize = 10;
+const TIMELINE_MEMORIES: usize = 20;
+
+const DAY_SECONDS: i64 = 86_400;
+const WEEK_SECONDS: i64 = 7 * DAY_SECONDS;
+
+/// Words too common to say anything about relevance.
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "are", "but", "not", "you", "your", "with", "this", "that",
+    "from", "have", "has", "was", "were", "will", "what", "when", "where", "which",
+    "who", "how", "can", "could", "would", "should", "about", "into", "there", "their",
+    "them", "they", "then", "than", "some", "any", "all", "its", "our", "out", "get",
+    "please", "does", "did", "just", "also", "like", "user", "users",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Memory {
+    pub id: u64,
+    pub content: String,
+    pub created: i64,
+}
+
+/// What a device was busy with in one week of a profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineWeek {
+    /// Monday 00:00 UTC of the week.
+    pub week_start: i64,
+    /// Memories saved that week, oldest first.
+    pub memories: Vec<Memory>,
+    /// Words from the user's messages that week, in the most messages first.
+    pub keywords: Vec<KeywordCount>,
+    /// Conversations the user wrote in that week, busiest first.
+    pub conversations: Vec<TimelineConversation>,
+    /// Memories and conversations that week left off the lists above.
+    pub more_memories: usize,
+    pub more_conversations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeywordCount {
+    pub keyword: String,
+    /// User messages the word appeared in.
+    pub messages: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineConversation {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    /// User messages sent in it that week.
+    pub messages: usize,
+}
+
+/// A user message as the timeline reads it.
+pub(crate) struct TimelineMessage {
+    pub conversation_id: u64,
+    pub title: Option<String>,
+    pub created: i64,
+    pub text: String,
+}
+
+/// The memories picked for one prompt.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySelection {
+    /// Profile the memories came from.
+    pub profile: String,
+    pub memories: Vec<Memory>,
+    /// Memories in the profile that didn't make the cut.
+    pub omitted: usize,
+}
+
+/// The one entry point for memories. Validates what is saved and decides what
+/// reaches a prompt; the `memories` table behind it is not used directly.
+#[derive(Clone)]
+pub struct MemoryStore {
+    db: Arc<Db>,
+}
+
+impl MemoryStore {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+
+    /// Remember a fact in one of the device's profiles. Returns the memory id;
+    /// remembering the same fact twice returns the existing one.
+    pub fn remember(&self, device_id: u64, profile: &str, fact: &str) -> Result<u64> {
+        let fact = fact.trim();
+        if fact.is_empty() {
+            return Err(anyhow::anyhow!("Memory cannot be empty"));
+        }
+        if fact.chars().count() > MAX_MEMORY_CHARS {
+            return Err(anyhow::anyhow!("Memory is longer than {} characters", MAX_MEMORY_CHARS));
+        }
+        self.db.insert_memory(device_id, profile, fact)
+    }
+
+    /// Forget a memory. Only memories in the given profile can be removed, so a
+    /// conversation can't reach into another profile. Returns false if there was
+    /// no such memory.
+    pub fn forget(&self, device_id: u64, profile: &str, memory_id: u64) -> Result<bool> {
+        self.db.delete_memory(device_id, profile, memory_id)
+    }
+
+    /// Every memory in one of the device's profiles, oldest first.
+    pub fn list(&self, device_id: u64, profile: &str) -> Result<Vec<Memory>> {
+        self.db.get_memories(device_id, profile)
+    }
+
+    /// Memories in one of the device's profiles that share keywords with
+    /// `query`, most relevant first.
+    pub fn search(&self, device_id: u64, profile: &str, query: &str, limit: usize) -> Result<Vec<Memory>> {
+        let memories = self.list(device_id, profile)?;
+        Ok(rank_memories(memories, query)
+            .into_iter()
+            .filter(|(_, score)| *score > 0)
+            .take(limit)
+            .map(|(memory, _)| memory)
+            .collect())
+    }
+
+    /// The last `weeks` weeks of a profile, this one included, newest first:
+    /// the memories saved, the words the user used most and the conversations
+    /// they were in. Weeks with nothing in them are left out. Locked
+    /// conversations and incognito messages don't count.
+    pub fn timeline(&self, device_id: u64, profile: &str, weeks: u32) -> Result<Vec<TimelineWeek>> {
+        let weeks = weeks.clamp(1, MAX_TIMELINE_WEEKS) as i64;
+        let since = week_start(db::now()) - (weeks - 1) * WEEK_SECONDS;
+        let memories: Vec<Memory> = self.list(device_id, profile)?
+            .into_iter()
+            .filter(|m| m.created >= since)
+            .collect();
+        let messages = self.db.timeline_messages(device_id, profile, since)?;
+        Ok(group_by_week(memories, messages))
+    }
+
+    /// The memories to inject into a prompt for `message` in a conversation,
+    /// drawn from the conversation's profile only.
+    pub fn for_prompt(&self, device_id: u64, conversation_id: u64, message: &str) -> Result<MemorySelection> {
+        let profile = self.db.get_conversation_profile(conversation_id)?;
+        let memories = self.list(device_id, &profile)?;
+        Ok(select_memories(&profile, memories, message, MAX_PROMPT_MEMORIES, MEMORY_PROMPT_CHARS))
+    }
+}
+
+/// Pick the memories to inject for a message. A profile whose memories all
+/// fit within `max_count` and `budget_chars` is injected whole, in the order
+/// saved. Otherwise memories are ranked by how many of the message's keywords
+/// they share, newest first among equals, and taken until either limit is hit.
+pub fn select_memories(
+    profile: &str,
+    memories: Vec<Memory>,
+    message: &str,
+    max_count: usize,
+    budget_chars: usize,
+) -> MemorySelection {
+    let total = memories.len();
+    let total_chars: usize = memories.iter().map(|m| m.content.chars().count()).sum();
+    if total <= max_count && total_chars <= budget_chars {
+        return MemorySelection { profile: profile.to_string(), memories, omitted: 0 };
+    }
+
+    let mut selected = Vec::new();
+    let mut used = 0;
+    for (memory, _) in rank_memories(memories, message) {
+        if selected.len() == max_count {
+            break;
+        }
+        let len = memory.content.chars().count();
+        if used + len > budget_chars {
+            continue;
+        }
+        used += len;
+        selected.push(memory);
+    }
+
+    MemorySelection {
+        profile: profile.to_string(),
+        omitted: total - selected.len(),
+        memories: selected,
+    }
+}
+
+/// Monday 00:00 UTC of the week `timestamp` falls in.
+fn week_start(timestamp: i64) -> i64 {
+    let day = timestamp.div_euclid(DAY_SECONDS);
+    // 1970-01-01 was a Thursday, three days into its week
+    (day - (day + 3).rem_euclid(7)) * DAY_SECONDS
+}
+
+#[derive(Default)]
+struct WeekActivity {
+    memories: Vec<Memory>,
+    keywords: HashMap<String, usize>,
+    conversations: HashMap<u64, TimelineConversation>,
+}
+
+fn group_by_week(memories: Vec<Memory>, messages: Vec<TimelineMessage>) -> Vec<TimelineWeek> {
+    let mut weeks: BTreeMap<i64, WeekActivity> = BTreeMap::new();
+    for memory in memories {
+        weeks.entry(week_start(memory.created)).or_default().memories.push(memory);
+    }
+    for message in messages {
+        let week = weeks.entry(week_start(message.created)).or_default();
+        for keyword in keywords(&message.text) {
+            *week.keywords.entry(keyword).or_default() += 1;
+        }
+        week.conversations
+            .entry(message.conversation_id)
+            .or_insert_with(|| TimelineConversation {
+                conversation_id: message.conversation_id,
+                title: message.title,
+                messages: 0,
+            })
+            .messages += 1;
+    }
+
+    weeks.into_iter().rev().map(|(week_start, activity)| {
+        let mut keywords: Vec<KeywordCount> = activity.keywords
+            .into_iter()
+            .map(|(keyword, messages)| KeywordCount { keyword, messages })
+            .collect();
+        keywords.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.keyword.cmp(&b.keyword)));
+        keywords.truncate(TIMELINE_KEYWORDS);
+
+        let mut conversations: Vec<TimelineConversation> = activity.conversations.into_values().collect();
+        conversations.sort_by(|a, b| b.messages.cmp(&a.messages).then(a.conversation_id.cmp(&b.conversation_id)));
+        let more_conversations = conversations.len().saturating_sub(TIMELINE_CONVERSATIONS);
+        conversations.truncate(TIMELINE_CONVERSATIONS);
+
+        let mut memories = activity.memories;
+        let more_memories = memories.len().saturating_sub(TIMELINE_MEMORIES);
+        memories.truncate(TIMELINE_MEMORIES);
+
+        TimelineWeek { week_start, memories, keywords, conversations, more_memories, more_conversations }
+    }).collect()
+}
+
+/// Memories paired with their keyword overlap with `query`, most relevant first.
+pub fn rank_memories(memories: Vec<Memory>, query: &str) -> Vec<(Memory, usize)> {
+    let query = keywords(query);
+    let mut ranked: Vec<(Memory, usize)> = memories
+        .into_iter()
+        .map(|memory| {
+            let score = keywords(&memory.content).intersection(&query).count();
+            (memory, score)
+        })
+        .collect();
+    ranked.sort_by(|(a, a_score), (b, b_score)| {
+        b_score.cmp(a_score)
+            .then(b.created.cmp(&a.created))
+            .then(b.id.cmp(&a.id))
+    });
+    ranked
+}
+
+/// The `limit` words found in the most texts, most first and alphabetical
+/// among equals. A word counts once per text however often it appears.
+pub fn top_keywords<S: AsRef<str>>(texts: &[S], limit: usize) -> Vec<KeywordCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        for keyword in keywords(text.as_ref()) {
+            *counts.entry(keyword).or_default() += 1;
+        }
+    }
+    let mut top: Vec<KeywordCount> = counts.into_iter()
+        .map(|(keyword, messages)| KeywordCount { keyword, messages })
+        .collect();
+    top.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.keyword.cmp(&b.keyword)));
+    top.truncate(limit);
+    top
+}
+
+/// Lowercase words of three or more characters, minus stopwords, with a
+/// plural `s` dropped so "dogs" matches "dog".
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.chars().count() >= 3 && !STOPWORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.chars().count() >= 3 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
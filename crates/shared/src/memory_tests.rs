@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::db::{normalize_profile, register_device, Db, DEFAULT_PROFILE};
+    use crate::memory::{select_memories, top_keywords, KeywordCount, Memory, MemoryStore, TimelineConversation};
+
+    #[test]
+    fn profiles_are_normalized() {
+        assert_eq!(normalize_profile(" Work "), Some("work".to_string()));
+        assert_eq!(normalize_profile(""), None);
+        assert_eq!(normalize_profile("side project"), None);
+    }
+
+    #[test]
+    fn memories_stay_in_their_profile() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let store = MemoryStore::new(db.clone());
+
+        let unset = db.create_conversation(device).unwrap();
+        assert_eq!(db.get_conversation_profile(unset).unwrap(), DEFAULT_PROFILE);
+        let conversation = db.start_conversation(device, Some("Work"), false).unwrap();
+        assert_eq!(db.get_conversation_profile(conversation).unwrap(), "work");
+
+        let deadline = store.remember(device, "work", "Project Falcon ships in March").unwrap();
+        store.remember(device, "personal", "Has a dog named Biscuit").unwrap();
+        assert_eq!(store.remember(device, "work", "Project Falcon ships in March").unwrap(), deadline);
+
+        let work = store.list(device, "work").unwrap();
+        assert_eq!(work.len(), 1);
+        assert_eq!(work[0].content, "Project Falcon ships in March");
+
+        // Prompts only draw on the conversation's profile
+        let selection = store.for_prompt(device, conversation, "Tell me about my dog").unwrap();
+        assert_eq!(selection.profile, "work");
+        assert_eq!(selection.memories.len(), 1);
+        assert!(store.remember(device, "work", "   ").is_err());
+
+        // Another profile can't forget it
+        assert!(!store.forget(device, "personal", deadline).unwrap());
+        assert!(store.forget(device, "work", deadline).unwrap());
+        assert!(store.list(device, "work").unwrap().is_empty());
+        assert_eq!(store.list(device, "personal").unwrap().len(), 1);
+    }
+
+    fn memory(id: u64, content: &str) -> Memory {
+        Memory { id, content: content.to_string(), created: id as i64 }
+    }
+
+    #[test]
+    fn small_profiles_are_injected_whole() {
+        let memories = vec![memory(1, "Prefers metric units"), memory(2, "Lives in Lisbon")];
+        let selection = select_memories("default", memories, "What's the weather?", 20, 2_000);
+        assert_eq!(selection.memories.len(), 2);
+        assert_eq!(selection.omitted, 0);
+    }
+
+    #[test]
+    fn large_profiles_keep_the_most_relevant() {
+        let memories = vec![
+            memory(1, "Has a dog named Biscuit"),
+            memory(2, "Project Falcon ships in March"),
+            memory(3, "Prefers metric units"),
+            memory(4, "Falcon deploys run on Fridays"),
+        ];
+        let selection = select_memories("work", memories, "When do the Falcon deploys happen?", 2, 2_000);
+        let ids: Vec<u64> = selection.memories.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![4, 2]);
+        assert_eq!(selection.omitted, 2);
+
+        // The character budget applies as well as the count
+        let memories = vec![memory(1, "Falcon"), memory(2, &"x".repeat(50))];
+        let selection = select_memories("work", memories, "falcon", 10, 20);
+        assert_eq!(selection.memories.len(), 1);
+        assert_eq!(selection.omitted, 1);
+    }
+
+    #[test]
+    fn timeline_groups_a_profile_by_week() {
+        let db = Arc::new(Db::in_memory().unwrap());
+        let device = register_device(&db, "laptop");
+        let store = MemoryStore::new(db.clone());
+
+        let falcon = db.start_conversation(device, Some("work"), false).unwrap();
+        let review = db.start_conversation(device, Some("work"), false).unwrap();
+        let garden = db.start_conversation(device, Some("personal"), false).unwrap();
+        for (conversation, text) in [
+            (falcon, "Falcon deploy checklist"),
+            (falcon, "Falcon rollback plan"),
+            (review, "salary review"),
+            (garden, "garden fence"),
+        ] {
+            db.add_message(conversation, None, "user", Some(text), None).unwrap();
+        }
+        db.add_message(falcon, None, "assistant", Some("Here is the deploy order"), None).unwrap();
+        db.lock_conversation(review, "2468").unwrap();
+        db.execute(
+            "UPDATE messages SET created = ?1 WHERE message = 'Falcon rollback plan'",
+            rusqlite::params![crate::db::now() - 7 * 86_400],
+        ).unwrap();
+        store.remember(device, "work", "Falcon ships in March").unwrap();
+
+        let weeks = store.timeline(device, "work", 4).unwrap();
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].week_start - weeks[1].week_start, 7 * 86_400);
+
+        let words = |week: usize| -> Vec<String> {
+            weeks[week].keywords.iter().map(|k| k.keyword.clone()).collect()
+        };
+        assert_eq!(words(0), ["checklist", "deploy", "falcon"]);
+        assert_eq!(words(1), ["falcon", "plan", "rollback"]);
+        assert_eq!(weeks[0].memories.len(), 1);
+        assert!(weeks[1].memories.is_empty());
+        assert_eq!(weeks[0].conversations, [
+            TimelineConversation { conversation_id: falcon, title: None, messages: 1 },
+        ]);
+
+        // The older week falls outside a one-week timeline
+        assert_eq!(store.timeline(device, "work", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn top_keywords_count_each_text_once() {
+        let texts = [
+            "Rust lifetimes, lifetimes everywhere",
+            "How do Rust traits work?",
+            "Best hiking trails near me",
+        ];
+        let top = top_keywords(&texts, 2);
+        assert_eq!(top, vec![
+            KeywordCount { keyword: "rust".to_string(), messages: 2 },
+            KeywordCount { keyword: "best".to_string(), messages: 1 },
+        ]);
+        // Repeats within one message don't add up
+        let all = top_keywords(&texts, 20);
+        assert_eq!(all.iter().find(|k| k.keyword == "lifetime").map(|k| k.messages), Some(1));
+        assert!(top_keywords::<&str>(&[], 5).is_empty());
+    }
+}
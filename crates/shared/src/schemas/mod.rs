@@ -3,12 +3,44 @@ use serde::Serialize;
 use serde_json::{json, Value};
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ToolLocation {
     Server,
     Client,
 }
 
+/// How much harm a toolbelt can do if its tools are misused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolRisk {
+    /// Only reads or looks things up.
+    Low,
+    /// Sees private data, or changes things that are easy to put back.
+    Medium,
+    /// Changes files or running services.
+    High,
+}
+
+impl ToolRisk {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ToolRisk::Low => "low",
+            ToolRisk::Medium => "medium",
+            ToolRisk::High => "high",
+        }
+    }
+}
+
+/// A toolbelt as declared with `register_toolbelt!`.
+#[derive(Debug)]
+pub struct ToolbeltInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub location: ToolLocation,
+    pub risk: ToolRisk,
+}
+
 #[derive(Debug, Clone)]
 pub struct ToolSchema {
     pub name: &'static str,
@@ -20,9 +52,96 @@ pub struct ToolSchema {
 #[derive(Debug, Clone)]
 pub struct ParameterSchema {
     pub name: &'static str,
-    pub type_name: &'static str,
+    pub param_type: ParamType,
     pub description: &'static str,
     pub required: bool,
+    /// Value the handler falls back to when the parameter is omitted.
+    pub default: Option<Value>,
+}
+
+/// The JSON type a tool parameter accepts.
+#[derive(Debug, Clone)]
+pub enum ParamType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// Any JSON value. No "type" is emitted.
+    Any,
+    Array(Box<ParamType>),
+    Object(Vec<ParameterSchema>),
+    /// A string restricted to the listed values.
+    Enum(&'static [&'static str]),
+}
+
+impl ParamType {
+    pub fn array(items: ParamType) -> Self {
+        ParamType::Array(Box::new(items))
+    }
+
+    pub fn to_json_schema(&self) -> Value {
+        match self {
+            ParamType::String => json!({ "type": "string" }),
+            ParamType::Integer => json!({ "type": "integer" }),
+            ParamType::Number => json!({ "type": "number" }),
+            ParamType::Boolean => json!({ "type": "boolean" }),
+            ParamType::Any => json!({}),
+            ParamType::Array(items) => json!({
+                "type": "array",
+                "items": items.to_json_schema()
+            }),
+            ParamType::Object(properties) => object_schema(properties),
+            ParamType::Enum(values) => json!({
+                "type": "string",
+                "enum": values
+            }),
+        }
+    }
+}
+
+/// Scalar type names, as written in `register_toolbelt!` params.
+impl From<&'static str> for ParamType {
+    fn from(name: &'static str) -> Self {
+        match name {
+            "string" => ParamType::String,
+            "integer" => ParamType::Integer,
+            "number" => ParamType::Number,
+            "boolean" => ParamType::Boolean,
+            "any" => ParamType::Any,
+            "array" => ParamType::array(ParamType::Any),
+            "object" => ParamType::Object(Vec::new()),
+            other => panic!("Unknown tool parameter type '{}'", other),
+        }
+    }
+}
+
+impl ParameterSchema {
+    pub fn to_json_schema(&self) -> Value {
+        let mut schema = self.param_type.to_json_schema();
+        schema["description"] = json!(self.description);
+        if let Some(default) = &self.default {
+            schema["default"] = default.clone();
+        }
+        schema
+    }
+}
+
+fn object_schema(parameters: &[ParameterSchema]) -> Value {
+    let mut properties = json!({});
+    let mut required = vec![];
+
+    for param in parameters {
+        properties[param.name] = param.to_json_schema();
+        if param.required {
+            required.push(param.name);
+        }
+    }
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -41,32 +160,18 @@ pub struct FunctionDefinition {
 
 impl ToolSchema {
     pub fn to_tool(&self) -> Tool {
-        let mut properties = json!({});
-        let mut required = vec![];
-
-        for param in &self.parameters {
-            properties[param.name] = json!({
-                "type": param.type_name,
-                "description": param.description
-            });
-            if param.required {
-                required.push(param.name);
-            }
-        }
-
         Tool {
             tool_type: "function".to_string(),
             function: FunctionDefinition {
                 name: self.name.to_string(),
                 description: self.description.to_string(),
-                parameters: json!({
-                    "type": "object",
-                    "properties": properties,
-                    "required": required
-                }),
+                parameters: object_schema(&self.parameters),
             },
         }
     }
 }
 
-pub type ToolHandler = fn(&Value) -> anyhow::Result<String>;
\ No newline at end of file
+pub type ToolHandler = fn(&Value, &crate::DeviceContext) -> anyhow::Result<String>;
+
+#[cfg(test)]
+mod schemas_tests;
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::schemas::{ParamType, ParameterSchema, ToolLocation, ToolSchema};
+    use crate::tools::toolbelts::archivist;
+
+    #[test]
+    fn nested_types_emit_json_schema() {
+        let schema = ToolSchema {
+            name: "test::nested",
+            description: "Nested parameters",
+            location: ToolLocation::Server,
+            parameters: vec![
+                ParameterSchema {
+                    name: "steps",
+                    param_type: ParamType::array(ParamType::Object(vec![
+                        ParameterSchema {
+                            name: "mode",
+                            param_type: ParamType::Enum(&["fast", "thorough"]),
+                            description: "How to run the step",
+                            required: true,
+                            default: None,
+                        },
+                    ])),
+                    description: "Steps to run",
+                    required: true,
+                    default: None,
+                },
+                ParameterSchema {
+                    name: "limit",
+                    param_type: ParamType::Integer,
+                    description: "Most results",
+                    required: false,
+                    default: Some(json!(5)),
+                },
+            ],
+        };
+
+        let params = schema.to_tool().function.parameters;
+        assert_eq!(params["required"], json!(["steps"]));
+        assert_eq!(params["properties"]["limit"], json!({
+            "type": "integer",
+            "description": "Most results",
+            "default": 5
+        }));
+        let items = &params["properties"]["steps"]["items"];
+        assert_eq!(items["type"], "object");
+        assert_eq!(items["properties"]["mode"]["enum"], json!(["fast", "thorough"]));
+        assert_eq!(items["required"], json!(["mode"]));
+    }
+
+    #[test]
+    fn macro_defaults_make_params_optional() {
+        let query_db = archivist::TOOL_SCHEMAS.iter()
+            .find(|s| s.name == "Archivist::query_db")
+            .unwrap()
+            .to_tool();
+        let params = query_db.function.parameters;
+        assert_eq!(params["required"], json!(["query"]));
+        assert_eq!(params["properties"]["params"]["type"], "array");
+        assert_eq!(params["properties"]["params"]["default"], json!([]));
+    }
+}
@@ -0,0 +1,430 @@
+//! Wall-clock time in a named timezone, without a timezone database dependency.
+//!
+//! Zones are `UTC`, a fixed offset (`+02:00`, `UTC-5`), or an IANA name
+//! (`Europe/Berlin`) looked up in the host's compiled zoneinfo files.
+
+use anyhow::Result;
+use serde::Serialize;
+use crate::db::Db;
+
+const ZONEINFO_DIR: &str = "/usr/share/zoneinfo";
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+/// A moment as seen on a clock in some zone.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalTime {
+    /// Unix seconds.
+    pub timestamp: i64,
+    pub zone: String,
+    /// Seconds east of UTC.
+    pub utc_offset: i32,
+    /// `2026-03-14`
+    pub date: String,
+    /// `09:26`
+    pub time: String,
+    pub weekday: &'static str,
+}
+
+impl LocalTime {
+    /// `Saturday, 2026-03-14 09:26 (Europe/Berlin, UTC+01:00)`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {} {} ({}, UTC{})",
+            self.weekday, self.date, self.time, self.zone, format_offset(self.utc_offset)
+        )
+    }
+}
+
+/// The current time in `zone`. `None` means UTC.
+pub fn now_in(zone: Option<&str>) -> Result<LocalTime> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    at(now, zone.unwrap_or("UTC"))
+}
+
+/// `timestamp` as seen in `zone`.
+pub fn at(timestamp: i64, zone: &str) -> Result<LocalTime> {
+    let utc_offset = utc_offset(zone, timestamp)?;
+    let local = timestamp + utc_offset as i64;
+    let days = local.div_euclid(86_400);
+    let seconds = local.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    Ok(LocalTime {
+        timestamp,
+        zone: zone.to_string(),
+        utc_offset,
+        date: format!("{:04}-{:02}-{:02}", year, month, day),
+        time: format!("{:02}:{:02}", seconds / 3600, seconds % 3600 / 60),
+        weekday: WEEKDAYS[weekday(days) as usize],
+    })
+}
+
+/// Unix seconds of a wall-clock time in `zone`. In a daylight-saving gap or
+/// overlap, the offset in force just after the moment is used.
+pub fn from_local(year: i64, month: u32, day: u32, seconds_of_day: i64, zone: &str) -> Result<i64> {
+    let local = days_from_civil(year, month, day) * 86_400 + seconds_of_day;
+    let guess = local - utc_offset(zone, local)? as i64;
+    Ok(local - utc_offset(zone, guess)? as i64)
+}
+
+/// Whether `zone` names a timezone this host can resolve.
+pub fn is_valid_zone(zone: &str) -> bool {
+    utc_offset(zone, 0).is_ok()
+}
+
+/// The host's own IANA zone: `TZ`, else the `/etc/localtime` link, else `/etc/timezone`.
+pub fn local_zone() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ") {
+        let tz = tz.trim_start_matches(':').to_string();
+        if is_valid_zone(&tz) {
+            return Some(tz);
+        }
+    }
+
+    if let Ok(target) = std::fs::read_link("/etc/localtime") {
+        let target = target.to_string_lossy().to_string();
+        if let Some((_, zone)) = target.split_once("zoneinfo/") {
+            return Some(zone.to_string());
+        }
+    }
+
+    std::fs::read_to_string("/etc/timezone")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|zone| is_valid_zone(zone))
+}
+
+/// The zone to show a device's user: the timezone its client reported,
+/// falling back to the server's, then UTC.
+pub fn device_zone(db: &Db, device_id: u64) -> String {
+    db.get_device_timezone(device_id)
+        .ok()
+        .flatten()
+        .filter(|zone| is_valid_zone(zone))
+        .or_else(local_zone)
+        .unwrap_or_else(|| "UTC".to_string())
+}
+
+// ============================================================================
+// OFFSETS
+// ============================================================================
+
+fn utc_offset(zone: &str, timestamp: i64) -> Result<i32> {
+    let upper = zone.to_ascii_uppercase();
+    if matches!(upper.as_str(), "UTC" | "GMT" | "Z") {
+        return Ok(0);
+    }
+
+    let fixed = upper.strip_prefix("UTC").or_else(|| upper.strip_prefix("GMT")).unwrap_or(&upper);
+    if fixed.starts_with('+') || fixed.starts_with('-') {
+        return parse_fixed_offset(fixed)
+            .ok_or_else(|| anyhow::anyhow!("Invalid UTC offset '{}'", zone));
+    }
+
+    zoneinfo_offset(zone, timestamp)
+}
+
+/// `+2`, `+02:00`, `-0530`
+fn parse_fixed_offset(s: &str) -> Option<i32> {
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = if digits.len() <= 2 {
+        (digits.parse::<i32>().ok()?, 0)
+    } else {
+        let split = digits.len() - 2;
+        (digits[..split].parse::<i32>().ok()?, digits[split..].parse::<i32>().ok()?)
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn zoneinfo_offset(zone: &str, timestamp: i64) -> Result<i32> {
+    // Zone names become a path; keep them to the shape IANA uses
+    let valid = !zone.is_empty()
+        && !zone.starts_with('/')
+        && !zone.split('/').any(|part| part.is_empty() || part == "." || part == "..")
+        && zone.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'));
+    if !valid {
+        return Err(anyhow::anyhow!("Invalid timezone '{}'", zone));
+    }
+
+    let data = std::fs::read(format!("{}/{}", ZONEINFO_DIR, zone))
+        .map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", zone))?;
+    tzif_offset(&data, timestamp).ok_or_else(|| anyhow::anyhow!("Unreadable timezone file for '{}'", zone))
+}
+
+/// Offset in effect at `timestamp` according to a TZif file. Uses the 64-bit
+/// block when present, and the footer rule for times past the last transition.
+fn tzif_offset(data: &[u8], timestamp: i64) -> Option<i32> {
+    let header = TzifHeader::parse(data)?;
+    let (header, body, time_size, footer) = if header.version >= b'2' {
+        let v2_start = 44 + header.block_len(4);
+        let v2 = TzifHeader::parse(data.get(v2_start..)?)?;
+        let body = data.get(v2_start + 44..)?;
+        let footer = body.get(v2.block_len(8)..).and_then(|rest| {
+            let rest = std::str::from_utf8(rest).ok()?;
+            Some(rest.trim_matches('\n').to_string())
+        });
+        (v2, body, 8, footer)
+    } else {
+        (header, data.get(44..)?, 4, None)
+    };
+
+    let read_time = |i: usize| -> Option<i64> {
+        let bytes = body.get(i * time_size..(i + 1) * time_size)?;
+        Some(if time_size == 8 {
+            i64::from_be_bytes(bytes.try_into().ok()?)
+        } else {
+            i32::from_be_bytes(bytes.try_into().ok()?) as i64
+        })
+    };
+    let indices = header.timecnt * time_size;
+    let types = indices + header.timecnt;
+    let type_offset = |index: usize| -> Option<i32> {
+        let at = types + index * 6;
+        Some(i32::from_be_bytes(body.get(at..at + 4)?.try_into().ok()?))
+    };
+
+    let transitions = (0..header.timecnt).filter_map(read_time).collect::<Vec<_>>();
+    let past = transitions.iter().rposition(|t| *t <= timestamp);
+
+    let after_last = header.timecnt == 0 || past == Some(header.timecnt - 1);
+    if after_last && let Some(offset) = footer.as_deref().and_then(|rule| posix_offset(rule, timestamp)) {
+        return Some(offset);
+    }
+
+    match past {
+        Some(i) => type_offset(*body.get(indices + i)? as usize),
+        // Before the first transition: the first type is the zone's original offset
+        None => type_offset(0),
+    }
+}
+
+struct TzifHeader {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl TzifHeader {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.get(..4)? != b"TZif" {
+            return None;
+        }
+        let count = |i: usize| -> Option<usize> {
+            let at = 20 + i * 4;
+            Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize)
+        };
+        Some(TzifHeader {
+            version: *data.get(4)?,
+            isutcnt: count(0)?,
+            isstdcnt: count(1)?,
+            leapcnt: count(2)?,
+            timecnt: count(3)?,
+            typecnt: count(4)?,
+            charcnt: count(5)?,
+        })
+    }
+
+    /// Length of the data block that follows this header.
+    fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+/// Offset from a POSIX TZ rule such as `CET-1CEST,M3.5.0,M10.5.0/3`.
+/// Only the `Mm.w.d` transition form is supported, which is what tzdata emits.
+pub(crate) fn posix_offset(rule: &str, timestamp: i64) -> Option<i32> {
+    let mut p = PosixParser { s: rule.as_bytes(), i: 0 };
+
+    p.name()?;
+    let std_offset = -p.offset()?;
+    if p.done() {
+        return Some(std_offset);
+    }
+
+    p.name()?;
+    let dst_offset = if p.peek() == Some(b',') { std_offset + 3600 } else { -p.offset()? };
+    p.expect(b',')?;
+    let start = p.rule()?;
+    p.expect(b',')?;
+    let end = p.rule()?;
+
+    let year = civil_from_days((timestamp + std_offset as i64).div_euclid(86_400)).0;
+    // Start is given in standard time, end in daylight time
+    let dst_start = start.instant(year) - std_offset as i64;
+    let dst_end = end.instant(year) - dst_offset as i64;
+
+    let in_dst = if dst_start < dst_end {
+        dst_start <= timestamp && timestamp < dst_end
+    } else {
+        timestamp >= dst_start || timestamp < dst_end
+    };
+    Some(if in_dst { dst_offset } else { std_offset })
+}
+
+struct PosixRule {
+    month: u32,
+    week: u32,
+    weekday: i64,
+    /// Seconds after local midnight.
+    time: i64,
+}
+
+impl PosixRule {
+    /// Local seconds since the epoch at which the rule fires in `year`.
+    fn instant(&self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        let mut day = (self.weekday - weekday(first)).rem_euclid(7) + (self.week as i64 - 1) * 7;
+        if day >= days_in_month(year, self.month) {
+            day -= 7;
+        }
+        (first + day) * 86_400 + self.time
+    }
+}
+
+struct PosixParser<'a> {
+    s: &'a [u8],
+    i: usize,
+}
+
+impl PosixParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.i).copied()
+    }
+
+    fn done(&self) -> bool {
+        self.i >= self.s.len()
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        (self.peek()? == c).then(|| self.i += 1)
+    }
+
+    fn name(&mut self) -> Option<()> {
+        if self.peek()? == b'<' {
+            let close = self.s[self.i..].iter().position(|c| *c == b'>')?;
+            self.i += close + 1;
+        } else {
+            let start = self.i;
+            while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                self.i += 1;
+            }
+            if self.i - start < 3 {
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<i64> {
+        let start = self.i;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.i += 1;
+        }
+        std::str::from_utf8(&self.s[start..self.i]).ok()?.parse().ok()
+    }
+
+    /// `[+-]hh[:mm[:ss]]` in seconds.
+    fn offset(&mut self) -> Option<i32> {
+        let sign = match self.peek()? {
+            b'-' => { self.i += 1; -1 }
+            b'+' => { self.i += 1; 1 }
+            _ => 1,
+        };
+        let mut seconds = self.number()? * 3600;
+        if self.peek() == Some(b':') {
+            self.i += 1;
+            seconds += self.number()? * 60;
+            if self.peek() == Some(b':') {
+                self.i += 1;
+                seconds += self.number()?;
+            }
+        }
+        Some(sign * seconds as i32)
+    }
+
+    /// `Mm.w.d[/time]`
+    fn rule(&mut self) -> Option<PosixRule> {
+        self.expect(b'M')?;
+        let month = self.number()? as u32;
+        self.expect(b'.')?;
+        let week = self.number()? as u32;
+        self.expect(b'.')?;
+        let weekday = self.number()?;
+        let time = if self.peek() == Some(b'/') {
+            self.i += 1;
+            self.offset()? as i64
+        } else {
+            2 * 3600
+        };
+        ((1..=12).contains(&month) && (1..=5).contains(&week) && weekday < 7)
+            .then_some(PosixRule { month, week, weekday, time })
+    }
+}
+
+// ============================================================================
+// CALENDAR
+// ============================================================================
+
+/// Days since 1970-01-01 to (year, month, day). Howard Hinnant's algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn days_in_month(year: i64, month: u32) -> i64 {
+    let next = if month == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, month + 1, 1) };
+    next - days_from_civil(year, month, 1)
+}
+
+/// 0 = Sunday.
+fn weekday(days: i64) -> i64 {
+    (days + 4).rem_euclid(7)
+}
+
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!("{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60)
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use crate::time::{at, from_local, is_valid_zone, posix_offset};
+
+    #[test]
+    fn formats_fixed_offsets() {
+        // 2026-03-14 08:26:00 UTC
+        let local = at(1_773_476_760, "+05:30").unwrap();
+        assert_eq!(local.date, "2026-03-14");
+        assert_eq!(local.time, "13:56");
+        assert_eq!(local.weekday, "Saturday");
+        assert_eq!(local.describe(), "Saturday, 2026-03-14 13:56 (+05:30, UTC+05:30)");
+
+        assert_eq!(at(0, "UTC-5").unwrap().date, "1969-12-31");
+    }
+
+    #[test]
+    fn converts_wall_clock_time_back() {
+        assert_eq!(from_local(2026, 3, 14, 13 * 3600 + 56 * 60, "+05:30").unwrap(), 1_773_476_760);
+        assert_eq!(from_local(1970, 1, 1, 0, "UTC").unwrap(), 0);
+    }
+
+    #[test]
+    fn posix_rules_switch_daylight_time() {
+        let berlin = "CET-1CEST,M3.5.0,M10.5.0/3";
+        // 2026-01-15 and 2026-07-15, noon UTC
+        assert_eq!(posix_offset(berlin, 1_768_478_400), Some(3600));
+        assert_eq!(posix_offset(berlin, 1_784_116_800), Some(7200));
+        // Southern hemisphere: daylight time spans the new year
+        let sydney = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+        assert_eq!(posix_offset(sydney, 1_768_478_400), Some(39600));
+        assert_eq!(posix_offset(sydney, 1_784_116_800), Some(36000));
+    }
+
+    #[test]
+    fn reads_host_zoneinfo() {
+        if !std::path::Path::new("/usr/share/zoneinfo/Europe/Berlin").exists() {
+            return;
+        }
+        assert_eq!(at(1_768_478_400, "Europe/Berlin").unwrap().utc_offset, 3600);
+        assert_eq!(at(1_784_116_800, "Europe/Berlin").unwrap().utc_offset, 7200);
+        // Far past the file's listed transitions, the footer rule applies
+        assert_eq!(at(4_102_444_800 + 180 * 86_400, "Europe/Berlin").unwrap().utc_offset, 7200);
+        assert_eq!(at(1_768_478_400, "Asia/Kolkata").unwrap().utc_offset, 19_800);
+    }
+
+    #[test]
+    fn rejects_zone_paths_outside_zoneinfo() {
+        assert!(!is_valid_zone("../../etc/passwd"));
+        assert!(!is_valid_zone("/etc/localtime"));
+        assert!(!is_valid_zone("Not/AZone"));
+        assert!(is_valid_zone("UTC"));
+        assert!(is_valid_zone("GMT+2"));
+    }
+}
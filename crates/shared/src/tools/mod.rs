@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use anyhow::Result;
 use serde_json::Value;
 
-use crate::schemas::{Tool, ToolHandler, ToolLocation, ToolSchema};
+use crate::schemas::{Tool, ToolHandler, ToolLocation, ToolSchema, ToolbeltInfo};
+use crate::DeviceContext;
 
 pub mod toolbelts;
 
@@ -11,24 +12,65 @@ static TOOL_REGISTRY: Lazy<HashMap<&'static str, ToolHandler>> = Lazy::new(|| {
     let mut map = HashMap::new();
 
     for (name, handler) in toolbelts::file_smith::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::git_smith::TOOL_ENTRIES { map.insert(*name, *handler); }
     for (name, handler) in toolbelts::archivist::TOOL_ENTRIES { map.insert(*name, *handler); }
     for (name, handler) in toolbelts::web_search::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::clock::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::places::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::reports::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::clipboard::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::screen::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::notifier::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::containers::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::log_scout::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::shell_smith::TOOL_ENTRIES { map.insert(*name, *handler); }
+    for (name, handler) in toolbelts::watcher::TOOL_ENTRIES { map.insert(*name, *handler); }
     map
 });
 
 static TOOL_SCHEMAS: Lazy<Vec<ToolSchema>> = Lazy::new(|| {
     let mut schemas = Vec::new();
     schemas.extend(toolbelts::file_smith::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::git_smith::TOOL_SCHEMAS.iter().cloned());
     schemas.extend(toolbelts::archivist::TOOL_SCHEMAS.iter().cloned());
     schemas.extend(toolbelts::web_search::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::clock::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::places::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::reports::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::clipboard::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::screen::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::notifier::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::containers::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::log_scout::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::shell_smith::TOOL_SCHEMAS.iter().cloned());
+    schemas.extend(toolbelts::watcher::TOOL_SCHEMAS.iter().cloned());
     schemas
 });
 
-pub fn use_tool(name: &str, args: &Value) -> Result<String> {
+static TOOL_EXAMPLES: Lazy<HashMap<&'static str, Value>> = Lazy::new(|| {
+    let mut map = HashMap::new();
+    for (name, example) in toolbelts::file_smith::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::git_smith::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::archivist::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::web_search::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::clock::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::places::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::reports::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::clipboard::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::screen::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::notifier::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::containers::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::log_scout::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::shell_smith::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    for (name, example) in toolbelts::watcher::TOOL_EXAMPLES.iter() { map.insert(*name, example.clone()); }
+    map
+});
+
+pub fn use_tool(name: &str, args: &Value, ctx: &DeviceContext) -> Result<String> {
     TOOL_REGISTRY
         .get(name)
         .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", name))
-        .and_then(|handler| handler(args))
+        .and_then(|handler| handler(args, ctx))
 }
 
 pub fn get_tools() -> Vec<Tool> {
@@ -71,4 +113,34 @@ pub fn get_tool_schemas_for(prefixes: &[&str]) -> Vec<&'static ToolSchema> {
         .iter()
         .filter(|s| prefixes.iter().any(|p| s.name.starts_with(p)))
         .collect()
-}
\ No newline at end of file
+}
+
+/// Arguments of a typical call to the tool, when its toolbelt declares one.
+pub fn tool_example(name: &str) -> Option<&'static Value> {
+    TOOL_EXAMPLES.get(name)
+}
+
+/// Every registered toolbelt, in registration order.
+pub fn toolbelts() -> Vec<&'static ToolbeltInfo> {
+    vec![
+        &toolbelts::file_smith::TOOLBELT,
+        &toolbelts::git_smith::TOOLBELT,
+        &toolbelts::archivist::TOOLBELT,
+        &toolbelts::web_search::TOOLBELT,
+        &toolbelts::clock::TOOLBELT,
+        &toolbelts::places::TOOLBELT,
+        &toolbelts::reports::TOOLBELT,
+        &toolbelts::clipboard::TOOLBELT,
+        &toolbelts::screen::TOOLBELT,
+        &toolbelts::notifier::TOOLBELT,
+        &toolbelts::containers::TOOLBELT,
+        &toolbelts::log_scout::TOOLBELT,
+        &toolbelts::shell_smith::TOOLBELT,
+        &toolbelts::watcher::TOOLBELT,
+    ]
+}
+
+/// Names of the registered toolbelts, e.g. `FileSmith`, in registration order.
+pub fn toolbelt_names() -> Vec<&'static str> {
+    toolbelts().into_iter().map(|t| t.name).collect()
+}
@@ -1,140 +1,682 @@
+use std::sync::Arc;
 use anyhow::Result;
-use crate::{register_toolbelt, ToolLocation, db};
+use crate::db::{self, Db};
+use crate::embeddings::{nearest, EmbeddingClient};
+use crate::memory::{top_keywords, MemoryStore, MAX_TIMELINE_WEEKS};
+use crate::time;
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
 
-pub struct Archivist;
+/// Longest output any Archivist tool hands back, in characters.
+pub const MAX_OUTPUT_CHARS: usize = 20_000;
 
-impl Default for Archivist {
-    fn default() -> Self {
-        Self
+/// Longest single message get_conversation shows, in characters.
+pub const MAX_MESSAGE_CHARS: usize = 2_000;
+
+/// Largest page a paginated tool returns.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Token budget load_conversation_context fits its output into, unless
+/// asked for another, and the most it may be asked for.
+pub const DEFAULT_CONTEXT_TOKENS: u64 = 2_000;
+pub const MAX_CONTEXT_TOKENS: u64 = 4_000;
+
+/// Rough characters per token, for budgeting without a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Longest earlier request load_conversation_context lists, in characters.
+const SUMMARY_LINE_CHARS: usize = 160;
+
+/// Most words most_discussed_keywords returns.
+pub const MAX_KEYWORDS: u64 = 50;
+
+/// Most results semantic_search returns.
+pub const MAX_SEMANTIC_RESULTS: u64 = 20;
+
+/// Furthest back the analytics tools look short of all time, in days.
+const MAX_DAYS: u64 = 36_500;
+
+const DAY_SECONDS: i64 = 86_400;
+
+pub struct Archivist {
+    db: Arc<Db>,
+    memories: MemoryStore,
+    /// Embeds semantic_search queries; None when no embedding model is set.
+    embeddings: Option<EmbeddingClient>,
+}
+
+impl Archivist {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { memories: MemoryStore::new(db.clone()), db, embeddings: None }
+    }
+
+    /// Search by meaning with `embeddings`, if an embedding model is configured.
+    pub fn with_embeddings(mut self, embeddings: Option<EmbeddingClient>) -> Self {
+        self.embeddings = embeddings;
+        self
     }
 }
 
 register_toolbelt! {
     Archivist {
-        description: "Tool for managing chat history. All queries are automatically scoped to the current device.",
+        description: "Tool for managing chat history. All queries are automatically scoped to the current device; locked sensitive conversations are hidden.",
         location: ToolLocation::Server,
+        risk: ToolRisk::Medium,
         tools: {
             "query_db" => query_db {
-                description: "Runs a SQL query against the database.",
-                params: ["query": "string" => "SQL query string", "params": "array" => "Ordered parameter values for ?1, ?2, etc."]
+                description: "Runs a read-only SELECT against the device_* views (device_conversations, device_messages, device_tasks, device_execution_traces). Returns at most 200 rows.",
+                params: ["query": "string" => "SQL SELECT statement", "params": ParamType::array(ParamType::Any) => "Ordered parameter values for ?1, ?2, etc." = []],
+                example: { "query": "SELECT id, title FROM device_conversations WHERE created > ?1", "params": [1760000000] }
             },
             "list_tables" => list_tables {
-                description: "Lists all tables and views in the database",
+                description: "Lists the views query_db can read and their columns",
                 params: []
             },
             "list_conversations" => list_conversations {
-                description: "Lists all conversations for the current device with their IDs and titles",
-                params: []
+                description: "Lists the current device's conversations with their IDs and titles, most recently used first, one page at a time",
+                params: [
+                    "limit": "integer" => "Conversations per page (max: 100)" = 20,
+                    "offset": "integer" => "Number of conversations to skip" = 0,
+                    "page": "integer" => "1-based page number; overrides offset" = null
+                ],
+                example: { "limit": 10, "page": 2 }
             },
             "get_conversation" => get_conversation {
-                description: "Retrieves a conversation and all messages by title for the current device",
-                params: ["title": "string" => "Title of the conversation to retrieve"]
+                description: "Retrieves a conversation by title for the current device, one page of messages at a time. Long messages are shortened.",
+                params: [
+                    "title": "string" => "Title of the conversation to retrieve",
+                    "limit": "integer" => "Messages per page (max: 100)" = 50,
+                    "offset": "integer" => "Number of messages to skip" = 0,
+                    "page": "integer" => "1-based page number; overrides offset" = null
+                ],
+                example: { "title": "Trip to Lisbon" }
+            },
+            "load_conversation_context" => load_conversation_context {
+                description: "Loads a conversation by title in a compact form meant to be carried into the current chat: a summary of the earlier requests and the last few messages, fitted to a token budget. Prefer this over get_conversation when the user wants to pick up where another conversation left off.",
+                params: [
+                    "title": "string" => "Title of the conversation to load",
+                    "max_messages": "integer" => "Most recent messages to include in full (max: 100)" = 10,
+                    "max_tokens": "integer" => "Rough size limit for the whole output (max: 4000)" = 2000
+                ],
+                example: { "title": "Trip to Lisbon", "max_messages": 6 }
+            },
+            "add_tag" => add_tag {
+                description: "Tags a conversation (e.g. 'work', 'personal') so it can be found with search_by_tag",
+                params: [
+                    "conversation_id": "integer" => "The conversation to tag",
+                    "tag": "string" => "Tag name: letters, digits, '-' or '_'"
+                ],
+                example: { "conversation_id": 42, "tag": "work" }
+            },
+            "remove_tag" => remove_tag {
+                description: "Removes a tag from a conversation",
+                params: [
+                    "conversation_id": "integer" => "The conversation to untag",
+                    "tag": "string" => "Tag name to remove"
+                ],
+                example: { "conversation_id": 42, "tag": "work" }
+            },
+            "search_by_tag" => search_by_tag {
+                description: "Lists conversations carrying a tag, most recently used first. With no tag, lists every tag in use and how many conversations have it.",
+                params: ["tag": "string" => "Tag to look for" = ""],
+                example: { "tag": "work" }
+            },
+            "search_memories" => search_memories {
+                description: "Searches the user's saved memories in a profile by keyword, most relevant first. Empty query lists the profile's memories, newest first.",
+                params: [
+                    "query": "string" => "Keywords to look for" = "",
+                    "profile": "string" => "Profile to search, e.g. 'work'" = "default",
+                    "limit": "integer" => "Maximum memories to return (max: 100)" = 20
+                ],
+                example: { "query": "allergies", "profile": "default" }
+            },
+            "semantic_search" => semantic_search {
+                description: "Finds past messages and saved memories in a profile that mean the same as the query, even when they use other words, most similar first. Use it when keywords miss, e.g. 'that time we talked about moving abroad'.",
+                params: [
+                    "query": "string" => "What to look for, in plain words",
+                    "top_k": "integer" => "How many results to return (max: 20)" = 5,
+                    "profile": "string" => "Profile to search, e.g. 'work'" = "default"
+                ],
+                example: { "query": "worries about changing jobs", "top_k": 5 }
+            },
+            "memory_timeline" => memory_timeline {
+                description: "Shows what the user was busy with week by week: memories saved, the words they used most and the conversations they were in, newest week first. Use it for questions like 'what was I focused on last month?', then open conversations by title with get_conversation.",
+                params: [
+                    "profile": "string" => "Profile to look at, e.g. 'work'" = "default",
+                    "weeks": "integer" => "Weeks back to cover, this one included (max: 52)" = 4
+                ],
+                example: { "profile": "work", "weeks": 5 }
+            },
+            "count_conversations" => count_conversations {
+                description: "Counts the user's activity over a span of days: conversations started and active, messages sent, days active, and active conversations per profile. Use it for questions like 'how much have I used you this month?' instead of paging through list_conversations.",
+                params: ["days": "integer" => "Days back to cover; 0 for all time" = 30],
+                example: { "days": 7 }
+            },
+            "most_discussed_keywords" => most_discussed_keywords {
+                description: "Lists the words that came up in the most of the user's messages over a span of days, most first. Use it for questions like 'what do I ask you about most?'.",
+                params: [
+                    "days": "integer" => "Days back to cover; 0 for all time" = 30,
+                    "limit": "integer" => "Maximum words to return (max: 50)" = 15
+                ],
+                example: { "days": 90, "limit": 10 }
+            },
+            "task_usage_summary" => task_usage_summary {
+                description: "Summarizes the requests handled over a span of days: how they ended, how many were delegated, and which agents and tools did the work most.",
+                params: ["days": "integer" => "Days back to cover; 0 for all time" = 30],
+                example: { "days": 30 }
             },
             "get_task_trace" => get_task_trace {
-                description: "Get the execution trace for a task showing every LLM iteration, what the model reasoned, what tools it called, and how each iteration was classified. Use this to debug agent behavior.",
-                params: ["task_id": "integer" => "The task ID to get traces for"]
+                description: "Get the execution trace for a task showing each LLM iteration, what the model reasoned, what tools it called, and how each iteration was classified. Use this to debug agent behavior.",
+                params: [
+                    "task_id": "integer" => "The task ID to get traces for",
+                    "limit": "integer" => "Iterations per page (max: 100)" = 20,
+                    "offset": "integer" => "Number of iterations to skip" = 0,
+                    "page": "integer" => "1-based page number; overrides offset" = null
+                ],
+                example: { "task_id": 17 }
             },
             "get_trace_detail" => get_trace_detail {
                 description: "Get the full detail for a specific iteration of a task trace, including the complete input context that was sent to the model. Use for deep debugging of a specific decision.",
                 params: [
                     "task_id": "integer" => "The task ID",
                     "iteration": "integer" => "The iteration number to inspect"
-                ]
+                ],
+                example: { "task_id": 17, "iteration": 3 }
             },
         }
     }
 }
 
 impl Archivist {
-    fn query_db(&self, args: &serde_json::Value) -> Result<String> {
+    fn query_db(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let query = args["query"].as_str().unwrap_or("");
         if query.is_empty() {
             return Ok("Error: query cannot be empty".to_string());
         }
 
         let params_json = args["params"].as_array()
-            .map(|arr| arr.clone())
+            .cloned()
             .unwrap_or_default();
 
         let params: Vec<rusqlite::types::Value> = params_json.iter()
-            .map(|v| db::json_to_rusqlite(v))
+            .map(db::json_to_rusqlite)
             .collect();
 
-        db::get().query(query, rusqlite::params_from_iter(params))
+        // Rejections are the model's to fix, not a tool failure
+        match self.db.query_sandboxed(ctx.device_id, query, rusqlite::params_from_iter(params)) {
+            Ok(rows) => Ok(truncate_output(rows)),
+            Err(e) => Ok(format!("Error: {}. Only SELECTs over the device_* views are allowed.", e)),
+        }
     }
 
-    fn list_tables(&self, _args: &serde_json::Value) -> Result<String> {
-        db::get().query(
-            "SELECT name, type FROM sqlite_master WHERE type IN ('table', 'view') ORDER BY type, name",
-            rusqlite::params![],
-        )
+    fn list_tables(&self, _args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        self.db.describe_sandbox(ctx.device_id)
     }
 
-    fn list_conversations(&self, _args: &serde_json::Value) -> Result<String> {
-        db::get().query(
+    fn list_conversations(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let page = Page::from_args(args, 20);
+        let total = self.db.query_row_optional(
+            "SELECT COUNT(*) FROM conversations WHERE device_id = ?1 AND (lock_hash IS NULL OR unlocked_until > ?2)",
+            rusqlite::params![ctx.device_id, db::now()],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0) as u64;
+
+        let rows = self.db.query(
             "SELECT id, title, created, last_accessed
              FROM conversations
-             ORDER BY last_accessed DESC",
-            rusqlite::params![],
-        )
+             WHERE device_id = ?1 AND (lock_hash IS NULL OR unlocked_until > ?4)
+             ORDER BY last_accessed DESC
+             LIMIT ?2 OFFSET ?3",
+            rusqlite::params![ctx.device_id, page.limit as i64, page.offset as i64, db::now()],
+        )?;
+        let conversations: Vec<serde_json::Value> = serde_json::from_str(&rows)?;
+
+        let mut output = serde_json::json!({
+            "total": total,
+            "offset": page.offset,
+            "conversations": conversations,
+        });
+        if let Some(more) = page.more(conversations.len() as u64, total, "conversations") {
+            output["more"] = more.into();
+        }
+        Ok(truncate_output(output.to_string()))
+    }
+
+    fn add_tag(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let Some((conversation_id, tag)) = tag_args(args) else {
+            return Ok("Error: conversation_id and tag are required".to_string());
+        };
+        match self.db.add_conversation_tag(ctx.device_id as u64, conversation_id, tag) {
+            Ok(true) => Ok(format!("Tagged conversation {} with '{}'", conversation_id, tag)),
+            Ok(false) => Ok(format!("Conversation {} already has tag '{}'", conversation_id, tag)),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn remove_tag(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let Some((conversation_id, tag)) = tag_args(args) else {
+            return Ok("Error: conversation_id and tag are required".to_string());
+        };
+        match self.db.remove_conversation_tag(ctx.device_id as u64, conversation_id, tag) {
+            Ok(true) => Ok(format!("Removed tag '{}' from conversation {}", tag, conversation_id)),
+            Ok(false) => Ok(format!("Conversation {} has no tag '{}'", conversation_id, tag)),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn search_by_tag(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let tag = args["tag"].as_str().unwrap_or("").trim();
+        let output = if tag.is_empty() {
+            serde_json::json!({ "tags": self.db.list_tags(ctx.device_id as u64)? })
+        } else {
+            serde_json::json!({
+                "tag": tag,
+                "conversations": self.db.conversations_with_tag(ctx.device_id as u64, tag)?,
+            })
+        };
+        Ok(truncate_output(output.to_string()))
+    }
+
+    fn search_memories(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let query = args["query"].as_str().unwrap_or("").trim();
+        let profile = args["profile"].as_str().unwrap_or(db::DEFAULT_PROFILE);
+        let limit = args["limit"].as_u64().unwrap_or(20).clamp(1, MAX_PAGE_SIZE) as usize;
+
+        let memories = if query.is_empty() {
+            self.memories.list(ctx.device_id as u64, profile).map(|mut all| {
+                all.reverse();
+                all.truncate(limit);
+                all
+            })
+        } else {
+            self.memories.search(ctx.device_id as u64, profile, query, limit)
+        };
+        match memories {
+            Ok(memories) => Ok(truncate_output(serde_json::json!({
+                "profile": profile,
+                "memories": memories,
+            }).to_string())),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn semantic_search(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let query = args["query"].as_str().unwrap_or("").trim();
+        if query.is_empty() {
+            return Ok("Error: query cannot be empty".to_string());
+        }
+        let Some(embeddings) = &self.embeddings else {
+            return Ok("Error: semantic search is not configured on this server; use search_memories or query_db instead".to_string());
+        };
+        let profile = args["profile"].as_str().unwrap_or(db::DEFAULT_PROFILE);
+        let top_k = args["top_k"].as_u64().unwrap_or(5).clamp(1, MAX_SEMANTIC_RESULTS) as usize;
+
+        let texts = match self.db.embedded_texts(ctx.device_id as u64, profile, embeddings.model()) {
+            Ok(texts) => texts,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+        if texts.is_empty() {
+            return Ok(format!("Nothing in profile '{}' is indexed for semantic search yet", profile));
+        }
+
+        let query_vector = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(embeddings.embed(query))
+        });
+        let query_vector = match query_vector {
+            Ok(vector) => vector,
+            Err(e) => return Ok(format!("Error: could not embed the query: {}", e)),
+        };
+
+        let results: Vec<serde_json::Value> = nearest(&query_vector, texts.iter().map(|t| t.vector.as_slice()), top_k)
+            .into_iter()
+            .map(|(index, score)| {
+                let text = &texts[index];
+                serde_json::json!({
+                    "score": (score * 1000.0).round() / 1000.0,
+                    "kind": text.source,
+                    "conversation_id": text.conversation_id,
+                    "title": text.title,
+                    "date": time::at(text.created, "UTC").ok().map(|at| at.date),
+                    "text": truncate_chars(&text.text, MAX_MESSAGE_CHARS),
+                })
+            })
+            .collect();
+        Ok(truncate_output(serde_json::json!({
+            "profile": profile,
+            "results": results,
+        }).to_string()))
     }
 
-    fn get_task_trace(&self, args: &serde_json::Value) -> Result<String> {
+    fn memory_timeline(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let profile = args["profile"].as_str().unwrap_or(db::DEFAULT_PROFILE);
+        let weeks = args["weeks"].as_u64().unwrap_or(4).min(MAX_TIMELINE_WEEKS as u64) as u32;
+
+        match self.memories.timeline(ctx.device_id as u64, profile, weeks) {
+            Ok(timeline) => {
+                let weeks: Vec<serde_json::Value> = timeline.iter().map(|week| {
+                    let mut entry = serde_json::json!(week);
+                    // Dates read better to the model than timestamps
+                    if let Ok(start) = time::at(week.week_start, "UTC") {
+                        entry["week_of"] = start.date.into();
+                    }
+                    entry
+                }).collect();
+                Ok(truncate_output(serde_json::json!({
+                    "profile": profile,
+                    "weeks": weeks,
+                }).to_string()))
+            }
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn count_conversations(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let since = since_days(args);
+        match self.db.conversation_counts(ctx.device_id as u64, since) {
+            Ok(counts) => {
+                let mut output = serde_json::json!(counts);
+                output["since"] = since_date(since);
+                Ok(truncate_output(output.to_string()))
+            }
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn most_discussed_keywords(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let since = since_days(args);
+        let limit = args["limit"].as_u64().unwrap_or(15).clamp(1, MAX_KEYWORDS) as usize;
+        match self.db.user_message_texts(ctx.device_id as u64, since) {
+            Ok(texts) => Ok(truncate_output(serde_json::json!({
+                "since": since_date(since),
+                "messages": texts.len(),
+                "keywords": top_keywords(&texts, limit),
+            }).to_string())),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn task_usage_summary(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let since = since_days(args);
+        match self.db.task_usage(ctx.device_id as u64, since) {
+            Ok(usage) => {
+                let mut output = serde_json::json!(usage);
+                output["since"] = since_date(since);
+                Ok(truncate_output(output.to_string()))
+            }
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+
+    fn get_task_trace(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let task_id = args["task_id"].as_u64().unwrap_or(0);
         if task_id == 0 {
             return Ok("Error: task_id is required".to_string());
         }
-        db::get().get_execution_traces(task_id)
+        if !self.owns_task(ctx, task_id)? {
+            return Ok(format!("Error: Task {} not found", task_id));
+        }
+
+        let page = Page::from_args(args, 20);
+        let total = self.db.count_execution_traces(task_id)?;
+        let rows = self.db.get_execution_traces(task_id, page.limit, page.offset)?;
+        let iterations: Vec<serde_json::Value> = serde_json::from_str(&rows)?;
+
+        let mut output = serde_json::json!({
+            "total": total,
+            "offset": page.offset,
+            "iterations": iterations,
+        });
+        if let Some(more) = page.more(iterations.len() as u64, total, "iterations") {
+            output["more"] = more.into();
+        }
+        Ok(truncate_output(output.to_string()))
     }
 
-    fn get_trace_detail(&self, args: &serde_json::Value) -> Result<String> {
+    fn get_trace_detail(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let task_id = args["task_id"].as_u64().unwrap_or(0);
         let iteration = args["iteration"].as_u64().unwrap_or(0) as u32;
         if task_id == 0 || iteration == 0 {
             return Ok("Error: task_id and iteration are required".to_string());
         }
-        db::get().get_execution_trace_detail(task_id, iteration)
+        if !self.owns_task(ctx, task_id)? {
+            return Ok(format!("Error: Task {} not found", task_id));
+        }
+        Ok(truncate_output(self.db.get_execution_trace_detail(task_id, iteration)?))
     }
 
-    fn get_conversation(&self, args: &serde_json::Value) -> Result<String> {
+    fn get_conversation(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let title = args["title"].as_str().unwrap_or("");
         if title.is_empty() {
             return Ok("Error: title cannot be empty".to_string());
         }
 
-        let conv_result = db::get().query(
-            "SELECT id, title FROM conversations WHERE title = ?1",
-            rusqlite::params![title],
-        )?;
-
-        let conversations: Vec<serde_json::Value> = serde_json::from_str(&conv_result)?;
-        if conversations.is_empty() {
+        let Some((conv_id, conv_title)) = self.find_conversation(title, ctx)? else {
             return Ok(format!("Error: Conversation '{}' not found", title));
-        }
-
-        let conv = &conversations[0];
-        let conv_id = conv["id"].as_i64().unwrap_or(0);
-        let conv_title = conv["title"].as_str().unwrap_or("Untitled");
+        };
 
-        let mut output = String::new();
-        output.push_str(&format!("title: {}\n", conv_title));
-        output.push_str("\nmessages:\n");
+        let page = Page::from_args(args, 50);
+        let total = self.db.query_row_optional(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1",
+            rusqlite::params![conv_id],
+            |row| row.get::<_, i64>(0),
+        )?.unwrap_or(0) as u64;
 
-        let messages_result = db::get().query(
+        let messages_result = self.db.query(
             "SELECT role, message FROM messages
              WHERE conversation_id = ?1
-             ORDER BY m_order",
-            rusqlite::params![conv_id],
+             ORDER BY m_order
+             LIMIT ?2 OFFSET ?3",
+            rusqlite::params![conv_id, page.limit as i64, page.offset as i64],
         )?;
-
         let messages: Vec<serde_json::Value> = serde_json::from_str(&messages_result)?;
-        for msg in messages {
+
+        let mut output = String::new();
+        output.push_str(&format!("title: {}\n", conv_title));
+        if messages.is_empty() {
+            output.push_str(&format!("\nmessages: none ({} total)\n", total));
+        } else {
+            output.push_str(&format!(
+                "\nmessages {}-{} of {}:\n",
+                page.offset + 1,
+                page.offset + messages.len() as u64,
+                total
+            ));
+        }
+
+        for msg in &messages {
             let role = msg["role"].as_str().unwrap_or("");
             let content = msg["message"].as_str().unwrap_or("");
             output.push_str(&format!("\nrole: {}\n", role));
-            output.push_str(&format!("message: {}\n", content));
+            output.push_str(&format!("message: {}\n", truncate_chars(content, MAX_MESSAGE_CHARS)));
+        }
+
+        if let Some(more) = page.more(messages.len() as u64, total, "messages") {
+            output.push_str(&format!("\n[{}]\n", more));
+        }
+
+        Ok(truncate_output(output))
+    }
+
+    fn load_conversation_context(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let title = args["title"].as_str().unwrap_or("");
+        if title.is_empty() {
+            return Ok("Error: title cannot be empty".to_string());
         }
+        let Some((conv_id, conv_title)) = self.find_conversation(title, ctx)? else {
+            return Ok(format!("Error: Conversation '{}' not found", title));
+        };
+
+        let max_messages = args["max_messages"].as_u64().unwrap_or(10).clamp(1, MAX_PAGE_SIZE) as usize;
+        let max_tokens = args["max_tokens"].as_u64()
+            .unwrap_or(DEFAULT_CONTEXT_TOKENS)
+            .clamp(100, MAX_CONTEXT_TOKENS) as usize;
+
+        let messages: Vec<(String, String)> = self.db.get_messages(conv_id as u64)?
+            .into_iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .filter_map(|m| {
+                let content = m.content?.trim().to_string();
+                (!content.is_empty()).then_some((m.role, content))
+            })
+            .collect();
+
+        Ok(render_context(&conv_title, &messages, max_messages, max_tokens * CHARS_PER_TOKEN))
+    }
+
+    /// The device's conversation with this title, unless it is locked.
+    fn find_conversation(&self, title: &str, ctx: &DeviceContext) -> Result<Option<(i64, String)>> {
+        self.db.query_row_optional(
+            "SELECT id, COALESCE(title, 'Untitled') FROM conversations
+             WHERE title = ?1 AND device_id = ?2 AND (lock_hash IS NULL OR unlocked_until > ?3)",
+            rusqlite::params![title, ctx.device_id, db::now()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    /// Traces belong to whichever device created the task, and stay hidden
+    /// while its conversation is locked.
+    fn owns_task(&self, ctx: &DeviceContext, task_id: u64) -> Result<bool> {
+        let owned = self.db.query_row_optional(
+            "SELECT 1 FROM tasks t JOIN conversations c ON c.id = t.conversation_id
+             WHERE t.id = ?1 AND t.device_id = ?2 AND (c.lock_hash IS NULL OR c.unlocked_until > ?3)",
+            rusqlite::params![task_id as i64, ctx.device_id, db::now()],
+            |_| Ok(()),
+        )?;
+        Ok(owned.is_some())
+    }
+}
+
+fn tag_args(args: &serde_json::Value) -> Option<(u64, &str)> {
+    let conversation_id = args["conversation_id"].as_u64().filter(|id| *id > 0)?;
+    let tag = args["tag"].as_str().map(str::trim).filter(|t| !t.is_empty())?;
+    Some((conversation_id, tag))
+}
+
+/// Start of the span an analytics tool covers: `days` back from now, or the
+/// beginning for 0.
+fn since_days(args: &serde_json::Value) -> i64 {
+    match args["days"].as_u64().unwrap_or(30) {
+        0 => 0,
+        days => db::now() - (days.min(MAX_DAYS) as i64) * DAY_SECONDS,
+    }
+}
+
+/// The span's first day, which reads better to the model than a timestamp.
+fn since_date(since: i64) -> serde_json::Value {
+    if since == 0 {
+        return "all time".into();
+    }
+    time::at(since, "UTC").map(|start| start.date.into()).unwrap_or(serde_json::Value::Null)
+}
+
+/// A conversation squeezed into `budget_chars`: its last `max_messages`
+/// messages (fewer if they don't fit), each shortened to share three
+/// quarters of the budget, then the earlier user requests, newest kept
+/// first, in what is left.
+fn render_context(title: &str, messages: &[(String, String)], max_messages: usize, budget_chars: usize) -> String {
+    let mut output = format!("Context from conversation '{}' ({} messages)\n", title, messages.len());
+    if messages.is_empty() {
+        output.push_str("\nNo messages yet.\n");
+        return output;
+    }
+
+    let recent_budget = budget_chars * 3 / 4;
+    let per_message = (recent_budget / max_messages.min(messages.len()))
+        .clamp(200, MAX_MESSAGE_CHARS);
+    let mut recent: Vec<String> = Vec::new();
+    let mut used = 0;
+    for (role, content) in messages.iter().rev().take(max_messages) {
+        let line = format!("{}: {}", role, truncate_chars(content, per_message));
+        let len = line.chars().count() + 1;
+        if !recent.is_empty() && used + len > recent_budget {
+            break;
+        }
+        used += len;
+        recent.push(line);
+    }
+    recent.reverse();
+
+    let earlier = &messages[..messages.len() - recent.len()];
+    if !earlier.is_empty() {
+        let requests: Vec<String> = earlier.iter()
+            .filter(|(role, _)| role == "user")
+            .filter_map(|(_, content)| content.lines().map(str::trim).find(|l| !l.is_empty()))
+            .map(|line| truncate_chars(line, SUMMARY_LINE_CHARS))
+            .collect();
+
+        let summary_budget = budget_chars.saturating_sub(used);
+        let mut kept: Vec<&String> = Vec::new();
+        let mut summary_used = 0;
+        for request in requests.iter().rev() {
+            let len = request.chars().count() + 3;
+            if summary_used + len > summary_budget {
+                break;
+            }
+            summary_used += len;
+            kept.push(request);
+        }
+        kept.reverse();
+
+        output.push_str(&format!("\nSummary of the {} earlier messages", earlier.len()));
+        if requests.is_empty() {
+            output.push_str(": no requests from the user.\n");
+        } else {
+            output.push_str(". The user asked:\n");
+            if kept.len() < requests.len() {
+                output.push_str(&format!("- ({} earlier requests left out)\n", requests.len() - kept.len()));
+            }
+            for request in kept {
+                output.push_str(&format!("- {}\n", request));
+            }
+        }
+    }
+
+    output.push_str(&format!("\nLast {} messages:\n", recent.len()));
+    for line in recent {
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Which slice of a result set a paginated tool returns.
+struct Page {
+    limit: u64,
+    offset: u64,
+}
+
+impl Page {
+    fn from_args(args: &serde_json::Value, default_limit: u64) -> Self {
+        let limit = args["limit"].as_u64().unwrap_or(default_limit).clamp(1, MAX_PAGE_SIZE);
+        let offset = match args["page"].as_u64() {
+            Some(page) if page > 0 => (page - 1) * limit,
+            _ => args["offset"].as_u64().unwrap_or(0),
+        };
+        Self { limit, offset }
+    }
+
+    /// "N more" marker when `shown` items from this page don't reach `total`.
+    fn more(&self, shown: u64, total: u64, noun: &str) -> Option<String> {
+        let next = self.offset + shown;
+        (next < total).then(|| format!(
+            "{} more {} — call again with offset={}",
+            total - next, noun, next
+        ))
+    }
+}
+
+/// Cut `text` to `max` characters, noting how much was dropped.
+fn truncate_chars(text: &str, max: usize) -> String {
+    let total = text.chars().count();
+    if total <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max).collect();
+    format!("{}... [{} more chars]", kept, total - max)
+}
 
-        Ok(output)
+fn truncate_output(text: String) -> String {
+    if text.chars().count() <= MAX_OUTPUT_CHARS {
+        return text;
     }
+    format!("{}\n[output truncated; use limit/offset or a narrower query]", truncate_chars(&text, MAX_OUTPUT_CHARS))
 }
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use std::sync::Mutex;
+
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
+
+/// Clipboard text beyond this is truncated before it reaches the model.
+const MAX_CLIPBOARD_CHARS: usize = 20_000;
+
+/// The system clipboard, through the platform's own clipboard API (X11,
+/// Wayland, macOS or Windows).
+pub struct Clipboard {
+    /// Kept for the life of the toolbelt: on X11 and Wayland, text set here
+    /// is only pasteable while its owner is alive.
+    clipboard: Mutex<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Opens the system clipboard, or None when no clipboard is reachable
+    /// (e.g. a headless session).
+    pub fn detect() -> Option<Self> {
+        arboard::Clipboard::new()
+            .ok()
+            .map(|clipboard| Self { clipboard: Mutex::new(clipboard) })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, arboard::Clipboard>> {
+        self.clipboard.lock().map_err(|_| anyhow::anyhow!("Clipboard unavailable"))
+    }
+}
+
+register_toolbelt! {
+    Clipboard {
+        description: "Tool for reading and writing the user's clipboard",
+        location: ToolLocation::Client,
+        risk: ToolRisk::Medium,
+        tools: {
+            "get_clipboard" => get_clipboard {
+                description: "Returns the text currently on the user's clipboard",
+                params: []
+            },
+            "set_clipboard" => set_clipboard {
+                description: "Replaces the user's clipboard with the given text so they can paste it",
+                params: ["text": "string" => "Text to put on the clipboard"],
+                example: { "text": "ssh deploy@10.0.0.4" }
+            },
+        }
+    }
+}
+
+impl Clipboard {
+    fn get_clipboard(&self, _args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let text = match self.lock()?.get_text() {
+            Ok(text) => text,
+            Err(arboard::Error::ContentNotAvailable) => String::new(),
+            Err(e) => return Err(anyhow::anyhow!("Failed to read the clipboard: {}", e)),
+        };
+        if text.is_empty() {
+            return Ok("The clipboard is empty or holds no text.".to_string());
+        }
+
+        let total = text.chars().count();
+        if total > MAX_CLIPBOARD_CHARS {
+            let truncated: String = text.chars().take(MAX_CLIPBOARD_CHARS).collect();
+            return Ok(format!("{}... [truncated at {} chars, clipboard holds {} chars]",
+                              truncated, MAX_CLIPBOARD_CHARS, total));
+        }
+
+        Ok(text)
+    }
+
+    fn set_clipboard(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let text = args["text"].as_str().unwrap_or("");
+        self.lock()?
+            .set_text(text)
+            .map_err(|e| anyhow::anyhow!("Failed to write the clipboard: {}", e))?;
+
+        Ok(format!("Copied {} characters to the clipboard", text.chars().count()))
+    }
+}
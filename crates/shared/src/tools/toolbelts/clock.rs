@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use anyhow::Result;
+use crate::db::Db;
+use crate::time;
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
+
+pub struct Clock {
+    db: Arc<Db>,
+}
+
+impl Clock {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+}
+
+register_toolbelt! {
+    Clock {
+        description: "Tool for telling the current date and time",
+        location: ToolLocation::Server,
+        risk: ToolRisk::Low,
+        tools: {
+            "current_time" => current_time {
+                description: "Gets the current date, time and weekday. Defaults to the user's own timezone.",
+                params: ["timezone": "string" => "IANA timezone (e.g. 'Asia/Tokyo') or UTC offset (e.g. '+05:30')" = ""],
+                example: { "timezone": "Asia/Tokyo" }
+            },
+        }
+    }
+}
+
+impl Clock {
+    fn current_time(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let requested = args["timezone"].as_str().map(str::trim).filter(|s| !s.is_empty());
+        let zone = match requested {
+            Some(zone) => zone.to_string(),
+            None => time::device_zone(&self.db, ctx.device_id as u64),
+        };
+
+        // A bad zone is the model's to fix, not a tool failure
+        match time::now_in(Some(&zone)) {
+            Ok(local) => Ok(serde_json::to_string(&serde_json::json!({
+                "description": local.describe(),
+                "time": local,
+            }))?),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+}
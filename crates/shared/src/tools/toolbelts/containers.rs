@@ -0,0 +1,269 @@
+use anyhow::Result;
+use bollard::query_parameters::{ListContainersOptions, LogsOptions, RestartContainerOptions};
+use bollard::{Docker, API_DEFAULT_VERSION};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
+
+/// Seconds to wait for the container engine to answer.
+const SOCKET_TIMEOUT_SECS: u64 = 60;
+
+/// Most log lines one call returns.
+const MAX_LOG_LINES: i64 = 1000;
+
+/// Most health check results shown when inspecting a container.
+const HEALTH_LOG_ENTRIES: usize = 3;
+
+/// Docker or Podman containers on the user's machine, through the engine's
+/// API socket.
+pub struct Containers {
+    docker: Docker,
+}
+
+impl Containers {
+    /// Connects to the socket in DOCKER_HOST, the Docker socket or a Podman
+    /// socket, whichever exists first. None when there is no container engine.
+    pub fn detect() -> Option<Self> {
+        let socket = std::env::var("DOCKER_HOST")
+            .ok()
+            .filter(|host| host.starts_with("unix://") || host.starts_with("npipe://"))
+            .or_else(|| socket_candidates().into_iter().find(|path| {
+                Path::new(path.trim_start_matches("unix://")).exists()
+            }))?;
+
+        Docker::connect_with_socket(&socket, SOCKET_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            .ok()
+            .map(|docker| Self { docker })
+    }
+}
+
+/// Where Docker and Podman put their API sockets.
+fn socket_candidates() -> Vec<String> {
+    if cfg!(windows) {
+        return vec!["npipe:////./pipe/docker_engine".to_string()];
+    }
+    let mut candidates = vec!["/var/run/docker.sock".to_string()];
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        candidates.push(Path::new(&runtime_dir).join("podman/podman.sock").to_string_lossy().into_owned());
+    }
+    candidates.push("/run/podman/podman.sock".to_string());
+    candidates
+}
+
+register_toolbelt! {
+    Containers {
+        description: "Tool for inspecting and managing Docker or Podman containers on the user's machine",
+        location: ToolLocation::Client,
+        risk: ToolRisk::High,
+        tools: {
+            "list_containers" => list_containers {
+                description: "Lists containers with their image, state and health, e.g. 'running (healthy)'. Stopped containers are included when all is true.",
+                params: [
+                    "all": "boolean" => "Include stopped containers" = false
+                ],
+                example: { "all": true }
+            },
+            "inspect_container" => inspect_container {
+                description: "Shows a container's state, exit code, restart count and its latest health check results. Start here when a container is unhealthy or keeps restarting.",
+                params: [
+                    "container": "string" => "Container name or id"
+                ],
+                example: { "container": "jellyfin" }
+            },
+            "container_logs" => container_logs {
+                description: "Shows the last lines of a container's output (stdout and stderr), with timestamps.",
+                params: [
+                    "container": "string" => "Container name or id",
+                    "lines": "integer" => "How many lines from the end, up to 1000" = 100
+                ],
+                example: { "container": "jellyfin", "lines": 200 }
+            },
+            "restart_container" => restart_container {
+                description: "Restarts a container. Only do this when the user asked for it or agreed to it.",
+                params: [
+                    "container": "string" => "Container name or id"
+                ],
+                example: { "container": "jellyfin" }
+            },
+            "inspect_image" => inspect_image {
+                description: "Shows an image's tags, creation date, size, platform, entrypoint, exposed ports and built-in health check.",
+                params: [
+                    "image": "string" => "Image name, tag or id, e.g. 'jellyfin/jellyfin:latest'"
+                ],
+                example: { "image": "jellyfin/jellyfin:latest" }
+            },
+        }
+    }
+}
+
+impl Containers {
+    fn list_containers(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let options = ListContainersOptions {
+            all: args["all"].as_bool().unwrap_or(false),
+            ..Default::default()
+        };
+        let containers = block_on(self.docker.list_containers(Some(options)))?;
+        if containers.is_empty() {
+            return Ok("No containers".to_string());
+        }
+
+        let lines: Vec<String> = containers
+            .iter()
+            .map(|c| {
+                let name = c.names
+                    .as_ref()
+                    .and_then(|names| names.first())
+                    .map(|name| name.trim_start_matches('/').to_string())
+                    .unwrap_or_else(|| short_id(c.id.as_deref().unwrap_or("?")));
+                format!(
+                    "{} — {} — {}",
+                    name,
+                    c.image.as_deref().unwrap_or("?"),
+                    c.status.as_deref().unwrap_or("unknown"),
+                )
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn inspect_container(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(container) = required(args, "container") else {
+            return Ok("Error: container cannot be empty".to_string());
+        };
+        let details = block_on(self.docker.inspect_container(container, None))?;
+        let details = serde_json::to_value(&details)?;
+        let state = &details["State"];
+
+        let mut out = format!(
+            "{} ({})\nImage: {}\nStatus: {}",
+            details["Name"].as_str().unwrap_or(container).trim_start_matches('/'),
+            short_id(details["Id"].as_str().unwrap_or("?")),
+            details["Config"]["Image"].as_str().unwrap_or("?"),
+            state["Status"].as_str().unwrap_or("unknown"),
+        );
+        if let Some(code) = state["ExitCode"].as_i64().filter(|code| *code != 0) {
+            out.push_str(&format!(", exit code {}", code));
+        }
+        if state["OOMKilled"].as_bool() == Some(true) {
+            out.push_str(", killed for running out of memory");
+        }
+        if let Some(error) = state["Error"].as_str().filter(|e| !e.is_empty()) {
+            out.push_str(&format!("\nError: {}", error));
+        }
+        out.push_str(&format!(
+            "\nStarted: {}\nRestarts: {}",
+            state["StartedAt"].as_str().unwrap_or("?"),
+            details["RestartCount"].as_i64().unwrap_or(0),
+        ));
+        if let Some(policy) = details["HostConfig"]["RestartPolicy"]["Name"].as_str().filter(|p| !p.is_empty()) {
+            out.push_str(&format!(" (policy: {})", policy));
+        }
+
+        let health = &state["Health"];
+        if let Some(status) = health["Status"].as_str() {
+            out.push_str(&format!(
+                "\nHealth: {} (failing streak {})",
+                status,
+                health["FailingStreak"].as_i64().unwrap_or(0),
+            ));
+            let checks = health["Log"].as_array().map(Vec::as_slice).unwrap_or_default();
+            for check in checks.iter().rev().take(HEALTH_LOG_ENTRIES) {
+                out.push_str(&format!(
+                    "\n- {} exit {}: {}",
+                    check["End"].as_str().unwrap_or("?"),
+                    check["ExitCode"].as_i64().unwrap_or(-1),
+                    check["Output"].as_str().unwrap_or("").trim(),
+                ));
+            }
+        }
+        Ok(out)
+    }
+
+    fn container_logs(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(container) = required(args, "container") else {
+            return Ok("Error: container cannot be empty".to_string());
+        };
+        let lines = args["lines"].as_i64().unwrap_or(100).clamp(1, MAX_LOG_LINES);
+        let options = LogsOptions {
+            stdout: true,
+            stderr: true,
+            timestamps: true,
+            tail: lines.to_string(),
+            ..Default::default()
+        };
+
+        let output = block_on(async {
+            let mut stream = self.docker.logs(container, Some(options));
+            let mut output = String::new();
+            while let Some(chunk) = stream.next().await {
+                output.push_str(&chunk?.to_string());
+            }
+            Ok::<_, bollard::errors::Error>(output)
+        })?;
+
+        if output.trim().is_empty() {
+            return Ok(format!("{} has no output", container));
+        }
+        Ok(output)
+    }
+
+    fn restart_container(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(container) = required(args, "container") else {
+            return Ok("Error: container cannot be empty".to_string());
+        };
+        block_on(self.docker.restart_container(container, None::<RestartContainerOptions>))?;
+        Ok(format!("Restarted {}", container))
+    }
+
+    fn inspect_image(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(image) = required(args, "image") else {
+            return Ok("Error: image cannot be empty".to_string());
+        };
+        let details = block_on(self.docker.inspect_image(image))?;
+        let details = serde_json::to_value(&details)?;
+        let config = &details["Config"];
+
+        let list = |value: &Value| -> String {
+            match value {
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" "),
+                Value::Object(map) => map.keys().cloned().collect::<Vec<_>>().join(", "),
+                Value::String(s) => s.clone(),
+                _ => "none".to_string(),
+            }
+        };
+
+        let mut out = format!(
+            "{} ({})\nTags: {}\nCreated: {}\nSize: {:.1} MB\nPlatform: {}/{}\nEntrypoint: {}\nCommand: {}\nExposed ports: {}",
+            image,
+            short_id(details["Id"].as_str().unwrap_or("?").trim_start_matches("sha256:")),
+            list(&details["RepoTags"]),
+            details["Created"].as_str().unwrap_or("?"),
+            details["Size"].as_f64().unwrap_or(0.0) / 1_000_000.0,
+            details["Os"].as_str().unwrap_or("?"),
+            details["Architecture"].as_str().unwrap_or("?"),
+            list(&config["Entrypoint"]),
+            list(&config["Cmd"]),
+            list(&config["ExposedPorts"]),
+        );
+        if let Some(test) = config["Healthcheck"]["Test"].as_array() {
+            out.push_str(&format!("\nHealth check: {}", list(&Value::Array(test.clone()))));
+        }
+        Ok(out)
+    }
+}
+
+/// A trimmed, non-empty string argument.
+fn required<'a>(args: &'a Value, name: &str) -> Option<&'a str> {
+    args[name].as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// The 12-character id Docker shows.
+fn short_id(id: &str) -> String {
+    id.chars().take(12).collect()
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
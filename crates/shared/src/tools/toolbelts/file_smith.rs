@@ -1,20 +1,108 @@
 use anyhow::Result;
 use serde_json::json;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::ToolLocation;
+use crate::{ToolLocation, ToolRisk};
+use crate::archive::{self, ArchiveFormat};
+use crate::db::Db;
+use crate::encoding::{self, Encoding};
+use crate::line_index::LineIndex;
 use crate::register_toolbelt;
+use crate::workspace_index::WorkspaceIndex;
+use crate::DeviceContext;
 
+/// Bytes read_file returns unless asked for another amount, and the most
+/// it may be asked for.
+pub const DEFAULT_READ_BYTES: u64 = 200_000;
+pub const MAX_READ_BYTES: u64 = 1_000_000;
+
+/// Lines read_lines returns unless asked for another amount, and the most
+/// it may be asked for.
+pub const DEFAULT_READ_LINES: u64 = 200;
+pub const MAX_READ_LINES: u64 = 1_000;
+
+/// Longest line read_lines shows, in characters.
+pub const MAX_LINE_CHARS: usize = 1_000;
+
+/// Files whose line offsets are kept between read_lines calls.
+const MAX_LINE_INDEXES: usize = 16;
+
+/// How stale the workspace index may get before a search refreshes it.
+pub const INDEX_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Largest number of results an index search returns.
+pub const MAX_INDEX_RESULTS: u64 = 100;
 
 pub struct FileSmith {
     directory: PathBuf,
+    index: Option<WorkspaceIndex>,
+    last_refresh: Mutex<Option<Instant>>,
+    line_indexes: Mutex<HashMap<PathBuf, LineIndex>>,
 }
 
 impl Default for FileSmith {
     fn default() -> Self {
+        Self::new(std::env::current_dir().unwrap_or_else(|_| PathBuf::from("../../../../../../../..")))
+    }
+}
+
+impl FileSmith {
+    /// A FileSmith whose relative paths resolve against `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
         Self {
-            directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from("../../../../../../../..")),
+            directory: directory.into(),
+            index: None,
+            last_refresh: Mutex::new(None),
+            line_indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep an index of the workspace in `db` for the search_index tools.
+    pub fn with_index(mut self, db: Arc<Db>) -> Self {
+        self.index = Some(WorkspaceIndex::new(db, self.directory.clone()));
+        self
+    }
+
+    /// Where the file's lines start, scanned again only when its size or
+    /// mtime changed since the last call.
+    fn line_index(&self, path: &Path, metadata: &fs::Metadata) -> std::io::Result<LineIndex> {
+        let mut indexes = self.line_indexes.lock().map_err(|e| std::io::Error::other(e.to_string()))?;
+        if let Some(index) = indexes.get(path).filter(|index| index.is_current(metadata)) {
+            return Ok(index.clone());
+        }
+        let index = LineIndex::build(path)?;
+        if indexes.len() >= MAX_LINE_INDEXES {
+            indexes.clear();
+        }
+        indexes.insert(path.to_path_buf(), index.clone());
+        Ok(index)
+    }
+
+    /// The workspace index, refreshed first if it is older than
+    /// [`INDEX_REFRESH_INTERVAL`]. Err is the message for the model.
+    fn fresh_index(&self) -> std::result::Result<&WorkspaceIndex, String> {
+        let index = self.index.as_ref()
+            .ok_or_else(|| "Error: the workspace index is not available on this machine".to_string())?;
+        let mut last_refresh = self.last_refresh.lock().map_err(|e| format!("Error: {}", e))?;
+        if last_refresh.is_none_or(|at| at.elapsed() >= INDEX_REFRESH_INTERVAL) {
+            index.refresh().map_err(|e| format!("Error refreshing the workspace index: {}", e))?;
+            *last_refresh = Some(Instant::now());
+        }
+        Ok(index)
+    }
+
+    /// Relative paths resolve against the workspace, `~/...` against the
+    /// user's home directory.
+    fn resolve(&self, path: &str) -> PathBuf {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+        let in_home = if path == "~" { Some("") } else { path.strip_prefix("~/") };
+        match (in_home, home) {
+            (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+            _ => self.directory.join(path),
         }
     }
 }
@@ -23,17 +111,40 @@ register_toolbelt! {
     FileSmith {
         description: "Tool for interacting with files and directories and performing related specialist",
         location: ToolLocation::Client,
+        risk: ToolRisk::High,
         tools: {
             "read_file" => read_file {
-                description: "Reads the entire contents of a file and returns it as a string",
-                params: ["path": "string" => "Path to the file to read"]
+                description: "Reads a text file and returns its contents. The encoding is detected (UTF-8, UTF-16, Latin-1) unless given. Files over max_bytes are cut to their start, or their end with mode='tail', with a note saying so. Binary files are refused; use read_binary_info for those.",
+                params: [
+                    "path": "string" => "Path to the file to read",
+                    "max_bytes": "integer" => "Most bytes to read (max: 1000000)" = 200000,
+                    "mode": "string" => "Which end of a long file to read: 'head' or 'tail'" = "head",
+                    "encoding": "string" => "'auto', 'utf-8', 'utf-16le', 'utf-16be' or 'latin-1'" = "auto",
+                    "lossy": "boolean" => "Replace bytes that don't decode with U+FFFD instead of failing" = true
+                ],
+                example: { "path": "~/notes/todo.md" }
+            },
+            "read_lines" => read_lines {
+                description: "Reads a range of lines from a text file, numbered, with the file's total line count and size. Use this to page through long logs or source files instead of read_file. A negative start counts from the end: start=-100 reads the last 100 lines.",
+                params: [
+                    "path": "string" => "Path to the file to read",
+                    "start": "integer" => "First line to read, 1-based; negative counts from the end" = 1,
+                    "count": "integer" => "Lines to read (max: 1000)" = 200
+                ],
+                example: { "path": "/var/log/syslog", "start": -100 }
+            },
+            "read_binary_info" => read_binary_info {
+                description: "Describes a file without dumping its bytes: its size, what kind of file its first bytes say it is (e.g. PNG image, ELF executable, ZIP archive), its MIME type, whether it reads as text and in which encoding, and a hex preview of its first 16 bytes",
+                params: ["path": "string" => "Path to the file to inspect"],
+                example: { "path": "~/Downloads/setup.bin" }
             },
             "write_file" => write_file {
                 description: "Writes content to a file, overwriting existing content",
                 params: [
                     "path": "string" => "Path to the file to write",
                     "content": "string" => "Content to write to the file"
-                ]
+                ],
+                example: { "path": "~/notes/todo.md", "content": "- renew passport\n" }
             },
             "replace_text" => replace_text {
                 description: "Replaces the first occurrence of old_text with new_text in a file. Case-sensitive. Returns error if old_text not found.",
@@ -41,7 +152,8 @@ register_toolbelt! {
                     "path": "string" => "Path to the file to modify",
                     "old_text": "string" => "Text to find (exact match)",
                     "new_text": "string" => "Replacement text"
-                ]
+                ],
+                example: { "path": "config.toml", "old_text": "port = 8080", "new_text": "port = 9090" }
             },
             "insert_at_line" => insert_at_line {
                 description: "Inserts content at the specified line number (1-indexed). If line number exceeds file length, inserts at end.",
@@ -49,95 +161,298 @@ register_toolbelt! {
                     "path": "string" => "Path to the file to modify",
                     "line_number": "integer" => "Line number to insert at (1-indexed, 0 inserts at beginning)",
                     "content": "string" => "Content to insert"
-                ]
+                ],
+                example: { "path": "README.md", "line_number": 3, "content": "## Setup" }
             },
             "append_file" => append_file {
                 description: "Appends content to the end of a file. Creates the file if it doesn't exist.",
                 params: [
                     "path": "string" => "Path to the file to append to",
                     "content": "string" => "Content to append"
-                ]
+                ],
+                example: { "path": "~/notes/log.md", "content": "Called the landlord.\n" }
             },
             "copy_file" => copy_file {
                 description: "Copies a file from source to destination",
                 params: [
                     "source": "string" => "Path to the source file",
                     "destination": "string" => "Path to the destination file"
-                ]
+                ],
+                example: { "source": "report.pdf", "destination": "~/Desktop/report.pdf" }
             },
             "move_file" => move_file {
                 description: "Moves a file from source to destination",
                 params: [
                     "source": "string" => "Path to the source file",
                     "destination": "string" => "Path to the destination"
-                ]
+                ],
+                example: { "source": "~/Downloads/invoice.pdf", "destination": "~/Documents/invoices/" }
             },
             "rename_file" => rename_file {
                 description: "Renames a file",
                 params: [
                     "old_name": "string" => "Current file name/path",
                     "new_name": "string" => "New file name/path"
-                ]
+                ],
+                example: { "old_name": "draft.md", "new_name": "final.md" }
             },
             "delete_file" => delete_file {
                 description: "Deletes a file",
-                params: ["path": "string" => "Path to the file to delete"]
+                params: ["path": "string" => "Path to the file to delete"],
+                example: { "path": "~/Downloads/old-installer.dmg" }
             },
             "file_exists" => file_exists {
                 description: "Checks if a file or directory exists. Returns JSON with exists, is_file, and is_directory flags.",
-                params: ["path": "string" => "Path to check"]
+                params: ["path": "string" => "Path to check"],
+                example: { "path": "~/.ssh/config" }
             },
             "get_file_info" => get_file_info {
                 description: "Gets metadata about a file including size, type, permissions, and modification time",
-                params: ["path": "string" => "Path to the file"]
+                params: ["path": "string" => "Path to the file"],
+                example: { "path": "backup.tar.gz" }
             },
             "list_directory" => list_directory {
                 description: "Lists all files and directories in the specified directory. Returns JSON array of names.",
-                params: ["path": "string" => "Path to the directory to list (defaults to current directory)"]
+                params: ["path": "string" => "Path to the directory to list" = "."],
+                example: { "path": "~/Documents" }
             },
             "create_directory" => create_directory {
                 description: "Creates a directory and all parent directories if they don't exist",
-                params: ["path": "string" => "Path to the directory to create"]
+                params: ["path": "string" => "Path to the directory to create"],
+                example: { "path": "~/projects/website/assets" }
             },
             "delete_directory" => delete_directory {
                 description: "Deletes a directory. Use recursive=true to delete non-empty directories.",
                 params: [
                     "path": "string" => "Path to the directory to delete",
-                    "recursive": "boolean" => "Whether to delete directory contents recursively (default: false)"
-                ]
+                    "recursive": "boolean" => "Whether to delete directory contents recursively" = false
+                ],
+                example: { "path": "build", "recursive": true }
             },
             "search_files" => search_files {
                 description: "Recursively searches for files matching a pattern. Returns JSON with matches and count.",
                 params: [
                     "pattern": "string" => "Pattern to search for in filenames",
-                    "path": "string" => "Directory to search in (defaults to current directory)"
-                ]
+                    "path": "string" => "Directory to search in" = "."
+                ],
+                example: { "pattern": ".pdf", "path": "~/Documents" }
+            },
+            "create_archive" => create_archive {
+                description: "Packs a file or directory into a zip or tar.gz archive, e.g. to zip up a folder and put it on the desktop. The format follows the destination's extension (.zip, .tar.gz, .tgz) unless given. Symlinks are left out. Returns JSON with the files, directories and bytes packed.",
+                params: [
+                    "source": "string" => "File or directory to pack",
+                    "destination": "string" => "Path of the archive to create, e.g. '~/Desktop/reports.zip'",
+                    "format": "string" => "'zip' or 'tar.gz'; empty to go by the destination's extension" = "",
+                    "overwrite": "boolean" => "Replace the destination if it already exists" = false
+                ],
+                example: { "source": "~/reports", "destination": "~/Desktop/reports.zip" }
+            },
+            "extract_archive" => extract_archive {
+                description: "Unpacks a zip or tar.gz archive into a directory, creating it if needed. Archives with entries that would land outside the destination, or that unpack to more than 1 GiB or 10000 entries, are refused before anything is written. Symlinks in the archive are skipped. Returns JSON with the files, directories and bytes written.",
+                params: [
+                    "path": "string" => "Path to the archive",
+                    "destination": "string" => "Directory to unpack into" = ".",
+                    "overwrite": "boolean" => "Replace files that already exist in the destination" = false
+                ],
+                example: { "path": "~/Downloads/photos.zip", "destination": "~/Pictures/trip" }
+            },
+            "search_index" => search_index {
+                description: "Searches the contents of every text file in the workspace at once, best matches first. Returns JSON with each match's path, the line its section starts at, and a snippet. Use this before reading files one by one.",
+                params: [
+                    "query": "string" => "Words or identifiers to look for",
+                    "limit": "integer" => "Maximum matches to return (max: 100)" = 20
+                ],
+                example: { "query": "retry backoff", "limit": 10 }
+            },
+            "find_indexed_files" => find_indexed_files {
+                description: "Lists workspace files whose path contains a name, with their size and language, from the workspace index. Faster than search_files on large trees.",
+                params: [
+                    "name": "string" => "Part of the path to look for; empty lists every file" = "",
+                    "language": "string" => "Only files in this language, e.g. 'rust' or 'python'" = "",
+                    "limit": "integer" => "Maximum files to return (max: 100)" = 50
+                ],
+                example: { "name": "config", "language": "rust" }
+            },
+            "index_workspace" => index_workspace {
+                description: "Brings the workspace index up to date now: new and changed files are read, deleted ones dropped. Searches do this on their own when the index is over a minute old.",
+                params: []
             }
         }
     }
 }
 
 impl FileSmith {
-    fn read_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn read_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
-        match fs::read_to_string(&full_path) {
-            Ok(content) => Ok(content),
-            Err(e) => Ok(format!("Error reading file: {}", e)),
+        let max_bytes = args["max_bytes"].as_u64().unwrap_or(DEFAULT_READ_BYTES).clamp(1, MAX_READ_BYTES) as usize;
+        let tail = match args["mode"].as_str().unwrap_or("head") {
+            "head" => false,
+            "tail" => true,
+            other => return Ok(format!("Error: unknown mode '{}'; use 'head' or 'tail'", other)),
+        };
+        let requested = args["encoding"].as_str().unwrap_or("auto");
+        let lossy = args["lossy"].as_bool().unwrap_or(true);
+        let full_path = self.resolve(path);
+
+        let bytes = match fs::read(&full_path) {
+            Ok(bytes) => bytes,
+            Err(e) => return Ok(format!("Error reading file: {}", e)),
+        };
+        let encoding = if requested.eq_ignore_ascii_case("auto") {
+            match encoding::detect(&bytes) {
+                Some(encoding) => encoding,
+                None => return Ok(format!(
+                    "Error: {} looks like a binary file ({} bytes); use read_binary_info to see what it is",
+                    path, bytes.len()
+                )),
+            }
+        } else {
+            match Encoding::parse(requested) {
+                Some(encoding) => encoding,
+                None => return Ok(format!("Error: unknown encoding '{}'", requested)),
+            }
+        };
+
+        let shown = encoding::window(&bytes, max_bytes, tail, encoding);
+        let mut content = match encoding::decode(shown, encoding, lossy) {
+            Ok(content) => content,
+            Err(e) => return Ok(format!("Error decoding {} as {}: {}; pass lossy=true to read it anyway", path, encoding.name(), e)),
+        };
+
+        let mut notes = Vec::new();
+        if shown.len() < bytes.len() {
+            let which = if tail { "last" } else { "first" };
+            notes.push(format!(
+                "showing the {} {} of {} bytes; read again with mode='{}' or a larger max_bytes for the rest",
+                which, shown.len(), bytes.len(), if tail { "head" } else { "tail" }
+            ));
+        }
+        if encoding != Encoding::Utf8 {
+            notes.push(format!("decoded from {}", encoding.name()));
+        }
+        if !notes.is_empty() {
+            content.push_str(&format!("\n[{}]", notes.join("; ")));
+        }
+        Ok(content)
+    }
+    fn read_lines(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        use std::io::Read;
+
+        let path = args["path"].as_str().unwrap_or("");
+        let start = args["start"].as_i64().unwrap_or(1);
+        let count = args["count"].as_u64().unwrap_or(DEFAULT_READ_LINES).clamp(1, MAX_READ_LINES);
+        let full_path = self.resolve(path);
+
+        let metadata = match fs::metadata(&full_path) {
+            Ok(metadata) if metadata.is_dir() => return Ok(format!("Error: {} is a directory", path)),
+            Ok(metadata) => metadata,
+            Err(e) => return Ok(format!("Error reading file: {}", e)),
+        };
+        let mut head = Vec::new();
+        if let Err(e) = fs::File::open(&full_path).and_then(|file| file.take(8192).read_to_end(&mut head)) {
+            return Ok(format!("Error reading file: {}", e));
         }
+        match encoding::detect(&head) {
+            None => return Ok(format!("Error: {} looks like a binary file; use read_binary_info to see what it is", path)),
+            Some(Encoding::Utf16Le | Encoding::Utf16Be) => {
+                return Ok(format!("Error: {} is UTF-16, which read_lines can't split; use read_file instead", path));
+            }
+            Some(_) => {}
+        }
+
+        let index = match self.line_index(&full_path, &metadata) {
+            Ok(index) => index,
+            Err(e) => return Ok(format!("Error reading file: {}", e)),
+        };
+        let mut output = format!("path: {} ({} lines, {} bytes)\n", path, index.lines, index.size);
+        if index.lines == 0 {
+            output.push_str("\nlines: none (empty file)\n");
+            return Ok(output);
+        }
+
+        let start = if start < 0 {
+            index.lines.saturating_sub(start.unsigned_abs()) + 1
+        } else {
+            (start as u64).max(1)
+        };
+        if start > index.lines {
+            return Ok(format!("Error: {} has only {} lines", path, index.lines));
+        }
+
+        let lines = match index.read(&full_path, start, count) {
+            Ok(lines) => lines,
+            Err(e) => return Ok(format!("Error reading file: {}", e)),
+        };
+        let last = start + lines.len() as u64 - 1;
+        let width = last.to_string().len();
+        output.push_str(&format!("\nlines {}-{} of {}:\n", start, last, index.lines));
+        for (i, line) in lines.iter().enumerate() {
+            let text = String::from_utf8_lossy(line);
+            let total = text.chars().count();
+            let text = if total > MAX_LINE_CHARS {
+                let kept: String = text.chars().take(MAX_LINE_CHARS).collect();
+                format!("{}... [{} more chars]", kept, total - MAX_LINE_CHARS)
+            } else {
+                text.into_owned()
+            };
+            output.push_str(&format!("{:>width$}| {}\n", start + i as u64, text, width = width));
+        }
+        if last < index.lines {
+            output.push_str(&format!(
+                "\n[{} more lines — call again with start={}]\n",
+                index.lines - last, last + 1
+            ));
+        }
+        Ok(output)
     }
-    fn write_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn read_binary_info(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        use std::io::Read;
+
+        let path = args["path"].as_str().unwrap_or("");
+        let full_path = self.resolve(path);
+
+        let size = match fs::metadata(&full_path) {
+            Ok(metadata) if metadata.is_dir() => return Ok(format!("Error: {} is a directory", path)),
+            Ok(metadata) => metadata.len(),
+            Err(e) => return Ok(format!("Error reading file: {}", e)),
+        };
+        // The start is enough to recognise a format or guess an encoding
+        let mut head = Vec::new();
+        let read = fs::File::open(&full_path).and_then(|file| file.take(8192).read_to_end(&mut head));
+        if let Err(e) = read {
+            return Ok(format!("Error reading file: {}", e));
+        }
+
+        let format = encoding::sniff(&head);
+        let text_encoding = if format.is_none() { encoding::detect(&head) } else { None };
+        let (kind, mime) = match (format, text_encoding) {
+            (Some(format), _) => format,
+            (None, Some(_)) => ("text", "text/plain"),
+            (None, None) => ("unrecognised binary data", "application/octet-stream"),
+        };
+        let preview = head.iter().take(16).map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+
+        Ok(json!({
+            "size": size,
+            "type": kind,
+            "mime": mime,
+            "is_text": text_encoding.is_some(),
+            "encoding": text_encoding.map(|e| e.name()),
+            "first_bytes": preview,
+        }).to_string())
+    }
+    fn write_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
         let content = args["content"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
         match fs::write(&full_path, content) {
             Ok(_) => Ok(format!("Successfully wrote to {}", path)),
             Err(e) => Ok(format!("Error writing file: {}", e)),
         }
     }
-    fn list_directory(&self, args: &serde_json::Value) -> Result<String> {
+    fn list_directory(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or(".");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
         match fs::read_dir(&full_path) {
             Ok(entries) => {
                 let files: Vec<String> = entries
@@ -149,29 +464,29 @@ impl FileSmith {
             Err(e) => Ok(format!("Error listing directory: {}", e)),
         }
     }
-    fn delete_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn delete_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
         match fs::remove_file(&full_path) {
             Ok(_) => Ok(format!("Successfully deleted {}", path)),
             Err(e) => Ok(format!("Error deleting file: {}", e)),
         }
     }
-    fn create_directory(&self, args: &serde_json::Value) -> Result<String> {
+    fn create_directory(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
         match fs::create_dir_all(&full_path) {
             Ok(_) => Ok(format!("Successfully created directory {}", path)),
             Err(e) => Ok(format!("Error creating directory: {}", e)),
         }
     }
-    fn append_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn append_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         use std::fs::OpenOptions;
         use std::io::Write;
 
         let path = args["path"].as_str().unwrap_or("");
         let content = args["content"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         match OpenOptions::new().append(true).create(true).open(&full_path) {
             Ok(mut file) => {
@@ -183,11 +498,11 @@ impl FileSmith {
             Err(e) => Ok(format!("Error opening file: {}", e)),
         }
     }
-    fn insert_at_line(&self, args: &serde_json::Value) -> Result<String> {
+    fn insert_at_line(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
         let line_number = args["line_number"].as_u64().unwrap_or(1) as usize;
         let content = args["content"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         match fs::read_to_string(&full_path) {
             Ok(file_content) => {
@@ -204,11 +519,11 @@ impl FileSmith {
             Err(e) => Ok(format!("Error reading file: {}", e)),
         }
     }
-    fn replace_text(&self, args: &serde_json::Value) -> Result<String> {
+    fn replace_text(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
         let old_text = args["old_text"].as_str().unwrap_or("");
         let new_text = args["new_text"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         match fs::read_to_string(&full_path) {
             Ok(file_content) => {
@@ -225,42 +540,42 @@ impl FileSmith {
             Err(e) => Ok(format!("Error reading file: {}", e)),
         }
     }
-    fn copy_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn copy_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let source = args["source"].as_str().unwrap_or("");
         let destination = args["destination"].as_str().unwrap_or("");
-        let source_path = self.directory.join(source);
-        let dest_path = self.directory.join(destination);
+        let source_path = self.resolve(source);
+        let dest_path = self.resolve(destination);
 
         match fs::copy(&source_path, &dest_path) {
             Ok(bytes) => Ok(format!("Successfully copied {} bytes from {} to {}", bytes, source, destination)),
             Err(e) => Ok(format!("Error copying file: {}", e)),
         }
     }
-    fn move_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn move_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let source = args["source"].as_str().unwrap_or("");
         let destination = args["destination"].as_str().unwrap_or("");
-        let source_path = self.directory.join(source);
-        let dest_path = self.directory.join(destination);
+        let source_path = self.resolve(source);
+        let dest_path = self.resolve(destination);
 
         match fs::rename(&source_path, &dest_path) {
             Ok(_) => Ok(format!("Successfully moved {} to {}", source, destination)),
             Err(e) => Ok(format!("Error moving file: {}", e)),
         }
     }
-    fn rename_file(&self, args: &serde_json::Value) -> Result<String> {
+    fn rename_file(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let old_name = args["old_name"].as_str().unwrap_or("");
         let new_name = args["new_name"].as_str().unwrap_or("");
-        let old_path = self.directory.join(old_name);
-        let new_path = self.directory.join(new_name);
+        let old_path = self.resolve(old_name);
+        let new_path = self.resolve(new_name);
 
         match fs::rename(&old_path, &new_path) {
             Ok(_) => Ok(format!("Successfully renamed {} to {}", old_name, new_name)),
             Err(e) => Ok(format!("Error renaming file: {}", e)),
         }
     }
-    fn file_exists(&self, args: &serde_json::Value) -> Result<String> {
+    fn file_exists(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         Ok(json!({
             "exists": full_path.exists(),
@@ -268,9 +583,9 @@ impl FileSmith {
             "is_directory": full_path.is_dir()
         }).to_string())
     }
-    fn get_file_info(&self, args: &serde_json::Value) -> Result<String> {
+    fn get_file_info(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         match fs::metadata(&full_path) {
             Ok(metadata) => {
@@ -292,9 +607,9 @@ impl FileSmith {
             Err(e) => Ok(format!("Error getting file info: {}", e)),
         }
     }
-    fn delete_directory(&self, args: &serde_json::Value) -> Result<String> {
+    fn delete_directory(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let path = args["path"].as_str().unwrap_or("");
-        let full_path = self.directory.join(path);
+        let full_path = self.resolve(path);
 
         // Check if recursive flag is set (default to false for safety)
         let recursive = args["recursive"].as_bool().unwrap_or(false);
@@ -311,10 +626,10 @@ impl FileSmith {
             }
         }
     }
-    fn search_files(&self, args: &serde_json::Value) -> Result<String> {
+    fn search_files(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let pattern = args["pattern"].as_str().unwrap_or("");
         let search_path = args["path"].as_str().unwrap_or(".");
-        let full_path = self.directory.join(search_path);
+        let full_path = self.resolve(search_path);
 
         fn search_recursive(dir: &std::path::Path, pattern: &str, results: &mut Vec<String>) -> std::io::Result<()> {
             if dir.is_dir() {
@@ -324,10 +639,10 @@ impl FileSmith {
 
                     if let Some(filename) = path.file_name() {
                         let filename_str = filename.to_string_lossy();
-                        if filename_str.contains(pattern) {
-                            if let Ok(relative) = path.strip_prefix(dir) {
-                                results.push(relative.to_string_lossy().to_string());
-                            }
+                        if filename_str.contains(pattern)
+                            && let Ok(relative) = path.strip_prefix(dir)
+                        {
+                            results.push(relative.to_string_lossy().to_string());
                         }
                     }
 
@@ -348,4 +663,105 @@ impl FileSmith {
             Err(e) => Ok(format!("Error searching files: {}", e)),
         }
     }
+
+    fn create_archive(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let source = args["source"].as_str().unwrap_or("");
+        let destination = args["destination"].as_str().unwrap_or("");
+        let requested = args["format"].as_str().unwrap_or("").trim();
+        let overwrite = args["overwrite"].as_bool().unwrap_or(false);
+        let dest_path = self.resolve(destination);
+
+        let format = if requested.is_empty() {
+            match ArchiveFormat::from_extension(&dest_path) {
+                Some(format) => format,
+                None => return Ok(format!("Error: can't tell the format from '{}'; end it in .zip or .tar.gz, or pass format", destination)),
+            }
+        } else {
+            match ArchiveFormat::parse(requested) {
+                Some(format) => format,
+                None => return Ok(format!("Error: unknown format '{}'; use 'zip' or 'tar.gz'", requested)),
+            }
+        };
+
+        match archive::create(&self.resolve(source), &dest_path, format, overwrite) {
+            Ok(report) => Ok(json!({
+                "archive": dest_path.to_string_lossy(),
+                "files": report.files,
+                "directories": report.directories,
+                "bytes": report.bytes,
+                "skipped": report.skipped,
+            }).to_string()),
+            Err(e) => Ok(format!("Error creating archive: {}", e)),
+        }
+    }
+    fn extract_archive(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let path = args["path"].as_str().unwrap_or("");
+        let destination = args["destination"].as_str().unwrap_or(".");
+        let overwrite = args["overwrite"].as_bool().unwrap_or(false);
+        let dest_path = self.resolve(destination);
+
+        match archive::extract(&self.resolve(path), &dest_path, overwrite) {
+            Ok(report) => Ok(json!({
+                "destination": dest_path.to_string_lossy(),
+                "files": report.files,
+                "directories": report.directories,
+                "bytes": report.bytes,
+                "skipped": report.skipped,
+            }).to_string()),
+            Err(e) => Ok(format!("Error extracting archive: {}", e)),
+        }
+    }
+
+    fn search_index(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let query = args["query"].as_str().unwrap_or("").trim();
+        if query.is_empty() {
+            return Ok("Error: query cannot be empty".to_string());
+        }
+        let limit = args["limit"].as_u64().unwrap_or(20).clamp(1, MAX_INDEX_RESULTS) as usize;
+
+        let index = match self.fresh_index() {
+            Ok(index) => index,
+            Err(message) => return Ok(message),
+        };
+        match index.search(query, limit) {
+            Ok(hits) => Ok(json!({
+                "matches": hits,
+                "count": hits.len()
+            }).to_string()),
+            Err(e) => Ok(format!("Error searching the workspace index: {}", e)),
+        }
+    }
+    fn find_indexed_files(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let name = args["name"].as_str().unwrap_or("").trim();
+        let language = args["language"].as_str()
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty());
+        let limit = args["limit"].as_u64().unwrap_or(50).clamp(1, MAX_INDEX_RESULTS) as usize;
+
+        let index = match self.fresh_index() {
+            Ok(index) => index,
+            Err(message) => return Ok(message),
+        };
+        match index.find(name, language.as_deref(), limit) {
+            Ok(files) => Ok(json!({
+                "files": files,
+                "count": files.len()
+            }).to_string()),
+            Err(e) => Ok(format!("Error searching the workspace index: {}", e)),
+        }
+    }
+    fn index_workspace(&self, _args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(index) = &self.index else {
+            return Ok("Error: the workspace index is not available on this machine".to_string());
+        };
+        match index.refresh() {
+            Ok(report) => {
+                if let Ok(mut last_refresh) = self.last_refresh.lock() {
+                    *last_refresh = Some(Instant::now());
+                }
+                Ok(json!(report).to_string())
+            }
+            Err(e) => Ok(format!("Error refreshing the workspace index: {}", e)),
+        }
+    }
 }
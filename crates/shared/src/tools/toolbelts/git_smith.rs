@@ -0,0 +1,238 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use super::is_on_path;
+use super::shell_smith::{resolve_in_workspace, run_with_timeout};
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
+
+/// Most characters of git output returned per call; the start is kept.
+const MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Most commits git_log lists.
+const MAX_LOG_ENTRIES: u64 = 100;
+
+/// How long one git command may run. Hooks and fsmonitor run as part of
+/// status and commit, and one that hangs mustn't hold the tool forever.
+const GIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Reads and records history in git repositories on the user's machine by
+/// running the `git` command line, so the user's own config and hooks apply.
+pub struct GitSmith {
+    /// Directory repositories are resolved against, as for FileSmith.
+    workspace: PathBuf,
+}
+
+impl GitSmith {
+    /// A GitSmith working in `workspace`, if git is installed.
+    pub fn detect(workspace: PathBuf) -> Option<Self> {
+        is_on_path("git").then_some(Self { workspace })
+    }
+}
+
+register_toolbelt! {
+    GitSmith {
+        description: "Tool for inspecting git repositories on the user's machine and committing changes to them",
+        location: ToolLocation::Client,
+        risk: ToolRisk::High,
+        tools: {
+            "git_status" => git_status {
+                description: "Shows the current branch, how far it is ahead of or behind its upstream, and which files are staged, changed or untracked",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = ""
+                ],
+                example: { "repo": "projects/artificer" }
+            },
+            "git_diff" => git_diff {
+                description: "Shows uncommitted changes as a unified diff: unstaged ones by default, or the staged ones that the next commit would record",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = "",
+                    "path": "string" => "Only changes to this file or directory, relative to the repository. Empty for all" = "",
+                    "staged": "boolean" => "Show staged changes instead of unstaged ones" = false
+                ],
+                example: { "path": "src/main.rs", "staged": true }
+            },
+            "git_log" => git_log {
+                description: "Lists recent commits, newest first, with hash, date, author and subject",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = "",
+                    "path": "string" => "Only commits touching this file or directory. Empty for all" = "",
+                    "limit": "integer" => "How many commits to list (max: 100)" = 10
+                ],
+                example: { "path": "README.md", "limit": 5 }
+            },
+            "git_show" => git_show {
+                description: "Shows one commit's message and changes, or a file's contents as of a commit when path is given",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = "",
+                    "revision": "string" => "Commit hash, branch or tag, e.g. 'HEAD~1'" = "HEAD",
+                    "path": "string" => "File to show as of that revision, relative to the repository. Empty for the commit itself" = ""
+                ],
+                example: { "revision": "a1b2c3d" }
+            },
+            "git_commit" => git_commit {
+                description: "Stages the given files (or every tracked change with all) and commits what is staged. Only commit when the user asked for it or agreed to the message.",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = "",
+                    "message": "string" => "Commit message: a short subject line, then optionally a blank line and a body",
+                    "paths": ParamType::array(ParamType::String) => "Files to stage before committing, relative to the repository" = [],
+                    "all": "boolean" => "Stage every change to tracked files before committing" = false
+                ],
+                example: { "message": "Fix typo in install guide", "paths": ["docs/install.md"] }
+            },
+            "git_branch" => git_branch {
+                description: "Lists local branches with the current one marked, or switches to a branch when name is given (create makes it first, from the current commit)",
+                params: [
+                    "repo": "string" => "Repository directory, relative to the workspace. Empty for the workspace" = "",
+                    "name": "string" => "Branch to switch to. Empty to list branches" = "",
+                    "create": "boolean" => "Create the branch before switching to it" = false
+                ],
+                example: { "name": "fix-login", "create": true }
+            },
+        }
+    }
+}
+
+impl GitSmith {
+    pub(crate) fn git_status(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        Ok(self.git(args, &["status", "--short", "--branch"]))
+    }
+
+    pub(crate) fn git_diff(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let mut git_args = vec!["diff"];
+        if args["staged"].as_bool().unwrap_or(false) {
+            git_args.push("--staged");
+        }
+        let path = args["path"].as_str().unwrap_or("").trim();
+        if !path.is_empty() {
+            git_args.extend(["--", path]);
+        }
+        let diff = self.git(args, &git_args);
+        if diff.is_empty() {
+            return Ok("No changes".to_string());
+        }
+        Ok(diff)
+    }
+
+    pub(crate) fn git_log(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let limit = args["limit"].as_u64().unwrap_or(10).clamp(1, MAX_LOG_ENTRIES).to_string();
+        let mut git_args = vec!["log", "--date=short", "--format=%h %ad %an: %s", "-n", limit.as_str()];
+        let path = args["path"].as_str().unwrap_or("").trim();
+        if !path.is_empty() {
+            git_args.extend(["--", path]);
+        }
+        let log = self.git(args, &git_args);
+        if log.is_empty() {
+            return Ok("No commits".to_string());
+        }
+        Ok(log)
+    }
+
+    pub(crate) fn git_show(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let revision = args["revision"].as_str().map(str::trim).filter(|r| !r.is_empty()).unwrap_or("HEAD");
+        if revision.starts_with('-') {
+            return Ok("Error: revision cannot start with '-'".to_string());
+        }
+        let path = args["path"].as_str().unwrap_or("").trim();
+        if path.is_empty() {
+            return Ok(self.git(args, &["show", "--stat", "--patch", revision]));
+        }
+        let object = format!("{}:{}", revision, path);
+        Ok(self.git(args, &["show", object.as_str()]))
+    }
+
+    pub(crate) fn git_commit(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let message = args["message"].as_str().unwrap_or("").trim();
+        if message.is_empty() {
+            return Ok("Error: message cannot be empty".to_string());
+        }
+        let paths: Vec<&str> = args["paths"].as_array()
+            .map(|items| items.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        if !paths.is_empty() {
+            let mut git_args = vec!["add", "--"];
+            git_args.extend(&paths);
+            let added = self.git(args, &git_args);
+            if added.starts_with("Error") {
+                return Ok(added);
+            }
+        }
+
+        let mut git_args = vec!["commit", "-m", message];
+        if args["all"].as_bool().unwrap_or(false) {
+            git_args.push("--all");
+        }
+        Ok(self.git(args, &git_args))
+    }
+
+    pub(crate) fn git_branch(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let name = args["name"].as_str().unwrap_or("").trim();
+        if name.is_empty() {
+            return Ok(self.git(args, &["branch", "--list", "-vv"]));
+        }
+        if name.starts_with('-') {
+            return Ok("Error: branch name cannot start with '-'".to_string());
+        }
+        let mut git_args = vec!["switch"];
+        if args["create"].as_bool().unwrap_or(false) {
+            git_args.push("--create");
+        }
+        git_args.push(name);
+        let switched = self.git(args, &git_args);
+        if switched.starts_with("Error") {
+            return Ok(switched);
+        }
+        Ok(format!("On branch {}", name))
+    }
+
+    /// Run git in the call's repository and return its output, or an
+    /// `Error:` line with what git said went wrong. The repository must be
+    /// inside the workspace, and git doesn't look above the workspace for one.
+    fn git(&self, args: &Value, git_args: &[&str]) -> String {
+        let dir = match resolve_in_workspace(&self.workspace, args["repo"].as_str().unwrap_or("")) {
+            Ok(dir) => dir,
+            Err(e) => return format!("Error: {}", e),
+        };
+
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(&dir)
+            .arg("--no-pager")
+            .args(git_args)
+            // Never wait on a credential or editor prompt nobody can answer
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_EDITOR", "true");
+        if let Ok(workspace) = self.workspace.canonicalize()
+            && let Some(above) = workspace.parent()
+        {
+            command.env("GIT_CEILING_DIRECTORIES", above);
+        }
+        let run = match run_with_timeout(command, None, GIT_TIMEOUT) {
+            Ok(run) => run,
+            Err(e) => return format!("Error: could not run git: {}", e),
+        };
+
+        if run.timed_out {
+            return format!("Error: git {} was stopped after {}s", git_args[0], GIT_TIMEOUT.as_secs());
+        }
+        if run.code != Some(0) {
+            let reason = if run.stderr.trim().is_empty() { run.stdout.trim() } else { run.stderr.trim() };
+            return format!("Error: git {} failed: {}", git_args[0], reason);
+        }
+        truncate(run.stdout.trim_end())
+    }
+}
+
+/// The first `MAX_OUTPUT_CHARS` characters of `text`, marked when cut.
+fn truncate(text: &str) -> String {
+    let count = text.chars().count();
+    if count <= MAX_OUTPUT_CHARS {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+    format!("{}\n[... {} more characters cut; narrow it with path]", kept, count - MAX_OUTPUT_CHARS)
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use crate::tools::toolbelts::git_smith::GitSmith;
+    use crate::DeviceContext;
+
+    /// A workspace holding a repository `repo` with one commit of
+    /// `notes.txt`, and a directory next to the workspace.
+    struct Fixture {
+        root: PathBuf,
+        smith: GitSmith,
+        ctx: DeviceContext,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("artificer-git-{}-{}", name, uuid::Uuid::new_v4()));
+            let repo = root.join("workspace/repo");
+            std::fs::create_dir_all(&repo).unwrap();
+            std::fs::create_dir_all(root.join("outside")).unwrap();
+            git(&repo, &["init", "--quiet", "--initial-branch=main"]);
+            git(&repo, &["config", "user.name", "Test"]);
+            git(&repo, &["config", "user.email", "test@example.com"]);
+            std::fs::write(repo.join("notes.txt"), "first\n").unwrap();
+            git(&repo, &["add", "notes.txt"]);
+            git(&repo, &["commit", "--quiet", "-m", "Add notes"]);
+
+            let smith = GitSmith::detect(root.join("workspace")).expect("git is not installed");
+            Self { root, smith, ctx: DeviceContext::new(1, "key") }
+        }
+
+        fn repo(&self) -> PathBuf {
+            self.root.join("workspace/repo")
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").arg("-C").arg(dir).args(args).output().unwrap();
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn status_diff_and_log_describe_the_repository() {
+        let f = Fixture::new("read");
+        std::fs::write(f.repo().join("notes.txt"), "first\nsecond\n").unwrap();
+        std::fs::write(f.repo().join("todo.txt"), "later\n").unwrap();
+
+        let status = f.smith.git_status(&json!({ "repo": "repo" }), &f.ctx).unwrap();
+        assert!(status.starts_with("## main"), "{}", status);
+        assert!(status.contains(" M notes.txt"));
+        assert!(status.contains("?? todo.txt"));
+
+        let diff = f.smith.git_diff(&json!({ "repo": "repo" }), &f.ctx).unwrap();
+        assert!(diff.contains("+second"));
+        let staged = f.smith.git_diff(&json!({ "repo": "repo", "staged": true }), &f.ctx).unwrap();
+        assert_eq!(staged, "No changes");
+
+        let log = f.smith.git_log(&json!({ "repo": "repo" }), &f.ctx).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(log.ends_with("Test: Add notes"), "{}", log);
+        let shown = f.smith.git_show(&json!({ "repo": "repo", "path": "notes.txt" }), &f.ctx).unwrap();
+        assert_eq!(shown, "first");
+    }
+
+    #[test]
+    fn commits_named_paths_or_every_tracked_change() {
+        let f = Fixture::new("commit");
+        std::fs::write(f.repo().join("notes.txt"), "changed\n").unwrap();
+        std::fs::write(f.repo().join("todo.txt"), "later\n").unwrap();
+
+        let committed = f.smith.git_commit(
+            &json!({ "repo": "repo", "message": "Add todo", "paths": ["todo.txt"] }),
+            &f.ctx,
+        ).unwrap();
+        assert!(!committed.starts_with("Error"), "{}", committed);
+        assert_eq!(git(&f.repo(), &["show", "--name-only", "--format=%s", "HEAD"]).trim(), "Add todo\n\ntodo.txt");
+        // The unnamed change stayed out of the commit
+        assert_eq!(git(&f.repo(), &["status", "--short"]).trim(), "M notes.txt");
+
+        let committed = f.smith.git_commit(&json!({ "repo": "repo", "message": "Update notes", "all": true }), &f.ctx).unwrap();
+        assert!(!committed.starts_with("Error"), "{}", committed);
+        assert_eq!(git(&f.repo(), &["status", "--short"]), "");
+        assert_eq!(git(&f.repo(), &["log", "--format=%s"]).lines().collect::<Vec<_>>(), ["Update notes", "Add todo", "Add notes"]);
+    }
+
+    #[test]
+    fn creates_and_switches_branches() {
+        let f = Fixture::new("branch");
+        let switched = f.smith.git_branch(&json!({ "repo": "repo", "name": "fix-login", "create": true }), &f.ctx).unwrap();
+        assert_eq!(switched, "On branch fix-login");
+        assert_eq!(git(&f.repo(), &["branch", "--show-current"]).trim(), "fix-login");
+
+        let branches = f.smith.git_branch(&json!({ "repo": "repo" }), &f.ctx).unwrap();
+        assert!(branches.contains("* fix-login"));
+        assert!(branches.contains("main"));
+
+        f.smith.git_branch(&json!({ "repo": "repo", "name": "main" }), &f.ctx).unwrap();
+        assert_eq!(git(&f.repo(), &["branch", "--show-current"]).trim(), "main");
+        let missing = f.smith.git_branch(&json!({ "repo": "repo", "name": "nowhere" }), &f.ctx).unwrap();
+        assert!(missing.starts_with("Error: git switch failed"), "{}", missing);
+    }
+
+    #[test]
+    fn refuses_options_in_place_of_names() {
+        let f = Fixture::new("options");
+        let shown = f.smith.git_show(&json!({ "repo": "repo", "revision": "--output=/tmp/x" }), &f.ctx).unwrap();
+        assert_eq!(shown, "Error: revision cannot start with '-'");
+        let branch = f.smith.git_branch(&json!({ "repo": "repo", "name": "-D main" }), &f.ctx).unwrap();
+        assert_eq!(branch, "Error: branch name cannot start with '-'");
+        assert_eq!(git(&f.repo(), &["branch", "--show-current"]).trim(), "main");
+    }
+
+    #[test]
+    fn repositories_outside_the_workspace_are_refused() {
+        let f = Fixture::new("outside");
+        git(&f.root.join("outside"), &["init", "--quiet"]);
+
+        let escaped = f.smith.git_status(&json!({ "repo": "../outside" }), &f.ctx).unwrap();
+        assert_eq!(escaped, "Error: ../outside is outside the workspace");
+        let absolute = f.root.join("outside").display().to_string();
+        let escaped = f.smith.git_status(&json!({ "repo": absolute }), &f.ctx).unwrap();
+        assert!(escaped.ends_with("is outside the workspace"), "{}", escaped);
+
+        // Nor does git look above the workspace for a repository
+        git(&f.root, &["init", "--quiet"]);
+        let above = f.smith.git_status(&json!({}), &f.ctx).unwrap();
+        assert!(above.starts_with("Error: git status failed"), "{}", above);
+    }
+
+    #[test]
+    fn an_empty_message_commits_nothing() {
+        let f = Fixture::new("message");
+        std::fs::write(f.repo().join("notes.txt"), "changed\n").unwrap();
+        let refused = f.smith.git_commit(&json!({ "repo": "repo", "message": "  ", "all": true }), &f.ctx).unwrap();
+        assert_eq!(refused, "Error: message cannot be empty");
+        assert_eq!(git(&f.repo(), &["log", "--format=%s"]).trim(), "Add notes");
+    }
+}
@@ -0,0 +1,496 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::is_on_path;
+use crate::{register_toolbelt, time, DeviceContext, ToolLocation, ToolRisk};
+
+/// Most lines read from one source per call; the newest are kept.
+const MAX_SCAN_LINES: usize = 50_000;
+
+/// Most bytes read from the end of a log file.
+const MAX_READ_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Longest line shown, in characters.
+const MAX_LINE_CHARS: usize = 500;
+
+/// Terms that mark a line as an error, as an FTS5 query.
+const ERROR_QUERY: &str = "error OR err OR fail* OR fatal OR panic* OR crit* OR exception OR denied \
+    OR refused OR timeout OR \"timed out\" OR segfault OR oom OR killed OR unhealthy";
+
+/// Reads journald and log files on the user's machine. Lines are indexed in
+/// an in-memory SQLite FTS5 table so they can be filtered by pattern, and
+/// error lines are grouped into clusters of the same message.
+pub struct LogScout {
+    /// Zone of timestamps in log lines that don't carry an offset.
+    zone: String,
+    journald: bool,
+}
+
+impl Default for LogScout {
+    fn default() -> Self {
+        Self {
+            zone: time::local_zone().unwrap_or_else(|| "UTC".to_string()),
+            journald: is_on_path("journalctl"),
+        }
+    }
+}
+
+struct LogLine {
+    timestamp: Option<i64>,
+    /// The line after its timestamp, for clustering.
+    message_start: usize,
+    text: String,
+}
+
+/// A time window; either end may be open.
+#[derive(Default)]
+struct Window {
+    since: Option<i64>,
+    until: Option<i64>,
+}
+
+impl Window {
+    fn contains(&self, timestamp: i64) -> bool {
+        self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp <= until)
+    }
+
+    fn is_open(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+}
+
+register_toolbelt! {
+    LogScout {
+        description: "Tool for reading and searching system logs and log files on the user's machine",
+        location: ToolLocation::Client,
+        risk: ToolRisk::Low,
+        tools: {
+            "read_logs" => read_logs {
+                description: "Reads the newest lines of a log, optionally only those matching a pattern within a time window. Source is 'journal' for the whole systemd journal, 'journal:<unit>' for one service (e.g. 'journal:nginx.service'), or a log file path.",
+                params: [
+                    "source": "string" => "'journal', 'journal:<unit>' or a log file path",
+                    "pattern": "string" => "Words that must all appear (case-insensitive). Supports \"quoted phrases\", OR, NOT and prefix* matches. Empty for every line" = "",
+                    "since": "string" => "Start of the window: '12h' or '2d' ago, 'yesterday', 'today', 'HH:MM' or 'YYYY-MM-DD HH:MM' in local time. Empty for no start" = "",
+                    "until": "string" => "End of the window, in the same forms. Empty for now" = "",
+                    "lines": "integer" => "How many of the newest matching lines to return, up to 500" = 100
+                ],
+                example: { "source": "journal:nginx.service", "pattern": "upstream timed out", "since": "12h" }
+            },
+            "summarize_errors" => summarize_errors {
+                description: "Groups the error lines of a log (errors, failures, panics, timeouts, denials...) into clusters of the same message with counts and first/last times. Use it to answer 'what went wrong last night?' before reading individual lines.",
+                params: [
+                    "source": "string" => "'journal', 'journal:<unit>' or a log file path",
+                    "since": "string" => "Start of the window, as for read_logs. Empty for no start" = "",
+                    "until": "string" => "End of the window, as for read_logs. Empty for now" = "",
+                    "clusters": "integer" => "How many of the largest clusters to show, up to 50" = 10
+                ],
+                example: { "source": "/var/log/syslog", "since": "yesterday" }
+            },
+        }
+    }
+}
+
+impl LogScout {
+    fn read_logs(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let (lines, window) = match self.load(args) {
+            Ok(loaded) => loaded,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+        let pattern = args["pattern"].as_str().unwrap_or("").trim();
+        let matched = match matching(&lines, pattern) {
+            Ok(matched) => matched,
+            Err(e) => return Ok(format!("Error: invalid pattern '{}': {}", pattern, e)),
+        };
+        let limit = args["lines"].as_u64().unwrap_or(100).clamp(1, 500) as usize;
+
+        let shown = &matched[matched.len().saturating_sub(limit)..];
+        let mut out = format!(
+            "{} of {} matching lines ({} scanned{}):\n",
+            shown.len(),
+            matched.len(),
+            lines.len(),
+            self.describe(&window),
+        );
+        for &index in shown {
+            out.push_str(&truncate(&lines[index].text));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    fn summarize_errors(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let (lines, window) = match self.load(args) {
+            Ok(loaded) => loaded,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+        let errors = matching(&lines, ERROR_QUERY)?;
+        if errors.is_empty() {
+            return Ok(format!("No error lines in {} lines{}", lines.len(), self.describe(&window)));
+        }
+
+        struct Cluster<'a> {
+            count: usize,
+            first: Option<i64>,
+            last: Option<i64>,
+            sample: &'a str,
+        }
+        let mut clusters: HashMap<String, Cluster> = HashMap::new();
+        for &index in &errors {
+            let line = &lines[index];
+            let cluster = clusters.entry(template(&line.text[line.message_start..])).or_insert(Cluster {
+                count: 0,
+                first: line.timestamp,
+                last: None,
+                sample: &line.text,
+            });
+            cluster.count += 1;
+            cluster.last = line.timestamp.or(cluster.last);
+        }
+
+        let mut clusters: Vec<(String, Cluster)> = clusters.into_iter().collect();
+        clusters.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.1.first.cmp(&b.1.first)));
+        let limit = args["clusters"].as_u64().unwrap_or(10).clamp(1, 50) as usize;
+
+        let mut out = format!(
+            "{} error lines in {} lines{}, {} kinds:\n",
+            errors.len(),
+            lines.len(),
+            self.describe(&window),
+            clusters.len(),
+        );
+        for (i, (template, cluster)) in clusters.iter().take(limit).enumerate() {
+            let span = match (cluster.first, cluster.last) {
+                (Some(first), Some(last)) if first != last => format!(" {} – {}", self.format(first), self.format(last)),
+                (Some(first), _) => format!(" {}", self.format(first)),
+                _ => String::new(),
+            };
+            out.push_str(&format!(
+                "\n{}. {}×{}\n   {}\n   e.g. {}\n",
+                i + 1,
+                cluster.count,
+                span,
+                truncate(template),
+                truncate(cluster.sample),
+            ));
+        }
+        if clusters.len() > limit {
+            out.push_str(&format!("\n({} smaller clusters not shown)\n", clusters.len() - limit));
+        }
+        Ok(out)
+    }
+
+    /// The lines of `args["source"]` inside the requested window, oldest first.
+    fn load(&self, args: &Value) -> Result<(Vec<LogLine>, Window)> {
+        let source = args["source"].as_str().unwrap_or("").trim();
+        if source.is_empty() {
+            return Err(anyhow::anyhow!("source cannot be empty"));
+        }
+        let now = time::now_in(None)?.timestamp;
+        let window = Window {
+            since: self.parse_time(args["since"].as_str().unwrap_or(""), now)?,
+            until: self.parse_time(args["until"].as_str().unwrap_or(""), now)?,
+        };
+
+        let lines = if source == "journal" || source == "journald" {
+            self.read_journal(None, &window)?
+        } else if let Some(unit) = source.strip_prefix("journal:").or_else(|| source.strip_prefix("journald:")) {
+            self.read_journal(Some(unit.trim()), &window)?
+        } else {
+            self.read_file(source, &window)?
+        };
+        Ok((lines, window))
+    }
+
+    fn read_journal(&self, unit: Option<&str>, window: &Window) -> Result<Vec<LogLine>> {
+        if !self.journald {
+            return Err(anyhow::anyhow!("this machine has no systemd journal; name a log file instead"));
+        }
+        let mut command = Command::new("journalctl");
+        command.args(["--no-pager", "--quiet", "-o", "short-iso", "-n", &MAX_SCAN_LINES.to_string()]);
+        if let Some(since) = window.since {
+            command.arg(format!("--since=@{}", since));
+        }
+        if let Some(until) = window.until {
+            command.arg(format!("--until=@{}", until));
+        }
+        if let Some(unit) = unit {
+            command.args(["-u", unit]);
+        }
+
+        let output = command.stdin(Stdio::null()).stderr(Stdio::piped()).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "journalctl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(self.parse_lines(text.lines().filter(|line| !line.starts_with("-- "))))
+    }
+
+    fn read_file(&self, path: &str, window: &Window) -> Result<Vec<LogLine>> {
+        let path = expand_home(path);
+        let mut file = std::fs::File::open(&path)
+            .map_err(|e| anyhow::anyhow!("cannot open {}: {}", path.display(), e))?;
+
+        // Only the end of large files; the first, partial line is dropped
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(MAX_READ_BYTES);
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes);
+        let text = if start > 0 { text.split_once('\n').map_or("", |(_, rest)| rest) } else { &text };
+
+        let all: Vec<&str> = text.lines().collect();
+        let lines = self.parse_lines(all[all.len().saturating_sub(MAX_SCAN_LINES)..].iter().copied());
+        if window.is_open() || lines.iter().all(|line| line.timestamp.is_none()) {
+            return Ok(lines);
+        }
+        Ok(lines
+            .into_iter()
+            .filter(|line| line.timestamp.is_some_and(|t| window.contains(t)))
+            .collect())
+    }
+
+    /// Lines without a timestamp of their own (stack traces, wrapped
+    /// messages) take the one of the line before.
+    fn parse_lines<'a>(&self, raw: impl Iterator<Item = &'a str>) -> Vec<LogLine> {
+        let now = time::now_in(None).map(|t| t.timestamp).unwrap_or(0);
+        let mut previous = None;
+        raw.filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let parsed = self.line_timestamp(line, now);
+                let timestamp = parsed.map(|(t, _)| t).or(previous);
+                previous = timestamp;
+                LogLine {
+                    timestamp,
+                    message_start: parsed.map_or(0, |(_, end)| end),
+                    text: line.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// The timestamp a log line starts with and where it ends: ISO 8601
+    /// (`2026-10-15T22:03:11.512+02:00`, optionally in brackets) or syslog
+    /// (`Oct 15 22:03:11`). Times without an offset are local.
+    fn line_timestamp(&self, line: &str, now: i64) -> Option<(i64, usize)> {
+        let skip = line.len() - line.trim_start_matches('[').len();
+        let s = &line[skip..];
+        let b = s.as_bytes();
+
+        if b.len() >= 19 && b[4] == b'-' && b[7] == b'-' && (b[10] == b'T' || b[10] == b' ') {
+            let year = s.get(0..4)?.parse().ok()?;
+            let month = s.get(5..7)?.parse().ok()?;
+            let day = s.get(8..10)?.parse().ok()?;
+            let seconds = clock_seconds(s.get(11..19)?)?;
+
+            let mut end = 19;
+            if b.get(end) == Some(&b'.') || b.get(end) == Some(&b',') {
+                end += 1;
+                while b.get(end).is_some_and(u8::is_ascii_digit) {
+                    end += 1;
+                }
+            }
+            let (offset, offset_len) = parse_offset(&s[end..]);
+            end += offset_len;
+            let timestamp = match offset {
+                Some(offset) => time::from_local(year, month, day, seconds, "UTC").ok()? - offset as i64,
+                None => time::from_local(year, month, day, seconds, &self.zone).ok()?,
+            };
+            return Some((timestamp, skip + end + usize::from(b.get(end) == Some(&b']'))));
+        }
+
+        // Syslog: no year, so the latest one that isn't in the future
+        let month = MONTHS.iter().position(|m| s.get(0..3) == Some(*m))? as u32 + 1;
+        let day: u32 = s.get(4..6)?.trim().parse().ok()?;
+        let seconds = clock_seconds(s.get(7..15)?)?;
+        let year: i64 = time::at(now, &self.zone).ok()?.date.get(0..4)?.parse().ok()?;
+        let mut timestamp = time::from_local(year, month, day, seconds, &self.zone).ok()?;
+        if timestamp > now + 86_400 {
+            timestamp = time::from_local(year - 1, month, day, seconds, &self.zone).ok()?;
+        }
+        Some((timestamp, skip + 15))
+    }
+
+    /// A window bound: empty, `now`, `today`, `yesterday`, a span ago (`30m`,
+    /// `12h`, `2d`, `1w`, optionally followed by `ago`), `HH:MM` (the last
+    /// time the clock showed it) or `YYYY-MM-DD[ HH:MM[:SS]]`, in local time.
+    fn parse_time(&self, s: &str, now: i64) -> Result<Option<i64>> {
+        let s = s.trim().to_lowercase();
+        let s = s.strip_suffix("ago").map(str::trim).unwrap_or(&s);
+        if s.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || anyhow::anyhow!("can't read the time '{}'; use e.g. '12h', 'yesterday', '22:00' or '2026-10-15 22:00'", s);
+
+        let today = time::at(now, &self.zone)?;
+        let (year, month, day) = parse_date(&today.date).ok_or_else(invalid)?;
+        let midnight = time::from_local(year, month, day, 0, &self.zone)?;
+
+        let timestamp = match s {
+            "now" => now,
+            "today" => midnight,
+            "yesterday" => time::from_local(year, month, day, -86_400, &self.zone)?,
+            _ if s.contains(':') && !s.contains('-') => {
+                let seconds = clock_seconds(s).ok_or_else(invalid)?;
+                let at = time::from_local(year, month, day, seconds, &self.zone)?;
+                if at > now { at - 86_400 } else { at }
+            }
+            _ if s.len() >= 10 && s.as_bytes()[4] == b'-' => {
+                let (year, month, day) = parse_date(&s[..10]).ok_or_else(invalid)?;
+                let clock = s[10..].trim_start_matches(['t', ' ']);
+                let seconds = if clock.is_empty() { 0 } else { clock_seconds(clock).ok_or_else(invalid)? };
+                time::from_local(year, month, day, seconds, &self.zone)?
+            }
+            _ => {
+                let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+                let amount: i64 = s[..split].parse().map_err(|_| invalid())?;
+                let unit = match s[split..].trim() {
+                    "s" | "sec" | "secs" | "second" | "seconds" => 1,
+                    "m" | "min" | "mins" | "minute" | "minutes" => 60,
+                    "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+                    "d" | "day" | "days" => 86_400,
+                    "w" | "week" | "weeks" => 7 * 86_400,
+                    _ => return Err(invalid()),
+                };
+                now - amount * unit
+            }
+        };
+        Ok(Some(timestamp))
+    }
+
+    /// ` since ... until ...` for headers, empty for an open window.
+    fn describe(&self, window: &Window) -> String {
+        let mut out = String::new();
+        if let Some(since) = window.since {
+            out.push_str(&format!(" since {}", self.format(since)));
+        }
+        if let Some(until) = window.until {
+            out.push_str(&format!(" until {}", self.format(until)));
+        }
+        out
+    }
+
+    fn format(&self, timestamp: i64) -> String {
+        match time::at(timestamp, &self.zone) {
+            Ok(local) => format!("{} {}", local.date, local.time),
+            Err(_) => timestamp.to_string(),
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Indexes of the lines matching an FTS5 query, oldest first. An empty
+/// query matches every line.
+fn matching(lines: &[LogLine], query: &str) -> Result<Vec<usize>> {
+    if query.is_empty() {
+        return Ok((0..lines.len()).collect());
+    }
+
+    let conn = rusqlite::Connection::open_in_memory()?;
+    conn.execute_batch("CREATE VIRTUAL TABLE lines USING fts5(text)")?;
+    {
+        let tx = conn.unchecked_transaction()?;
+        let mut insert = tx.prepare("INSERT INTO lines (rowid, text) VALUES (?1, ?2)")?;
+        for (i, line) in lines.iter().enumerate() {
+            insert.execute(rusqlite::params![i as i64, line.text])?;
+        }
+        drop(insert);
+        tx.commit()?;
+    }
+
+    let mut stmt = conn.prepare("SELECT rowid FROM lines WHERE lines MATCH ?1 ORDER BY rowid")?;
+    let rows = stmt.query_map([query], |row| row.get::<_, i64>(0))?;
+    Ok(rows.map(|row| row.map(|i| i as usize)).collect::<rusqlite::Result<_>>()?)
+}
+
+/// A message with its variable parts (numbers, ids, addresses, durations)
+/// replaced by `#`, so repeats of the same error group together.
+fn template(message: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '.' | ':' | '-' | '_' | '/');
+    let mut out = String::with_capacity(message.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        if word.chars().any(|c| c.is_ascii_digit()) {
+            out.push('#');
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+    for c in message.trim().chars() {
+        if is_word(c) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/// `HH:MM` or `HH:MM:SS` as seconds since midnight.
+fn clock_seconds(s: &str) -> Option<i64> {
+    let mut parts = s.split(':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds: i64 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// `YYYY-MM-DD`
+fn parse_date(s: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = s.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok().filter(|m| (1..=12).contains(m))?;
+    let day = parts.next()?.parse().ok().filter(|d| (1..=31).contains(d))?;
+    Some((year, month, day))
+}
+
+/// A `Z`, `+02:00` or `+0200` offset at the start of `s`, in seconds east of
+/// UTC, and its length.
+fn parse_offset(s: &str) -> (Option<i32>, usize) {
+    let b = s.as_bytes();
+    match b.first() {
+        Some(b'Z') => (Some(0), 1),
+        Some(sign @ (b'+' | b'-')) => {
+            let digits: String = s[1..].chars().take(5).filter(|c| *c != ':').take(4).collect();
+            let len = if b.get(3) == Some(&b':') { 6 } else { 5 };
+            if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) || s.len() < len {
+                return (None, 0);
+            }
+            let hours: i32 = digits[..2].parse().unwrap_or(0);
+            let minutes: i32 = digits[2..].parse().unwrap_or(0);
+            let offset = (hours * 3600 + minutes * 60) * if *sign == b'-' { -1 } else { 1 };
+            (Some(offset), len)
+        }
+        _ => (None, 0),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn truncate(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_LINE_CHARS).collect();
+    format!("{}…", cut)
+}
@@ -1,30 +1,84 @@
 pub mod archivist;
+pub mod clipboard;
+pub mod clock;
+pub mod containers;
 pub mod file_smith;
+pub mod git_smith;
+#[cfg(test)]
+mod git_smith_tests;
+pub mod log_scout;
+pub mod notifier;
+pub mod places;
+pub mod reports;
+pub mod screen;
+pub mod shell_smith;
+#[cfg(test)]
+mod shell_smith_tests;
+pub mod watcher;
 pub mod web_search;
+/// Declares a toolbelt's tools and generates its handlers and schemas.
+///
+/// Handlers dispatch to an instance handed to the generated `install` at
+/// startup, so toolbelts can carry config, DB handles and secrets. Until it is
+/// installed, the toolbelt's tools return an error.
+///
+/// A param's type is a scalar name (`"string"`, `"integer"`, ...) or any
+/// `ParamType` expression. A trailing `= value` (one JSON token: `5`, `"."`, `[]`)
+/// gives the param a default and makes it optional.
+///
+/// `risk` is the toolbelt's `ToolRisk`, reported when the assistant describes
+/// what it can do.
+///
+/// A tool may end with `example: { ... }`, the arguments of a typical call as
+/// JSON, shown in the generated tool documentation (`GET /tools`).
 #[macro_export]
 macro_rules! register_toolbelt {
     (
         $toolbelt_type:ty {
             description: $toolbelt_desc:literal,
             location: $location:expr,
+            risk: $risk:expr,
             tools: {
                 $(
                     $name:literal => $method:ident {
                         description: $desc:literal,
-                        params: [$($param_name:literal: $param_type:literal => $param_desc:literal),* $(,)?]
+                        params: [$($param_name:literal: $param_type:expr => $param_desc:literal $(= $default:tt)?),* $(,)?]
+                        $(, example: $example:tt)?
                     }
                 ),* $(,)?
             }
         }
     ) => {
-        use once_cell::sync::Lazy;
+        use once_cell::sync::{Lazy, OnceCell};
 
-        pub static INSTANCE: Lazy<$toolbelt_type> = Lazy::new(<$toolbelt_type>::default);
+        static INSTANCE: OnceCell<$toolbelt_type> = OnceCell::new();
+
+        pub static TOOLBELT: $crate::schemas::ToolbeltInfo = $crate::schemas::ToolbeltInfo {
+            name: stringify!($toolbelt_type),
+            description: $toolbelt_desc,
+            location: $location,
+            risk: $risk,
+        };
+
+        /// Make a configured toolbelt available to its handlers. Call once at startup.
+        pub fn install(toolbelt: $toolbelt_type) -> anyhow::Result<()> {
+            INSTANCE.set(toolbelt).map_err(|_| {
+                anyhow::anyhow!("{} is already installed", stringify!($toolbelt_type))
+            })
+        }
 
         $(
             paste::paste! {
-                pub fn [<$method _handler>](args: &serde_json::Value) -> anyhow::Result<String> {
-                    INSTANCE.$method(args)
+                pub fn [<$method _handler>](
+                    args: &serde_json::Value,
+                    ctx: &$crate::DeviceContext,
+                ) -> anyhow::Result<String> {
+                    INSTANCE
+                        .get()
+                        .ok_or_else(|| anyhow::anyhow!(
+                            "{} is not available on this host", stringify!($toolbelt_type)
+                        ))?
+                        .$method(args, ctx)
                 }
             }
         )*
@@ -43,16 +97,40 @@ macro_rules! register_toolbelt {
                     location: $location,
                     parameters: vec![
                         $(
-                            $crate::schemas::ParameterSchema {
-                                name: $param_name,
-                                type_name: $param_type,
-                                description: $param_desc,
-                                required: true,
+                            {
+                                let default: Option<serde_json::Value> = None
+                                    $(.or(Some(serde_json::json!($default))))?;
+                                $crate::schemas::ParameterSchema {
+                                    name: $param_name,
+                                    param_type: $crate::schemas::ParamType::from($param_type),
+                                    description: $param_desc,
+                                    required: default.is_none(),
+                                    default,
+                                }
                             }
                         ),*
                     ],
                 }
             ),*
         ]);
+
+        pub static TOOL_EXAMPLES: Lazy<Vec<(&'static str, serde_json::Value)>> = Lazy::new(|| {
+            let examples: Vec<(&'static str, Option<serde_json::Value>)> = vec![
+                $((
+                    concat!(stringify!($toolbelt_type), "::", $name),
+                    None $(.or(Some(serde_json::json!($example))))?,
+                )),*
+            ];
+            examples.into_iter().filter_map(|(name, example)| Some((name, example?))).collect()
+        });
+    };
+}
+/// Whether `program` can be found on PATH, for toolbelts that drive host commands.
+pub(crate) fn is_on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
     };
-}
\ No newline at end of file
+    std::env::split_paths(&path).any(|dir| {
+        dir.join(program).is_file() || dir.join(format!("{}.exe", program)).is_file()
+    })
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
+
+/// Desktop notifications on the user's machine, through the platform's
+/// notification service (freedesktop D-Bus, macOS or Windows).
+pub struct Notifier;
+
+impl Notifier {
+    /// The notifier, or None when there is no desktop to notify: on Linux
+    /// and the BSDs, no notification server answers on the session bus.
+    pub fn detect() -> Option<Self> {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if notify_rust::get_server_information().is_err() {
+            return None;
+        }
+        Some(Self)
+    }
+
+    /// Pop up a notification.
+    pub fn send(&self, title: &str, message: &str) -> Result<()> {
+        notify_rust::Notification::new()
+            .appname("Artificer")
+            .summary(title)
+            .body(message)
+            .show()
+            .map_err(|e| anyhow::anyhow!("Failed to show the notification: {}", e))?;
+        Ok(())
+    }
+}
+
+register_toolbelt! {
+    Notifier {
+        description: "Tool for showing desktop notifications to the user",
+        location: ToolLocation::Client,
+        risk: ToolRisk::Low,
+        tools: {
+            "notify" => notify {
+                description: "Shows a desktop notification on the user's device. Use for reminders or to flag something that needs attention outside the chat.",
+                params: [
+                    "title": "string" => "Short notification title",
+                    "message": "string" => "Notification body"
+                ],
+                example: { "title": "Stand-up", "message": "Team call starts in 5 minutes" }
+            },
+        }
+    }
+}
+
+impl Notifier {
+    fn notify(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let title = args["title"].as_str().unwrap_or("").trim();
+        if title.is_empty() {
+            return Ok("Error: title cannot be empty".to_string());
+        }
+        let message = args["message"].as_str().unwrap_or("");
+
+        self.send(title, message)?;
+        Ok("Notification shown".to_string())
+    }
+}
@@ -0,0 +1,265 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
+
+const DEFAULT_PROVIDER_URL: &str = "https://nominatim.openstreetmap.org";
+
+/// The public Nominatim instance allows one request per second.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+const MAX_RADIUS_KM: f64 = 50.0;
+
+/// Place search against a Nominatim-compatible geocoding service.
+pub struct Places {
+    base_url: String,
+    user_agent: String,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl Places {
+    pub fn new(base_url: impl Into<String>, user_agent: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            user_agent: user_agent.into(),
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Uses PLACES_PROVIDER_URL, defaulting to the public OpenStreetMap Nominatim.
+    /// PLACES_USER_AGENT identifies this deployment to the provider, as its usage
+    /// policy requires.
+    pub fn from_env() -> Self {
+        let base_url = std::env::var("PLACES_PROVIDER_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_PROVIDER_URL.to_string());
+        let user_agent = std::env::var("PLACES_USER_AGENT")
+            .ok()
+            .filter(|ua| !ua.is_empty())
+            .unwrap_or_else(|| "Artificer/0.1".to_string());
+        Self::new(base_url, user_agent)
+    }
+}
+
+register_toolbelt! {
+    Places {
+        description: "Find places, businesses and addresses using OpenStreetMap data",
+        location: ToolLocation::Server,
+        risk: ToolRisk::Low,
+        tools: {
+            "search_places" => search_places {
+                description: "Search for places by name or kind (e.g. 'coffee shop', 'pharmacy', 'Eiffel Tower'). Returns names, addresses, coordinates and, when 'near' is given, distances.",
+                params: [
+                    "query": "string" => "What to look for",
+                    "near": "string" => "Address, city or 'lat,lon' to search around. Empty searches everywhere." = "",
+                    "radius_km": ParamType::Number => "Search radius around 'near' in kilometres (max: 50)" = 2,
+                    "max_results": "integer" => "Maximum number of results to return (max: 20)" = 10
+                ],
+                example: { "query": "pharmacy", "near": "Alexanderplatz, Berlin", "radius_km": 1 }
+            },
+        }
+    }
+}
+
+struct Place {
+    name: String,
+    address: String,
+    kind: String,
+    lat: f64,
+    lon: f64,
+    distance_km: Option<f64>,
+}
+
+impl Places {
+    fn search_places(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
+        let query = args["query"].as_str().unwrap_or("").trim();
+        if query.is_empty() {
+            return Ok("Error: query cannot be empty".to_string());
+        }
+
+        let near = args["near"].as_str().unwrap_or("").trim();
+        let radius_km = args["radius_km"].as_f64().unwrap_or(2.0).clamp(0.1, MAX_RADIUS_KM);
+        let max_results = args["max_results"].as_i64().unwrap_or(10).clamp(1, 20) as usize;
+
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                self.search_places_async(query, near, radius_km, max_results).await
+            })
+        })
+    }
+
+    async fn search_places_async(
+        &self,
+        query: &str,
+        near: &str,
+        radius_km: f64,
+        max_results: usize,
+    ) -> Result<String> {
+        let center = if near.is_empty() {
+            None
+        } else {
+            match self.locate(near).await? {
+                Some(center) => Some(center),
+                None => return Ok(format!("Could not find the location '{}'.", near)),
+            }
+        };
+
+        let limit = max_results.to_string();
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("format", "jsonv2".to_string()),
+            ("limit", limit),
+        ];
+        if let Some((lat, lon)) = center {
+            let (min_lat, min_lon, max_lat, max_lon) = bounding_box(lat, lon, radius_km);
+            params.push(("viewbox", format!("{},{},{},{}", min_lon, max_lat, max_lon, min_lat)));
+            params.push(("bounded", "1".to_string()));
+        }
+
+        let results = match self.get("/search", &params).await? {
+            Ok(results) => results,
+            Err(failure) => return Ok(failure),
+        };
+
+        let mut places: Vec<Place> = results
+            .as_array()
+            .map(|a| a.iter().filter_map(parse_place).collect())
+            .unwrap_or_default();
+
+        // The box's corners reach past the radius; trim to the circle and sort by distance
+        if let Some((lat, lon)) = center {
+            for place in &mut places {
+                place.distance_km = Some(haversine_km(lat, lon, place.lat, place.lon));
+            }
+            places.retain(|p| p.distance_km.is_some_and(|d| d <= radius_km));
+            places.sort_by(|a, b| a.distance_km.partial_cmp(&b.distance_km).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
+        let mut output = match center {
+            Some(_) => format!("Places matching '{}' within {} km of {}:\n\n", query, radius_km, near),
+            None => format!("Places matching '{}':\n\n", query),
+        };
+
+        if places.is_empty() {
+            output.push_str("No places found. Try a broader query or a larger radius.\n");
+            return Ok(output);
+        }
+
+        for (i, place) in places.iter().take(max_results).enumerate() {
+            output.push_str(&format!("{}. {}", i + 1, place.name));
+            if !place.kind.is_empty() {
+                output.push_str(&format!(" ({})", place.kind));
+            }
+            output.push('\n');
+            output.push_str(&format!("   Address: {}\n", place.address));
+            output.push_str(&format!("   Coordinates: {:.6}, {:.6}\n", place.lat, place.lon));
+            if let Some(distance) = place.distance_km {
+                output.push_str(&format!("   Distance: {:.2} km\n", distance));
+            }
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    /// Resolve `near` to coordinates: a literal "lat,lon" or the provider's best match.
+    async fn locate(&self, near: &str) -> Result<Option<(f64, f64)>> {
+        if let Some(coords) = parse_coordinates(near) {
+            return Ok(Some(coords));
+        }
+
+        let params = [
+            ("q", near.to_string()),
+            ("format", "jsonv2".to_string()),
+            ("limit", "1".to_string()),
+        ];
+        let results = match self.get("/search", &params).await? {
+            Ok(results) => results,
+            Err(_) => return Ok(None),
+        };
+
+        Ok(results
+            .as_array()
+            .and_then(|a| a.first())
+            .and_then(parse_place)
+            .map(|place| (place.lat, place.lon)))
+    }
+
+    /// GET a provider endpoint. The inner Err is a message for the model.
+    async fn get(&self, path: &str, params: &[(&str, String)]) -> Result<std::result::Result<serde_json::Value, String>> {
+        self.wait_for_rate_limit().await;
+
+        let client = reqwest::Client::builder()
+            .user_agent(self.user_agent.as_str())
+            .timeout(Duration::from_secs(15))
+            .build()?;
+
+        let response = client
+            .get(format!("{}{}", self.base_url, path))
+            .query(params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Ok(Err(format!("Place search failed: {}", response.status())));
+        }
+
+        Ok(Ok(response.json().await?))
+    }
+
+    async fn wait_for_rate_limit(&self) {
+        let wait = {
+            let mut last = self.last_request.lock().unwrap();
+            let now = Instant::now();
+            let next = last.map_or(now, |t| (t + MIN_REQUEST_INTERVAL).max(now));
+            *last = Some(next);
+            next - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+fn parse_place(value: &serde_json::Value) -> Option<Place> {
+    let lat = value["lat"].as_str()?.parse().ok()?;
+    let lon = value["lon"].as_str()?.parse().ok()?;
+    let address = value["display_name"].as_str().unwrap_or("").to_string();
+    let name = value["name"]
+        .as_str()
+        .filter(|n| !n.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| address.split(',').next().unwrap_or("").trim().to_string());
+    let kind = value["type"].as_str().unwrap_or("").replace('_', " ");
+
+    Some(Place { name, address, kind, lat, lon, distance_km: None })
+}
+
+/// "48.8584, 2.2945" → (48.8584, 2.2945)
+fn parse_coordinates(s: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = s.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// (min_lat, min_lon, max_lat, max_lon) enclosing a circle of `radius_km`.
+fn bounding_box(lat: f64, lon: f64, radius_km: f64) -> (f64, f64, f64, f64) {
+    let lat_delta = radius_km / 111.32;
+    let lon_delta = radius_km / (111.32 * lat.to_radians().cos().max(0.01));
+    (
+        (lat - lat_delta).max(-90.0),
+        (lon - lon_delta).max(-180.0),
+        (lat + lat_delta).min(90.0),
+        (lon + lon_delta).min(180.0),
+    )
+}
+
+fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    6371.0 * 2.0 * a.sqrt().asin()
+}
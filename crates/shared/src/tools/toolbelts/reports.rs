@@ -0,0 +1,190 @@
+use std::sync::Arc;
+use anyhow::Result;
+use serde_json::json;
+
+use crate::db::Db;
+use crate::schemas::ParameterSchema;
+use crate::time;
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
+
+/// Assembles research into a Markdown report: YAML front matter, a summary,
+/// the sections in order, and a numbered source list the sections cite as [n].
+pub struct Reports {
+    db: Arc<Db>,
+}
+
+impl Reports {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+}
+
+struct Section {
+    heading: String,
+    body: String,
+}
+
+struct Source {
+    title: String,
+    url: String,
+}
+
+register_toolbelt! {
+    Reports {
+        description: "Tool for assembling research into a Markdown report",
+        location: ToolLocation::Server,
+        risk: ToolRisk::Medium,
+        tools: {
+            "build_report" => build_report {
+                description: "Builds a Markdown report with front matter, summary, sections and a numbered source list. Cite sources in section bodies as [1], [2], ... in the order of `sources`. Returns the Markdown, a suggested filename, and warnings about citations that don't match a source.",
+                params: [
+                    "title": "string" => "Report title",
+                    "summary": "string" => "Short summary of the findings",
+                    "sections": ParamType::array(ParamType::Object(vec![
+                        ParameterSchema { name: "heading", param_type: ParamType::String, description: "Section heading", required: true, default: None },
+                        ParameterSchema { name: "body", param_type: ParamType::String, description: "Section text in Markdown, citing sources as [n]", required: true, default: None },
+                    ])) => "Report sections, in order",
+                    "sources": ParamType::array(ParamType::Object(vec![
+                        ParameterSchema { name: "title", param_type: ParamType::String, description: "Source title", required: true, default: None },
+                        ParameterSchema { name: "url", param_type: ParamType::String, description: "Source URL", required: true, default: None },
+                    ])) => "Sources, numbered from 1 in this order" = [],
+                    "attach": "boolean" => "Also store the report as an attachment, for when it shouldn't be written to a file" = false
+                ],
+                example: {
+                    "title": "Home battery options",
+                    "summary": "Two systems fit the budget.",
+                    "sections": [{ "heading": "Costs", "body": "Prices start at 6000 EUR [1]." }],
+                    "sources": [{ "title": "Battery price survey", "url": "https://example.com/survey" }]
+                }
+            },
+        }
+    }
+}
+
+impl Reports {
+    fn build_report(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let title = args["title"].as_str().unwrap_or("").trim();
+        if title.is_empty() {
+            return Ok("Error: title cannot be empty".to_string());
+        }
+        let summary = args["summary"].as_str().unwrap_or("").trim();
+
+        let sections: Vec<Section> = args["sections"]
+            .as_array()
+            .map(|items| items.iter().filter_map(|item| Some(Section {
+                heading: item["heading"].as_str()?.trim().to_string(),
+                body: item["body"].as_str()?.trim().to_string(),
+            })).collect())
+            .unwrap_or_default();
+        if sections.is_empty() {
+            return Ok("Error: a report needs at least one section with a heading and body".to_string());
+        }
+
+        let sources: Vec<Source> = args["sources"]
+            .as_array()
+            .map(|items| items.iter().filter_map(|item| Some(Source {
+                title: item["title"].as_str()?.trim().to_string(),
+                url: item["url"].as_str()?.trim().to_string(),
+            })).collect())
+            .unwrap_or_default();
+
+        let zone = time::device_zone(&self.db, ctx.device_id as u64);
+        let date = time::now_in(Some(&zone))
+            .map(|now| now.date)
+            .unwrap_or_default();
+
+        let markdown = render(title, summary, &sections, &sources, &date);
+        let warnings = citation_warnings(&sections, sources.len());
+        let filename = format!("{}.md", slug(title));
+
+        let mut output = json!({
+            "filename": filename,
+            "markdown": markdown,
+            "warnings": warnings,
+        });
+        if args["attach"].as_bool().unwrap_or(false) {
+            let id = self.db.store_attachment(ctx.device_id as u64, &filename, "text/markdown", markdown.as_bytes())?;
+            output["attachment_id"] = json!(id);
+        }
+        Ok(output.to_string())
+    }
+}
+
+fn render(title: &str, summary: &str, sections: &[Section], sources: &[Source], date: &str) -> String {
+    let mut md = String::new();
+
+    md.push_str("---\n");
+    md.push_str(&format!("title: {}\n", yaml_string(title)));
+    if !date.is_empty() {
+        md.push_str(&format!("date: {}\n", date));
+    }
+    md.push_str(&format!("sources: {}\n", sources.len()));
+    md.push_str("generated_by: Artificer\n");
+    md.push_str("---\n\n");
+
+    md.push_str(&format!("# {}\n\n", title));
+    if !summary.is_empty() {
+        md.push_str("## Summary\n\n");
+        md.push_str(summary);
+        md.push_str("\n\n");
+    }
+    for section in sections {
+        md.push_str(&format!("## {}\n\n{}\n\n", section.heading, section.body));
+    }
+    if !sources.is_empty() {
+        md.push_str("## Sources\n\n");
+        for (i, source) in sources.iter().enumerate() {
+            md.push_str(&format!("{}. [{}]({})\n", i + 1, source.title, source.url));
+        }
+    }
+
+    md.trim_end().to_string() + "\n"
+}
+
+/// Citations pointing past the source list, and sources never cited.
+fn citation_warnings(sections: &[Section], source_count: usize) -> Vec<String> {
+    let mut cited = vec![false; source_count];
+    let mut warnings = Vec::new();
+
+    for section in sections {
+        for n in citations(&section.body) {
+            match cited.get_mut(n.wrapping_sub(1)) {
+                Some(seen) => *seen = true,
+                None => warnings.push(format!("Section '{}' cites [{}], but there is no source {}", section.heading, n, n)),
+            }
+        }
+    }
+    for (i, seen) in cited.iter().enumerate() {
+        if !seen {
+            warnings.push(format!("Source {} is never cited", i + 1));
+        }
+    }
+    warnings
+}
+
+/// Every `[n]` in the text. Markdown links (`[text](url)`) don't match since
+/// their brackets hold more than digits.
+fn citations(text: &str) -> Vec<usize> {
+    text.split('[')
+        .skip(1)
+        .filter_map(|rest| rest.split_once(']'))
+        .filter_map(|(inside, _)| inside.parse().ok())
+        .collect()
+}
+
+fn slug(title: &str) -> String {
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(60).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() { "report".to_string() } else { slug.to_string() }
+}
+
+/// A double-quoted YAML scalar.
+fn yaml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
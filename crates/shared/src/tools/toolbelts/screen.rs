@@ -0,0 +1,194 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::json;
+use std::path::Path;
+use std::process::Command;
+
+use super::is_on_path;
+use crate::schemas::ParameterSchema;
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
+
+/// Screen capture on the user's machine. Captures are kept as temp files and
+/// uploaded to the server as attachments.
+pub struct Screen {
+    server_url: String,
+    backend: Backend,
+}
+
+/// The platform screenshot command.
+#[derive(Clone, Copy)]
+enum Backend {
+    /// macOS `screencapture`
+    ScreenCapture,
+    /// Wayland `grim`
+    Grim,
+    /// X11 `maim`
+    Maim,
+    /// X11 ImageMagick `import`
+    Import,
+}
+
+struct Region {
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Screen {
+    /// Picks the screenshot command for this platform and display server, or
+    /// None when there is no screen to capture (e.g. a headless session).
+    pub fn detect(server_url: impl Into<String>) -> Option<Self> {
+        let candidates: &[(Backend, &str)] = if cfg!(target_os = "macos") {
+            &[(Backend::ScreenCapture, "screencapture")]
+        } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            &[(Backend::Grim, "grim")]
+        } else if std::env::var_os("DISPLAY").is_some() {
+            &[(Backend::Maim, "maim"), (Backend::Import, "import")]
+        } else {
+            &[]
+        };
+
+        candidates
+            .iter()
+            .find(|(_, program)| is_on_path(program))
+            .map(|(backend, _)| Self {
+                server_url: server_url.into().trim_end_matches('/').to_string(),
+                backend: *backend,
+            })
+    }
+}
+
+register_toolbelt! {
+    Screen {
+        description: "Tool for capturing the user's screen",
+        location: ToolLocation::Client,
+        risk: ToolRisk::Medium,
+        tools: {
+            "take_screenshot" => take_screenshot {
+                description: "Captures the user's screen, or a region of it, as a PNG and uploads it as an attachment. Returns the attachment id and the local file path.",
+                params: [
+                    "region": ParamType::Object(vec![
+                        ParameterSchema { name: "x", param_type: ParamType::Integer, description: "Left edge in pixels", required: true, default: None },
+                        ParameterSchema { name: "y", param_type: ParamType::Integer, description: "Top edge in pixels", required: true, default: None },
+                        ParameterSchema { name: "width", param_type: ParamType::Integer, description: "Width in pixels", required: true, default: None },
+                        ParameterSchema { name: "height", param_type: ParamType::Integer, description: "Height in pixels", required: true, default: None },
+                    ]) => "Area to capture. Omit for the full screen." = null
+                ],
+                example: { "region": { "x": 0, "y": 0, "width": 1280, "height": 720 } }
+            },
+        }
+    }
+}
+
+impl Screen {
+    fn take_screenshot(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let region = match parse_region(&args["region"]) {
+            Ok(region) => region,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+
+        let path = std::env::temp_dir().join(format!("artificer-screenshot-{}.png", uuid::Uuid::new_v4()));
+        self.capture(&path, region.as_ref())?;
+
+        let data = std::fs::read(&path)?;
+        let filename = path.file_name().unwrap().to_string_lossy().to_string();
+
+        let attachment_id = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                self.upload(ctx, &filename, &data).await
+            })
+        })?;
+
+        Ok(json!({
+            "attachment_id": attachment_id,
+            "path": path,
+            "size": data.len(),
+        }).to_string())
+    }
+
+    fn capture(&self, path: &Path, region: Option<&Region>) -> Result<()> {
+        let mut command = match self.backend {
+            Backend::ScreenCapture => {
+                let mut c = Command::new("screencapture");
+                c.arg("-x");
+                if let Some(r) = region {
+                    c.arg(format!("-R{},{},{},{}", r.x, r.y, r.width, r.height));
+                }
+                c
+            }
+            Backend::Grim => {
+                let mut c = Command::new("grim");
+                if let Some(r) = region {
+                    c.args(["-g", &format!("{},{} {}x{}", r.x, r.y, r.width, r.height)]);
+                }
+                c
+            }
+            Backend::Maim => {
+                let mut c = Command::new("maim");
+                if let Some(r) = region {
+                    c.args(["-g", &format!("{}x{}+{}+{}", r.width, r.height, r.x, r.y)]);
+                }
+                c
+            }
+            Backend::Import => {
+                let mut c = Command::new("import");
+                c.args(["-window", "root"]);
+                if let Some(r) = region {
+                    c.args(["-crop", &format!("{}x{}+{}+{}", r.width, r.height, r.x, r.y)]);
+                }
+                c
+            }
+        };
+
+        let output = command.arg(path).output()?;
+        if !output.status.success() || !path.exists() {
+            return Err(anyhow::anyhow!(
+                "Screenshot failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    async fn upload(&self, ctx: &DeviceContext, filename: &str, data: &[u8]) -> Result<u64> {
+        let response = reqwest::Client::new()
+            .post(format!("{}/attachments/upload", self.server_url))
+            .json(&json!({
+                "device_id": ctx.device_id,
+                "device_key": ctx.device_key,
+                "filename": filename,
+                "mime_type": "image/png",
+                "data": BASE64.encode(data),
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Attachment upload failed: {}", response.status()));
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        body["attachment_id"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Upload response had no attachment_id"))
+    }
+}
+
+fn parse_region(value: &serde_json::Value) -> Result<Option<Region>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let field = |name: &str| value[name].as_i64().ok_or_else(|| anyhow::anyhow!("region.{} must be an integer", name));
+    let region = Region {
+        x: field("x")?,
+        y: field("y")?,
+        width: field("width")?,
+        height: field("height")?,
+    };
+    if region.width <= 0 || region.height <= 0 {
+        return Err(anyhow::anyhow!("region width and height must be positive"));
+    }
+    Ok(Some(region))
+}
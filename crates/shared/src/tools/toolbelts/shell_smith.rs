@@ -0,0 +1,475 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::is_on_path;
+use crate::{register_toolbelt, DeviceContext, ParamType, ToolLocation, ToolRisk};
+
+/// Timeout for a command when neither the call nor the config sets one.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Longest a command may be allowed to run, whatever is asked.
+pub const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Most characters of stdout and of stderr returned; the end is kept.
+const MAX_STREAM_CHARS: usize = 10_000;
+
+/// How often a running command is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long output is still read after a command was killed, for
+/// processes that left its group but kept its pipes open.
+const PIPE_GRACE: Duration = Duration::from_millis(500);
+
+/// Programs that change or destroy things beyond undoing, with the arguments
+/// that make them so. An empty list means any use of the program. Commands
+/// matching one only run with `confirm`.
+const DESTRUCTIVE: &[(&str, &[&str])] = &[
+    ("rm", &[]),
+    ("rmdir", &[]),
+    ("shred", &[]),
+    ("dd", &[]),
+    ("mkfs", &[]),
+    ("fdisk", &[]),
+    ("parted", &[]),
+    ("wipefs", &[]),
+    ("truncate", &[]),
+    ("shutdown", &[]),
+    ("reboot", &[]),
+    ("poweroff", &[]),
+    ("halt", &[]),
+    ("kill", &[]),
+    ("killall", &[]),
+    ("pkill", &[]),
+    ("chmod", &["-R", "--recursive"]),
+    ("chown", &["-R", "--recursive"]),
+    ("git", &["--force", "-f", "--hard", "clean", "-D"]),
+    ("docker", &["rm", "rmi", "prune", "kill"]),
+    ("podman", &["rm", "rmi", "prune", "kill"]),
+    ("systemctl", &["stop", "disable", "mask", "kill", "poweroff", "reboot"]),
+];
+
+/// The user's limits on ShellSmith, from the `shell` section of envoy's
+/// config.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellConfig {
+    /// Programs that may run, by name. Empty allows any program not denied.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Programs that never run, even when allowed.
+    #[serde(default = "default_deny")]
+    pub deny: Vec<String>,
+    /// Programs that need `confirm`, besides the built-in destructive ones.
+    #[serde(default)]
+    pub confirm: Vec<String>,
+    /// Timeout for commands that don't set their own, in seconds.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_deny() -> Vec<String> {
+    ["sudo", "su", "doas", "pkexec"].iter().map(|p| p.to_string()).collect()
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: default_deny(),
+            confirm: Vec::new(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+}
+
+/// Runs commands and scripts on the user's machine, within the limits in
+/// `ShellConfig`. Commands run without a shell, so arguments are passed
+/// as they are; scripts are handed to an interpreter.
+pub struct ShellSmith {
+    /// Directory commands run in unless they name another.
+    workspace: PathBuf,
+    config: ShellConfig,
+}
+
+impl ShellSmith {
+    pub fn new(workspace: PathBuf, config: ShellConfig) -> Self {
+        Self { workspace, config }
+    }
+}
+
+register_toolbelt! {
+    ShellSmith {
+        description: "Tool for running shell commands and scripts on the user's machine, limited to the programs the user allows",
+        location: ToolLocation::Client,
+        risk: ToolRisk::High,
+        tools: {
+            "run_command" => run_command {
+                description: "Runs one program with arguments, without a shell (no pipes, globs or variables), and returns its exit code, stdout and stderr. Commands that delete, overwrite, kill or shut down something are refused unless confirm is true; only set it after the user agreed to that exact command.",
+                params: [
+                    "command": "string" => "Program to run, e.g. 'df' or 'git'",
+                    "args": ParamType::array(ParamType::String) => "Arguments, one per item" = [],
+                    "cwd": "string" => "Directory to run in, relative to the workspace. Empty for the workspace" = "",
+                    "timeout_secs": "integer" => "Seconds before the command is killed (max: 600). 0 for the configured default" = 0,
+                    "confirm": "boolean" => "The user agreed to run this destructive command" = false
+                ],
+                example: { "command": "df", "args": ["-h", "/"] }
+            },
+            "run_script" => run_script {
+                description: "Runs a short script through an interpreter (sh, bash, python3, ...) for work that needs pipes, loops or variables, and returns its exit code, stdout and stderr. Prefer run_command when one program will do. Scripts with destructive commands need confirm, as for run_command.",
+                params: [
+                    "script": "string" => "The script's source",
+                    "interpreter": "string" => "Program that runs the script, e.g. 'sh', 'bash' or 'python3'" = "sh",
+                    "cwd": "string" => "Directory to run in, relative to the workspace. Empty for the workspace" = "",
+                    "timeout_secs": "integer" => "Seconds before the script is killed (max: 600). 0 for the configured default" = 0,
+                    "confirm": "boolean" => "The user agreed to run this destructive script" = false
+                ],
+                example: { "script": "du -sh ~/Downloads/* | sort -h | tail -5", "interpreter": "sh" }
+            },
+            "which" => which {
+                description: "Finds where a program is installed and whether ShellSmith may run it",
+                params: [
+                    "program": "string" => "Program name, e.g. 'ffmpeg'"
+                ],
+                example: { "program": "ffmpeg" }
+            },
+        }
+    }
+}
+
+impl ShellSmith {
+    fn run_command(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(program) = args["command"].as_str().map(str::trim).filter(|p| !p.is_empty()) else {
+            return Ok("Error: command cannot be empty".to_string());
+        };
+        let arguments: Vec<String> = args["args"].as_array()
+            .map(|items| items.iter().filter_map(|a| a.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        if let Err(reason) = self.check_program(program) {
+            return Ok(format!("Error: {}", reason));
+        }
+        let words: Vec<&str> = std::iter::once(program).chain(arguments.iter().map(String::as_str)).collect();
+        if !args["confirm"].as_bool().unwrap_or(false)
+            && let Some(reason) = self.needs_confirmation(&words)
+        {
+            return Ok(format!(
+                "Error: not run: {}. Ask the user, and call again with confirm: true if they agree.",
+                reason
+            ));
+        }
+
+        let cwd = match self.resolve_cwd(args) {
+            Ok(cwd) => cwd,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+        let mut command = Command::new(program);
+        command.args(&arguments).current_dir(cwd);
+        Ok(self.execute(command, None, self.timeout(args)))
+    }
+
+    fn run_script(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(script) = args["script"].as_str().filter(|s| !s.trim().is_empty()) else {
+            return Ok("Error: script cannot be empty".to_string());
+        };
+        let interpreter = args["interpreter"].as_str().map(str::trim).filter(|i| !i.is_empty()).unwrap_or("sh");
+
+        if let Err(reason) = self.check_program(interpreter) {
+            return Ok(format!("Error: {}", reason));
+        }
+        // The script's own commands are held to the same lists, as far as
+        // they can be told apart; an allowed interpreter can still run more
+        for words in simple_commands(script) {
+            if let Some(program) = words.first()
+                && is_on_path(program_name(program))
+                && let Err(reason) = self.check_program(program)
+            {
+                return Ok(format!("Error: the script runs {}", reason));
+            }
+            if !args["confirm"].as_bool().unwrap_or(false)
+                && let Some(reason) = self.needs_confirmation(&words)
+            {
+                return Ok(format!(
+                    "Error: not run: the script {}. Ask the user, and call again with confirm: true if they agree.",
+                    reason
+                ));
+            }
+        }
+
+        let cwd = match self.resolve_cwd(args) {
+            Ok(cwd) => cwd,
+            Err(e) => return Ok(format!("Error: {}", e)),
+        };
+        let mut command = Command::new(interpreter);
+        command.current_dir(cwd);
+        Ok(self.execute(command, Some(script), self.timeout(args)))
+    }
+
+    fn which(&self, args: &Value, _ctx: &DeviceContext) -> Result<String> {
+        let Some(program) = args["program"].as_str().map(str::trim).filter(|p| !p.is_empty()) else {
+            return Ok("Error: program cannot be empty".to_string());
+        };
+        let Some(path) = find_on_path(program) else {
+            return Ok(format!("{} is not installed (not found on PATH)", program));
+        };
+        let allowed = match self.check_program(program) {
+            Ok(()) => "ShellSmith may run it".to_string(),
+            Err(reason) => format!("ShellSmith may not run it: {}", reason),
+        };
+        Ok(format!("{}\n{}", path.display(), allowed))
+    }
+
+    /// Whether the config lets `program` run at all.
+    pub(crate) fn check_program(&self, program: &str) -> std::result::Result<(), String> {
+        let name = program_name(program);
+        if self.config.deny.iter().any(|denied| denied == name) {
+            return Err(format!("{} is denied in the shell config", name));
+        }
+        if !self.config.allow.is_empty() && !self.config.allow.iter().any(|allowed| allowed == name) {
+            return Err(format!("{} is not in the shell config's allow list", name));
+        }
+        Ok(())
+    }
+
+    /// Why a command needs the user's confirmation, if it does.
+    pub(crate) fn needs_confirmation(&self, words: &[&str]) -> Option<String> {
+        let (program, arguments) = words.split_first()?;
+        let name = program_name(program);
+        if self.config.confirm.iter().any(|p| p == name) {
+            return Some(format!("{} needs confirmation in the shell config", name));
+        }
+        let (_, triggers) = DESTRUCTIVE.iter().find(|(destructive, _)| {
+            name == *destructive || name.starts_with(&format!("{}.", destructive))
+        })?;
+        if triggers.is_empty() {
+            return Some(format!("{} can delete or stop things for good", name));
+        }
+        arguments.iter()
+            .find(|arg| triggers.contains(arg))
+            .map(|arg| format!("{} {} can delete or stop things for good", name, arg))
+    }
+
+    /// The directory to run in, which must be inside the workspace once
+    /// `..` and symlinks are resolved.
+    pub(crate) fn resolve_cwd(&self, args: &Value) -> std::result::Result<PathBuf, String> {
+        resolve_in_workspace(&self.workspace, args["cwd"].as_str().unwrap_or(""))
+    }
+
+    fn timeout(&self, args: &Value) -> Duration {
+        let secs = match args["timeout_secs"].as_u64().unwrap_or(0) {
+            0 => self.config.timeout_secs,
+            secs => secs,
+        };
+        Duration::from_secs(secs.clamp(1, MAX_TIMEOUT_SECS))
+    }
+
+    /// Run `command`, feeding it `stdin` if given, and describe how it went.
+    pub(crate) fn execute(&self, command: Command, stdin: Option<&str>, timeout: Duration) -> String {
+        let program = command.get_program().to_os_string();
+        let run = match run_with_timeout(command, stdin, timeout) {
+            Ok(run) => run,
+            Err(e) => return format!("Error: could not start {:?}: {}", program, e),
+        };
+
+        let mut out = match (run.code, run.timed_out) {
+            (_, true) => format!("Killed after {}s (timeout)", timeout.as_secs()),
+            (Some(code), false) => format!("Exit code {} after {:.1}s", code, run.elapsed.as_secs_f32()),
+            (None, false) => "Ended by a signal".to_string(),
+        };
+        for (name, text) in [("stdout", run.stdout), ("stderr", run.stderr)] {
+            if !text.trim().is_empty() {
+                out.push_str(&format!("\n--- {} ---\n{}", name, tail(&text, MAX_STREAM_CHARS)));
+            }
+        }
+        out
+    }
+}
+
+/// A directory under `workspace`, given relative to it; empty is the
+/// workspace itself. Symlinks are resolved first, so neither `..` nor a
+/// link can lead outside.
+pub(crate) fn resolve_in_workspace(workspace: &Path, relative: &str) -> std::result::Result<PathBuf, String> {
+    let workspace = workspace.canonicalize()
+        .map_err(|e| format!("workspace {} is unavailable: {}", workspace.display(), e))?;
+    let relative = relative.trim();
+    if relative.is_empty() {
+        return Ok(workspace);
+    }
+    let dir = workspace.join(relative).canonicalize()
+        .map_err(|_| format!("{} is not a directory", relative))?;
+    if !dir.starts_with(&workspace) {
+        return Err(format!("{} is outside the workspace", relative));
+    }
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", relative));
+    }
+    Ok(dir)
+}
+
+/// How a command run by `run_with_timeout` ended, and what it printed.
+pub(crate) struct Finished {
+    /// None if a signal, or the timeout, ended it.
+    pub code: Option<i32>,
+    pub timed_out: bool,
+    pub elapsed: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Run `command` with `stdin` as its input, killing it and everything it
+/// started once `timeout` passes.
+pub(crate) fn run_with_timeout(mut command: Command, stdin: Option<&str>, timeout: Duration) -> std::io::Result<Finished> {
+    command
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so a timeout also kills what it started
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command.spawn()?;
+
+    // Read both pipes while waiting, so a chatty command can't fill one and stall
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        let input = input.to_string();
+        std::thread::spawn(move || {
+            use std::io::Write;
+            let _ = pipe.write_all(input.as_bytes());
+        });
+    }
+
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let (code, timed_out) = wait_with_timeout(&mut child, deadline);
+    let elapsed = started.elapsed();
+    // Whatever still holds the pipes open doesn't get to outlast the timeout
+    let stop_reading = deadline.max(Instant::now() + PIPE_GRACE);
+    Ok(Finished {
+        code,
+        timed_out,
+        elapsed,
+        stdout: stdout.collect(stop_reading),
+        stderr: stderr.collect(stop_reading),
+    })
+}
+
+/// Wait for `child` to exit, killing it and its process group once
+/// `deadline` passes. Returns its exit code (None if a signal ended it) and
+/// whether it timed out.
+fn wait_with_timeout(child: &mut Child, deadline: Instant) -> (Option<i32>, bool) {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return (status.code(), false),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            _ => {
+                kill_group(child);
+                let _ = child.wait();
+                return (None, true);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    // The child leads its group, so the group id is its pid
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// A pipe being read on its own thread. What was read so far can be taken
+/// at any time, so a pipe that never closes doesn't hold up the result.
+struct PipeReader {
+    bytes: Arc<Mutex<Vec<u8>>>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl PipeReader {
+    /// The pipe's text once it closes, or what arrived by `deadline`.
+    fn collect(self, deadline: Instant) -> String {
+        while !self.thread.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        let bytes = self.bytes.lock().map(|b| b.clone()).unwrap_or_default();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> PipeReader {
+    let bytes = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&bytes);
+    let thread = std::thread::spawn(move || {
+        let Some(mut pipe) = pipe else { return };
+        let mut chunk = [0u8; 8192];
+        loop {
+            let n = match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            match sink.lock() {
+                Ok(mut bytes) => bytes.extend_from_slice(&chunk[..n]),
+                Err(_) => break,
+            }
+        }
+    });
+    PipeReader { bytes, thread }
+}
+
+/// The last `max` characters of `text`, marked when cut.
+fn tail(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(count - max).collect();
+    format!("[... {} earlier characters cut]\n{}", count - max, kept)
+}
+
+/// A program's bare name, so '/usr/bin/rm' and 'rm' are held to the same rules.
+fn program_name(program: &str) -> &str {
+    Path::new(program).file_name().and_then(|name| name.to_str()).unwrap_or(program)
+}
+
+/// The script's simple commands as words, split at newlines, `;`, `&&`,
+/// `||` and `|`. Good enough to spot the programs a short script runs, not
+/// a shell parser.
+pub(crate) fn simple_commands(script: &str) -> Vec<Vec<&str>> {
+    script
+        .split(['\n', ';', '|', '&'])
+        .map(|part| {
+            part.split_whitespace()
+                .skip_while(|word| word.contains('=') && !word.starts_with('-'))
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty() && !words[0].starts_with('#'))
+        .collect()
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .flat_map(|dir| [dir.join(program), dir.join(format!("{}.exe", program))])
+        .find(|candidate| candidate.is_file())
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+    use crate::tools::toolbelts::shell_smith::{simple_commands, ShellConfig, ShellSmith};
+
+    fn smith(config: ShellConfig) -> ShellSmith {
+        ShellSmith::new(std::env::temp_dir(), config)
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let config = ShellConfig {
+            allow: vec!["ls".to_string(), "sudo".to_string()],
+            ..ShellConfig::default()
+        };
+        let smith = smith(config);
+        assert!(smith.check_program("ls").is_ok());
+        assert!(smith.check_program("/bin/ls").is_ok());
+        assert!(smith.check_program("sudo").is_err());
+        assert!(smith.check_program("df").is_err());
+        assert!(ShellSmith::new(std::env::temp_dir(), ShellConfig::default()).check_program("df").is_ok());
+    }
+
+    #[test]
+    fn destructive_commands_need_confirmation() {
+        let smith = smith(ShellConfig { confirm: vec!["curl".to_string()], ..ShellConfig::default() });
+        assert!(smith.needs_confirmation(&["rm", "notes.txt"]).is_some());
+        assert!(smith.needs_confirmation(&["/usr/bin/rm", "-r", "dir"]).is_some());
+        assert!(smith.needs_confirmation(&["mkfs.ext4", "/dev/sdb1"]).is_some());
+        assert!(smith.needs_confirmation(&["git", "reset", "--hard"]).is_some());
+        assert!(smith.needs_confirmation(&["curl", "example.com"]).is_some());
+
+        assert!(smith.needs_confirmation(&["git", "status"]).is_none());
+        assert!(smith.needs_confirmation(&["chmod", "644", "notes.txt"]).is_none());
+        assert!(smith.needs_confirmation(&["ls"]).is_none());
+        assert!(smith.needs_confirmation(&[]).is_none());
+    }
+
+    #[test]
+    fn scripts_split_into_simple_commands() {
+        let script = "# tidy up\nFOO=1 du -sh * | sort -h; rm -rf build && echo done\n";
+        assert_eq!(
+            simple_commands(script),
+            vec![vec!["du", "-sh", "*"], vec!["sort", "-h"], vec!["rm", "-rf", "build"], vec!["echo", "done"]]
+        );
+        assert!(simple_commands("  \n;\n").is_empty());
+    }
+
+    #[test]
+    fn cwd_stays_inside_the_workspace() {
+        let workspace = std::env::temp_dir().join(format!("artificer-shell-{}", std::process::id()));
+        std::fs::create_dir_all(workspace.join("sub")).unwrap();
+        let smith = ShellSmith::new(workspace.clone(), ShellConfig::default());
+        let root = workspace.canonicalize().unwrap();
+
+        assert_eq!(smith.resolve_cwd(&json!({ "cwd": "" })).unwrap(), root);
+        assert_eq!(smith.resolve_cwd(&json!({ "cwd": "sub" })).unwrap(), root.join("sub"));
+        assert_eq!(smith.resolve_cwd(&json!({ "cwd": "sub/.." })).unwrap(), root);
+        assert!(smith.resolve_cwd(&json!({ "cwd": ".." })).is_err());
+        assert!(smith.resolve_cwd(&json!({ "cwd": "/" })).is_err());
+        assert!(smith.resolve_cwd(&json!({ "cwd": "missing" })).is_err());
+
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_kills_what_the_command_started() {
+        let smith = smith(ShellConfig::default());
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 8; echo done"]);
+
+        let started = Instant::now();
+        let out = smith.execute(command, None, Duration::from_secs(1));
+        assert!(out.starts_with("Killed after 1s"), "{}", out);
+        assert!(!out.contains("done"));
+        assert!(started.elapsed() < Duration::from_secs(4), "took {:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use anyhow::Result;
+use serde_json::json;
+
+use crate::db::{Db, MAX_WATCH_TASK_CHARS};
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
+
+/// Configures the paths a device's envoy watches. The watching itself runs on
+/// the device; this only stores what to watch and what to do.
+pub struct Watcher {
+    db: Arc<Db>,
+}
+
+impl Watcher {
+    pub fn new(db: Arc<Db>) -> Self {
+        Self { db }
+    }
+}
+
+register_toolbelt! {
+    Watcher {
+        description: "Tool for watching folders on the user's machine and acting on new or changed files",
+        location: ToolLocation::Server,
+        risk: ToolRisk::Medium,
+        tools: {
+            "watch_path" => watch_path {
+                description: "Watches a folder or file on the user's current machine. When files under it are added or changed, task is queued as a background request along with the changed files' paths, and the answer arrives in the user's inbox. Example: path '~/inbox', task 'Summarize each new file'. Changes are only noticed while `envoy watch` runs on the machine. Have the task write its results outside the watched path, or set a pattern they don't match, so they don't trigger it again.",
+                params: [
+                    "path": "string" => "Folder or file to watch; ~/ is the user's home directory",
+                    "task": "string" => "What to do with the changed files, written as a request to the assistant",
+                    "pattern": "string" => "Only react to file names matching this glob, e.g. '*.pdf'; empty for every file" = ""
+                ],
+                example: { "path": "~/inbox", "task": "Summarize each new file", "pattern": "*.pdf" }
+            },
+            "list_watches" => list_watches {
+                description: "Lists the paths watched on the user's current machine, with their ids, tasks, patterns and when each last triggered",
+                params: []
+            },
+            "remove_watch" => remove_watch {
+                description: "Stops watching a path",
+                params: ["watch_id": "integer" => "The watch to remove, from list_watches"],
+                example: { "watch_id": 3 }
+            },
+        }
+    }
+}
+
+impl Watcher {
+    fn watch_path(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let path = args["path"].as_str().unwrap_or("").trim();
+        let task = args["task"].as_str().unwrap_or("").trim();
+        let pattern = args["pattern"].as_str().map(str::trim).filter(|p| !p.is_empty());
+
+        if path.is_empty() {
+            return Ok("Error: path cannot be empty".to_string());
+        }
+        if task.is_empty() {
+            return Ok("Error: task cannot be empty".to_string());
+        }
+        if task.chars().count() > MAX_WATCH_TASK_CHARS {
+            return Ok(format!("Error: task is longer than {} characters", MAX_WATCH_TASK_CHARS));
+        }
+
+        match self.db.add_watch(ctx.device_id as u64, path, task, pattern) {
+            Ok(watch_id) => Ok(json!({
+                "watch_id": watch_id,
+                "path": path,
+                "pattern": pattern,
+                "note": "`envoy watch` on this machine starts following the path within a minute",
+            }).to_string()),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+    fn list_watches(&self, _args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let watches = self.db.list_watches(ctx.device_id as u64)?;
+        Ok(json!({
+            "watches": watches,
+            "count": watches.len()
+        }).to_string())
+    }
+    fn remove_watch(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
+        let Some(watch_id) = args["watch_id"].as_u64() else {
+            return Ok("Error: watch_id is required".to_string());
+        };
+        match self.db.delete_watch(ctx.device_id as u64, watch_id) {
+            Ok(true) => Ok(format!("Stopped watch {}", watch_id)),
+            Ok(false) => Ok(format!("Error: Watch {} not found", watch_id)),
+            Err(e) => Ok(format!("Error: {}", e)),
+        }
+    }
+}
@@ -1,57 +1,173 @@
+use std::sync::Arc;
 use anyhow::Result;
 use scraper::{Html, Selector};
-use crate::{register_toolbelt, ToolLocation};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use crate::db::{self, Db, ProviderHealth, Quota, QuotaCheck};
+use crate::{register_toolbelt, DeviceContext, ToolLocation, ToolRisk};
 
 const BRAVE_API_BASE: &str = "https://api.search.brave.com/res/v1";
 
+/// Provider name Brave calls are counted under.
+pub const BRAVE_PROVIDER: &str = "brave";
+
+/// What search tools answer once Brave's own monthly quota is used up. The
+/// fixed prefix lets an agent tell this apart from a search with no results.
+pub const QUOTA_EXHAUSTED: &str = "Error: search unavailable: quota exhausted (Brave). Do not retry web searches; work with the results you already have, and fetch_page still works for known URLs.";
+
+const KEY_REJECTED: &str = "Brave rejected the API key";
+
+/// Characters of page text fetch_page returns per call unless asked for fewer or more.
+const PAGE_CHARS: usize = 20_000;
+
+/// Most characters of page text one fetch_page call returns.
+const MAX_PAGE_CHARS: usize = 40_000;
+
 pub struct WebSearch {
     api_key: String,
+    db: Arc<Db>,
+    quota: Quota,
 }
 
-impl Default for WebSearch {
-    fn default() -> Self {
-        Self {
-            api_key: std::env::var("BRAVE_API_KEY")
-                .expect("BRAVE_API_KEY environment variable not set"),
+impl WebSearch {
+    pub fn new(api_key: impl Into<String>, db: Arc<Db>, quota: Quota) -> Self {
+        Self { api_key: api_key.into(), db, quota }
+    }
+
+    /// Built from BRAVE_API_KEY, if it is set, with `quota` from `quota_from_env`.
+    pub fn from_env(db: Arc<Db>, quota: Quota) -> Option<Self> {
+        std::env::var("BRAVE_API_KEY")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| Self::new(key, db, quota))
+    }
+
+    /// Brave limits from BRAVE_DAILY_LIMIT (per device) and BRAVE_MONTHLY_LIMIT
+    /// (all devices). Unset or unparsable means unlimited.
+    pub fn quota_from_env() -> Quota {
+        let limit = |var: &str| std::env::var(var).ok().and_then(|v| v.trim().parse().ok());
+        Quota {
+            provider: BRAVE_PROVIDER,
+            daily_per_device: limit("BRAVE_DAILY_LIMIT"),
+            monthly: limit("BRAVE_MONTHLY_LIMIT"),
+        }
+    }
+
+    /// Make one minimal search to confirm the API key is accepted and learn
+    /// how much of Brave's monthly quota is left, recording what Brave said
+    /// for /health/providers. Not counted against our own quota.
+    pub async fn check_health(&self) -> ProviderHealth {
+        let response = async {
+            Ok::<_, anyhow::Error>(self.brave_client()?
+                .get(format!("{}/web/search", BRAVE_API_BASE))
+                .query(&[("q", "artificer"), ("count", "1")])
+                .send()
+                .await?)
+        }.await;
+        match response {
+            Ok(response) => self.record_health(response.status(), response.headers()),
+            Err(e) => {
+                // Brave being unreachable says nothing new about the quota
+                let last = self.db.get_provider_health(BRAVE_PROVIDER).ok().flatten();
+                let health = ProviderHealth {
+                    provider: BRAVE_PROVIDER.to_string(),
+                    ok: false,
+                    message: Some(format!("Brave unreachable: {}", e)),
+                    remaining: last.as_ref().and_then(|h| h.remaining),
+                    limit: last.as_ref().and_then(|h| h.limit),
+                    resets_at: last.as_ref().and_then(|h| h.resets_at),
+                    checked_at: db::now(),
+                };
+                self.store_health(&health);
+                health
+            }
+        }
+    }
+
+    /// Record the key and quota state a Brave response reports.
+    fn record_health(&self, status: StatusCode, headers: &HeaderMap) -> ProviderHealth {
+        let health = brave_health(status, headers);
+        self.store_health(&health);
+        health
+    }
+
+    fn store_health(&self, health: &ProviderHealth) {
+        if let Err(e) = self.db.record_provider_health(health) {
+            eprintln!("Failed to record Brave health: {}", e);
         }
     }
+
+    /// Why Brave can't be searched right now, going by its last report. A
+    /// rejected key or an exhausted quota stays that way until the next
+    /// check or the quota's reset, so searching again would only fail.
+    fn unavailable(&self) -> Option<String> {
+        let health = self.db.get_provider_health(BRAVE_PROVIDER).ok().flatten()?;
+        health_error(&health)
+    }
+
+    /// Count a Brave call for the device, or explain why it can't be made.
+    fn spend_quota(&self, ctx: &DeviceContext) -> Result<Option<String>> {
+        Ok(match self.db.consume_quota(ctx.device_id as u64, &self.quota)? {
+            QuotaCheck::Allowed => None,
+            QuotaCheck::DailyLimit(limit) => Some(format!(
+                "Error: this device has used its {} web searches for today. Work with the results you already have, or try again tomorrow.",
+                limit
+            )),
+            QuotaCheck::MonthlyLimit(limit) => Some(format!(
+                "Error: the monthly web search quota ({} searches) is used up. Work with the results you already have; fetch_page still works for known URLs.",
+                limit
+            )),
+        })
+    }
 }
 
 register_toolbelt! {
     WebSearch {
         description: "Search the web and fetch webpage content using Brave Search API",
         location: ToolLocation::Server,
+        risk: ToolRisk::Low,
         tools: {
             "search" => search {
                 description: "Search the web for information. Returns titles, URLs, and rich snippets. Use for general queries.",
                 params: [
                     "query": "string" => "Search query",
-                    "max_results": "integer" => "Maximum number of results to return (default: 5, max: 10)"
-                ]
+                    "max_results": "integer" => "Maximum number of results to return (max: 10)" = 5
+                ],
+                example: { "query": "rust async cancellation safety", "max_results": 5 }
             },
             "search_news" => search_news {
                 description: "Search for recent news articles on a topic. Returns articles with publish dates and descriptions. Use for news, current events, or anything time-sensitive.",
                 params: [
                     "query": "string" => "News search query",
-                    "max_results": "integer" => "Maximum number of results to return (default: 5, max: 10)"
-                ]
+                    "max_results": "integer" => "Maximum number of results to return (max: 10)" = 5
+                ],
+                example: { "query": "European Central Bank interest rates" }
             },
             "fetch_page" => fetch_page {
-                description: "Fetch a webpage and extract readable text content. Use after search to read full articles.",
+                description: "Fetch a webpage and extract readable text content. Use after search to read full articles. Long pages come in parts; when the text ends with a note that the content continues, call again with the from_char it gives to read on.",
                 params: [
-                    "url": "string" => "URL to fetch"
-                ]
+                    "url": "string" => "URL to fetch",
+                    "from_char": "integer" => "Character to start reading at, for the next part of a long page" = 0,
+                    "max_chars": "integer" => "Most characters to return (max: 40000)" = 20000
+                ],
+                example: { "url": "https://www.rust-lang.org/learn" }
             }
         }
     }
 }
 
 impl WebSearch {
-    fn search(&self, args: &serde_json::Value) -> Result<String> {
+    fn search(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let query = args["query"].as_str().unwrap_or("");
         if query.is_empty() {
             return Ok("Error: query cannot be empty".to_string());
         }
+        if let Some(message) = self.unavailable() {
+            return Ok(message);
+        }
+        if let Some(message) = self.spend_quota(ctx)? {
+            return Ok(message);
+        }
 
         let max_results = args["max_results"]
             .as_i64()
@@ -65,11 +181,17 @@ impl WebSearch {
         })
     }
 
-    fn search_news(&self, args: &serde_json::Value) -> Result<String> {
+    fn search_news(&self, args: &serde_json::Value, ctx: &DeviceContext) -> Result<String> {
         let query = args["query"].as_str().unwrap_or("");
         if query.is_empty() {
             return Ok("Error: query cannot be empty".to_string());
         }
+        if let Some(message) = self.unavailable() {
+            return Ok(message);
+        }
+        if let Some(message) = self.spend_quota(ctx)? {
+            return Ok(message);
+        }
 
         let max_results = args["max_results"]
             .as_i64()
@@ -83,7 +205,7 @@ impl WebSearch {
         })
     }
 
-    fn fetch_page(&self, args: &serde_json::Value) -> Result<String> {
+    fn fetch_page(&self, args: &serde_json::Value, _ctx: &DeviceContext) -> Result<String> {
         let url = args["url"].as_str().unwrap_or("");
         if url.is_empty() {
             return Ok("Error: url cannot be empty".to_string());
@@ -93,9 +215,13 @@ impl WebSearch {
             return Ok("Error: URL must start with http:// or https://".to_string());
         }
 
+        let from_char = args["from_char"].as_u64().unwrap_or(0) as usize;
+        let max_chars = (args["max_chars"].as_u64().unwrap_or(PAGE_CHARS as u64) as usize)
+            .clamp(1, MAX_PAGE_CHARS);
+
         tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
-                self.fetch_page_async(url).await
+                self.fetch_page_async(url, from_char, max_chars).await
             })
         })
     }
@@ -137,6 +263,10 @@ impl WebSearch {
             .send()
             .await?;
 
+        let health = self.record_health(response.status(), response.headers());
+        if let Some(message) = health_error(&health) {
+            return Ok(message);
+        }
         if !response.status().is_success() {
             return Ok(format!("Search failed: {}", response.status()));
         }
@@ -182,6 +312,10 @@ impl WebSearch {
             .send()
             .await?;
 
+        let health = self.record_health(response.status(), response.headers());
+        if let Some(message) = health_error(&health) {
+            return Ok(message);
+        }
         if !response.status().is_success() {
             return Ok(format!("News search failed: {}", response.status()));
         }
@@ -228,7 +362,7 @@ impl WebSearch {
         Ok(output)
     }
 
-    async fn fetch_page_async(&self, url: &str) -> Result<String> {
+    async fn fetch_page_async(&self, url: &str, from_char: usize, max_chars: usize) -> Result<String> {
         let client = reqwest::Client::builder()
             .user_agent("Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0")
             .timeout(std::time::Duration::from_secs(15))
@@ -254,16 +388,7 @@ impl WebSearch {
         let html = response.text().await?;
         let text = self.html_to_text(&html)?;
 
-        // Trim aggressively — the model doesn't need 50k chars of boilerplate
-        let trimmed = if text.len() > 20_000 {
-            format!("{}... [truncated at 20,000 chars, full page is {} chars]",
-                    &text[..20_000],
-                    text.len())
-        } else {
-            text
-        };
-
-        Ok(trimmed)
+        Ok(page_part(&text, from_char, max_chars))
     }
 
     fn html_to_text(&self, html: &str) -> Result<String> {
@@ -293,6 +418,52 @@ impl WebSearch {
     }
 }
 
+/// The monthly figure of a Brave rate-limit header such as `1, 15000`, which
+/// lists the per-second window first and the monthly one last.
+fn monthly_window(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.rsplit(',').next()?.trim().parse().ok()
+}
+
+/// What a Brave response says about the key and the monthly quota.
+fn brave_health(status: StatusCode, headers: &HeaderMap) -> ProviderHealth {
+    let checked_at = db::now();
+    let mut remaining = monthly_window(headers, "X-RateLimit-Remaining");
+    // A 429 without headers can only mean the quota is gone; with them, a
+    // monthly count above zero means only the per-second limit was hit
+    if status == StatusCode::TOO_MANY_REQUESTS && remaining.is_none() {
+        remaining = Some(0);
+    }
+    let message = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Some(format!("{} ({})", KEY_REJECTED, status)),
+        status if status.is_success() || status == StatusCode::TOO_MANY_REQUESTS => None,
+        status => Some(format!("Brave answered {}", status)),
+    };
+    ProviderHealth {
+        provider: BRAVE_PROVIDER.to_string(),
+        ok: message.is_none(),
+        message,
+        remaining,
+        limit: monthly_window(headers, "X-RateLimit-Limit"),
+        resets_at: monthly_window(headers, "X-RateLimit-Reset").map(|secs| checked_at + secs as i64),
+        checked_at,
+    }
+}
+
+/// The message a search answers with when Brave refused it for its quota or
+/// key rather than for the query.
+fn health_error(health: &ProviderHealth) -> Option<String> {
+    if health.exhausted() {
+        return Some(QUOTA_EXHAUSTED.to_string());
+    }
+    match health.message.as_deref() {
+        Some(message) if message.starts_with(KEY_REJECTED) => Some(format!(
+            "Error: search unavailable: {}. Work with the results you already have; fetch_page still works for known URLs.",
+            message
+        )),
+        _ => None,
+    }
+}
+
 fn extract_text(element: &scraper::ElementRef, parts: &mut Vec<String>) {
     let tag = element.value().name();
 
@@ -312,3 +483,25 @@ fn extract_text(element: &scraper::ElementRef, parts: &mut Vec<String>) {
         }
     }
 }
+
+/// Up to `max_chars` characters of `text` from `from_char` on. A part that
+/// stops before the end says where the next one starts, so a long article
+/// can be read over several calls.
+fn page_part(text: &str, from_char: usize, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if from_char >= total {
+        return format!("No content from char {}: the page is {} chars long.", from_char, total);
+    }
+    let part: String = text.chars().skip(from_char).take(max_chars).collect();
+    let end = from_char + part.chars().count();
+    if end < total {
+        format!(
+            "{}\n\n[content continues: chars {}-{} of {}. Call fetch_page again with from_char={} to read on.]",
+            part, from_char, end, total, end
+        )
+    } else if from_char > 0 {
+        format!("{}\n\n[end of page: chars {}-{} of {}]", part, from_char, end, total)
+    } else {
+        part
+    }
+}
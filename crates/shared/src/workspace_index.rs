@@ -0,0 +1,222 @@
+//! Workspace index: the files under a workspace, their sizes and languages,
+//! and the text of the readable ones, kept in the database so FileSmith can
+//! answer "where is X" with one search instead of a walk.
+//!
+//! [`WorkspaceIndex::refresh`] walks the workspace and only reads files whose
+//! size or mtime changed since the last walk.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::db::{Db, IndexHit, IndexedFile};
+
+/// Most files one workspace indexes; the walk stops there.
+pub const MAX_INDEXED_FILES: usize = 20_000;
+
+/// Files larger than this are indexed by path only.
+pub const MAX_CONTENT_BYTES: u64 = 512 * 1024;
+
+/// Lines per searchable chunk of a file.
+pub const CHUNK_LINES: usize = 40;
+
+/// Directories never worth indexing: build output and dependencies. Hidden
+/// directories (`.git`, `.venv`, ...) are skipped too.
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "__pycache__", "venv"];
+
+/// What a refresh changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RefreshReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    /// The walk hit [`MAX_INDEXED_FILES`] and left the rest out.
+    pub truncated: bool,
+}
+
+/// The index of one workspace root.
+#[derive(Clone)]
+pub struct WorkspaceIndex {
+    db: Arc<Db>,
+    root: PathBuf,
+}
+
+impl WorkspaceIndex {
+    pub fn new(db: Arc<Db>, root: impl Into<PathBuf>) -> Self {
+        Self { db, root: root.into() }
+    }
+
+    /// The root as stored in the database, so several workspaces can share it.
+    fn key(&self) -> String {
+        self.root.to_string_lossy().to_string()
+    }
+
+    /// Bring the index in line with the files on disk.
+    pub fn refresh(&self) -> Result<RefreshReport> {
+        let key = self.key();
+        let mut known: HashMap<String, IndexedFile> = self.db.indexed_files(&key)?
+            .into_iter()
+            .map(|f| (f.path.clone(), f))
+            .collect();
+
+        let mut report = RefreshReport::default();
+        let (found, truncated) = walk(&self.root);
+        report.truncated = truncated;
+
+        for (path, full_path, size, modified) in found {
+            let previous = known.remove(&path);
+            if previous.as_ref().is_some_and(|f| f.size == size && f.modified == modified) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let file = IndexedFile {
+                language: language_for(&full_path).map(str::to_string),
+                path,
+                size,
+                modified,
+            };
+            let chunks = if size <= MAX_CONTENT_BYTES {
+                read_text(&full_path).map(|text| chunk_lines(&text)).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            self.db.index_file(&key, &file, &chunks)?;
+
+            if previous.is_some() {
+                report.updated += 1;
+            } else {
+                report.added += 1;
+            }
+        }
+
+        // A cut-short walk didn't see every file, so absence proves nothing
+        if !report.truncated {
+            let gone: Vec<String> = known.into_keys().collect();
+            report.removed = self.db.unindex_files(&key, &gone)?;
+        }
+        Ok(report)
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<IndexHit>> {
+        self.db.search_workspace(&self.key(), query, limit)
+    }
+
+    pub fn find(&self, name: &str, language: Option<&str>, limit: usize) -> Result<Vec<IndexedFile>> {
+        self.db.find_indexed_files(&self.key(), name, language, limit)
+    }
+}
+
+/// The language a file is written in, from its extension or name.
+pub fn language_for(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "Dockerfile" => return Some("dockerfile"),
+        "Makefile" => return Some("make"),
+        _ => {}
+    }
+    let language = match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "sh" | "bash" | "zsh" => "shell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" | "scss" => "css",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "xml" => "xml",
+        "md" | "markdown" => "markdown",
+        "txt" => "text",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Split text into `(start_line, text)` chunks of [`CHUNK_LINES`] lines.
+/// Start lines are 1-based.
+pub fn chunk_lines(text: &str) -> Vec<(u64, String)> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines.chunks(CHUNK_LINES)
+        .enumerate()
+        .map(|(i, chunk)| ((i * CHUNK_LINES + 1) as u64, chunk.join("\n")))
+        .filter(|(_, text)| !text.trim().is_empty())
+        .collect()
+}
+
+/// Every indexable file under `root` as `(relative path, full path, size,
+/// mtime in milliseconds)`, and whether the walk stopped at [`MAX_INDEXED_FILES`].
+/// Symlinks are not followed.
+fn walk(root: &Path) -> (Vec<(String, PathBuf, u64, i64)>, bool) {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            if found.len() >= MAX_INDEXED_FILES {
+                return (found, true);
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_millis() as i64)
+                .unwrap_or(0);
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative = relative.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            found.push((relative, path, metadata.len(), modified));
+        }
+    }
+
+    (found, false)
+}
+
+/// A file's contents if it is UTF-8 text; None for binaries.
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
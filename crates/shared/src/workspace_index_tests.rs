@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use crate::db::Db;
+    use crate::workspace_index::{chunk_lines, RefreshReport, WorkspaceIndex, CHUNK_LINES};
+
+    fn workspace() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("artificer-index-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("src/parser.rs"), "fn parse_config() {}\n").unwrap();
+        std::fs::write(dir.join("README.md"), "How to configure the parser\n").unwrap();
+        std::fs::write(dir.join("target/build.rs"), "fn parse_config() {}\n").unwrap();
+        std::fs::write(dir.join("logo.bin"), [0u8, 1, 2, 3]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn refresh_only_rereads_what_changed() {
+        let dir = workspace();
+        let index = WorkspaceIndex::new(Arc::new(Db::in_memory().unwrap()), &dir);
+
+        let first = index.refresh().unwrap();
+        assert_eq!((first.added, first.unchanged), (3, 0));
+
+        let hits = index.search("parse_config", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "src/parser.rs");
+        assert_eq!(hits[0].language.as_deref(), Some("rust"));
+        assert_eq!(index.find("", Some("markdown"), 10).unwrap()[0].path, "README.md");
+
+        std::fs::write(dir.join("src/parser.rs"), "fn parse_settings_file() {}\n").unwrap();
+        std::fs::remove_file(dir.join("README.md")).unwrap();
+        assert_eq!(index.refresh().unwrap(), RefreshReport {
+            added: 0,
+            updated: 1,
+            removed: 1,
+            unchanged: 1,
+            truncated: false,
+        });
+        assert!(index.search("parse_config", 10).unwrap().is_empty());
+        assert_eq!(index.search("settings", 10).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chunks_start_on_their_first_line() {
+        let text = (1..=CHUNK_LINES + 1).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n");
+        let chunks = chunk_lines(&text);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1], ((CHUNK_LINES + 1) as u64, format!("line {}", CHUNK_LINES + 1)));
+    }
+}